@@ -0,0 +1,331 @@
+//! Loading of sprite sheets, tagged animations and slices exported by Aseprite.
+
+use std::collections::HashMap;
+
+use minterpolate::InterpolationFunction;
+
+use amethyst_assets::{
+    Asset, AssetStorage, Error as AssetsError, ErrorKind as AssetsErrorKind, Handle, Loader,
+    ProcessingState, Result as AssetsResult, SimpleFormat,
+};
+use amethyst_core::specs::prelude::VecStorage;
+use amethyst_renderer::{SpriteRender, SpriteSheet, Texture, TexturePackerFormat};
+
+use crate::{Animation, Sampler, SpriteRenderChannel, SpriteRenderPrimitive};
+
+/// Direction an Aseprite tag is played in.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum AsepriteDirection {
+    /// From the first to the last frame of the tag
+    #[serde(rename = "forward")]
+    Forward,
+    /// From the last to the first frame of the tag
+    #[serde(rename = "reverse")]
+    Reverse,
+    /// From the first to the last frame and back, without repeating the first and last frames
+    #[serde(rename = "pingpong")]
+    PingPong,
+    /// From the last to the first frame and back, without repeating the first and last frames
+    #[serde(rename = "pingpong_reverse")]
+    PingPongReverse,
+}
+
+/// A named range of frames of an Aseprite file, e.g. "idle", "run" or "attack".
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct AsepriteTag {
+    /// Name of the tag
+    pub name: String,
+    /// Index of the first frame of the tag
+    pub from: usize,
+    /// Index of the last frame of the tag
+    pub to: usize,
+    /// Direction the frames are played in
+    pub direction: AsepriteDirection,
+}
+
+impl AsepriteTag {
+    /// Returns the indices of the frames in the order they are played.
+    pub fn frames(&self) -> Vec<usize> {
+        match self.direction {
+            AsepriteDirection::Forward => (self.from..=self.to).collect(),
+            AsepriteDirection::Reverse => (self.from..=self.to).rev().collect(),
+            AsepriteDirection::PingPong => (self.from..=self.to)
+                .chain((self.from + 1..self.to).rev())
+                .collect(),
+            AsepriteDirection::PingPongReverse => (self.from..=self.to)
+                .rev()
+                .chain(self.from + 1..self.to)
+                .collect(),
+        }
+    }
+}
+
+/// Rectangle in pixels from the top left corner of a frame.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AsepriteRect {
+    /// Left edge
+    pub x: i32,
+    /// Top edge
+    pub y: i32,
+    /// Width of the rectangle
+    #[serde(rename = "w")]
+    pub width: u32,
+    /// Height of the rectangle
+    #[serde(rename = "h")]
+    pub height: u32,
+}
+
+/// Point in pixels from the top left corner of a frame.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AsepritePoint {
+    /// Horizontal position
+    pub x: i32,
+    /// Vertical position
+    pub y: i32,
+}
+
+/// State of a slice from a frame on.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct AsepriteSliceKey {
+    /// Index of the first frame with this state
+    pub frame: usize,
+    /// Bounds of the slice in the frame
+    pub bounds: AsepriteRect,
+    /// Center of a 9-slice, relative to the bounds
+    pub center: Option<AsepriteRect>,
+    /// Pivot of the slice, relative to the bounds
+    pub pivot: Option<AsepritePoint>,
+}
+
+/// A named region of the frames of an Aseprite file, e.g. a hitbox or the point to attach a
+/// weapon to.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct AsepriteSlice {
+    /// Name of the slice
+    pub name: String,
+    /// States of the slice, by increasing frame
+    pub keys: Vec<AsepriteSliceKey>,
+}
+
+impl AsepriteSlice {
+    /// Returns the state of the slice in the given frame, or `None` if the slice starts after it.
+    pub fn key(&self, frame: usize) -> Option<&AsepriteSliceKey> {
+        self.keys.iter().rev().find(|key| key.frame <= frame)
+    }
+}
+
+/// Sprite sheet, tags and slices of an Aseprite file.
+///
+/// Load it with the `AsepriteFormat`, and add a `Processor::<AsepriteSheet>` to your dispatcher.
+/// Once loaded, load the `sprite_sheet` as an asset with `Loader::load_from_data` to render it,
+/// and the animations of the tags with `load_animations`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AsepriteSheet {
+    /// The frames, named by their frame name
+    pub sprite_sheet: SpriteSheet,
+    /// Duration of each frame in seconds
+    pub durations: Vec<f32>,
+    /// The tags of the file
+    pub tags: Vec<AsepriteTag>,
+    /// The slices of the file
+    pub slices: Vec<AsepriteSlice>,
+}
+
+impl AsepriteSheet {
+    /// Returns the tag with the given name.
+    pub fn tag(&self, name: &str) -> Option<&AsepriteTag> {
+        self.tags.iter().find(|tag| tag.name == name)
+    }
+
+    /// Returns the slice with the given name.
+    pub fn slice(&self, name: &str) -> Option<&AsepriteSlice> {
+        self.slices.iter().find(|slice| slice.name == name)
+    }
+
+    /// Returns a sampler playing the frames of the tag once, with the durations of the frames.
+    pub fn sampler(&self, tag: &AsepriteTag) -> Sampler<SpriteRenderPrimitive> {
+        tag_sampler(&tag.frames(), &self.durations)
+    }
+
+    /// Loads the animations of all the tags, by tag name.
+    ///
+    /// The animations only change the `sprite_number` of the `SpriteRender`, so they work with
+    /// the sprite sheet loaded from `sprite_sheet`.
+    pub fn load_animations(
+        &self,
+        loader: &Loader,
+        sampler_storage: &AssetStorage<Sampler<SpriteRenderPrimitive>>,
+        animation_storage: &AssetStorage<Animation<SpriteRender>>,
+    ) -> HashMap<String, Handle<Animation<SpriteRender>>> {
+        self.tags
+            .iter()
+            .map(|tag| {
+                let sampler = loader.load_from_data(self.sampler(tag), (), sampler_storage);
+                let animation = Animation::new_single(0, SpriteRenderChannel::SpriteIndex, sampler);
+                (
+                    tag.name.clone(),
+                    loader.load_from_data(animation, (), animation_storage),
+                )
+            })
+            .collect()
+    }
+}
+
+impl Asset for AsepriteSheet {
+    const NAME: &'static str = "animation::AsepriteSheet";
+    type Data = Self;
+    type HandleStorage = VecStorage<Handle<Self>>;
+}
+
+impl Into<AssetsResult<ProcessingState<AsepriteSheet>>> for AsepriteSheet {
+    fn into(self) -> AssetsResult<ProcessingState<AsepriteSheet>> {
+        Ok(ProcessingState::Loaded(self))
+    }
+}
+
+/// Step sampler showing each frame for its duration, holding the last one until the end.
+fn tag_sampler(frames: &[usize], durations: &[f32]) -> Sampler<SpriteRenderPrimitive> {
+    let mut input = Vec::with_capacity(frames.len() + 1);
+    let mut output = Vec::with_capacity(frames.len() + 1);
+    let mut time = 0.;
+    for &frame in frames {
+        input.push(time);
+        output.push(SpriteRenderPrimitive::SpriteIndex(frame));
+        time += durations.get(frame).cloned().unwrap_or(0.);
+    }
+    if let Some(&last) = frames.last() {
+        input.push(time);
+        output.push(SpriteRenderPrimitive::SpriteIndex(last));
+    }
+    Sampler {
+        input,
+        output,
+        function: InterpolationFunction::Step,
+    }
+}
+
+/// Structure acting as scaffolding for serde when loading an Aseprite file.
+#[derive(Deserialize)]
+struct JsonFrame {
+    #[serde(default)]
+    filename: String,
+    /// Duration in milliseconds
+    duration: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonFrames {
+    Hash(HashMap<String, JsonFrame>),
+    Array(Vec<JsonFrame>),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonMeta {
+    #[serde(default)]
+    frame_tags: Vec<AsepriteTag>,
+    #[serde(default)]
+    slices: Vec<AsepriteSlice>,
+}
+
+#[derive(Deserialize)]
+struct JsonAseprite {
+    frames: JsonFrames,
+    meta: JsonMeta,
+}
+
+/// Allows loading the JSON data Aseprite exports with a sprite sheet, into an `AsepriteSheet`.
+///
+/// Export it from Aseprite with "Export Sprite Sheet", or on the command line with:
+///
+/// ```text
+/// aseprite -b hero.aseprite --sheet hero.png --data hero.json --list-tags --list-slices
+/// ```
+///
+/// Both the "Hash" and the "Array" layouts are supported. Like the `SpriteSheetFormat`, it takes
+/// the handle of the loaded texture as options.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct AsepriteFormat;
+
+impl SimpleFormat<AsepriteSheet> for AsepriteFormat {
+    const NAME: &'static str = "ASEPRITE";
+
+    type Options = Handle<Texture>;
+
+    fn import(&self, bytes: Vec<u8>, texture: Self::Options) -> AssetsResult<AsepriteSheet> {
+        let data: JsonAseprite = serde_json::from_slice(&bytes).map_err(|_| {
+            AssetsError::from_kind(AssetsErrorKind::Format(
+                "Failed to parse Json file for AsepriteSheet",
+            ))
+        })?;
+        let sprite_sheet = TexturePackerFormat.import(bytes, texture)?;
+
+        let frames: Vec<JsonFrame> = match data.frames {
+            JsonFrames::Hash(frames) => frames
+                .into_iter()
+                .map(|(filename, frame)| JsonFrame { filename, ..frame })
+                .collect(),
+            JsonFrames::Array(frames) => frames,
+        };
+        let mut durations = vec![0.; sprite_sheet.sprites.len()];
+        for (position, frame) in frames.iter().enumerate() {
+            let index = sprite_sheet
+                .sprite_number(&frame.filename)
+                .unwrap_or(position);
+            if let Some(duration) = durations.get_mut(index) {
+                *duration = frame.duration as f32 / 1000.;
+            }
+        }
+        if data
+            .meta
+            .frame_tags
+            .iter()
+            .any(|tag| tag.from > tag.to || tag.to >= durations.len())
+        {
+            return Err(AssetsError::from_kind(AssetsErrorKind::Format(
+                "Invalid frame range of tag in AsepriteSheet",
+            )));
+        }
+
+        Ok(AsepriteSheet {
+            sprite_sheet,
+            durations,
+            tags: data.meta.frame_tags,
+            slices: data.meta.slices,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ping_pong_tag_sampler() {
+        let tag = AsepriteTag {
+            name: "run".to_string(),
+            from: 1,
+            to: 3,
+            direction: AsepriteDirection::PingPong,
+        };
+        assert_eq!(vec![1, 2, 3, 2], tag.frames());
+
+        let sampler = tag_sampler(&tag.frames(), &[1., 0.25, 0.5, 0.125]);
+        assert_eq!(vec![0., 0.25, 0.75, 0.875, 1.375], sampler.input);
+        assert_eq!(
+            Some(&SpriteRenderPrimitive::SpriteIndex(2)),
+            sampler.output.last()
+        );
+    }
+
+    #[test]
+    fn ping_pong_reverse_tag() {
+        let tag: AsepriteTag = serde_json::from_str(
+            r#"{ "name": "run", "from": 1, "to": 3, "direction": "pingpong_reverse" }"#,
+        )
+        .unwrap();
+        assert_eq!(AsepriteDirection::PingPongReverse, tag.direction);
+        assert_eq!(vec![3, 2, 1, 2], tag.frames());
+    }
+}
@@ -2,7 +2,7 @@ use std::{hash::Hash, marker};
 
 use crate::{
     resources::AnimationSampling,
-    skinning::VertexSkinningSystem,
+    skinning::{BoneAttachmentSystem, VertexSkinningSystem},
     systems::{
         AnimationControlSystem, AnimationProcessor, SamplerInterpolationSystem, SamplerProcessor,
     },
@@ -13,10 +13,16 @@ use amethyst_core::{
     Result, SystemBundle,
 };
 
+#[cfg(feature = "json")]
+use amethyst_assets::Processor;
+
+#[cfg(feature = "json")]
+use crate::skeleton2d::{Skeleton2dSystem, SkeletonData};
+
 /// Bundle for vertex skinning
 ///
-/// This registers `VertexSkinningSystem`.
-/// Note that the user must make sure this system runs after `TransformSystem`
+/// This registers `VertexSkinningSystem` and `BoneAttachmentSystem`.
+/// Note that the user must make sure these systems run after `TransformSystem`
 #[derive(Default)]
 pub struct VertexSkinningBundle<'a> {
     dep: &'a [&'a str],
@@ -28,7 +34,7 @@ impl<'a> VertexSkinningBundle<'a> {
         Default::default()
     }
 
-    /// Set dependencies for the `VertexSkinningSystem`
+    /// Set dependencies for the `VertexSkinningSystem` and `BoneAttachmentSystem`
     pub fn with_dep(mut self, dep: &'a [&'a str]) -> Self {
         self.dep = dep;
         self
@@ -42,6 +48,11 @@ impl<'a, 'b, 'c> SystemBundle<'a, 'b> for VertexSkinningBundle<'c> {
             "vertex_skinning_system",
             self.dep,
         );
+        builder.add(
+            BoneAttachmentSystem::new(),
+            "bone_attachment_system",
+            self.dep,
+        );
         Ok(())
     }
 }
@@ -153,3 +164,37 @@ where
             .build(builder)
     }
 }
+
+/// Bundle for 2D skeletal animation.
+///
+/// This registers `Skeleton2dSystem` and `Processor::<SkeletonData>`.
+/// Note that the user must make sure this system runs before `TransformSystem`, and add the
+/// `DrawSkeletonMeshes` pass to draw mesh attachments.
+#[cfg(feature = "json")]
+#[derive(Default)]
+pub struct Skeleton2dBundle<'a> {
+    dep: &'a [&'a str],
+}
+
+#[cfg(feature = "json")]
+impl<'a> Skeleton2dBundle<'a> {
+    /// Create a new skeleton bundle
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Set dependencies for the `Skeleton2dSystem`
+    pub fn with_dep(mut self, dep: &'a [&'a str]) -> Self {
+        self.dep = dep;
+        self
+    }
+}
+
+#[cfg(feature = "json")]
+impl<'a, 'b, 'c> SystemBundle<'a, 'b> for Skeleton2dBundle<'c> {
+    fn build(self, builder: &mut DispatcherBuilder<'a, 'b>) -> Result<()> {
+        builder.add(Processor::<SkeletonData>::new(), "", &[]);
+        builder.add(Skeleton2dSystem::new(), "skeleton_2d_system", self.dep);
+        Ok(())
+    }
+}
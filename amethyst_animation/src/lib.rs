@@ -55,16 +55,32 @@ extern crate log;
 #[macro_use]
 extern crate serde;
 
+#[cfg(feature = "json")]
+pub use self::{
+    aseprite::{
+        AsepriteDirection, AsepriteFormat, AsepritePoint, AsepriteRect, AsepriteSheet,
+        AsepriteSlice, AsepriteSliceKey, AsepriteTag,
+    },
+    bundle::Skeleton2dBundle,
+    skeleton2d::{
+        AttachmentData, BoneData, BonePose, BoneTimeline, DeformTimeline, DrawSkeletonMeshes,
+        EventKey, MeshAttachment, Skeleton2d, Skeleton2dSystem, SkeletonAnimation, SkeletonData,
+        SkeletonEvent, SkeletonMesh, SlotData, SpineFormat, TimelineKey, VertexInfluence,
+    },
+};
 pub use self::{
     bundle::{AnimationBundle, SamplingBundle, VertexSkinningBundle},
-    material::{MaterialChannel, MaterialPrimitive},
+    material::{MaterialChannel, MaterialOverrideChannel, MaterialPrimitive, RgbaChannel},
     prefab::{AnimatablePrefab, AnimationHierarchyPrefab, AnimationPrefab, AnimationSetPrefab},
     resources::{
         Animation, AnimationCommand, AnimationControl, AnimationControlSet, AnimationHierarchy,
         AnimationSampling, AnimationSet, ApplyData, BlendMethod, ControlState, DeferStartRelation,
         EndControl, RestState, Sampler, SamplerControl, SamplerControlSet, StepDirection,
     },
-    skinning::{Joint, JointPrefab, Skin, SkinPrefab, SkinnablePrefab, VertexSkinningSystem},
+    skinning::{
+        BoneAttachment, BoneAttachmentSystem, Joint, JointPrefab, Skin, SkinPrefab,
+        SkinnablePrefab, VertexSkinningSystem,
+    },
     sprite::{SpriteRenderChannel, SpriteRenderPrimitive},
     systems::{
         AnimationControlSystem, AnimationProcessor, SamplerInterpolationSystem, SamplerProcessor,
@@ -75,10 +91,14 @@ pub use self::{
 
 pub use minterpolate::{InterpolationFunction, InterpolationPrimitive};
 
+#[cfg(feature = "json")]
+mod aseprite;
 mod bundle;
 mod material;
 mod prefab;
 mod resources;
+#[cfg(feature = "json")]
+mod skeleton2d;
 mod skinning;
 mod sprite;
 mod systems;
@@ -1,9 +1,9 @@
 use minterpolate::InterpolationPrimitive;
 
 use amethyst_assets::Handle;
-use amethyst_renderer::{Material, Sprite, Texture, TextureOffset};
+use amethyst_renderer::{Material, MaterialOverride, Rgba, Sprite, Texture, TextureOffset};
 
-use crate::{AnimationSampling, ApplyData, BlendMethod};
+use crate::{util::SamplerPrimitive, AnimationSampling, ApplyData, BlendMethod};
 
 /// Sampler primitive for Material animations
 /// Note that material can only ever be animated with `Step`, or a panic will occur.
@@ -193,3 +193,215 @@ impl AnimationSampling for Material {
         None
     }
 }
+
+/// Channels that are animatable on `MaterialOverride`, so the material of one entity can change
+/// without a custom system, e.g. to scroll the maps of water or pulse an emissive sign.
+///
+/// Unlike `MaterialChannel`, these channels are interpolated and blended. Offsets are sampled as
+/// `Vec4([u_start, u_end, v_start, v_end])`, and scroll the maps when they tile. Parameters the
+/// override leaves to the `Material` start at their defaults: an intensity of `1.0` and the
+/// whole texture.
+///
+/// The animated entities need a `MaterialOverride`, which can be left to its default, and the
+/// game needs the systems of an `AnimationBundle::<I, MaterialOverride>`.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // Scrolls the maps of water along u every 10 seconds, with `EndControl::Loop(None)`.
+/// let scroll = Sampler {
+///     input: vec![0., 10.],
+///     output: vec![
+///         SamplerPrimitive::Vec4([0., 1., 0., 1.]),
+///         SamplerPrimitive::Vec4([1., 2., 0., 1.]),
+///     ],
+///     function: InterpolationFunction::Linear,
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub enum MaterialOverrideChannel {
+    /// Animating the multiplier of the emission, sampled as a `Scalar`.
+    EmissionIntensity,
+    /// Animating the "window" used to render the albedo.
+    AlbedoOffset,
+    /// Animating the "window" used to render the emission.
+    EmissionOffset,
+    /// Animating the "window" used to render the normal.
+    NormalOffset,
+    /// Animating the "window" used to render the metallic.
+    MetallicOffset,
+    /// Animating the "window" used to render the roughness.
+    RoughnessOffset,
+    /// Animating the "window" used to render the ambient occlusion.
+    AmbientOcclusionOffset,
+    /// Animating the "window" used to render the caveat.
+    CaveatOffset,
+}
+
+impl<'a> ApplyData<'a> for MaterialOverride {
+    type ApplyData = ();
+}
+
+fn override_offset(
+    material: &mut MaterialOverride,
+    channel: MaterialOverrideChannel,
+) -> &mut Option<TextureOffset> {
+    use self::MaterialOverrideChannel::*;
+
+    match channel {
+        EmissionIntensity => unreachable!("The emission intensity is not an offset"),
+        AlbedoOffset => &mut material.albedo_offset,
+        EmissionOffset => &mut material.emission_offset,
+        NormalOffset => &mut material.normal_offset,
+        MetallicOffset => &mut material.metallic_offset,
+        RoughnessOffset => &mut material.roughness_offset,
+        AmbientOcclusionOffset => &mut material.ambient_occlusion_offset,
+        CaveatOffset => &mut material.caveat_offset,
+    }
+}
+
+impl AnimationSampling for MaterialOverride {
+    type Primitive = SamplerPrimitive<f32>;
+    type Channel = MaterialOverrideChannel;
+
+    fn apply_sample(&mut self, channel: &Self::Channel, data: &SamplerPrimitive<f32>, _: &()) {
+        use self::MaterialOverrideChannel::EmissionIntensity;
+
+        let offset = match (*channel, *data) {
+            (EmissionIntensity, SamplerPrimitive::Scalar(intensity)) => {
+                self.emission_intensity = Some(intensity);
+                return;
+            }
+            (EmissionIntensity, _) => panic!("Attempt to apply invalid sample to MaterialOverride"),
+            (_, SamplerPrimitive::Vec4([u_start, u_end, v_start, v_end])) => {
+                texture_offset((u_start, u_end), (v_start, v_end))
+            }
+            _ => panic!("Attempt to apply invalid sample to MaterialOverride"),
+        };
+        *override_offset(self, *channel) = Some(offset);
+    }
+
+    fn current_sample(&self, channel: &Self::Channel, _: &()) -> SamplerPrimitive<f32> {
+        use self::MaterialOverrideChannel::*;
+
+        let offset = match *channel {
+            EmissionIntensity => {
+                return SamplerPrimitive::Scalar(self.emission_intensity.unwrap_or(1.));
+            }
+            AlbedoOffset => &self.albedo_offset,
+            EmissionOffset => &self.emission_offset,
+            NormalOffset => &self.normal_offset,
+            MetallicOffset => &self.metallic_offset,
+            RoughnessOffset => &self.roughness_offset,
+            AmbientOcclusionOffset => &self.ambient_occlusion_offset,
+            CaveatOffset => &self.caveat_offset,
+        };
+        let TextureOffset { u, v } = offset.clone().unwrap_or_default();
+        SamplerPrimitive::Vec4([u.0, u.1, v.0, v.1])
+    }
+
+    fn default_primitive(channel: &Self::Channel) -> Self::Primitive {
+        match channel {
+            MaterialOverrideChannel::EmissionIntensity => SamplerPrimitive::Scalar(0.),
+            _ => SamplerPrimitive::Vec4([0.; 4]),
+        }
+    }
+
+    fn blend_method(&self, _: &Self::Channel) -> Option<BlendMethod> {
+        Some(BlendMethod::Linear)
+    }
+}
+
+/// Channels that are animatable on `Rgba`, which tints the albedo of the meshes, sprites and UI
+/// images of an entity.
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub enum RgbaChannel {
+    /// Animating the tint, sampled as a `Vec4` of linear red, green, blue and alpha.
+    Tint,
+}
+
+impl<'a> ApplyData<'a> for Rgba {
+    type ApplyData = ();
+}
+
+impl AnimationSampling for Rgba {
+    type Primitive = SamplerPrimitive<f32>;
+    type Channel = RgbaChannel;
+
+    fn apply_sample(&mut self, channel: &Self::Channel, data: &SamplerPrimitive<f32>, _: &()) {
+        match (channel, *data) {
+            (RgbaChannel::Tint, SamplerPrimitive::Vec4([r, g, b, a])) => {
+                *self = Rgba(r, g, b, a);
+            }
+            _ => panic!("Attempt to apply invalid sample to Rgba"),
+        }
+    }
+
+    fn current_sample(&self, channel: &Self::Channel, _: &()) -> SamplerPrimitive<f32> {
+        match channel {
+            RgbaChannel::Tint => SamplerPrimitive::Vec4([self.0, self.1, self.2, self.3]),
+        }
+    }
+
+    fn default_primitive(channel: &Self::Channel) -> Self::Primitive {
+        match channel {
+            RgbaChannel::Tint => SamplerPrimitive::Vec4([0.; 4]),
+        }
+    }
+
+    fn blend_method(&self, _: &Self::Channel) -> Option<BlendMethod> {
+        Some(BlendMethod::Linear)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vec4(sample: SamplerPrimitive<f32>) -> [f32; 4] {
+        match sample {
+            SamplerPrimitive::Vec4(v) => v,
+            sample => panic!("Expected a Vec4, got {:?}", sample),
+        }
+    }
+
+    #[test]
+    fn material_override_round_trip() {
+        use self::MaterialOverrideChannel::*;
+
+        let mut material = MaterialOverride::default();
+        match material.current_sample(&EmissionIntensity, &()) {
+            SamplerPrimitive::Scalar(intensity) => assert!((intensity - 1.).abs() < 1.0e-6),
+            sample => panic!("Expected a Scalar, got {:?}", sample),
+        }
+        assert_eq!(
+            [0., 1., 0., 1.],
+            vec4(material.current_sample(&AlbedoOffset, &()))
+        );
+
+        material.apply_sample(&EmissionIntensity, &SamplerPrimitive::Scalar(2.5), &());
+        material.apply_sample(
+            &NormalOffset,
+            &SamplerPrimitive::Vec4([0.5, 1., 0., 0.5]),
+            &(),
+        );
+        assert_eq!(Some(2.5), material.emission_intensity);
+        assert_eq!(
+            [0.5, 1., 0., 0.5],
+            vec4(material.current_sample(&NormalOffset, &()))
+        );
+        assert_eq!(None, material.albedo_offset);
+    }
+
+    #[test]
+    fn rgba_round_trip() {
+        let mut rgba = Rgba::WHITE;
+        let tint = SamplerPrimitive::Vec4([0.25, 0.5, 0.75, 1.]);
+        rgba.apply_sample(&RgbaChannel::Tint, &tint, &());
+        assert_eq!(Rgba(0.25, 0.5, 0.75, 1.), rgba);
+        assert_eq!(
+            [0.25, 0.5, 0.75, 1.],
+            vec4(rgba.current_sample(&RgbaChannel::Tint, &()))
+        );
+    }
+}
@@ -0,0 +1,563 @@
+use std::{cmp::Ordering, collections::HashMap};
+
+use serde_json::Value;
+
+use amethyst_assets::{Error, ErrorKind, Result, SimpleFormat};
+
+use super::resources::{
+    AttachmentData, BoneData, BonePose, BoneTimeline, DeformTimeline, EventKey, MeshAttachment,
+    SkeletonAnimation, SkeletonData, SlotData, TimelineKey, VertexInfluence,
+};
+
+/// Structures acting as scaffolding for serde when loading a Spine file.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonBone {
+    name: String,
+    parent: Option<String>,
+    #[serde(default)]
+    x: f32,
+    #[serde(default)]
+    y: f32,
+    #[serde(default)]
+    rotation: f32,
+    #[serde(default = "one")]
+    scale_x: f32,
+    #[serde(default = "one")]
+    scale_y: f32,
+}
+
+fn one() -> f32 {
+    1.
+}
+
+#[derive(Deserialize)]
+struct JsonSlot {
+    name: String,
+    bone: String,
+    attachment: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonAttachment {
+    #[serde(rename = "type", default = "region")]
+    kind: String,
+    name: Option<String>,
+    path: Option<String>,
+    #[serde(default)]
+    x: f32,
+    #[serde(default)]
+    y: f32,
+    #[serde(default)]
+    rotation: f32,
+    #[serde(default = "one")]
+    scale_x: f32,
+    #[serde(default = "one")]
+    scale_y: f32,
+    /// Texture coordinates of a mesh, by pairs
+    #[serde(default)]
+    uvs: Vec<f32>,
+    #[serde(default)]
+    triangles: Vec<usize>,
+    /// Positions of the vertices of a mesh, or bone influences if the mesh is weighted
+    #[serde(default)]
+    vertices: Vec<f32>,
+}
+
+fn region() -> String {
+    "region".to_string()
+}
+
+/// Attachments by slot name and attachment name.
+type JsonSkin = HashMap<String, HashMap<String, JsonAttachment>>;
+
+#[derive(Deserialize)]
+struct JsonNamedSkin {
+    name: String,
+    #[serde(default)]
+    attachments: JsonSkin,
+}
+
+/// Skins are a map up to Spine 3.7, and a list since Spine 3.8.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonSkins {
+    Map(HashMap<String, JsonSkin>),
+    List(Vec<JsonNamedSkin>),
+}
+
+impl Default for JsonSkins {
+    fn default() -> Self {
+        JsonSkins::List(Vec::new())
+    }
+}
+
+#[derive(Default, Deserialize)]
+struct JsonEventData {
+    #[serde(default)]
+    int: i32,
+    #[serde(default)]
+    float: f32,
+    string: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct JsonKey {
+    #[serde(default)]
+    time: f32,
+    /// `angle` up to Spine 3.8, `value` since Spine 4.0.
+    #[serde(alias = "value")]
+    angle: Option<f32>,
+    x: Option<f32>,
+    y: Option<f32>,
+    curve: Option<Value>,
+}
+
+fn stepped(curve: Option<&Value>) -> bool {
+    curve.and_then(Value::as_str) == Some("stepped")
+}
+
+#[derive(Default, Deserialize)]
+struct JsonBoneTimeline {
+    #[serde(default)]
+    rotate: Vec<JsonKey>,
+    #[serde(default)]
+    translate: Vec<JsonKey>,
+    #[serde(default)]
+    scale: Vec<JsonKey>,
+}
+
+#[derive(Deserialize)]
+struct JsonAttachmentKey {
+    #[serde(default)]
+    time: f32,
+    name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct JsonSlotTimeline {
+    #[serde(default)]
+    attachment: Vec<JsonAttachmentKey>,
+}
+
+#[derive(Deserialize)]
+struct JsonDeformKey {
+    #[serde(default)]
+    time: f32,
+    #[serde(default)]
+    offset: usize,
+    #[serde(default)]
+    vertices: Vec<f32>,
+    curve: Option<Value>,
+}
+
+/// Deform keys by skin name, slot name and attachment name.
+type JsonDeform = HashMap<String, HashMap<String, HashMap<String, Vec<JsonDeformKey>>>>;
+
+/// Timelines of an attachment since Spine 4.0.
+#[derive(Deserialize)]
+struct JsonAttachmentTimelines {
+    #[serde(default)]
+    deform: Vec<JsonDeformKey>,
+}
+
+#[derive(Deserialize)]
+struct JsonEventKey {
+    #[serde(default)]
+    time: f32,
+    name: String,
+    int: Option<i32>,
+    float: Option<f32>,
+    string: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct JsonAnimation {
+    #[serde(default)]
+    bones: HashMap<String, JsonBoneTimeline>,
+    #[serde(default)]
+    slots: HashMap<String, JsonSlotTimeline>,
+    /// Deform timelines up to Spine 3.8.
+    #[serde(default)]
+    deform: JsonDeform,
+    /// Deform timelines since Spine 4.0.
+    #[serde(default)]
+    attachments: HashMap<String, HashMap<String, HashMap<String, JsonAttachmentTimelines>>>,
+    #[serde(default)]
+    events: Vec<JsonEventKey>,
+}
+
+#[derive(Deserialize)]
+struct JsonSkeleton {
+    #[serde(default)]
+    bones: Vec<JsonBone>,
+    #[serde(default)]
+    slots: Vec<JsonSlot>,
+    #[serde(default)]
+    skins: JsonSkins,
+    #[serde(default)]
+    events: HashMap<String, JsonEventData>,
+    #[serde(default)]
+    animations: HashMap<String, JsonAnimation>,
+}
+
+/// Allows loading a `SkeletonData` from the JSON export of Spine, from version 3.6 on.
+///
+/// DragonBones projects can be loaded too, once exported in the Spine format.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct SpineFormat;
+
+impl SimpleFormat<SkeletonData> for SpineFormat {
+    const NAME: &'static str = "SPINE";
+
+    type Options = ();
+
+    fn import(&self, bytes: Vec<u8>, _: ()) -> Result<SkeletonData> {
+        let json: JsonSkeleton = serde_json::from_slice(&bytes)
+            .map_err(|_| Error::from_kind(ErrorKind::Format("Failed to parse Spine file")))?;
+        convert(json).ok_or_else(|| {
+            Error::from_kind(ErrorKind::Format(
+                "Spine file refers to a bone, slot or vertex that doesn't exist",
+            ))
+        })
+    }
+}
+
+fn convert(json: JsonSkeleton) -> Option<SkeletonData> {
+    let bone_names: Vec<String> = json.bones.iter().map(|bone| bone.name.clone()).collect();
+    let bone_index = |name: &str| bone_names.iter().position(|bone| bone == name);
+    let mut bones = Vec::with_capacity(json.bones.len());
+    for bone in json.bones {
+        let parent = match bone.parent {
+            Some(ref parent) => Some(bone_index(parent)?),
+            None => None,
+        };
+        bones.push(BoneData {
+            name: bone.name,
+            parent,
+            pose: BonePose {
+                x: bone.x,
+                y: bone.y,
+                rotation: bone.rotation,
+                scale_x: bone.scale_x,
+                scale_y: bone.scale_y,
+            },
+        });
+    }
+
+    let mut slots = Vec::with_capacity(json.slots.len());
+    for slot in json.slots {
+        slots.push(SlotData {
+            bone: bone_index(&slot.bone)?,
+            name: slot.name,
+            attachment: slot.attachment,
+        });
+    }
+    let slot_index = |name: &str| slots.iter().position(|slot| slot.name == name);
+
+    let skins = match json.skins {
+        JsonSkins::Map(skins) => skins.into_iter().collect::<Vec<_>>(),
+        JsonSkins::List(skins) => skins
+            .into_iter()
+            .map(|skin| (skin.name, skin.attachments))
+            .collect(),
+    };
+    let mut skin_data = HashMap::new();
+    for (skin_name, skin) in skins {
+        let mut attachments = HashMap::new();
+        for (slot_name, slot_attachments) in skin {
+            let slot = slot_index(&slot_name)?;
+            for (name, attachment) in slot_attachments {
+                let mesh = match attachment.kind.as_str() {
+                    "region" => None,
+                    "mesh" => Some(mesh(&attachment, slots[slot].bone, bones.len())?),
+                    _ => {
+                        warn!(
+                            "Skipping {} attachment {:?} of slot {:?}, only region and mesh \
+                             attachments are supported",
+                            attachment.kind, name, slot_name
+                        );
+                        continue;
+                    }
+                };
+                let sprite = attachment
+                    .path
+                    .or(attachment.name)
+                    .unwrap_or_else(|| name.clone());
+                let pose = BonePose {
+                    x: attachment.x,
+                    y: attachment.y,
+                    rotation: attachment.rotation,
+                    scale_x: attachment.scale_x,
+                    scale_y: attachment.scale_y,
+                };
+                attachments.insert((slot, name), AttachmentData { sprite, pose, mesh });
+            }
+        }
+        skin_data.insert(skin_name, attachments);
+    }
+
+    let mut animations = HashMap::new();
+    for (name, animation) in json.animations {
+        let mut duration: f32 = 0.;
+        let mut bone_timelines = Vec::new();
+        for (bone, timeline) in animation.bones {
+            bone_timelines.push(BoneTimeline {
+                bone: bone_index(&bone)?,
+                rotate: timeline_keys(&timeline.rotate, &mut duration, |key| {
+                    key.angle.unwrap_or(0.)
+                }),
+                translate: timeline_keys(&timeline.translate, &mut duration, |key| {
+                    [key.x.unwrap_or(0.), key.y.unwrap_or(0.)]
+                }),
+                scale: timeline_keys(&timeline.scale, &mut duration, |key| {
+                    [key.x.unwrap_or(1.), key.y.unwrap_or(1.)]
+                }),
+            });
+        }
+
+        let mut attachments = Vec::new();
+        for (slot, timeline) in animation.slots {
+            let keys = timeline
+                .attachment
+                .into_iter()
+                .map(|key| {
+                    duration = duration.max(key.time);
+                    (key.time, key.name)
+                })
+                .collect::<Vec<_>>();
+            attachments.push((slot_index(&slot)?, keys));
+        }
+
+        let deform = animation
+            .deform
+            .into_iter()
+            .chain(animation.attachments.into_iter().map(|(skin, slots)| {
+                let slots = slots.into_iter().map(|(slot, attachments)| {
+                    let attachments = attachments
+                        .into_iter()
+                        .map(|(name, timelines)| (name, timelines.deform))
+                        .collect::<HashMap<_, _>>();
+                    (slot, attachments)
+                });
+                (skin, slots.collect::<HashMap<_, _>>())
+            }));
+        let mut deforms = Vec::new();
+        for (skin, skin_slots) in deform {
+            for (slot_name, slot_attachments) in skin_slots {
+                let slot = slot_index(&slot_name)?;
+                for (attachment, keys) in slot_attachments {
+                    let influences = match skin_data
+                        .get(&skin)
+                        .and_then(|skin| skin.get(&(slot, attachment.clone())))
+                        .and_then(|attachment| attachment.mesh.as_ref())
+                    {
+                        Some(mesh) => mesh.influences() * 2,
+                        None => {
+                            warn!(
+                                "Skipping deform timeline of {:?} in slot {:?}, it isn't a mesh",
+                                attachment, slot_name
+                            );
+                            continue;
+                        }
+                    };
+                    let keys = keys
+                        .into_iter()
+                        .map(|key| {
+                            duration = duration.max(key.time);
+                            let mut value = vec![0.; influences];
+                            for (offset, vertex) in
+                                value.iter_mut().skip(key.offset).zip(&key.vertices)
+                            {
+                                *offset = *vertex;
+                            }
+                            TimelineKey {
+                                time: key.time,
+                                value,
+                                stepped: stepped(key.curve.as_ref()),
+                            }
+                        })
+                        .collect();
+                    deforms.push(DeformTimeline {
+                        skin: skin.clone(),
+                        slot,
+                        attachment,
+                        keys,
+                    });
+                }
+            }
+        }
+
+        let mut events = animation
+            .events
+            .into_iter()
+            .map(|key| {
+                duration = duration.max(key.time);
+                let data = json.events.get(&key.name);
+                EventKey {
+                    time: key.time,
+                    int: key.int.or_else(|| data.map(|data| data.int)).unwrap_or(0),
+                    float: key
+                        .float
+                        .or_else(|| data.map(|data| data.float))
+                        .unwrap_or(0.),
+                    string: key
+                        .string
+                        .or_else(|| data.and_then(|data| data.string.clone())),
+                    name: key.name,
+                }
+            })
+            .collect::<Vec<_>>();
+        events.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap_or(Ordering::Equal));
+
+        animations.insert(
+            name,
+            SkeletonAnimation {
+                duration,
+                bones: bone_timelines,
+                attachments,
+                deforms,
+                events,
+            },
+        );
+    }
+
+    Some(SkeletonData {
+        bones,
+        slots,
+        skins: skin_data,
+        animations,
+    })
+}
+
+/// Reads the vertices of a mesh attachment, returning `None` if they refer to a bone or vertex
+/// that doesn't exist.
+fn mesh(
+    attachment: &JsonAttachment,
+    slot_bone: usize,
+    bone_count: usize,
+) -> Option<MeshAttachment> {
+    let uvs = attachment
+        .uvs
+        .chunks(2)
+        .map(|uv| [uv[0], *uv.get(1).unwrap_or(&0.)])
+        .collect::<Vec<_>>();
+    let mut vertices = Vec::with_capacity(uvs.len());
+    if attachment.vertices.len() == attachment.uvs.len() {
+        for position in attachment.vertices.chunks(2) {
+            vertices.push(vec![VertexInfluence {
+                bone: slot_bone,
+                position: [position[0], *position.get(1).unwrap_or(&0.)],
+                weight: 1.,
+            }]);
+        }
+    } else {
+        // Weighted meshes list the number of bones of each vertex, followed by the index,
+        // position and weight of every bone.
+        let mut values = attachment.vertices.iter();
+        while let Some(&count) = values.next() {
+            let mut influences = Vec::with_capacity(count as usize);
+            for _ in 0..count as usize {
+                let bone = *values.next()? as usize;
+                if bone >= bone_count {
+                    return None;
+                }
+                let position = [*values.next()?, *values.next()?];
+                let weight = *values.next()?;
+                influences.push(VertexInfluence {
+                    bone,
+                    position,
+                    weight,
+                });
+            }
+            vertices.push(influences);
+        }
+    }
+    if vertices.len() != uvs.len()
+        || attachment.triangles.len() % 3 != 0
+        || attachment.triangles.iter().any(|&index| index >= uvs.len())
+    {
+        return None;
+    }
+    Some(MeshAttachment {
+        vertices,
+        uvs,
+        triangles: attachment.triangles.clone(),
+    })
+}
+
+fn timeline_keys<T, F>(keys: &[JsonKey], duration: &mut f32, value: F) -> Vec<TimelineKey<T>>
+where
+    F: Fn(&JsonKey) -> T,
+{
+    keys.iter()
+        .map(|key| {
+            *duration = duration.max(key.time);
+            TimelineKey {
+                time: key.time,
+                value: value(key),
+                stepped: stepped(key.curve.as_ref()),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_spine_skeleton() {
+        let json = br#"{
+            "bones": [{ "name": "root" }, { "name": "arm", "parent": "root", "x": 10 }],
+            "slots": [{ "name": "arm", "bone": "arm", "attachment": "arm" }],
+            "skins": { "default": { "arm": {
+                "arm": { "x": 2, "width": 8, "height": 8 },
+                "mesh": {
+                    "type": "mesh", "uvs": [0, 0, 1, 0, 0, 1], "triangles": [0, 1, 2],
+                    "vertices": [1, 1, 0, 0, 1, 2, 0, 4, 0, 0.5, 1, 4, 0, 0.5, 1, 0, 0, 8, 1]
+                }
+            } } },
+            "animations": { "wave": {
+                "bones": { "arm": { "rotate": [{ "time": 0, "angle": 0 }, { "time": 0.5, "angle": 30 }] } },
+                "slots": { "arm": { "attachment": [{ "time": 0.25, "name": null }] } },
+                "deform": { "default": { "arm": { "mesh": [
+                    { "time": 0.5, "offset": 2, "vertices": [3, 4] }
+                ] } } },
+                "events": [{ "time": 0.75, "name": "done" }]
+            } }
+        }"#;
+        let data = SpineFormat.import(json.to_vec(), ()).unwrap();
+        assert_eq!(Some(0), data.bones[1].parent);
+        assert_eq!(2, data.skins["default"].len());
+        let attachment = data.attachment(None, 0, "arm").unwrap();
+        assert_eq!(
+            ("arm", None),
+            (attachment.sprite.as_str(), &attachment.mesh)
+        );
+        let mesh = data
+            .attachment(None, 0, "mesh")
+            .unwrap()
+            .mesh
+            .as_ref()
+            .unwrap();
+        assert_eq!(
+            vec![1, 2, 1],
+            mesh.vertices.iter().map(Vec::len).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            (0, [4., 0.]),
+            (mesh.vertices[1][0].bone, mesh.vertices[1][0].position)
+        );
+
+        let wave = &data.animations["wave"];
+        assert_eq!((0.75, 1), (wave.duration, wave.events.len()));
+        assert_eq!(Some(None), wave.attachment(0, 0.5));
+        assert_eq!(None, wave.attachment(0, 0.));
+        assert_eq!(
+            Some(vec![0., 0., 3., 4., 0., 0., 0., 0.]),
+            wave.deform(None, 0, "mesh", 0.5)
+        );
+    }
+}
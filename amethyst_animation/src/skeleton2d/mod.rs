@@ -0,0 +1,6 @@
+pub use self::{format::*, pass::*, resources::*, systems::*};
+
+mod format;
+mod pass;
+mod resources;
+mod systems;
@@ -0,0 +1,144 @@
+use gfx::{preset::blend, pso::buffer::ElemStride, state::ColorMask};
+use glsl_layout::{mat4, Uniform};
+
+use amethyst_assets::AssetStorage;
+use amethyst_core::{
+    specs::prelude::{Join, Read, ReadStorage},
+    GlobalTransform,
+};
+use amethyst_renderer::{
+    error::Result,
+    get_camera,
+    pipe::{
+        pass::{Pass, PassData},
+        DepthMode, Effect, NewEffect,
+    },
+    ActiveCamera, Camera, Encoder, Factory, Hidden, HiddenPropagate, Mesh, PosTex, Texture,
+    VertexFormat,
+};
+
+use super::resources::SkeletonMesh;
+
+const VERT_SRC: &[u8] = include_bytes!("shaders/mesh_vertex.glsl");
+const FRAG_SRC: &[u8] = include_bytes!("shaders/mesh_frag.glsl");
+
+#[derive(Copy, Clone, Debug, Uniform)]
+#[allow(dead_code)] // This is used by the shaders
+#[repr(C)]
+struct SkeletonMeshArgs {
+    proj: mat4,
+    view: mat4,
+    model: mat4,
+}
+
+/// Draws the `SkeletonMesh`es of the mesh attachments of `Skeleton2d`s.
+///
+/// The meshes are blended over what was drawn before and write depth, like the sprites of the
+/// other slots, so add the pass after the sprite pass drawing the skeletons.
+#[derive(Default)]
+pub struct DrawSkeletonMeshes;
+
+impl DrawSkeletonMeshes {
+    /// Creates the pass.
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<'a> PassData<'a> for DrawSkeletonMeshes {
+    type Data = (
+        Read<'a, ActiveCamera>,
+        ReadStorage<'a, Camera>,
+        Read<'a, AssetStorage<Texture>>,
+        ReadStorage<'a, GlobalTransform>,
+        ReadStorage<'a, SkeletonMesh>,
+        ReadStorage<'a, Hidden>,
+        ReadStorage<'a, HiddenPropagate>,
+    );
+}
+
+impl Pass for DrawSkeletonMeshes {
+    fn compile(&mut self, effect: NewEffect<'_>) -> Result<Effect> {
+        use std::mem;
+
+        effect
+            .simple(VERT_SRC, FRAG_SRC)
+            .without_back_face_culling()
+            .with_raw_constant_buffer(
+                "SkeletonMeshArgs",
+                mem::size_of::<<SkeletonMeshArgs as Uniform>::Std140>(),
+                1,
+            )
+            .with_raw_vertex_buffer(PosTex::ATTRIBUTES, PosTex::size() as ElemStride, 0)
+            .with_texture("albedo")
+            .with_blended_output(
+                "color",
+                ColorMask::all(),
+                blend::ALPHA,
+                Some(DepthMode::LessEqualWrite),
+            )
+            .build()
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        mut factory: Factory,
+        (
+            active,
+            camera,
+            textures,
+            globals,
+            meshes,
+            hidden,
+            hidden_prop,
+        ): <Self as PassData<'a>>::Data,
+    ) {
+        let (camera, camera_transform) = match get_camera(active, &camera, &globals) {
+            Some(camera) => camera,
+            None => return,
+        };
+        let view = match camera_transform.0.try_inverse() {
+            Some(view) => view,
+            None => return,
+        };
+        let proj: [[f32; 4]; 4] = camera.proj.into();
+        let view: [[f32; 4]; 4] = view.into();
+
+        for (mesh, global, _, _) in (&meshes, &globals, !&hidden, !&hidden_prop).join() {
+            if mesh.vertices.is_empty() {
+                continue;
+            }
+            let texture = match textures.get(&mesh.texture) {
+                Some(texture) => texture,
+                None => continue,
+            };
+            // The vertices move every frame, so the mesh is built again each time.
+            let triangles = match Mesh::build(mesh.vertices.clone()).build(&mut factory) {
+                Ok(triangles) => triangles,
+                Err(e) => {
+                    error!("Failed to build the mesh of a skeleton: {}", e);
+                    continue;
+                }
+            };
+            let vbuf = match triangles.buffer(PosTex::ATTRIBUTES) {
+                Some(vbuf) => vbuf.clone(),
+                None => continue,
+            };
+
+            let model: [[f32; 4]; 4] = global.0.into();
+            let args = SkeletonMeshArgs {
+                proj: proj.into(),
+                view: view.into(),
+                model: model.into(),
+            };
+            effect.update_constant_buffer("SkeletonMeshArgs", &args.std140(), encoder);
+            effect.data.vertex_bufs.push(vbuf);
+            effect.data.textures.push(texture.view().clone());
+            effect.data.samplers.push(texture.sampler().clone());
+            effect.draw(triangles.slice(), encoder);
+            effect.clear();
+        }
+    }
+}
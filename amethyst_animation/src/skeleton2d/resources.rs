@@ -0,0 +1,542 @@
+use std::collections::HashMap;
+
+use amethyst_assets::{Asset, Handle, ProcessingState, Result};
+use amethyst_core::{
+    specs::prelude::{Component, DenseVecStorage, Entity, VecStorage},
+    Transform,
+};
+use amethyst_renderer::{PosTex, SpriteSheetHandle, TextureHandle};
+
+/// Local transform of a bone or an attachment, as in Spine: in pixels, with the rotation in
+/// degrees counter clockwise.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BonePose {
+    /// Horizontal position relative to the parent
+    pub x: f32,
+    /// Vertical position relative to the parent
+    pub y: f32,
+    /// Rotation in degrees
+    pub rotation: f32,
+    /// Horizontal scale
+    pub scale_x: f32,
+    /// Vertical scale
+    pub scale_y: f32,
+}
+
+impl Default for BonePose {
+    fn default() -> Self {
+        BonePose {
+            x: 0.,
+            y: 0.,
+            rotation: 0.,
+            scale_x: 1.,
+            scale_y: 1.,
+        }
+    }
+}
+
+impl BonePose {
+    /// Interpolates between two poses, rotating the shortest way.
+    pub fn lerp(&self, other: &BonePose, t: f32) -> BonePose {
+        let mut rotation = other.rotation - self.rotation;
+        rotation -= (rotation / 360.).round() * 360.;
+        BonePose {
+            x: self.x + (other.x - self.x) * t,
+            y: self.y + (other.y - self.y) * t,
+            rotation: self.rotation + rotation * t,
+            scale_x: self.scale_x + (other.scale_x - self.scale_x) * t,
+            scale_y: self.scale_y + (other.scale_y - self.scale_y) * t,
+        }
+    }
+
+    /// Sets the pose on a `Transform`, keeping its depth.
+    pub fn apply(&self, transform: &mut Transform) {
+        transform
+            .set_x(self.x)
+            .set_y(self.y)
+            .set_rotation_euler(0., 0., self.rotation.to_radians())
+            .set_scale(self.scale_x, self.scale_y, 1.);
+    }
+}
+
+/// A bone of a skeleton.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BoneData {
+    /// Name of the bone
+    pub name: String,
+    /// Index of the parent bone, `None` for the root bone
+    pub parent: Option<usize>,
+    /// Setup pose of the bone
+    pub pose: BonePose,
+}
+
+/// A slot of a skeleton, holding at most one attachment at a time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SlotData {
+    /// Name of the slot
+    pub name: String,
+    /// Index of the bone the slot is attached to
+    pub bone: usize,
+    /// Name of the attachment in the setup pose
+    pub attachment: Option<String>,
+}
+
+/// An attachment, drawn as a sprite, or as a mesh textured with the sprite.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AttachmentData {
+    /// Name of the sprite in the sprite sheet of the skeleton
+    pub sprite: String,
+    /// Pose of the sprite relative to the bone of the slot
+    pub pose: BonePose,
+    /// Mesh of a mesh attachment, `None` for a region attachment
+    pub mesh: Option<MeshAttachment>,
+}
+
+/// A bone moving a vertex of a mesh attachment.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VertexInfluence {
+    /// Index of the bone
+    pub bone: usize,
+    /// Position of the vertex relative to the bone
+    pub position: [f32; 2],
+    /// Weight of the bone, the weights of a vertex adding up to 1
+    pub weight: f32,
+}
+
+/// Mesh of a mesh attachment, following the bones of the skeleton.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MeshAttachment {
+    /// Bones moving each vertex. Meshes that aren't weighted have a single influence per vertex,
+    /// the bone of the slot.
+    pub vertices: Vec<Vec<VertexInfluence>>,
+    /// Texture coordinates of each vertex in the sprite, from its top left corner
+    pub uvs: Vec<[f32; 2]>,
+    /// Indices of the vertices, by three for each triangle
+    pub triangles: Vec<usize>,
+}
+
+impl MeshAttachment {
+    /// Returns the number of influences of all the vertices, a deform timeline holding an `x`
+    /// and `y` offset for each of them.
+    pub fn influences(&self) -> usize {
+        self.vertices.iter().map(Vec::len).sum()
+    }
+}
+
+/// Key of a timeline. The value is interpolated linearly until the next key, or held if
+/// `stepped`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimelineKey<T> {
+    /// Time of the key in seconds
+    pub time: f32,
+    /// Value at the key
+    pub value: T,
+    /// Holds the value until the next key
+    pub stepped: bool,
+}
+
+/// Timelines of a bone in an animation, relative to its setup pose.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BoneTimeline {
+    /// Index of the bone
+    pub bone: usize,
+    /// Rotation in degrees added to the setup pose
+    pub rotate: Vec<TimelineKey<f32>>,
+    /// Translation added to the setup pose
+    pub translate: Vec<TimelineKey<[f32; 2]>>,
+    /// Scale multiplying the setup pose
+    pub scale: Vec<TimelineKey<[f32; 2]>>,
+}
+
+/// Deform timeline of a mesh attachment in an animation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeformTimeline {
+    /// Name of the skin holding the attachment
+    pub skin: String,
+    /// Index of the slot
+    pub slot: usize,
+    /// Name of the attachment
+    pub attachment: String,
+    /// Offsets added to the position of each influence, as `x` and `y` pairs in the order of
+    /// `MeshAttachment::vertices`
+    pub keys: Vec<TimelineKey<Vec<f32>>>,
+}
+
+/// Key of an event timeline.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EventKey {
+    /// Time of the event in seconds
+    pub time: f32,
+    /// Name of the event
+    pub name: String,
+    /// Integer value of the event
+    pub int: i32,
+    /// Float value of the event
+    pub float: f32,
+    /// String value of the event
+    pub string: Option<String>,
+}
+
+/// An animation of a skeleton.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SkeletonAnimation {
+    /// Duration of the animation in seconds
+    pub duration: f32,
+    /// Timelines of the animated bones
+    pub bones: Vec<BoneTimeline>,
+    /// Attachment changes by slot index, as `(time, attachment)` keys
+    pub attachments: Vec<(usize, Vec<(f32, Option<String>)>)>,
+    /// Deform timelines of the mesh attachments
+    pub deforms: Vec<DeformTimeline>,
+    /// Events, by increasing time
+    pub events: Vec<EventKey>,
+}
+
+impl SkeletonAnimation {
+    /// Writes the pose of the bones at the given time into `poses`, which holds the setup pose.
+    pub fn pose(&self, time: f32, poses: &mut [BonePose]) {
+        for timeline in &self.bones {
+            let pose = match poses.get_mut(timeline.bone) {
+                Some(pose) => pose,
+                None => continue,
+            };
+            if let Some(rotation) = sample(&timeline.rotate, time, |&a, &b, t| a + (b - a) * t) {
+                pose.rotation += rotation;
+            }
+            if let Some([x, y]) = sample(&timeline.translate, time, lerp2) {
+                pose.x += x;
+                pose.y += y;
+            }
+            if let Some([x, y]) = sample(&timeline.scale, time, lerp2) {
+                pose.scale_x *= x;
+                pose.scale_y *= y;
+            }
+        }
+    }
+
+    /// Returns the attachment of the slot at the given time, or `None` if the animation doesn't
+    /// change it.
+    pub fn attachment(&self, slot: usize, time: f32) -> Option<Option<&str>> {
+        self.attachments
+            .iter()
+            .find(|(index, _)| *index == slot)
+            .and_then(|(_, keys)| keys.iter().rev().find(|(key, _)| *key <= time))
+            .map(|(_, attachment)| attachment.as_ref().map(String::as_str))
+    }
+
+    /// Returns the offsets of the influences of a mesh attachment at the given time, or `None`
+    /// if the animation doesn't deform it. Timelines of the "default" skin apply to every skin.
+    pub fn deform(
+        &self,
+        skin: Option<&str>,
+        slot: usize,
+        attachment: &str,
+        time: f32,
+    ) -> Option<Vec<f32>> {
+        let find = |skin: &str| {
+            self.deforms.iter().find(|timeline| {
+                timeline.slot == slot && timeline.attachment == attachment && timeline.skin == skin
+            })
+        };
+        skin.and_then(find)
+            .or_else(|| find("default"))
+            .and_then(|timeline| {
+                sample(&timeline.keys, time, |a, b, t| {
+                    a.iter().zip(b).map(|(a, b)| a + (b - a) * t).collect()
+                })
+            })
+    }
+}
+
+fn lerp2(a: &[f32; 2], b: &[f32; 2], t: f32) -> [f32; 2] {
+    [a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t]
+}
+
+fn sample<T, F>(keys: &[TimelineKey<T>], time: f32, lerp: F) -> Option<T>
+where
+    T: Clone,
+    F: Fn(&T, &T, f32) -> T,
+{
+    let next = keys.iter().position(|key| key.time > time);
+    match next {
+        None => keys.last().map(|key| key.value.clone()),
+        Some(0) => keys.first().map(|key| key.value.clone()),
+        Some(next) => {
+            let (from, to) = (&keys[next - 1], &keys[next]);
+            if from.stepped {
+                Some(from.value.clone())
+            } else {
+                let t = (time - from.time) / (to.time - from.time);
+                Some(lerp(&from.value, &to.value, t))
+            }
+        }
+    }
+}
+
+/// Bones, slots, skins and animations of a 2D skeleton.
+///
+/// Load it from a Spine JSON export with the `SpineFormat`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SkeletonData {
+    /// The bones, parents coming before their children
+    pub bones: Vec<BoneData>,
+    /// The slots, in draw order
+    pub slots: Vec<SlotData>,
+    /// The attachments by skin name, slot index and attachment name
+    pub skins: HashMap<String, HashMap<(usize, String), AttachmentData>>,
+    /// The animations by name
+    pub animations: HashMap<String, SkeletonAnimation>,
+}
+
+impl SkeletonData {
+    /// Returns the index of the bone with the given name.
+    pub fn bone(&self, name: &str) -> Option<usize> {
+        self.bones.iter().position(|bone| bone.name == name)
+    }
+
+    /// Returns the index of the slot with the given name.
+    pub fn slot(&self, name: &str) -> Option<usize> {
+        self.slots.iter().position(|slot| slot.name == name)
+    }
+
+    /// Returns the attachment of a slot in the given skin, falling back to the "default" skin.
+    pub fn attachment(
+        &self,
+        skin: Option<&str>,
+        slot: usize,
+        name: &str,
+    ) -> Option<&AttachmentData> {
+        let key = (slot, name.to_string());
+        skin.and_then(|skin| self.skins.get(skin))
+            .and_then(|skin| skin.get(&key))
+            .or_else(|| self.skins.get("default").and_then(|skin| skin.get(&key)))
+    }
+}
+
+impl Asset for SkeletonData {
+    const NAME: &'static str = "animation::SkeletonData";
+    type Data = Self;
+    type HandleStorage = VecStorage<Handle<Self>>;
+}
+
+impl Into<Result<ProcessingState<SkeletonData>>> for SkeletonData {
+    fn into(self) -> Result<ProcessingState<SkeletonData>> {
+        Ok(ProcessingState::Loaded(self))
+    }
+}
+
+/// An animation being played on a skeleton.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Track {
+    pub animation: String,
+    pub time: f32,
+    pub looping: bool,
+}
+
+/// Component making its entity the root of a 2D skeleton.
+///
+/// The `Skeleton2dSystem` creates a child entity with a `Transform` for each bone, and a child
+/// entity of the bone for each slot, drawing the current attachment of the slot with a
+/// `SpriteRender` from the sprite sheet, looked up by name. Slots are drawn in order, each slightly
+/// in front of the previous one.
+///
+/// Mesh attachments are textured with their sprite instead: their slot gets a `SkeletonMesh`
+/// moved by the bones and the deform timeline of the current animation, drawn by the
+/// `DrawSkeletonMeshes` pass.
+///
+/// Play animations with `play`, or mix into them with `crossfade`. The `Skeleton2dBundle` adds the
+/// system and the processor of `SkeletonData`.
+#[derive(Clone, Debug)]
+pub struct Skeleton2d {
+    /// Handle of the skeleton
+    pub data: Handle<SkeletonData>,
+    /// Sprite sheet with sprites named like the attachments
+    pub sprite_sheet: SpriteSheetHandle,
+    /// Skin of the skeleton, falling back to the "default" skin
+    pub skin: Option<String>,
+    /// Speed multiplier of the animations
+    pub speed: f32,
+    pub(crate) current: Option<Track>,
+    pub(crate) previous: Option<Track>,
+    /// Elapsed and total time of the crossfade from the previous animation.
+    pub(crate) mix: (f32, f32),
+    pub(crate) bones: Vec<Entity>,
+    pub(crate) slots: Vec<Entity>,
+}
+
+impl Skeleton2d {
+    /// Creates a skeleton in its setup pose, drawing its attachments from the sprite sheet.
+    pub fn new(data: Handle<SkeletonData>, sprite_sheet: SpriteSheetHandle) -> Self {
+        Skeleton2d {
+            data,
+            sprite_sheet,
+            skin: None,
+            speed: 1.,
+            current: None,
+            previous: None,
+            mix: (0., 0.),
+            bones: Vec::new(),
+            slots: Vec::new(),
+        }
+    }
+
+    /// Sets the skin of the skeleton.
+    pub fn with_skin(mut self, skin: &str) -> Self {
+        self.skin = Some(skin.to_string());
+        self
+    }
+
+    /// Plays an animation from the start, replacing the current one.
+    pub fn play(&mut self, animation: &str, looping: bool) {
+        self.crossfade(animation, looping, 0.);
+    }
+
+    /// Plays an animation from the start, mixing from the current one over `duration` seconds.
+    pub fn crossfade(&mut self, animation: &str, looping: bool, duration: f32) {
+        self.previous = if duration > 0. {
+            self.current.take()
+        } else {
+            None
+        };
+        self.current = Some(Track {
+            animation: animation.to_string(),
+            time: 0.,
+            looping,
+        });
+        self.mix = (0., duration);
+    }
+
+    /// Stops the animations, going back to the setup pose.
+    pub fn stop(&mut self) {
+        self.current = None;
+        self.previous = None;
+    }
+
+    /// Returns the name of the current animation.
+    pub fn animation(&self) -> Option<&str> {
+        self.current.as_ref().map(|track| track.animation.as_str())
+    }
+
+    /// Returns the entities of the bones, indexed like the bones of the `SkeletonData`, or an
+    /// empty slice until the system created them.
+    pub fn bones(&self) -> &[Entity] {
+        &self.bones
+    }
+
+    /// Returns the entities of the slots, indexed like the slots of the `SkeletonData`.
+    pub fn slots(&self) -> &[Entity] {
+        &self.slots
+    }
+}
+
+impl Component for Skeleton2d {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Triangles of a mesh attachment, written on the slot entity by the `Skeleton2dSystem` and drawn
+/// by the `DrawSkeletonMeshes` pass.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SkeletonMesh {
+    /// Texture of the sprite sheet of the skeleton
+    pub texture: TextureHandle,
+    /// Vertices of the triangles, relative to the slot entity
+    pub vertices: Vec<PosTex>,
+}
+
+impl Component for SkeletonMesh {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Event sent by the `Skeleton2dSystem` on an `EventChannel<SkeletonEvent>`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SkeletonEvent {
+    /// An event key of the current animation was reached.
+    Event {
+        /// The skeleton entity
+        entity: Entity,
+        /// The event key
+        event: EventKey,
+    },
+    /// An animation that doesn't loop reached its end.
+    Complete {
+        /// The skeleton entity
+        entity: Entity,
+        /// Name of the animation
+        animation: String,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timelines_are_relative_to_setup_pose() {
+        let animation = SkeletonAnimation {
+            duration: 1.,
+            bones: vec![BoneTimeline {
+                bone: 0,
+                rotate: vec![
+                    TimelineKey {
+                        time: 0.,
+                        value: 0.,
+                        stepped: false,
+                    },
+                    TimelineKey {
+                        time: 1.,
+                        value: 90.,
+                        stepped: false,
+                    },
+                ],
+                translate: vec![TimelineKey {
+                    time: 0.,
+                    value: [2., 4.],
+                    stepped: true,
+                }],
+                scale: Vec::new(),
+            }],
+            ..Default::default()
+        };
+        let mut poses = [BonePose {
+            x: 1.,
+            rotation: 10.,
+            ..Default::default()
+        }];
+        animation.pose(0.5, &mut poses);
+        assert_eq!((3., 4., 55.), (poses[0].x, poses[0].y, poses[0].rotation));
+
+        let from = BonePose {
+            rotation: 350.,
+            ..Default::default()
+        };
+        let to = BonePose {
+            rotation: 10.,
+            ..Default::default()
+        };
+        assert!((from.lerp(&to, 0.5).rotation - 360.).abs() < 1.0e-5);
+    }
+
+    #[test]
+    fn deform_falls_back_to_default_skin() {
+        let key = |time, value| TimelineKey {
+            time,
+            value,
+            stepped: false,
+        };
+        let animation = SkeletonAnimation {
+            duration: 1.,
+            deforms: vec![DeformTimeline {
+                skin: "default".to_string(),
+                slot: 0,
+                attachment: "cape".to_string(),
+                keys: vec![key(0., vec![0., 0.]), key(1., vec![4., -2.])],
+            }],
+            ..Default::default()
+        };
+        assert_eq!(
+            Some(vec![2., -1.]),
+            animation.deform(Some("red"), 0, "cape", 0.5)
+        );
+        assert_eq!(None, animation.deform(None, 1, "cape", 0.5));
+    }
+}
@@ -0,0 +1,17 @@
+#version 150 core
+
+uniform sampler2D albedo;
+
+in VertexData {
+    vec2 tex_coord;
+} vertex;
+
+out vec4 color;
+
+void main() {
+    color = texture(albedo, vertex.tex_coord);
+    // Keep the transparent parts of the sprite from hiding the slots behind.
+    if (color.a <= 0.0) {
+        discard;
+    }
+}
@@ -0,0 +1,20 @@
+#version 150 core
+
+layout (std140) uniform SkeletonMeshArgs {
+    uniform mat4 proj;
+    uniform mat4 view;
+    uniform mat4 model;
+};
+
+// Triangles relative to the slot entity.
+in vec3 position;
+in vec2 tex_coord;
+
+out VertexData {
+    vec2 tex_coord;
+} vertex;
+
+void main() {
+    vertex.tex_coord = tex_coord;
+    gl_Position = proj * view * model * vec4(position, 1.0);
+}
@@ -0,0 +1,503 @@
+use amethyst_assets::AssetStorage;
+use amethyst_core::{
+    nalgebra::{Vector2, Vector3},
+    shrev::EventChannel,
+    specs::prelude::{Entities, Entity, Join, Read, System, Write, WriteStorage},
+    timing::Time,
+    Parent, Transform,
+};
+use amethyst_renderer::{Hidden, PosTex, Sprite, SpriteRender, SpriteSheet};
+
+use super::resources::{
+    BonePose, EventKey, MeshAttachment, Skeleton2d, SkeletonData, SkeletonEvent, SkeletonMesh,
+    Track,
+};
+
+/// Depth between the slots of a skeleton, so later slots are drawn in front.
+const SLOT_DEPTH: f32 = 0.001;
+
+/// 2D affine transform `[a, b, c, d, x, y]`, mapping `(u, v)` to
+/// `(a * u + c * v + x, b * u + d * v + y)`.
+type Affine = [f32; 6];
+
+/// System creating the bone and slot entities of `Skeleton2d`s, playing their animations and
+/// updating the attachments of their slots, including the `SkeletonMesh`es of the mesh
+/// attachments.
+///
+/// Sends `SkeletonEvent`s for the events of the animations. It should run before the
+/// `TransformSystem`.
+#[derive(Default)]
+pub struct Skeleton2dSystem {
+    poses: Vec<BonePose>,
+    mixed: Vec<BonePose>,
+    worlds: Vec<Affine>,
+}
+
+impl Skeleton2dSystem {
+    /// Creates a new `Skeleton2dSystem`.
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<'a> System<'a> for Skeleton2dSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, Time>,
+        Read<'a, AssetStorage<SkeletonData>>,
+        Read<'a, AssetStorage<SpriteSheet>>,
+        WriteStorage<'a, Skeleton2d>,
+        WriteStorage<'a, Transform>,
+        WriteStorage<'a, Parent>,
+        WriteStorage<'a, SpriteRender>,
+        WriteStorage<'a, SkeletonMesh>,
+        WriteStorage<'a, Hidden>,
+        Write<'a, EventChannel<SkeletonEvent>>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            time,
+            skeleton_storage,
+            sprite_sheet_storage,
+            mut skeletons,
+            mut transforms,
+            mut parents,
+            mut sprites,
+            mut meshes,
+            mut hidden,
+            mut events,
+        ): Self::SystemData,
+    ) {
+        for (entity, skeleton) in (&*entities, &mut skeletons).join() {
+            let data = match skeleton_storage.get(&skeleton.data) {
+                Some(data) => data,
+                None => continue,
+            };
+            let sprite_sheet = match sprite_sheet_storage.get(&skeleton.sprite_sheet) {
+                Some(sprite_sheet) => sprite_sheet,
+                None => continue,
+            };
+            if skeleton.bones.len() != data.bones.len() {
+                spawn(
+                    entity,
+                    skeleton,
+                    data,
+                    &entities,
+                    &mut transforms,
+                    &mut parents,
+                );
+            }
+
+            // Advance the animations.
+            let delta = time.delta_seconds() * skeleton.speed;
+            if let Some(ref mut track) = skeleton.current {
+                if let Some(animation) = data.animations.get(&track.animation) {
+                    let from = track.time;
+                    let finished = advance(track, animation.duration, delta);
+                    for key in crossed(&animation.events, from, track.time, animation.duration) {
+                        events.single_write(SkeletonEvent::Event {
+                            entity,
+                            event: key.clone(),
+                        });
+                    }
+                    if finished {
+                        events.single_write(SkeletonEvent::Complete {
+                            entity,
+                            animation: track.animation.clone(),
+                        });
+                    }
+                }
+            }
+            if let Some(ref mut track) = skeleton.previous {
+                if let Some(animation) = data.animations.get(&track.animation) {
+                    advance(track, animation.duration, delta);
+                }
+            }
+            skeleton.mix.0 += delta;
+            if skeleton.mix.0 >= skeleton.mix.1 {
+                skeleton.previous = None;
+            }
+
+            // Pose the bones.
+            sample_pose(data, skeleton.current.as_ref(), &mut self.poses);
+            if let Some(ref previous) = skeleton.previous {
+                sample_pose(data, Some(previous), &mut self.mixed);
+                let t = skeleton.mix.0 / skeleton.mix.1;
+                for (mixed, pose) in self.mixed.iter().zip(self.poses.iter_mut()) {
+                    *pose = mixed.lerp(pose, t);
+                }
+            }
+            for (bone, pose) in skeleton.bones.iter().zip(&self.poses) {
+                if let Some(transform) = transforms.get_mut(*bone) {
+                    pose.apply(transform);
+                }
+            }
+
+            // Show the current attachments.
+            world_transforms(data, &self.poses, &mut self.worlds);
+            let skin = skeleton.skin.as_ref().map(String::as_str);
+            let current = skeleton
+                .current
+                .as_ref()
+                .and_then(|track| Some((data.animations.get(&track.animation)?, track.time)));
+            for (index, (slot_entity, slot)) in skeleton.slots.iter().zip(&data.slots).enumerate() {
+                let name = current
+                    .and_then(|(animation, time)| animation.attachment(index, time))
+                    .unwrap_or_else(|| slot.attachment.as_ref().map(String::as_str));
+                let attachment =
+                    name.and_then(|name| Some((name, data.attachment(skin, index, name)?)));
+                let sprite_number = attachment
+                    .and_then(|(_, attachment)| sprite_sheet.sprite_number(&attachment.sprite));
+                let shown = match (attachment, sprite_number) {
+                    (Some((name, attachment)), Some(sprite_number)) => match attachment.mesh {
+                        Some(ref mesh) => {
+                            let deform = current.and_then(|(animation, time)| {
+                                animation.deform(skin, index, name, time)
+                            });
+                            let vertices = mesh_vertices(
+                                mesh,
+                                &self.worlds,
+                                slot.bone,
+                                deform.as_ref().map(Vec::as_slice),
+                                &sprite_sheet.sprites[sprite_number],
+                            );
+                            if let Some(transform) = transforms.get_mut(*slot_entity) {
+                                BonePose::default().apply(transform);
+                            }
+                            sprites.remove(*slot_entity);
+                            match vertices {
+                                Some(vertices) => {
+                                    let mesh = SkeletonMesh {
+                                        texture: sprite_sheet.texture.clone(),
+                                        vertices,
+                                    };
+                                    if let Err(e) = meshes.insert(*slot_entity, mesh) {
+                                        error!("Failed to show mesh of skeleton: {}", e);
+                                    }
+                                    true
+                                }
+                                None => false,
+                            }
+                        }
+                        None => {
+                            if let Some(transform) = transforms.get_mut(*slot_entity) {
+                                attachment.pose.apply(transform);
+                            }
+                            let sprite = SpriteRender {
+                                sprite_sheet: skeleton.sprite_sheet.clone(),
+                                sprite_number,
+                            };
+                            if sprites.get(*slot_entity) != Some(&sprite) {
+                                if let Err(e) = sprites.insert(*slot_entity, sprite) {
+                                    error!("Failed to show attachment of skeleton: {}", e);
+                                }
+                            }
+                            meshes.remove(*slot_entity);
+                            true
+                        }
+                    },
+                    _ => false,
+                };
+                if shown {
+                    hidden.remove(*slot_entity);
+                } else if !hidden.contains(*slot_entity) {
+                    if let Err(e) = hidden.insert(*slot_entity, Hidden) {
+                        error!("Failed to hide slot of skeleton: {}", e);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Creates the entities of the bones and slots of a skeleton.
+fn spawn(
+    entity: Entity,
+    skeleton: &mut Skeleton2d,
+    data: &SkeletonData,
+    entities: &Entities<'_>,
+    transforms: &mut WriteStorage<'_, Transform>,
+    parents: &mut WriteStorage<'_, Parent>,
+) {
+    for old in skeleton.bones.drain(..).chain(skeleton.slots.drain(..)) {
+        if let Err(e) = entities.delete(old) {
+            error!("Failed to delete entity of skeleton: {}", e);
+        }
+    }
+    let mut create = |parent: Entity, pose: &BonePose, depth: f32| {
+        let new = entities.create();
+        let mut transform = Transform::default();
+        transform.set_z(depth);
+        pose.apply(&mut transform);
+        transforms
+            .insert(new, transform)
+            .expect("Unreachable: Inserting into a freshly created entity");
+        parents
+            .insert(new, Parent { entity: parent })
+            .expect("Unreachable: Inserting into a freshly created entity");
+        new
+    };
+    for bone in &data.bones {
+        let parent = bone.parent.map_or(entity, |parent| skeleton.bones[parent]);
+        let new = create(parent, &bone.pose, 0.);
+        skeleton.bones.push(new);
+    }
+    for (index, slot) in data.slots.iter().enumerate() {
+        let new = create(
+            skeleton.bones[slot.bone],
+            &BonePose::default(),
+            index as f32 * SLOT_DEPTH,
+        );
+        skeleton.slots.push(new);
+    }
+}
+
+/// Advances the time of a track, returning `true` when an animation that doesn't loop reaches
+/// its end. It then stays in its last pose.
+fn advance(track: &mut Track, duration: f32, delta: f32) -> bool {
+    let from = track.time;
+    track.time += delta;
+    if track.time < duration {
+        false
+    } else if track.looping && duration > 0. {
+        track.time %= duration;
+        false
+    } else {
+        track.time = duration;
+        from < duration
+    }
+}
+
+/// Returns the events in `(from, to]`, wrapping around the end of the animation. Events at the
+/// start are included when the track leaves it.
+fn crossed(events: &[EventKey], from: f32, to: f32, duration: f32) -> Vec<&EventKey> {
+    if from < to {
+        events
+            .iter()
+            .filter(|key| (key.time > from || from <= 0.) && key.time <= to)
+            .collect()
+    } else if from > to {
+        events
+            .iter()
+            .filter(|key| (key.time > from && key.time <= duration) || key.time <= to)
+            .collect()
+    } else {
+        Vec::new()
+    }
+}
+
+/// Writes the pose of the bones for a track into `poses`.
+fn sample_pose(data: &SkeletonData, track: Option<&Track>, poses: &mut Vec<BonePose>) {
+    poses.clear();
+    poses.extend(data.bones.iter().map(|bone| bone.pose));
+    if let Some(track) = track {
+        if let Some(animation) = data.animations.get(&track.animation) {
+            animation.pose(track.time, poses);
+        }
+    }
+}
+
+/// Writes the transforms of the bones relative to the skeleton into `worlds`.
+fn world_transforms(data: &SkeletonData, poses: &[BonePose], worlds: &mut Vec<Affine>) {
+    worlds.clear();
+    for (bone, pose) in data.bones.iter().zip(poses) {
+        let local = local_transform(pose);
+        let world = match bone.parent.and_then(|parent| worlds.get(parent)) {
+            Some(parent) => multiply(parent, &local),
+            None => local,
+        };
+        worlds.push(world);
+    }
+}
+
+/// Returns the transform of a pose, scaling, then rotating, then translating like a `Transform`.
+fn local_transform(pose: &BonePose) -> Affine {
+    let (sin, cos) = pose.rotation.to_radians().sin_cos();
+    [
+        cos * pose.scale_x,
+        sin * pose.scale_x,
+        -sin * pose.scale_y,
+        cos * pose.scale_y,
+        pose.x,
+        pose.y,
+    ]
+}
+
+fn multiply(a: &Affine, b: &Affine) -> Affine {
+    [
+        a[0] * b[0] + a[2] * b[1],
+        a[1] * b[0] + a[3] * b[1],
+        a[0] * b[2] + a[2] * b[3],
+        a[1] * b[2] + a[3] * b[3],
+        a[0] * b[4] + a[2] * b[5] + a[4],
+        a[1] * b[4] + a[3] * b[5] + a[5],
+    ]
+}
+
+fn invert(m: &Affine) -> Option<Affine> {
+    let det = m[0] * m[3] - m[2] * m[1];
+    if det.abs() < ::std::f32::EPSILON {
+        return None;
+    }
+    let (a, b, c, d) = (m[3] / det, -m[1] / det, -m[2] / det, m[0] / det);
+    Some([a, b, c, d, -(a * m[4] + c * m[5]), -(b * m[4] + d * m[5])])
+}
+
+fn transform_point(m: &Affine, [x, y]: [f32; 2]) -> [f32; 2] {
+    [m[0] * x + m[2] * y + m[4], m[1] * x + m[3] * y + m[5]]
+}
+
+/// Returns the triangles of a mesh attachment moved by the bones and the deform offsets,
+/// relative to the bone of its slot and textured with its sprite, or `None` if that bone is
+/// scaled to nothing.
+fn mesh_vertices(
+    mesh: &MeshAttachment,
+    worlds: &[Affine],
+    slot_bone: usize,
+    deform: Option<&[f32]>,
+    sprite: &Sprite,
+) -> Option<Vec<PosTex>> {
+    let to_slot = invert(worlds.get(slot_bone)?)?;
+    let mut offsets = deform.unwrap_or(&[]).chunks(2);
+    let positions = mesh
+        .vertices
+        .iter()
+        .map(|influences| {
+            let mut position = [0., 0.];
+            for influence in influences {
+                let offset = offsets.next().unwrap_or(&[0., 0.]);
+                let local = [
+                    influence.position[0] + offset[0],
+                    influence.position[1] + *offset.get(1).unwrap_or(&0.),
+                ];
+                let world = worlds
+                    .get(influence.bone)
+                    .map_or(local, |bone| transform_point(bone, local));
+                position[0] += world[0] * influence.weight;
+                position[1] += world[1] * influence.weight;
+            }
+            transform_point(&to_slot, position)
+        })
+        .collect::<Vec<_>>();
+
+    // Mesh texture coordinates go down from the top of the sprite.
+    let tex = &sprite.tex_coords;
+    let vertices = mesh
+        .triangles
+        .iter()
+        .filter_map(|&index| {
+            let ([x, y], [u, v]) = (positions.get(index)?, mesh.uvs.get(index)?);
+            Some(PosTex {
+                position: Vector3::new(*x, *y, 0.),
+                tex_coord: Vector2::new(
+                    tex.left + (tex.right - tex.left) * u,
+                    tex.top + (tex.bottom - tex.top) * v,
+                ),
+            })
+        })
+        .collect();
+    Some(vertices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use amethyst_renderer::TextureCoordinates;
+
+    use super::super::resources::VertexInfluence;
+
+    fn event(time: f32) -> EventKey {
+        EventKey {
+            time,
+            name: "step".to_string(),
+            int: 0,
+            float: 0.,
+            string: None,
+        }
+    }
+
+    #[test]
+    fn start_events_fire_once() {
+        let events = [event(0.), event(0.5)];
+        assert_eq!(1, crossed(&events, 0., 0.25, 1.).len());
+        assert!(crossed(&events, 0., 0., 1.).is_empty());
+        assert_eq!(vec![&events[1]], crossed(&events, 0.25, 0.75, 1.));
+        assert_eq!(vec![&events[0]], crossed(&events, 0.75, 0.25, 1.));
+    }
+
+    #[test]
+    fn mesh_follows_weighted_bones() {
+        let mesh = MeshAttachment {
+            vertices: vec![
+                vec![VertexInfluence {
+                    bone: 0,
+                    position: [0., 0.],
+                    weight: 1.,
+                }],
+                vec![
+                    VertexInfluence {
+                        bone: 0,
+                        position: [2., 0.],
+                        weight: 0.5,
+                    },
+                    VertexInfluence {
+                        bone: 1,
+                        position: [0., 0.],
+                        weight: 0.5,
+                    },
+                ],
+                vec![VertexInfluence {
+                    bone: 1,
+                    position: [0., 2.],
+                    weight: 1.,
+                }],
+            ],
+            uvs: vec![[0., 0.], [1., 0.], [0., 1.]],
+            triangles: vec![0, 1, 2],
+        };
+        // The second bone is 4 to the right, turned a quarter counter clockwise.
+        let bones = [
+            local_transform(&BonePose::default()),
+            local_transform(&BonePose {
+                x: 4.,
+                rotation: 90.,
+                ..Default::default()
+            }),
+        ];
+        let sprite = Sprite {
+            width: 8.,
+            height: 8.,
+            offsets: [0., 0.],
+            tex_coords: TextureCoordinates {
+                left: 0.5,
+                right: 1.,
+                bottom: 0.,
+                top: 0.5,
+            },
+        };
+        let deform = [0., 0., 0., 0., 0., 0., 1., 0.];
+        let vertices = mesh_vertices(&mesh, &bones, 0, Some(&deform), &sprite).unwrap();
+        let positions = vertices
+            .iter()
+            .map(|vertex| [vertex.position.x, vertex.position.y])
+            .collect::<Vec<_>>();
+        let expected = [[0., 0.], [3., 0.], [2., 1.]];
+        for (position, expected) in positions.iter().zip(&expected) {
+            assert!(
+                (position[0] - expected[0]).abs() < 1.0e-5,
+                "{:?}",
+                positions
+            );
+            assert!(
+                (position[1] - expected[1]).abs() < 1.0e-5,
+                "{:?}",
+                positions
+            );
+        }
+        assert_eq!(Vector2::new(0.5, 0.), vertices[2].tex_coord);
+    }
+}
@@ -0,0 +1,182 @@
+use std::borrow::Cow;
+
+use amethyst_core::{
+    specs::prelude::{
+        Component, DenseVecStorage, Entities, Entity, Join, ReadExpect, ReadStorage, System,
+        WriteStorage,
+    },
+    transform::components::{Parent, ParentHierarchy},
+    Named,
+};
+
+use super::resources::Joint;
+
+/// Attaches an entity to a named joint of an animated skeleton, e.g. a sword to the hand of a
+/// character.
+///
+/// The `BoneAttachmentSystem` parents the entity to the joint, so its `Transform` becomes the
+/// offset from the joint, and its `GlobalTransform` follows the animated joint. The joint is
+/// searched for among the descendants of the skeleton entity, by the `Named` component the glTF
+/// loader gives to every named node, and is found once the skeleton is loaded.
+///
+/// To attach the entity to another joint, insert a new `BoneAttachment`.
+#[derive(Debug, Clone)]
+pub struct BoneAttachment {
+    skeleton: Entity,
+    bone: Cow<'static, str>,
+    joint: Option<Entity>,
+}
+
+impl BoneAttachment {
+    /// Creates an attachment to the joint named `bone` in the hierarchy below `skeleton`.
+    pub fn new<S>(skeleton: Entity, bone: S) -> Self
+    where
+        S: Into<Cow<'static, str>>,
+    {
+        BoneAttachment {
+            skeleton,
+            bone: bone.into(),
+            joint: None,
+        }
+    }
+
+    /// Returns the root entity of the skeleton.
+    pub fn skeleton(&self) -> Entity {
+        self.skeleton
+    }
+
+    /// Returns the name of the joint.
+    pub fn bone(&self) -> &str {
+        &self.bone
+    }
+
+    /// Returns the joint entity, once it was found.
+    pub fn joint(&self) -> Option<Entity> {
+        self.joint
+    }
+}
+
+impl Component for BoneAttachment {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// System parenting the entities with a `BoneAttachment` to their joint.
+///
+/// Needs the `ParentHierarchy` of the `TransformBundle`. When it runs after the
+/// `TransformSystem`, the attachment follows the joint from the frame after it was found.
+#[derive(Default)]
+pub struct BoneAttachmentSystem;
+
+impl BoneAttachmentSystem {
+    /// Creates a new `BoneAttachmentSystem`
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<'a> System<'a> for BoneAttachmentSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, ParentHierarchy>,
+        ReadStorage<'a, Joint>,
+        ReadStorage<'a, Named>,
+        WriteStorage<'a, BoneAttachment>,
+        WriteStorage<'a, Parent>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, hierarchy, joints, names, mut attachments, mut parents): Self::SystemData,
+    ) {
+        for (entity, attachment) in (&*entities, &mut attachments).join() {
+            if let Some(joint) = attachment.joint {
+                if entities.is_alive(joint)
+                    && parents.get(entity).map(|parent| parent.entity) == Some(joint)
+                {
+                    continue;
+                }
+                // The skeleton was unloaded, or the parent replaced, search again.
+                attachment.joint = None;
+                if parents.get(entity).map(|parent| parent.entity) == Some(joint) {
+                    parents.remove(entity);
+                }
+            }
+            if !entities.is_alive(attachment.skeleton) {
+                continue;
+            }
+
+            let found = hierarchy
+                .all_children_iter(attachment.skeleton)
+                .filter(|child| joints.contains(*child))
+                .find(|child| {
+                    names
+                        .get(*child)
+                        .map_or(false, |named| named.name == attachment.bone)
+                });
+            if let Some(joint) = found {
+                if let Err(e) = parents.insert(entity, Parent { entity: joint }) {
+                    error!("Failed to attach {:?} to joint {:?}: {}", entity, joint, e);
+                    continue;
+                }
+                attachment.joint = Some(joint);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_core::{
+        bundle::SystemBundle,
+        specs::prelude::{Builder, DispatcherBuilder, World},
+        TransformBundle,
+    };
+
+    use super::*;
+
+    #[test]
+    fn attachments_are_parented_to_their_joint() {
+        let mut world = World::new();
+        let mut builder = DispatcherBuilder::new();
+        TransformBundle::new().build(&mut builder).unwrap();
+        builder.add(
+            BoneAttachmentSystem::new(),
+            "bone_attachment",
+            &["parent_hierarchy_system"],
+        );
+        let mut dispatcher = builder.build();
+        dispatcher.setup(&mut world.res);
+
+        let skeleton = world.create_entity().build();
+        let spine = world
+            .create_entity()
+            .with(Joint { skins: Vec::new() })
+            .with(Named::new("spine"))
+            .with(Parent { entity: skeleton })
+            .build();
+        let hand = world
+            .create_entity()
+            .with(Joint { skins: Vec::new() })
+            .with(Named::new("hand"))
+            .with(Parent { entity: spine })
+            .build();
+        let sword = world
+            .create_entity()
+            .with(BoneAttachment::new(skeleton, "hand"))
+            .build();
+        let missing = world
+            .create_entity()
+            .with(BoneAttachment::new(skeleton, "tail"))
+            .build();
+
+        dispatcher.dispatch(&world.res);
+        world.maintain();
+
+        let attachments = world.read_storage::<BoneAttachment>();
+        let parents = world.read_storage::<Parent>();
+        assert_eq!(Some(hand), attachments.get(sword).unwrap().joint());
+        assert_eq!(Some(hand), parents.get(sword).map(|parent| parent.entity));
+        assert_eq!(None, attachments.get(missing).unwrap().joint());
+        assert!(parents.get(missing).is_none());
+    }
+}
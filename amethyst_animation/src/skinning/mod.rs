@@ -1,4 +1,5 @@
-pub use self::{resources::*, systems::*};
+pub use self::{attachment::*, resources::*, systems::*};
 
+mod attachment;
 mod resources;
 mod systems;
@@ -24,6 +24,12 @@ error_chain! {
             display("Format {:?} could not load asset", format)
         }
 
+        /// Returned if an asset group isn't in the manifest.
+        UnknownGroup(name: String) {
+            description("Asset group is not in the manifest")
+            display("Asset group {:?} is not in the manifest", name)
+        }
+
         /// Returned if an asset is loaded and never used.
         UnusedHandle {
             description("Asset was loaded but no handle to it was saved.")
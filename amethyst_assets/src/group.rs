@@ -0,0 +1,210 @@
+//! Named groups of assets, loaded and unloaded together.
+
+use std::{any::Any, collections::HashMap, path::Path};
+
+use amethyst_core::specs::prelude::Resources;
+
+use crate::{
+    error::{Error, ErrorKind, Result, ResultExt},
+    Asset, AssetStorage, Format, Handle, Loader, ProgressCounter,
+};
+
+type LoadFn =
+    Box<dyn Fn(&str, &Resources, &mut ProgressCounter) -> Box<dyn Any + Send + Sync> + Send + Sync>;
+
+/// Lists the assets of every group, e.g. read from a RON file like:
+///
+/// ```ron
+/// (
+///     groups: {
+///         "menu": ["texture/logo.png", "font/title.ttf"],
+///         "level1": ["mesh/castle.obj", "texture/castle.png"],
+///     },
+/// )
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct AssetManifest {
+    /// Names of the assets of each group, as given to the `Loader`.
+    pub groups: HashMap<String, Vec<String>>,
+}
+
+impl AssetManifest {
+    /// Reads a manifest from the bytes of a RON file.
+    pub fn from_ron(bytes: &[u8]) -> Result<Self> {
+        use ron::de::Deserializer;
+        use serde::Deserialize;
+
+        let mut d =
+            Deserializer::from_bytes(bytes).chain_err(|| "Failed parsing asset manifest")?;
+        let manifest = Self::deserialize(&mut d).chain_err(|| "Failed parsing asset manifest")?;
+        d.end().chain_err(|| "Failed parsing asset manifest")?;
+        Ok(manifest)
+    }
+}
+
+struct LoadedGroup {
+    handles: HashMap<String, Box<dyn Any + Send + Sync>>,
+    progress: ProgressCounter,
+}
+
+/// Resource loading the groups of an `AssetManifest`, e.g. everything a level needs while its
+/// loading screen is shown, and unloading them once they're no longer needed.
+///
+/// Which asset type and format to load an asset with is found from the extension of its name,
+/// registered with `with_format`. A loaded group holds a handle to each of its assets, so they
+/// stay loaded until the group is unloaded and every other handle to them is dropped.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let groups = AssetGroups::new(AssetManifest::from_ron(&bytes)?)
+///     .with_format::<Texture, _>("png", PngFormat, TextureMetadata::srgb())
+///     .with_format::<Mesh, _>("obj", ObjFormat, ());
+/// world.add_resource(groups);
+///
+/// // In `on_start` of the loading screen:
+/// world.write_resource::<AssetGroups>().preload("level1", &world.res)?;
+///
+/// // Every frame:
+/// let done = world.read_resource::<AssetGroups>().progress("level1").map_or(false, |p| p.is_complete());
+/// ```
+pub struct AssetGroups {
+    manifest: AssetManifest,
+    formats: HashMap<String, LoadFn>,
+    loaded: HashMap<String, LoadedGroup>,
+}
+
+impl AssetGroups {
+    /// Creates the groups of the manifest, none of them loaded.
+    pub fn new(manifest: AssetManifest) -> Self {
+        AssetGroups {
+            manifest,
+            formats: HashMap::new(),
+            loaded: HashMap::new(),
+        }
+    }
+
+    /// Loads the assets whose names end with `.extension` as `A`, with the given format.
+    ///
+    /// The `AssetStorage<A>` has to be in the world when a group is preloaded.
+    pub fn with_format<A, F>(mut self, extension: &str, format: F, options: F::Options) -> Self
+    where
+        A: Asset,
+        F: Format<A> + Clone + Sync,
+        F::Options: Clone + Sync,
+    {
+        let load: LoadFn = Box::new(move |name, res, progress| {
+            let handle: Handle<A> = res.fetch::<Loader>().load(
+                name,
+                format.clone(),
+                options.clone(),
+                progress,
+                &res.fetch::<AssetStorage<A>>(),
+            );
+            Box::new(handle)
+        });
+        self.formats.insert(extension.to_lowercase(), load);
+        self
+    }
+
+    /// Returns the manifest.
+    pub fn manifest(&self) -> &AssetManifest {
+        &self.manifest
+    }
+
+    /// Starts loading the assets of a group, if it isn't loaded already.
+    ///
+    /// Fails without loading anything if the group isn't in the manifest, or if one of its
+    /// assets has an extension without a format.
+    pub fn preload(&mut self, group: &str, res: &Resources) -> Result<()> {
+        if self.loaded.contains_key(group) {
+            return Ok(());
+        }
+        let names = self
+            .manifest
+            .groups
+            .get(group)
+            .ok_or_else(|| Error::from(ErrorKind::UnknownGroup(group.to_owned())))?;
+        let loads = names
+            .iter()
+            .map(|name| {
+                let extension = Path::new(name)
+                    .extension()
+                    .and_then(|extension| extension.to_str())
+                    .unwrap_or("")
+                    .to_lowercase();
+                self.formats.get(&extension).ok_or_else(|| {
+                    Error::from(format!(
+                        "No format for the extension of {:?}, in group {:?}",
+                        name, group
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut progress = ProgressCounter::new();
+        let handles = names
+            .iter()
+            .zip(loads)
+            .map(|(name, load)| (name.clone(), load(name.as_str(), res, &mut progress)))
+            .collect();
+        self.loaded
+            .insert(group.to_owned(), LoadedGroup { handles, progress });
+        Ok(())
+    }
+
+    /// Drops the handles of a group, unloading the assets no other handle points to.
+    pub fn unload(&mut self, group: &str) {
+        self.loaded.remove(group);
+    }
+
+    /// Checks whether a group was preloaded and not unloaded since.
+    pub fn is_loaded(&self, group: &str) -> bool {
+        self.loaded.contains_key(group)
+    }
+
+    /// Returns the combined progress of the assets of a group, if it was preloaded.
+    pub fn progress(&self, group: &str) -> Option<&ProgressCounter> {
+        self.loaded.get(group).map(|loaded| &loaded.progress)
+    }
+
+    /// Returns the handle of an asset of a preloaded group, if it was loaded as an `A`.
+    pub fn handle<A>(&self, group: &str, name: &str) -> Option<Handle<A>>
+    where
+        A: Asset,
+    {
+        self.loaded
+            .get(group)?
+            .handles
+            .get(name)?
+            .downcast_ref::<Handle<A>>()
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_manifest() {
+        let manifest = AssetManifest::from_ron(
+            br#"(groups: { "level1": ["mesh/castle.obj", "texture/castle.png"] })"#,
+        )
+        .unwrap();
+        assert_eq!(
+            Some(&vec![
+                "mesh/castle.obj".to_owned(),
+                "texture/castle.png".to_owned()
+            ]),
+            manifest.groups.get("level1")
+        );
+    }
+
+    #[test]
+    fn unknown_group() {
+        let mut groups = AssetGroups::new(AssetManifest::default());
+        assert!(groups.preload("level1", &Resources::new()).is_err());
+        assert!(!groups.is_loaded("level1"));
+    }
+}
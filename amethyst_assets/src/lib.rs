@@ -31,15 +31,23 @@ extern crate thread_profiler;
 
 #[cfg(feature = "json")]
 pub use crate::formats::JsonFormat;
+#[cfg(target_os = "android")]
+pub use crate::source::ApkSource;
+#[cfg(feature = "web_source")]
+pub use crate::source::WebSource;
 pub use crate::{
     asset::{Asset, Format, FormatValue, SimpleFormat},
     cache::Cache,
     error::{Error, ErrorKind, Result, ResultExt},
     formats::RonFormat,
+    group::{AssetGroups, AssetManifest},
     helper::AssetLoaderSystemData,
     loader::Loader,
-    prefab::{AssetPrefab, Prefab, PrefabData, PrefabError, PrefabLoader, PrefabLoaderSystem},
-    progress::{Completion, Progress, ProgressCounter, Tracker},
+    memory::AssetMemorySystem,
+    prefab::{
+        AssetPrefab, Prefab, PrefabData, PrefabError, PrefabLoader, PrefabLoaderSystem, PrefabTag,
+    },
+    progress::{AssetLoadFailure, Completion, Progress, ProgressCounter, Tracker},
     reload::{HotReloadBundle, HotReloadStrategy, HotReloadSystem, Reload, SingleFile},
     source::{Directory, Source},
     storage::{AssetStorage, Handle, ProcessingState, Processor, WeakHandle},
@@ -49,8 +57,10 @@ mod asset;
 mod cache;
 mod error;
 mod formats;
+mod group;
 mod helper;
 mod loader;
+mod memory;
 mod prefab;
 mod progress;
 mod reload;
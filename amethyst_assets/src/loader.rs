@@ -1,9 +1,11 @@
 use std::{borrow::Borrow, hash::Hash, path::PathBuf, sync::Arc};
 
 use fnv::FnvHashMap;
+use parking_lot::Mutex;
 use rayon::ThreadPool;
 
 use crate::{
+    progress::{AssetLoadFailure, ReportingTracker, Tracker},
     storage::{AssetStorage, Handle, Processed},
     Asset, Directory, ErrorKind, Format, FormatValue, Progress, ResultExt, Source,
 };
@@ -13,6 +15,7 @@ pub struct Loader {
     hot_reload: bool,
     pool: Arc<ThreadPool>,
     sources: FnvHashMap<String, Arc<dyn Source>>,
+    failures: Option<Arc<Mutex<Vec<AssetLoadFailure>>>>,
 }
 
 impl Loader {
@@ -34,6 +37,7 @@ impl Loader {
             hot_reload: true,
             pool,
             sources: Default::default(),
+            failures: None,
         };
 
         loader.set_default_source(source);
@@ -67,6 +71,28 @@ impl Loader {
         self.hot_reload = value;
     }
 
+    /// If set to `true`, this `Loader` records every asset that fails to load from now on.
+    /// The failures are collected with `take_load_failures`.
+    ///
+    /// Failures are still reported to the `Progress` the asset was loaded with.
+    pub fn set_failure_reporting(&mut self, value: bool) {
+        self.failures = if value {
+            Some(self.failures.take().unwrap_or_default())
+        } else {
+            None
+        };
+    }
+
+    /// Removes and returns the recorded asset load failures, oldest first.
+    ///
+    /// Always returns an empty list unless failure reporting is enabled.
+    pub fn take_load_failures(&self) -> Vec<AssetLoadFailure> {
+        self.failures
+            .as_ref()
+            .map(|failures| failures.lock().drain(..).collect())
+            .unwrap_or_default()
+    }
+
     /// Loads an asset with a given format from the default (directory) source.
     /// If you want to load from a custom source instead, use `load_from`.
     ///
@@ -124,7 +150,6 @@ impl Loader {
     {
         #[cfg(feature = "profiler")]
         profile_scope!("load_asset_from");
-        use crate::progress::Tracker;
 
         let name = name.into();
         let source = source.as_ref();
@@ -147,7 +172,7 @@ impl Loader {
         );
 
         progress.add_assets(1);
-        let tracker = progress.create_tracker();
+        let tracker = self.wrap_tracker(Box::new(progress.create_tracker()));
 
         let source = self.source(source);
         let handle_clone = handle.clone();
@@ -161,7 +186,6 @@ impl Loader {
             let data = format
                 .import(name.clone(), source, options, hot_reload)
                 .chain_err(|| ErrorKind::Format(F::NAME));
-            let tracker = Box::new(tracker) as Box<dyn Tracker>;
 
             processed.push(Processed::NewAsset {
                 data,
@@ -187,8 +211,7 @@ impl Loader {
         P: Progress,
     {
         progress.add_assets(1);
-        let tracker = progress.create_tracker();
-        let tracker = Box::new(tracker);
+        let tracker = self.wrap_tracker(Box::new(progress.create_tracker()));
         let handle = storage.allocate();
         storage.processed.push(Processed::NewAsset {
             data: Ok(FormatValue::data(data)),
@@ -200,6 +223,16 @@ impl Loader {
         handle
     }
 
+    fn wrap_tracker(&self, tracker: Box<dyn Tracker>) -> Box<dyn Tracker> {
+        match self.failures {
+            Some(ref failures) => Box::new(ReportingTracker {
+                inner: tracker,
+                failures: failures.clone(),
+            }),
+            None => tracker,
+        }
+    }
+
     fn source(&self, source: &str) -> Arc<dyn Source> {
         self.sources
             .get(source)
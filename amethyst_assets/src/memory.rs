@@ -0,0 +1,42 @@
+use std::marker::PhantomData;
+
+use amethyst_core::{
+    specs::prelude::{Read, System, Write},
+    MemoryCategory, MemorySize, MemoryStats, MemoryUsage,
+};
+
+use crate::{asset::Asset, storage::AssetStorage};
+
+/// Records the memory used by the loaded assets of type `A` in the `MemoryStats`, under the
+/// `NAME` of the asset.
+#[derive(Debug)]
+pub struct AssetMemorySystem<A> {
+    category: MemoryCategory,
+    marker: PhantomData<A>,
+}
+
+impl<A> AssetMemorySystem<A> {
+    /// Creates a new system recording the assets in the category.
+    pub fn new(category: MemoryCategory) -> Self {
+        AssetMemorySystem {
+            category,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, A> System<'a> for AssetMemorySystem<A>
+where
+    A: Asset + MemorySize,
+{
+    type SystemData = (Read<'a, AssetStorage<A>>, Write<'a, MemoryStats>);
+
+    fn run(&mut self, (storage, mut stats): Self::SystemData) {
+        let (count, usage) = storage
+            .iter()
+            .fold((0, MemoryUsage::default()), |(count, usage), asset| {
+                (count + 1, usage + asset.memory_usage())
+            });
+        stats.record(self.category, A::NAME, count, usage);
+    }
+}
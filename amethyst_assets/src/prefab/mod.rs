@@ -238,6 +238,12 @@ impl<T> Prefab<T> {
         self.entities[index].data_or_default()
     }
 
+    /// Get the tag placed in a `PrefabTag` on the entities created from the prefab, set when the
+    /// prefab is processed.
+    pub fn tag(&self) -> Option<u64> {
+        self.tag
+    }
+
     /// Check if sub asset loading have been triggered
     pub fn loading(&self) -> bool {
         self.counter.is_some()
@@ -163,6 +163,54 @@ pub struct AssetErrorMeta {
     pub asset_name: String,
 }
 
+/// Description of an asset that failed to load, collected by the `Loader` when failure reporting
+/// is enabled.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AssetLoadFailure {
+    /// Handle id of the asset.
+    pub handle_id: u32,
+    /// Name of the asset type, e.g. `"Mesh"`.
+    pub asset_type_name: &'static str,
+    /// Name the asset was loaded with, most likely a file name.
+    pub asset_name: String,
+    /// The error and its causes.
+    pub error: String,
+}
+
+/// Tracker that records failures before passing them on to the wrapped tracker.
+pub(crate) struct ReportingTracker {
+    pub(crate) inner: Box<dyn Tracker>,
+    pub(crate) failures: Arc<Mutex<Vec<AssetLoadFailure>>>,
+}
+
+impl Tracker for ReportingTracker {
+    fn success(self: Box<Self>) {
+        self.inner.success();
+    }
+
+    fn fail(
+        self: Box<Self>,
+        handle_id: u32,
+        asset_type_name: &'static str,
+        asset_name: String,
+        error: Error,
+    ) {
+        let description = error
+            .iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<_>>()
+            .join(": ");
+        self.failures.lock().push(AssetLoadFailure {
+            handle_id,
+            asset_type_name,
+            asset_name: asset_name.clone(),
+            error: description,
+        });
+        self.inner
+            .fail(handle_id, asset_type_name, asset_name, error);
+    }
+}
+
 /// The `Tracker` trait which will be used by the loader to report
 /// back to `Progress`.
 pub trait Tracker: Send + 'static {
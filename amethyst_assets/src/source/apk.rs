@@ -0,0 +1,30 @@
+use crate::{source::Source, ErrorKind, Result, ResultExt};
+
+/// Source loading assets from the `assets` directory packaged in the APK of an Android build.
+///
+/// The packaged files can't change while the application runs, so their modification time is
+/// always 0 and they are never hot reloaded.
+#[derive(Debug, Default)]
+pub struct ApkSource;
+
+impl ApkSource {
+    /// Creates a source loading the assets of the APK.
+    pub fn new() -> Self {
+        ApkSource
+    }
+}
+
+impl Source for ApkSource {
+    fn modified(&self, _path: &str) -> Result<u64> {
+        Ok(0)
+    }
+
+    fn load(&self, path: &str) -> Result<Vec<u8>> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("apk_load_asset");
+
+        android_glue::load_asset(path)
+            .map_err(|e| format!("Failed to load {:?} from the APK: {:?}", path, e))
+            .chain_err(|| ErrorKind::Source)
+    }
+}
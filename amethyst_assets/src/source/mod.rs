@@ -1,8 +1,16 @@
 use crate::Result;
 
+#[cfg(target_os = "android")]
+pub use self::apk::ApkSource;
 pub use self::dir::Directory;
+#[cfg(feature = "web_source")]
+pub use self::web::WebSource;
 
+#[cfg(target_os = "android")]
+mod apk;
 mod dir;
+#[cfg(feature = "web_source")]
+mod web;
 
 /// A trait for asset sources, which provides
 /// methods for loading bytes.
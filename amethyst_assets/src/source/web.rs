@@ -0,0 +1,111 @@
+use crate::{source::Source, ErrorKind, Result, ResultExt};
+
+/// Source fetching assets over HTTP from the page running a web build.
+///
+/// Only asset loading is covered: the windowing, rendering and audio backends have no web
+/// support, so the engine itself doesn't run in a browser yet. On other targets than `wasm32`
+/// every load fails.
+///
+/// Assets are fetched synchronously, since sources return their bytes directly, so only use it
+/// from a worker or during loading screens. Modification times aren't known, so assets loaded
+/// from it are never hot reloaded.
+#[derive(Debug)]
+pub struct WebSource {
+    base_url: String,
+}
+
+impl WebSource {
+    /// Creates a source fetching assets relative to `base_url`, e.g. `"assets"` for the assets
+    /// next to the page.
+    pub fn new<S>(base_url: S) -> Self
+    where
+        S: Into<String>,
+    {
+        WebSource {
+            base_url: base_url.into(),
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!(
+            "{}/{}",
+            self.base_url.trim_end_matches('/'),
+            path.trim_start_matches('/')
+        )
+    }
+}
+
+impl Source for WebSource {
+    fn modified(&self, _path: &str) -> Result<u64> {
+        Ok(0)
+    }
+
+    fn load(&self, path: &str) -> Result<Vec<u8>> {
+        #[cfg(feature = "profiler")]
+        profile_scope!("web_load_asset");
+
+        let url = self.url(path);
+        let (status, text) = fetch(&url)
+            .map_err(|e| format!("Failed to fetch {:?}: {}", url, e))
+            .chain_err(|| ErrorKind::Source)?;
+        if status != 200 {
+            return Err(format!("Failed to fetch {:?}: HTTP status {}", url, status))
+                .chain_err(|| ErrorKind::Source);
+        }
+        Ok(decode_binary(&text.unwrap_or_default()))
+    }
+}
+
+/// Fetches `url`, returning the HTTP status and the response as a binary string.
+#[cfg(target_arch = "wasm32")]
+fn fetch(url: &str) -> ::std::result::Result<(u16, Option<String>), String> {
+    use web_sys::XmlHttpRequest;
+
+    let error = |e| format!("{:?}", e);
+    let request = XmlHttpRequest::new().map_err(error)?;
+    request.open_with_async("GET", url, false).map_err(error)?;
+    // Synchronous requests can't return an `ArrayBuffer`, but this charset keeps every byte in
+    // the low bits of a character.
+    request
+        .override_mime_type("text/plain; charset=x-user-defined")
+        .map_err(error)?;
+    request.send().map_err(error)?;
+    Ok((
+        request.status().map_err(error)?,
+        request.response_text().map_err(error)?,
+    ))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn fetch(_url: &str) -> ::std::result::Result<(u16, Option<String>), String> {
+    Err("assets can only be fetched by web builds".to_string())
+}
+
+/// Returns the bytes of a response fetched with the `x-user-defined` charset.
+fn decode_binary(text: &str) -> Vec<u8> {
+    text.chars().map(|c| (c as u32 & 0xff) as u8).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn url_joins_base_and_path() {
+        assert_eq!("assets/a/b.png", WebSource::new("assets/").url("/a/b.png"));
+        assert_eq!(
+            "https://example.com/assets/a.ron",
+            WebSource::new("https://example.com/assets").url("a.ron")
+        );
+    }
+
+    #[test]
+    fn binary_strings_decode_to_bytes() {
+        // Bytes from 0x80 are mapped to the private use area by `x-user-defined`.
+        let text: String = [0x41, 0xf780, 0xf7ff, 0]
+            .iter()
+            .map(|&c| ::std::char::from_u32(c).unwrap())
+            .collect();
+        assert_eq!(vec![0x41, 0x80, 0xff, 0], decode_binary(&text));
+    }
+}
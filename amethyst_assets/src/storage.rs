@@ -127,6 +127,25 @@ impl<A: Asset> AssetStorage<A> {
         }
     }
 
+    /// Returns all the loaded assets.
+    pub fn iter(&self) -> impl Iterator<Item = &A> {
+        let assets = &self.assets;
+        // Every handle in `handles` is for a loaded asset.
+        self.handles
+            .iter()
+            .map(move |handle| unsafe { assets.get(handle.id()) })
+    }
+
+    /// Returns all the loaded assets mutably, e.g. to rebuild them.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut A> {
+        let assets = &mut self.assets;
+        self.handles.iter().map(move |handle| {
+            // Every handle in `handles` is for a different loaded asset, so the references are
+            // unique.
+            unsafe { &mut *(assets.get_mut(handle.id()) as *mut A) }
+        })
+    }
+
     /// Process finished asset data and maintain the storage.
     pub fn process<F>(
         &mut self,
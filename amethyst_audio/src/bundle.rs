@@ -4,20 +4,22 @@ use std::marker::PhantomData;
 
 use rodio::default_output_device;
 
-use amethyst_assets::Processor;
+use amethyst_assets::{AssetMemorySystem, Processor};
 use amethyst_core::{
     bundle::{Result, SystemBundle},
     specs::prelude::DispatcherBuilder,
+    MemoryCategory,
 };
 
-use crate::{source::*, systems::DjSystem};
+use crate::{caption::CaptionSystem, music_sync::MusicSyncSystem, source::*, systems::DjSystem};
 
 /// Audio bundle
 ///
 /// Will only register the `AudioSink` and the `DjSystem` if an audio output is found.
 /// `DjSystem` will be registered with name "dj_system".
 ///
-/// This will also add the asset processor for `Source`.
+/// This will also add the asset processor for `Source`, the `CaptionSystem` with name
+/// "caption_system" and the `MusicSyncSystem` with name "music_sync_system".
 ///
 /// ## Errors
 ///
@@ -57,6 +59,13 @@ where
 {
     fn build(self, builder: &mut DispatcherBuilder<'a, 'b>) -> Result<()> {
         builder.add(Processor::<Source>::new(), "source_processor", &[]);
+        builder.add(
+            AssetMemorySystem::<Source>::new(MemoryCategory::Audio),
+            "source_memory",
+            &["source_processor"],
+        );
+        builder.add(CaptionSystem, "caption_system", &[]);
+        builder.add(MusicSyncSystem::default(), "music_sync_system", &[]);
         if default_output_device().is_some() {
             builder.add(DjSystem::new(self.picker), "dj_system", self.dep);
         }
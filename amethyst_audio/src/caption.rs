@@ -0,0 +1,152 @@
+//! Subtitles shown while sounds play.
+
+use amethyst_core::{
+    specs::prelude::{Read, System, Write},
+    timing::Time,
+};
+
+/// A subtitle of a sound, like a line of dialogue or a description of a noise.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Caption {
+    /// The text, or the key of the text in the locale of the UI.
+    pub text: String,
+    /// The name of who is speaking, if anyone.
+    pub speaker: Option<String>,
+    /// How long the caption is shown, in seconds.
+    pub duration: f32,
+    /// Captions with a higher priority interrupt the shown one and are shown before the queued
+    /// ones.
+    #[serde(default)]
+    pub priority: i32,
+}
+
+impl Caption {
+    /// Creates a caption showing the text for `duration` seconds.
+    pub fn new<S: Into<String>>(text: S, duration: f32) -> Self {
+        Caption {
+            text: text.into(),
+            speaker: None,
+            duration,
+            priority: 0,
+        }
+    }
+
+    /// Sets the name of the speaker.
+    pub fn with_speaker<S: Into<String>>(mut self, speaker: S) -> Self {
+        self.speaker = Some(speaker.into());
+        self
+    }
+
+    /// Sets the priority of the caption.
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+/// Resource with the caption shown right now and the ones waiting for it.
+///
+/// The `AudioSystem` adds the captions of the sounds played by
+/// `AudioEmitter::play_with_caption` when they start, sounds played otherwise can add theirs
+/// with `show`. The `CaptionSystem`
+/// moves on to the next caption when the shown one is over, and the `UiCaptionSystem` of
+/// `amethyst_ui` shows them.
+#[derive(Clone, Debug, Default)]
+pub struct Captions {
+    current: Option<Caption>,
+    elapsed: f32,
+    queue: Vec<Caption>,
+    /// The most captions waiting to be shown, the last ones in the queue are dropped beyond it.
+    /// `None` keeps them all.
+    pub max_queued: Option<usize>,
+}
+
+impl Captions {
+    /// Shows the caption, right away if nothing is shown or it has a higher priority than the
+    /// shown caption, otherwise after the queued captions with at least the same priority.
+    pub fn show(&mut self, caption: Caption) {
+        match self.current {
+            Some(ref current) if current.priority >= caption.priority => {
+                let index = self
+                    .queue
+                    .iter()
+                    .position(|queued| queued.priority < caption.priority)
+                    .unwrap_or_else(|| self.queue.len());
+                self.queue.insert(index, caption);
+                if let Some(max) = self.max_queued {
+                    self.queue.truncate(max);
+                }
+            }
+            _ => {
+                self.current = Some(caption);
+                self.elapsed = 0.0;
+            }
+        }
+    }
+
+    /// Returns the caption shown right now.
+    pub fn current(&self) -> Option<&Caption> {
+        self.current.as_ref()
+    }
+
+    /// Returns the captions waiting to be shown, in order.
+    pub fn queued(&self) -> &[Caption] {
+        &self.queue
+    }
+
+    /// Hides the shown caption and drops the queued ones.
+    pub fn clear(&mut self) {
+        self.current = None;
+        self.queue.clear();
+    }
+
+    /// Advances the shown caption by `delta` seconds, moving on to the next one when it's over.
+    pub fn advance(&mut self, delta: f32) {
+        self.elapsed += delta;
+        while self
+            .current
+            .as_ref()
+            .map_or(false, |current| self.elapsed >= current.duration)
+        {
+            self.elapsed -= self.current.take().map_or(0.0, |caption| caption.duration);
+            if self.queue.is_empty() {
+                self.elapsed = 0.0;
+            } else {
+                self.current = Some(self.queue.remove(0));
+            }
+        }
+    }
+}
+
+/// Advances the `Captions` with the time of the game.
+#[derive(Default)]
+pub struct CaptionSystem;
+
+impl<'a> System<'a> for CaptionSystem {
+    type SystemData = (Read<'a, Time>, Write<'a, Captions>);
+
+    fn run(&mut self, (time, mut captions): Self::SystemData) {
+        captions.advance(time.delta_seconds());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn captions_follow_priorities() {
+        let mut captions = Captions::default();
+        captions.show(Caption::new("first", 1.0));
+        captions.show(Caption::new("second", 1.0));
+        captions.show(Caption::new("urgent", 1.0).with_priority(1));
+        assert_eq!("urgent", captions.current().unwrap().text);
+        captions.show(Caption::new("important", 1.0).with_priority(1));
+        captions.advance(1.5);
+        assert_eq!("important", captions.current().unwrap().text);
+        captions.advance(1.0);
+        assert_eq!("second", captions.current().unwrap().text);
+        captions.advance(1.0);
+        assert_eq!(None, captions.current());
+    }
+}
@@ -3,18 +3,26 @@ use std::{
     sync::{atomic::AtomicBool, Arc},
 };
 
-use rodio::{Decoder, SpatialSink};
+use rodio::{Decoder, Source as RSource, SpatialSink};
 use smallvec::SmallVec;
 
 use amethyst_core::specs::{prelude::Component, storage::BTreeStorage};
 
-use crate::{source::Source, DecoderError};
+use crate::{
+    caption::Caption,
+    source::Source,
+    synth::{Synth, SynthSource},
+    DecoderError,
+};
+
+/// A sound waiting in the queue of an `AudioEmitter`, decoded from a `Source` or synthesized.
+pub(crate) type QueuedSound = Box<dyn RSource<Item = i16> + Send + Sync>;
 
 /// An audio source, add this component to anything that emits sound.
 #[derive(Default)]
 pub struct AudioEmitter {
     pub(crate) sinks: SmallVec<[(SpatialSink, Arc<AtomicBool>); 4]>,
-    pub(crate) sound_queue: SmallVec<[Decoder<Cursor<Source>>; 4]>,
+    pub(crate) sound_queue: SmallVec<[(QueuedSound, Option<Caption>); 4]>,
     pub(crate) picker: Option<Box<dyn FnMut(&mut AudioEmitter) -> bool + Send + Sync>>,
 }
 
@@ -28,11 +36,33 @@ impl AudioEmitter {
 
     /// Plays an audio source from this emitter.
     pub fn play(&mut self, source: &Source) -> Result<(), DecoderError> {
-        self.sound_queue
-            .push(Decoder::new(Cursor::new(source.clone())).map_err(|_| DecoderError)?);
+        self.sound_queue.push((
+            Box::new(Decoder::new(Cursor::new(source.clone())).map_err(|_| DecoderError)?),
+            None,
+        ));
         Ok(())
     }
 
+    /// Plays an audio source from this emitter, showing the caption in the `Captions` when it
+    /// starts playing.
+    pub fn play_with_caption(
+        &mut self,
+        source: &Source,
+        caption: Caption,
+    ) -> Result<(), DecoderError> {
+        self.sound_queue.push((
+            Box::new(Decoder::new(Cursor::new(source.clone())).map_err(|_| DecoderError)?),
+            Some(caption),
+        ));
+        Ok(())
+    }
+
+    /// Plays a synthesized sound from this emitter.
+    pub fn play_synth<S: Synth>(&mut self, synth: S) {
+        self.sound_queue
+            .push((Box::new(SynthSource::new(synth)), None));
+    }
+
     /// An emitter's picker will be called by the AudioSystem whenever the emitter runs out of
     /// sounds to play.
     ///
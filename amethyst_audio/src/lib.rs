@@ -1,6 +1,6 @@
 #![warn(missing_docs, rust_2018_idioms, rust_2018_compatibility)]
 
-//! Loading and playing of audio files.
+//! Loading and playing of audio files, and of synthesized sounds.
 #[macro_use]
 extern crate log;
 
@@ -13,10 +13,13 @@ extern crate thread_profiler;
 
 pub use self::{
     bundle::AudioBundle,
+    caption::{Caption, CaptionSystem, Captions},
     components::*,
     formats::{AudioFormat, FlacFormat, Mp3Format, OggFormat, WavFormat},
+    music_sync::{BeatEvent, MusicClock, MusicSync, MusicSyncSystem},
     sink::AudioSink,
     source::{Source, SourceHandle},
+    synth::{Envelope, Fm, Noise, Oscillator, Synth, SynthParam, Waveform, SYNTH_SAMPLE_RATE},
     systems::*,
 };
 
@@ -28,11 +31,14 @@ use std::{
 };
 
 mod bundle;
+mod caption;
 mod components;
 mod end_signal;
 mod formats;
+mod music_sync;
 mod sink;
 mod source;
+mod synth;
 mod systems;
 
 /// An error occurred while decoding the source.
@@ -0,0 +1,389 @@
+//! Gameplay synchronized with the beats of the music.
+
+use std::{
+    cmp,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use rodio::{Sample, Source as RSource};
+
+use amethyst_core::{
+    shrev::EventChannel,
+    specs::prelude::{Read, System, Write},
+    timing::Time,
+};
+
+/// How far, in seconds, the position of the music is extrapolated between two reports of the
+/// audio thread, which plays the samples by chunks.
+const MAX_DRIFT: f64 = 0.1;
+
+#[derive(Debug, Default)]
+struct ClockState {
+    samples: AtomicUsize,
+    samples_per_second: AtomicUsize,
+    tracks: AtomicUsize,
+}
+
+/// Counts the samples of the music played by the audio thread.
+///
+/// The clock of the `MusicSync` is given to `AudioSink::append_with_clock`, it restarts when the
+/// track starts playing.
+#[derive(Clone, Debug, Default)]
+pub struct MusicClock(Arc<ClockState>);
+
+impl MusicClock {
+    /// Returns the seconds of the track played so far.
+    pub fn seconds(&self) -> f64 {
+        match self.0.samples_per_second.load(Ordering::Relaxed) {
+            0 => 0.,
+            rate => self.0.samples.load(Ordering::Relaxed) as f64 / rate as f64,
+        }
+    }
+
+    /// Returns how many tracks started playing with this clock.
+    pub fn tracks(&self) -> usize {
+        self.0.tracks.load(Ordering::Relaxed)
+    }
+}
+
+/// Wraps a source and counts its samples on a `MusicClock`.
+pub(crate) struct ClockedSource<S> {
+    input: S,
+    clock: MusicClock,
+    started: bool,
+}
+
+impl<S> ClockedSource<S> {
+    pub(crate) fn new(input: S, clock: MusicClock) -> Self {
+        ClockedSource {
+            input,
+            clock,
+            started: false,
+        }
+    }
+}
+
+impl<S: RSource> Iterator for ClockedSource<S>
+where
+    S::Item: Sample,
+{
+    type Item = S::Item;
+
+    fn next(&mut self) -> Option<S::Item> {
+        let state = &self.clock.0;
+        if !self.started {
+            self.started = true;
+            let rate = self.input.sample_rate() as usize * self.input.channels() as usize;
+            state.samples.store(0, Ordering::Relaxed);
+            state.samples_per_second.store(rate, Ordering::Relaxed);
+            state.tracks.fetch_add(1, Ordering::Relaxed);
+        }
+        let next = self.input.next();
+        if next.is_some() {
+            state.samples.fetch_add(1, Ordering::Relaxed);
+        }
+        next
+    }
+}
+
+impl<S: RSource> RSource for ClockedSource<S>
+where
+    S::Item: Sample,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+/// Event sent by the `MusicSyncSystem` on an `EventChannel<BeatEvent>` ahead of a step of the
+/// music being heard.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BeatEvent {
+    /// The subdivision of the beat the step belongs to, 1 for beats, 2 for half beats and so on.
+    pub subdivision: u32,
+    /// The index of the step in the track, counted in subdivisions of beats.
+    pub step: u64,
+    /// The index of the beat in the track.
+    pub beat: u64,
+    /// The index of the bar in the track.
+    pub bar: u64,
+    /// The index of the beat in its bar.
+    pub beat_in_bar: u32,
+    /// The seconds until the step is heard, negative if the event is late.
+    pub delay: f32,
+}
+
+impl BeatEvent {
+    /// Returns true if the step is the first one of its beat.
+    pub fn is_on_beat(&self) -> bool {
+        self.step % u64::from(self.subdivision.max(1)) == 0
+    }
+}
+
+/// Resource following the beats of the music, for rhythm games and effects in time with it.
+///
+/// Play the music with `AudioSink::append_with_clock` and the `clock` of this resource, the
+/// `MusicSyncSystem` then follows the position of the track heard by the player, and sends
+/// `BeatEvent`s `look_ahead` seconds before each step of the `subdivisions`, so the game can
+/// start animations ending on the beat. Every subdivision sends its own events, so with
+/// subdivisions 1 and 2 the beats get two events.
+///
+/// The audio is heard a bit after the audio thread plays it, depending on the output. Games
+/// usually let the player calibrate `latency`, e.g. by tapping along with a metronome.
+#[derive(Clone, Debug)]
+pub struct MusicSync {
+    /// The tempo of the track, in beats per minute.
+    pub bpm: f32,
+    /// The number of beats of a bar.
+    pub beats_per_bar: u32,
+    /// The seconds into the track of the first beat.
+    pub offset: f32,
+    /// The seconds between the audio thread playing the samples and the player hearing them.
+    pub latency: f32,
+    /// The seconds the `BeatEvent`s are sent before the steps are heard.
+    pub look_ahead: f32,
+    /// The subdivisions of the beats sending `BeatEvent`s.
+    pub subdivisions: Vec<u32>,
+    clock: MusicClock,
+    track: usize,
+    position: f64,
+    scheduled: Option<f64>,
+}
+
+impl Default for MusicSync {
+    fn default() -> Self {
+        MusicSync {
+            bpm: 120.,
+            beats_per_bar: 4,
+            offset: 0.,
+            latency: 0.,
+            look_ahead: 0.,
+            subdivisions: vec![1],
+            clock: MusicClock::default(),
+            track: 0,
+            position: 0.,
+            scheduled: None,
+        }
+    }
+}
+
+impl MusicSync {
+    /// Creates the resource for a track of the tempo, in beats per minute, with 4 beats per bar.
+    pub fn new(bpm: f32) -> Self {
+        MusicSync {
+            bpm,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the number of beats of a bar.
+    pub fn with_beats_per_bar(mut self, beats_per_bar: u32) -> Self {
+        self.beats_per_bar = beats_per_bar;
+        self
+    }
+
+    /// Sets the seconds into the track of the first beat.
+    pub fn with_offset(mut self, offset: f32) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Sets the latency of the audio output, in seconds.
+    pub fn with_latency(mut self, latency: f32) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Sets the seconds the `BeatEvent`s are sent before the steps are heard.
+    pub fn with_look_ahead(mut self, look_ahead: f32) -> Self {
+        self.look_ahead = look_ahead;
+        self
+    }
+
+    /// Sets the subdivisions of the beats sending `BeatEvent`s.
+    pub fn with_subdivisions(mut self, subdivisions: Vec<u32>) -> Self {
+        self.subdivisions = subdivisions;
+        self
+    }
+
+    /// Returns the clock to play the track with.
+    pub fn clock(&self) -> &MusicClock {
+        &self.clock
+    }
+
+    /// Returns true once a track started playing with the clock.
+    pub fn has_started(&self) -> bool {
+        self.track > 0
+    }
+
+    /// Returns the seconds of the track heard by the player.
+    pub fn position(&self) -> f64 {
+        self.position - f64::from(self.latency)
+    }
+
+    /// Returns the beats heard since the first one, negative before it.
+    pub fn beats(&self) -> f64 {
+        (self.position() - f64::from(self.offset)) * f64::from(self.bpm) / 60.
+    }
+
+    /// Returns the index of the beat being heard.
+    pub fn beat(&self) -> i64 {
+        self.beats().floor() as i64
+    }
+
+    /// Returns the index of the bar being heard.
+    pub fn bar(&self) -> i64 {
+        (self.beats() / f64::from(self.beats_per_bar.max(1))).floor() as i64
+    }
+
+    /// Returns how far into the beat being heard the music is, from `0.0` to `1.0`, e.g. to
+    /// pulse effects on the beat.
+    pub fn phase(&self) -> f32 {
+        let beats = self.beats();
+        (beats - beats.floor()) as f32
+    }
+
+    /// Follows the track for `delta` seconds of real time, adding the steps coming within the
+    /// look ahead to `events`.
+    pub fn advance(&mut self, delta: f64, events: &mut Vec<BeatEvent>) {
+        let tracks = self.clock.tracks();
+        let reported = self.clock.seconds();
+        if tracks != self.track {
+            self.track = tracks;
+            self.position = reported;
+            self.scheduled = None;
+        } else {
+            self.position = (self.position + delta)
+                .max(reported - MAX_DRIFT)
+                .min(reported + MAX_DRIFT);
+        }
+        if !self.has_started() || self.bpm <= 0. {
+            return;
+        }
+
+        let beats_per_second = f64::from(self.bpm) / 60.;
+        let now = self.beats();
+        let horizon = now + f64::from(self.look_ahead) * beats_per_second;
+        let beats_per_bar = u64::from(self.beats_per_bar.max(1));
+        let start = events.len();
+        for &subdivision in &self.subdivisions {
+            let steps = f64::from(subdivision.max(1));
+            let first = match self.scheduled {
+                Some(scheduled) => (scheduled * steps).floor() + 1.,
+                None => (now * steps).ceil(),
+            };
+            let mut step = first.max(0.);
+            while step <= (horizon * steps).floor() {
+                let beat = (step / steps).floor() as u64;
+                events.push(BeatEvent {
+                    subdivision,
+                    step: step as u64,
+                    beat,
+                    bar: beat / beats_per_bar,
+                    beat_in_bar: (beat % beats_per_bar) as u32,
+                    delay: ((step / steps - now) / beats_per_second) as f32,
+                });
+                step += 1.;
+            }
+        }
+        events[start..].sort_by(|a, b| {
+            a.delay
+                .partial_cmp(&b.delay)
+                .unwrap_or(cmp::Ordering::Equal)
+        });
+        self.scheduled = Some(
+            self.scheduled
+                .map_or(horizon, |scheduled| scheduled.max(horizon)),
+        );
+    }
+}
+
+/// Follows the music of the `MusicSync` and sends `BeatEvent`s.
+#[derive(Default)]
+pub struct MusicSyncSystem {
+    events: Vec<BeatEvent>,
+}
+
+impl<'a> System<'a> for MusicSyncSystem {
+    type SystemData = (
+        Read<'a, Time>,
+        Write<'a, MusicSync>,
+        Write<'a, EventChannel<BeatEvent>>,
+    );
+
+    fn run(&mut self, (time, mut sync, mut channel): Self::SystemData) {
+        sync.advance(f64::from(time.delta_real_seconds()), &mut self.events);
+        channel.iter_write(self.events.drain(..));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rodio::buffer::SamplesBuffer;
+
+    #[test]
+    fn beats_are_sent_ahead_of_being_heard() {
+        let mut sync = MusicSync::new(120.)
+            .with_latency(0.25)
+            .with_look_ahead(0.5)
+            .with_subdivisions(vec![1, 2]);
+        let mut events = Vec::new();
+        sync.advance(0.1, &mut events);
+        assert!(!sync.has_started());
+        assert!(events.is_empty());
+
+        // A mono track of 4 samples per second, played a second at a time.
+        let mut track = ClockedSource::new(
+            SamplesBuffer::new(1, 4, vec![0i16; 16]),
+            sync.clock().clone(),
+        );
+        let mut play = |sync: &mut MusicSync, events: &mut Vec<BeatEvent>| {
+            for _ in 0..4 {
+                track.next();
+            }
+            sync.advance(1., events);
+        };
+        play(&mut sync, &mut events);
+        // The second of the track played is heard up to 0.75 seconds, so the steps from the half
+        // beat being heard to the beat at 1.25 seconds are sent.
+        assert!((sync.position() - 0.75).abs() < 1e-6);
+        assert_eq!(1, sync.beat());
+        let steps = events
+            .iter()
+            .map(|event| (event.subdivision, event.step))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![(2, 3), (1, 2), (2, 4), (2, 5)], steps);
+        assert!((events[1].delay - 0.25).abs() < 1e-6);
+        assert!(events[1].is_on_beat() && !events[3].is_on_beat());
+
+        events.clear();
+        play(&mut sync, &mut events);
+        let steps = events
+            .iter()
+            .map(|event| (event.subdivision, event.step))
+            .collect::<Vec<_>>();
+        // Advancing by more than the look ahead sends the missed steps late.
+        assert_eq!(vec![(1, 3), (2, 6), (2, 7), (1, 4), (2, 8), (2, 9)], steps);
+        assert!(events[0].delay < 0.);
+        assert_eq!(1, events[3].bar);
+        assert_eq!(0, events[3].beat_in_bar);
+    }
+}
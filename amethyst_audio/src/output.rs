@@ -11,7 +11,12 @@ use rodio::{default_output_device, output_devices, Decoder, Device, Sink, Source
 
 use amethyst_core::shred::Resources;
 
-use crate::{sink::AudioSink, source::Source, DecoderError};
+use crate::{
+    sink::AudioSink,
+    source::Source,
+    synth::{Synth, SynthSource},
+    DecoderError,
+};
 
 /// A speaker(s) through which audio can be played.
 ///
@@ -70,6 +75,14 @@ impl Output {
         sink.detach();
         Ok(())
     }
+
+    /// Plays a synthesized sound until it ends. A volume of 1.0 is unchanged, while 0.0 is
+    /// silent.
+    pub fn play_synth<S: Synth>(&self, synth: S, volume: f32) {
+        let sink = Sink::new(&self.device);
+        sink.append(SynthSource::new(synth).amplify(volume));
+        sink.detach();
+    }
 }
 
 impl Debug for Output {
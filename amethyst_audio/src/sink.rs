@@ -2,7 +2,13 @@ use std::io::Cursor;
 
 use rodio::{Decoder, Sink};
 
-use crate::{output::Output, source::Source, DecoderError};
+use crate::{
+    music_sync::{ClockedSource, MusicClock},
+    output::Output,
+    source::Source,
+    synth::{Synth, SynthSource},
+    DecoderError,
+};
 
 /// This structure provides a way to programmatically pick and play music.
 pub struct AudioSink {
@@ -24,6 +30,23 @@ impl AudioSink {
         Ok(())
     }
 
+    /// Adds a source to the sink's queue of music to play, counting its samples on the clock,
+    /// usually the one of the `MusicSync`.
+    pub fn append_with_clock(
+        &self,
+        source: &Source,
+        clock: &MusicClock,
+    ) -> Result<(), DecoderError> {
+        let decoder = Decoder::new(Cursor::new(source.clone())).map_err(|_| DecoderError)?;
+        self.sink.append(ClockedSource::new(decoder, clock.clone()));
+        Ok(())
+    }
+
+    /// Adds a synthesized sound to the sink's queue of music to play.
+    pub fn append_synth<S: Synth>(&self, synth: S) {
+        self.sink.append(SynthSource::new(synth));
+    }
+
     /// Returns true if the sink has no more music to play.
     pub fn empty(&self) -> bool {
         self.sink.empty()
@@ -5,7 +5,10 @@ use std::result::Result as StdResult;
 use amethyst_assets::{
     Asset, AssetStorage, Handle, Loader, PrefabData, PrefabError, ProcessingState, Result,
 };
-use amethyst_core::specs::prelude::{Entity, Read, ReadExpect, VecStorage};
+use amethyst_core::{
+    specs::prelude::{Entity, Read, ReadExpect, VecStorage},
+    MemorySize, MemoryUsage,
+};
 
 use crate::formats::AudioData;
 
@@ -31,6 +34,12 @@ impl Asset for Source {
     type HandleStorage = VecStorage<SourceHandle>;
 }
 
+impl MemorySize for Source {
+    fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage::cpu(self.bytes.len())
+    }
+}
+
 impl Into<Result<ProcessingState<Source>>> for AudioData {
     fn into(self) -> Result<ProcessingState<Source>> {
         Ok(ProcessingState::Loaded(Source { bytes: self.0 }))
@@ -0,0 +1,366 @@
+//! Sounds generated by the game while they play.
+
+use std::{
+    f32::consts::PI,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use rodio::Source as RSource;
+
+/// The sample rate synths are played at.
+pub const SYNTH_SAMPLE_RATE: u32 = 44_100;
+
+/// Generates the samples of a sound on demand, instead of decoding a sound file, e.g. for the
+/// engine of a car following its speed, retro sound effects or tones.
+///
+/// Synths are played like `Source`s, by an `AudioEmitter`, an `AudioSink` or the `Output`. They
+/// are mono and run on the audio thread, so the game changes a playing synth through the
+/// `SynthParam`s it shares with it.
+///
+/// Closures taking the sample rate are synths too:
+///
+/// ```rust
+/// # use amethyst_audio::Synth;
+/// let mut time = 0.;
+/// // A tone falling from 880 Hz for half a second, like a laser.
+/// let laser = move |rate: u32| {
+///     time += 1. / rate as f32;
+///     let frequency = 880. * (1. - time);
+///     if time < 0.5 {
+///         Some((time * frequency * std::f32::consts::PI * 2.).sin())
+///     } else {
+///         None
+///     }
+/// };
+/// # fn synth<S: Synth>(_: S) {}
+/// # synth(laser);
+/// ```
+pub trait Synth: Send + Sync + 'static {
+    /// Returns the next sample, between `-1.0` and `1.0`, or `None` when the sound ended.
+    fn next_sample(&mut self, sample_rate: u32) -> Option<f32>;
+}
+
+impl<F> Synth for F
+where
+    F: FnMut(u32) -> Option<f32> + Send + Sync + 'static,
+{
+    fn next_sample(&mut self, sample_rate: u32) -> Option<f32> {
+        self(sample_rate)
+    }
+}
+
+/// A value shared between the game and a playing synth.
+#[derive(Clone, Debug)]
+pub struct SynthParam(Arc<AtomicUsize>);
+
+impl SynthParam {
+    /// Creates a parameter with the value.
+    pub fn new(value: f32) -> Self {
+        SynthParam(Arc::new(AtomicUsize::new(value.to_bits() as usize)))
+    }
+
+    /// Returns the value.
+    pub fn get(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed) as u32)
+    }
+
+    /// Sets the value, used by the synth from its next sample.
+    pub fn set(&self, value: f32) {
+        self.0.store(value.to_bits() as usize, Ordering::Relaxed);
+    }
+}
+
+/// The shape of the wave of an `Oscillator`.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Waveform {
+    /// A smooth sine wave.
+    Sine,
+    /// A square wave, like the sounds of old consoles.
+    Square,
+    /// A sawtooth wave, rising and falling at once.
+    Saw,
+    /// A triangle wave.
+    Triangle,
+}
+
+impl Waveform {
+    /// Returns the value of the wave at the phase, from `0.0` to `1.0`.
+    pub fn at(self, phase: f32) -> f32 {
+        match self {
+            Waveform::Sine => (phase * 2. * PI).sin(),
+            Waveform::Square => {
+                if phase < 0.5 {
+                    1.
+                } else {
+                    -1.
+                }
+            }
+            Waveform::Saw => 2. * phase - 1.,
+            Waveform::Triangle => 1. - 4. * (phase - 0.5).abs(),
+        }
+    }
+}
+
+/// A synth playing a wave forever.
+#[derive(Clone, Debug)]
+pub struct Oscillator {
+    /// The shape of the wave.
+    pub waveform: Waveform,
+    /// The frequency, in hertz.
+    pub frequency: SynthParam,
+    /// The amplitude, from `0.0` to `1.0`.
+    pub amplitude: SynthParam,
+    phase: f32,
+}
+
+impl Oscillator {
+    /// Creates an oscillator playing the wave at the frequency, at full amplitude.
+    pub fn new(waveform: Waveform, frequency: f32) -> Self {
+        Oscillator {
+            waveform,
+            frequency: SynthParam::new(frequency),
+            amplitude: SynthParam::new(1.),
+            phase: 0.,
+        }
+    }
+
+    /// Sets the amplitude.
+    pub fn with_amplitude(self, amplitude: f32) -> Self {
+        self.amplitude.set(amplitude);
+        self
+    }
+}
+
+impl Synth for Oscillator {
+    fn next_sample(&mut self, sample_rate: u32) -> Option<f32> {
+        let sample = self.waveform.at(self.phase) * self.amplitude.get();
+        self.phase = advance(self.phase, self.frequency.get(), sample_rate);
+        Some(sample)
+    }
+}
+
+/// A synth playing white noise forever, e.g. for explosions and wind.
+#[derive(Clone, Debug)]
+pub struct Noise {
+    /// The amplitude, from `0.0` to `1.0`.
+    pub amplitude: SynthParam,
+    state: u32,
+}
+
+impl Noise {
+    /// Creates noise at full amplitude, the seed picking the sequence of samples.
+    pub fn new(seed: u32) -> Self {
+        Noise {
+            amplitude: SynthParam::new(1.),
+            state: seed.max(1),
+        }
+    }
+}
+
+impl Synth for Noise {
+    fn next_sample(&mut self, _: u32) -> Option<f32> {
+        // Xorshift, fast enough for the audio thread.
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 17;
+        self.state ^= self.state << 5;
+        let sample = self.state as f32 / u32::max_value() as f32 * 2. - 1.;
+        Some(sample * self.amplitude.get())
+    }
+}
+
+/// A synth playing a frequency modulated sine forever, a carrier whose phase is pushed back and
+/// forth by a modulator, for bells, metallic sounds and engines.
+#[derive(Clone, Debug)]
+pub struct Fm {
+    /// The frequency of the carrier, in hertz.
+    pub carrier: SynthParam,
+    /// The frequency of the modulator, in hertz.
+    pub modulator: SynthParam,
+    /// How far the modulator pushes the phase of the carrier, the higher the brighter.
+    pub index: SynthParam,
+    /// The amplitude, from `0.0` to `1.0`.
+    pub amplitude: SynthParam,
+    carrier_phase: f32,
+    modulator_phase: f32,
+}
+
+impl Fm {
+    /// Creates the synth with the frequencies and modulation index, at full amplitude.
+    pub fn new(carrier: f32, modulator: f32, index: f32) -> Self {
+        Fm {
+            carrier: SynthParam::new(carrier),
+            modulator: SynthParam::new(modulator),
+            index: SynthParam::new(index),
+            amplitude: SynthParam::new(1.),
+            carrier_phase: 0.,
+            modulator_phase: 0.,
+        }
+    }
+}
+
+impl Synth for Fm {
+    fn next_sample(&mut self, sample_rate: u32) -> Option<f32> {
+        let modulation = (self.modulator_phase * 2. * PI).sin() * self.index.get();
+        let sample = (self.carrier_phase * 2. * PI + modulation).sin() * self.amplitude.get();
+        self.carrier_phase = advance(self.carrier_phase, self.carrier.get(), sample_rate);
+        self.modulator_phase = advance(self.modulator_phase, self.modulator.get(), sample_rate);
+        Some(sample)
+    }
+}
+
+/// Shapes the volume of a synth over time and ends it, e.g. to turn an `Oscillator` into a short
+/// sound effect.
+///
+/// The volume rises during the attack, falls to the sustain level during the decay, stays there
+/// until the duration is over, then falls to silence during the release.
+#[derive(Clone, Debug)]
+pub struct Envelope<S> {
+    synth: S,
+    attack: f32,
+    decay: f32,
+    sustain: f32,
+    duration: f32,
+    release: f32,
+    time: f32,
+}
+
+impl<S: Synth> Envelope<S> {
+    /// Plays the synth for the duration in seconds, without attack, decay nor release.
+    pub fn new(synth: S, duration: f32) -> Self {
+        Envelope {
+            synth,
+            attack: 0.,
+            decay: 0.,
+            sustain: 1.,
+            duration,
+            release: 0.,
+            time: 0.,
+        }
+    }
+
+    /// Sets the seconds the volume rises for.
+    pub fn with_attack(mut self, attack: f32) -> Self {
+        self.attack = attack;
+        self
+    }
+
+    /// Sets the seconds the volume falls for after the attack, and the level it falls to.
+    pub fn with_decay(mut self, decay: f32, sustain: f32) -> Self {
+        self.decay = decay;
+        self.sustain = sustain;
+        self
+    }
+
+    /// Sets the seconds the volume falls to silence for after the duration.
+    pub fn with_release(mut self, release: f32) -> Self {
+        self.release = release;
+        self
+    }
+
+    fn volume(&self) -> f32 {
+        let time = self.time;
+        if time < self.attack {
+            time / self.attack
+        } else if time < self.attack + self.decay {
+            1. - (1. - self.sustain) * (time - self.attack) / self.decay
+        } else if time < self.duration {
+            self.sustain
+        } else {
+            self.sustain * (1. - (time - self.duration) / self.release.max(std::f32::EPSILON))
+        }
+    }
+}
+
+impl<S: Synth> Synth for Envelope<S> {
+    fn next_sample(&mut self, sample_rate: u32) -> Option<f32> {
+        if self.time >= self.duration + self.release {
+            return None;
+        }
+        let volume = self.volume();
+        self.time += 1. / sample_rate as f32;
+        self.synth
+            .next_sample(sample_rate)
+            .map(|sample| sample * volume)
+    }
+}
+
+/// Returns the phase moved by one sample of the frequency, wrapped from `0.0` to `1.0`.
+fn advance(phase: f32, frequency: f32, sample_rate: u32) -> f32 {
+    let phase = phase + frequency / sample_rate as f32;
+    phase - phase.floor()
+}
+
+/// Plays a `Synth` through rodio.
+pub(crate) struct SynthSource<S> {
+    synth: S,
+}
+
+impl<S: Synth> SynthSource<S> {
+    pub(crate) fn new(synth: S) -> Self {
+        SynthSource { synth }
+    }
+}
+
+impl<S: Synth> Iterator for SynthSource<S> {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        self.synth
+            .next_sample(SYNTH_SAMPLE_RATE)
+            .map(|sample| (sample.max(-1.).min(1.) * f32::from(i16::max_value())) as i16)
+    }
+}
+
+impl<S: Synth> RSource for SynthSource<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SYNTH_SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oscillators_follow_their_params_and_envelopes_end() {
+        let mut square = Oscillator::new(Waveform::Square, 1.);
+        let frequency = square.frequency.clone();
+        let samples = (0..4)
+            .map(|_| square.next_sample(4).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(vec![1., 1., -1., -1.], samples);
+        frequency.set(2.);
+        let samples = (0..4)
+            .map(|_| square.next_sample(4).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(vec![1., -1., 1., -1.], samples);
+
+        let mut blip = Envelope::new(Oscillator::new(Waveform::Square, 1.), 0.5).with_release(0.5);
+        let mut samples = Vec::new();
+        while let Some(sample) = blip.next_sample(4) {
+            samples.push(sample);
+        }
+        assert_eq!(4, samples.len());
+        // Halfway through the release.
+        assert!((samples[3] + 0.5).abs() < 1e-6);
+
+        let loud = Oscillator::new(Waveform::Square, 1.).with_amplitude(2.);
+        assert_eq!(Some(i16::max_value()), SynthSource::new(loud).next());
+    }
+}
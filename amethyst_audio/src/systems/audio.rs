@@ -10,11 +10,12 @@ use std::{
 use rodio::SpatialSink;
 
 use amethyst_core::{
-    specs::prelude::{Entities, Entity, Join, Read, ReadStorage, System, WriteStorage},
+    specs::prelude::{Entities, Entity, Join, Read, ReadStorage, System, Write, WriteStorage},
     transform::GlobalTransform,
 };
 
 use crate::{
+    caption::Captions,
     components::{AudioEmitter, AudioListener},
     end_signal::EndSignalSource,
 };
@@ -42,12 +43,12 @@ impl<'a> System<'a> for AudioSystem {
         ReadStorage<'a, GlobalTransform>,
         ReadStorage<'a, AudioListener>,
         WriteStorage<'a, AudioEmitter>,
+        Write<'a, Captions>,
     );
 
-    fn run(
-        &mut self,
-        (select_listener, entities, transform, listener, mut audio_emitter): Self::SystemData,
-    ) {
+    fn run(&mut self, data: Self::SystemData) {
+        let (select_listener, entities, transform, listener, mut audio_emitter, mut captions) =
+            data;
         #[cfg(feature = "profiler")]
         profile_scope!("audio_system");
         // Process emitters and listener.
@@ -89,7 +90,10 @@ impl<'a> System<'a> for AudioSystem {
                             }
                         }
                     }
-                    while let Some(source) = audio_emitter.sound_queue.pop() {
+                    while let Some((source, caption)) = audio_emitter.sound_queue.pop() {
+                        if let Some(caption) = caption {
+                            captions.show(caption);
+                        }
                         let sink = SpatialSink::new(
                             &listener.output.device,
                             emitter_position,
@@ -2,8 +2,10 @@ use std::{hash::Hash, marker::PhantomData};
 
 use amethyst_core::{
     bundle::{Result, SystemBundle},
+    nalgebra::{Unit, Vector3},
     specs::prelude::DispatcherBuilder,
 };
+use winit::{MouseButton, VirtualKeyCode};
 
 use super::*;
 
@@ -16,6 +18,8 @@ use super::*;
 /// `TransformSystem` in order to apply changes made by these systems in the same frame.
 /// Adding this bundle will grab the mouse, hide it and keep it centered.
 ///
+/// Keys to move faster and slower while held can be set with `with_speed_modifiers`.
+///
 /// # Type parameters
 ///
 /// * `A`: This is the key the `InputHandler` is using for axes. Often, this is a `String`.
@@ -36,6 +40,11 @@ pub struct FlyControlBundle<A, B> {
     right_input_axis: Option<A>,
     up_input_axis: Option<A>,
     forward_input_axis: Option<A>,
+    sprint: Option<(VirtualKeyCode, f32)>,
+    slow: Option<(VirtualKeyCode, f32)>,
+    scroll_speed_factor: f32,
+    up_axis: Option<Unit<Vector3<f32>>>,
+    smoothing: f32,
     _marker: PhantomData<B>,
 }
 
@@ -53,6 +62,11 @@ impl<A, B> FlyControlBundle<A, B> {
             right_input_axis,
             up_input_axis,
             forward_input_axis,
+            sprint: None,
+            slow: None,
+            scroll_speed_factor: 0.,
+            up_axis: None,
+            smoothing: 0.,
             _marker: PhantomData,
         }
     }
@@ -69,6 +83,41 @@ impl<A, B> FlyControlBundle<A, B> {
         self.speed = speed;
         self
     }
+
+    /// Sets the keys that multiply and divide the speed while held, with their factors, e.g.
+    /// `Some((VirtualKeyCode::LShift, 4.))` to sprint. Both are disabled by default.
+    pub fn with_speed_modifiers(
+        mut self,
+        sprint: Option<(VirtualKeyCode, f32)>,
+        slow: Option<(VirtualKeyCode, f32)>,
+    ) -> Self {
+        self.sprint = sprint;
+        self.slow = slow;
+        self
+    }
+
+    /// Changes the speed by the given fraction with each step of the mouse wheel. Defaults to
+    /// 0.0, which disables it.
+    pub fn with_scroll_speed_factor(mut self, factor: f32) -> Self {
+        self.scroll_speed_factor = factor;
+        self
+    }
+
+    /// Uses the given global axis as up, e.g. `Vector3::z_axis()` for Z-up scenes. The up input
+    /// then moves along it, the other inputs perpendicular to it, and the camera yaws around it.
+    /// By default, all inputs move along the local axes and the camera yaws around the global y
+    /// axis.
+    pub fn with_up_axis(mut self, up_axis: Unit<Vector3<f32>>) -> Self {
+        self.up_axis = Some(up_axis);
+        self
+    }
+
+    /// Smooths movement and rotation, following the input at the given rate in inverse
+    /// seconds. Defaults to 0.0, which follows instantly.
+    pub fn with_smoothing(mut self, smoothing: f32) -> Self {
+        self.smoothing = smoothing;
+        self
+    }
 }
 
 impl<'a, 'b, A, B> SystemBundle<'a, 'b> for FlyControlBundle<A, B>
@@ -77,21 +126,23 @@ where
     B: Send + Sync + Hash + Eq + Clone + 'static,
 {
     fn build(self, builder: &mut DispatcherBuilder<'a, 'b>) -> Result<()> {
-        builder.add(
-            FlyMovementSystem::<A, B>::new(
-                self.speed,
-                self.right_input_axis,
-                self.up_input_axis,
-                self.forward_input_axis,
-            ),
-            "fly_movement",
-            &[],
-        );
-        builder.add(
-            FreeRotationSystem::<A, B>::new(self.sensitivity_x, self.sensitivity_y),
-            "free_rotation",
-            &[],
-        );
+        let mut movement = FlyMovementSystem::<A, B>::new(
+            self.speed,
+            self.right_input_axis,
+            self.up_input_axis,
+            self.forward_input_axis,
+        )
+        .with_speed_modifiers(self.sprint, self.slow)
+        .with_scroll_speed_factor(self.scroll_speed_factor)
+        .with_smoothing(self.smoothing);
+        let mut rotation = FreeRotationSystem::<A, B>::new(self.sensitivity_x, self.sensitivity_y)
+            .with_smoothing(self.smoothing);
+        if let Some(up_axis) = self.up_axis {
+            movement = movement.with_up_axis(up_axis);
+            rotation = rotation.with_up_axis(up_axis);
+        }
+        builder.add(movement, "fly_movement", &[]);
+        builder.add(rotation, "free_rotation", &[]);
         builder.add(
             MouseFocusUpdateSystem::new(),
             "mouse_focus",
@@ -105,13 +156,25 @@ where
 /// The bundle that creates an arc ball movement system.
 /// Note: Will not actually create a moving entity. It will only register the needed resources and systems.
 /// The generic parameters A and B are the ones used in InputHandler<A,B>.
-/// You might want to add "fly_movement" and "free_rotation" as dependencies of the TransformSystem.
-/// Adding this bundle will grab the mouse, hide it and keep it centered.
+/// You might want to add "arc_ball_rotation" as a dependency of the TransformSystem.
+/// Adding this bundle will grab the mouse, hide it and keep it centered, unless a rotate button
+/// is set, in which case the mouse is only grabbed while the button is held.
 ///
 /// See the `arc_ball_camera` example to see how to use the arc ball camera.
+///
+/// # Systems
+///
+/// This bundle adds the following systems:
+///
+/// * `ArcBallInputSystem`
+/// * `ArcBallRotationSystem`
+/// * `MouseFocusUpdateSystem`
+/// * `CursorHideSystem`
 pub struct ArcBallControlBundle<A, B> {
     sensitivity_x: f32,
     sensitivity_y: f32,
+    zoom_factor: f32,
+    rotate_button: Option<MouseButton>,
     _marker: PhantomData<(A, B)>,
 }
 
@@ -121,6 +184,8 @@ impl<A, B> ArcBallControlBundle<A, B> {
         ArcBallControlBundle {
             sensitivity_x: 1.0,
             sensitivity_y: 1.0,
+            zoom_factor: 0.1,
+            rotate_button: None,
             _marker: PhantomData,
         }
     }
@@ -131,6 +196,20 @@ impl<A, B> ArcBallControlBundle<A, B> {
         self.sensitivity_y = y;
         self
     }
+
+    /// Alters the fraction of the distance that each step of the mouse wheel zooms, defaults to
+    /// 0.1. Use 0.0 to disable zooming with the mouse wheel.
+    pub fn with_zoom_factor(mut self, zoom_factor: f32) -> Self {
+        self.zoom_factor = zoom_factor;
+        self
+    }
+
+    /// Only rotates the camera while the given mouse button is held, capturing the cursor in
+    /// the meantime.
+    pub fn with_rotate_button(mut self, button: MouseButton) -> Self {
+        self.rotate_button = Some(button);
+        self
+    }
 }
 
 impl<'a, 'b, A, B> SystemBundle<'a, 'b> for ArcBallControlBundle<A, B>
@@ -139,16 +218,25 @@ where
     B: Send + Sync + Hash + Eq + Clone + 'static,
 {
     fn build(self, builder: &mut DispatcherBuilder<'a, 'b>) -> Result<()> {
-        builder.add(ArcBallRotationSystem::default(), "arc_ball_rotation", &[]);
         builder.add(
-            FreeRotationSystem::<A, B>::new(self.sensitivity_x, self.sensitivity_y),
-            "free_rotation",
+            ArcBallInputSystem::<A, B>::new(
+                self.sensitivity_x,
+                self.sensitivity_y,
+                self.zoom_factor,
+                self.rotate_button,
+            ),
+            "arc_ball_input",
             &[],
         );
+        builder.add(
+            ArcBallRotationSystem::default(),
+            "arc_ball_rotation",
+            &["arc_ball_input"],
+        );
         builder.add(
             MouseFocusUpdateSystem::new(),
             "mouse_focus",
-            &["free_rotation"],
+            &["arc_ball_input"],
         );
         builder.add(CursorHideSystem::new(), "cursor_hide", &["mouse_focus"]);
         Ok(())
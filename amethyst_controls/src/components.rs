@@ -1,5 +1,10 @@
 use amethyst_assets::{PrefabData, PrefabError};
-use amethyst_core::specs::prelude::{Component, Entity, HashMapStorage, NullStorage, WriteStorage};
+use amethyst_core::{
+    nalgebra::{UnitQuaternion, Vector3},
+    specs::prelude::{
+        Component, DenseVecStorage, Entity, HashMapStorage, NullStorage, WriteStorage,
+    },
+};
 
 /// Add this to a camera if you want it to be a fly camera.
 /// You need to add the FlyControlBundle or the required systems for it to work.
@@ -10,13 +15,86 @@ impl Component for FlyControlTag {
     type Storage = NullStorage<FlyControlTag>;
 }
 
-/// To add an arc ball behaviour, add this to a camera which already has the FlyControlTag added.
+/// Add this to a camera to make it orbit around a target entity.
+/// You need to add the ArcBallControlBundle or the required systems for it to work.
+///
+/// The orbit is controlled by `yaw`, `pitch` and `distance`, which the `ArcBallInputSystem`
+/// changes from mouse input, but which can also be set directly. The camera follows changes
+/// smoothly if `smoothing` is set, and is pulled towards the target when an `ArcBallObstacle`
+/// is in the way.
 #[derive(Debug, Clone)]
 pub struct ArcBallControlTag {
     /// The target entity which the camera will orbit
     pub target: Entity,
     /// The distance from the target entity that the camera should orbit at.
     pub distance: f32,
+    /// The minimum distance zooming can reach.
+    pub min_distance: f32,
+    /// The maximum distance zooming can reach.
+    pub max_distance: f32,
+    /// Rotation around the global y axis in radians.
+    pub yaw: f32,
+    /// Rotation around the local x axis in radians, limited to just below straight up and down.
+    pub pitch: f32,
+    /// How fast the camera follows rotation and zoom changes, in inverse seconds. `0.` applies
+    /// changes instantly, higher values follow faster.
+    pub smoothing: f32,
+    /// How far the camera stays in front of obstacles.
+    pub collision_padding: f32,
+    /// Yaw, pitch and distance currently shown, `None` until the system first ran.
+    pub(crate) current: Option<(f32, f32, f32)>,
+}
+
+impl ArcBallControlTag {
+    /// Creates a tag orbiting `target` at `distance`, with the orientation of the camera's
+    /// transform when the system first runs.
+    pub fn new(target: Entity, distance: f32) -> Self {
+        ArcBallControlTag {
+            target,
+            distance,
+            min_distance: 0.,
+            max_distance: std::f32::MAX,
+            yaw: 0.,
+            pitch: 0.,
+            smoothing: 0.,
+            collision_padding: 0.1,
+            current: None,
+        }
+    }
+
+    /// Sets the distance limits for zooming.
+    pub fn with_zoom_limits(mut self, min_distance: f32, max_distance: f32) -> Self {
+        self.min_distance = min_distance;
+        self.max_distance = max_distance;
+        self
+    }
+
+    /// Sets how fast the camera follows rotation and zoom changes, in inverse seconds.
+    pub fn with_smoothing(mut self, smoothing: f32) -> Self {
+        self.smoothing = smoothing;
+        self
+    }
+
+    /// Sets how far the camera stays in front of obstacles.
+    pub fn with_collision_padding(mut self, collision_padding: f32) -> Self {
+        self.collision_padding = collision_padding;
+        self
+    }
+
+    /// Returns the rotation of a camera looking at the target with the given yaw and pitch.
+    pub(crate) fn rotation(yaw: f32, pitch: f32) -> UnitQuaternion<f32> {
+        UnitQuaternion::from_axis_angle(&Vector3::y_axis(), yaw)
+            * UnitQuaternion::from_axis_angle(&Vector3::x_axis(), pitch)
+    }
+
+    /// Returns the yaw and pitch of a camera with the given rotation, the inverse of `rotation`.
+    pub(crate) fn yaw_pitch(rotation: &UnitQuaternion<f32>) -> (f32, f32) {
+        let forward = rotation * -Vector3::z();
+        (
+            (-forward.x).atan2(-forward.z),
+            forward.y.max(-1.).min(1.).asin(),
+        )
+    }
 }
 
 impl Component for ArcBallControlTag {
@@ -26,6 +104,75 @@ impl Component for ArcBallControlTag {
     type Storage = HashMapStorage<ArcBallControlTag>;
 }
 
+/// Add this to entities that an arc ball camera can't move through, e.g. walls.
+///
+/// The obstacle is an axis-aligned box around the entity's global position.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ArcBallObstacle {
+    /// Half the size of the box along each axis.
+    pub half_extents: Vector3<f32>,
+}
+
+impl Component for ArcBallObstacle {
+    type Storage = DenseVecStorage<ArcBallObstacle>;
+}
+
+/// Add this to a camera to keep a group of entities on screen, e.g. all players of a local
+/// multiplayer game. You need to add the `CameraTargetGroupSystem` for it to work.
+///
+/// The camera keeps its rotation, and is moved so it looks at the center of the targets from
+/// far enough away that all of them are visible with the given margin. Orthographic cameras
+/// stay at `min_distance` from the center and zoom instead.
+#[derive(Debug, Clone)]
+pub struct CameraTargetGroup {
+    /// The entities to keep on screen.
+    pub targets: Vec<Entity>,
+    /// Space to keep around each target, in world units.
+    pub margin: f32,
+    /// The closest the camera gets to the center of the targets.
+    pub min_distance: f32,
+    /// The farthest the camera gets from the center of the targets.
+    pub max_distance: f32,
+    /// How fast the camera follows the targets, in inverse seconds. `0.` follows instantly.
+    pub smoothing: f32,
+}
+
+impl CameraTargetGroup {
+    /// Creates a group framing the given targets, with a margin of one unit.
+    pub fn new(targets: Vec<Entity>) -> Self {
+        CameraTargetGroup {
+            targets,
+            margin: 1.,
+            min_distance: 1.,
+            max_distance: std::f32::MAX,
+            smoothing: 0.,
+        }
+    }
+
+    /// Sets the space to keep around each target.
+    pub fn with_margin(mut self, margin: f32) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    /// Sets the limits of the distance from the center of the targets.
+    pub fn with_distance_limits(mut self, min_distance: f32, max_distance: f32) -> Self {
+        self.min_distance = min_distance;
+        self.max_distance = max_distance;
+        self
+    }
+
+    /// Sets how fast the camera follows the targets, in inverse seconds.
+    pub fn with_smoothing(mut self, smoothing: f32) -> Self {
+        self.smoothing = smoothing;
+        self
+    }
+}
+
+impl Component for CameraTargetGroup {
+    type Storage = HashMapStorage<CameraTargetGroup>;
+}
+
 /// `PrefabData` for loading control tags on an `Entity`
 ///
 /// Will always load a `FlyControlTag`
@@ -50,13 +197,9 @@ impl<'a> PrefabData<'a> for ControlTagPrefab {
     ) -> Result<(), PrefabError> {
         system_data.0.insert(entity, FlyControlTag)?;
         if let Some((index, distance)) = self.arc_ball {
-            system_data.1.insert(
-                entity,
-                ArcBallControlTag {
-                    target: entities[index],
-                    distance,
-                },
-            )?;
+            system_data
+                .1
+                .insert(entity, ArcBallControlTag::new(entities[index], distance))?;
         }
         Ok(())
     }
@@ -13,10 +13,12 @@ mod systems;
 
 pub use self::{
     bundles::{ArcBallControlBundle, FlyControlBundle},
-    components::{ArcBallControlTag, ControlTagPrefab, FlyControlTag},
+    components::{
+        ArcBallControlTag, ArcBallObstacle, CameraTargetGroup, ControlTagPrefab, FlyControlTag,
+    },
     resources::{HideCursor, WindowFocus},
     systems::{
-        ArcBallRotationSystem, CursorHideSystem, FlyMovementSystem, FreeRotationSystem,
-        MouseFocusUpdateSystem,
+        ArcBallInputSystem, ArcBallRotationSystem, CameraTargetGroupSystem, CursorHideSystem,
+        FlyMovementSystem, FreeRotationSystem, MouseFocusUpdateSystem,
     },
 };
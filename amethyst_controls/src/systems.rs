@@ -1,29 +1,38 @@
 use std::{hash::Hash, marker::PhantomData};
 
-use winit::{DeviceEvent, Event, WindowEvent};
+use winit::{DeviceEvent, Event, MouseButton, VirtualKeyCode, WindowEvent};
 
 use amethyst_core::{
-    nalgebra::{Unit, Vector3},
+    nalgebra::{Unit, UnitQuaternion, Vector3},
     shrev::{EventChannel, ReaderId},
     specs::prelude::{Join, Read, ReadStorage, Resources, System, Write, WriteStorage},
     timing::Time,
-    transform::Transform,
+    transform::{GlobalTransform, Transform},
 };
-use amethyst_input::{get_input_axis_simple, InputHandler};
-use amethyst_renderer::WindowMessages;
+use amethyst_input::{get_input_axis_simple, InputEvent, InputHandler, ScrollDirection};
+use amethyst_renderer::{Camera, WindowMessages};
 
 use crate::{
-    components::{ArcBallControlTag, FlyControlTag},
+    components::{ArcBallControlTag, ArcBallObstacle, CameraTargetGroup, FlyControlTag},
     resources::{HideCursor, WindowFocus},
 };
 
+/// Pitch limit of arc ball cameras, just below straight up and down.
+const MAX_PITCH: f32 = 89. * std::f32::consts::PI / 180.;
+
 /// The system that manages the fly movement.
 ///
+/// If speed modifiers are set, holding the sprint key multiplies the speed and holding the slow
+/// key divides it. The mouse wheel changes the base speed, if a scroll speed factor is set.
+///
 /// # Type parameters
 ///
 /// * `A`: This is the key the `InputHandler` is using for axes. Often, this is a `String`.
 /// * `B`: This is the key the `InputHandler` is using for actions. Often, this is a `String`.
-pub struct FlyMovementSystem<A, B> {
+pub struct FlyMovementSystem<A, B>
+where
+    B: 'static,
+{
     /// The movement speed of the movement in units per second.
     speed: f32,
     /// The name of the input axis to locally move in the x coordinates.
@@ -32,7 +41,19 @@ pub struct FlyMovementSystem<A, B> {
     up_input_axis: Option<A>,
     /// The name of the input axis to locally move in the z coordinates.
     forward_input_axis: Option<A>,
-    _marker: PhantomData<B>,
+    /// The key multiplying the speed while held, and the multiplier.
+    sprint: Option<(VirtualKeyCode, f32)>,
+    /// The key dividing the speed while held, and the divisor.
+    slow: Option<(VirtualKeyCode, f32)>,
+    /// The fraction of the speed that each step of the mouse wheel adds or removes.
+    scroll_speed_factor: f32,
+    /// The global axis to move along with the up input, instead of the local y axis. The other
+    /// inputs then move perpendicular to it.
+    up_axis: Option<Unit<Vector3<f32>>>,
+    /// How fast the velocity follows the input, in inverse seconds, `0.` to follow instantly.
+    smoothing: f32,
+    velocity: Vector3<f32>,
+    input_reader: Option<ReaderId<InputEvent<B>>>,
 }
 
 impl<A, B> FlyMovementSystem<A, B>
@@ -52,8 +73,62 @@ where
             right_input_axis,
             up_input_axis,
             forward_input_axis,
-            _marker: PhantomData,
+            sprint: None,
+            slow: None,
+            scroll_speed_factor: 0.,
+            up_axis: None,
+            smoothing: 0.,
+            velocity: Vector3::zeros(),
+            input_reader: None,
+        }
+    }
+
+    /// Sets the keys that multiply and divide the speed while held, with their factors, or
+    /// `None` to disable them.
+    pub fn with_speed_modifiers(
+        mut self,
+        sprint: Option<(VirtualKeyCode, f32)>,
+        slow: Option<(VirtualKeyCode, f32)>,
+    ) -> Self {
+        self.sprint = sprint;
+        self.slow = slow;
+        self
+    }
+
+    /// Sets the fraction of the speed that each step of the mouse wheel adds or removes, `0.`
+    /// to disable it.
+    pub fn with_scroll_speed_factor(mut self, factor: f32) -> Self {
+        self.scroll_speed_factor = factor;
+        self
+    }
+
+    /// Moves along the given global axis with the up input, instead of the local y axis, and
+    /// perpendicular to it with the other inputs, however the camera is pitched.
+    pub fn with_up_axis(mut self, up_axis: Unit<Vector3<f32>>) -> Self {
+        self.up_axis = Some(up_axis);
+        self
+    }
+
+    /// Sets how fast the movement follows the input, in inverse seconds. `0.` follows
+    /// instantly, lower values accelerate and brake more smoothly.
+    pub fn with_smoothing(mut self, smoothing: f32) -> Self {
+        self.smoothing = smoothing;
+        self
+    }
+
+    fn current_speed(&self, input: &InputHandler<A, B>) -> f32 {
+        let mut speed = self.speed;
+        if let Some((key, multiplier)) = self.sprint {
+            if input.key_is_down(key) {
+                speed *= multiplier;
+            }
+        }
+        if let Some((key, divisor)) = self.slow {
+            if input.key_is_down(key) {
+                speed /= divisor;
+            }
         }
+        speed
     }
 }
 
@@ -66,51 +141,398 @@ where
         Read<'a, Time>,
         WriteStorage<'a, Transform>,
         Read<'a, InputHandler<A, B>>,
+        Read<'a, EventChannel<InputEvent<B>>>,
         ReadStorage<'a, FlyControlTag>,
     );
 
-    fn run(&mut self, (time, mut transform, input, tag): Self::SystemData) {
+    fn run(&mut self, (time, mut transform, input, input_events, tag): Self::SystemData) {
+        for event in
+            input_events.read(self.input_reader.as_mut().expect(
+                "`FlyMovementSystem::setup` was not called before `FlyMovementSystem::run`",
+            ))
+        {
+            match *event {
+                InputEvent::MouseWheelMoved(ScrollDirection::ScrollUp) => {
+                    self.speed *= 1. + self.scroll_speed_factor;
+                }
+                InputEvent::MouseWheelMoved(ScrollDirection::ScrollDown) => {
+                    self.speed /= 1. + self.scroll_speed_factor;
+                }
+                _ => {}
+            }
+        }
+
         let x = get_input_axis_simple(&self.right_input_axis, &input);
         let y = get_input_axis_simple(&self.up_input_axis, &input);
         let z = get_input_axis_simple(&self.forward_input_axis, &input);
+        let target = Vector3::new(x, y, z)
+            .try_normalize(1.0e-6)
+            .unwrap_or_else(Vector3::zeros)
+            * self.current_speed(&input);
 
-        if let Some(dir) = Unit::try_new(Vector3::new(x, y, z), 1.0e-6) {
-            for (transform, _) in (&mut transform, &tag).join() {
-                transform.move_along_local(dir, time.delta_seconds() * self.speed);
-            }
+        let blend = if self.smoothing > 0. {
+            1. - (-self.smoothing * time.delta_seconds()).exp()
+        } else {
+            1.
+        };
+        self.velocity += (target - self.velocity) * blend;
+
+        let delta = time.delta_seconds();
+        for (transform, _) in (&mut transform, &tag).join() {
+            let translation = fly_translation(&self.velocity, transform.rotation(), self.up_axis);
+            transform.move_global(translation * delta);
         }
     }
+
+    fn setup(&mut self, res: &mut Resources) {
+        use amethyst_core::specs::prelude::SystemData;
+
+        Self::SystemData::setup(res);
+        self.input_reader = Some(
+            res.fetch_mut::<EventChannel<InputEvent<B>>>()
+                .register_reader(),
+        );
+    }
+}
+
+/// Returns the global velocity of a fly camera with the given rotation, from its local velocity.
+///
+/// With an up axis, the local x and z movement is kept perpendicular to it, at the same speed,
+/// and only the local y movement goes along it.
+fn fly_translation(
+    velocity: &Vector3<f32>,
+    rotation: &UnitQuaternion<f32>,
+    up_axis: Option<Unit<Vector3<f32>>>,
+) -> Vector3<f32> {
+    let up = match up_axis {
+        Some(up_axis) => up_axis.into_inner(),
+        None => return rotation * velocity,
+    };
+    let horizontal = rotation * Vector3::new(velocity.x, 0., velocity.z);
+    let projected = horizontal - up * horizontal.dot(&up);
+    let horizontal = projected
+        .try_normalize(1.0e-6)
+        .map_or_else(Vector3::zeros, |dir| dir * horizontal.norm());
+    horizontal + up * velocity.y
 }
 
 /// The system that manages the arc ball movement;
-/// In essence, the system will align the camera with its target while keeping the distance to it
-/// and while keeping the orientation of the camera.
+/// In essence, the system will place the camera on a sphere around its target, according to the
+/// yaw, pitch and distance of its `ArcBallControlTag`, facing the target.
 ///
-/// To modify the orientation of the camera in accordance with the mouse input, please use the
-/// `FreeRotationSystem`.
+/// Changes of the tag are followed smoothly if the tag's `smoothing` is set. When an
+/// `ArcBallObstacle` is between the target and the camera, the camera is pulled in in front of
+/// it, and eases back out once the view is clear.
+///
+/// To modify the orientation and distance of the camera in accordance with the mouse input,
+/// please use the `ArcBallInputSystem`.
 #[derive(Default)]
 pub struct ArcBallRotationSystem;
 
 impl<'a> System<'a> for ArcBallRotationSystem {
     type SystemData = (
+        Read<'a, Time>,
         WriteStorage<'a, Transform>,
-        ReadStorage<'a, ArcBallControlTag>,
+        WriteStorage<'a, ArcBallControlTag>,
+        ReadStorage<'a, ArcBallObstacle>,
+        ReadStorage<'a, GlobalTransform>,
     );
 
-    fn run(&mut self, (mut transforms, tags): Self::SystemData) {
-        let mut position = None;
-        for (transform, arc_ball_camera_tag) in (&transforms, &tags).join() {
-            let pos_vec = transform.rotation() * -Vector3::z() * arc_ball_camera_tag.distance;
-            if let Some(target_transform) = transforms.get(arc_ball_camera_tag.target) {
-                position = Some(target_transform.translation() - pos_vec);
+    fn run(&mut self, (time, mut transforms, mut tags, obstacles, globals): Self::SystemData) {
+        let obstacles = (&obstacles, &globals)
+            .join()
+            .map(|(obstacle, global)| {
+                let center = Vector3::new(global.0[(0, 3)], global.0[(1, 3)], global.0[(2, 3)]);
+                (
+                    center - obstacle.half_extents,
+                    center + obstacle.half_extents,
+                )
+            })
+            .collect::<Vec<_>>();
+        let targets = (&transforms, &tags)
+            .join()
+            .map(|(_, tag)| transforms.get(tag.target).map(|t| *t.translation()))
+            .collect::<Vec<_>>();
+
+        for ((transform, tag), target) in (&mut transforms, &mut tags).join().zip(targets) {
+            let target = match target {
+                Some(target) => target,
+                None => continue,
+            };
+
+            if tag.current.is_none() {
+                let (yaw, pitch) = ArcBallControlTag::yaw_pitch(transform.rotation());
+                tag.yaw = yaw;
+                tag.pitch = pitch;
+            }
+            tag.pitch = tag.pitch.max(-MAX_PITCH).min(MAX_PITCH);
+            tag.distance = tag.distance.max(tag.min_distance).min(tag.max_distance);
+
+            let (yaw, pitch, distance) = tag.current.unwrap_or((tag.yaw, tag.pitch, tag.distance));
+            let blend = if tag.smoothing > 0. {
+                1. - (-tag.smoothing * time.delta_seconds()).exp()
+            } else {
+                1.
+            };
+            let yaw = yaw + (tag.yaw - yaw) * blend;
+            let pitch = pitch + (tag.pitch - pitch) * blend;
+            let mut distance = distance + (tag.distance - distance) * blend;
+
+            let rotation = ArcBallControlTag::rotation(yaw, pitch);
+            let backward = rotation * Vector3::z();
+            for &(min, max) in &obstacles {
+                if let Some(hit) = ray_box_distance(&target, &backward, &min, &max) {
+                    distance = distance.min((hit - tag.collision_padding).max(0.));
+                }
             }
+            tag.current = Some((yaw, pitch, distance));
+
+            transform.set_rotation(rotation);
+            *transform.translation_mut() = target + backward * distance;
         }
-        if let Some(new_pos) = position {
-            for (transform, _) in (&mut transforms, &tags).join() {
-                *transform.translation_mut() = new_pos;
+    }
+}
+
+/// Returns the distance along a ray from `origin` to the box from `min` to `max`, or `None` if
+/// the ray misses it or starts inside it.
+fn ray_box_distance(
+    origin: &Vector3<f32>,
+    direction: &Vector3<f32>,
+    min: &Vector3<f32>,
+    max: &Vector3<f32>,
+) -> Option<f32> {
+    let mut near = 0f32;
+    let mut far = std::f32::INFINITY;
+    for i in 0..3 {
+        if direction[i].abs() < 1.0e-6 {
+            if origin[i] < min[i] || origin[i] > max[i] {
+                return None;
+            }
+        } else {
+            let t0 = (min[i] - origin[i]) / direction[i];
+            let t1 = (max[i] - origin[i]) / direction[i];
+            near = near.max(t0.min(t1));
+            far = far.min(t0.max(t1));
+            if near > far {
+                return None;
             }
         }
     }
+    if near > 0. {
+        Some(near)
+    } else {
+        None
+    }
+}
+
+/// The system that moves cameras with a `CameraTargetGroup` to keep their targets on screen.
+///
+/// Targets are read from their `GlobalTransform`, and the camera's `Transform` is written, so
+/// the camera shouldn't have a parent. Targets without a `GlobalTransform` are ignored.
+#[derive(Default)]
+pub struct CameraTargetGroupSystem;
+
+impl<'a> System<'a> for CameraTargetGroupSystem {
+    type SystemData = (
+        Read<'a, Time>,
+        ReadStorage<'a, CameraTargetGroup>,
+        ReadStorage<'a, GlobalTransform>,
+        WriteStorage<'a, Transform>,
+        WriteStorage<'a, Camera>,
+    );
+
+    fn run(&mut self, (time, groups, globals, mut transforms, mut cameras): Self::SystemData) {
+        for (group, transform, camera) in (&groups, &mut transforms, &mut cameras).join() {
+            let points = group
+                .targets
+                .iter()
+                .filter_map(|&target| globals.get(target))
+                .map(|global| Vector3::new(global.0[(0, 3)], global.0[(1, 3)], global.0[(2, 3)]))
+                .collect::<Vec<_>>();
+            if points.is_empty() {
+                continue;
+            }
+            let center =
+                points.iter().fold(Vector3::zeros(), |sum, p| sum + p) / points.len() as f32;
+            // Offsets of the targets from the center, in camera space.
+            let inverse = transform.rotation().inverse();
+            let offsets = points
+                .iter()
+                .map(|p| inverse * (p - center))
+                .collect::<Vec<_>>();
+
+            let blend = if group.smoothing > 0. {
+                1. - (-group.smoothing * time.delta_seconds()).exp()
+            } else {
+                1.
+            };
+            // The last row of orthographic projections is (0, 0, 0, 1).
+            let orthographic = camera.proj[(3, 2)].abs() < 1.0e-6;
+            let distance = if orthographic {
+                let aspect = camera.proj[(1, 1)] / camera.proj[(0, 0)];
+                let half_height = offsets.iter().fold(1.0e-3f32, |half_height, p| {
+                    half_height
+                        .max(p.y.abs() + group.margin)
+                        .max((p.x.abs() + group.margin) / aspect)
+                });
+                let scale = camera.proj[(1, 1)] + (1. / half_height - camera.proj[(1, 1)]) * blend;
+                camera.proj[(1, 1)] = scale;
+                camera.proj[(0, 0)] = scale / aspect;
+                group.min_distance
+            } else {
+                framing_distance(
+                    &offsets,
+                    group.margin,
+                    1. / camera.proj[(0, 0)],
+                    1. / camera.proj[(1, 1)],
+                )
+                .max(group.min_distance)
+                .min(group.max_distance)
+            };
+
+            let backward = transform.rotation() * Vector3::z();
+            let position = center + backward * distance;
+            let current = *transform.translation();
+            *transform.translation_mut() = current + (position - current) * blend;
+        }
+    }
+}
+
+/// Returns how far behind the center a perspective camera has to be to see all offsets, given in
+/// camera space, with the given margin. `tan_x` and `tan_y` are the tangents of half the
+/// horizontal and vertical field of view.
+fn framing_distance(offsets: &[Vector3<f32>], margin: f32, tan_x: f32, tan_y: f32) -> f32 {
+    offsets.iter().fold(0f32, |distance, p| {
+        distance
+            .max(p.z + (p.x.abs() + margin) / tan_x)
+            .max(p.z + (p.y.abs() + margin) / tan_y)
+    })
+}
+
+/// The system that controls arc ball cameras with the mouse.
+///
+/// Moving the mouse changes the yaw and pitch of every `ArcBallControlTag`, the mouse wheel
+/// zooms in and out. Like the `FreeRotationSystem`, it goes into an inactive state if the
+/// window is not focused. If a rotate button is set, the camera only rotates while the button
+/// is held, and the cursor is captured by setting `HideCursor.hide` during that time; otherwise
+/// the camera rotates while `HideCursor.hide` is `true`.
+///
+/// # Type parameters
+///
+/// * `A`: This is the key the `InputHandler` is using for axes. Often, this is a `String`.
+/// * `B`: This is the key the `InputHandler` is using for actions. Often, this is a `String`.
+pub struct ArcBallInputSystem<A, B>
+where
+    B: 'static,
+{
+    sensitivity_x: f32,
+    sensitivity_y: f32,
+    zoom_factor: f32,
+    rotate_button: Option<MouseButton>,
+    _marker: PhantomData<A>,
+    event_reader: Option<ReaderId<Event>>,
+    input_reader: Option<ReaderId<InputEvent<B>>>,
+}
+
+impl<A, B> ArcBallInputSystem<A, B>
+where
+    B: 'static,
+{
+    /// Builds a new `ArcBallInputSystem` with the specified mouse sensitivity values, the
+    /// fraction of the distance that each step of the mouse wheel zooms, and an optional button
+    /// that has to be held to rotate.
+    pub fn new(
+        sensitivity_x: f32,
+        sensitivity_y: f32,
+        zoom_factor: f32,
+        rotate_button: Option<MouseButton>,
+    ) -> Self {
+        ArcBallInputSystem {
+            sensitivity_x,
+            sensitivity_y,
+            zoom_factor,
+            rotate_button,
+            _marker: PhantomData,
+            event_reader: None,
+            input_reader: None,
+        }
+    }
+}
+
+impl<'a, A, B> System<'a> for ArcBallInputSystem<A, B>
+where
+    A: Send + Sync + Hash + Eq + Clone + 'static,
+    B: Send + Sync + Hash + Eq + Clone + 'static,
+{
+    type SystemData = (
+        Read<'a, EventChannel<Event>>,
+        Read<'a, EventChannel<InputEvent<B>>>,
+        Read<'a, InputHandler<A, B>>,
+        WriteStorage<'a, ArcBallControlTag>,
+        Read<'a, WindowFocus>,
+        Write<'a, HideCursor>,
+    );
+
+    fn run(&mut self, (events, input_events, input, mut tags, focus, mut hide): Self::SystemData) {
+        let rotating = match self.rotate_button {
+            Some(button) => {
+                hide.hide = input.mouse_button_is_down(button);
+                focus.is_focused && hide.hide
+            }
+            None => focus.is_focused && hide.hide,
+        };
+
+        for event in
+            events.read(self.event_reader.as_mut().expect(
+                "`ArcBallInputSystem::setup` was not called before `ArcBallInputSystem::run`",
+            ))
+        {
+            if let Event::DeviceEvent {
+                event: DeviceEvent::MouseMotion { delta: (x, y) },
+                ..
+            } = *event
+            {
+                if rotating {
+                    for tag in (&mut tags).join() {
+                        tag.yaw -= (x as f32 * self.sensitivity_x).to_radians();
+                        tag.pitch = (tag.pitch - (y as f32 * self.sensitivity_y).to_radians())
+                            .max(-MAX_PITCH)
+                            .min(MAX_PITCH);
+                    }
+                }
+            }
+        }
+
+        for event in
+            input_events.read(self.input_reader.as_mut().expect(
+                "`ArcBallInputSystem::setup` was not called before `ArcBallInputSystem::run`",
+            ))
+        {
+            let factor = match *event {
+                InputEvent::MouseWheelMoved(ScrollDirection::ScrollUp) => 1. - self.zoom_factor,
+                InputEvent::MouseWheelMoved(ScrollDirection::ScrollDown) => 1. + self.zoom_factor,
+                _ => continue,
+            };
+            if focus.is_focused {
+                for tag in (&mut tags).join() {
+                    tag.distance = (tag.distance * factor)
+                        .max(tag.min_distance)
+                        .min(tag.max_distance);
+                }
+            }
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        use amethyst_core::specs::prelude::SystemData;
+
+        Self::SystemData::setup(res);
+        self.event_reader = Some(res.fetch_mut::<EventChannel<Event>>().register_reader());
+        self.input_reader = Some(
+            res.fetch_mut::<EventChannel<InputEvent<B>>>()
+                .register_reader(),
+        );
+    }
 }
 
 /// The system that manages the view rotation.
@@ -127,6 +549,10 @@ impl<'a> System<'a> for ArcBallRotationSystem {
 pub struct FreeRotationSystem<A, B> {
     sensitivity_x: f32,
     sensitivity_y: f32,
+    up_axis: Option<Unit<Vector3<f32>>>,
+    smoothing: f32,
+    /// Yaw and pitch in radians that weren't applied yet because of smoothing.
+    pending: (f32, f32),
     _marker1: PhantomData<A>,
     _marker2: PhantomData<B>,
     event_reader: Option<ReaderId<Event>>,
@@ -138,11 +564,26 @@ impl<A, B> FreeRotationSystem<A, B> {
         FreeRotationSystem {
             sensitivity_x,
             sensitivity_y,
+            up_axis: None,
+            smoothing: 0.,
+            pending: (0., 0.),
             _marker1: PhantomData,
             _marker2: PhantomData,
             event_reader: None,
         }
     }
+
+    /// Yaws around the given global axis, instead of the global y axis.
+    pub fn with_up_axis(mut self, up_axis: Unit<Vector3<f32>>) -> Self {
+        self.up_axis = Some(up_axis);
+        self
+    }
+
+    /// Sets how fast the rotation follows the mouse, in inverse seconds. `0.` follows instantly.
+    pub fn with_smoothing(mut self, smoothing: f32) -> Self {
+        self.smoothing = smoothing;
+        self
+    }
 }
 
 impl<'a, A, B> System<'a> for FreeRotationSystem<A, B>
@@ -151,6 +592,7 @@ where
     B: Send + Sync + Hash + Eq + Clone + 'static,
 {
     type SystemData = (
+        Read<'a, Time>,
         Read<'a, EventChannel<Event>>,
         WriteStorage<'a, Transform>,
         ReadStorage<'a, FlyControlTag>,
@@ -158,7 +600,7 @@ where
         Read<'a, HideCursor>,
     );
 
-    fn run(&mut self, (events, mut transform, tag, focus, hide): Self::SystemData) {
+    fn run(&mut self, (time, events, mut transform, tag, focus, hide): Self::SystemData) {
         let focused = focus.is_focused;
         for event in
             events.read(&mut self.event_reader.as_mut().expect(
@@ -168,14 +610,27 @@ where
             if focused && hide.hide {
                 if let Event::DeviceEvent { ref event, .. } = *event {
                     if let DeviceEvent::MouseMotion { delta: (x, y) } = *event {
-                        for (transform, _) in (&mut transform, &tag).join() {
-                            transform.pitch_local((-y as f32 * self.sensitivity_y).to_radians());
-                            transform.yaw_global((-x as f32 * self.sensitivity_x).to_radians());
-                        }
+                        self.pending.0 += (-x as f32 * self.sensitivity_x).to_radians();
+                        self.pending.1 += (-y as f32 * self.sensitivity_y).to_radians();
                     }
                 }
             }
         }
+
+        let blend = if self.smoothing > 0. {
+            1. - (-self.smoothing * time.delta_seconds()).exp()
+        } else {
+            1.
+        };
+        let (yaw, pitch) = (self.pending.0 * blend, self.pending.1 * blend);
+        self.pending = (self.pending.0 - yaw, self.pending.1 - pitch);
+        for (transform, _) in (&mut transform, &tag).join() {
+            transform.pitch_local(pitch);
+            match self.up_axis {
+                Some(up_axis) => transform.rotate_global(up_axis, yaw),
+                None => transform.yaw_global(yaw),
+            };
+        }
     }
 
     fn setup(&mut self, res: &mut Resources) {
@@ -271,3 +726,84 @@ impl<'a> System<'a> for CursorHideSystem {
         self.is_hidden = true;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn framing_distance_fits_widest_target() {
+        // 90 degree field of view in both directions.
+        let offsets = [Vector3::new(-2., 0., 0.), Vector3::new(2., 1., 0.)];
+        assert!((framing_distance(&offsets, 1., 1., 1.) - 3.).abs() < 1.0e-5);
+        // Targets closer to the camera need more distance.
+        let offsets = [Vector3::new(2., 0., 1.)];
+        assert!((framing_distance(&offsets, 0., 1., 1.) - 3.).abs() < 1.0e-5);
+    }
+
+    #[test]
+    fn ray_box_distance_hits_box_in_front() {
+        let min = Vector3::new(-1., -1., 4.);
+        let max = Vector3::new(1., 1., 6.);
+        let origin = Vector3::new(0., 0., 0.);
+        assert_eq!(
+            Some(4.),
+            ray_box_distance(&origin, &Vector3::z(), &min, &max)
+        );
+        assert_eq!(None, ray_box_distance(&origin, &-Vector3::z(), &min, &max));
+        assert_eq!(None, ray_box_distance(&origin, &Vector3::x(), &min, &max));
+    }
+
+    #[test]
+    fn speed_modifiers_are_opt_in() {
+        use winit::{DeviceId, ElementState, KeyboardInput, ModifiersState, WindowId};
+
+        let mut input = InputHandler::<String, String>::new();
+        let event = Event::WindowEvent {
+            window_id: unsafe { WindowId::dummy() },
+            event: WindowEvent::KeyboardInput {
+                device_id: unsafe { DeviceId::dummy() },
+                input: KeyboardInput {
+                    scancode: 42,
+                    state: ElementState::Pressed,
+                    virtual_keycode: Some(VirtualKeyCode::LShift),
+                    modifiers: ModifiersState::default(),
+                },
+            },
+        };
+        input.send_event(&event, &mut EventChannel::new(), 1.);
+
+        let system = FlyMovementSystem::<String, String>::new(2., None, None, None);
+        assert!((system.current_speed(&input) - 2.).abs() < 1.0e-6);
+        let system = system.with_speed_modifiers(Some((VirtualKeyCode::LShift, 4.)), None);
+        assert!((system.current_speed(&input) - 8.).abs() < 1.0e-6);
+    }
+
+    #[test]
+    fn up_axis_keeps_pitched_movement_level() {
+        use std::f32::consts::FRAC_PI_4;
+
+        // Looking 45 degrees down, moving forward at speed 1.
+        let rotation = UnitQuaternion::from_axis_angle(&Vector3::x_axis(), -FRAC_PI_4);
+        let forward = Vector3::new(0., 0., -1.);
+        let free = fly_translation(&forward, &rotation, None);
+        assert!(free.y < -0.5);
+
+        let level = fly_translation(&forward, &rotation, Some(Vector3::y_axis()));
+        assert!((level - Vector3::new(0., 0., -1.)).norm() < 1.0e-5);
+        let up = fly_translation(
+            &Vector3::new(0., 1., 0.),
+            &rotation,
+            Some(Vector3::y_axis()),
+        );
+        assert!((up - Vector3::new(0., 1., 0.)).norm() < 1.0e-5);
+    }
+
+    #[test]
+    fn yaw_pitch_round_trip() {
+        let rotation = ArcBallControlTag::rotation(0.5, -0.3);
+        let (yaw, pitch) = ArcBallControlTag::yaw_pitch(&rotation);
+        assert!((yaw - 0.5).abs() < 1.0e-5);
+        assert!((pitch + 0.3).abs() < 1.0e-5);
+    }
+}
@@ -145,6 +145,7 @@ impl Default for FrameRateLimitConfig {
 #[derive(Debug)]
 pub struct FrameLimiter {
     frame_duration: Duration,
+    fps: u32,
     strategy: FrameRateLimitStrategy,
     last_call: Instant,
 }
@@ -160,6 +161,7 @@ impl FrameLimiter {
     pub fn new(strategy: FrameRateLimitStrategy, fps: u32) -> Self {
         let mut s = Self {
             frame_duration: Duration::from_secs(0),
+            fps: 0,
             strategy: Default::default(),
             last_call: Instant::now(),
         };
@@ -174,9 +176,23 @@ impl FrameLimiter {
             fps = 144;
         }
         self.strategy = strategy;
+        self.fps = fps;
         self.frame_duration = Duration::from_secs(1) / fps;
     }
 
+    /// Returns the frame rate limiting strategy.
+    pub fn strategy(&self) -> FrameRateLimitStrategy {
+        self.strategy.clone()
+    }
+
+    /// Returns the maximum fps, or `None` if the frame rate is unlimited.
+    pub fn fps(&self) -> Option<u32> {
+        match self.strategy {
+            FrameRateLimitStrategy::Unlimited => None,
+            _ => Some(self.fps),
+        }
+    }
+
     /// Creates a new frame limiter with the given config.
     pub fn from_config(config: FrameRateLimitConfig) -> Self {
         Self::new(config.strategy, config.fps)
@@ -227,3 +243,24 @@ impl FrameLimiter {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fps_is_none_when_unlimited() {
+        assert_eq!(
+            None,
+            FrameLimiter::new(FrameRateLimitStrategy::Unlimited, 60).fps()
+        );
+        assert_eq!(
+            None,
+            FrameLimiter::new(FrameRateLimitStrategy::Sleep, 0).fps()
+        );
+        assert_eq!(
+            Some(60),
+            FrameLimiter::new(FrameRateLimitStrategy::Yield, 60).fps()
+        );
+    }
+}
@@ -26,9 +26,17 @@ use std::sync::Arc;
 pub use crate::{
     bundle::{Error, ErrorKind, Result, SystemBundle},
     event::EventReader,
-    system_ext::{Pausable, SystemExt},
+    memory::{
+        Bytes, ComponentMemorySystem, MemoryCategory, MemoryEntry, MemorySize, MemoryStats,
+        MemoryUsage, TrackingAllocator,
+    },
+    pause::PauseState,
+    rng::{Rng, RngConfig, RngStream},
+    shutdown::{ShutdownHandlers, ShutdownStatus},
+    system_ext::{Pausable, ResourceCriteria, RunCriteria, RunIf, SystemExt},
     timing::*,
     transform::*,
+    turn::{TurnCriteria, TurnEvent, TurnMode, TurnQueue, TurnSystem},
 };
 
 pub use self::{
@@ -40,10 +48,15 @@ mod axis;
 pub mod bundle;
 mod event;
 pub mod frame_limiter;
+mod memory;
 mod named;
+mod pause;
+mod rng;
+mod shutdown;
 mod system_ext;
 pub mod timing;
 pub mod transform;
+mod turn;
 
 /// A rayon thread pool wrapped in an `Arc`. This should be used as resource in `World`.
 pub type ArcThreadPool = Arc<rayon::ThreadPool>;
@@ -0,0 +1,338 @@
+//! Tracking of the memory used by the subsystems of the engine.
+//!
+//! The `MemoryStats` resource holds the memory used by every type of asset and component, grouped
+//! by `MemoryCategory`, along with the high-water marks. It is filled by systems like the
+//! `ComponentMemorySystem` and the `AssetMemorySystem` of `amethyst_assets`, which the bundles add
+//! for the types they own.
+//!
+//! The tracked memory doesn't cover everything a game allocates. Installing the
+//! `TrackingAllocator` as the global allocator also tracks the total heap usage, to see how much
+//! isn't attributed to any subsystem:
+//!
+//! ```rust,ignore
+//! #[global_allocator]
+//! static ALLOCATOR: TrackingAllocator = TrackingAllocator;
+//! ```
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System as SystemAlloc},
+    collections::BTreeMap,
+    fmt,
+    marker::PhantomData,
+    mem,
+    ops::{Add, AddAssign},
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
+use crate::specs::prelude::{Component, Join, ReadStorage, System, Write};
+
+/// The subsystem memory is attributed to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum MemoryCategory {
+    /// Assets like meshes and textures.
+    Assets,
+    /// Component storages.
+    Ecs,
+    /// User interface components and assets.
+    Ui,
+    /// Audio assets.
+    Audio,
+    /// Anything else.
+    Other,
+}
+
+/// An amount of memory, in bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MemoryUsage {
+    /// Bytes of main memory.
+    pub cpu: usize,
+    /// Bytes of graphics memory.
+    pub gpu: usize,
+}
+
+impl MemoryUsage {
+    /// Creates an amount of main memory.
+    pub fn cpu(cpu: usize) -> Self {
+        MemoryUsage { cpu, gpu: 0 }
+    }
+
+    /// Creates an amount of graphics memory.
+    pub fn gpu(gpu: usize) -> Self {
+        MemoryUsage { cpu: 0, gpu }
+    }
+
+    /// Returns the sum of the main and graphics memory.
+    pub fn total(&self) -> usize {
+        self.cpu + self.gpu
+    }
+
+    /// Returns the larger amounts of main and graphics memory of `self` and `other`.
+    pub fn max(self, other: MemoryUsage) -> Self {
+        MemoryUsage {
+            cpu: self.cpu.max(other.cpu),
+            gpu: self.gpu.max(other.gpu),
+        }
+    }
+}
+
+impl Add for MemoryUsage {
+    type Output = MemoryUsage;
+
+    fn add(self, other: MemoryUsage) -> MemoryUsage {
+        MemoryUsage {
+            cpu: self.cpu + other.cpu,
+            gpu: self.gpu + other.gpu,
+        }
+    }
+}
+
+impl AddAssign for MemoryUsage {
+    fn add_assign(&mut self, other: MemoryUsage) {
+        *self = *self + other;
+    }
+}
+
+impl fmt::Display for MemoryUsage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} CPU + {} GPU", Bytes(self.cpu), Bytes(self.gpu))
+    }
+}
+
+/// Formats an amount of bytes with a binary unit, like `1.5 MiB`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Bytes(pub usize);
+
+impl fmt::Display for Bytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+        if self.0 < 1024 {
+            return write!(f, "{} B", self.0);
+        }
+        let mut value = self.0 as f64 / 1024.;
+        let mut unit = 0;
+        while value >= 1024. && unit + 1 < UNITS.len() {
+            value /= 1024.;
+            unit += 1;
+        }
+        write!(f, "{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// Types which can report the memory they use, including what they own on the heap and on the
+/// graphics device.
+pub trait MemorySize {
+    /// Returns the memory used by `self`.
+    fn memory_usage(&self) -> MemoryUsage;
+}
+
+/// The memory used by one type of asset or component.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MemoryEntry {
+    /// The number of assets or components.
+    pub count: usize,
+    /// The memory they use.
+    pub usage: MemoryUsage,
+    /// The most memory they used since the entry was created.
+    pub peak: MemoryUsage,
+}
+
+/// Resource holding the memory used by the subsystems, by type of asset or component.
+#[derive(Debug, Default)]
+pub struct MemoryStats {
+    entries: BTreeMap<(MemoryCategory, &'static str), MemoryEntry>,
+    peak: MemoryUsage,
+}
+
+impl MemoryStats {
+    /// Creates empty stats.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Records the memory currently used by the `count` assets or components called `name`.
+    pub fn record(
+        &mut self,
+        category: MemoryCategory,
+        name: &'static str,
+        count: usize,
+        usage: MemoryUsage,
+    ) {
+        let entry = self.entries.entry((category, name)).or_default();
+        entry.count = count;
+        entry.usage = usage;
+        entry.peak = entry.peak.max(usage);
+        self.peak = self.peak.max(self.total());
+    }
+
+    /// Returns the memory used by the assets or components called `name`.
+    pub fn get(&self, category: MemoryCategory, name: &str) -> Option<&MemoryEntry> {
+        self.entries
+            .iter()
+            .find(|((c, n), _)| *c == category && *n == name)
+            .map(|(_, entry)| entry)
+    }
+
+    /// Iterates over the entries by category, then name.
+    pub fn entries(&self) -> impl Iterator<Item = (MemoryCategory, &'static str, &MemoryEntry)> {
+        self.entries
+            .iter()
+            .map(|(&(category, name), entry)| (category, name, entry))
+    }
+
+    /// Returns the memory used by a category.
+    pub fn category(&self, category: MemoryCategory) -> MemoryUsage {
+        self.entries()
+            .filter(|(c, _, _)| *c == category)
+            .fold(MemoryUsage::default(), |sum, (_, _, entry)| {
+                sum + entry.usage
+            })
+    }
+
+    /// Returns the memory used by all categories.
+    pub fn total(&self) -> MemoryUsage {
+        self.entries
+            .values()
+            .fold(MemoryUsage::default(), |sum, entry| sum + entry.usage)
+    }
+
+    /// Returns the most memory used by all categories together.
+    pub fn peak(&self) -> MemoryUsage {
+        self.peak
+    }
+
+    /// Returns the current and peak bytes allocated on the heap, if the `TrackingAllocator` is
+    /// the global allocator.
+    pub fn heap(&self) -> Option<(usize, usize)> {
+        if TrackingAllocator::is_installed() {
+            Some((TrackingAllocator::allocated(), TrackingAllocator::peak()))
+        } else {
+            None
+        }
+    }
+}
+
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+/// A global allocator counting the bytes allocated on the heap, for `MemoryStats::heap`.
+///
+/// It forwards to the system allocator.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TrackingAllocator;
+
+impl TrackingAllocator {
+    /// Returns whether the allocator is in use.
+    pub fn is_installed() -> bool {
+        INSTALLED.load(Ordering::Relaxed)
+    }
+
+    /// Returns the bytes currently allocated.
+    pub fn allocated() -> usize {
+        ALLOCATED.load(Ordering::Relaxed)
+    }
+
+    /// Returns the most bytes that were allocated at once.
+    pub fn peak() -> usize {
+        PEAK.load(Ordering::Relaxed)
+    }
+
+    fn added(size: usize) {
+        let allocated = ALLOCATED.fetch_add(size, Ordering::Relaxed) + size;
+        let mut peak = PEAK.load(Ordering::Relaxed);
+        while allocated > peak {
+            match PEAK.compare_exchange_weak(peak, allocated, Ordering::Relaxed, Ordering::Relaxed)
+            {
+                Ok(_) => break,
+                Err(current) => peak = current,
+            }
+        }
+    }
+}
+
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = SystemAlloc.alloc(layout);
+        if !ptr.is_null() {
+            INSTALLED.store(true, Ordering::Relaxed);
+            Self::added(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        SystemAlloc.dealloc(ptr, layout);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = SystemAlloc.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+            Self::added(new_size);
+        }
+        new_ptr
+    }
+}
+
+/// Records the memory used by the storage of a component in the `MemoryStats`.
+///
+/// Only the size of the components themselves is counted, not what they own on the heap.
+#[derive(Debug)]
+pub struct ComponentMemorySystem<T> {
+    category: MemoryCategory,
+    name: &'static str,
+    _marker: PhantomData<T>,
+}
+
+impl<T> ComponentMemorySystem<T> {
+    /// Creates a new system recording the components as `name` in the category.
+    pub fn new(category: MemoryCategory, name: &'static str) -> Self {
+        ComponentMemorySystem {
+            category,
+            name,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, T> System<'a> for ComponentMemorySystem<T>
+where
+    T: Component,
+{
+    type SystemData = (ReadStorage<'a, T>, Write<'a, MemoryStats>);
+
+    fn run(&mut self, (storage, mut stats): Self::SystemData) {
+        let count = storage.join().count();
+        let usage = MemoryUsage::cpu(count * mem::size_of::<T>());
+        stats.record(self.category, self.name, count, usage);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_keeps_high_water_marks() {
+        let mut stats = MemoryStats::new();
+        stats.record(MemoryCategory::Assets, "Texture", 2, MemoryUsage::gpu(800));
+        stats.record(MemoryCategory::Audio, "Source", 1, MemoryUsage::cpu(300));
+        stats.record(MemoryCategory::Assets, "Texture", 1, MemoryUsage::gpu(200));
+
+        let texture = stats.get(MemoryCategory::Assets, "Texture").unwrap();
+        assert_eq!(1, texture.count);
+        assert_eq!(MemoryUsage::gpu(200), texture.usage);
+        assert_eq!(MemoryUsage::gpu(800), texture.peak);
+        assert_eq!(MemoryUsage { cpu: 300, gpu: 200 }, stats.total());
+        assert_eq!(MemoryUsage { cpu: 300, gpu: 800 }, stats.peak());
+        assert_eq!(MemoryUsage::cpu(300), stats.category(MemoryCategory::Audio));
+    }
+
+    #[test]
+    fn bytes_use_binary_units() {
+        assert_eq!("512 B", Bytes(512).to_string());
+        assert_eq!("1.5 KiB", Bytes(1536).to_string());
+        assert_eq!("3.0 GiB", Bytes(3 << 30).to_string());
+    }
+}
@@ -0,0 +1,19 @@
+//! Game-wide pause flag.
+
+/// Resource telling whether the game is paused.
+///
+/// Systems that should stop while the game is paused, e.g. physics or AI, are made pausable with
+/// `system.pausable(PauseState::Running)`. Systems that aren't, like UI and audio, keep running.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PauseState {
+    /// The game is running.
+    Running,
+    /// The game is paused.
+    Paused,
+}
+
+impl Default for PauseState {
+    fn default() -> Self {
+        PauseState::Running
+    }
+}
@@ -0,0 +1,262 @@
+//! Deterministic, seedable random number generation.
+//!
+//! The `Rng` resource is created from a single seed. Systems fork their own named streams from
+//! it, so that the numbers one system draws don't depend on how many numbers other systems drew
+//! or in which order the systems ran. Running the game again with the same seed produces the
+//! same numbers, which is what deterministic replays rely on.
+//!
+//! The generator is xoshiro256**, implemented here so that the sequence for a seed never changes
+//! with dependency updates.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use fnv::FnvHashMap;
+use rand_core::{impls, Error as RandError, RngCore};
+
+/// Configuration for the `Rng` resource.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RngConfig {
+    /// Seed of the generator. If `None`, a seed is chosen from the system time and logged, so
+    /// that the run can be reproduced.
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+/// Random number resource with independent named streams.
+///
+/// # Examples
+///
+/// ```rust
+/// # extern crate amethyst;
+/// use amethyst::core::Rng;
+///
+/// let mut rng = Rng::new(42);
+/// let roll = rng.stream("loot").range_u32(1, 7);
+/// assert!(1 <= roll && roll < 7);
+///
+/// // Streams only depend on the seed and their name.
+/// assert_eq!(roll, Rng::new(42).stream("loot").range_u32(1, 7));
+/// ```
+///
+/// The state of all streams is serialized, so saving the `Rng` and loading it later, e.g. in a
+/// replay keyframe, continues the same sequences.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Rng {
+    seed: u64,
+    main: RngStream,
+    streams: FnvHashMap<String, RngStream>,
+}
+
+impl Rng {
+    /// Creates a new `Rng` with the given seed.
+    pub fn new(seed: u64) -> Self {
+        Rng {
+            seed,
+            main: RngStream::new(seed),
+            streams: FnvHashMap::default(),
+        }
+    }
+
+    /// Creates a new `Rng` from the configuration.
+    pub fn from_config(config: &RngConfig) -> Self {
+        let seed = config.seed.unwrap_or_else(|| {
+            let seed = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs() ^ (u64::from(d.subsec_nanos()) << 32))
+                .unwrap_or(0);
+            log::info!("Using random seed {}", seed);
+            seed
+        });
+        Rng::new(seed)
+    }
+
+    /// Returns the seed this `Rng` was created with.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns the main stream, shared by everything that doesn't need its own stream.
+    pub fn main(&mut self) -> &mut RngStream {
+        &mut self.main
+    }
+
+    /// Returns the stream with the given name, creating it on first use.
+    ///
+    /// The stream keeps its position across frames. Use one name per system, e.g. the system's
+    /// name in the dispatcher.
+    pub fn stream(&mut self, name: &str) -> &mut RngStream {
+        let seed = self.seed;
+        self.streams
+            .entry(name.to_string())
+            .or_insert_with(|| RngStream::new(seed ^ name_hash(name)))
+    }
+
+    /// Creates a new stream with the given name, starting from the beginning of its sequence.
+    ///
+    /// Unlike `stream`, the returned stream is owned by the caller and isn't stored in the
+    /// resource, which is useful for systems that keep their stream as a field.
+    pub fn fork(&self, name: &str) -> RngStream {
+        RngStream::new(self.seed ^ name_hash(name))
+    }
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Rng::from_config(&RngConfig::default())
+    }
+}
+
+/// A single deterministic random number sequence.
+///
+/// Implements `rand_core::RngCore`, so it can be used with the distributions of the `rand` crate.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RngStream {
+    state: [u64; 4],
+}
+
+impl RngStream {
+    /// Creates a new stream from a seed.
+    pub fn new(seed: u64) -> Self {
+        // Expand the seed with SplitMix64, as recommended for seeding xoshiro generators. This
+        // makes the invalid all-zero state practically impossible.
+        let mut x = seed;
+        let mut next = || {
+            x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = x;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^ (z >> 31)
+        };
+        RngStream {
+            state: [next(), next(), next(), next()],
+        }
+    }
+
+    /// Returns a uniformly distributed `u64`.
+    pub fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+
+    /// Returns a uniformly distributed `u32`.
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    /// Returns a uniformly distributed `f32` in `[0, 1)`.
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u32 << 24) as f32
+    }
+
+    /// Returns a uniformly distributed `f64` in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns a uniformly distributed `u32` in `[low, high)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `low >= high`.
+    pub fn range_u32(&mut self, low: u32, high: u32) -> u32 {
+        assert!(low < high, "`low` must be less than `high`");
+        let range = u64::from(high - low);
+        // Reject the top of the range that would bias the result towards low numbers.
+        let zone = u64::max_value() - u64::max_value() % range;
+        loop {
+            let value = self.next_u64();
+            if value < zone {
+                return low + (value % range) as u32;
+            }
+        }
+    }
+
+    /// Returns a uniformly distributed `f32` in `[low, high)`.
+    pub fn range_f32(&mut self, low: f32, high: f32) -> f32 {
+        low + (high - low) * self.next_f32()
+    }
+
+    /// Returns `true` with the given probability.
+    pub fn chance(&mut self, probability: f64) -> bool {
+        self.next_f64() < probability
+    }
+
+    /// Shuffles a slice in place.
+    pub fn shuffle<T>(&mut self, slice: &mut [T]) {
+        for i in (1..slice.len()).rev() {
+            let j = self.range_u32(0, i as u32 + 1) as usize;
+            slice.swap(i, j);
+        }
+    }
+}
+
+impl RngCore for RngStream {
+    fn next_u32(&mut self) -> u32 {
+        RngStream::next_u32(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        RngStream::next_u64(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), RandError> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// FNV-1a hash of a stream name. Unlike `std`'s hashers it is guaranteed to never change.
+fn name_hash(name: &str) -> u64 {
+    name.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Rng, RngStream};
+
+    #[test]
+    fn same_seed_produces_same_sequence() {
+        let mut a = RngStream::new(7);
+        let mut b = RngStream::new(7);
+        for _ in 0..100 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+    }
+
+    #[test]
+    fn streams_are_independent_of_each_other() {
+        let mut rng = Rng::new(7);
+        rng.stream("other").next_u64();
+        let first = rng.stream("physics").next_u64();
+
+        let mut fresh = Rng::new(7);
+        assert_eq!(first, fresh.stream("physics").next_u64());
+        assert_eq!(Rng::new(7).fork("physics").next_u64(), first);
+        assert_ne!(first, Rng::new(7).fork("ai").next_u64());
+    }
+
+    #[test]
+    fn ranges_stay_in_bounds() {
+        let mut stream = RngStream::new(1);
+        for _ in 0..1000 {
+            let value = stream.range_u32(3, 9);
+            assert!(3 <= value && value < 9);
+            let value = stream.next_f32();
+            assert!(0. <= value && value < 1.);
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! Cleanup handlers that run when the application shuts down.
+
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use specs::World;
+
+/// Result of a single call of a shutdown handler.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ShutdownStatus {
+    /// The handler finished its cleanup.
+    Done,
+    /// The handler is still waiting for something, e.g. a file write or a network session to
+    /// close, and wants to be called again.
+    Pending,
+}
+
+type ShutdownHandler = Box<dyn FnMut(&mut World) -> ShutdownStatus + Send + Sync>;
+
+/// Resource collecting cleanup handlers, which run after the last state stopped, but before the
+/// world is dropped.
+///
+/// Systems usually register their handlers in `System::setup`. Handlers are called repeatedly
+/// until they return `ShutdownStatus::Done`, or the shutdown timeout of the application elapsed,
+/// which is set with `ApplicationBuilder::with_shutdown_timeout`.
+///
+/// # Examples
+///
+/// ```rust
+/// # extern crate amethyst_core;
+/// use std::time::Duration;
+///
+/// use amethyst_core::{
+///     specs::World,
+///     ShutdownHandlers, ShutdownStatus,
+/// };
+///
+/// let mut world = World::new();
+/// let mut handlers = ShutdownHandlers::default();
+/// handlers.add("flush_save", |_: &mut World| {
+///     // Write the save game here.
+///     ShutdownStatus::Done
+/// });
+/// assert!(handlers.run(&mut world, Duration::from_secs(1)));
+/// ```
+#[derive(Default)]
+pub struct ShutdownHandlers {
+    handlers: Vec<(String, ShutdownHandler)>,
+}
+
+impl ShutdownHandlers {
+    /// Adds a cleanup handler. The name is used to report handlers that didn't finish in time.
+    pub fn add<F>(&mut self, name: &str, handler: F)
+    where
+        F: FnMut(&mut World) -> ShutdownStatus + Send + Sync + 'static,
+    {
+        self.handlers.push((name.to_string(), Box::new(handler)));
+    }
+
+    /// Returns the number of registered handlers.
+    pub fn len(&self) -> usize {
+        self.handlers.len()
+    }
+
+    /// Checks whether no handlers are registered.
+    pub fn is_empty(&self) -> bool {
+        self.handlers.is_empty()
+    }
+
+    /// Calls the handlers in the order they were added until all of them are done, or the
+    /// timeout elapsed. Every handler is called at least once.
+    ///
+    /// Returns `false` if some handlers didn't finish in time.
+    pub fn run(&mut self, world: &mut World, timeout: Duration) -> bool {
+        let start = Instant::now();
+        loop {
+            let mut i = 0;
+            while i < self.handlers.len() {
+                if (self.handlers[i].1)(world) == ShutdownStatus::Done {
+                    let _ = self.handlers.remove(i);
+                } else {
+                    i += 1;
+                }
+            }
+            if self.handlers.is_empty() {
+                return true;
+            }
+            if start.elapsed() >= timeout {
+                for (name, _) in self.handlers.drain(..) {
+                    log::warn!("Shutdown handler `{}` didn't finish in time", name);
+                }
+                return false;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pending_handlers_are_called_again_until_timeout() {
+        let mut world = World::new();
+        let mut handlers = ShutdownHandlers::default();
+        let mut calls = 0;
+        handlers.add("twice", move |_: &mut World| {
+            calls += 1;
+            if calls < 2 {
+                ShutdownStatus::Pending
+            } else {
+                ShutdownStatus::Done
+            }
+        });
+        assert!(handlers.run(&mut world, Duration::from_secs(1)));
+        assert!(handlers.is_empty());
+
+        handlers.add("never", |_: &mut World| ShutdownStatus::Pending);
+        assert!(!handlers.run(&mut world, Duration::from_millis(5)));
+        assert!(handlers.is_empty());
+    }
+}
@@ -3,7 +3,9 @@
 //! This modules contains an extension trait for the System trait which adds useful transformation
 //! functions.
 
-use shred::{RunningTime, SystemData};
+use std::marker::PhantomData;
+
+use shred::{Resource, Resources, RunningTime, SystemData};
 use specs::prelude::{Read, System};
 
 /// Extension functionality associated systems.
@@ -76,6 +78,61 @@ pub trait SystemExt {
     where
         Self: Sized,
         V: Send + Sync + Default + PartialEq;
+
+    /// Make a system only run while the given criteria are met.
+    ///
+    /// The criteria read their own system data, which is fetched together with the data of the
+    /// system, so both are accounted for when the dispatcher schedules systems. The same note
+    /// about event channels as for [`pausable`](#tymethod.pausable) applies.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # extern crate amethyst;
+    /// use amethyst::{
+    ///     core::ResourceCriteria,
+    ///     ecs::{System, Write},
+    ///     shred::DispatcherBuilder,
+    ///     prelude::*,
+    /// };
+    ///
+    /// #[derive(Default)]
+    /// struct Settings {
+    ///     physics: bool,
+    /// }
+    ///
+    /// struct AddNumber(u32);
+    ///
+    /// impl<'s> System<'s> for AddNumber {
+    ///     type SystemData = Write<'s, u32>;
+    ///
+    ///     fn run(&mut self, mut number: Self::SystemData) {
+    ///         *number += self.0;
+    ///     }
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.add_resource(Settings { physics: false });
+    ///
+    /// let mut dispatcher = DispatcherBuilder::default()
+    ///     .with(
+    ///         AddNumber(1).run_if(ResourceCriteria::new(|settings: &Settings| settings.physics)),
+    ///         "physics",
+    ///         &[],
+    ///     )
+    ///     .build();
+    /// dispatcher.setup(&mut world.res);
+    ///
+    /// dispatcher.dispatch(&mut world.res);
+    /// assert_eq!(0, *world.read_resource::<u32>());
+    ///
+    /// world.write_resource::<Settings>().physics = true;
+    /// dispatcher.dispatch(&mut world.res);
+    /// assert_eq!(1, *world.read_resource::<u32>());
+    /// ```
+    fn run_if<C>(self, criteria: C) -> RunIf<Self, C>
+    where
+        Self: Sized;
 }
 
 impl<'s, S> SystemExt for S
@@ -92,6 +149,16 @@ where
             value,
         }
     }
+
+    fn run_if<C>(self, criteria: C) -> RunIf<Self, C>
+    where
+        Self: Sized,
+    {
+        RunIf {
+            system: self,
+            criteria,
+        }
+    }
 }
 
 /// A system that is enabled when `V` has a specific value.
@@ -123,4 +190,132 @@ where
     fn running_time(&self) -> RunningTime {
         self.system.running_time()
     }
+
+    fn setup(&mut self, res: &mut Resources) {
+        Read::<V>::setup(res);
+        self.system.setup(res);
+    }
+}
+
+/// Condition deciding whether a system runs, see [`SystemExt::run_if`].
+///
+/// [`SystemExt::run_if`]: trait.SystemExt.html#tymethod.run_if
+pub trait RunCriteria<'s> {
+    /// The data the criteria need, fetched like the data of a system.
+    type SystemData: SystemData<'s>;
+
+    /// Returns `true` if the system should run this frame.
+    fn should_run(&mut self, data: Self::SystemData) -> bool;
+}
+
+/// A system that only runs while its criteria are met.
+///
+/// This is created using the [`SystemExt::run_if`] method.
+///
+/// [`SystemExt::run_if`]: trait.SystemExt.html#tymethod.run_if
+pub struct RunIf<S, C> {
+    system: S,
+    criteria: C,
+}
+
+impl<'s, S, C> System<'s> for RunIf<S, C>
+where
+    S: System<'s>,
+    S::SystemData: SystemData<'s>,
+    C: RunCriteria<'s>,
+{
+    type SystemData = (C::SystemData, S::SystemData);
+
+    fn run(&mut self, (criteria, data): Self::SystemData) {
+        if self.criteria.should_run(criteria) {
+            self.system.run(data);
+        }
+    }
+
+    fn running_time(&self) -> RunningTime {
+        self.system.running_time()
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        C::SystemData::setup(res);
+        self.system.setup(res);
+    }
+}
+
+/// Criteria that run a system if a function of a resource returns `true`.
+///
+/// The system doesn't run while the resource is missing.
+pub struct ResourceCriteria<R, F> {
+    condition: F,
+    marker: PhantomData<fn(&R)>,
+}
+
+impl<R, F> ResourceCriteria<R, F>
+where
+    R: Resource,
+    F: FnMut(&R) -> bool,
+{
+    /// Creates criteria from a function of the resource.
+    pub fn new(condition: F) -> Self {
+        ResourceCriteria {
+            condition,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'s, R, F> RunCriteria<'s> for ResourceCriteria<R, F>
+where
+    R: Resource,
+    F: FnMut(&R) -> bool,
+{
+    type SystemData = Option<Read<'s, R>>;
+
+    fn should_run(&mut self, resource: Self::SystemData) -> bool {
+        match resource {
+            Some(resource) => (self.condition)(&resource),
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use specs::prelude::{RunNow, World};
+
+    #[derive(Default)]
+    struct Count(u32);
+
+    struct InsertsCount;
+
+    impl<'s> System<'s> for InsertsCount {
+        type SystemData = ();
+
+        fn run(&mut self, _: ()) {}
+
+        fn setup(&mut self, res: &mut Resources) {
+            res.insert(Count(1));
+        }
+    }
+
+    #[test]
+    fn pausable_forwards_setup() {
+        use crate::PauseState;
+
+        let mut world = World::new();
+        let mut system = InsertsCount.pausable(PauseState::Running);
+        RunNow::setup(&mut system, &mut world.res);
+        assert_eq!(1, world.read_resource::<Count>().0);
+        assert_eq!(PauseState::Running, *world.read_resource::<PauseState>());
+    }
+
+    #[test]
+    fn run_if_forwards_setup() {
+        let mut world = World::new();
+        let mut system = InsertsCount.run_if(ResourceCriteria::new(|count: &Count| count.0 > 0));
+        RunNow::setup(&mut system, &mut world.res);
+        assert_eq!(1, world.read_resource::<Count>().0);
+    }
 }
@@ -19,6 +19,8 @@ pub struct Time {
     fixed_time: Duration,
     /// Time at which `State::fixed_update` was last called.
     pub last_fixed_update: Instant,
+    /// Progress of the current frame between the last and the next fixed update.
+    fixed_alpha: f32,
     /// The total number of frames that have been played in this session.
     frame_number: u64,
     ///Time elapsed since game start, ignoring the speed multipler.
@@ -60,6 +62,14 @@ impl Time {
         self.fixed_time
     }
 
+    /// Gets how far the current frame is between the last and the next fixed update, from 0 to 1.
+    ///
+    /// All fixed updates of a frame run before its variable update, so render-side systems can
+    /// interpolate between the two most recent fixed steps, e.g. `previous.lerp(current, alpha)`.
+    pub fn fixed_alpha(&self) -> f32 {
+        self.fixed_alpha
+    }
+
     /// Gets the current frame number.  This increments by 1 every frame.  There is no frame 0.
     pub fn frame_number(&self) -> u64 {
         self.frame_number
@@ -156,6 +166,14 @@ impl Time {
         self.time_scale = multiplier;
     }
 
+    /// Sets the progress between the last and the next fixed update, clamped to `[0, 1]`.
+    ///
+    /// This should only be called by the engine.  Bad things might happen if you call this in
+    /// your game.
+    pub fn set_fixed_alpha(&mut self, alpha: f32) {
+        self.fixed_alpha = alpha.max(0.).min(1.);
+    }
+
     /// Indicates a fixed update just finished.
     ///
     /// This should only be called by the engine.  Bad things might happen if you call this in
@@ -175,6 +193,7 @@ impl Default for Time {
             fixed_seconds: duration_to_secs(Duration::new(0, 16_666_666)),
             fixed_time: Duration::new(0, 16_666_666),
             last_fixed_update: Instant::now(),
+            fixed_alpha: 0.0,
             frame_number: 0,
             absolute_real_time: Duration::default(),
             absolute_time: Duration::default(),
@@ -254,9 +273,34 @@ impl Stopwatch {
 // Unit tests
 #[cfg(test)]
 mod tests {
-    use std::{thread, time::Duration};
+    use std::{
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use super::{duration_to_secs, Stopwatch, Time};
+
+    #[test]
+    fn fixed_updates_catch_up_and_leave_alpha() {
+        let mut time = Time::default();
+        time.set_fixed_time(Duration::from_millis(100));
+        time.last_fixed_update = Instant::now() - Duration::from_millis(250);
+
+        let mut fixed_updates = 0;
+        while time.last_fixed_update().elapsed() >= time.fixed_time() {
+            time.finish_fixed_update();
+            fixed_updates += 1;
+        }
+        assert_eq!(2, fixed_updates);
 
-    use super::Stopwatch;
+        let alpha = duration_to_secs(time.last_fixed_update().elapsed()) / time.fixed_seconds();
+        time.set_fixed_alpha(alpha);
+        assert!(time.fixed_alpha() >= 0.5 && time.fixed_alpha() < 1.);
+        time.set_fixed_alpha(1.5);
+        assert!((time.fixed_alpha() - 1.).abs() < 1.0e-6);
+        time.set_fixed_alpha(-0.5);
+        assert!(time.fixed_alpha().abs() < 1.0e-6);
+    }
 
     #[test]
     fn elapsed() {
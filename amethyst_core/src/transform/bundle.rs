@@ -5,6 +5,7 @@ use specs_hierarchy::HierarchySystem;
 
 use crate::{
     bundle::{Result, SystemBundle},
+    memory::{ComponentMemorySystem, MemoryCategory},
     transform::*,
 };
 
@@ -12,6 +13,7 @@ use crate::{
 ///
 /// Will register transform components, and the `TransformSystem`.
 /// `TransformSystem` will be registered with name "transform_system".
+/// The memory of the transforms is recorded in the `MemoryStats`.
 ///
 /// ## Errors
 ///
@@ -51,6 +53,16 @@ impl<'a, 'b, 'c> SystemBundle<'a, 'b> for TransformBundle<'c> {
             "transform_system",
             &["parent_hierarchy_system"],
         );
+        builder.add(
+            ComponentMemorySystem::<Transform>::new(MemoryCategory::Ecs, "Transform"),
+            "transform_memory",
+            &[],
+        );
+        builder.add(
+            ComponentMemorySystem::<GlobalTransform>::new(MemoryCategory::Ecs, "GlobalTransform"),
+            "global_transform_memory",
+            &[],
+        );
         Ok(())
     }
 }
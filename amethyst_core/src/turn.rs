@@ -0,0 +1,309 @@
+//! Turn-based scheduling alongside the real-time loop.
+
+use std::marker::PhantomData;
+
+use shrev::EventChannel;
+use specs::prelude::{Component, Entities, Entity, Read, ReadStorage, System, Write};
+
+use crate::system_ext::RunCriteria;
+
+/// How the `TurnQueue` orders the actors.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TurnMode {
+    /// Every actor acts once per round, from the highest initiative down.
+    Initiative,
+    /// Actors gain their speed as energy in every round, and act while they have at least the
+    /// threshold, the one with the most energy first. Ending a turn spends its cost.
+    Energy {
+        /// The energy needed to act, and the default cost of a turn.
+        threshold: f32,
+    },
+}
+
+impl Default for TurnMode {
+    fn default() -> Self {
+        TurnMode::Initiative
+    }
+}
+
+#[derive(Clone, Debug)]
+struct TurnActor {
+    entity: Entity,
+    /// The initiative or the speed, following the mode.
+    value: f32,
+    energy: f32,
+}
+
+/// Resource scheduling the turns of the actors of a turn-based game.
+///
+/// Actors are ordered by initiative in rounds, or by the energy they gain with their speed.
+/// Turns don't depend on `Time`: a turn lasts until `end_turn` is called, e.g. by the system
+/// moving the player once they picked an action, and the `TurnSystem` then starts the turn of the
+/// next actor. Systems acting for an actor are gated to its turns with
+/// `system.run_if(TurnCriteria::<Player>::new())`.
+#[derive(Clone, Debug, Default)]
+pub struct TurnQueue {
+    mode: TurnMode,
+    actors: Vec<TurnActor>,
+    /// The actors still to act in this round, in `Initiative` mode, last first.
+    waiting: Vec<Entity>,
+    current: Option<Entity>,
+    round: u32,
+    ending: Option<Option<f32>>,
+}
+
+impl TurnQueue {
+    /// Creates a queue without actors.
+    pub fn new(mode: TurnMode) -> Self {
+        TurnQueue {
+            mode,
+            ..Default::default()
+        }
+    }
+
+    /// Adds an actor with its initiative, or its speed in `Energy` mode. Adding an actor again
+    /// changes its value.
+    pub fn add_actor(&mut self, entity: Entity, value: f32) {
+        match self.actors.iter_mut().find(|actor| actor.entity == entity) {
+            Some(actor) => actor.value = value,
+            None => self.actors.push(TurnActor {
+                entity,
+                value,
+                energy: 0.,
+            }),
+        }
+    }
+
+    /// Removes the actor, ending its turn if it's acting.
+    pub fn remove_actor(&mut self, entity: Entity) {
+        self.actors.retain(|actor| actor.entity != entity);
+        self.waiting.retain(|&waiting| waiting != entity);
+        if self.current == Some(entity) {
+            self.current = None;
+            self.ending = None;
+        }
+    }
+
+    /// Returns the actor whose turn it is.
+    pub fn current(&self) -> Option<Entity> {
+        self.current
+    }
+
+    /// Returns whether it's the turn of the entity.
+    pub fn is_turn_of(&self, entity: Entity) -> bool {
+        self.current == Some(entity)
+    }
+
+    /// Returns the round, counted from 1 once the first turn started.
+    pub fn round(&self) -> u32 {
+        self.round
+    }
+
+    /// Returns the energy of the actor in `Energy` mode.
+    pub fn energy(&self, entity: Entity) -> Option<f32> {
+        self.actors
+            .iter()
+            .find(|actor| actor.entity == entity)
+            .map(|actor| actor.energy)
+    }
+
+    /// Ends the turn of the current actor; the `TurnSystem` starts the next one.
+    pub fn end_turn(&mut self) {
+        self.ending = Some(None);
+    }
+
+    /// Ends the turn of the current actor, spending that much energy in `Energy` mode, e.g. less
+    /// for a quick action.
+    pub fn end_turn_with_cost(&mut self, cost: f32) {
+        self.ending = Some(Some(cost));
+    }
+
+    /// Ends the turn that was ended and starts the next one, if there's no current turn.
+    fn advance(&mut self, events: &mut Vec<TurnEvent>) {
+        if let (Some(cost), Some(entity)) = (self.ending.take(), self.current) {
+            if let TurnMode::Energy { threshold } = self.mode {
+                if let Some(actor) = self.actors.iter_mut().find(|a| a.entity == entity) {
+                    actor.energy -= cost.unwrap_or(threshold);
+                }
+            }
+            self.current = None;
+            events.push(TurnEvent::Ended { entity });
+        }
+        if self.current.is_some() || self.actors.is_empty() {
+            return;
+        }
+
+        let next = match self.mode {
+            TurnMode::Initiative => {
+                if self.waiting.is_empty() {
+                    self.start_round(events);
+                    let mut actors = self.actors.clone();
+                    // Stable, so actors with the same initiative act in the order they were added.
+                    actors.sort_by(|a, b| {
+                        b.value
+                            .partial_cmp(&a.value)
+                            .unwrap_or(std::cmp::Ordering::Equal)
+                    });
+                    self.waiting = actors.iter().rev().map(|actor| actor.entity).collect();
+                }
+                self.waiting.pop()
+            }
+            TurnMode::Energy { threshold } => {
+                if self.actors.iter().all(|actor| actor.value <= 0.) {
+                    log::warn!("No actor with speed in the turn queue, no turn can start");
+                    return;
+                }
+                loop {
+                    let ready = self
+                        .actors
+                        .iter()
+                        .filter(|actor| actor.energy >= threshold)
+                        .fold(None, |best: Option<&TurnActor>, actor| match best {
+                            Some(best) if best.energy >= actor.energy => Some(best),
+                            _ => Some(actor),
+                        });
+                    if let Some(actor) = ready {
+                        break Some(actor.entity);
+                    }
+                    self.start_round(events);
+                    for actor in &mut self.actors {
+                        actor.energy += actor.value.max(0.);
+                    }
+                }
+            }
+        };
+        if let Some(entity) = next {
+            self.current = Some(entity);
+            events.push(TurnEvent::Started {
+                entity,
+                round: self.round,
+            });
+        }
+    }
+
+    fn start_round(&mut self, events: &mut Vec<TurnEvent>) {
+        self.round += 1;
+        events.push(TurnEvent::RoundStarted { round: self.round });
+    }
+}
+
+/// Event sent by the `TurnSystem` on an `EventChannel<TurnEvent>`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TurnEvent {
+    /// A round started, before the first turn in it.
+    RoundStarted {
+        /// The round.
+        round: u32,
+    },
+    /// The turn of the actor started.
+    Started {
+        /// The actor.
+        entity: Entity,
+        /// The round of the turn.
+        round: u32,
+    },
+    /// The turn of the actor ended.
+    Ended {
+        /// The actor.
+        entity: Entity,
+    },
+}
+
+/// Removes the dead actors from the `TurnQueue`, ends the turns that were ended and starts the
+/// next ones, sending `TurnEvent`s.
+#[derive(Default)]
+pub struct TurnSystem;
+
+impl<'a> System<'a> for TurnSystem {
+    type SystemData = (
+        Entities<'a>,
+        Write<'a, TurnQueue>,
+        Write<'a, EventChannel<TurnEvent>>,
+    );
+
+    fn run(&mut self, (entities, mut queue, mut channel): Self::SystemData) {
+        let dead = queue
+            .actors
+            .iter()
+            .map(|actor| actor.entity)
+            .filter(|&entity| !entities.is_alive(entity))
+            .collect::<Vec<_>>();
+        for entity in dead {
+            queue.remove_actor(entity);
+        }
+        let mut events = Vec::new();
+        queue.advance(&mut events);
+        channel.iter_write(events);
+    }
+}
+
+/// Criteria running a system only during the turns of actors with the component `T`, e.g. a
+/// `Player` marker for the systems handling the input of the player.
+///
+/// See [`SystemExt::run_if`](trait.SystemExt.html#tymethod.run_if).
+pub struct TurnCriteria<T> {
+    marker: PhantomData<T>,
+}
+
+impl<T> TurnCriteria<T> {
+    /// Creates the criteria.
+    pub fn new() -> Self {
+        TurnCriteria {
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Default for TurnCriteria<T> {
+    fn default() -> Self {
+        TurnCriteria::new()
+    }
+}
+
+impl<'s, T: Component> RunCriteria<'s> for TurnCriteria<T> {
+    type SystemData = (Read<'s, TurnQueue>, ReadStorage<'s, T>);
+
+    fn should_run(&mut self, (queue, actors): Self::SystemData) -> bool {
+        queue
+            .current()
+            .map_or(false, |entity| actors.contains(entity))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use specs::prelude::{Builder, World};
+
+    #[test]
+    fn initiative_orders_rounds_and_energy_lets_fast_actors_act_more() {
+        let mut world = World::new();
+        let slow = world.create_entity().build();
+        let fast = world.create_entity().build();
+        let mut events = Vec::new();
+
+        let mut queue = TurnQueue::new(TurnMode::Initiative);
+        queue.add_actor(slow, 5.);
+        queue.add_actor(fast, 12.);
+        let mut turns = Vec::new();
+        for _ in 0..4 {
+            queue.advance(&mut events);
+            turns.push(queue.current().unwrap());
+            queue.end_turn();
+        }
+        assert_eq!(vec![fast, slow, fast, slow], turns);
+        assert_eq!(2, queue.round());
+
+        let mut queue = TurnQueue::new(TurnMode::Energy { threshold: 10. });
+        queue.add_actor(slow, 4.);
+        queue.add_actor(fast, 10.);
+        let mut turns = Vec::new();
+        for _ in 0..3 {
+            queue.advance(&mut events);
+            turns.push(queue.current().unwrap());
+            queue.end_turn();
+        }
+        assert_eq!(vec![fast, fast, slow], turns);
+    }
+}
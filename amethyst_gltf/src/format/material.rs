@@ -7,8 +7,8 @@ use itertools::Itertools;
 use crate::{
     assets::Source,
     renderer::{
-        JpgFormat, MaterialPrefab, PngFormat, TextureData, TextureFormat, TextureMetadata,
-        TexturePrefab,
+        CullMode, JpgFormat, MaterialPrefab, PngFormat, TextureData, TextureFormat,
+        TextureMetadata, TexturePrefab,
     },
 };
 
@@ -111,6 +111,9 @@ pub fn load_material(
     if let AlphaMode::Mask = material.alpha_mode() {
         prefab.alpha_cutoff = material.alpha_cutoff();
     }
+    if material.double_sided() {
+        prefab.cull_mode = CullMode::None;
+    }
 
     Ok(prefab)
 }
@@ -197,11 +200,20 @@ fn load_texture(
 
 fn load_sampler_info(sampler: &gltf::texture::Sampler<'_>) -> SamplerInfo {
     use gfx::texture::{FilterMethod, WrapMode};
-    use gltf::texture::{MagFilter, WrappingMode};
-    // gfx only have support for a single filter, therefore we use mag filter, and ignore min filter
-    let filter = match sampler.mag_filter() {
-        None | Some(MagFilter::Nearest) => FilterMethod::Scale,
-        Some(MagFilter::Linear) => FilterMethod::Bilinear,
+    use gltf::texture::{MagFilter, MinFilter, WrappingMode};
+    // gfx only have support for a single filter, therefore we use mag filter, and only check
+    // whether the min filter blends the mip levels
+    let filter = match (sampler.mag_filter(), sampler.min_filter()) {
+        (None, _) | (Some(MagFilter::Nearest), None) => FilterMethod::Scale,
+        (Some(MagFilter::Nearest), Some(min)) => match min {
+            MinFilter::Nearest | MinFilter::Linear => FilterMethod::Scale,
+            _ => FilterMethod::Mipmap,
+        },
+        (Some(MagFilter::Linear), Some(MinFilter::LinearMipmapLinear))
+        | (Some(MagFilter::Linear), Some(MinFilter::NearestMipmapLinear)) => {
+            FilterMethod::Trilinear
+        }
+        (Some(MagFilter::Linear), _) => FilterMethod::Bilinear,
     };
     let wrap_s = match sampler.wrap_s() {
         WrappingMode::ClampToEdge => WrapMode::Clamp,
@@ -2,7 +2,7 @@
 
 use std::{borrow::Borrow, hash::Hash};
 
-use fnv::FnvHashMap as HashMap;
+use fnv::{FnvHashMap as HashMap, FnvHashSet as HashSet};
 use smallvec::SmallVec;
 
 use super::{Axis, Button};
@@ -29,7 +29,8 @@ use super::{Axis, Button};
 ///     actions: {
 ///         "fire": [ [Mouse(Left)], [Key(X)] ], // Multiple bindings for one action
 ///         "reload": [ [Key(LControl), Key(R)] ] // Combinations of multiple bindings possible
-///     }
+///     },
+///     toggles: ["crouch"], // Optional, actions switched on and off by pressing them
 /// )
 /// ```
 #[derive(Derivative, Serialize, Deserialize, Clone)]
@@ -45,6 +46,10 @@ where
     /// So for example if you want to quit by either "Esc" or "Ctrl+q" you would have
     /// `[[Esc], [Ctrl, Q]]`.
     pub(super) actions: HashMap<AC, SmallVec<[SmallVec<[Button; 2]>; 4]>>,
+    /// Actions that are switched on by pressing them and off by pressing them again, instead of
+    /// being held down.
+    #[serde(default = "HashSet::default")]
+    pub(super) toggles: HashSet<AC>,
 }
 
 impl<AX, AC> Bindings<AX, AC>
@@ -56,6 +61,14 @@ where
     pub fn new() -> Self {
         Default::default()
     }
+
+    /// Returns whether the action is switched on and off by pressing it, see `set_toggle`.
+    pub fn is_toggle<A: Hash + Eq + ?Sized>(&self, id: &A) -> bool
+    where
+        AC: Borrow<A>,
+    {
+        self.toggles.contains(id)
+    }
 }
 
 impl<AX, AC> Bindings<AX, AC>
@@ -158,4 +171,18 @@ where
     pub fn actions(&self) -> Vec<AC> {
         self.actions.keys().cloned().collect::<Vec<AC>>()
     }
+
+    /// Makes an action toggle, so pressing it switches it on and pressing it again switches it
+    /// off, for players who can't hold buttons down.
+    ///
+    /// `InputEvent::ActionPressed` is sent when it's switched on, `InputEvent::ActionReleased`
+    /// when it's switched off, and `InputHandler::action_is_down` returns whether it's on.
+    pub fn set_toggle<A: Into<AC>>(&mut self, id: A, toggle: bool) {
+        let id = id.into();
+        if toggle {
+            self.toggles.insert(id);
+        } else {
+            self.toggles.remove(&id);
+        }
+    }
 }
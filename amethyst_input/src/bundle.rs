@@ -9,7 +9,7 @@ use amethyst_core::{
     specs::prelude::DispatcherBuilder,
 };
 
-use crate::{Bindings, InputSystem};
+use crate::{Bindings, InputSystem, VirtualCursor, VirtualCursorSystem};
 
 #[cfg(feature = "sdl_controller")]
 use crate::sdl_events_system::ControllerMappings;
@@ -38,6 +38,7 @@ where
     AC: Hash + Eq,
 {
     bindings: Option<Bindings<AX, AC>>,
+    virtual_cursor: Option<VirtualCursor>,
     #[cfg(feature = "sdl_controller")]
     controller_mappings: Option<ControllerMappings>,
 }
@@ -67,6 +68,12 @@ where
         Ok(self.with_bindings(Bindings::load_no_fallback(file)?))
     }
 
+    /// Move the mouse pointer with the right stick of a controller, see `VirtualCursorSystem`
+    pub fn with_virtual_cursor(mut self, cursor: VirtualCursor) -> Self {
+        self.virtual_cursor = Some(cursor);
+        self
+    }
+
     /// Load SDL controller mappings from file
     #[cfg(feature = "sdl_controller")]
     pub fn with_sdl_controller_mappings(mut self, mappings: String) -> Self {
@@ -108,6 +115,13 @@ where
             "input_system",
             &[],
         );
+        if let Some(cursor) = self.virtual_cursor {
+            builder.add(
+                VirtualCursorSystem::<AX, AC>::new(cursor),
+                "virtual_cursor_system",
+                &["input_system"],
+            );
+        }
         Ok(())
     }
 }
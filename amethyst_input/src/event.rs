@@ -50,6 +50,33 @@ pub enum InputEvent<T> {
         /// The amount the mouse moved vertically.
         delta_y: f64,
     },
+    /// A finger touched the screen.
+    TouchStarted {
+        /// The id of the finger, the same until it is lifted.
+        id: u64,
+        /// The position of the finger in pixels.
+        position: (f64, f64),
+    },
+    /// A finger moved on the screen.
+    TouchMoved {
+        /// The id of the finger.
+        id: u64,
+        /// The new position of the finger in pixels.
+        position: (f64, f64),
+    },
+    /// A finger was lifted from the screen.
+    TouchEnded {
+        /// The id of the finger, which might be reused by the next touch.
+        id: u64,
+        /// The last position of the finger in pixels.
+        position: (f64, f64),
+    },
+    /// The OS took over a touch, e.g. for a system gesture. It should be treated as if it never
+    /// happened.
+    TouchCancelled {
+        /// The id of the finger.
+        id: u64,
+    },
     /// The mousewheel was moved in either direction
     MouseWheelMoved(ScrollDirection),
     /// A controller Axis was moved.
@@ -5,7 +5,7 @@ use std::{borrow::Borrow, hash::Hash};
 use smallvec::SmallVec;
 use winit::{
     dpi::LogicalPosition, DeviceEvent, ElementState, Event, KeyboardInput, MouseButton,
-    MouseScrollDelta, VirtualKeyCode, WindowEvent,
+    MouseScrollDelta, Touch, TouchPhase, VirtualKeyCode, WindowEvent,
 };
 
 use amethyst_core::shrev::EventChannel;
@@ -41,6 +41,10 @@ where
     /// while second is the ID used by incoming events.
     connected_controllers: SmallVec<[(u32, u32); 8]>,
     mouse_position: Option<(f64, f64)>,
+    /// Ids and positions of the fingers currently on a touch screen.
+    touches: SmallVec<[(u64, (f64, f64)); 10]>,
+    /// The toggle actions that are switched on.
+    toggled_actions: SmallVec<[AC; 8]>,
 }
 
 impl<AX, AC> InputHandler<AX, AC>
@@ -95,6 +99,7 @@ where
                             .iter()
                             .cloned(),
                         );
+                        let mut pressed = SmallVec::<[AC; 4]>::new();
                         for (action, combinations) in self.bindings.actions.iter() {
                             for combination in combinations.iter().filter(|c| {
                                 c.contains(&Button::Key(key_code))
@@ -104,10 +109,11 @@ where
                                     .iter()
                                     .all(|button| self.button_is_down(*button))
                                 {
-                                    event_handler.single_write(ActionPressed(action.clone()));
+                                    pressed.push(action.clone());
                                 }
                             }
                         }
+                        self.send_actions_pressed(pressed, event_handler);
                     }
                 }
                 WindowEvent::KeyboardInput {
@@ -132,6 +138,7 @@ where
                             .iter()
                             .cloned(),
                         );
+                        let mut released = SmallVec::<[AC; 4]>::new();
                         for (action, combinations) in self.bindings.actions.iter() {
                             for combination in combinations {
                                 if combination.contains(&Button::Key(key_code))
@@ -140,7 +147,7 @@ where
                                         .filter(|b| b != &&Button::Key(key_code))
                                         .all(|b| self.button_is_down(*b))
                                 {
-                                    event_handler.single_write(ActionReleased(action.clone()));
+                                    released.push(action.clone());
                                 }
                                 if combination.contains(&Button::ScanCode(scancode))
                                     && combination
@@ -148,10 +155,11 @@ where
                                         .filter(|b| b != &&Button::ScanCode(scancode))
                                         .all(|b| self.button_is_down(*b))
                                 {
-                                    event_handler.single_write(ActionReleased(action.clone()));
+                                    released.push(action.clone());
                                 }
                             }
                         }
+                        self.send_actions_released(released, event_handler);
                     }
                 }
                 WindowEvent::MouseInput {
@@ -159,86 +167,55 @@ where
                     button,
                     ..
                 } => {
-                    let mouse_button = button;
-                    if self
-                        .pressed_mouse_buttons
-                        .iter()
-                        .all(|&b| b != mouse_button)
-                    {
-                        self.pressed_mouse_buttons.push(mouse_button);
-                        event_handler.iter_write(
-                            [
-                                MouseButtonPressed(mouse_button),
-                                ButtonPressed(Button::Mouse(mouse_button)),
-                            ]
-                            .iter()
-                            .cloned(),
-                        );
-                        for (action, combinations) in self.bindings.actions.iter() {
-                            for combination in combinations
-                                .iter()
-                                .filter(|c| c.contains(&Button::Mouse(mouse_button)))
-                            {
-                                if combination
-                                    .iter()
-                                    .all(|button| self.button_is_down(*button))
-                                {
-                                    event_handler.single_write(ActionPressed(action.clone()));
-                                }
-                            }
-                        }
-                    }
+                    self.press_mouse_button(button, event_handler);
                 }
                 WindowEvent::MouseInput {
                     state: ElementState::Released,
                     button,
                     ..
                 } => {
-                    let mouse_button = button;
-                    let index = self
-                        .pressed_mouse_buttons
-                        .iter()
-                        .position(|&b| b == mouse_button);
-                    if let Some(i) = index {
-                        self.pressed_mouse_buttons.swap_remove(i);
-                        event_handler.iter_write(
-                            [
-                                MouseButtonReleased(mouse_button),
-                                ButtonReleased(Button::Mouse(mouse_button)),
-                            ]
-                            .iter()
-                            .cloned(),
-                        );
-                        for (action, combinations) in self.bindings.actions.iter() {
-                            for combination in combinations {
-                                if combination.contains(&Button::Mouse(mouse_button))
-                                    && combination
-                                        .iter()
-                                        .filter(|b| b != &&Button::Mouse(mouse_button))
-                                        .all(|b| self.button_is_down(*b))
-                                {
-                                    event_handler.single_write(ActionReleased(action.clone()));
-                                }
-                            }
-                        }
-                    }
+                    self.release_mouse_button(button, event_handler);
                 }
                 WindowEvent::CursorMoved {
                     position: LogicalPosition { x, y },
                     ..
                 } => {
-                    if let Some((old_x, old_y)) = self.mouse_position {
-                        event_handler.single_write(CursorMoved {
-                            delta_x: x * hidpi - old_x,
-                            delta_y: y * hidpi - old_y,
-                        });
+                    self.move_mouse((x * hidpi, y * hidpi), event_handler);
+                }
+                WindowEvent::Touch(Touch {
+                    phase,
+                    location: LogicalPosition { x, y },
+                    id,
+                    ..
+                }) => {
+                    let position = (x * hidpi, y * hidpi);
+                    let index = self.touches.iter().position(|touch| touch.0 == id);
+                    match (phase, index) {
+                        (TouchPhase::Started, None) => {
+                            self.touches.push((id, position));
+                            event_handler.single_write(TouchStarted { id, position });
+                        }
+                        (TouchPhase::Started, Some(index)) | (TouchPhase::Moved, Some(index)) => {
+                            self.touches[index].1 = position;
+                            event_handler.single_write(TouchMoved { id, position });
+                        }
+                        (TouchPhase::Ended, Some(index)) => {
+                            self.touches.swap_remove(index);
+                            event_handler.single_write(TouchEnded { id, position });
+                        }
+                        (TouchPhase::Cancelled, Some(index)) => {
+                            self.touches.swap_remove(index);
+                            event_handler.single_write(TouchCancelled { id });
+                        }
+                        // Touches that started before the window got the focus are ignored.
+                        _ => {}
                     }
-                    self.mouse_position = Some((x * hidpi, y * hidpi));
                 }
                 WindowEvent::Focused(false) => {
                     self.pressed_keys.clear();
                     self.pressed_mouse_buttons.clear();
                     self.mouse_position = None;
+                    self.touches.clear();
                 }
                 _ => {}
             },
@@ -306,6 +283,7 @@ where
                             .iter()
                             .cloned(),
                         );
+                        let mut pressed = SmallVec::<[AC; 4]>::new();
                         for (action, combinations) in self.bindings.actions.iter() {
                             for combination in combinations
                                 .iter()
@@ -315,10 +293,11 @@ where
                                     .iter()
                                     .all(|button| self.button_is_down(*button))
                                 {
-                                    event_handler.single_write(ActionPressed(action.clone()));
+                                    pressed.push(action.clone());
                                 }
                             }
                         }
+                        self.send_actions_pressed(pressed, event_handler);
                     }
                 }
             }
@@ -338,6 +317,7 @@ where
                             .iter()
                             .cloned(),
                         );
+                        let mut released = SmallVec::<[AC; 4]>::new();
                         for (action, combinations) in self.bindings.actions.iter() {
                             for combination in combinations {
                                 if combination.contains(&Button::Controller(controller_id, button))
@@ -348,10 +328,11 @@ where
                                         })
                                         .all(|b| self.button_is_down(*b))
                                 {
-                                    event_handler.single_write(ActionReleased(action.clone()));
+                                    released.push(action.clone());
                                 }
                             }
                         }
+                        self.send_actions_released(released, event_handler);
                     }
                 }
             }
@@ -384,6 +365,99 @@ where
         }
     }
 
+    /// Presses a mouse button, sending the same events as the mouse, e.g. for the
+    /// `VirtualCursorSystem`.
+    pub fn press_mouse_button(
+        &mut self,
+        mouse_button: MouseButton,
+        event_handler: &mut EventChannel<InputEvent<AC>>,
+    ) {
+        if self
+            .pressed_mouse_buttons
+            .iter()
+            .all(|&b| b != mouse_button)
+        {
+            self.pressed_mouse_buttons.push(mouse_button);
+            event_handler.iter_write(
+                [
+                    MouseButtonPressed(mouse_button),
+                    ButtonPressed(Button::Mouse(mouse_button)),
+                ]
+                .iter()
+                .cloned(),
+            );
+            let mut pressed = SmallVec::<[AC; 4]>::new();
+            for (action, combinations) in self.bindings.actions.iter() {
+                for combination in combinations
+                    .iter()
+                    .filter(|c| c.contains(&Button::Mouse(mouse_button)))
+                {
+                    if combination
+                        .iter()
+                        .all(|button| self.button_is_down(*button))
+                    {
+                        pressed.push(action.clone());
+                    }
+                }
+            }
+            self.send_actions_pressed(pressed, event_handler);
+        }
+    }
+
+    /// Releases a mouse button, sending the same events as the mouse, e.g. for the
+    /// `VirtualCursorSystem`.
+    pub fn release_mouse_button(
+        &mut self,
+        mouse_button: MouseButton,
+        event_handler: &mut EventChannel<InputEvent<AC>>,
+    ) {
+        let index = self
+            .pressed_mouse_buttons
+            .iter()
+            .position(|&b| b == mouse_button);
+        if let Some(i) = index {
+            self.pressed_mouse_buttons.swap_remove(i);
+            event_handler.iter_write(
+                [
+                    MouseButtonReleased(mouse_button),
+                    ButtonReleased(Button::Mouse(mouse_button)),
+                ]
+                .iter()
+                .cloned(),
+            );
+            let mut released = SmallVec::<[AC; 4]>::new();
+            for (action, combinations) in self.bindings.actions.iter() {
+                for combination in combinations {
+                    if combination.contains(&Button::Mouse(mouse_button))
+                        && combination
+                            .iter()
+                            .filter(|b| b != &&Button::Mouse(mouse_button))
+                            .all(|b| self.button_is_down(*b))
+                    {
+                        released.push(action.clone());
+                    }
+                }
+            }
+            self.send_actions_released(released, event_handler);
+        }
+    }
+
+    /// Moves the mouse pointer to the position in pixels, sending the same events as the mouse,
+    /// e.g. for the `VirtualCursorSystem`.
+    pub fn move_mouse(
+        &mut self,
+        position: (f64, f64),
+        event_handler: &mut EventChannel<InputEvent<AC>>,
+    ) {
+        if let Some((old_x, old_y)) = self.mouse_position {
+            event_handler.single_write(CursorMoved {
+                delta_x: position.0 - old_x,
+                delta_y: position.1 - old_y,
+            });
+        }
+        self.mouse_position = Some(position);
+    }
+
     /// Returns an iterator over all keys that are down.
     pub fn keys_that_are_down(&self) -> impl Iterator<Item = VirtualKeyCode> + '_ {
         self.pressed_keys.iter().map(|k| k.0)
@@ -448,6 +522,20 @@ where
             .any(|ids| ids.0 == controller_id)
     }
 
+    /// Returns the position of a controller axis, from `-1.0` to `1.0`, without the dead zone
+    /// of an `Axis` binding, or `None` if the controller isn't connected.
+    pub fn controller_axis_value(&self, controller_id: u32, axis: ControllerAxis) -> Option<f64> {
+        if !self.is_controller_connected(controller_id) {
+            return None;
+        }
+        Some(
+            self.controller_axes
+                .iter()
+                .find(|&&(id, a, _)| id == controller_id && a == axis)
+                .map_or(0.0, |&(_, _, value)| value),
+        )
+    }
+
     /// Gets the current mouse position.
     ///
     /// this method can return None, either if no mouse is connected, or if no mouse events have
@@ -456,6 +544,19 @@ where
         self.mouse_position
     }
 
+    /// Returns an iterator over the ids and positions of the fingers on the touch screen.
+    pub fn touches(&self) -> impl Iterator<Item = (u64, (f64, f64))> + '_ {
+        self.touches.iter().cloned()
+    }
+
+    /// Gets the position of the finger with the given id, if it is on the touch screen.
+    pub fn touch_position(&self, id: u64) -> Option<(f64, f64)> {
+        self.touches
+            .iter()
+            .find(|touch| touch.0 == id)
+            .map(|touch| touch.1)
+    }
+
     /// Returns an iterator over all buttons that are down.
     pub fn buttons_that_are_down<'a>(&self) -> impl Iterator<Item = Button> + '_ {
         let mouse_buttons = self
@@ -526,10 +627,18 @@ where
     /// Returns true if any of the actions bindings is down.
     ///
     /// If a binding represents a combination of buttons, all of them need to be down.
+    /// A toggle action is down from one press of its bindings to the next.
     pub fn action_is_down<T: Hash + Eq + ?Sized>(&self, action: &T) -> Option<bool>
     where
         AC: Borrow<T>,
     {
+        if self.bindings.is_toggle(action) {
+            return self.bindings.actions.get(action).map(|_| {
+                self.toggled_actions
+                    .iter()
+                    .any(|toggled| Borrow::<T>::borrow(toggled) == action)
+            });
+        }
         self.bindings.actions.get(action).map(|combinations| {
             combinations.iter().any(|combination| {
                 combination
@@ -539,6 +648,48 @@ where
         })
     }
 
+    /// Sends `ActionPressed` for the actions whose bindings were pressed, switching the toggle
+    /// actions on or off instead.
+    fn send_actions_pressed(
+        &mut self,
+        actions: SmallVec<[AC; 4]>,
+        event_handler: &mut EventChannel<InputEvent<AC>>,
+    ) {
+        let mut switched = SmallVec::<[AC; 4]>::new();
+        for action in actions {
+            if !self.bindings.is_toggle(&action) {
+                event_handler.single_write(ActionPressed(action));
+            } else if !switched.contains(&action) {
+                // Several bindings pressed by the same button switch the action once.
+                switched.push(action.clone());
+                match self.toggled_actions.iter().position(|a| *a == action) {
+                    Some(i) => {
+                        self.toggled_actions.swap_remove(i);
+                        event_handler.single_write(ActionReleased(action));
+                    }
+                    None => {
+                        self.toggled_actions.push(action.clone());
+                        event_handler.single_write(ActionPressed(action));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Sends `ActionReleased` for the actions whose bindings were released, except for the
+    /// toggle actions, which stay on until they are pressed again.
+    fn send_actions_released(
+        &mut self,
+        actions: SmallVec<[AC; 4]>,
+        event_handler: &mut EventChannel<InputEvent<AC>>,
+    ) {
+        for action in actions {
+            if !self.bindings.is_toggle(&action) {
+                event_handler.single_write(ActionReleased(action));
+            }
+        }
+    }
+
     /// Retrieve next free controller number to allocate new controller to
     fn alloc_controller_id(&self) -> u32 {
         let mut i = 0u32;
@@ -628,3 +779,63 @@ where
         event_handler.iter_write(events);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use winit::{DeviceId, WindowId};
+
+    use super::*;
+
+    fn touch(phase: TouchPhase, id: u64, x: f64, y: f64) -> Event {
+        Event::WindowEvent {
+            window_id: unsafe { WindowId::dummy() },
+            event: WindowEvent::Touch(Touch {
+                device_id: unsafe { DeviceId::dummy() },
+                phase,
+                location: LogicalPosition { x, y },
+                id,
+            }),
+        }
+    }
+
+    #[test]
+    fn touches_are_tracked_until_lifted() {
+        let mut input = InputHandler::<String, String>::new();
+        let mut events = EventChannel::new();
+        let mut reader = events.register_reader();
+
+        input.send_event(&touch(TouchPhase::Started, 1, 10., 20.), &mut events, 2.);
+        input.send_event(&touch(TouchPhase::Started, 2, 5., 5.), &mut events, 2.);
+        input.send_event(&touch(TouchPhase::Moved, 1, 15., 20.), &mut events, 2.);
+        assert_eq!(Some((30., 40.)), input.touch_position(1));
+        assert_eq!(2, input.touches().count());
+
+        input.send_event(&touch(TouchPhase::Ended, 1, 15., 20.), &mut events, 2.);
+        input.send_event(&touch(TouchPhase::Cancelled, 2, 5., 5.), &mut events, 2.);
+        // Touches that aren't tracked are ignored.
+        input.send_event(&touch(TouchPhase::Moved, 3, 0., 0.), &mut events, 2.);
+        assert_eq!(None, input.touch_position(1));
+        assert_eq!(0, input.touches().count());
+
+        let ids = events
+            .read(&mut reader)
+            .map(|event| match *event {
+                TouchStarted { id, .. } => ("started", id),
+                TouchMoved { id, .. } => ("moved", id),
+                TouchEnded { id, .. } => ("ended", id),
+                TouchCancelled { id } => ("cancelled", id),
+                ref event => panic!("Unexpected event {:?}", event),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                ("started", 1),
+                ("started", 2),
+                ("moved", 1),
+                ("ended", 1),
+                ("cancelled", 2),
+            ],
+            ids
+        );
+    }
+}
@@ -27,6 +27,7 @@ pub use self::{
     scroll_direction::ScrollDirection,
     system::InputSystem,
     util::{get_input_axis_simple, get_key, is_close_requested, is_key_down},
+    virtual_cursor::{VirtualCursor, VirtualCursorSystem},
 };
 
 use std::iter::Iterator;
@@ -42,6 +43,7 @@ mod input_handler;
 mod scroll_direction;
 mod system;
 mod util;
+mod virtual_cursor;
 
 #[cfg(feature = "sdl_controller")]
 mod sdl_events_system;
@@ -4,14 +4,34 @@ use std::hash::Hash;
 
 use winit::Event;
 
+#[cfg(feature = "renderer")]
+use amethyst_core::specs::prelude::ReadExpect;
 use amethyst_core::{
     shrev::{EventChannel, ReaderId},
-    specs::prelude::{Read, ReadExpect, Resources, System, Write},
+    specs::prelude::{Read, Resources, System, Write},
 };
+#[cfg(feature = "renderer")]
 use amethyst_renderer::ScreenDimensions;
 
 use crate::{Bindings, InputEvent, InputHandler};
 
+/// Resource holding the hidpi factor of the window.
+#[cfg(feature = "renderer")]
+type Hidpi<'a> = ReadExpect<'a, ScreenDimensions>;
+/// Without the renderer there is no window, so the factor is always 1.
+#[cfg(not(feature = "renderer"))]
+type Hidpi<'a> = ();
+
+#[cfg(feature = "renderer")]
+fn hidpi_factor(hidpi: &Hidpi<'_>) -> f64 {
+    hidpi.hidpi_factor()
+}
+
+#[cfg(not(feature = "renderer"))]
+fn hidpi_factor(_: &Hidpi<'_>) -> f64 {
+    1.0
+}
+
 /// Input system
 ///
 /// Will read `winit::Event` from `EventHandler<winit::Event>`, process them with `InputHandler`,
@@ -60,22 +80,17 @@ where
         Read<'a, EventChannel<Event>>,
         Write<'a, InputHandler<AX, AC>>,
         Write<'a, EventChannel<InputEvent<AC>>>,
-        ReadExpect<'a, ScreenDimensions>,
+        Hidpi<'a>,
     );
 
-    fn run(&mut self, (input, mut handler, mut output, screen_dimensions): Self::SystemData) {
+    fn run(&mut self, (input, mut handler, mut output, hidpi): Self::SystemData) {
         for event in input.read(
             &mut self
                 .reader
                 .as_mut()
                 .expect("`InputSystem::setup` was not called before `InputSystem::run`"),
         ) {
-            Self::process_event(
-                event,
-                &mut *handler,
-                &mut *output,
-                screen_dimensions.hidpi_factor(),
-            );
+            Self::process_event(event, &mut *handler, &mut *output, hidpi_factor(&hidpi));
         }
     }
 
@@ -0,0 +1,207 @@
+//! Mouse pointer moved with a controller stick.
+
+use std::{hash::Hash, marker::PhantomData};
+
+use winit::MouseButton;
+
+#[cfg(feature = "renderer")]
+use amethyst_core::specs::prelude::ReadExpect;
+use amethyst_core::{
+    shrev::EventChannel,
+    specs::prelude::{Read, Resources, System, Write},
+    timing::Time,
+};
+#[cfg(feature = "renderer")]
+use amethyst_renderer::ScreenDimensions;
+
+use crate::{ControllerAxis, ControllerButton, InputEvent, InputHandler};
+
+/// Bounds of the window the cursor stays in.
+#[cfg(feature = "renderer")]
+type Screen<'a> = Option<Read<'a, ScreenDimensions>>;
+/// Without the renderer there is no window, so the cursor isn't kept in it.
+#[cfg(not(feature = "renderer"))]
+type Screen<'a> = ();
+
+#[cfg(feature = "renderer")]
+fn screen_size(screen: &Screen<'_>) -> Option<(f64, f64)> {
+    screen
+        .as_ref()
+        .map(|screen| (f64::from(screen.width()), f64::from(screen.height())))
+}
+
+#[cfg(not(feature = "renderer"))]
+fn screen_size(_: &Screen<'_>) -> Option<(f64, f64)> {
+    None
+}
+
+/// Resource configuring the virtual cursor of the `VirtualCursorSystem`.
+///
+/// The right stick of the controller moves the mouse pointer and the `click` button presses the
+/// left mouse button, so the UI reacts to it the same as to the mouse.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct VirtualCursor {
+    /// Whether the stick moves the cursor.
+    pub enabled: bool,
+    /// The controller moving the cursor.
+    pub controller_id: u32,
+    /// Speed of the cursor with the stick tilted all the way, in pixels per second.
+    pub speed: f64,
+    /// How fast the cursor speeds up while the stick is tilted, in pixels per second squared.
+    /// With `0.0` it moves at full speed right away.
+    pub acceleration: f64,
+    /// How far the stick has to be tilted to move the cursor, from `0.0` to `1.0`.
+    pub dead_zone: f64,
+    /// The button clicking with the cursor.
+    pub click: ControllerButton,
+}
+
+impl Default for VirtualCursor {
+    fn default() -> Self {
+        VirtualCursor {
+            enabled: true,
+            controller_id: 0,
+            speed: 800.0,
+            acceleration: 2400.0,
+            dead_zone: 0.2,
+            click: ControllerButton::A,
+        }
+    }
+}
+
+impl VirtualCursor {
+    /// Returns the velocity of the cursor in pixels per second for the position of the stick,
+    /// speeding up from `current` speed over `delta` seconds.
+    fn velocity(&self, (x, y): (f64, f64), current: f64, delta: f64) -> (f64, f64) {
+        let tilt = (x * x + y * y).sqrt();
+        if tilt <= self.dead_zone {
+            return (0.0, 0.0);
+        }
+        let target = self.speed * ((tilt - self.dead_zone) / (1.0 - self.dead_zone)).min(1.0);
+        let speed = if self.acceleration > 0.0 {
+            target.min(current + self.acceleration * delta)
+        } else {
+            target
+        };
+        (x / tilt * speed, y / tilt * speed)
+    }
+}
+
+/// Moves the mouse pointer of the `InputHandler` with the right stick of a controller, as set up
+/// by the `VirtualCursor` resource, for couch play in menus made for the mouse.
+///
+/// It's added by `InputBundle::with_virtual_cursor`. The mouse keeps working, moving the same
+/// pointer.
+pub struct VirtualCursorSystem<AX, AC> {
+    cursor: Option<VirtualCursor>,
+    speed: f64,
+    clicking: bool,
+    _marker: PhantomData<(AX, AC)>,
+}
+
+impl<AX, AC> VirtualCursorSystem<AX, AC> {
+    /// Creates the system, inserting the `VirtualCursor` on setup.
+    pub fn new(cursor: VirtualCursor) -> Self {
+        VirtualCursorSystem {
+            cursor: Some(cursor),
+            speed: 0.0,
+            clicking: false,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, AX, AC> System<'a> for VirtualCursorSystem<AX, AC>
+where
+    AX: Hash + Eq + Clone + Send + Sync + 'static,
+    AC: Hash + Eq + Clone + Send + Sync + 'static,
+{
+    type SystemData = (
+        Read<'a, VirtualCursor>,
+        Read<'a, Time>,
+        Write<'a, InputHandler<AX, AC>>,
+        Write<'a, EventChannel<InputEvent<AC>>>,
+        Screen<'a>,
+    );
+
+    fn run(&mut self, (cursor, time, mut handler, mut events, screen): Self::SystemData) {
+        let stick = match (
+            handler.controller_axis_value(cursor.controller_id, ControllerAxis::RightX),
+            handler.controller_axis_value(cursor.controller_id, ControllerAxis::RightY),
+        ) {
+            (Some(x), Some(y)) if cursor.enabled => (x, y),
+            _ => {
+                self.speed = 0.0;
+                if self.clicking {
+                    self.clicking = false;
+                    handler.release_mouse_button(MouseButton::Left, &mut events);
+                }
+                return;
+            }
+        };
+
+        let delta = f64::from(time.delta_real_seconds());
+        let (velocity_x, velocity_y) = cursor.velocity(stick, self.speed, delta);
+        self.speed = (velocity_x * velocity_x + velocity_y * velocity_y).sqrt();
+        if self.speed > 0.0 {
+            let size = screen_size(&screen);
+            let (x, y) = handler
+                .mouse_position()
+                .or_else(|| size.map(|(width, height)| (width / 2.0, height / 2.0)))
+                .unwrap_or((0.0, 0.0));
+            // The stick's y axis points down, like the pixels of the window.
+            let (mut x, mut y) = (x + velocity_x * delta, y + velocity_y * delta);
+            if let Some((width, height)) = size {
+                x = x.max(0.0).min(width);
+                y = y.max(0.0).min(height);
+            }
+            handler.move_mouse((x, y), &mut events);
+        }
+
+        let clicking = handler.controller_button_is_down(cursor.controller_id, cursor.click);
+        if clicking != self.clicking {
+            self.clicking = clicking;
+            if clicking {
+                handler.press_mouse_button(MouseButton::Left, &mut events);
+            } else {
+                handler.release_mouse_button(MouseButton::Left, &mut events);
+            }
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        use amethyst_core::specs::prelude::SystemData;
+        Self::SystemData::setup(res);
+        if let Some(cursor) = self.cursor.take() {
+            res.insert(cursor);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn length((x, y): (f64, f64)) -> f64 {
+        (x * x + y * y).sqrt()
+    }
+
+    #[test]
+    fn velocity_ignores_dead_zone_and_accelerates() {
+        let cursor = VirtualCursor::default();
+        assert_eq!((0.0, 0.0), cursor.velocity((0.1, 0.1), 0.0, 0.1));
+
+        // Full tilt speeds up by the acceleration, up to the speed.
+        let velocity = cursor.velocity((1.0, 0.0), 0.0, 0.1);
+        assert!((velocity.0 - 240.0).abs() < 1.0e-6 && velocity.1.abs() < 1.0e-6);
+        assert!((length(cursor.velocity((0.0, -1.0), 700.0, 0.1)) - 800.0).abs() < 1.0e-6);
+
+        // Halfway between the dead zone and full tilt is half the speed.
+        let instant = VirtualCursor {
+            acceleration: 0.0,
+            ..Default::default()
+        };
+        assert!((length(instant.velocity((0.6, 0.0), 0.0, 0.1)) - 400.0).abs() < 1.0e-6);
+    }
+}
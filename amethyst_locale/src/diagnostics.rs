@@ -0,0 +1,151 @@
+//! Finding the problems of the localisation while playing.
+
+use std::{
+    collections::BTreeSet,
+    fs::File,
+    io::{Result as IoResult, Write as IoWrite},
+    path::{Path, PathBuf},
+};
+
+use amethyst_core::specs::prelude::{Read, System};
+
+/// Turns the texts into ones looking translated but still readable, like "[Ĥéļļó ŵóŕļð ~~~]",
+/// to find the texts of the game which aren't localised, the ones cut by the layout once
+/// translated to longer languages, and the fonts missing accented glyphs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PseudoLocale {
+    /// Replaces the latin letters by accented ones.
+    pub accents: bool,
+    /// How much longer the texts are made, `0.3` adding 30% of their length.
+    pub expansion: f32,
+    /// Puts the texts between brackets, to see where they are cut.
+    pub brackets: bool,
+}
+
+impl Default for PseudoLocale {
+    fn default() -> Self {
+        PseudoLocale {
+            accents: true,
+            expansion: 0.3,
+            brackets: true,
+        }
+    }
+}
+
+impl PseudoLocale {
+    /// Returns the pseudo-localised text.
+    pub fn apply(&self, text: &str) -> String {
+        let mut result = String::with_capacity(text.len() * 2);
+        if self.brackets {
+            result.push('[');
+        }
+        let mut length = 0;
+        for c in text.chars() {
+            result.push(if self.accents { accented(c) } else { c });
+            length += 1;
+        }
+        let padding = (length as f32 * self.expansion.max(0.)).ceil() as usize;
+        if padding > 0 {
+            result.push(' ');
+            result.extend((1..padding).map(|_| '~'));
+        }
+        if self.brackets {
+            result.push(']');
+        }
+        result
+    }
+}
+
+fn accented(c: char) -> char {
+    const LOWER: &str = "áƀçðéƒĝĥíĵķļɱñóþǫŕšţúṽŵẋýž";
+    const UPPER: &str = "ÅƁÇÐÉƑĜĤÍĴĶĻṀÑÓÞǪŔŠŢÚṼŴẊÝŽ";
+    let (letters, first) = if c.is_ascii_lowercase() {
+        (LOWER, b'a')
+    } else if c.is_ascii_uppercase() {
+        (UPPER, b'A')
+    } else {
+        return c;
+    };
+    letters.chars().nth((c as u8 - first) as usize).unwrap_or(c)
+}
+
+/// Resource with the pseudo-localisation mode and the keys missing from the locales, filled by
+/// `Locale::text`.
+///
+/// Add a `LocaleReportSystem` to write the missing keys to a file while playtesting.
+#[derive(Clone, Debug, Default)]
+pub struct LocaleDiagnostics {
+    /// Pseudo-localises the texts of the locales when set.
+    pub pseudo: Option<PseudoLocale>,
+    missing: BTreeSet<String>,
+}
+
+impl LocaleDiagnostics {
+    /// Creates the diagnostics, without pseudo-localisation.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets the pseudo-localisation mode.
+    pub fn with_pseudo(mut self, pseudo: PseudoLocale) -> Self {
+        self.pseudo = Some(pseudo);
+        self
+    }
+
+    /// Records a key missing from a locale.
+    pub fn report_missing(&mut self, key: &str) {
+        if !self.missing.contains(key) {
+            warn!("Missing translation for `{}`", key);
+            self.missing.insert(key.to_owned());
+        }
+    }
+
+    /// Returns the keys missing from the locales so far, in order.
+    pub fn missing(&self) -> impl Iterator<Item = &str> {
+        self.missing.iter().map(String::as_str)
+    }
+
+    /// Forgets the missing keys, e.g. after the locales are reloaded.
+    pub fn clear_missing(&mut self) {
+        self.missing.clear();
+    }
+
+    /// Writes the missing keys to the file, one per line.
+    pub fn write_report<P: AsRef<Path>>(&self, path: P) -> IoResult<()> {
+        let mut file = File::create(path)?;
+        for key in &self.missing {
+            writeln!(file, "{}", key)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the keys missing from the locales to a file each time a new one is found.
+pub struct LocaleReportSystem {
+    path: PathBuf,
+    written: usize,
+}
+
+impl LocaleReportSystem {
+    /// Creates the system writing the report to the file.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        LocaleReportSystem {
+            path: path.into(),
+            written: 0,
+        }
+    }
+}
+
+impl<'a> System<'a> for LocaleReportSystem {
+    type SystemData = Read<'a, LocaleDiagnostics>;
+
+    fn run(&mut self, diagnostics: Self::SystemData) {
+        let missing = diagnostics.missing.len();
+        if missing != self.written {
+            if let Err(err) = diagnostics.write_report(&self.path) {
+                error!("Failed to write the missing translations report: {}", err);
+            }
+            self.written = missing;
+        }
+    }
+}
@@ -2,11 +2,19 @@
 //!
 //! Localisation binding a `Fluent` file to an Asset<Locale> via the use of amethyst_assets.
 #![warn(missing_docs, rust_2018_idioms, rust_2018_compatibility)]
+
+#[macro_use]
+extern crate log;
+
+pub use self::diagnostics::{LocaleDiagnostics, LocaleReportSystem, PseudoLocale};
+
 use fluent::bundle::FluentBundle;
 
 use amethyst_assets::{Asset, Handle, ProcessingState, Result, SimpleFormat};
 use amethyst_core::specs::prelude::VecStorage;
 
+mod diagnostics;
+
 /// Loads the strings from localisation files.
 #[derive(Clone)]
 pub struct LocaleFormat;
@@ -42,8 +50,44 @@ pub struct Locale {
     pub bundle: FluentBundle<'static>,
 }
 
+impl Locale {
+    /// Returns the text of the key, pseudo-localised if the diagnostics ask for it.
+    ///
+    /// Returns the key itself when it's missing, reporting it to the diagnostics.
+    pub fn text(&self, key: &str, diagnostics: &mut LocaleDiagnostics) -> String {
+        match self.bundle.format(key, None) {
+            Some((text, _)) => match diagnostics.pseudo {
+                Some(ref pseudo) => pseudo.apply(&text),
+                None => text,
+            },
+            None => {
+                diagnostics.report_missing(key);
+                key.to_owned()
+            }
+        }
+    }
+}
+
 impl Asset for Locale {
     const NAME: &'static str = "locale::Locale";
     type Data = Locale;
     type HandleStorage = VecStorage<LocaleHandle>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn texts_are_pseudo_localised_and_missing_keys_reported() {
+        let mut bundle = FluentBundle::new::<&'static str>(&[]);
+        bundle.add_messages("hello = Hello world").unwrap();
+        let locale = Locale { bundle };
+
+        let mut diagnostics = LocaleDiagnostics::new().with_pseudo(PseudoLocale::default());
+        assert_eq!("[Ĥéļļó ŵóŕļð ~~~]", locale.text("hello", &mut diagnostics));
+        assert_eq!("bye", locale.text("bye", &mut diagnostics));
+        locale.text("bye", &mut diagnostics);
+        assert_eq!(vec!["bye"], diagnostics.missing().collect::<Vec<_>>());
+    }
+}
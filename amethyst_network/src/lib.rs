@@ -10,6 +10,7 @@ mod error;
 mod filter;
 mod net_event;
 mod network_socket;
+mod rollback;
 mod server;
 mod test;
 
@@ -20,6 +21,7 @@ pub use crate::{
     filter::{FilterConnected, NetFilter},
     net_event::NetEvent,
     network_socket::NetSocketSystem,
+    rollback::{RollbackConfig, RollbackFrame, RollbackInput, RollbackSession},
     server::{Host, ServerConfig, ServerSocketEvent},
 };
 
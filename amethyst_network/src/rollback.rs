@@ -0,0 +1,394 @@
+//! Rollback netcode, for games where input has to feel immediate over the network, like fighting
+//! games.
+
+use std::{
+    any::Any,
+    collections::{BTreeMap, VecDeque},
+    marker::PhantomData,
+};
+
+use amethyst_core::{
+    specs::prelude::{Component, Dispatcher, Entity, Join, World},
+    Rng,
+};
+use log::warn;
+
+/// Configuration of a `RollbackSession`, the same on all the peers but for the local player.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default)]
+pub struct RollbackConfig {
+    /// The number of players.
+    pub players: usize,
+    /// The player playing on this peer.
+    pub local_player: usize,
+    /// Frames between the local input and the frame it's for. A few frames give the input time
+    /// to reach the other peers, so that they need to roll back less.
+    pub input_delay: u64,
+    /// Frames simulated at most without the input of all the players; the session waits for the
+    /// late players past them.
+    pub max_rollback: u64,
+}
+
+impl Default for RollbackConfig {
+    fn default() -> Self {
+        RollbackConfig {
+            players: 2,
+            local_player: 0,
+            input_delay: 2,
+            max_rollback: 8,
+        }
+    }
+}
+
+/// The input of a player for a frame, sent to the other peers, e.g. in a `NetEvent::Custom`.
+///
+/// Inputs can be sent more than once, to make up for lost packets: the ones already received are
+/// ignored.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct RollbackInput<I> {
+    /// The player.
+    pub player: usize,
+    /// The frame, counted from the start of the session.
+    pub frame: u64,
+    /// The input.
+    pub input: I,
+}
+
+/// Resource with the frame the `RollbackSession` simulates, for the systems of the simulation.
+#[derive(Clone, Debug, Default)]
+pub struct RollbackFrame<I> {
+    /// The frame, counted from the start of the session.
+    pub frame: u64,
+    /// The inputs of the players, predicted for the ones not received yet.
+    pub inputs: Vec<I>,
+    /// Whether the frame is simulated again after a rollback, e.g. to not play its sounds twice.
+    pub resimulating: bool,
+}
+
+trait ComponentRollback: Send + Sync {
+    fn save(&self, world: &World) -> Box<dyn Any + Send + Sync>;
+
+    fn restore(&self, world: &World, saved: &(dyn Any + Send + Sync));
+}
+
+struct Registered<T>(PhantomData<T>);
+
+impl<T> ComponentRollback for Registered<T>
+where
+    T: Component + Clone + Send + Sync,
+{
+    fn save(&self, world: &World) -> Box<dyn Any + Send + Sync> {
+        let entities = world.entities();
+        let storage = world.read_storage::<T>();
+        let saved = (&entities, &storage)
+            .join()
+            .map(|(entity, component)| (entity, component.clone()))
+            .collect::<Vec<_>>();
+        Box::new(saved)
+    }
+
+    fn restore(&self, world: &World, saved: &(dyn Any + Send + Sync)) {
+        let saved = saved
+            .downcast_ref::<Vec<(Entity, T)>>()
+            .expect("Snapshot of another component");
+        let entities = world.entities();
+        let mut storage = world.write_storage::<T>();
+        storage.clear();
+        for (entity, component) in saved {
+            if entities.is_alive(*entity) {
+                storage
+                    .insert(*entity, component.clone())
+                    .expect("Inserting a component on an alive entity");
+            }
+        }
+    }
+}
+
+struct Snapshot<I> {
+    frame: u64,
+    rng: Option<Rng>,
+    components: Vec<Box<dyn Any + Send + Sync>>,
+    /// The inputs the frame was simulated with.
+    inputs: Vec<I>,
+}
+
+/// Runs the simulation of a match over the network with rollbacks.
+///
+/// Every peer simulates the match from the local input right away, predicting that the remote
+/// players keep the input they last sent. When the input of a remote player arrives for a frame
+/// already simulated and differs from the prediction, the session puts the registered
+/// components and the `Rng` back as they were at that frame and simulates the frames again, all
+/// before the next frame is shown.
+///
+/// The simulation has to be deterministic: the systems of the dispatcher given to the session
+/// read the inputs of the frame from the `RollbackFrame` resource, and draw their random numbers
+/// from the `Rng`. Entities can't come back once deleted, so the simulation should hide them
+/// while they can still be rolled back, instead of deleting them.
+///
+/// On every fixed update, the game adds the local input with `add_local_input`, sends the
+/// returned `RollbackInput` to the other peers, adds the ones received from them with
+/// `add_remote_input` and calls `update`.
+pub struct RollbackSession<I> {
+    config: RollbackConfig,
+    components: Vec<Box<dyn ComponentRollback>>,
+    /// The inputs received, per player.
+    inputs: Vec<BTreeMap<u64, I>>,
+    /// The first frame without the inputs of all the players.
+    confirmed_frame: u64,
+    /// The next frame to simulate.
+    frame: u64,
+    /// The world before each frame since the confirmed one.
+    snapshots: VecDeque<Snapshot<I>>,
+    rollback_to: Option<u64>,
+}
+
+impl<I> RollbackSession<I>
+where
+    I: Clone + Default + PartialEq + Send + Sync + 'static,
+{
+    /// Creates a session without registered components.
+    pub fn new(config: RollbackConfig) -> Self {
+        RollbackSession {
+            inputs: vec![BTreeMap::new(); config.players],
+            confirmed_frame: config.input_delay,
+            config,
+            components: Vec::new(),
+            frame: 0,
+            snapshots: VecDeque::new(),
+            rollback_to: None,
+        }
+    }
+
+    /// Registers a component changed by the simulation, to roll it back.
+    pub fn with_component<T>(mut self) -> Self
+    where
+        T: Component + Clone + Send + Sync,
+    {
+        self.components.push(Box::new(Registered::<T>(PhantomData)));
+        self
+    }
+
+    /// Returns the configuration.
+    pub fn config(&self) -> &RollbackConfig {
+        &self.config
+    }
+
+    /// Returns the next frame to simulate.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Returns the first frame without the inputs of all the players; frames before it won't be
+    /// rolled back anymore.
+    pub fn confirmed_frame(&self) -> u64 {
+        self.confirmed_frame
+    }
+
+    /// Adds the local input for the frame `input_delay` frames after the next one, returning it
+    /// to send to the other peers. Returns `None` if there's already an input for that frame,
+    /// because the session is waiting for the other players.
+    pub fn add_local_input(&mut self, input: I) -> Option<RollbackInput<I>> {
+        let message = RollbackInput {
+            player: self.config.local_player,
+            frame: self.frame + self.config.input_delay,
+            input,
+        };
+        if self.add_input(message.clone()) {
+            Some(message)
+        } else {
+            None
+        }
+    }
+
+    /// Adds the input of a remote player, rolling back on the next `update` if the frame was
+    /// simulated with another input.
+    pub fn add_remote_input(&mut self, message: RollbackInput<I>) {
+        if message.player == self.config.local_player || message.player >= self.config.players {
+            warn!("Rollback input of an unexpected player {}", message.player);
+            return;
+        }
+        let (frame, player) = (message.frame, message.player);
+        let input = message.input.clone();
+        if !self.add_input(message) {
+            return;
+        }
+        let mispredicted = self
+            .snapshots
+            .iter()
+            .find(|snapshot| snapshot.frame == frame)
+            .map_or(false, |snapshot| snapshot.inputs[player] != input);
+        if mispredicted {
+            self.rollback_to = Some(self.rollback_to.map_or(frame, |f| f.min(frame)));
+        }
+    }
+
+    /// Adds the input, returning whether it wasn't received yet.
+    fn add_input(&mut self, message: RollbackInput<I>) -> bool {
+        if message.frame < self.confirmed_frame {
+            return false;
+        }
+        let inputs = &mut self.inputs[message.player];
+        if inputs.contains_key(&message.frame) {
+            return false;
+        }
+        inputs.insert(message.frame, message.input);
+        while self
+            .inputs
+            .iter()
+            .all(|inputs| inputs.contains_key(&self.confirmed_frame))
+        {
+            self.confirmed_frame += 1;
+        }
+        true
+    }
+
+    /// Rolls back and simulates the mispredicted frames again if needed, then simulates the next
+    /// frame. Returns `false` if the next frame has to wait for the input of other players.
+    pub fn update(&mut self, world: &mut World, simulation: &mut Dispatcher<'_, '_>) -> bool {
+        if let Some(target) = self.rollback_to.take() {
+            let now = self.frame;
+            if let Some(index) = self.snapshots.iter().position(|s| s.frame == target) {
+                let snapshot = &self.snapshots[index];
+                if let Some(ref rng) = snapshot.rng {
+                    *world.write_resource::<Rng>() = rng.clone();
+                }
+                for (component, saved) in self.components.iter().zip(&snapshot.components) {
+                    component.restore(world, saved.as_ref());
+                }
+                self.snapshots.truncate(index);
+                self.frame = target;
+                while self.frame < now {
+                    self.simulate(world, simulation, true);
+                }
+            }
+        }
+
+        let local = &self.inputs[self.config.local_player];
+        let waiting = self.frame >= self.config.input_delay && !local.contains_key(&self.frame);
+        if waiting || self.frame >= self.confirmed_frame + self.config.max_rollback {
+            return false;
+        }
+        self.simulate(world, simulation, false);
+        true
+    }
+
+    fn simulate(&mut self, world: &mut World, simulation: &mut Dispatcher<'_, '_>, again: bool) {
+        let frame = self.frame;
+        let inputs = (0..self.config.players)
+            .map(|player| self.input(player, frame))
+            .collect::<Vec<_>>();
+        self.snapshots.push_back(Snapshot {
+            frame,
+            rng: world.res.try_fetch::<Rng>().map(|rng| rng.clone()),
+            components: self.components.iter().map(|c| c.save(world)).collect(),
+            inputs: inputs.clone(),
+        });
+        world.add_resource(RollbackFrame {
+            frame,
+            inputs,
+            resimulating: again,
+        });
+        simulation.dispatch(&world.res);
+        world.maintain();
+        self.frame += 1;
+
+        let confirmed = self.confirmed_frame;
+        while self
+            .snapshots
+            .front()
+            .map_or(false, |s| s.frame < confirmed)
+        {
+            self.snapshots.pop_front();
+        }
+        // The inputs of the frames still to simulate are kept, and the last one before them for
+        // the predictions.
+        let kept_from = confirmed.min(self.frame).saturating_sub(1);
+        for inputs in &mut self.inputs {
+            let kept = inputs.split_off(&kept_from);
+            *inputs = kept;
+        }
+    }
+
+    /// Returns the input of the player, or its prediction: the last input received.
+    fn input(&self, player: usize, frame: u64) -> I {
+        if frame < self.config.input_delay {
+            return I::default();
+        }
+        let inputs = &self.inputs[player];
+        inputs
+            .get(&frame)
+            .or_else(|| inputs.range(..frame).next_back().map(|(_, input)| input))
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use amethyst_core::specs::prelude::{
+        Builder, DenseVecStorage, DispatcherBuilder, Read, System, WriteStorage,
+    };
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Position(i32);
+
+    impl Component for Position {
+        type Storage = DenseVecStorage<Self>;
+    }
+
+    struct MoveSystem;
+
+    impl<'a> System<'a> for MoveSystem {
+        type SystemData = (Read<'a, RollbackFrame<i32>>, WriteStorage<'a, Position>);
+
+        fn run(&mut self, (frame, mut positions): Self::SystemData) {
+            for position in (&mut positions).join() {
+                position.0 = position.0 * 2 + frame.inputs.iter().sum::<i32>();
+            }
+        }
+    }
+
+    #[test]
+    fn late_remote_input_rolls_back_to_its_frame() {
+        let mut world = World::new();
+        world.register::<Position>();
+        world.add_resource(RollbackFrame::<i32>::default());
+        let entity = world.create_entity().with(Position(0)).build();
+        let mut dispatcher = DispatcherBuilder::new()
+            .with(MoveSystem, "move", &[])
+            .build();
+        let config = RollbackConfig {
+            input_delay: 0,
+            ..Default::default()
+        };
+        let mut session = RollbackSession::<i32>::new(config).with_component::<Position>();
+
+        for _ in 0..3 {
+            assert!(session.add_local_input(1).is_some());
+            assert!(session.update(&mut world, &mut dispatcher));
+        }
+        // Predicted without remote input: ((0 * 2 + 1) * 2 + 1) * 2 + 1.
+        let positions = world.read_storage::<Position>();
+        assert_eq!(Some(&Position(7)), positions.get(entity));
+        drop(positions);
+
+        session.add_remote_input(RollbackInput {
+            player: 1,
+            frame: 1,
+            input: 3,
+        });
+        session.add_remote_input(RollbackInput {
+            player: 1,
+            frame: 0,
+            input: 0,
+        });
+        session.add_local_input(1);
+        assert!(session.update(&mut world, &mut dispatcher));
+        // 1 after frame 0, then adding 4 from frame 1 on, the last remote input being predicted.
+        let positions = world.read_storage::<Position>();
+        assert_eq!(Some(&Position(36)), positions.get(entity));
+        assert_eq!(2, session.confirmed_frame());
+    }
+}
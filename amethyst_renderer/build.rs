@@ -0,0 +1,23 @@
+use std::{env, process::Command};
+
+fn main() {
+    // `std::any::type_name` is stable since Rust 1.38, older compilers get the fallbacks.
+    if rustc_minor_version().map_or(false, |minor| minor >= 38) {
+        println!("cargo:rustc-cfg=type_name");
+    }
+
+    println!("cargo:rerun-if-changed=build.rs");
+}
+
+fn rustc_minor_version() -> Option<u32> {
+    let rustc = env::var_os("RUSTC")?;
+    let output = Command::new(rustc).arg("--version").output().ok()?;
+    let version = String::from_utf8(output.stdout).ok()?;
+    version
+        .split_whitespace()
+        .nth(1)?
+        .split('.')
+        .nth(1)?
+        .parse()
+        .ok()
+}
@@ -0,0 +1,122 @@
+//! Information on the graphics backend the renderer started with, and on its loss.
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+/// Graphics API of a renderer backend.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum GraphicsApi {
+    /// OpenGL, 3.2 core or later.
+    OpenGl,
+    /// Vulkan.
+    Vulkan,
+    /// Metal, on macOS.
+    Metal,
+    /// Direct3D 11, on Windows.
+    Direct3D11,
+}
+
+impl GraphicsApi {
+    /// Returns the APIs the renderer was compiled with, in the order they are tried when it
+    /// starts.
+    ///
+    /// The types of the GPU resources are chosen at compile time, so only one of the backend
+    /// features can be enabled in a build for now.
+    pub fn compiled() -> Vec<GraphicsApi> {
+        let mut apis = Vec::new();
+        if cfg!(all(feature = "d3d11", target_os = "windows")) {
+            apis.push(GraphicsApi::Direct3D11);
+        }
+        if cfg!(all(feature = "metal", target_os = "macos")) {
+            apis.push(GraphicsApi::Metal);
+        }
+        if cfg!(feature = "vulkan") {
+            apis.push(GraphicsApi::Vulkan);
+        }
+        if cfg!(feature = "opengl") {
+            apis.push(GraphicsApi::OpenGl);
+        }
+        apis
+    }
+}
+
+impl Display for GraphicsApi {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> FmtResult {
+        let name = match *self {
+            GraphicsApi::OpenGl => "OpenGL",
+            GraphicsApi::Vulkan => "Vulkan",
+            GraphicsApi::Metal => "Metal",
+            GraphicsApi::Direct3D11 => "Direct3D 11",
+        };
+        fmt.write_str(name)
+    }
+}
+
+/// Resource describing the backend the renderer started with, e.g. to show it in a settings menu
+/// or to lower the quality on old hardware.
+///
+/// When the context asked for by the `DisplayConfig` can't be created, the renderer falls back
+/// to simpler ones, without multisampling, then with the oldest version of the API the passes
+/// support, before giving up with `Error::BackendCreation`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenderBackend {
+    /// The graphics API.
+    pub api: GraphicsApi,
+    /// Version of the API, as reported by the driver, or empty if unknown.
+    pub version: String,
+    /// Name of the GPU or of the driver, as reported by the driver, or empty if unknown.
+    pub device: String,
+    /// Number of samples per pixel of the window, 0 without multisampling.
+    pub multisampling: u16,
+    /// Whether the renderer had to fall back from the context asked for by the `DisplayConfig`.
+    pub fallback: bool,
+    /// Whether the context runs compute shaders, which OpenGL only does from 4.3.
+    pub compute: bool,
+}
+
+impl Display for RenderBackend {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> FmtResult {
+        write!(fmt, "{} {} on {}", self.api, self.version, self.device)?;
+        if self.fallback {
+            write!(fmt, " (fallback)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Event sent by the `RenderSystem` when the graphics device is lost, e.g. when the GPU is reset
+/// or its driver updated, and once it is restored.
+///
+/// With device recovery enabled on the `RenderBundle`, the renderer creates a new window and
+/// context, builds the pipeline again, and uploads the `Mesh` and `Texture` assets again from
+/// the copies of their data it kept. Any other GPU resource the game made directly with the
+/// `Factory` is lost and has to be made again on `DeviceRestored`. Without recovery, nothing is
+/// drawn anymore once the device is lost, giving the game a chance to save and quit.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RenderEvent {
+    /// The device is lost, and nothing is drawn until it is restored.
+    DeviceLost,
+    /// A new device is drawing, after it was lost.
+    DeviceRestored,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fallback_backends_are_reported() {
+        let backend = RenderBackend {
+            api: GraphicsApi::OpenGl,
+            version: String::from("3.2"),
+            device: String::from("llvmpipe"),
+            multisampling: 0,
+            fallback: true,
+            compute: false,
+        };
+        assert_eq!("OpenGL 3.2 on llvmpipe (fallback)", backend.to_string());
+        assert_eq!(
+            cfg!(feature = "opengl"),
+            GraphicsApi::compiled().contains(&GraphicsApi::OpenGl)
+        );
+    }
+}
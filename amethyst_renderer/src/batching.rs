@@ -0,0 +1,285 @@
+//! Static batching of the meshes of non-moving entities.
+
+use amethyst_assets::{AssetStorage, Loader};
+use amethyst_core::{
+    nalgebra::{Matrix3, Matrix4, Point3, Vector3, U3},
+    specs::{
+        prelude::{
+            BitSet, Component, DenseVecStorage, Entities, Entity, Join, Read, ReadExpect,
+            ReadStorage, System, WriteStorage,
+        },
+        storage::NullStorage,
+    },
+    GlobalTransform,
+};
+
+use crate::{
+    color::Rgba,
+    formats::MeshData,
+    hidden::Hidden,
+    mesh::{Mesh, MeshHandle},
+    mtl::{Material, MaterialOverride},
+};
+
+/// Marks an entity which never moves, so `StaticBatchingSystem` can merge its mesh with the
+/// meshes of other static entities.
+#[derive(Clone, Debug, Default)]
+pub struct Static;
+
+impl Component for Static {
+    type Storage = NullStorage<Self>;
+}
+
+/// The entity drawing the merged meshes of static entities, created by `StaticBatchingSystem`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StaticBatch {
+    /// The entities merged into the batch, which are hidden.
+    pub entities: Vec<Entity>,
+}
+
+impl Component for StaticBatch {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Merges the meshes of the entities with a `Static`, a `MeshData` and a `Material` into one
+/// mesh per material, cutting down the draw calls of level geometry.
+///
+/// Each batch gets an entity of its own with a `StaticBatch`, and the merged entities are hidden
+/// with `Hidden`. The meshes are merged with the `GlobalTransform`s they have when they're
+/// first seen, so this should run after the transform system. Entities with a
+/// `MaterialOverride`, or a `MeshData::Creator`, are left alone.
+#[derive(Default)]
+pub struct StaticBatchingSystem {
+    batched: BitSet,
+}
+
+impl StaticBatchingSystem {
+    /// Create new static batching system
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<'a> System<'a> for StaticBatchingSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Loader>,
+        Read<'a, AssetStorage<Mesh>>,
+        ReadStorage<'a, Static>,
+        ReadStorage<'a, MeshData>,
+        ReadStorage<'a, MaterialOverride>,
+        WriteStorage<'a, Material>,
+        WriteStorage<'a, Rgba>,
+        WriteStorage<'a, GlobalTransform>,
+        WriteStorage<'a, MeshHandle>,
+        WriteStorage<'a, Hidden>,
+        WriteStorage<'a, StaticBatch>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            loader,
+            mesh_storage,
+            statics,
+            mesh_data,
+            overrides,
+            mut materials,
+            mut rgba,
+            mut globals,
+            mut meshes,
+            mut hidden,
+            mut batches,
+        ): Self::SystemData,
+    ) {
+        // The new static entities, grouped by their material and color.
+        let mut groups: Vec<(
+            Material,
+            Option<Rgba>,
+            Vec<(Entity, &MeshData, Matrix4<f32>)>,
+        )> = Vec::new();
+        for (entity, _, data, material, global, _, _) in (
+            &*entities,
+            &statics,
+            &mesh_data,
+            &materials,
+            &globals,
+            !&overrides,
+            !&self.batched,
+        )
+            .join()
+        {
+            if let MeshData::Creator(_) = *data {
+                continue;
+            }
+            let color = rgba.get(entity).cloned();
+            let group = groups
+                .iter()
+                .position(|&(ref m, ref c, _)| m == material && *c == color);
+            let member = (entity, data, global.0);
+            match group {
+                Some(group) => groups[group].2.push(member),
+                None => groups.push((material.clone(), color, vec![member])),
+            }
+        }
+
+        for (material, color, members) in groups {
+            let mut merged = Vec::new();
+            let mut sources: Vec<Vec<Entity>> = Vec::new();
+            // Meshes of different vertex formats end up in different batches.
+            for (entity, data, model) in members {
+                self.batched.add(entity.id());
+                let data = transform(data, &model);
+                match merged.iter_mut().position(|m| same_format(m, &data)) {
+                    Some(i) => {
+                        append(&mut merged[i], data);
+                        sources[i].push(entity);
+                    }
+                    None => {
+                        merged.push(data);
+                        sources.push(vec![entity]);
+                    }
+                }
+            }
+
+            for (data, sources) in merged.into_iter().zip(sources) {
+                let handle = loader.load_from_data(data, (), &mesh_storage);
+                let batch = entities.create();
+                for &source in &sources {
+                    if let Err(e) = hidden.insert(source, Hidden) {
+                        error!("Failed to hide a batched entity: {}", e);
+                    }
+                }
+                let inserted = meshes
+                    .insert(batch, handle)
+                    .and_then(|_| materials.insert(batch, material.clone()))
+                    .and_then(|_| globals.insert(batch, GlobalTransform::default()))
+                    .and_then(|_| match color {
+                        Some(color) => rgba.insert(batch, color).map(|_| ()),
+                        None => Ok(()),
+                    })
+                    .and_then(|_| {
+                        batches
+                            .insert(batch, StaticBatch { entities: sources })
+                            .map(|_| ())
+                    });
+                if let Err(e) = inserted {
+                    error!("Failed to create a static batch: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// Checks whether two meshes have the same vertex format, so they can be appended.
+fn same_format(a: &MeshData, b: &MeshData) -> bool {
+    use std::mem::discriminant;
+
+    discriminant(a) == discriminant(b)
+}
+
+/// Appends the vertices of `from` to `into`, which have the same vertex format.
+fn append(into: &mut MeshData, from: MeshData) {
+    match (into, from) {
+        (MeshData::PosColor(into), MeshData::PosColor(from)) => into.extend(from),
+        (MeshData::PosColorNorm(into), MeshData::PosColorNorm(from)) => into.extend(from),
+        (MeshData::PosColorNormTex(into), MeshData::PosColorNormTex(from)) => into.extend(from),
+        (MeshData::PosTex(into), MeshData::PosTex(from)) => into.extend(from),
+        (MeshData::PosNormTex(into), MeshData::PosNormTex(from)) => into.extend(from),
+        (MeshData::PosNormTangTex(into), MeshData::PosNormTangTex(from)) => into.extend(from),
+        _ => unreachable!("Meshes of different vertex formats can't be appended"),
+    }
+}
+
+/// Returns the vertices of a mesh moved into world space by its model matrix.
+fn transform(data: &MeshData, model: &Matrix4<f32>) -> MeshData {
+    let position = |p: Vector3<f32>| (model * Point3::from(p).to_homogeneous()).xyz();
+    let linear: Matrix3<f32> = model.fixed_slice::<U3, U3>(0, 0).into_owned();
+    let normals = linear
+        .try_inverse()
+        .map(|inverse| inverse.transpose())
+        .unwrap_or(linear);
+    let normal = |n: Vector3<f32>| (normals * n).try_normalize(0.0).unwrap_or(n);
+    let tangent = |t: Vector3<f32>| (linear * t).try_normalize(0.0).unwrap_or(t);
+
+    let mut data = data.clone();
+    match data {
+        MeshData::PosColor(ref mut vertices) => {
+            for v in vertices {
+                v.position = position(v.position);
+            }
+        }
+        MeshData::PosColorNorm(ref mut vertices) => {
+            for v in vertices {
+                v.position = position(v.position);
+                v.normal = normal(v.normal);
+            }
+        }
+        MeshData::PosColorNormTex(ref mut vertices) => {
+            for v in vertices {
+                v.position = position(v.position);
+                v.normal = normal(v.normal);
+            }
+        }
+        MeshData::PosTex(ref mut vertices) => {
+            for v in vertices {
+                v.position = position(v.position);
+            }
+        }
+        MeshData::PosNormTex(ref mut vertices) => {
+            for v in vertices {
+                v.position = position(v.position);
+                v.normal = normal(v.normal);
+            }
+        }
+        MeshData::PosNormTangTex(ref mut vertices) => {
+            for v in vertices {
+                v.position = position(v.position);
+                v.normal = normal(v.normal);
+                v.tangent = tangent(v.tangent);
+            }
+        }
+        MeshData::Creator(_) => {}
+    }
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_core::nalgebra::{Translation3, Vector2};
+
+    use super::*;
+    use crate::vertex::{PosNormTex, PosTex};
+
+    #[test]
+    fn transform_and_append() {
+        let vertex = PosNormTex {
+            position: Vector3::new(1.0, 0.0, 0.0),
+            normal: Vector3::new(0.0, 1.0, 0.0),
+            tex_coord: Vector2::zeros(),
+        };
+        let mesh = MeshData::PosNormTex(vec![vertex; 3]);
+        let model = Translation3::new(0.0, 0.0, 2.0).to_homogeneous()
+            * Matrix4::new_nonuniform_scaling(&Vector3::new(2.0, 4.0, 1.0));
+
+        let mut merged = transform(&mesh, &model);
+        append(&mut merged, transform(&mesh, &Matrix4::identity()));
+        match merged {
+            MeshData::PosNormTex(ref vertices) => {
+                assert_eq!(6, vertices.len());
+                assert_eq!(Vector3::new(2.0, 0.0, 2.0), vertices[0].position);
+                assert_eq!(Vector3::new(0.0, 1.0, 0.0), vertices[0].normal);
+                assert_eq!(Vector3::new(1.0, 0.0, 0.0), vertices[3].position);
+            }
+            _ => panic!("Wrong vertex format"),
+        }
+        assert!(!same_format(
+            &merged,
+            &MeshData::PosTex(vec![PosTex {
+                position: Vector3::zeros(),
+                tex_coord: Vector2::zeros(),
+            }])
+        ));
+    }
+}
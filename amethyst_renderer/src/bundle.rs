@@ -1,17 +1,22 @@
 //! ECS rendering bundle
 
-use amethyst_assets::Processor;
+use amethyst_assets::{AssetMemorySystem, Processor};
 use amethyst_core::{
     bundle::{Result, ResultExt, SystemBundle},
     specs::prelude::DispatcherBuilder,
+    MemoryCategory,
 };
 
 use crate::{
     config::DisplayConfig,
+    error::Result as RenderResult,
+    mesh::Mesh,
     pipe::{PipelineBuild, PolyPipeline},
+    renderer::Renderer,
     sprite::SpriteSheet,
     sprite_visibility::SpriteVisibilitySortingSystem,
     system::RenderSystem,
+    tex::Texture,
     visibility::VisibilitySortingSystem,
     HideHierarchySystem,
 };
@@ -33,9 +38,11 @@ where
     pipe: B,
     config: Option<DisplayConfig>,
     visibility_sorting: Option<&'a [&'a str]>,
+    occlusion_culling: Option<(usize, usize)>,
     sprite_visibility_sorting: Option<&'a [&'a str]>,
     sprite_sheet_processor_enabled: bool,
     hide_hierarchy_system_enabled: bool,
+    rebuild: Option<Box<dyn FnMut(&mut Renderer) -> RenderResult<P>>>,
 }
 
 impl<'a, B, P> RenderBundle<'a, B, P>
@@ -49,9 +56,11 @@ where
             pipe,
             config,
             visibility_sorting: None,
+            occlusion_culling: None,
             sprite_visibility_sorting: None,
             sprite_sheet_processor_enabled: false,
             hide_hierarchy_system_enabled: false,
+            rebuild: None,
         }
     }
 
@@ -61,6 +70,14 @@ where
         self
     }
 
+    /// Enable occlusion culling in the mesh sorting, with a depth buffer of the given size
+    ///
+    /// Only has an effect together with `with_visibility_sorting`.
+    pub fn with_occlusion_culling(mut self, width: usize, height: usize) -> Self {
+        self.occlusion_culling = Some((width, height));
+        self
+    }
+
     /// Enable transparent sprite sorting, with the given dependencies
     pub fn with_sprite_visibility_sorting(mut self, dep: &'a [&'a str]) -> Self {
         self.sprite_visibility_sorting = Some(dep);
@@ -84,16 +101,34 @@ where
     }
 }
 
+impl<'a, B, P> RenderBundle<'a, B, P>
+where
+    B: PipelineBuild<Pipeline = P> + Clone + 'static,
+    P: PolyPipeline,
+{
+    /// Enable recovery from device loss, by building the pipeline again on a new device.
+    ///
+    /// This keeps a copy of the data of the `Mesh` and `Texture` assets in memory to upload them
+    /// again. See `RenderEvent` for what is and isn't recovered.
+    pub fn with_device_recovery(mut self) -> Self {
+        let pipe = self.pipe.clone();
+        self.rebuild = Some(Box::new(move |renderer: &mut Renderer| {
+            renderer.create_pipe(pipe.clone())
+        }));
+        self
+    }
+}
+
 impl<'a, 'b, 'c, B: PipelineBuild<Pipeline = P>, P: 'b + PolyPipeline> SystemBundle<'a, 'b>
     for RenderBundle<'c, B, P>
 {
     fn build(self, builder: &mut DispatcherBuilder<'a, 'b>) -> Result<()> {
         if let Some(dep) = self.visibility_sorting {
-            builder.add(
-                VisibilitySortingSystem::new(),
-                "visibility_sorting_system",
-                dep,
-            );
+            let mut system = VisibilitySortingSystem::new();
+            if let Some((width, height)) = self.occlusion_culling {
+                system = system.with_occlusion_culling(width, height);
+            }
+            builder.add(system, "visibility_sorting_system", dep);
         };
         if let Some(dep) = self.sprite_visibility_sorting {
             builder.add(
@@ -116,9 +151,22 @@ impl<'a, 'b, 'c, B: PipelineBuild<Pipeline = P>, P: 'b + PolyPipeline> SystemBun
                 &["parent_hierarchy_system"],
             );
         }
-        builder.add_thread_local(
-            RenderSystem::build(self.pipe, self.config).chain_err(|| "Renderer error!")?,
+        builder.add(
+            AssetMemorySystem::<Texture>::new(MemoryCategory::Assets),
+            "texture_memory",
+            &[],
         );
+        builder.add(
+            AssetMemorySystem::<Mesh>::new(MemoryCategory::Assets),
+            "mesh_memory",
+            &[],
+        );
+        let mut system =
+            RenderSystem::build(self.pipe, self.config).chain_err(|| "Renderer error!")?;
+        if let Some(rebuild) = self.rebuild {
+            system = system.with_boxed_device_recovery(rebuild);
+        }
+        builder.add_thread_local(system);
         Ok(())
     }
 }
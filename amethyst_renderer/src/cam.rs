@@ -2,10 +2,13 @@
 
 use amethyst_assets::{PrefabData, PrefabError};
 use amethyst_core::{
-    nalgebra::{Matrix4, Orthographic3, Perspective3},
+    nalgebra::{Matrix4, Orthographic3, Perspective3, Point2, Point3, Vector3, Vector4},
     specs::prelude::{Component, Entity, HashMapStorage, Write, WriteStorage},
+    GlobalTransform,
 };
 
+use crate::{layers::RenderLayers, resources::ScreenDimensions};
+
 /// The projection mode of a `Camera`.
 ///
 /// TODO: Remove and integrate with `Camera`.
@@ -41,7 +44,10 @@ impl From<Projection> for Camera {
             Projection::Perspective(p) => p.to_homogeneous(),
             Projection::Orthographic(o) => o.to_homogeneous(),
         };
-        Camera { proj }
+        Camera {
+            proj,
+            layers: RenderLayers::all(),
+        }
     }
 }
 
@@ -50,6 +56,9 @@ impl From<Projection> for Camera {
 pub struct Camera {
     /// Graphical projection of the camera.
     pub proj: Matrix4<f32>,
+    /// The render layers the camera sees, all of them by default.
+    #[serde(default = "RenderLayers::all")]
+    pub layers: RenderLayers,
 }
 
 impl Camera {
@@ -73,6 +82,180 @@ impl Camera {
             std::f32::consts::FRAC_PI_3,
         ))
     }
+
+    /// Sets the render layers the camera sees.
+    pub fn with_layers(mut self, layers: RenderLayers) -> Self {
+        self.layers = layers;
+        self
+    }
+
+    /// Returns the ray from the camera through the given position on the screen, e.g. for mouse
+    /// picking.
+    ///
+    /// The screen position is in pixels, with the origin at the top left corner of the window, as
+    /// reported by window events. `transform` is the `GlobalTransform` of the camera entity.
+    /// The ray starts on the near plane, so it works for orthographic cameras too.
+    pub fn screen_to_world_ray(
+        &self,
+        screen_position: Point2<f32>,
+        dimensions: &ScreenDimensions,
+        transform: &GlobalTransform,
+    ) -> Ray {
+        let x = 2. * screen_position.x / dimensions.width() - 1.;
+        let y = 1. - 2. * screen_position.y / dimensions.height();
+        let inverse = transform.0
+            * self
+                .proj
+                .try_inverse()
+                .expect("Unable to get inverse of camera projection");
+        let unproject = |z: f32| {
+            let point = inverse * Vector4::new(x, y, z, 1.);
+            Point3::from(point.xyz() / point.w)
+        };
+        let near = unproject(-1.);
+        let far = unproject(1.);
+        Ray {
+            origin: near,
+            direction: (far - near).normalize(),
+        }
+    }
+
+    /// Returns the position on the screen of a point in the world, in pixels with the origin at
+    /// the top left corner of the window, or `None` if the point is behind the camera.
+    ///
+    /// `transform` is the `GlobalTransform` of the camera entity. Points outside the view return
+    /// positions outside the window.
+    pub fn world_to_screen(
+        &self,
+        world_position: Point3<f32>,
+        dimensions: &ScreenDimensions,
+        transform: &GlobalTransform,
+    ) -> Option<Point2<f32>> {
+        let view = transform
+            .0
+            .try_inverse()
+            .expect("Unable to get inverse of camera transform");
+        let clip = self.proj * view * world_position.to_homogeneous();
+        if clip.w <= 0. {
+            return None;
+        }
+        Some(Point2::new(
+            (clip.x / clip.w + 1.) / 2. * dimensions.width(),
+            (1. - clip.y / clip.w) / 2. * dimensions.height(),
+        ))
+    }
+
+    /// Returns the view frustum of the camera in world space, for culling.
+    ///
+    /// `transform` is the `GlobalTransform` of the camera entity.
+    pub fn frustum(&self, transform: &GlobalTransform) -> Frustum {
+        let view = transform
+            .0
+            .try_inverse()
+            .expect("Unable to get inverse of camera transform");
+        let m = self.proj * view;
+        let row = |i: usize| m.row(i).transpose();
+        let (x, y, z, w) = (row(0), row(1), row(2), row(3));
+        Frustum {
+            planes: [
+                Plane::from_coefficients(w + x),
+                Plane::from_coefficients(w - x),
+                Plane::from_coefficients(w + y),
+                Plane::from_coefficients(w - y),
+                Plane::from_coefficients(w + z),
+                Plane::from_coefficients(w - z),
+            ],
+        }
+    }
+}
+
+/// A ray in world space, returned by `Camera::screen_to_world_ray`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ray {
+    /// Start of the ray.
+    pub origin: Point3<f32>,
+    /// Normalized direction of the ray.
+    pub direction: Vector3<f32>,
+}
+
+impl Ray {
+    /// Returns the point at the given distance along the ray.
+    pub fn at(&self, distance: f32) -> Point3<f32> {
+        self.origin + self.direction * distance
+    }
+
+    /// Returns the distance along the ray to its intersection with the plane, or `None` if the
+    /// ray is parallel to the plane or points away from it.
+    pub fn intersect_plane(&self, plane: &Plane) -> Option<f32> {
+        let denominator = plane.normal.dot(&self.direction);
+        if denominator.abs() <= std::f32::EPSILON {
+            return None;
+        }
+        let distance = -plane.signed_distance(&self.origin) / denominator;
+        if distance >= 0. {
+            Some(distance)
+        } else {
+            None
+        }
+    }
+}
+
+/// A plane, made of the points `p` for which `normal.dot(p) + distance` is zero.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Plane {
+    /// Normalized normal of the plane.
+    pub normal: Vector3<f32>,
+    /// Distance of the plane from the origin, along the negated normal.
+    pub distance: f32,
+}
+
+impl Plane {
+    /// Creates a plane with the given normal through the given point.
+    pub fn new(normal: Vector3<f32>, point: Point3<f32>) -> Self {
+        let normal = normal.normalize();
+        Plane {
+            normal,
+            distance: -normal.dot(&point.coords),
+        }
+    }
+
+    fn from_coefficients(coefficients: Vector4<f32>) -> Self {
+        let length = coefficients.xyz().norm();
+        Plane {
+            normal: coefficients.xyz() / length,
+            distance: coefficients.w / length,
+        }
+    }
+
+    /// Returns the distance of the point from the plane, positive on the side the normal points
+    /// to.
+    pub fn signed_distance(&self, point: &Point3<f32>) -> f32 {
+        self.normal.dot(&point.coords) + self.distance
+    }
+}
+
+/// The view frustum of a camera, returned by `Camera::frustum`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frustum {
+    /// The left, right, bottom, top, near and far planes, with normals pointing inside.
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    /// Checks whether the point is inside the frustum.
+    pub fn contains_point(&self, point: &Point3<f32>) -> bool {
+        self.planes.iter().all(|p| p.signed_distance(point) >= 0.)
+    }
+
+    /// Checks whether a sphere is at least partially inside the frustum.
+    ///
+    /// This is conservative: spheres close to the corners of the frustum may be reported as
+    /// visible even if they are not.
+    pub fn intersects_sphere(&self, center: &Point3<f32>, radius: f32) -> bool {
+        self.planes
+            .iter()
+            .all(|p| p.signed_distance(center) >= -radius)
+    }
 }
 
 impl Component for Camera {
@@ -121,7 +304,15 @@ impl<'a> PrefabData<'a> for CameraPrefab {
             CameraPrefab::Orthographic(ortho) => ortho.to_homogeneous(),
             CameraPrefab::Perspective(perspective) => perspective.to_homogeneous(),
         };
-        storage.insert(entity, Camera { proj }).map(|_| ())
+        storage
+            .insert(
+                entity,
+                Camera {
+                    proj,
+                    layers: RenderLayers::all(),
+                },
+            )
+            .map(|_| ())
     }
 }
 
@@ -424,3 +615,34 @@ mod serde_persp {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn screen_ray_and_projection_round_trip() {
+        let camera = Camera::standard_3d(800., 600.);
+        let dimensions = ScreenDimensions::new(800, 600, 1.);
+        let transform = GlobalTransform(Matrix4::new_translation(&Vector3::new(1., 2., 10.)));
+
+        let point = Point3::new(2., 1., 0.);
+        let screen = camera
+            .world_to_screen(point, &dimensions, &transform)
+            .unwrap();
+        let ray = camera.screen_to_world_ray(screen, &dimensions, &transform);
+        let hit = ray
+            .intersect_plane(&Plane::new(Vector3::z(), Point3::origin()))
+            .map(|distance| ray.at(distance))
+            .unwrap();
+        assert!((hit - point).norm() < 1.0e-3);
+
+        let center = camera.screen_to_world_ray(Point2::new(400., 300.), &dimensions, &transform);
+        assert!((center.direction + Vector3::z()).norm() < 1.0e-5);
+
+        let frustum = camera.frustum(&transform);
+        assert!(frustum.contains_point(&point));
+        assert!(!frustum.contains_point(&Point3::new(1., 2., 20.)));
+        assert!(frustum.intersects_sphere(&Point3::new(1., 2., 10.5), 1.));
+    }
+}
@@ -0,0 +1,101 @@
+//! Read back of rendered frames.
+
+/// A single frame read back from the main render target.
+///
+/// Pixels are tightly packed RGBA8 values, with the top row of the image first.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CapturedFrame {
+    /// Width of the frame in pixels.
+    pub width: u32,
+    /// Height of the frame in pixels.
+    pub height: u32,
+    /// RGBA8 pixel data, `width * height * 4` bytes long.
+    pub pixels: Vec<u8>,
+}
+
+impl CapturedFrame {
+    /// Creates a frame from pixel rows ordered bottom to top, as they are returned by the
+    /// graphics API.
+    pub fn from_bottom_up(width: u32, height: u32, mut pixels: Vec<u8>) -> Self {
+        let stride = width as usize * 4;
+        let rows = height as usize;
+        for row in 0..rows / 2 {
+            let (top, bottom) = pixels.split_at_mut((rows - row - 1) * stride);
+            top[row * stride..(row + 1) * stride].swap_with_slice(&mut bottom[..stride]);
+        }
+        CapturedFrame {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Returns the RGBA value of the pixel at the given coordinates, if it is in bounds.
+    pub fn pixel(&self, x: u32, y: u32) -> Option<[u8; 4]> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        Some([
+            self.pixels[i],
+            self.pixels[i + 1],
+            self.pixels[i + 2],
+            self.pixels[i + 3],
+        ])
+    }
+}
+
+/// Resource used to request a copy of the next presented frame.
+///
+/// Call `request` and the `RenderSystem` will read back the main render target right before the
+/// buffers are swapped. The result can be collected with `take` on any later frame.
+#[derive(Debug, Default)]
+pub struct FrameCapture {
+    requested: bool,
+    frame: Option<CapturedFrame>,
+}
+
+impl FrameCapture {
+    /// Requests that the next rendered frame is captured.
+    pub fn request(&mut self) {
+        self.requested = true;
+    }
+
+    /// Returns true if a capture was requested but has not been performed yet.
+    pub fn is_pending(&self) -> bool {
+        self.requested
+    }
+
+    /// Takes the captured frame, if one is available.
+    pub fn take(&mut self) -> Option<CapturedFrame> {
+        self.frame.take()
+    }
+
+    pub(crate) fn take_request(&mut self) -> bool {
+        std::mem::replace(&mut self.requested, false)
+    }
+
+    pub(crate) fn complete(&mut self, frame: CapturedFrame) {
+        self.frame = Some(frame);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CapturedFrame;
+
+    #[test]
+    fn from_bottom_up_flips_rows() {
+        let pixels = vec![
+            1, 1, 1, 1, 2, 2, 2, 2, // bottom row
+            3, 3, 3, 3, 4, 4, 4, 4, // middle row
+            5, 5, 5, 5, 6, 6, 6, 6, // top row
+        ];
+        let frame = CapturedFrame::from_bottom_up(2, 3, pixels);
+
+        assert_eq!(Some([5, 5, 5, 5]), frame.pixel(0, 0));
+        assert_eq!(Some([4, 4, 4, 4]), frame.pixel(1, 1));
+        assert_eq!(Some([2, 2, 2, 2]), frame.pixel(1, 2));
+        assert_eq!(None, frame.pixel(2, 0));
+    }
+}
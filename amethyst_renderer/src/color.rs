@@ -6,7 +6,12 @@ use gfx::shade::{Formatted, ToUniform};
 use gfx_core::shade::{BaseType, ContainerType, UniformValue};
 use glsl_layout::{vec3, vec4};
 
-/// An RGBA color value.
+/// An RGBA color value, in linear space.
+///
+/// Lights, tints, clear colors and the UI all take linear colors, which the shaders blend and
+/// light before the output encodes them to sRGB. Colors picked in an image editor or written as
+/// hex codes are in sRGB, convert them with `SrgbRgba`, or they end up gamma corrected twice and
+/// look washed out.
 ///
 /// ## As a Component
 /// If you attach this as a component to an entity then passes should multiply any rendered pixels
@@ -65,6 +70,18 @@ impl Rgba {
     }
 }
 
+impl Rgba {
+    /// Returns the color encoded in sRGB.
+    pub fn to_srgb(self) -> SrgbRgba {
+        SrgbRgba(
+            linear_to_srgb(self.0),
+            linear_to_srgb(self.1),
+            linear_to_srgb(self.2),
+            self.3,
+        )
+    }
+}
+
 impl Default for Rgba {
     fn default() -> Rgba {
         Rgba::black()
@@ -146,3 +163,101 @@ impl From<Rgba> for vec4 {
         [r, g, b, a].into()
     }
 }
+
+/// A linear RGBA color, the one used everywhere in the renderer.
+pub type LinearRgba = Rgba;
+
+/// An RGBA color encoded in sRGB, like the colors of image editors and hex codes.
+///
+/// It doesn't convert to arrays on purpose, so it can't be given where a linear color is
+/// expected without going through `Rgba`. The alpha is linear in both.
+///
+/// ```rust
+/// # use amethyst_renderer::{Rgba, SrgbRgba};
+/// let orange: Rgba = SrgbRgba::from_hex(0xff_80_00_ff).into();
+/// assert!(orange.1 < 0.25);
+/// ```
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, PartialOrd, Serialize)]
+pub struct SrgbRgba(pub f32, pub f32, pub f32, pub f32);
+
+impl SrgbRgba {
+    /// Creates the color from channels between 0 and 255.
+    pub fn from_u8(r: u8, g: u8, b: u8, a: u8) -> Self {
+        let channel = |value: u8| f32::from(value) / 255.0;
+        SrgbRgba(channel(r), channel(g), channel(b), channel(a))
+    }
+
+    /// Creates the color from a hex code in the `0xRRGGBBAA` order.
+    pub fn from_hex(hex: u32) -> Self {
+        let channel = |shift: u32| ((hex >> shift) & 0xff) as u8;
+        SrgbRgba::from_u8(channel(24), channel(16), channel(8), channel(0))
+    }
+
+    /// Returns the color in linear space.
+    pub fn to_linear(self) -> Rgba {
+        Rgba(
+            srgb_to_linear(self.0),
+            srgb_to_linear(self.1),
+            srgb_to_linear(self.2),
+            self.3,
+        )
+    }
+}
+
+impl From<SrgbRgba> for Rgba {
+    fn from(color: SrgbRgba) -> Rgba {
+        color.to_linear()
+    }
+}
+
+impl From<Rgba> for SrgbRgba {
+    fn from(color: Rgba) -> SrgbRgba {
+        color.to_srgb()
+    }
+}
+
+/// The space the values of colors and textures are in.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum ColorSpace {
+    /// Linear values, which can be blended and lit as they are. Normal, roughness and other data
+    /// maps are linear too.
+    Linear,
+    /// Values encoded in sRGB, which the GPU decodes to linear when sampling the textures.
+    Srgb,
+}
+
+/// Decodes a channel encoded in sRGB to linear.
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.040_45 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Encodes a linear channel in sRGB.
+pub fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn srgb_round_trips_through_linear() {
+        let color = SrgbRgba::from_hex(0x80_20_ff_80);
+        let linear = color.to_linear();
+        assert!((linear.0 - 0.2158).abs() < 1e-3);
+        assert!((linear.2 - 1.0).abs() < 1e-6);
+        assert!((linear.3 - color.3).abs() < 1e-6);
+        let back = linear.to_srgb();
+        for &(a, b) in &[(back.0, color.0), (back.1, color.1), (back.2, color.2)] {
+            assert!((a - b).abs() < 1e-5);
+        }
+    }
+}
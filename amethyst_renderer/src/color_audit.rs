@@ -0,0 +1,103 @@
+//! Finding the textures in the wrong color space.
+
+use amethyst_assets::AssetStorage;
+use amethyst_core::specs::prelude::{Entities, Entity, Join, Read, ReadStorage, System, Write};
+
+use crate::{
+    color::ColorSpace,
+    mtl::Material,
+    tex::{Texture, TextureHandle},
+};
+
+/// A map of a `Material` loaded in the wrong color space.
+///
+/// An albedo map loaded as `Unorm` isn't decoded from sRGB and is drawn washed out, while a normal
+/// map loaded as `Srgb` is decoded although it holds directions, bending the lighting.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColorIssue {
+    /// The entity with the material.
+    pub entity: Entity,
+    /// The name of the map, e.g. `"albedo"`.
+    pub map: &'static str,
+    /// The color space the map should be in.
+    pub expected: ColorSpace,
+    /// The color space the texture was loaded in.
+    pub found: ColorSpace,
+}
+
+/// Resource with the issues found by the `ColorAuditSystem`.
+#[derive(Clone, Debug, Default)]
+pub struct ColorAudit {
+    issues: Vec<ColorIssue>,
+}
+
+impl ColorAudit {
+    /// Returns the issues found so far.
+    pub fn issues(&self) -> &[ColorIssue] {
+        &self.issues
+    }
+
+    /// Forgets the issues, e.g. after the textures are reloaded.
+    pub fn clear(&mut self) {
+        self.issues.clear();
+    }
+}
+
+/// Checks the color space of the maps of the `Material`s, logging a warning for each issue
+/// found and keeping them in the `ColorAudit`.
+///
+/// The color maps, albedo and emission, should be `Srgb`, the other maps `Unorm`. The audit runs
+/// every frame, so it's best left to development builds.
+#[derive(Default)]
+pub struct ColorAuditSystem;
+
+impl<'a> System<'a> for ColorAuditSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, AssetStorage<Texture>>,
+        ReadStorage<'a, Material>,
+        Write<'a, ColorAudit>,
+    );
+
+    fn run(&mut self, (entities, textures, materials, mut audit): Self::SystemData) {
+        for (entity, material) in (&*entities, &materials).join() {
+            let maps: [(&'static str, &TextureHandle, ColorSpace); 7] = [
+                ("albedo", &material.albedo, ColorSpace::Srgb),
+                ("emission", &material.emission, ColorSpace::Srgb),
+                ("normal", &material.normal, ColorSpace::Linear),
+                ("metallic", &material.metallic, ColorSpace::Linear),
+                ("roughness", &material.roughness, ColorSpace::Linear),
+                (
+                    "ambient_occlusion",
+                    &material.ambient_occlusion,
+                    ColorSpace::Linear,
+                ),
+                ("caveat", &material.caveat, ColorSpace::Linear),
+            ];
+            for &(map, handle, expected) in &maps {
+                let found = match textures.get(handle) {
+                    Some(texture) => texture.color_space(),
+                    None => continue,
+                };
+                let known = || {
+                    audit
+                        .issues
+                        .iter()
+                        .any(|issue| issue.entity == entity && issue.map == map)
+                };
+                if found != expected && !known() {
+                    warn!(
+                        "The {} map of the material of {:?} is {:?} instead of {:?}",
+                        map, entity, found, expected
+                    );
+                    audit.issues.push(ColorIssue {
+                        entity,
+                        map,
+                        expected,
+                        found,
+                    });
+                }
+            }
+        }
+    }
+}
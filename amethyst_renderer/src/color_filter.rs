@@ -0,0 +1,160 @@
+//! Remapping of the colors of the screen for color vision deficiencies.
+
+/// Common color vision deficiencies.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Deserialize, Serialize)]
+pub enum ColorDeficiency {
+    /// Missing red cones, confusing reds with greens and darkening reds.
+    Protanopia,
+    /// Missing green cones, confusing reds with greens.
+    Deuteranopia,
+    /// Missing blue cones, confusing blues with greens and yellows with violets.
+    Tritanopia,
+}
+
+impl ColorDeficiency {
+    /// The colors seen with the deficiency, as rows of weights of the red, green and blue
+    /// channels.
+    fn simulation(self) -> [[f32; 3]; 3] {
+        match self {
+            ColorDeficiency::Protanopia => [
+                [0.567, 0.433, 0.0],
+                [0.558, 0.442, 0.0],
+                [0.0, 0.242, 0.758],
+            ],
+            ColorDeficiency::Deuteranopia => {
+                [[0.625, 0.375, 0.0], [0.7, 0.3, 0.0], [0.0, 0.3, 0.7]]
+            }
+            ColorDeficiency::Tritanopia => {
+                [[0.95, 0.05, 0.0], [0.0, 0.433, 0.567], [0.0, 0.475, 0.525]]
+            }
+        }
+    }
+}
+
+/// How `ColorFilter` changes the colors.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Deserialize, Serialize)]
+pub enum ColorFilterMode {
+    /// Moves the differences a player can't see into channels they can see, so colors that look
+    /// alike become distinct.
+    Correct,
+    /// Shows the colors as seen with the deficiency, to check that a game is playable with it.
+    Simulate,
+}
+
+/// Resource selecting the remapping of the colors drawn by `DrawColorFilter`.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ColorFilter {
+    /// The deficiency to filter for, `None` to keep the colors.
+    pub deficiency: Option<ColorDeficiency>,
+    /// Whether the colors are corrected or simulated.
+    pub mode: ColorFilterMode,
+    /// How much of the filter is applied, from `0.0` to `1.0`.
+    pub strength: f32,
+}
+
+impl Default for ColorFilter {
+    fn default() -> Self {
+        ColorFilter {
+            deficiency: None,
+            mode: ColorFilterMode::Correct,
+            strength: 1.0,
+        }
+    }
+}
+
+impl ColorFilter {
+    /// Corrects the colors for the deficiency.
+    pub fn correct(deficiency: ColorDeficiency) -> Self {
+        ColorFilter {
+            deficiency: Some(deficiency),
+            ..Default::default()
+        }
+    }
+
+    /// Simulates the deficiency.
+    pub fn simulate(deficiency: ColorDeficiency) -> Self {
+        ColorFilter {
+            deficiency: Some(deficiency),
+            mode: ColorFilterMode::Simulate,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the matrix multiplying the colors, as rows of weights of the red, green and blue
+    /// channels.
+    pub(crate) fn matrix(&self) -> [[f32; 3]; 3] {
+        let identity = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+        let simulation = match self.deficiency {
+            Some(deficiency) => deficiency.simulation(),
+            None => return identity,
+        };
+        let filter = match self.mode {
+            ColorFilterMode::Simulate => simulation,
+            ColorFilterMode::Correct => {
+                // Daltonization: the error between the colors and the simulation is added to
+                // the green and blue channels.
+                let shift = [[0.0, 0.0, 0.0], [0.7, 1.0, 0.0], [0.7, 0.0, 1.0]];
+                matrix_from(|row, column| {
+                    identity[row][column]
+                        + (0..3)
+                            .map(|i| shift[row][i] * (identity[i][column] - simulation[i][column]))
+                            .sum::<f32>()
+                })
+            }
+        };
+        let strength = self.strength.max(0.0).min(1.0);
+        matrix_from(|row, column| {
+            identity[row][column] + (filter[row][column] - identity[row][column]) * strength
+        })
+    }
+}
+
+fn matrix_from(element: impl Fn(usize, usize) -> f32) -> [[f32; 3]; 3] {
+    let mut matrix = [[0.0; 3]; 3];
+    for (row, values) in matrix.iter_mut().enumerate() {
+        for (column, value) in values.iter_mut().enumerate() {
+            *value = element(row, column);
+        }
+    }
+    matrix
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(matrix: [[f32; 3]; 3], color: [f32; 3]) -> Vec<f32> {
+        matrix
+            .iter()
+            .map(|row| row.iter().zip(&color).map(|(m, c)| m * c).sum())
+            .collect()
+    }
+
+    #[test]
+    fn grays_stay_gray() {
+        for &deficiency in &[
+            ColorDeficiency::Protanopia,
+            ColorDeficiency::Deuteranopia,
+            ColorDeficiency::Tritanopia,
+        ] {
+            for filter in &[
+                ColorFilter::correct(deficiency),
+                ColorFilter::simulate(deficiency),
+            ] {
+                for out in apply(filter.matrix(), [0.5; 3]) {
+                    assert!((out - 0.5).abs() < 1e-3);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn no_strength_keeps_colors() {
+        let filter = ColorFilter {
+            strength: 0.0,
+            ..ColorFilter::correct(ColorDeficiency::Deuteranopia)
+        };
+        assert_eq!(ColorFilter::default().matrix(), filter.matrix());
+    }
+}
@@ -0,0 +1,323 @@
+//! Compute shaders dispatched before the pipeline draws a frame.
+
+use std::{
+    mem,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+};
+
+use gfx::{memory::cast_slice, traits::Pod};
+
+use crate::{
+    error::Result,
+    tex::TextureHandle,
+    types::{Factory, RawBuffer},
+};
+
+static NEXT_SHADER: AtomicUsize = AtomicUsize::new(0);
+
+/// The GLSL source of a compute shader, compiled by the renderer the first time it's dispatched.
+#[derive(Clone, Debug)]
+pub struct ComputeShader {
+    id: usize,
+    source: Arc<str>,
+}
+
+impl ComputeShader {
+    /// Creates a shader from its source, which has to start with a `#version` of at least 430.
+    pub fn new<S: Into<String>>(source: S) -> Self {
+        ComputeShader {
+            id: NEXT_SHADER.fetch_add(1, Ordering::Relaxed),
+            source: source.into().into(),
+        }
+    }
+
+    /// Returns the source of the shader.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub(crate) fn id(&self) -> usize {
+        self.id
+    }
+}
+
+struct BufferState {
+    data: Vec<u8>,
+    stride: usize,
+    raw: Option<RawBuffer>,
+}
+
+/// A buffer compute shaders read and write, shared with the render passes binding it, e.g. as the
+/// vertex buffer of GPU particles or skinned vertices.
+///
+/// The buffer is created on the device by the first dispatch binding it, or by `create` from
+/// the `Pass::compile` of a render pass. Clones refer to the same buffer.
+#[derive(Clone)]
+pub struct StorageBuffer {
+    state: Arc<Mutex<BufferState>>,
+}
+
+impl StorageBuffer {
+    /// Creates a buffer holding the elements.
+    pub fn new<T: Pod>(data: &[T]) -> Self {
+        StorageBuffer::from_bytes(cast_slice(data).to_vec(), mem::size_of::<T>())
+    }
+
+    /// Creates a buffer of `count` zeroed elements.
+    pub fn zeroed<T: Pod>(count: usize) -> Self {
+        let stride = mem::size_of::<T>();
+        StorageBuffer::from_bytes(vec![0; count * stride], stride)
+    }
+
+    fn from_bytes(data: Vec<u8>, stride: usize) -> Self {
+        StorageBuffer {
+            state: Arc::new(Mutex::new(BufferState {
+                data,
+                stride,
+                raw: None,
+            })),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BufferState> {
+        self.state
+            .lock()
+            .expect("The mutex of a `StorageBuffer` was poisoned")
+    }
+
+    /// Returns the size of the buffer in bytes.
+    pub fn size(&self) -> usize {
+        self.lock().data.len()
+    }
+
+    /// Returns the buffer on the device, if it was created.
+    pub fn raw(&self) -> Option<RawBuffer> {
+        self.lock().raw.clone()
+    }
+
+    /// Creates the buffer on the device with its initial data, if it wasn't created yet.
+    pub fn create(&self, factory: &mut Factory) -> Result<RawBuffer> {
+        use gfx::{buffer::Role, memory::Bind, Factory};
+
+        let mut state = self.lock();
+        if let Some(ref raw) = state.raw {
+            return Ok(raw.clone());
+        }
+        let raw = factory.create_buffer_immutable_raw(
+            &state.data,
+            state.stride,
+            Role::Vertex,
+            Bind::UNORDERED_ACCESS,
+        )?;
+        state.raw = Some(raw.clone());
+        Ok(raw)
+    }
+
+    /// Forgets the buffer on the device, to create it again on a new one.
+    pub(crate) fn reset(&self) {
+        self.lock().raw = None;
+    }
+}
+
+/// How a compute shader accesses an image.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ImageAccess {
+    /// The shader only reads the image.
+    Read,
+    /// The shader only writes the image.
+    Write,
+    /// The shader reads and writes the image.
+    ReadWrite,
+}
+
+/// A compute shader, the buffers and images it binds, and the number of work groups to dispatch.
+#[derive(Clone)]
+pub struct ComputePass {
+    /// The shader.
+    pub shader: ComputeShader,
+    /// The number of work groups in each dimension.
+    pub groups: [u32; 3],
+    /// Whether the pass is dispatched.
+    pub enabled: bool,
+    pub(crate) buffers: Vec<(u32, StorageBuffer)>,
+    pub(crate) images: Vec<(u32, TextureHandle, ImageAccess)>,
+}
+
+impl ComputePass {
+    /// Creates an enabled pass dispatching the shader over the work groups.
+    pub fn new(shader: ComputeShader, groups: [u32; 3]) -> Self {
+        ComputePass {
+            shader,
+            groups,
+            enabled: true,
+            buffers: Vec::new(),
+            images: Vec::new(),
+        }
+    }
+
+    /// Binds a buffer to the shader storage block with the `binding`.
+    pub fn with_buffer(mut self, binding: u32, buffer: StorageBuffer) -> Self {
+        self.buffers.push((binding, buffer));
+        self
+    }
+
+    /// Binds the first mipmap level of a texture to the image unit with the `binding`.
+    ///
+    /// The texture has to use the `R8_G8_B8_A8` format with the `Unorm` channel type, or
+    /// `R16_G16_B16_A16` or `R32_G32_B32_A32` with `Float`.
+    pub fn with_image(mut self, binding: u32, texture: TextureHandle, access: ImageAccess) -> Self {
+        self.images.push((binding, texture, access));
+        self
+    }
+}
+
+/// Resource holding the `ComputePass`es the `RenderSystem` dispatches before it draws each frame,
+/// in the order they were first inserted.
+///
+/// Compute passes are only supported by the OpenGL backend, and need OpenGL 4.3. Their results
+/// can be used by every render pass of the frame.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let particles = StorageBuffer::zeroed::<Particle>(4096);
+/// world.write_resource::<ComputePasses>().insert(
+///     "particles",
+///     ComputePass::new(ComputeShader::new(UPDATE_PARTICLES), [4096 / 64, 1, 1])
+///         .with_buffer(0, particles.clone()),
+/// );
+/// ```
+#[derive(Clone, Default)]
+pub struct ComputePasses {
+    pub(crate) passes: Vec<(String, ComputePass)>,
+}
+
+impl ComputePasses {
+    /// Creates an empty set of passes.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Inserts a pass, replacing the pass with the same name if there is one.
+    pub fn insert<N: Into<String>>(&mut self, name: N, pass: ComputePass) {
+        let name = name.into();
+        match self.passes.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, existing)) => *existing = pass,
+            None => self.passes.push((name, pass)),
+        }
+    }
+
+    /// Returns the pass with the name.
+    pub fn get(&self, name: &str) -> Option<&ComputePass> {
+        self.passes
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, pass)| pass)
+    }
+
+    /// Returns the pass with the name mutably, e.g. to change its number of work groups.
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut ComputePass> {
+        self.passes
+            .iter_mut()
+            .find(|(n, _)| n == name)
+            .map(|(_, pass)| pass)
+    }
+
+    /// Removes the pass with the name.
+    pub fn remove(&mut self, name: &str) -> Option<ComputePass> {
+        let index = self.passes.iter().position(|(n, _)| n == name)?;
+        Some(self.passes.remove(index).1)
+    }
+
+    /// Checks whether there is no pass.
+    pub fn is_empty(&self) -> bool {
+        self.passes.is_empty()
+    }
+
+    /// Forgets the buffers of the passes on the device, to create them again on a new one.
+    pub(crate) fn reset(&self) {
+        for (_, pass) in &self.passes {
+            for (_, buffer) in &pass.buffers {
+                buffer.reset();
+            }
+        }
+    }
+}
+
+/// Compiles and links a compute shader, returning the program or the info log.
+#[cfg(feature = "opengl")]
+pub(crate) unsafe fn compile(gl: &gfx_gl::Gl, source: &str) -> std::result::Result<u32, String> {
+    use gfx_gl as gl;
+
+    let shader = gl.CreateShader(gl::COMPUTE_SHADER);
+    let length = source.len() as gl::types::GLint;
+    let source = source.as_ptr() as *const gl::types::GLchar;
+    gl.ShaderSource(shader, 1, &source, &length);
+    gl.CompileShader(shader);
+    let mut status = 0;
+    gl.GetShaderiv(shader, gl::COMPILE_STATUS, &mut status);
+    if status == 0 {
+        let mut length = 0;
+        gl.GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut length);
+        let mut log = vec![0u8; length.max(1) as usize];
+        gl.GetShaderInfoLog(
+            shader,
+            length,
+            std::ptr::null_mut(),
+            log.as_mut_ptr() as *mut _,
+        );
+        gl.DeleteShader(shader);
+        return Err(String::from_utf8_lossy(&log)
+            .trim_end_matches('\0')
+            .to_owned());
+    }
+
+    let program = gl.CreateProgram();
+    gl.AttachShader(program, shader);
+    gl.LinkProgram(program);
+    gl.DeleteShader(shader);
+    gl.GetProgramiv(program, gl::LINK_STATUS, &mut status);
+    if status == 0 {
+        let mut length = 0;
+        gl.GetProgramiv(program, gl::INFO_LOG_LENGTH, &mut length);
+        let mut log = vec![0u8; length.max(1) as usize];
+        gl.GetProgramInfoLog(
+            program,
+            length,
+            std::ptr::null_mut(),
+            log.as_mut_ptr() as *mut _,
+        );
+        gl.DeleteProgram(program);
+        return Err(String::from_utf8_lossy(&log)
+            .trim_end_matches('\0')
+            .to_owned());
+    }
+    Ok(program)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_replaces_passes_in_place() {
+        let pass = |groups| ComputePass::new(ComputeShader::new("#version 430"), groups);
+        let mut passes = ComputePasses::new();
+        passes.insert("particles", pass([1, 1, 1]));
+        passes.insert("culling", pass([2, 1, 1]));
+        passes.insert("particles", pass([3, 1, 1]));
+
+        let names = passes
+            .passes
+            .iter()
+            .map(|(name, pass)| (name.as_str(), pass.groups[0]))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![("particles", 3), ("culling", 2)], names);
+        assert!(passes.remove("particles").is_some());
+        assert!(passes.get("particles").is_none());
+        assert_eq!(48, StorageBuffer::zeroed::<[f32; 4]>(3).size());
+    }
+}
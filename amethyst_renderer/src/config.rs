@@ -2,6 +2,8 @@
 
 use winit::{self, dpi::LogicalSize, WindowBuilder};
 
+use crate::gpu::GpuPreference;
+
 /// Structure for holding the renderer configuration.
 ///
 /// # Examples
@@ -16,7 +18,8 @@ use winit::{self, dpi::LogicalSize, WindowBuilder};
 ///     fullscreen: false,
 ///     multisampling: 0,
 ///     visibility: true,
-///     vsync: true
+///     vsync: true,
+///     gpu: HighPerformance,
 /// )
 /// ```
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -45,6 +48,13 @@ pub struct DisplayConfig {
     /// Sets the visibility of the window.
     #[serde(default = "default_visibility")]
     pub visibility: bool,
+    /// Which GPU to prefer on machines with more than one.
+    #[serde(default)]
+    pub gpu: GpuPreference,
+    /// Draws into an offscreen target of the size of the window, which stays hidden, e.g. to
+    /// capture frames in tests on machines without a display.
+    #[serde(default)]
+    pub headless: bool,
 }
 
 impl Default for DisplayConfig {
@@ -58,6 +68,8 @@ impl Default for DisplayConfig {
             vsync: default_vsync(),
             multisampling: default_multisampling(),
             visibility: default_visibility(),
+            gpu: GpuPreference::default(),
+            headless: false,
         }
     }
 }
@@ -0,0 +1,102 @@
+//! Debug visualizations of meshes.
+
+use amethyst_core::specs::prelude::{Component, DenseVecStorage};
+
+/// What the `DrawDebugView` passes show of the texture coordinates of a mesh.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum UvView {
+    /// Nothing, the mesh keeps its own color.
+    None,
+    /// A checkerboard, to see how the texture coordinates are stretched.
+    Checkerboard,
+    /// The mip level of the albedo texture the mesh is sampled at, from red for the full size
+    /// texture through yellow, green, cyan and blue to magenta for the fifth level and smaller.
+    MipLevel,
+}
+
+impl Default for UvView {
+    fn default() -> Self {
+        UvView::None
+    }
+}
+
+/// Debug visualizations drawn over an entity by the `DrawDebugView` and `DrawDebugViewSeparate`
+/// passes, for debugging imported assets.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct DebugView {
+    /// Draws the edges of the triangles.
+    pub wireframe: bool,
+    /// Draws the vertex normals in blue, and the face normals in yellow.
+    pub normals: bool,
+    /// Replaces the color of the mesh with a visualization of its texture coordinates.
+    pub uv: UvView,
+}
+
+impl DebugView {
+    /// Creates a view drawing the edges of the triangles.
+    pub fn wireframe() -> Self {
+        DebugView {
+            wireframe: true,
+            ..Default::default()
+        }
+    }
+
+    /// Creates a view drawing the vertex and face normals.
+    pub fn normals() -> Self {
+        DebugView {
+            normals: true,
+            ..Default::default()
+        }
+    }
+
+    /// Creates a view drawing the texture coordinates.
+    pub fn uv(uv: UvView) -> Self {
+        DebugView {
+            uv,
+            ..Default::default()
+        }
+    }
+
+    /// Checks whether nothing is drawn.
+    pub fn is_empty(&self) -> bool {
+        *self == DebugView::default()
+    }
+
+    /// The flags read by the shaders.
+    pub(crate) fn mode(&self) -> i32 {
+        let mut mode = 0;
+        if self.wireframe {
+            mode |= 1;
+        }
+        if self.normals {
+            mode |= 2;
+        }
+        match self.uv {
+            UvView::None => {}
+            UvView::Checkerboard => mode |= 4,
+            UvView::MipLevel => mode |= 8,
+        }
+        mode
+    }
+}
+
+impl Component for DebugView {
+    type Storage = DenseVecStorage<Self>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mode_flags() {
+        assert_eq!(0, DebugView::default().mode());
+        assert_eq!(1, DebugView::wireframe().mode());
+        let view = DebugView {
+            normals: true,
+            uv: UvView::MipLevel,
+            ..Default::default()
+        };
+        assert_eq!(10, view.mode());
+    }
+}
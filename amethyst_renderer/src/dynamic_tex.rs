@@ -0,0 +1,119 @@
+//! Updates of the pixels of textures from the CPU.
+
+use crate::tex::TextureHandle;
+
+/// An update of a rectangle of a texture.
+pub(crate) struct TextureUpdate {
+    pub(crate) texture: TextureHandle,
+    pub(crate) offset: (u16, u16),
+    pub(crate) size: (u16, u16),
+    pub(crate) data: Vec<u8>,
+}
+
+/// Resource queuing updates of the pixels of textures, e.g. for procedural textures, fog of war
+/// masks or video frames, uploaded by the `RenderSystem` before it draws the next frame.
+///
+/// Only mutable textures can be updated, made with `TextureMetadata::dynamic(true)` or
+/// `TextureBuilder::dynamic(true)`, in one of the 8-bit formats `R8`, `R8_G8`, `R8_G8_B8_A8` or
+/// `B8_G8_R8_A8`. The updates of a texture which isn't loaded yet wait until it
+/// is. With device recovery enabled, a texture comes back with the data it was loaded from, so
+/// the game has to update it again after a `RenderEvent::DeviceRestored`.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let fog = loader.load_from_data(
+///     TextureData::U8(vec![0; 64 * 64 * 4], TextureMetadata::unorm().with_size(64, 64).dynamic(true)),
+///     (),
+///     &world.read_resource(),
+/// );
+///
+/// // In a system, reveal a 4x4 square at (10, 20):
+/// updates.update(&fog, (10, 20), (4, 4), vec![255; 4 * 4 * 4]);
+/// ```
+#[derive(Default)]
+pub struct TextureUpdates {
+    pub(crate) updates: Vec<TextureUpdate>,
+}
+
+impl TextureUpdates {
+    /// Creates an empty queue.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Replaces the pixels of the rectangle of `size` at `offset` from the top left corner of
+    /// the texture with `data`, which holds its rows from the top in the format of the texture,
+    /// e.g. 4 bytes per pixel for the default `R8_G8_B8_A8`.
+    ///
+    /// Updates of the same texture are applied in the order they were queued.
+    pub fn update(
+        &mut self,
+        texture: &TextureHandle,
+        offset: (u16, u16),
+        size: (u16, u16),
+        data: Vec<u8>,
+    ) {
+        self.updates.push(TextureUpdate {
+            texture: texture.clone(),
+            offset,
+            size,
+            data,
+        });
+    }
+
+    /// Replaces all pixels of a texture of the given size.
+    pub fn update_all(&mut self, texture: &TextureHandle, size: (u16, u16), data: Vec<u8>) {
+        self.update(texture, (0, 0), size, data);
+    }
+
+    /// Returns the number of updates waiting to be uploaded.
+    pub fn len(&self) -> usize {
+        self.updates.len()
+    }
+
+    /// Checks whether no update is waiting to be uploaded.
+    pub fn is_empty(&self) -> bool {
+        self.updates.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use amethyst_assets::{AssetStorage, Loader};
+    use rayon::ThreadPoolBuilder;
+
+    use crate::{formats::TextureData, tex::Texture, TextureMetadata};
+
+    use super::*;
+
+    #[test]
+    fn updates_are_queued_in_order() {
+        let pool = Arc::new(ThreadPoolBuilder::default().build().unwrap());
+        let loader = Loader::new(".", pool);
+        let storage = AssetStorage::<Texture>::new();
+        let texture = |value| {
+            let meta = TextureMetadata::unorm().with_size(2, 2).dynamic(true);
+            loader.load_from_data(TextureData::U8(vec![value; 16], meta), (), &storage)
+        };
+        let (first, second) = (texture(0), texture(255));
+
+        let mut updates = TextureUpdates::new();
+        assert!(updates.is_empty());
+        updates.update(&second, (1, 1), (1, 1), vec![0; 4]);
+        updates.update_all(&first, (2, 2), vec![255; 16]);
+        assert_eq!(2, updates.len());
+
+        let queued = updates
+            .updates
+            .iter()
+            .map(|update| (update.texture.clone(), update.offset, update.size))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![(second, (1, 1), (1, 1)), (first, (0, 0), (2, 2))],
+            queued
+        );
+    }
+}
@@ -17,6 +17,8 @@ pub type Result<T> = StdResult<T, Error>;
 /// Common renderer error type.
 #[derive(Debug)]
 pub enum Error {
+    /// Failed to start any of the graphics backends.
+    BackendCreation(String),
     /// Failed to create a buffer.
     BufferCreation(gfx::buffer::CreationError),
     /// A render target with the given name does not exist.
@@ -39,6 +41,8 @@ pub enum Error {
     TextureCreation(gfx::texture::CreationError),
     /// The given pixel data and metadata do not match.
     PixelDataMismatch(String),
+    /// Failed to update the pixels of a texture.
+    TextureUpdate(String),
     /// The window handle associated with the renderer has been destroyed.
     WindowDestroyed,
 }
@@ -46,6 +50,7 @@ pub enum Error {
 impl StdError for Error {
     fn description(&self) -> &str {
         match *self {
+            Error::BackendCreation(_) => "Failed to create graphics backend!",
             Error::BufferCreation(_) => "Failed to create buffer!",
             Error::NoSuchTarget(_) => "Target with this name does not exist!",
             Error::PassInit(_) => "Failed to initialize render pass!",
@@ -57,6 +62,7 @@ impl StdError for Error {
             Error::TargetCreation(_) => "Failed to create render target!",
             Error::TextureCreation(_) => "Failed to create texture!",
             Error::PixelDataMismatch(_) => "Pixel data and metadata do not match!",
+            Error::TextureUpdate(_) => "Failed to update texture!",
             Error::WindowDestroyed => "Window has been destroyed!",
         }
     }
@@ -79,6 +85,7 @@ impl StdError for Error {
 impl Display for Error {
     fn fmt(&self, fmt: &mut Formatter<'_>) -> FmtResult {
         match *self {
+            Error::BackendCreation(ref e) => write!(fmt, "Backend creation failed: {}", e),
             Error::BufferCreation(ref e) => write!(fmt, "Buffer creation failed: {}", e),
             Error::NoSuchTarget(ref e) => write!(fmt, "Nonexistent target: {}", e),
             Error::PassInit(ref e) => write!(fmt, "Pass initialization failed: {}", e),
@@ -92,6 +99,7 @@ impl Display for Error {
             Error::PixelDataMismatch(ref e) => {
                 write!(fmt, "Pixel data and metadata do not match: {}", e)
             }
+            Error::TextureUpdate(ref e) => write!(fmt, "Texture update failed: {}", e),
             Error::WindowDestroyed => write!(fmt, "Window has been destroyed"),
         }
     }
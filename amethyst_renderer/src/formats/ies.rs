@@ -0,0 +1,181 @@
+use amethyst_assets::{Result, ResultExt, SimpleFormat};
+
+use crate::{
+    formats::{TextureData, TextureMetadata},
+    tex::{FilterMethod, Texture},
+};
+
+/// Number of texels of the textures `IesFormat` bakes the profiles into.
+const PROFILE_SIZE: usize = 128;
+
+/// The intensity of a light over the angle from its axis, read from an IES (IESNA LM-63) file.
+///
+/// The candela values are averaged over the horizontal angles, so the profile is rotationally
+/// symmetric around the axis of the light.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IesProfile {
+    /// Vertical angles in degrees, from `0.0`, pointing along the axis of the light, up to
+    /// `180.0`, pointing away from it.
+    pub angles: Vec<f32>,
+    /// Candela at each of the `angles`.
+    pub candela: Vec<f32>,
+}
+
+impl IesProfile {
+    /// Parses the text of an IES file.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut lines = text.lines();
+        let tilt = lines
+            .by_ref()
+            .map(str::trim)
+            .find(|line| line.starts_with("TILT="))
+            .ok_or("Missing TILT line")?;
+        let mut numbers = lines
+            .flat_map(|line| line.split(|c: char| c.is_whitespace() || c == ','))
+            .filter(|word| !word.is_empty())
+            .map(|word| {
+                word.parse::<f32>()
+                    .chain_err(|| format!("Invalid number {:?}", word))
+            });
+        let mut next = move || {
+            numbers
+                .next()
+                .unwrap_or_else(|| Err("Unexpected end".into()))
+        };
+        match tilt {
+            "TILT=NONE" => {}
+            "TILT=INCLUDE" => {
+                // The tilt only changes the output of lamps which aren't mounted upright.
+                next()?;
+                let count = next()? as usize;
+                for _ in 0..count * 2 {
+                    next()?;
+                }
+            }
+            _ => bail!(
+                "Unsupported tilt {:?}, only NONE and INCLUDE are supported",
+                tilt
+            ),
+        }
+
+        let _lamps = next()?;
+        let _lumens = next()?;
+        let multiplier = next()?;
+        let vertical = next()? as usize;
+        let horizontal = next()? as usize;
+        // Photometric type, units, dimensions, ballast factor, future use and input watts.
+        for _ in 0..8 {
+            next()?;
+        }
+        if vertical == 0 || horizontal == 0 {
+            bail!("No candela values");
+        }
+        let angles = (0..vertical).map(|_| next()).collect::<Result<Vec<_>>>()?;
+        for _ in 0..horizontal {
+            next()?;
+        }
+        let mut candela = vec![0.0; vertical];
+        for _ in 0..horizontal {
+            for value in &mut candela {
+                *value += next()? * multiplier / horizontal as f32;
+            }
+        }
+        Ok(IesProfile { angles, candela })
+    }
+
+    /// Returns the candela at an angle in degrees, interpolated between the angles of the
+    /// profile, and `0.0` outside of them.
+    pub fn candela_at(&self, angle: f32) -> f32 {
+        let last = self.angles.len() - 1;
+        if angle < self.angles[0] || angle > self.angles[last] {
+            return 0.0;
+        }
+        match self.angles.iter().position(|&a| a >= angle) {
+            Some(0) | None => self.candela[0],
+            Some(i) => {
+                let t = (angle - self.angles[i - 1]) / (self.angles[i] - self.angles[i - 1]);
+                self.candela[i - 1] + (self.candela[i] - self.candela[i - 1]) * t
+            }
+        }
+    }
+
+    /// Samples the profile at evenly spaced angles from `0.0` to `180.0` degrees, relative to
+    /// its brightest angle.
+    pub fn samples(&self, count: usize) -> Vec<f32> {
+        let max = self.candela.iter().cloned().fold(0.0, f32::max);
+        (0..count)
+            .map(|i| {
+                let angle = 180.0 * i as f32 / (count - 1) as f32;
+                if max > 0.0 {
+                    self.candela_at(angle) / max
+                } else {
+                    0.0
+                }
+            })
+            .collect()
+    }
+}
+
+/// Loads an IES file as the profile texture of a `LightCookie`, which holds the intensity of the
+/// light from its axis in the first texel to pointing away from it in the last.
+///
+/// Use `TextureMetadata::unorm()` as options, the size and filter are set by the format.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct IesFormat;
+
+impl SimpleFormat<Texture> for IesFormat {
+    const NAME: &'static str = "IES";
+
+    type Options = TextureMetadata;
+
+    fn import(&self, bytes: Vec<u8>, options: TextureMetadata) -> Result<TextureData> {
+        let text = String::from_utf8_lossy(&bytes);
+        let profile = IesProfile::parse(&text).chain_err(|| "Failed to parse IES profile")?;
+        let data = profile
+            .samples(PROFILE_SIZE)
+            .into_iter()
+            .flat_map(|intensity| {
+                let value = (intensity * 255.0).round() as u8;
+                vec![value, value, value, 255]
+            })
+            .collect();
+        let options = options
+            .with_size(PROFILE_SIZE as u16, 1)
+            .with_filter(FilterMethod::Bilinear);
+        Ok(TextureData::U8(data, options))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IES: &str = "IESNA:LM-63-2002
+[TEST] test
+[MANUFAC] amethyst
+TILT=NONE
+1 1000 2.0 3 2 1 2 0 0 0
+1.0 1.0 100
+0 45 90
+0 180
+100 50 0
+300 150 0
+";
+
+    #[test]
+    fn parse_profile() {
+        let profile = IesProfile::parse(IES).unwrap();
+        assert_eq!(vec![0.0, 45.0, 90.0], profile.angles);
+        assert_eq!(vec![400.0, 200.0, 0.0], profile.candela);
+        assert_eq!(300.0, profile.candela_at(22.5));
+        assert_eq!(0.0, profile.candela_at(120.0));
+
+        let samples = profile.samples(5);
+        assert_eq!(vec![1.0, 0.5, 0.0, 0.0, 0.0], samples);
+    }
+
+    #[test]
+    fn truncated_profile() {
+        assert!(IesProfile::parse("TILT=NONE\n1 1000 1.0 3 2").is_err());
+    }
+}
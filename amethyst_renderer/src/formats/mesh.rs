@@ -28,6 +28,9 @@ pub enum MeshData {
     /// Position, color and normal
     PosColorNorm(Vec<PosColorNorm>),
 
+    /// Position, color, normal and texture coordinates
+    PosColorNormTex(Vec<PosColorNormTex>),
+
     /// Position and texture coordinates
     PosTex(Vec<PosTex>),
 
@@ -58,6 +61,12 @@ impl From<Vec<PosColorNorm>> for MeshData {
     }
 }
 
+impl From<Vec<PosColorNormTex>> for MeshData {
+    fn from(data: Vec<PosColorNormTex>) -> Self {
+        MeshData::PosColorNormTex(data)
+    }
+}
+
 impl From<Vec<PosTex>> for MeshData {
     fn from(data: Vec<PosTex>) -> Self {
         MeshData::PosTex(data)
@@ -76,6 +85,23 @@ impl From<Vec<PosNormTangTex>> for MeshData {
     }
 }
 
+impl MeshData {
+    /// Returns the number of bytes of vertex data, zero for a `Creator`.
+    pub(crate) fn byte_size(&self) -> usize {
+        use std::mem::size_of_val;
+
+        match *self {
+            MeshData::PosColor(ref data) => size_of_val(&data[..]),
+            MeshData::PosColorNorm(ref data) => size_of_val(&data[..]),
+            MeshData::PosColorNormTex(ref data) => size_of_val(&data[..]),
+            MeshData::PosTex(ref data) => size_of_val(&data[..]),
+            MeshData::PosNormTex(ref data) => size_of_val(&data[..]),
+            MeshData::PosNormTangTex(ref data) => size_of_val(&data[..]),
+            MeshData::Creator(_) => 0,
+        }
+    }
+}
+
 impl<M> From<M> for MeshData
 where
     M: MeshCreator,
@@ -206,6 +232,10 @@ pub fn create_mesh_asset(data: MeshData, renderer: &mut Renderer) -> Result<Proc
             let mb = MeshBuilder::new(vertices);
             renderer.create_mesh(mb)
         }
+        MeshData::PosColorNormTex(ref vertices) => {
+            let mb = MeshBuilder::new(vertices);
+            renderer.create_mesh(mb)
+        }
         MeshData::PosTex(ref vertices) => {
             let mb = MeshBuilder::new(vertices);
             renderer.create_mesh(mb)
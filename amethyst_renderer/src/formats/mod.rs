@@ -1,7 +1,7 @@
 //! Provides texture formats
 //!
 
-pub use self::{mesh::*, mtl::*, texture::*};
+pub use self::{ies::*, mesh::*, mtl::*, texture::*};
 
 use serde::{de::DeserializeOwned, Serialize};
 
@@ -10,6 +10,7 @@ use amethyst_core::specs::prelude::Entity;
 
 use crate::{shape::InternalShape, Mesh, ShapePrefab, Texture};
 
+mod ies;
 mod mesh;
 mod mtl;
 mod texture;
@@ -2,7 +2,7 @@ use amethyst_assets::{Format, Handle, PrefabData, PrefabError, ProgressCounter};
 use amethyst_core::specs::prelude::{Entity, ReadExpect, WriteStorage};
 
 use crate::{
-    mtl::{Material, MaterialDefaults, TextureOffset},
+    mtl::{CullMode, Material, MaterialDefaults, ShaderParams, TextureOffset},
     transparent::Transparent,
 };
 
@@ -27,6 +27,8 @@ where
     pub emission: Option<TexturePrefab<F>>,
     /// Emission texture offset
     pub emission_offset: TextureOffset,
+    /// Multiplier of the emission map
+    pub emission_intensity: f32,
     /// Normal map.
     pub normal: Option<TexturePrefab<F>>,
     /// Normal texture offset
@@ -51,6 +53,10 @@ where
     pub transparent: bool,
     /// Alpha cutoff: the value below which we do not draw the pixel
     pub alpha_cutoff: f32,
+    /// Which faces are not drawn
+    pub cull_mode: CullMode,
+    /// Extra parameters for the shader of a custom pass
+    pub params: ShaderParams,
 }
 
 impl<F> Default for MaterialPrefab<F>
@@ -63,6 +69,7 @@ where
             albedo_offset: TextureOffset::default(),
             emission: None,
             emission_offset: TextureOffset::default(),
+            emission_intensity: 1.0,
             normal: None,
             normal_offset: TextureOffset::default(),
             metallic: None,
@@ -75,6 +82,8 @@ where
             caveat_offset: TextureOffset::default(),
             transparent: false,
             alpha_cutoff: 0.01,
+            cull_mode: CullMode::Back,
+            params: ShaderParams::default(),
         }
     }
 }
@@ -119,6 +128,7 @@ where
             albedo_offset: self.albedo_offset.clone(),
             emission: load_handle(entity, &self.emission, tp_data, &mat_default.0.emission),
             emission_offset: self.emission_offset.clone(),
+            emission_intensity: self.emission_intensity,
             normal: load_handle(entity, &self.normal, tp_data, &mat_default.0.normal),
             normal_offset: self.normal_offset.clone(),
             metallic: load_handle(entity, &self.metallic, tp_data, &mat_default.0.metallic),
@@ -135,6 +145,8 @@ where
             caveat: load_handle(entity, &self.caveat, tp_data, &mat_default.0.caveat),
             caveat_offset: self.caveat_offset.clone(),
             alpha_cutoff: self.alpha_cutoff,
+            cull_mode: self.cull_mode,
+            params: self.params.clone(),
         };
         material.insert(entity, mtl)?;
         if self.transparent {
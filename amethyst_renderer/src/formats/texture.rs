@@ -14,7 +14,8 @@ use amethyst_assets::{
 use amethyst_core::specs::prelude::{Entity, Read, ReadExpect};
 
 use crate::{
-    tex::{FilterMethod, Texture, TextureBuilder},
+    color::{Rgba, SrgbRgba},
+    tex::{FilterMethod, Texture, TextureBuilder, WrapMode},
     types::SurfaceFormat,
     Renderer,
 };
@@ -24,15 +25,34 @@ use crate::{
 pub struct TextureMetadata {
     /// The sampler info describes how to read from the texture, thus specifies
     /// filter and wrap mode.
-    /// The default is nearest filtering (`FilterMethod::Scale`) and clamping (`WrapMode::Clamp`).
+    /// The default is trilinear filtering (`FilterMethod::Trilinear`) and clamping (`WrapMode::Clamp`).
+    ///
+    /// Every texture of a `MaterialPrefab` is loaded with its own metadata, so each binding can
+    /// filter and wrap differently, e.g. in RON:
+    ///
+    /// ```ron
+    /// sampler: (
+    ///     filter: Anisotropic(8),
+    ///     wrap_mode: (Tile, Tile, Tile),
+    ///     lod_bias: (-4),
+    /// ),
+    /// ```
+    ///
+    /// The filter applies to both minification and magnification: `Scale` is nearest filtering
+    /// for pixel art, `Mipmap` nearest filtering between mip levels, `Bilinear` and `Trilinear`
+    /// linear filtering without and with blending the mip levels, and `Anisotropic(n)`
+    /// trilinear filtering with up to `n` samples. The `lod_bias` is in eighths of a mip level.
     #[serde(default = "serde_helper::default_sampler")]
     pub sampler: SamplerInfo,
     /// Mipmapping levels. The default is one level.
     #[serde(default = "serde_helper::default_mip_levels")]
     pub mip_levels: u8,
-    /// Dynamic texture
+    /// Dynamic texture, whose pixels can be replaced with `TextureUpdates`.
     #[serde(default)]
     pub dynamic: bool,
+    /// Texture passes can draw into, e.g. the texture of a `UiSurface`.
+    #[serde(default)]
+    pub render_target: bool,
     /// The surface type of the texture which describes the number of color channels and their size.
     /// In simpler words, this defines the color format, e.g. RGBA 32-bit.
     ///
@@ -58,6 +78,7 @@ impl TextureMetadata {
             sampler: serde_helper::default_sampler(),
             mip_levels: serde_helper::default_mip_levels(),
             dynamic: false,
+            render_target: false,
             format: SurfaceFormat::get_surface_type(),
             size: None,
             channel: ChannelType::Unorm,
@@ -99,6 +120,27 @@ impl TextureMetadata {
         self
     }
 
+    /// Sets the wrap mode of the sampler along all axes, e.g. `WrapMode::Tile` for tiling
+    /// surfaces.
+    pub fn with_wrap_mode(mut self, wrap_mode: WrapMode) -> Self {
+        self.sampler.wrap_mode = (wrap_mode, wrap_mode, wrap_mode);
+        self
+    }
+
+    /// Filters anisotropically with up to `max` samples, which keeps surfaces seen at grazing
+    /// angles sharp.
+    pub fn with_anisotropy(mut self, max: u8) -> Self {
+        self.sampler.filter = FilterMethod::Anisotropic(max);
+        self
+    }
+
+    /// Sets the bias added to the mip level the texture is sampled at, negative values for
+    /// sharper and positive values for blurrier textures.
+    pub fn with_mip_bias(mut self, bias: f32) -> Self {
+        self.sampler.lod_bias = bias.into();
+        self
+    }
+
     /// Mipmapping
     pub fn with_mip_levels(mut self, mip_levels: u8) -> Self {
         self.mip_levels = mip_levels;
@@ -129,6 +171,12 @@ impl TextureMetadata {
         self.dynamic = d;
         self
     }
+
+    /// Texture is a render target
+    pub fn render_target(mut self, target: bool) -> Self {
+        self.render_target = target;
+        self
+    }
 }
 
 /// Texture data for loading
@@ -172,11 +220,46 @@ impl From<[f32; 3]> for TextureData {
     }
 }
 
+impl From<Rgba> for TextureData {
+    fn from(color: Rgba) -> Self {
+        color.to_srgb().into()
+    }
+}
+
+impl From<SrgbRgba> for TextureData {
+    fn from(SrgbRgba(r, g, b, a): SrgbRgba) -> Self {
+        TextureData::Rgba([r, g, b, a], TextureMetadata::srgb())
+    }
+}
+
 impl TextureData {
-    /// Creates texture data from color.
+    /// Creates texture data from color, encoded in sRGB like the arrays converted to
+    /// `TextureData`.
     pub fn color(value: [f32; 4]) -> Self {
         TextureData::Rgba(value, TextureMetadata::srgb())
     }
+
+    /// Creates texture data from values which aren't colors, like a flat normal or roughness,
+    /// sampled as they are.
+    pub fn linear(value: [f32; 4]) -> Self {
+        TextureData::Rgba(value, TextureMetadata::unorm())
+    }
+
+    /// Returns the number of bytes of pixel data.
+    pub(crate) fn byte_size(&self) -> usize {
+        use std::mem::size_of_val;
+
+        match *self {
+            TextureData::Image(ref image, _) => image.rgba.len(),
+            TextureData::Rgba(ref color, _) => size_of_val(color),
+            TextureData::F32(ref data, _) => size_of_val(&data[..]),
+            TextureData::F64(ref data, _) => size_of_val(&data[..]),
+            TextureData::U8(ref data, _) => data.len(),
+            TextureData::U16(ref data, _) => size_of_val(&data[..]),
+            TextureData::U32(ref data, _) => size_of_val(&data[..]),
+            TextureData::U64(ref data, _) => size_of_val(&data[..]),
+        }
+    }
 }
 
 impl<'a> PrefabData<'a> for TextureData {
@@ -456,6 +539,7 @@ where
         .with_sampler(metadata.sampler)
         .mip_levels(metadata.mip_levels)
         .dynamic(metadata.dynamic)
+        .render_target(metadata.render_target)
         .with_format(metadata.format)
         .with_channel_type(metadata.channel);
     if let Some((x, y)) = metadata.size {
@@ -548,7 +632,7 @@ mod serde_helper {
 
 #[cfg(test)]
 mod tests {
-    use super::TextureData;
+    use super::{FilterMethod, TextureData, TextureMetadata, WrapMode};
 
     #[test]
     fn texture_data_from_f32_3() {
@@ -559,4 +643,19 @@ mod tests {
             _ => panic!("Expected [f32; 3] to turn into TextureData::Rgba"),
         }
     }
+
+    #[test]
+    fn sampler_builders() {
+        let metadata = TextureMetadata::srgb()
+            .with_wrap_mode(WrapMode::Tile)
+            .with_anisotropy(8)
+            .with_mip_bias(-0.5);
+        assert_eq!(FilterMethod::Anisotropic(8), metadata.sampler.filter);
+        assert_eq!(
+            (WrapMode::Tile, WrapMode::Tile, WrapMode::Tile),
+            metadata.sampler.wrap_mode
+        );
+        let bias: f32 = metadata.sampler.lod_bias.into();
+        assert_eq!(-0.5, bias);
+    }
 }
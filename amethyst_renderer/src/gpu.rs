@@ -0,0 +1,147 @@
+//! Information on the GPU the renderer runs on, and which one it should prefer.
+
+/// Which GPU to run on, on machines with both an integrated and a discrete one.
+///
+/// OpenGL can't list the GPUs nor pick one, so this is a hint to the driver: on Linux it sets
+/// `DRI_PRIME` for Mesa drivers, unless already set. On Windows, drivers only read it from the
+/// executable, which has to export the `NvOptimusEnablement` and
+/// `AmdPowerXpressRequestHighPerformance` symbols to run on the discrete GPU.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum GpuPreference {
+    /// Lets the system choose.
+    Default,
+    /// Prefers the discrete GPU.
+    HighPerformance,
+    /// Prefers the integrated GPU.
+    LowPower,
+}
+
+impl Default for GpuPreference {
+    fn default() -> Self {
+        GpuPreference::Default
+    }
+}
+
+impl GpuPreference {
+    /// Passes the preference to the driver, before the context is created.
+    pub(crate) fn apply(self) {
+        #[cfg(target_os = "linux")]
+        {
+            use std::env;
+
+            let prime = match self {
+                GpuPreference::Default => return,
+                GpuPreference::HighPerformance => "1",
+                GpuPreference::LowPower => "0",
+            };
+            if env::var_os("DRI_PRIME").is_none() {
+                env::set_var("DRI_PRIME", prime);
+            }
+        }
+    }
+}
+
+/// Kind of GPU.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum GpuKind {
+    /// A GPU of its own, with its own memory.
+    Discrete,
+    /// A GPU sharing the processor and memory of the machine.
+    Integrated,
+    /// Rendering on the processor, e.g. in a virtual machine or without a working driver.
+    Software,
+    /// The driver doesn't tell.
+    Unknown,
+}
+
+impl GpuKind {
+    /// Guesses the kind of GPU from the vendor and the renderer names reported by the driver.
+    pub fn guess(vendor: &str, renderer: &str) -> GpuKind {
+        let renderer = renderer.to_lowercase();
+        let vendor = vendor.to_lowercase();
+        let any = |names: &[&str]| {
+            names
+                .iter()
+                .any(|name| vendor.contains(name) || renderer.contains(name))
+        };
+        if any(&[
+            "llvmpipe",
+            "softpipe",
+            "swiftshader",
+            "software",
+            "basic render",
+        ]) {
+            GpuKind::Software
+        } else if any(&[
+            "intel", "apple", "mali", "adreno", "powervr", "vega 8", "vega 11",
+        ]) {
+            GpuKind::Integrated
+        } else if any(&["nvidia", "geforce", "quadro", "radeon", "amd", "ati "]) {
+            GpuKind::Discrete
+        } else {
+            GpuKind::Unknown
+        }
+    }
+}
+
+/// Resource describing the GPU the renderer runs on, e.g. to choose the default quality preset
+/// of a game.
+///
+/// OpenGL only knows of the GPU its context was created on, so this is the only one listed; use
+/// `GpuPreference` in the `DisplayConfig` to hint which one to use.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GpuInfo {
+    /// Name of the GPU, as reported by the driver.
+    pub name: String,
+    /// Name of the vendor of the GPU or of the driver, as reported by the driver.
+    pub vendor: String,
+    /// Kind of GPU, guessed from its names.
+    pub kind: GpuKind,
+    /// Dedicated video memory in bytes, when the driver reports it.
+    pub memory: Option<u64>,
+    /// Largest width and height of textures, in pixels.
+    pub max_texture_size: u32,
+    /// Whether meshes can be drawn instanced.
+    pub instancing: bool,
+    /// Whether the color targets can be sRGB.
+    pub srgb: bool,
+}
+
+impl Default for GpuInfo {
+    fn default() -> Self {
+        GpuInfo {
+            name: String::new(),
+            vendor: String::new(),
+            kind: GpuKind::Unknown,
+            memory: None,
+            max_texture_size: 0,
+            instancing: false,
+            srgb: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guess_kinds() {
+        assert_eq!(
+            GpuKind::Discrete,
+            GpuKind::guess("NVIDIA Corporation", "GeForce GTX 1060/PCIe/SSE2")
+        );
+        assert_eq!(
+            GpuKind::Integrated,
+            GpuKind::guess(
+                "Intel Open Source Technology Center",
+                "Mesa DRI Intel(R) HD 620"
+            )
+        );
+        assert_eq!(
+            GpuKind::Software,
+            GpuKind::guess("VMware, Inc.", "llvmpipe (LLVM 7.0, 256 bits)")
+        );
+        assert_eq!(GpuKind::Unknown, GpuKind::guess("", ""));
+    }
+}
@@ -0,0 +1,77 @@
+//! Render layers, to choose which cameras see which entities.
+
+use amethyst_assets::{PrefabData, PrefabError};
+use amethyst_core::specs::prelude::{Component, DenseVecStorage, Entity, WriteStorage};
+
+/// Bitmask of up to 32 render layers.
+///
+/// As a component, it sets the layers an entity is on. Entities without it are on
+/// `RenderLayers::DEFAULT`. Each `Camera` has a mask of the layers it sees, and the passes only
+/// draw entities that share at least one layer with the active camera.
+///
+/// # Examples
+///
+/// ```rust
+/// # extern crate amethyst_renderer;
+/// use amethyst_renderer::RenderLayers;
+///
+/// let particles = RenderLayers::layer(1);
+/// let minimap = RenderLayers::all().without(particles);
+/// assert!(!minimap.sees(Some(&particles)));
+/// assert!(minimap.sees(None));
+/// ```
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, PrefabData, Serialize)]
+#[prefab(Component)]
+pub struct RenderLayers(pub u32);
+
+impl RenderLayers {
+    /// The layer of entities without `RenderLayers`.
+    pub const DEFAULT: RenderLayers = RenderLayers(1);
+
+    /// Returns the mask of the given layer, from 0 to 31.
+    pub fn layer(layer: u8) -> Self {
+        assert!(layer < 32, "Render layers go from 0 to 31, got {}", layer);
+        RenderLayers(1 << layer)
+    }
+
+    /// Returns the mask of all layers.
+    pub fn all() -> Self {
+        RenderLayers(!0)
+    }
+
+    /// Returns the mask of no layers.
+    pub fn none() -> Self {
+        RenderLayers(0)
+    }
+
+    /// Returns this mask with the layers of `other` added.
+    pub fn with(self, other: RenderLayers) -> Self {
+        RenderLayers(self.0 | other.0)
+    }
+
+    /// Returns this mask with the layers of `other` removed.
+    pub fn without(self, other: RenderLayers) -> Self {
+        RenderLayers(self.0 & !other.0)
+    }
+
+    /// Checks whether this mask shares at least one layer with `other`.
+    pub fn intersects(self, other: RenderLayers) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    /// Checks whether a camera with this mask sees an entity with the given layers, `None`
+    /// being an entity without `RenderLayers`.
+    pub fn sees(self, layers: Option<&RenderLayers>) -> bool {
+        self.intersects(layers.cloned().unwrap_or(RenderLayers::DEFAULT))
+    }
+}
+
+impl Default for RenderLayers {
+    fn default() -> Self {
+        RenderLayers::DEFAULT
+    }
+}
+
+impl Component for RenderLayers {
+    type Storage = DenseVecStorage<Self>;
+}
@@ -43,6 +43,8 @@ extern crate log;
 use rayon;
 #[macro_use]
 extern crate serde;
+#[cfg(feature = "json")]
+use serde_json;
 use shred;
 #[macro_use]
 extern crate shred_derive;
@@ -54,43 +56,76 @@ extern crate thread_profiler;
 #[cfg(feature = "opengl")]
 use gfx_device_gl;
 #[cfg(feature = "opengl")]
+use gfx_gl;
+#[cfg(feature = "opengl")]
 use gfx_window_glutin;
 #[cfg(feature = "opengl")]
 use glutin;
 
+#[cfg(feature = "json")]
+pub use crate::sprite::TexturePackerFormat;
 pub use crate::{
+    backend::{GraphicsApi, RenderBackend, RenderEvent},
+    batching::{Static, StaticBatch, StaticBatchingSystem},
     blink::{Blink, BlinkSystem},
     bundle::RenderBundle,
-    cam::{ActiveCamera, ActiveCameraPrefab, Camera, CameraPrefab, Projection},
-    color::Rgba,
+    cam::{
+        ActiveCamera, ActiveCameraPrefab, Camera, CameraPrefab, Frustum, Plane, Projection, Ray,
+    },
+    capture::{CapturedFrame, FrameCapture},
+    color::{linear_to_srgb, srgb_to_linear, ColorSpace, LinearRgba, Rgba, SrgbRgba},
+    color_audit::{ColorAudit, ColorAuditSystem, ColorIssue},
+    color_filter::{ColorDeficiency, ColorFilter, ColorFilterMode},
+    compute::{ComputePass, ComputePasses, ComputeShader, ImageAccess, StorageBuffer},
     config::DisplayConfig,
     debug_drawing::{DebugLines, DebugLinesComponent},
+    debug_view::{DebugView, UvView},
+    dynamic_tex::TextureUpdates,
     formats::{
         build_mesh_with_combo, create_mesh_asset, create_texture_asset, BmpFormat,
-        ComboMeshCreator, GraphicsPrefab, ImageData, JpgFormat, MaterialPrefab, MeshCreator,
-        MeshData, ObjFormat, PngFormat, TextureData, TextureFormat, TextureMetadata, TexturePrefab,
-        TgaFormat,
+        ComboMeshCreator, GraphicsPrefab, IesFormat, IesProfile, ImageData, JpgFormat,
+        MaterialPrefab, MeshCreator, MeshData, ObjFormat, PngFormat, TextureData, TextureFormat,
+        TextureMetadata, TexturePrefab, TgaFormat,
     },
+    gpu::{GpuInfo, GpuKind, GpuPreference},
     hidden::{Hidden, HiddenPropagate},
     hide_system::HideHierarchySystem,
     input::{
         DeviceEvent, ElementState, Event, KeyboardInput, MouseButton, VirtualKeyCode, WindowEvent,
     },
-    light::{DirectionalLight, Light, LightPrefab, PointLight, SpotLight, SunLight},
+    layers::RenderLayers,
+    light::{
+        AreaLight, AreaShape, DirectionalLight, Light, LightCookie, LightPrefab, PointLight,
+        SpotLight, SunLight,
+    },
     mesh::{vertex_data, Mesh, MeshBuilder, MeshHandle, VertexBuffer},
-    mtl::{Material, MaterialDefaults, TextureOffset},
+    mtl::{
+        CullMode, Material, MaterialDefaults, MaterialOverride, ShaderParam, ShaderParams,
+        TextureOffset, MAX_SHADER_PARAMS,
+    },
+    occlusion::{Occluder, OcclusionBounds},
     pass::{
-        get_camera, set_vertex_args, DebugLinesParams, DrawDebugLines, DrawFlat, DrawFlat2D,
-        DrawFlatSeparate, DrawPbm, DrawPbmSeparate, DrawShaded, DrawShadedSeparate, DrawSkybox,
-        SkyboxColor,
+        get_camera, set_material_params, set_vertex_args, setup_material_params, DebugLinesParams,
+        DebugViewParams, DrawColorFilter, DrawDebugLines, DrawDebugView, DrawDebugViewSeparate,
+        DrawFlat, DrawFlat2D, DrawFlatSeparate, DrawGlow, DrawPbm, DrawPbmSeparate, DrawScatter,
+        DrawShaded, DrawShadedSeparate, DrawSkybox, DrawTransition, Glow, SkyboxColor,
     },
     pipe::{
         ColorBuffer, Data, DepthBuffer, DepthMode, Effect, EffectBuilder, Init, Meta, NewEffect,
-        Pipeline, PipelineBuild, PipelineBuilder, PipelineData, PolyPipeline, PolyStage,
-        PolyStages, Stage, StageBuilder, Target, TargetBuilder, Targets,
+        PassDescription, Pipeline, PipelineBuild, PipelineBuilder, PipelineData,
+        PipelineDescription, PolyPipeline, PolyStage, PolyStages, Stage, StageBuilder,
+        StageDescription, Target, TargetBuilder, TargetDescription, Targets,
+    },
+    pipeline_dump::PipelineDump,
+    probe::{
+        BakedProbes, BakedProbesHandle, EnvironmentMap, Irradiance, IrradianceGrid, ProbeBake,
+        ProbeBakeSystem, ReflectionProbe, SceneProbes,
     },
     renderer::Renderer,
     resources::{AmbientColor, ScreenDimensions, WindowMessages},
+    scatter::{
+        DensityMap, Scatter, ScatterBuilder, ScatterInstance, ScatterSurface, ScatterTriangle, Wind,
+    },
     shape::{InternalShape, Shape, ShapePrefab, ShapeUpload},
     skinning::{
         AnimatedComboMeshCreator, AnimatedVertexBufferCombination, JointIds, JointTransforms,
@@ -98,22 +133,24 @@ pub use crate::{
     },
     sprite::{
         Flipped, Sprite, SpriteRender, SpriteSheet, SpriteSheetFormat, SpriteSheetHandle,
-        TextureCoordinates,
+        SpriteTrim, TextureCoordinates,
     },
     sprite_visibility::{SpriteVisibility, SpriteVisibilitySortingSystem},
     system::RenderSystem,
     tex::{
         FilterMethod, SamplerInfo, SurfaceType, Texture, TextureBuilder, TextureHandle, WrapMode,
     },
+    transition::{ScreenTransition, TransitionEffect, WipeDirection},
     transparent::{
         Blend, BlendChannel, BlendValue, ColorMask, Equation, Factor, Transparent, ALPHA, REPLACE,
     },
-    types::{Encoder, Factory, PipelineState, Resources},
+    types::{DepthStencilView, Encoder, Factory, PipelineState, RenderTargetView, Resources},
     vertex::{
         Attribute, AttributeFormat, Attributes, Color, Normal, PosColor, PosColorNorm,
-        PosNormTangTex, PosNormTex, PosTex, Position, Query, Separate, Tangent, TexCoord,
-        VertexBufferCombination, VertexFormat, With,
+        PosColorNormTex, PosNormTangTex, PosNormTex, PosTex, Position, Query, Separate, Tangent,
+        TexCoord, VertexBufferCombination, VertexFormat, With,
     },
+    view_model::ViewModelCamera,
     visibility::{Visibility, VisibilitySortingSystem},
 };
 
@@ -124,29 +161,45 @@ pub mod pipe;
 #[macro_use]
 mod macros;
 
+mod backend;
+mod batching;
 mod blink;
 mod bundle;
 mod cam;
+mod capture;
 mod color;
+mod color_audit;
+mod color_filter;
+mod compute;
 mod config;
 mod debug_drawing;
+mod debug_view;
+mod dynamic_tex;
 mod formats;
+mod gpu;
 mod hidden;
 mod hide_system;
 mod input;
+mod layers;
 mod light;
 mod mesh;
 mod mtl;
+mod occlusion;
 mod pass;
+mod pipeline_dump;
+mod probe;
 mod renderer;
 mod resources;
+mod scatter;
 mod shape;
 mod skinning;
 mod sprite;
 mod sprite_visibility;
 mod system;
 mod tex;
+mod transition;
 mod transparent;
 mod types;
 mod vertex;
+mod view_model;
 mod visibility;
@@ -7,15 +7,14 @@ use gfx;
 use amethyst_assets::{PrefabData, PrefabError, ProgressCounter};
 use amethyst_core::specs::prelude::{Component, DenseVecStorage, Entity, WriteStorage};
 
-use crate::{color::Rgba, resources::AmbientColor};
+use crate::{color::Rgba, resources::AmbientColor, tex::TextureHandle};
 
 /// A light source.
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize, PrefabData)]
 #[prefab(Component)]
 pub enum Light {
     /// An area light.
-    /// FIXME: Missing implementation!
-    Area,
+    Area(AreaLight),
     /// A directional light.
     Directional(DirectionalLight),
     /// A point light.
@@ -26,6 +25,69 @@ pub enum Light {
     Sun(SunLight),
 }
 
+/// The shape of an `AreaLight`.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum AreaShape {
+    /// A rectangle in the XY plane of the `GlobalTransform`, lighting along its Z axis, e.g. a
+    /// window or a screen.
+    Rectangle {
+        /// Size along the X axis.
+        width: f32,
+        /// Size along the Y axis.
+        height: f32,
+    },
+    /// A capsule along the X axis of the `GlobalTransform`, e.g. a fluorescent tube.
+    Tube {
+        /// Length of the segment the capsule is around.
+        length: f32,
+        /// Radius of the capsule.
+        radius: f32,
+    },
+}
+
+/// A light source emitting from a rectangle or a tube, positioned and oriented by the
+/// `GlobalTransform` of its entity. Only the PBM passes draw area lights.
+///
+/// Area lights are approximated by the point of their shape closest to the reflection of the
+/// view ray, which gives the highlights their shape, and are attenuated like a `PointLight`
+/// from that point.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct AreaLight {
+    /// Color of the light in RGBA8 format.
+    pub color: Rgba,
+    /// Luminous intensity of the light source, in candela.
+    pub intensity: f32,
+    /// Shape of the light source.
+    pub shape: AreaShape,
+    /// Maximum radius of the light's affected area, also used to cull the lights outside of the
+    /// view.
+    pub radius: f32,
+    /// Smoothness of the light-to-dark transition towards the radius.
+    pub smoothness: f32,
+}
+
+impl Default for AreaLight {
+    fn default() -> Self {
+        AreaLight {
+            color: Rgba::default(),
+            intensity: 10.0,
+            shape: AreaShape::Rectangle {
+                width: 1.0,
+                height: 1.0,
+            },
+            radius: 10.0,
+            smoothness: 4.0,
+        }
+    }
+}
+
+impl From<AreaLight> for Light {
+    fn from(area: AreaLight) -> Self {
+        Light::Area(area)
+    }
+}
+
 /// A directional light source.
 #[repr(C)]
 #[derive(Clone, ConstantBuffer, Debug, Deserialize, PartialEq, Serialize)]
@@ -73,6 +135,15 @@ impl From<DirectionalLight> for Light {
 /// * *lightRadius* = `radius`
 /// * *n* = `smoothness`
 ///
+/// The lights whose radius doesn't reach into the view are culled, so a radius of `0.0` has to
+/// be set to light everything.
+///
+/// The passes used to ignore the radius, so a light with a small radius, like the `1.0` older
+/// examples used, now barely lights anything. With a radius of `0.0`, the shaded passes keep
+/// their former look, but the PBM passes don't: they used to light with the full `intensity` at
+/// any distance, and now fall off with the inverse square of the distance too, so their lights
+/// need a much higher intensity.
+///
 /// [fb]: http://www.frostbite.com/wp-content/uploads/2014/11/course_notes_moving_frostbite_to_pbr.pdf
 #[repr(C)]
 #[derive(Clone, ConstantBuffer, Debug, Deserialize, PartialEq, Serialize)]
@@ -80,9 +151,10 @@ impl From<DirectionalLight> for Light {
 pub struct PointLight {
     /// Color of the light in RGBA8 format.
     pub color: Rgba,
-    /// Brightness of the light source, in lumens.
+    /// Luminous intensity of the light source, in candela, see `with_lumens` to set it from the
+    /// luminous power of a lamp.
     pub intensity: f32,
-    /// Maximum radius of the point light's affected area.
+    /// Maximum radius of the point light's affected area, `0.0` for an unlimited radius.
     pub radius: f32,
     /// Smoothness of the light-to-dark transition from the center to the
     /// radius.
@@ -100,6 +172,15 @@ impl Default for PointLight {
     }
 }
 
+impl PointLight {
+    /// Sets the intensity from the luminous power of a light bulb, e.g. about 800 lumens for a
+    /// 60 watt incandescent bulb, emitted evenly in all directions.
+    pub fn with_lumens(mut self, lumens: f32) -> Self {
+        self.intensity = lumens / (4.0 * std::f32::consts::PI);
+        self
+    }
+}
+
 impl From<PointLight> for Light {
     fn from(pt: PointLight) -> Self {
         Light::Point(pt)
@@ -119,7 +200,7 @@ pub struct SpotLight {
     pub direction: [f32; 3], //TODO: Replace with a nalgebra type
     /// Brightness of the light source, in lumens.
     pub intensity: f32,
-    /// Range/length of the light source.
+    /// Range/length of the light source, also used to cull the lights outside of the view.
     pub range: f32,
     /// Smoothness of the light-to-dark transition from the center to the
     /// radius.
@@ -181,6 +262,47 @@ impl Component for Light {
     type Storage = DenseVecStorage<Self>;
 }
 
+/// Shapes the light of the `SpotLight` or `PointLight` of the entity, drawn by the PBM passes.
+///
+/// The `cookie` is projected over the cone of a spot light, e.g. the lens of a flashlight or a
+/// stained-glass window, and multiplied with its color. Point lights don't project cookies.
+///
+/// The `profile` holds the intensity of the light from its axis in the first texel to pointing
+/// away from it in the last, as loaded from IES files with `IesFormat`. The axis of a spot light
+/// is its direction, the one of a point light points down the Y axis of its `GlobalTransform`,
+/// like the lamps measured for IES files.
+///
+/// Only the first four spot and point lights with a cookie or a profile are shaped.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LightCookie {
+    /// Texture projected by a spot light.
+    pub cookie: Option<TextureHandle>,
+    /// Intensity profile of the light.
+    pub profile: Option<TextureHandle>,
+}
+
+impl LightCookie {
+    /// Creates a cookie projecting a texture.
+    pub fn cookie(cookie: TextureHandle) -> Self {
+        LightCookie {
+            cookie: Some(cookie),
+            profile: None,
+        }
+    }
+
+    /// Creates a cookie with an intensity profile.
+    pub fn profile(profile: TextureHandle) -> Self {
+        LightCookie {
+            cookie: None,
+            profile: Some(profile),
+        }
+    }
+}
+
+impl Component for LightCookie {
+    type Storage = DenseVecStorage<Self>;
+}
+
 /// Prefab for lighting
 #[derive(Default, Clone, Serialize, Deserialize, PrefabData)]
 #[serde(default)]
@@ -8,10 +8,14 @@ use std::{
 use gfx::Primitive;
 
 use amethyst_assets::Handle;
-use amethyst_core::nalgebra::{Matrix4, Point3, Rotation3, Translation3, Unit, Vector3};
+use amethyst_core::{
+    nalgebra::{Matrix4, Point3, Rotation3, Translation3, Unit, Vector3},
+    MemorySize, MemoryUsage,
+};
 
 use crate::{
     error::Result,
+    formats::MeshData,
     types::{Factory, RawBuffer, Slice},
     vertex::{Attributes, VertexFormat},
 };
@@ -127,11 +131,15 @@ where
 pub type MeshHandle = Handle<Mesh>;
 
 /// Represents a polygonal mesh.
-#[derive(Clone, Debug)]
+#[derive(Derivative)]
+#[derivative(Clone, Debug)]
 pub struct Mesh {
     slice: Slice,
     transform: Matrix4<f32>,
     vbufs: Vec<VertexBuffer>,
+    /// The data the mesh was built from, kept to build it again if the device is lost.
+    #[derivative(Debug = "ignore")]
+    source: Option<MeshData>,
 }
 
 impl Mesh {
@@ -144,6 +152,16 @@ impl Mesh {
         MeshBuilder::new(verts)
     }
 
+    /// Returns the data the mesh was built from, if it was kept.
+    pub(crate) fn source(&self) -> Option<&MeshData> {
+        self.source.as_ref()
+    }
+
+    /// Keeps the data the mesh was built from.
+    pub(crate) fn set_source(&mut self, source: MeshData) {
+        self.source = Some(source);
+    }
+
     /// Returns the mesh's vertex buffer which matches requested attributes
     pub fn buffer(&self, attributes: Attributes<'_>) -> Option<&RawBuffer> {
         for vbuf in self.vbufs.iter() {
@@ -195,6 +213,15 @@ impl Mesh {
     }
 }
 
+impl MemorySize for Mesh {
+    fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage {
+            cpu: self.source.as_ref().map_or(0, MeshData::byte_size),
+            gpu: self.vbufs.iter().map(|vbuf| vbuf.raw.get_info().size).sum(),
+        }
+    }
+}
+
 /// Builds new meshes.
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct MeshBuilder<T> {
@@ -293,6 +320,7 @@ where
             slice,
             transform: self.transform,
             vbufs: self.vertices.build(fac)?.collect(),
+            source: None,
         })
     }
 }
@@ -1,8 +1,10 @@
 //! Physically-based material.
 
+use gfx_core::state::CullFace;
+
 use amethyst_core::specs::prelude::{Component, DenseVecStorage};
 
-use crate::tex::TextureHandle;
+use crate::{color::Rgba, tex::TextureHandle};
 
 /// Material reference this part of the texture
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -22,11 +24,46 @@ impl Default for TextureOffset {
     }
 }
 
+/// Which faces of the meshes using a `Material` are not drawn.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Deserialize, Serialize)]
+pub enum CullMode {
+    /// The faces pointing away from the camera are not drawn, the default.
+    Back,
+    /// The faces pointing towards the camera are not drawn, e.g. to see the inside of a sphere.
+    Front,
+    /// Both sides of the faces are drawn, e.g. for foliage cards and cloth.
+    None,
+}
+
+impl Default for CullMode {
+    fn default() -> Self {
+        CullMode::Back
+    }
+}
+
+impl From<CullMode> for CullFace {
+    fn from(mode: CullMode) -> Self {
+        match mode {
+            CullMode::Back => CullFace::Back,
+            CullMode::Front => CullFace::Front,
+            CullMode::None => CullFace::Nothing,
+        }
+    }
+}
+
 /// Material struct.
 #[derive(Clone, PartialEq)]
 pub struct Material {
     /// Alpha cutoff: the value at which we do not draw the pixel
+    ///
+    /// The pixels where the alpha of the albedo map is below the cutoff are left out, while the
+    /// others are drawn opaque and write depth. This cuts out foliage and fences without the
+    /// sorting of `Transparent` entities, which blend their alpha instead. Set it to `0.0` to
+    /// draw every pixel.
     pub alpha_cutoff: f32,
+    /// Which faces are not drawn. The shaded and PBM passes light the back side of the faces
+    /// they draw as if its normals pointed the other way.
+    pub cull_mode: CullMode,
     /// Diffuse map.
     pub albedo: TextureHandle,
     /// Diffuse texture offset
@@ -35,6 +72,11 @@ pub struct Material {
     pub emission: TextureHandle,
     /// Emission texture offset
     pub emission_offset: TextureOffset,
+    /// Multiplier of the emission map, above `1.0` for surfaces brighter than the map can store.
+    ///
+    /// The shaded and PBM passes add the emission to the lit color, and `DrawGlow` spreads it
+    /// around the meshes of `Glow` entities.
+    pub emission_intensity: f32,
     /// Normal map.
     pub normal: TextureHandle,
     /// Normal texture offset
@@ -55,6 +97,8 @@ pub struct Material {
     pub caveat: TextureHandle,
     /// Caveat texture offset
     pub caveat_offset: TextureOffset,
+    /// Extra parameters for the shader of a custom pass, see `ShaderParams`.
+    pub params: ShaderParams,
 }
 
 impl Component for Material {
@@ -68,3 +112,242 @@ impl Component for Material {
 /// `Material` you don't want to specify.
 #[derive(Clone)]
 pub struct MaterialDefaults(pub Material);
+
+/// Per entity changes to its `Material`, so entities can share one material and differ only in
+/// a few of its parameters, without a copy of the whole material each.
+///
+/// Every parameter left to `None` is taken from the `Material` of the entity when it is drawn by
+/// the flat, shaded and PBM passes. The tint is multiplied with the `Rgba` of the entity, if it
+/// has one.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // A damaged variant of a shared crate material.
+/// world
+///     .create_entity()
+///     .with(crate_mesh.clone())
+///     .with(crate_material.clone())
+///     .with(MaterialOverride {
+///         albedo: Some(cracked_albedo.clone()),
+///         ..Default::default()
+///     })
+///     .build();
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MaterialOverride {
+    /// Color multiplied with the albedo, in linear space.
+    pub tint: Option<Rgba>,
+    /// Diffuse map.
+    pub albedo: Option<TextureHandle>,
+    /// Diffuse texture offset
+    pub albedo_offset: Option<TextureOffset>,
+    /// Emission map.
+    pub emission: Option<TextureHandle>,
+    /// Emission texture offset
+    pub emission_offset: Option<TextureOffset>,
+    /// Multiplier of the emission map, e.g. to pulse the glow of one entity.
+    pub emission_intensity: Option<f32>,
+    /// Normal map.
+    pub normal: Option<TextureHandle>,
+    /// Normal texture offset
+    pub normal_offset: Option<TextureOffset>,
+    /// Metallic map.
+    pub metallic: Option<TextureHandle>,
+    /// Metallic texture offset
+    pub metallic_offset: Option<TextureOffset>,
+    /// Roughness map.
+    pub roughness: Option<TextureHandle>,
+    /// Roughness texture offset
+    pub roughness_offset: Option<TextureOffset>,
+    /// Ambient occlusion map.
+    pub ambient_occlusion: Option<TextureHandle>,
+    /// Ambient occlusion texture offset
+    pub ambient_occlusion_offset: Option<TextureOffset>,
+    /// Caveat map.
+    pub caveat: Option<TextureHandle>,
+    /// Caveat texture offset
+    pub caveat_offset: Option<TextureOffset>,
+}
+
+impl MaterialOverride {
+    /// Returns the `Rgba` of an entity multiplied with the tint.
+    pub(crate) fn tinted(&self, rgba: Rgba) -> Rgba {
+        match self.tint {
+            Some(Rgba(r, g, b, a)) => Rgba(rgba.0 * r, rgba.1 * g, rgba.2 * b, rgba.3 * a),
+            None => rgba,
+        }
+    }
+}
+
+impl Component for MaterialOverride {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// The most `vec4`s the parameters of `ShaderParams` can fill, see `setup_material_params`.
+pub const MAX_SHADER_PARAMS: usize = 16;
+
+/// A value of `ShaderParams`.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub enum ShaderParam {
+    /// A `float` of the block.
+    Float(f32),
+    /// A `vec2` of the block.
+    Vec2([f32; 2]),
+    /// A `vec3` of the block.
+    Vec3([f32; 3]),
+    /// A `vec4` of the block.
+    Vec4([f32; 4]),
+    /// A color, a `vec4` of the block.
+    Color(Rgba),
+}
+
+impl ShaderParam {
+    /// The alignment of the value in a `std140` block, and its components, in floats.
+    fn layout(&self) -> (usize, Vec<f32>) {
+        match *self {
+            ShaderParam::Float(x) => (1, vec![x]),
+            ShaderParam::Vec2([x, y]) => (2, vec![x, y]),
+            ShaderParam::Vec3([x, y, z]) => (4, vec![x, y, z]),
+            ShaderParam::Vec4([x, y, z, w]) => (4, vec![x, y, z, w]),
+            ShaderParam::Color(Rgba(r, g, b, a)) => (4, vec![r, g, b, a]),
+        }
+    }
+}
+
+/// Named values of the `MaterialParams` uniform block of a custom pass.
+///
+/// The `params` of a `Material` declare the members of the block, in the order of the block in
+/// the shader, with their default values. Given to an entity as a component, they replace the
+/// values of the same names for that entity, so effects like a dissolve amount or a damage flash
+/// can be animated per entity while the entities share a material. Custom passes bind the block
+/// with `setup_material_params` and `set_material_params`.
+///
+/// # Examples
+///
+/// In the RON of a `MaterialPrefab`:
+///
+/// ```ron,ignore
+/// params: [
+///     ("dissolve", Float(0.0)),
+///     ("flash_color", Color((1.0, 1.0, 1.0, 0.0))),
+/// ],
+/// ```
+///
+/// Matching the block of the shader:
+///
+/// ```glsl,ignore
+/// layout (std140) uniform MaterialParams {
+///     float dissolve;
+///     vec4 flash_color;
+/// };
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct ShaderParams {
+    /// The values by name, in the order of the block.
+    pub params: Vec<(String, ShaderParam)>,
+}
+
+impl ShaderParams {
+    /// Creates empty parameters.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds a parameter, or replaces the value of the parameter with the same name.
+    pub fn with<N: Into<String>>(mut self, name: N, value: ShaderParam) -> Self {
+        self.set(name, value);
+        self
+    }
+
+    /// Sets a parameter, appended when there's no parameter with the same name.
+    pub fn set<N: Into<String>>(&mut self, name: N, value: ShaderParam) {
+        let name = name.into();
+        match self.params.iter_mut().find(|&&mut (ref n, _)| *n == name) {
+            Some(param) => param.1 = value,
+            None => self.params.push((name, value)),
+        }
+    }
+
+    /// Returns the value of a parameter.
+    pub fn get(&self, name: &str) -> Option<&ShaderParam> {
+        self.params
+            .iter()
+            .find(|&&(ref n, _)| n == name)
+            .map(|&(_, ref value)| value)
+    }
+
+    /// Packs the parameters by the `std140` layout, with the values of `overrides` replacing
+    /// the ones of the same names.
+    ///
+    /// Parameters past `MAX_SHADER_PARAMS` `vec4`s are left out.
+    pub(crate) fn std140(&self, overrides: Option<&ShaderParams>) -> Vec<[f32; 4]> {
+        let mut floats = Vec::new();
+        for &(ref name, ref value) in &self.params {
+            let value = overrides
+                .and_then(|overrides| overrides.get(name))
+                .unwrap_or(value);
+            let (align, components) = value.layout();
+            let start = (floats.len() + align - 1) / align * align;
+            if start + components.len() > MAX_SHADER_PARAMS * 4 {
+                warn!(
+                    "Shader parameter {} doesn't fit in the parameter block",
+                    name
+                );
+                break;
+            }
+            floats.resize(start, 0.0);
+            floats.extend(components);
+        }
+        floats
+            .chunks(4)
+            .map(|chunk| {
+                let mut vec = [0.0; 4];
+                vec[..chunk.len()].copy_from_slice(chunk);
+                vec
+            })
+            .collect()
+    }
+}
+
+impl Component for ShaderParams {
+    type Storage = DenseVecStorage<Self>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn std140_layout() {
+        let params = ShaderParams::new()
+            .with("dissolve", ShaderParam::Float(0.5))
+            .with("offset", ShaderParam::Vec2([1.0, 2.0]))
+            .with("axis", ShaderParam::Vec3([3.0, 4.0, 5.0]))
+            .with("scale", ShaderParam::Float(6.0))
+            .with("flash", ShaderParam::Color(Rgba::WHITE));
+        assert_eq!(
+            vec![
+                [0.5, 0.0, 1.0, 2.0],
+                [3.0, 4.0, 5.0, 6.0],
+                [1.0, 1.0, 1.0, 1.0],
+            ],
+            params.std140(None)
+        );
+
+        let overrides = ShaderParams::new().with("scale", ShaderParam::Float(7.0));
+        assert_eq!([3.0, 4.0, 5.0, 7.0], params.std140(Some(&overrides))[1]);
+    }
+
+    #[test]
+    fn override_tint_multiplies_rgba() {
+        let rgba = Rgba(0.5, 1.0, 1.0, 0.5);
+        assert_eq!(rgba, MaterialOverride::default().tinted(rgba));
+        let overrides = MaterialOverride {
+            tint: Some(Rgba(1.0, 0.5, 0.0, 1.0)),
+            ..Default::default()
+        };
+        assert_eq!(Rgba(0.5, 0.5, 0.0, 0.5), overrides.tinted(rgba));
+    }
+}
@@ -0,0 +1,362 @@
+//! Occlusion culling with a software depth buffer.
+//!
+//! The meshes of `Occluder`s, usually simplified walls and floors, are rasterized on the CPU into
+//! a small depth buffer, against which the bounding spheres of `OcclusionBounds` are tested.
+
+use amethyst_core::{
+    nalgebra::{self, Matrix4, Point3, Vector2, Vector3, Vector4},
+    specs::prelude::{Component, DenseVecStorage},
+};
+
+use crate::formats::MeshData;
+
+/// Marks an entity as hiding whatever is behind it from the `VisibilitySortingSystem`, when
+/// occlusion culling is enabled.
+///
+/// The triangles should lie inside the drawn mesh, so nothing visible is culled. A handful of
+/// large triangles is much cheaper to rasterize than the full mesh.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Occluder {
+    /// Corners of the triangles, in the space of the entity.
+    pub triangles: Vec<[Vector3<f32>; 3]>,
+}
+
+impl Occluder {
+    /// Creates an occluder of the given triangles.
+    pub fn new(triangles: Vec<[Vector3<f32>; 3]>) -> Self {
+        Occluder { triangles }
+    }
+
+    /// Creates an occluder of the triangles of a mesh, or `None` for a `MeshData::Creator`.
+    pub fn from_mesh_data(data: &MeshData) -> Option<Self> {
+        fn triangles<V>(vertices: &[V], position: impl Fn(&V) -> Vector3<f32>) -> Occluder {
+            Occluder::new(
+                vertices
+                    .chunks(3)
+                    .filter(|corners| corners.len() == 3)
+                    .map(|corners| {
+                        [
+                            position(&corners[0]),
+                            position(&corners[1]),
+                            position(&corners[2]),
+                        ]
+                    })
+                    .collect(),
+            )
+        }
+
+        Some(match *data {
+            MeshData::PosColor(ref v) => triangles(v, |v| v.position),
+            MeshData::PosColorNorm(ref v) => triangles(v, |v| v.position),
+            MeshData::PosColorNormTex(ref v) => triangles(v, |v| v.position),
+            MeshData::PosTex(ref v) => triangles(v, |v| v.position),
+            MeshData::PosNormTex(ref v) => triangles(v, |v| v.position),
+            MeshData::PosNormTangTex(ref v) => triangles(v, |v| v.position),
+            MeshData::Creator(_) => return None,
+        })
+    }
+
+    /// Creates an occluder of a box centered on the entity, e.g. for a wall.
+    pub fn cuboid(half_extents: Vector3<f32>) -> Self {
+        let corner = |x: f32, y: f32, z: f32| {
+            Vector3::new(x * half_extents.x, y * half_extents.y, z * half_extents.z)
+        };
+        let mut triangles = Vec::with_capacity(12);
+        for axis in 0..3 {
+            for &side in &[-1.0, 1.0] {
+                // The four corners of the face on the side of the axis.
+                let mut face = [[0.0; 3]; 4];
+                for (i, &(u, v)) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+                    .iter()
+                    .enumerate()
+                {
+                    face[i][axis] = side;
+                    face[i][(axis + 1) % 3] = u;
+                    face[i][(axis + 2) % 3] = v;
+                }
+                let face: Vec<_> = face.iter().map(|c| corner(c[0], c[1], c[2])).collect();
+                triangles.push([face[0], face[1], face[2]]);
+                triangles.push([face[0], face[2], face[3]]);
+            }
+        }
+        Occluder::new(triangles)
+    }
+}
+
+impl Component for Occluder {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// The bounding sphere of the mesh of an entity, letting the `VisibilitySortingSystem` cull it
+/// when it's hidden behind `Occluder`s. Entities without one are never occluded.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub struct OcclusionBounds {
+    /// Center of the sphere, in the space of the entity.
+    pub center: Vector3<f32>,
+    /// Radius of the sphere, in the space of the entity.
+    pub radius: f32,
+}
+
+impl OcclusionBounds {
+    /// Creates a bounding sphere around the origin of the entity.
+    pub fn new(radius: f32) -> Self {
+        OcclusionBounds {
+            center: Vector3::zeros(),
+            radius,
+        }
+    }
+
+    /// Returns the center and radius of the sphere in world space.
+    pub(crate) fn world(&self, model: &Matrix4<f32>) -> (Point3<f32>, f32) {
+        let scale = (0..3)
+            .map(|i| model.column(i).xyz().norm())
+            .fold(0.0, f32::max);
+        let center = (model * Point3::from(self.center).to_homogeneous()).xyz();
+        (Point3::from(center), self.radius * scale)
+    }
+}
+
+impl Component for OcclusionBounds {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// A low resolution depth buffer of the occluders, in normalized device coordinates.
+#[derive(Clone, Debug)]
+pub(crate) struct OcclusionBuffer {
+    width: usize,
+    height: usize,
+    depth: Vec<f32>,
+    view_proj: Matrix4<f32>,
+}
+
+impl OcclusionBuffer {
+    pub(crate) fn new(width: usize, height: usize) -> Self {
+        assert!(width > 0 && height > 0);
+        OcclusionBuffer {
+            width,
+            height,
+            depth: vec![std::f32::INFINITY; width * height],
+            view_proj: Matrix4::identity(),
+        }
+    }
+
+    /// Clears the buffer for a new view, given by the projection and view matrices of a camera.
+    pub(crate) fn clear(&mut self, view_proj: Matrix4<f32>) {
+        self.view_proj = view_proj;
+        for depth in &mut self.depth {
+            *depth = std::f32::INFINITY;
+        }
+    }
+
+    /// Returns the pixel coordinates and depth of a point in world space, or `None` if it's
+    /// behind the near plane.
+    fn project(&self, point: &Point3<f32>) -> Option<Vector3<f32>> {
+        let clip: Vector4<f32> = self.view_proj * point.to_homogeneous();
+        if clip.w <= std::f32::EPSILON || clip.z < -clip.w {
+            return None;
+        }
+        let ndc = clip.xyz() / clip.w;
+        Some(Vector3::new(
+            (ndc.x * 0.5 + 0.5) * self.width as f32,
+            (ndc.y * 0.5 + 0.5) * self.height as f32,
+            ndc.z,
+        ))
+    }
+
+    /// Rasterizes the triangles of an occluder with the given model matrix, keeping the closest
+    /// depth of each pixel.
+    pub(crate) fn rasterize(&mut self, occluder: &Occluder, model: &Matrix4<f32>) {
+        for triangle in &occluder.triangles {
+            let mut corners = [Vector3::zeros(); 3];
+            let mut skip = false;
+            for (corner, position) in corners.iter_mut().zip(triangle) {
+                let world = Point3::from((model * Point3::from(*position).to_homogeneous()).xyz());
+                match self.project(&world) {
+                    Some(projected) => *corner = projected,
+                    // Triangles crossing the near plane are left out, which only culls less.
+                    None => skip = true,
+                }
+            }
+            if !skip {
+                self.triangle(&corners);
+            }
+        }
+    }
+
+    fn triangle(&mut self, [a, b, c]: &[Vector3<f32>; 3]) {
+        let edge = |from: &Vector3<f32>, to: &Vector3<f32>, x: f32, y: f32| {
+            (to.x - from.x) * (y - from.y) - (to.y - from.y) * (x - from.x)
+        };
+        let area = edge(a, b, c.x, c.y);
+        if area.abs() <= std::f32::EPSILON {
+            return;
+        }
+        let min = Vector2::new(a.x.min(b.x).min(c.x), a.y.min(b.y).min(c.y));
+        let max = Vector2::new(a.x.max(b.x).max(c.x), a.y.max(b.y).max(c.y));
+        let (x0, x1) = self.pixels(min.x, max.x, self.width);
+        let (y0, y1) = self.pixels(min.y, max.y, self.height);
+        // Pixels keep the farthest depth of the triangle over their area, since a sphere may
+        // reach in front of the occluder anywhere in the pixel.
+        let dz_dx = -((c.y - b.y) * a.z + (a.y - c.y) * b.z + (b.y - a.y) * c.z) / area;
+        let dz_dy = ((c.x - b.x) * a.z + (a.x - c.x) * b.z + (b.x - a.x) * c.z) / area;
+        let slack = (dz_dx.abs() + dz_dy.abs()) * 0.5;
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+                // Barycentric coordinates, positive inside the triangle for either winding.
+                let wa = edge(b, c, px, py) / area;
+                let wb = edge(c, a, px, py) / area;
+                let wc = edge(a, b, px, py) / area;
+                if wa < 0.0 || wb < 0.0 || wc < 0.0 {
+                    continue;
+                }
+                let depth = wa * a.z + wb * b.z + wc * c.z + slack;
+                let pixel = &mut self.depth[x + y * self.width];
+                if depth < *pixel {
+                    *pixel = depth;
+                }
+            }
+        }
+    }
+
+    /// Returns the range of pixels covered by the range of coordinates, clamped to the buffer.
+    fn pixels(&self, min: f32, max: f32, size: usize) -> (usize, usize) {
+        let clamp = |v: f32| (v.max(0.0) as usize).min(size);
+        (clamp(min.floor()), clamp(max.ceil()))
+    }
+
+    /// Checks whether a sphere in world space is hidden behind the occluders. The camera looks
+    /// down the negative Z axis of `view`.
+    ///
+    /// Occluders cover the pixels whose center they cover, so a pixel on the edge of an occluder
+    /// may be partly uncovered. The pixels around the sphere are tested too, one of which lies
+    /// past that edge, so spheres peeking past an occluder are never culled.
+    pub(crate) fn occludes(
+        &self,
+        view: &Matrix4<f32>,
+        proj: &Matrix4<f32>,
+        center: &Point3<f32>,
+        radius: f32,
+    ) -> bool {
+        let center = (view * center.to_homogeneous()).xyz();
+        let (min, max) = match projected_bounds(proj, &center, radius) {
+            Some(bounds) => bounds,
+            None => return false,
+        };
+        let nearest = proj * Point3::from(center + Vector3::new(0.0, 0.0, radius)).to_homogeneous();
+        if nearest.w <= std::f32::EPSILON || nearest.z < -nearest.w {
+            return false;
+        }
+        let depth = nearest.z / nearest.w;
+
+        let to_pixel = |ndc: f32, size: usize| (ndc * 0.5 + 0.5) * size as f32;
+        let (x0, x1) = self.pixels(
+            to_pixel(min.x, self.width) - 1.0,
+            to_pixel(max.x, self.width) + 1.0,
+            self.width,
+        );
+        let (y0, y1) = self.pixels(
+            to_pixel(min.y, self.height) - 1.0,
+            to_pixel(max.y, self.height) + 1.0,
+            self.height,
+        );
+        if x0 >= x1 || y0 >= y1 {
+            // Outside of the view, which is left to the other culling.
+            return false;
+        }
+        (y0..y1).all(|y| (x0..x1).all(|x| self.depth[x + y * self.width] < depth))
+    }
+}
+
+/// Returns the corners in normalized device coordinates of the screen rectangle covered by a
+/// sphere in view space, or `None` when part of the sphere is behind the camera.
+pub(crate) fn projected_bounds(
+    proj: &Matrix4<f32>,
+    center: &Vector3<f32>,
+    radius: f32,
+) -> Option<(Vector2<f32>, Vector2<f32>)> {
+    let mut min = Vector2::repeat(std::f32::MAX);
+    let mut max = Vector2::repeat(std::f32::MIN);
+    for &dx in &[-radius, radius] {
+        for &dy in &[-radius, radius] {
+            for &dz in &[-radius, radius] {
+                let clip = proj * Point3::from(center + Vector3::new(dx, dy, dz)).to_homogeneous();
+                if clip.w <= std::f32::EPSILON {
+                    return None;
+                }
+                let ndc = clip.xy() / clip.w;
+                min = nalgebra::inf(&min, &ndc);
+                max = nalgebra::sup(&max, &ndc);
+            }
+        }
+    }
+    Some((min, max))
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_core::nalgebra::{Perspective3, Translation3};
+
+    use super::*;
+
+    fn buffer_with_wall() -> (OcclusionBuffer, Matrix4<f32>) {
+        let proj = Perspective3::new(1.0, std::f32::consts::FRAC_PI_2, 0.1, 100.0).to_homogeneous();
+        let mut buffer = OcclusionBuffer::new(64, 64);
+        buffer.clear(proj);
+        // A wall of 20 by 20 units, 5 units in front of the camera.
+        let wall = Occluder::cuboid(Vector3::new(10.0, 10.0, 0.5));
+        buffer.rasterize(&wall, &Translation3::new(0.0, 0.0, -5.0).to_homogeneous());
+        (buffer, proj)
+    }
+
+    #[test]
+    fn cuboid_triangles() {
+        let cuboid = Occluder::cuboid(Vector3::new(1.0, 2.0, 3.0));
+        assert_eq!(12, cuboid.triangles.len());
+        for corner in cuboid.triangles.iter().flat_map(|t| t.iter()) {
+            assert_eq!(1.0, corner.x.abs());
+            assert_eq!(2.0, corner.y.abs());
+            assert_eq!(3.0, corner.z.abs());
+        }
+    }
+
+    #[test]
+    fn occluded_behind_wall() {
+        let (buffer, proj) = buffer_with_wall();
+        let view = Matrix4::identity();
+        assert!(buffer.occludes(&view, &proj, &Point3::new(0.0, 0.0, -20.0), 1.0));
+        // In front of the wall.
+        assert!(!buffer.occludes(&view, &proj, &Point3::new(0.0, 0.0, -2.0), 1.0));
+        // Reaching through the wall.
+        assert!(!buffer.occludes(&view, &proj, &Point3::new(0.0, 0.0, -6.0), 2.0));
+        // Around the camera.
+        assert!(!buffer.occludes(&view, &proj, &Point3::origin(), 1.0));
+    }
+
+    #[test]
+    fn peeking_past_edge_is_visible() {
+        let proj = Perspective3::new(1.0, std::f32::consts::FRAC_PI_2, 0.1, 100.0).to_homogeneous();
+        let mut buffer = OcclusionBuffer::new(64, 64);
+        buffer.clear(proj);
+        // A wall ending at x = 0.1, which covers most of pixel column 32 but not all of it.
+        let wall = Occluder::cuboid(Vector3::new(10.0, 10.0, 0.5));
+        buffer.rasterize(&wall, &Translation3::new(-9.9, 0.0, -5.0).to_homogeneous());
+        let view = Matrix4::identity();
+        assert!(buffer.occludes(&view, &proj, &Point3::new(-5.0, 0.0, -20.0), 1.0));
+        // A small sphere in the uncovered part of column 32.
+        assert!(!buffer.occludes(&view, &proj, &Point3::new(0.5625, 0.0, -20.0), 0.05));
+    }
+
+    #[test]
+    fn bounds_in_world() {
+        let bounds = OcclusionBounds {
+            center: Vector3::new(1.0, 0.0, 0.0),
+            radius: 2.0,
+        };
+        let model = Translation3::new(0.0, 3.0, 0.0).to_homogeneous()
+            * Matrix4::new_nonuniform_scaling(&Vector3::new(2.0, 1.0, 1.0));
+        let (center, radius) = bounds.world(&model);
+        assert_eq!(Point3::new(2.0, 3.0, 0.0), center);
+        assert_eq!(4.0, radius);
+    }
+}
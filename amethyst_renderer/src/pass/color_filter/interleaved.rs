@@ -0,0 +1,160 @@
+//! Color filter pass
+
+use gfx::{memory::Typed, pso::buffer::ElemStride};
+use glsl_layout::*;
+
+use amethyst_core::specs::Read;
+
+use crate::{
+    color_filter::ColorFilter,
+    error::Result,
+    mesh::Mesh,
+    pipe::{
+        pass::{Pass, PassData},
+        Effect, NewEffect, Targets,
+    },
+    shape::Shape,
+    tex::{FilterMethod, SamplerInfo, WrapMode},
+    types::{Encoder, Factory, RawShaderResourceView, Sampler},
+    vertex::{PosTex, VertexFormat},
+};
+
+use super::{FRAG_SRC, VERT_SRC};
+
+#[derive(Clone, Copy, Debug, Uniform)]
+pub(crate) struct ColorFilterArgs {
+    red: vec4,
+    green: vec4,
+    blue: vec4,
+}
+
+impl ColorFilterArgs {
+    fn new(filter: &ColorFilter) -> Self {
+        let m = filter.matrix();
+        let column = |i: usize| [m[0][i], m[1][i], m[2][i], 0.0].into();
+        ColorFilterArgs {
+            red: column(0),
+            green: column(1),
+            blue: column(2),
+        }
+    }
+}
+
+/// Draws the frame of a target onto the screen through the `ColorFilter`.
+///
+/// Have the stages drawing the world and the UI draw into the target, and add this pass to a
+/// stage drawing to the backbuffer after them. It copies the frame even without a filter.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let pipe = Pipeline::build()
+///     .with_target(Target::named("frame").with_num_color_bufs(1).with_depth_buf(true))
+///     .with_stage(
+///         Stage::with_target("frame")
+///             .clear_target([0.0, 0.0, 0.0, 1.0], 1.0)
+///             .with_pass(DrawShaded::<PosNormTex>::new())
+///             .with_pass(DrawUi::new()),
+///     )
+///     .with_stage(Stage::with_backbuffer().with_pass(DrawColorFilter::new("frame")));
+/// ```
+#[derive(Clone, Debug)]
+pub struct DrawColorFilter {
+    input: String,
+    mesh: Option<Mesh>,
+    frame: Option<RawShaderResourceView>,
+    sampler: Option<Sampler>,
+}
+
+impl DrawColorFilter {
+    /// Create instance of `DrawColorFilter` pass, reading the first color buffer of the target
+    /// named `input`.
+    pub fn new<N: Into<String>>(input: N) -> Self {
+        DrawColorFilter {
+            input: input.into(),
+            mesh: None,
+            frame: None,
+            sampler: None,
+        }
+    }
+}
+
+impl<'a> PassData<'a> for DrawColorFilter {
+    type Data = Read<'a, ColorFilter>;
+}
+
+impl Pass for DrawColorFilter {
+    fn compile(&mut self, mut effect: NewEffect<'_>) -> Result<Effect> {
+        use gfx::Factory;
+
+        let verts = Shape::Plane(None).generate_vertices::<Vec<PosTex>>(None);
+        self.mesh = Some(Mesh::build(verts).build(&mut effect.factory)?);
+        self.sampler = Some(
+            effect
+                .factory
+                .create_sampler(SamplerInfo::new(FilterMethod::Scale, WrapMode::Clamp)),
+        );
+
+        effect
+            .simple(VERT_SRC, FRAG_SRC)
+            .without_back_face_culling()
+            .with_raw_constant_buffer(
+                "ColorFilterArgs",
+                std::mem::size_of::<<ColorFilterArgs as Uniform>::Std140>(),
+                1,
+            )
+            .with_raw_vertex_buffer(PosTex::ATTRIBUTES, PosTex::size() as ElemStride, 0)
+            .with_texture("frame")
+            .with_output("color", None)
+            .build()
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        _factory: Factory,
+        filter: <Self as PassData<'a>>::Data,
+    ) {
+        let (frame, sampler) = match (self.frame.as_ref(), self.sampler.as_ref()) {
+            (Some(frame), Some(sampler)) => (frame, sampler),
+            _ => return,
+        };
+        let mesh = self
+            .mesh
+            .as_ref()
+            .expect("Pass doesn't seem to be compiled.");
+
+        if let Some(vbuf) = mesh.buffer(PosTex::ATTRIBUTES) {
+            effect.data.vertex_bufs.push(vbuf.clone());
+        } else {
+            effect.clear();
+            return;
+        }
+
+        let args = ColorFilterArgs::new(&filter);
+        effect.update_constant_buffer("ColorFilterArgs", &args.std140(), encoder);
+        effect.data.textures.push(frame.clone());
+        effect.data.samplers.push(sampler.clone());
+        effect.draw(mesh.slice(), encoder);
+        effect.clear();
+    }
+
+    fn input_targets(&mut self, targets: &Targets) {
+        self.frame = targets
+            .get(&self.input)
+            .and_then(|target| target.color_buf(0))
+            .and_then(|buffer| buffer.as_input.as_ref())
+            .map(|view| view.raw().clone());
+        if self.frame.is_none() {
+            error!(
+                "`DrawColorFilter` found no color buffer to read in the target {:?}",
+                self.input
+            );
+        }
+    }
+
+    fn inputs(&self) -> Vec<String> {
+        vec![self.input.clone()]
+    }
+}
@@ -14,14 +14,18 @@ use crate::{
     cam::{ActiveCamera, Camera},
     debug_drawing::{DebugLine, DebugLines, DebugLinesComponent},
     error::Result,
+    layers::RenderLayers,
     mesh::Mesh,
-    pass::util::{get_camera, set_attribute_buffers, set_vertex_args, setup_vertex_args},
+    pass::util::{
+        camera_layers, set_attribute_buffers, set_vertex_args, setup_vertex_args, PassCameras,
+    },
     pipe::{
         pass::{Pass, PassData},
         DepthMode, Effect, NewEffect,
     },
     types::{Encoder, Factory},
     vertex::{Color, Normal, Position, Query},
+    view_model::ViewModelCamera,
     Rgba,
 };
 
@@ -76,6 +80,8 @@ where
         WriteStorage<'a, DebugLinesComponent>, // DebugLines components
         Option<Write<'a, DebugLines>>,         // DebugLines resource
         Read<'a, DebugLinesParams>,
+        ReadStorage<'a, RenderLayers>,
+        ReadStorage<'a, ViewModelCamera>,
     );
 }
 
@@ -104,14 +110,29 @@ where
         encoder: &mut Encoder,
         effect: &mut Effect,
         mut factory: Factory,
-        (active, camera, global, lines_components, lines_resource, lines_params): <Self as PassData<'a>>::Data,
+        (
+            active,
+            camera,
+            global,
+            lines_components,
+            lines_resource,
+            lines_params,
+            render_layers,
+            view_models,
+        ): <Self as PassData<'a>>::Data,
     ) {
         trace!("Drawing debug lines pass");
+        let cameras = PassCameras::new(&active, &camera, &global, &view_models);
+        let camera = cameras.main();
+        let seen_layers = camera_layers(camera);
         let debug_lines = {
             let mut lines = Vec::<DebugLine>::new();
 
-            for debug_lines_component in (&lines_components).join() {
-                lines.extend(&debug_lines_component.lines);
+            for (debug_lines_component, layers) in (&lines_components, render_layers.maybe()).join()
+            {
+                if seen_layers.sees(layers) {
+                    lines.extend(&debug_lines_component.lines);
+                }
             }
 
             if let Some(mut lines_resource) = lines_resource {
@@ -126,7 +147,6 @@ where
             return;
         }
 
-        let camera = get_camera(active, &camera, &global);
         effect.update_global(
             "camera_position",
             camera
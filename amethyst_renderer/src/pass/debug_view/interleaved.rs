@@ -0,0 +1,75 @@
+//! Debug view pass
+
+use std::marker::PhantomData;
+
+use gfx::pso::buffer::ElemStride;
+
+use crate::{
+    error::Result,
+    pipe::{
+        pass::{Pass, PassData},
+        Effect, NewEffect,
+    },
+    types::{Encoder, Factory},
+    vertex::{Normal, Position, Query, TexCoord},
+};
+
+use super::*;
+
+/// Draw the `DebugView`s of meshes: their wireframe, normals and texture coordinates
+///
+/// The pass draws over the meshes already drawn, so it has to be added after the other passes.
+/// The parameters shared by all entities are in the `DebugViewParams` resource.
+///
+/// See the [crate level documentation](index.html) for information about interleaved and separate
+/// passes.
+///
+/// # Type Parameters:
+///
+/// * `V`: `VertexFormat`
+#[derive(Derivative, Clone, Debug, PartialEq)]
+#[derivative(Default(bound = "V: Query<(Position, Normal, TexCoord)>"))]
+pub struct DrawDebugView<V> {
+    _pd: PhantomData<V>,
+}
+
+impl<V> DrawDebugView<V>
+where
+    V: Query<(Position, Normal, TexCoord)>,
+{
+    /// Create instance of `DrawDebugView` pass
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<'a, V> PassData<'a> for DrawDebugView<V>
+where
+    V: Query<(Position, Normal, TexCoord)>,
+{
+    type Data = DebugViewData<'a>;
+}
+
+impl<V> Pass for DrawDebugView<V>
+where
+    V: Query<(Position, Normal, TexCoord)>,
+{
+    fn compile(&mut self, effect: NewEffect<'_>) -> Result<Effect> {
+        debug!("Building debug view pass");
+        let mut builder = effect.geom(VERT_SRC, GEOM_SRC, FRAG_SRC);
+        builder.with_raw_vertex_buffer(V::QUERIED_ATTRIBUTES, V::size() as ElemStride, 0);
+        setup_debug_view(&mut builder);
+        builder.build()
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        _factory: Factory,
+        data: <Self as PassData<'a>>::Data,
+    ) {
+        trace!("Drawing debug view pass");
+        draw_debug_view(encoder, effect, data, &[V::QUERIED_ATTRIBUTES]);
+    }
+}
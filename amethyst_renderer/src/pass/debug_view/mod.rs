@@ -0,0 +1,165 @@
+pub use self::{interleaved::DrawDebugView, separate::DrawDebugViewSeparate};
+
+mod interleaved;
+mod separate;
+
+use gfx_core::state::ColorMask;
+
+use amethyst_assets::AssetStorage;
+use amethyst_core::{
+    specs::prelude::{Join, Read, ReadExpect, ReadStorage},
+    transform::GlobalTransform,
+};
+
+use crate::{
+    cam::{ActiveCamera, Camera},
+    debug_view::DebugView,
+    hidden::{Hidden, HiddenPropagate},
+    layers::RenderLayers,
+    mesh::{Mesh, MeshHandle},
+    mtl::{Material, MaterialDefaults, MaterialOverride},
+    pass::util::{draw_mesh, setup_textures, setup_vertex_args, PassCameras, TextureType},
+    pipe::{DepthMode, Effect, EffectBuilder},
+    tex::Texture,
+    transparent::ALPHA,
+    types::Encoder,
+    vertex::Attributes,
+    view_model::ViewModelCamera,
+};
+
+static VERT_SRC: &[u8] = include_bytes!("../shaders/vertex/debug_view.glsl");
+static GEOM_SRC: &[u8] = include_bytes!("../shaders/geometry/debug_view.glsl");
+static FRAG_SRC: &[u8] = include_bytes!("../shaders/fragment/debug_view.glsl");
+
+static TEXTURES: [TextureType; 1] = [TextureType::Albedo];
+
+/// Parameters of the debug view passes, the same for every entity.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DebugViewParams {
+    /// Length of the drawn normals, in world units, default is 0.1 units
+    pub normal_length: f32,
+    /// Width of the drawn normals, in world units, default is 1.0 / 400.0 units
+    pub line_width: f32,
+    /// Number of checkerboard cells along each texture coordinate, default is 8
+    pub checker_size: f32,
+}
+
+impl Default for DebugViewParams {
+    fn default() -> Self {
+        DebugViewParams {
+            normal_length: 0.1,
+            line_width: 1.0 / 400.0,
+            checker_size: 8.0,
+        }
+    }
+}
+
+type DebugViewData<'a> = (
+    Read<'a, ActiveCamera>,
+    ReadStorage<'a, Camera>,
+    Read<'a, AssetStorage<Mesh>>,
+    Read<'a, AssetStorage<Texture>>,
+    ReadExpect<'a, MaterialDefaults>,
+    Read<'a, DebugViewParams>,
+    ReadStorage<'a, Hidden>,
+    ReadStorage<'a, HiddenPropagate>,
+    ReadStorage<'a, MeshHandle>,
+    ReadStorage<'a, Material>,
+    ReadStorage<'a, MaterialOverride>,
+    ReadStorage<'a, GlobalTransform>,
+    ReadStorage<'a, DebugView>,
+    ReadStorage<'a, RenderLayers>,
+    ReadStorage<'a, ViewModelCamera>,
+);
+
+fn setup_debug_view(builder: &mut EffectBuilder<'_>) {
+    setup_vertex_args(builder);
+    setup_textures(builder, &TEXTURES);
+    builder
+        .with_raw_global("debug_mode")
+        .with_raw_global("camera_position")
+        .with_raw_global("normal_length")
+        .with_raw_global("line_width")
+        .with_raw_global("checker_size")
+        // The overlay is drawn over the already drawn meshes, without hiding the ones behind it.
+        .without_back_face_culling()
+        .with_blended_output(
+            "color",
+            ColorMask::all(),
+            ALPHA,
+            Some(DepthMode::LessEqualTest),
+        );
+}
+
+fn draw_debug_view<'a>(
+    encoder: &mut Encoder,
+    effect: &mut Effect,
+    (
+        active,
+        camera,
+        mesh_storage,
+        tex_storage,
+        material_defaults,
+        params,
+        hidden,
+        hidden_prop,
+        mesh,
+        material,
+        material_overrides,
+        global,
+        debug_view,
+        render_layers,
+        view_models,
+    ): DebugViewData<'a>,
+    attributes: &[Attributes<'static>],
+) {
+    let cameras = PassCameras::new(&active, &camera, &global, &view_models);
+
+    effect.update_global("normal_length", params.normal_length);
+    effect.update_global("line_width", params.line_width);
+    effect.update_global("checker_size", params.checker_size);
+
+    for (mesh, material, overrides, global, view, _, _, layers) in (
+        &mesh,
+        &material,
+        material_overrides.maybe(),
+        &global,
+        &debug_view,
+        !&hidden,
+        !&hidden_prop,
+        render_layers.maybe(),
+    )
+        .join()
+    {
+        if view.is_empty() {
+            continue;
+        }
+        let camera = match cameras.select(layers) {
+            Some(camera) => camera,
+            None => continue,
+        };
+        effect.update_global("debug_mode", view.mode());
+        effect.update_global(
+            "camera_position",
+            camera
+                .map(|(_, transform)| transform.0.column(3).xyz().into())
+                .unwrap_or([0.0; 3]),
+        );
+        draw_mesh(
+            encoder,
+            effect,
+            false,
+            mesh_storage.get(mesh),
+            None,
+            &tex_storage,
+            Some(material),
+            overrides,
+            &material_defaults,
+            None,
+            camera,
+            Some(global),
+            attributes,
+            &TEXTURES,
+        );
+    }
+}
@@ -0,0 +1,78 @@
+//! Debug view pass
+
+use gfx::pso::buffer::ElemStride;
+
+use crate::{
+    error::Result,
+    pipe::{
+        pass::{Pass, PassData},
+        Effect, NewEffect,
+    },
+    types::{Encoder, Factory},
+    vertex::{Attributes, Normal, Position, Separate, TexCoord, VertexFormat},
+};
+
+use super::*;
+
+static ATTRIBUTES: [Attributes<'static>; 3] = [
+    Separate::<Position>::ATTRIBUTES,
+    Separate::<Normal>::ATTRIBUTES,
+    Separate::<TexCoord>::ATTRIBUTES,
+];
+
+/// Draw the `DebugView`s of meshes: their wireframe, normals and texture coordinates
+///
+/// The pass draws over the meshes already drawn, so it has to be added after the other passes.
+/// The parameters shared by all entities are in the `DebugViewParams` resource.
+///
+/// See the [crate level documentation](index.html) for information about interleaved and separate
+/// passes.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct DrawDebugViewSeparate;
+
+impl DrawDebugViewSeparate {
+    /// Create instance of `DrawDebugViewSeparate` pass
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<'a> PassData<'a> for DrawDebugViewSeparate {
+    type Data = DebugViewData<'a>;
+}
+
+impl Pass for DrawDebugViewSeparate {
+    fn compile(&mut self, effect: NewEffect<'_>) -> Result<Effect> {
+        debug!("Building debug view pass");
+        let mut builder = effect.geom(VERT_SRC, GEOM_SRC, FRAG_SRC);
+        builder
+            .with_raw_vertex_buffer(
+                Separate::<Position>::ATTRIBUTES,
+                Separate::<Position>::size() as ElemStride,
+                0,
+            )
+            .with_raw_vertex_buffer(
+                Separate::<Normal>::ATTRIBUTES,
+                Separate::<Normal>::size() as ElemStride,
+                0,
+            )
+            .with_raw_vertex_buffer(
+                Separate::<TexCoord>::ATTRIBUTES,
+                Separate::<TexCoord>::size() as ElemStride,
+                0,
+            );
+        setup_debug_view(&mut builder);
+        builder.build()
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        _factory: Factory,
+        data: <Self as PassData<'a>>::Data,
+    ) {
+        trace!("Drawing debug view pass");
+        draw_debug_view(encoder, effect, data, &ATTRIBUTES);
+    }
+}
@@ -16,9 +16,10 @@ use crate::{
     cam::{ActiveCamera, Camera},
     error::Result,
     hidden::{Hidden, HiddenPropagate},
+    layers::RenderLayers,
     mesh::{Mesh, MeshHandle},
-    mtl::{Material, MaterialDefaults},
-    pass::util::{draw_mesh, get_camera, setup_textures, VertexArgs},
+    mtl::{Material, MaterialDefaults, MaterialOverride},
+    pass::util::{draw_mesh, setup_textures, PassCameras, VertexArgs},
     pipe::{
         pass::{Pass, PassData},
         DepthMode, Effect, NewEffect,
@@ -26,6 +27,7 @@ use crate::{
     tex::Texture,
     types::{Encoder, Factory},
     vertex::{Position, Query, TexCoord},
+    view_model::ViewModelCamera,
     visibility::Visibility,
     Rgba,
 };
@@ -84,8 +86,11 @@ where
         ReadStorage<'a, HiddenPropagate>,
         ReadStorage<'a, MeshHandle>,
         ReadStorage<'a, Material>,
+        ReadStorage<'a, MaterialOverride>,
         ReadStorage<'a, GlobalTransform>,
         ReadStorage<'a, Rgba>,
+        ReadStorage<'a, RenderLayers>,
+        ReadStorage<'a, ViewModelCamera>,
     );
 }
 
@@ -103,6 +108,7 @@ where
                 1,
             )
             .with_raw_vertex_buffer(V::QUERIED_ATTRIBUTES, V::size() as ElemStride, 0);
+        builder.with_cull_face_variants();
         setup_textures(&mut builder, &TEXTURES);
         match self.transparency {
             Some((mask, blend, depth)) => builder.with_blended_output("color", mask, blend, depth),
@@ -127,24 +133,33 @@ where
             hidden_prop,
             mesh,
             material,
+            material_overrides,
             global,
             rgba,
+            render_layers,
+            view_models,
         ): <Self as PassData<'a>>::Data,
     ) {
-        let camera = get_camera(active, &camera, &global);
+        let cameras = PassCameras::new(&active, &camera, &global, &view_models);
 
         match visibility {
             None => {
-                for (mesh, material, global, rgba, _, _) in (
+                for (mesh, material, overrides, global, rgba, _, _, layers) in (
                     &mesh,
                     &material,
+                    material_overrides.maybe(),
                     &global,
                     rgba.maybe(),
                     !&hidden,
                     !&hidden_prop,
+                    render_layers.maybe(),
                 )
                     .join()
                 {
+                    let camera = match cameras.select(layers) {
+                        Some(camera) => camera,
+                        None => continue,
+                    };
                     draw_mesh(
                         encoder,
                         effect,
@@ -153,6 +168,7 @@ where
                         None,
                         &tex_storage,
                         Some(material),
+                        overrides,
                         &material_defaults,
                         rgba,
                         camera,
@@ -163,15 +179,21 @@ where
                 }
             }
             Some(ref visibility) => {
-                for (mesh, material, global, rgba, _) in (
+                for (mesh, material, overrides, global, rgba, _, layers) in (
                     &mesh,
                     &material,
+                    material_overrides.maybe(),
                     &global,
                     rgba.maybe(),
                     &visibility.visible_unordered,
+                    render_layers.maybe(),
                 )
                     .join()
                 {
+                    let camera = match cameras.select(layers) {
+                        Some(camera) => camera,
+                        None => continue,
+                    };
                     draw_mesh(
                         encoder,
                         effect,
@@ -180,6 +202,7 @@ where
                         None,
                         &tex_storage,
                         Some(material),
+                        overrides,
                         &material_defaults,
                         rgba,
                         camera,
@@ -190,6 +213,10 @@ where
                 }
 
                 for entity in &visibility.visible_ordered {
+                    let camera = match cameras.select(render_layers.get(*entity)) {
+                        Some(camera) => camera,
+                        None => continue,
+                    };
                     if let Some(mesh) = mesh.get(*entity) {
                         draw_mesh(
                             encoder,
@@ -199,6 +226,7 @@ where
                             None,
                             &tex_storage,
                             material.get(*entity),
+                            material_overrides.get(*entity),
                             &material_defaults,
                             rgba.get(*entity),
                             camera,
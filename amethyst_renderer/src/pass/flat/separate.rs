@@ -14,11 +14,12 @@ use crate::{
     cam::{ActiveCamera, Camera},
     error::Result,
     hidden::{Hidden, HiddenPropagate},
+    layers::RenderLayers,
     mesh::{Mesh, MeshHandle},
-    mtl::{Material, MaterialDefaults},
+    mtl::{Material, MaterialDefaults, MaterialOverride},
     pass::{
         skinning::{create_skinning_effect, setup_skinning_buffers},
-        util::{draw_mesh, get_camera, setup_textures, VertexArgs},
+        util::{draw_mesh, setup_textures, PassCameras, VertexArgs},
     },
     pipe::{
         pass::{Pass, PassData},
@@ -28,6 +29,7 @@ use crate::{
     tex::Texture,
     types::{Encoder, Factory},
     vertex::{Attributes, Position, Separate, TexCoord, VertexFormat},
+    view_model::ViewModelCamera,
     visibility::Visibility,
     Rgba,
 };
@@ -93,9 +95,12 @@ impl<'a> PassData<'a> for DrawFlatSeparate {
         ReadStorage<'a, HiddenPropagate>,
         ReadStorage<'a, MeshHandle>,
         ReadStorage<'a, Material>,
+        ReadStorage<'a, MaterialOverride>,
         ReadStorage<'a, GlobalTransform>,
         ReadStorage<'a, JointTransforms>,
         ReadStorage<'a, Rgba>,
+        ReadStorage<'a, RenderLayers>,
+        ReadStorage<'a, ViewModelCamera>,
     );
 }
 
@@ -103,7 +108,7 @@ impl Pass for DrawFlatSeparate {
     fn compile(&mut self, effect: NewEffect<'_>) -> Result<Effect> {
         use std::mem;
         let mut builder = if self.skinning {
-            create_skinning_effect(effect, FRAG_SRC)
+            create_skinning_effect(effect, false, FRAG_SRC)
         } else {
             effect.simple(VERT_SRC, FRAG_SRC)
         };
@@ -126,6 +131,7 @@ impl Pass for DrawFlatSeparate {
             mem::size_of::<<VertexArgs as Uniform>::Std140>(),
             1,
         );
+        builder.with_cull_face_variants();
         setup_textures(&mut builder, &TEXTURES);
         match self.transparency {
             Some((mask, blend, depth)) => builder.with_blended_output("color", mask, blend, depth),
@@ -150,26 +156,35 @@ impl Pass for DrawFlatSeparate {
             hidden_prop,
             mesh,
             material,
+            material_overrides,
             global,
             joints,
             rgba,
+            render_layers,
+            view_models,
         ): <Self as PassData<'a>>::Data,
     ) {
-        let camera = get_camera(active, &camera, &global);
+        let cameras = PassCameras::new(&active, &camera, &global, &view_models);
 
         match visibility {
             None => {
-                for (joint, mesh, material, global, rgba, _, _) in (
+                for (joint, mesh, material, overrides, global, rgba, _, _, layers) in (
                     joints.maybe(),
                     &mesh,
                     &material,
+                    material_overrides.maybe(),
                     &global,
                     rgba.maybe(),
                     !&hidden,
                     !&hidden_prop,
+                    render_layers.maybe(),
                 )
                     .join()
                 {
+                    let camera = match cameras.select(layers) {
+                        Some(camera) => camera,
+                        None => continue,
+                    };
                     draw_mesh(
                         encoder,
                         effect,
@@ -178,6 +193,7 @@ impl Pass for DrawFlatSeparate {
                         joint,
                         &tex_storage,
                         Some(material),
+                        overrides,
                         &material_defaults,
                         rgba,
                         camera,
@@ -188,16 +204,22 @@ impl Pass for DrawFlatSeparate {
                 }
             }
             Some(ref visibility) => {
-                for (joint, mesh, material, global, rgba, _) in (
+                for (joint, mesh, material, overrides, global, rgba, _, layers) in (
                     joints.maybe(),
                     &mesh,
                     &material,
+                    material_overrides.maybe(),
                     &global,
                     rgba.maybe(),
                     &visibility.visible_unordered,
+                    render_layers.maybe(),
                 )
                     .join()
                 {
+                    let camera = match cameras.select(layers) {
+                        Some(camera) => camera,
+                        None => continue,
+                    };
                     draw_mesh(
                         encoder,
                         effect,
@@ -206,6 +228,7 @@ impl Pass for DrawFlatSeparate {
                         joint,
                         &tex_storage,
                         Some(material),
+                        overrides,
                         &material_defaults,
                         rgba,
                         camera,
@@ -216,6 +239,10 @@ impl Pass for DrawFlatSeparate {
                 }
 
                 for entity in &visibility.visible_ordered {
+                    let camera = match cameras.select(render_layers.get(*entity)) {
+                        Some(camera) => camera,
+                        None => continue,
+                    };
                     if let Some(mesh) = mesh.get(*entity) {
                         draw_mesh(
                             encoder,
@@ -225,6 +252,7 @@ impl Pass for DrawFlatSeparate {
                             joints.get(*entity),
                             &tex_storage,
                             material.get(*entity),
+                            material_overrides.get(*entity),
                             &material_defaults,
                             rgba.get(*entity),
                             camera,
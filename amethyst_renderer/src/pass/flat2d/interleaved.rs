@@ -15,8 +15,11 @@ use crate::{
     cam::{ActiveCamera, Camera},
     error::Result,
     hidden::{Hidden, HiddenPropagate},
+    layers::RenderLayers,
     mesh::MeshHandle,
-    pass::util::{add_texture, get_camera, set_view_args, setup_textures, ViewArgs},
+    pass::util::{
+        add_texture, camera_layers, set_view_args, setup_textures, PassCameras, ViewArgs,
+    },
     pipe::{
         pass::{Pass, PassData},
         DepthMode, Effect, NewEffect,
@@ -26,6 +29,7 @@ use crate::{
     tex::{Texture, TextureHandle},
     types::{Encoder, Factory, Slice},
     vertex::{Attributes, Query, VertexFormat},
+    view_model::ViewModelCamera,
     Color, Rgba,
 };
 
@@ -79,6 +83,8 @@ impl<'a> PassData<'a> for DrawFlat2D {
         ReadStorage<'a, Flipped>,
         ReadStorage<'a, MeshHandle>,
         ReadStorage<'a, Rgba>,
+        ReadStorage<'a, RenderLayers>,
+        ReadStorage<'a, ViewModelCamera>,
     );
 }
 
@@ -122,22 +128,30 @@ impl Pass for DrawFlat2D {
             flipped,
             mesh,
             rgba,
+            render_layers,
+            view_models,
         ): <Self as PassData<'a>>::Data,
     ) {
-        let camera = get_camera(active, &camera, &global);
+        let cameras = PassCameras::new(&active, &camera, &global, &view_models);
+        let camera = cameras.main();
+        let seen_layers = camera_layers(camera);
 
         match visibility {
             None => {
-                for (sprite_render, global, flipped, rgba, _, _) in (
+                for (sprite_render, global, flipped, rgba, _, _, layers) in (
                     &sprite_render,
                     &global,
                     flipped.maybe(),
                     rgba.maybe(),
                     !&hidden,
                     !&hidden_prop,
+                    render_layers.maybe(),
                 )
                     .join()
                 {
+                    if !seen_layers.sees(layers) {
+                        continue;
+                    }
                     self.batch.add_sprite(
                         sprite_render,
                         Some(global),
@@ -148,7 +162,7 @@ impl Pass for DrawFlat2D {
                     );
                 }
 
-                for (image_render, global, flipped, rgba, _, _, _) in (
+                for (image_render, global, flipped, rgba, _, _, _, layers) in (
                     &texture_handle,
                     &global,
                     flipped.maybe(),
@@ -156,9 +170,13 @@ impl Pass for DrawFlat2D {
                     !&hidden,
                     !&hidden_prop,
                     !&mesh,
+                    render_layers.maybe(),
                 )
                     .join()
                 {
+                    if !seen_layers.sees(layers) {
+                        continue;
+                    }
                     self.batch
                         .add_image(image_render, Some(global), flipped, rgba, &tex_storage);
                 }
@@ -166,15 +184,19 @@ impl Pass for DrawFlat2D {
                 self.batch.sort();
             }
             Some(ref visibility) => {
-                for (sprite_render, global, flipped, rgba, _) in (
+                for (sprite_render, global, flipped, rgba, _, layers) in (
                     &sprite_render,
                     &global,
                     flipped.maybe(),
                     rgba.maybe(),
                     &visibility.visible_unordered,
+                    render_layers.maybe(),
                 )
                     .join()
                 {
+                    if !seen_layers.sees(layers) {
+                        continue;
+                    }
                     self.batch.add_sprite(
                         sprite_render,
                         Some(global),
@@ -185,16 +207,20 @@ impl Pass for DrawFlat2D {
                     );
                 }
 
-                for (image_render, global, flipped, rgba, _, _) in (
+                for (image_render, global, flipped, rgba, _, _, layers) in (
                     &texture_handle,
                     &global,
                     flipped.maybe(),
                     rgba.maybe(),
                     &visibility.visible_unordered,
                     !&mesh,
+                    render_layers.maybe(),
                 )
                     .join()
                 {
+                    if !seen_layers.sees(layers) {
+                        continue;
+                    }
                     self.batch
                         .add_image(image_render, Some(global), flipped, rgba, &tex_storage);
                 }
@@ -203,6 +229,9 @@ impl Pass for DrawFlat2D {
                 self.batch.sort();
 
                 for entity in &visibility.visible_ordered {
+                    if !seen_layers.sees(render_layers.get(*entity)) {
+                        continue;
+                    }
                     if let Some(sprite_render) = sprite_render.get(*entity) {
                         self.batch.add_sprite(
                             sprite_render,
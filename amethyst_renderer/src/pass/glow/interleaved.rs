@@ -0,0 +1,193 @@
+//! Glow pass
+
+use std::marker::PhantomData;
+
+use gfx::{preset::blend::ADD, pso::buffer::ElemStride};
+use gfx_core::state::{ColorMask, CullFace};
+
+use amethyst_assets::AssetStorage;
+use amethyst_core::{
+    specs::prelude::{Join, Read, ReadExpect, ReadStorage},
+    transform::GlobalTransform,
+};
+
+use crate::{
+    cam::{ActiveCamera, Camera},
+    error::Result,
+    hidden::{Hidden, HiddenPropagate},
+    layers::RenderLayers,
+    mesh::{Mesh, MeshHandle},
+    mtl::{Material, MaterialDefaults, MaterialOverride},
+    pass::util::{
+        add_textures, set_attribute_buffers, set_vertex_args, setup_textures, setup_vertex_args,
+        PassCameras,
+    },
+    pipe::{
+        pass::{Pass, PassData},
+        DepthMode, Effect, NewEffect,
+    },
+    tex::Texture,
+    types::{Encoder, Factory},
+    vertex::{Normal, Position, Query, TexCoord},
+    view_model::ViewModelCamera,
+    Rgba,
+};
+
+use super::*;
+
+/// Draw a glow around the meshes of entities with a `Glow`, in the color of the emission of
+/// their material.
+///
+/// A cheap glow without render targets for the forward pipelines: a shell grown out of each
+/// mesh along its normals is added onto the image, fading out towards its outer edge. Add the
+/// pass after the passes drawing the meshes, so they hide the inside of the shells.
+///
+/// # Type Parameters
+///
+/// * `V`: `VertexFormat`
+#[derive(Derivative, Clone, Debug, PartialEq)]
+#[derivative(Default(bound = "V: Query<(Position, Normal, TexCoord)>"))]
+pub struct DrawGlow<V> {
+    _pd: PhantomData<V>,
+}
+
+impl<V> DrawGlow<V>
+where
+    V: Query<(Position, Normal, TexCoord)>,
+{
+    /// Create instance of `DrawGlow` pass
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<'a, V> PassData<'a> for DrawGlow<V>
+where
+    V: Query<(Position, Normal, TexCoord)>,
+{
+    type Data = (
+        Read<'a, ActiveCamera>,
+        ReadStorage<'a, Camera>,
+        Read<'a, AssetStorage<Mesh>>,
+        Read<'a, AssetStorage<Texture>>,
+        ReadExpect<'a, MaterialDefaults>,
+        ReadStorage<'a, Hidden>,
+        ReadStorage<'a, HiddenPropagate>,
+        ReadStorage<'a, MeshHandle>,
+        ReadStorage<'a, Material>,
+        ReadStorage<'a, MaterialOverride>,
+        ReadStorage<'a, GlobalTransform>,
+        ReadStorage<'a, Glow>,
+        ReadStorage<'a, Rgba>,
+        ReadStorage<'a, RenderLayers>,
+        ReadStorage<'a, ViewModelCamera>,
+    );
+}
+
+impl<V> Pass for DrawGlow<V>
+where
+    V: Query<(Position, Normal, TexCoord)>,
+{
+    fn compile(&mut self, effect: NewEffect<'_>) -> Result<Effect> {
+        let mut builder = effect.simple(VERT_SRC, FRAG_SRC);
+        builder.with_raw_vertex_buffer(V::QUERIED_ATTRIBUTES, V::size() as ElemStride, 0);
+        builder.with_cull_face_variants();
+        setup_vertex_args(&mut builder);
+        setup_textures(&mut builder, &TEXTURES);
+        builder
+            .with_raw_global("camera_position")
+            .with_raw_global("glow_radius")
+            .with_raw_global("glow_intensity")
+            .with_blended_output(
+                "color",
+                ColorMask::all(),
+                ADD,
+                Some(DepthMode::LessEqualTest),
+            );
+        builder.build()
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        _factory: Factory,
+        (
+            active,
+            camera,
+            mesh_storage,
+            tex_storage,
+            material_defaults,
+            hidden,
+            hidden_prop,
+            mesh,
+            material,
+            material_overrides,
+            global,
+            glow,
+            rgba,
+            render_layers,
+            view_models,
+        ): <Self as PassData<'a>>::Data,
+    ) {
+        let cameras = PassCameras::new(&active, &camera, &global, &view_models);
+        // Only the back faces of the shells are drawn, the front faces would cover the meshes.
+        effect.set_cull_face(CullFace::Front);
+
+        for (mesh, material, overrides, global, glow, rgba, _, _, layers) in (
+            &mesh,
+            &material,
+            material_overrides.maybe(),
+            &global,
+            &glow,
+            rgba.maybe(),
+            !&hidden,
+            !&hidden_prop,
+            render_layers.maybe(),
+        )
+            .join()
+        {
+            let camera = match cameras.select(layers) {
+                Some(camera) => camera,
+                None => continue,
+            };
+            let mesh = match mesh_storage.get(mesh) {
+                Some(mesh) => mesh,
+                None => continue,
+            };
+            if !set_attribute_buffers(effect, mesh, &[V::QUERIED_ATTRIBUTES]) {
+                effect.clear();
+                continue;
+            }
+
+            let rgba = rgba.cloned().unwrap_or(Rgba::WHITE);
+            set_vertex_args(
+                effect,
+                encoder,
+                camera,
+                global,
+                overrides.map_or(rgba, |overrides| overrides.tinted(rgba)),
+            );
+            effect.update_global(
+                "camera_position",
+                camera
+                    .map(|(_, transform)| transform.0.column(3).xyz().into())
+                    .unwrap_or([0.0; 3]),
+            );
+            effect.update_global("glow_radius", glow.radius);
+            effect.update_global("glow_intensity", glow.intensity);
+            add_textures(
+                effect,
+                encoder,
+                &tex_storage,
+                material,
+                overrides,
+                &material_defaults.0,
+                &TEXTURES,
+            );
+
+            effect.draw(mesh.slice(), encoder);
+            effect.clear();
+        }
+    }
+}
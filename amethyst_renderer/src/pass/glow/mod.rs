@@ -0,0 +1,35 @@
+pub use self::interleaved::DrawGlow;
+
+use amethyst_core::specs::prelude::{Component, DenseVecStorage};
+
+use crate::pass::util::TextureType;
+
+mod interleaved;
+
+static VERT_SRC: &[u8] = include_bytes!("../shaders/vertex/glow.glsl");
+static FRAG_SRC: &[u8] = include_bytes!("../shaders/fragment/glow.glsl");
+
+static TEXTURES: [TextureType; 1] = [TextureType::Emission];
+
+/// Makes the emission of the material of an entity glow around its mesh, drawn by `DrawGlow`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Glow {
+    /// How far the glow reaches out of the mesh, in world units.
+    pub radius: f32,
+    /// Multiplier of the emission of the material in the glow.
+    pub intensity: f32,
+}
+
+impl Default for Glow {
+    fn default() -> Self {
+        Glow {
+            radius: 0.1,
+            intensity: 1.0,
+        }
+    }
+}
+
+impl Component for Glow {
+    type Storage = DenseVecStorage<Self>;
+}
@@ -0,0 +1,326 @@
+//! Clustered culling of point lights.
+//!
+//! The view frustum is divided into clusters, tiles of the screen sliced exponentially along the
+//! depth. Every frame, the lights are assigned on the CPU to the clusters their radius reaches
+//! into, so each fragment only goes through the lights of its cluster.
+
+use std::mem;
+
+use glsl_layout::*;
+
+use amethyst_core::{
+    nalgebra::{Matrix4, Point3},
+    GlobalTransform,
+};
+
+use crate::{
+    cam::Camera,
+    error::Result,
+    occlusion::projected_bounds,
+    pass::util::add_texture,
+    pipe::{Effect, EffectBuilder},
+    tex::{ChannelType, SurfaceType, Texture, TextureBuilder},
+    types::{Encoder, Factory},
+};
+
+/// Number of clusters along the width, height and depth of the view.
+const CLUSTERS: [usize; 3] = [16, 9, 24];
+/// Width of the texture of the light indices.
+const INDEX_WIDTH: usize = 1024;
+/// Height of the texture of the light indices.
+const INDEX_HEIGHT: usize = 64;
+
+#[derive(Clone, Copy, Debug, Uniform)]
+pub(crate) struct ClusterArgs {
+    proj: mat4,
+    view: mat4,
+    clusters_x: int,
+    clusters_y: int,
+    clusters_z: int,
+    near: float,
+    far: float,
+    /// `0` when the camera isn't a perspective camera, and every light is gone through.
+    clustered: int,
+}
+
+/// The textures holding the lights of the clusters: the grid of the offsets and counts of the
+/// lights of each cluster, and the list of their indices.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct LightClusters {
+    grid: Texture,
+    indices: Texture,
+}
+
+impl LightClusters {
+    pub(crate) fn new(factory: &mut Factory) -> Result<Self> {
+        let grid = TextureBuilder::new(vec![0u32; CLUSTERS[0] * CLUSTERS[1] * CLUSTERS[2] * 2])
+            .with_format(SurfaceType::R32_G32)
+            .with_channel_type(ChannelType::Uint)
+            .with_size((CLUSTERS[0] * CLUSTERS[1]) as u16, CLUSTERS[2] as u16)
+            .dynamic(true)
+            .build(factory)?;
+        let indices = TextureBuilder::new(vec![0u32; INDEX_WIDTH * INDEX_HEIGHT])
+            .with_format(SurfaceType::R32)
+            .with_channel_type(ChannelType::Uint)
+            .with_size(INDEX_WIDTH as u16, INDEX_HEIGHT as u16)
+            .dynamic(true)
+            .build(factory)?;
+        Ok(LightClusters { grid, indices })
+    }
+
+    /// Adds the textures of the clusters, which have to come before the textures of the
+    /// materials, after the ones of `setup_light_cookies`.
+    pub(crate) fn setup(builder: &mut EffectBuilder<'_>) {
+        builder
+            .with_raw_constant_buffer(
+                "ClusterArgs",
+                mem::size_of::<<ClusterArgs as Uniform>::Std140>(),
+                1,
+            )
+            .with_texture("cluster_grid")
+            .with_texture("cluster_lights");
+    }
+
+    /// Assigns the point lights, given by their position and radius, to the clusters of the
+    /// camera, uploads them and binds the textures.
+    pub(crate) fn update(
+        &self,
+        effect: &mut Effect,
+        encoder: &mut Encoder,
+        lights: &[(Point3<f32>, f32)],
+        camera: Option<(&Camera, &GlobalTransform)>,
+    ) {
+        let grid = camera.and_then(|(camera, transform)| ClusterGrid::new(camera, transform));
+        let args = match grid {
+            Some(ref grid) => {
+                let proj: [[f32; 4]; 4] = grid.proj.into();
+                let view: [[f32; 4]; 4] = grid.view.into();
+                ClusterArgs {
+                    proj: proj.into(),
+                    view: view.into(),
+                    clusters_x: CLUSTERS[0] as i32,
+                    clusters_y: CLUSTERS[1] as i32,
+                    clusters_z: CLUSTERS[2] as i32,
+                    near: grid.near,
+                    far: grid.far,
+                    clustered: 1,
+                }
+            }
+            None => {
+                let identity: [[f32; 4]; 4] = Matrix4::identity().into();
+                ClusterArgs {
+                    proj: identity.into(),
+                    view: identity.into(),
+                    clusters_x: CLUSTERS[0] as i32,
+                    clusters_y: CLUSTERS[1] as i32,
+                    clusters_z: CLUSTERS[2] as i32,
+                    near: 1.0,
+                    far: 1.0,
+                    clustered: 0,
+                }
+            }
+        };
+        effect.update_constant_buffer("ClusterArgs", &args.std140(), encoder);
+
+        if let Some(grid) = grid {
+            if let Err(e) = self.upload(encoder, grid.assign(lights)) {
+                error!("Failed to upload the light clusters: {}", e);
+            }
+        }
+        add_texture(effect, &self.grid);
+        add_texture(effect, &self.indices);
+    }
+
+    fn upload(
+        &self,
+        encoder: &mut Encoder,
+        (grid, mut indices): (Vec<u32>, Vec<u32>),
+    ) -> Result<()> {
+        use gfx::memory::cast_slice;
+
+        self.grid.update(
+            encoder,
+            (0, 0),
+            ((CLUSTERS[0] * CLUSTERS[1]) as u16, CLUSTERS[2] as u16),
+            cast_slice(&grid),
+        )?;
+        let rows = (indices.len() + INDEX_WIDTH - 1) / INDEX_WIDTH;
+        if rows > 0 {
+            indices.resize(rows * INDEX_WIDTH, 0);
+            self.indices.update(
+                encoder,
+                (0, 0),
+                (INDEX_WIDTH as u16, rows as u16),
+                cast_slice(&indices),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The clusters of the view of a perspective camera.
+struct ClusterGrid {
+    proj: Matrix4<f32>,
+    view: Matrix4<f32>,
+    near: f32,
+    far: f32,
+}
+
+impl ClusterGrid {
+    /// Returns the clusters of the camera, or `None` if it isn't a perspective camera.
+    fn new(camera: &Camera, transform: &GlobalTransform) -> Option<Self> {
+        let proj = camera.proj;
+        if proj[(3, 3)] != 0.0 {
+            return None;
+        }
+        let view = transform.0.try_inverse()?;
+        let near = proj[(2, 3)] / (proj[(2, 2)] - 1.0);
+        let far = proj[(2, 3)] / (proj[(2, 2)] + 1.0);
+        if near <= 0.0 || far <= near {
+            return None;
+        }
+        Some(ClusterGrid {
+            proj,
+            view,
+            near,
+            far,
+        })
+    }
+
+    /// Returns the offset and count of the lights of every cluster, and the indices of the
+    /// lights. A radius of `0.0` reaches every cluster.
+    fn assign(&self, lights: &[(Point3<f32>, f32)]) -> (Vec<u32>, Vec<u32>) {
+        let mut clusters = vec![Vec::new(); CLUSTERS[0] * CLUSTERS[1] * CLUSTERS[2]];
+        for (i, &(position, radius)) in lights.iter().enumerate() {
+            let (xs, ys, zs) = match self.ranges(position, radius) {
+                Some(ranges) => ranges,
+                None => continue,
+            };
+            for z in zs.0..=zs.1 {
+                for y in ys.0..=ys.1 {
+                    for x in xs.0..=xs.1 {
+                        clusters[x + CLUSTERS[0] * (y + CLUSTERS[1] * z)].push(i as u32);
+                    }
+                }
+            }
+        }
+
+        let mut grid = Vec::with_capacity(clusters.len() * 2);
+        let mut indices = Vec::new();
+        for lights in clusters {
+            // The lights which don't fit anymore are left out of the cluster.
+            let count = lights.len().min(INDEX_WIDTH * INDEX_HEIGHT - indices.len());
+            grid.push(indices.len() as u32);
+            grid.push(count as u32);
+            indices.extend_from_slice(&lights[..count]);
+        }
+        (grid, indices)
+    }
+
+    /// Returns the first and last clusters along each axis which a light reaches into.
+    fn ranges(
+        &self,
+        position: Point3<f32>,
+        radius: f32,
+    ) -> Option<((usize, usize), (usize, usize), (usize, usize))> {
+        let all = |axis: usize| (0, CLUSTERS[axis] - 1);
+        if radius <= 0.0 {
+            return Some((all(0), all(1), all(2)));
+        }
+        let center = (self.view * position.to_homogeneous()).xyz();
+        // The camera looks down the negative Z axis.
+        let closest = -center.z - radius;
+        let farthest = -center.z + radius;
+        if farthest < self.near || closest > self.far {
+            return None;
+        }
+        let zs = (
+            self.slice(closest.max(self.near)),
+            self.slice(farthest.min(self.far)),
+        );
+        if closest <= self.near {
+            // The light reaches behind the camera, where its corners can't be projected.
+            return Some((all(0), all(1), zs));
+        }
+
+        let (min, max) = match projected_bounds(&self.proj, &center, radius) {
+            Some(bounds) => bounds,
+            None => return Some((all(0), all(1), zs)),
+        };
+        if max.x < -1.0 || max.y < -1.0 || min.x > 1.0 || min.y > 1.0 {
+            return None;
+        }
+        Some((
+            (tile(min.x, CLUSTERS[0]), tile(max.x, CLUSTERS[0])),
+            (tile(min.y, CLUSTERS[1]), tile(max.y, CLUSTERS[1])),
+            zs,
+        ))
+    }
+
+    /// Returns the depth slice of a distance from the camera along its view direction.
+    fn slice(&self, depth: f32) -> usize {
+        let slice = (depth / self.near).ln() / (self.far / self.near).ln() * CLUSTERS[2] as f32;
+        (slice.max(0.0) as usize).min(CLUSTERS[2] - 1)
+    }
+}
+
+/// Returns the tile of a coordinate in normalized device coordinates.
+fn tile(ndc: f32, count: usize) -> usize {
+    let tile = (ndc * 0.5 + 0.5) * count as f32;
+    (tile.max(0.0) as usize).min(count - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid() -> ClusterGrid {
+        ClusterGrid::new(
+            &Camera::standard_3d(16.0, 9.0),
+            &GlobalTransform(Matrix4::identity()),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn near_and_far() {
+        let grid = grid();
+        assert!((grid.near - 0.1).abs() < 0.001);
+        // The far plane loses precision in the projection matrix.
+        assert!((grid.far - 2000.0).abs() < 10.0);
+        assert!(ClusterGrid::new(
+            &Camera::standard_2d(),
+            &GlobalTransform(Matrix4::identity())
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn assign_lights() {
+        let lights = [
+            // In the center of the view.
+            (Point3::new(0.0, 0.0, -10.0), 0.5),
+            // Behind the camera.
+            (Point3::new(0.0, 0.0, 10.0), 0.5),
+            // Everywhere.
+            (Point3::new(0.0, 0.0, 10.0), 0.0),
+        ];
+        let (clusters, indices) = grid().assign(&lights);
+        assert_eq!(CLUSTERS[0] * CLUSTERS[1] * CLUSTERS[2] * 2, clusters.len());
+        assert!(!indices.contains(&1));
+        assert_eq!(
+            CLUSTERS[0] * CLUSTERS[1] * CLUSTERS[2],
+            indices.iter().filter(|&&i| i == 2).count()
+        );
+
+        let lit = indices.iter().filter(|&&i| i == 0).count();
+        assert!(lit > 0 && lit < 16);
+        let slice = grid().slice(10.0);
+        let center = CLUSTERS[0] / 2 + CLUSTERS[0] * (CLUSTERS[1] / 2 + CLUSTERS[1] * slice);
+        let (offset, count) = (
+            clusters[center * 2] as usize,
+            clusters[center * 2 + 1] as usize,
+        );
+        assert!(indices[offset..offset + count].contains(&0));
+    }
+}
@@ -1,22 +1,34 @@
 //! Different kinds of render passes.
 //
 pub use self::{
+    color_filter::*,
     debug_lines::*,
+    debug_view::*,
     flat::*,
     flat2d::*,
+    glow::*,
     pbm::*,
+    scatter::*,
     shaded::*,
     skinning::set_skinning_buffers,
     skybox::*,
-    util::{get_camera, set_vertex_args},
+    transition::*,
+    util::{get_camera, set_material_params, set_vertex_args, setup_material_params},
 };
 
+mod color_filter;
 mod debug_lines;
+mod debug_view;
 mod flat;
 mod flat2d;
+mod glow;
+mod light_clusters;
 mod pbm;
+mod probes;
+mod scatter;
 mod shaded;
 mod shaded_util;
 mod skinning;
 mod skybox;
+mod transition;
 mod util;
@@ -15,21 +15,28 @@ use crate::{
     cam::{ActiveCamera, Camera},
     error::Result,
     hidden::{Hidden, HiddenPropagate},
-    light::Light,
+    layers::RenderLayers,
+    light::{Light, LightCookie},
     mesh::{Mesh, MeshHandle},
-    mtl::{Material, MaterialDefaults},
+    mtl::{Material, MaterialDefaults, MaterialOverride},
     pass::{
-        shaded_util::{set_light_args, setup_light_buffers},
-        util::{draw_mesh, get_camera, setup_textures, setup_vertex_args},
+        light_clusters::LightClusters,
+        probes::ProbeTextures,
+        shaded_util::{
+            set_light_args_with_textures, setup_light_buffers, setup_light_cookies, LightTextures,
+        },
+        util::{draw_mesh, get_camera, setup_textures, setup_vertex_args, PassCameras},
     },
     pipe::{
         pass::{Pass, PassData},
         DepthMode, Effect, NewEffect,
     },
+    probe::{BakedProbes, SceneProbes},
     resources::AmbientColor,
     tex::Texture,
     types::{Encoder, Factory},
-    vertex::{Normal, Position, Query, Tangent, TexCoord},
+    vertex::{Attributes, Color, Normal, Position, Query, Tangent, TexCoord},
+    view_model::ViewModelCamera,
     visibility::Visibility,
     Rgba,
 };
@@ -48,7 +55,10 @@ use super::*;
 #[derivative(Default(bound = "V: Query<(Position, Normal, Tangent, TexCoord)>"))]
 pub struct DrawPbm<V> {
     _pd: PhantomData<V>,
+    vertex_colors: Option<Attributes<'static>>,
     transparency: Option<(ColorMask, Blend, Option<DepthMode>)>,
+    clusters: Option<LightClusters>,
+    probes: ProbeTextures,
 }
 
 impl<V> DrawPbm<V>
@@ -70,6 +80,25 @@ where
         self.transparency = Some((mask, blend, depth));
         self
     }
+
+    fn attributes(&self) -> Attributes<'static> {
+        self.vertex_colors.unwrap_or(V::QUERIED_ATTRIBUTES)
+    }
+}
+
+impl<V> DrawPbm<V>
+where
+    V: Query<(Position, Normal, Tangent, TexCoord)>
+        + Query<(Position, Color, Normal, Tangent, TexCoord)>,
+{
+    /// Enable vertex colors, multiplied with the shaded color like the `Rgba` tint.
+    ///
+    /// Needs a vertex format with a `Color` attribute.
+    pub fn with_vertex_colors(mut self) -> Self {
+        self.vertex_colors =
+            Some(<V as Query<(Position, Color, Normal, Tangent, TexCoord)>>::QUERIED_ATTRIBUTES);
+        self
+    }
 }
 
 impl<'a, V> PassData<'a> for DrawPbm<V>
@@ -88,9 +117,15 @@ where
         ReadStorage<'a, HiddenPropagate>,
         ReadStorage<'a, MeshHandle>,
         ReadStorage<'a, Material>,
+        ReadStorage<'a, MaterialOverride>,
         ReadStorage<'a, GlobalTransform>,
         ReadStorage<'a, Light>,
+        ReadStorage<'a, LightCookie>,
         ReadStorage<'a, Rgba>,
+        ReadStorage<'a, RenderLayers>,
+        ReadStorage<'a, ViewModelCamera>,
+        Option<Read<'a, SceneProbes>>,
+        Read<'a, AssetStorage<BakedProbes>>,
     );
 }
 
@@ -98,11 +133,21 @@ impl<V> Pass for DrawPbm<V>
 where
     V: Query<(Position, Normal, Tangent, TexCoord)>,
 {
-    fn compile(&mut self, effect: NewEffect<'_>) -> Result<Effect> {
-        let mut builder = effect.simple(VERT_SRC, FRAG_SRC);
-        builder.with_raw_vertex_buffer(V::QUERIED_ATTRIBUTES, V::size() as ElemStride, 0);
+    fn compile(&mut self, mut effect: NewEffect<'_>) -> Result<Effect> {
+        self.clusters = Some(LightClusters::new(&mut effect.factory)?);
+        let vert = if self.vertex_colors.is_some() {
+            VERT_COLOR_SRC
+        } else {
+            VERT_SRC
+        };
+        let mut builder = effect.simple(vert, FRAG_SRC);
+        builder.with_raw_vertex_buffer(self.attributes(), V::size() as ElemStride, 0);
+        builder.with_cull_face_variants();
         setup_vertex_args(&mut builder);
         setup_light_buffers(&mut builder);
+        setup_light_cookies(&mut builder);
+        LightClusters::setup(&mut builder);
+        ProbeTextures::setup(&mut builder);
         setup_textures(&mut builder, &TEXTURES);
         match self.transparency {
             Some((mask, blend, depth)) => builder.with_blended_output("color", mask, blend, depth),
@@ -115,7 +160,7 @@ where
         &'a mut self,
         encoder: &mut Encoder,
         effect: &mut Effect,
-        _factory: Factory,
+        mut factory: Factory,
         (
             active,
             camera,
@@ -128,27 +173,66 @@ where
             hidden_prop,
             mesh,
             material,
+            material_overrides,
             global,
             light,
+            cookies,
             rgba,
+            render_layers,
+            view_models,
+            scene_probes,
+            probe_storage,
         ): <Self as PassData<'a>>::Data,
     ) {
+        let cameras = PassCameras::new(&active, &camera, &global, &view_models);
         let camera = get_camera(active, &camera, &global);
 
-        set_light_args(effect, encoder, &light, &global, &ambient, camera);
+        set_light_args_with_textures(
+            effect,
+            encoder,
+            &light,
+            &global,
+            &ambient,
+            camera,
+            &LightTextures {
+                cookies: &cookies,
+                textures: &tex_storage,
+                fallback: &material_defaults.0.albedo,
+                clusters: self.clusters.as_ref(),
+            },
+        );
+        let scene_probes = scene_probes.as_ref().map(|probes| &**probes);
+        let probes = ProbeTextures::probes(scene_probes, &probe_storage);
+        self.probes.update(&mut factory, scene_probes, probes);
+        self.probes.bind(
+            effect,
+            encoder,
+            probes,
+            camera.map(|(_, transform)| transform),
+            tex_storage
+                .get(&material_defaults.0.albedo)
+                .expect("Texture missing in asset storage"),
+        );
 
         match visibility {
             None => {
-                for (mesh, material, global, rgba, _, _) in (
+                for (mesh, material, overrides, global, rgba, _, _, layers) in (
                     &mesh,
                     &material,
+                    material_overrides.maybe(),
                     &global,
                     rgba.maybe(),
                     !&hidden,
                     !&hidden_prop,
+                    render_layers.maybe(),
                 )
                     .join()
                 {
+                    let camera = match cameras.select(layers) {
+                        Some(camera) => camera,
+                        None => continue,
+                    };
+                    ProbeTextures::set_irradiance(effect, encoder, probes, Some(global));
                     draw_mesh(
                         encoder,
                         effect,
@@ -157,25 +241,33 @@ where
                         None,
                         &tex_storage,
                         Some(material),
+                        overrides,
                         &material_defaults,
                         rgba,
                         camera,
                         Some(global),
-                        &[V::QUERIED_ATTRIBUTES],
+                        &[self.attributes()],
                         &TEXTURES,
                     );
                 }
             }
             Some(ref visibility) => {
-                for (mesh, material, global, rgba, _) in (
+                for (mesh, material, overrides, global, rgba, _, layers) in (
                     &mesh,
                     &material,
+                    material_overrides.maybe(),
                     &global,
                     rgba.maybe(),
                     &visibility.visible_unordered,
+                    render_layers.maybe(),
                 )
                     .join()
                 {
+                    let camera = match cameras.select(layers) {
+                        Some(camera) => camera,
+                        None => continue,
+                    };
+                    ProbeTextures::set_irradiance(effect, encoder, probes, Some(global));
                     draw_mesh(
                         encoder,
                         effect,
@@ -184,17 +276,23 @@ where
                         None,
                         &tex_storage,
                         Some(material),
+                        overrides,
                         &material_defaults,
                         rgba,
                         camera,
                         Some(global),
-                        &[V::QUERIED_ATTRIBUTES],
+                        &[self.attributes()],
                         &TEXTURES,
                     );
                 }
 
                 for entity in &visibility.visible_ordered {
+                    let camera = match cameras.select(render_layers.get(*entity)) {
+                        Some(camera) => camera,
+                        None => continue,
+                    };
                     if let Some(mesh) = mesh.get(*entity) {
+                        ProbeTextures::set_irradiance(effect, encoder, probes, global.get(*entity));
                         draw_mesh(
                             encoder,
                             effect,
@@ -203,11 +301,12 @@ where
                             None,
                             &tex_storage,
                             material.get(*entity),
+                            material_overrides.get(*entity),
                             &material_defaults,
                             rgba.get(*entity),
                             camera,
                             global.get(*entity),
-                            &[V::QUERIED_ATTRIBUTES],
+                            &[self.attributes()],
                             &TEXTURES,
                         );
                     }
@@ -13,23 +13,30 @@ use crate::{
     cam::{ActiveCamera, Camera},
     error::Result,
     hidden::{Hidden, HiddenPropagate},
-    light::Light,
+    layers::RenderLayers,
+    light::{Light, LightCookie},
     mesh::{Mesh, MeshHandle},
-    mtl::{Material, MaterialDefaults},
+    mtl::{Material, MaterialDefaults, MaterialOverride},
     pass::{
-        shaded_util::{set_light_args, setup_light_buffers},
+        light_clusters::LightClusters,
+        probes::ProbeTextures,
+        shaded_util::{
+            set_light_args_with_textures, setup_light_buffers, setup_light_cookies, LightTextures,
+        },
         skinning::{create_skinning_effect, setup_skinning_buffers},
-        util::{draw_mesh, get_camera, setup_textures, setup_vertex_args},
+        util::{draw_mesh, get_camera, setup_textures, setup_vertex_args, PassCameras},
     },
     pipe::{
         pass::{Pass, PassData},
         DepthMode, Effect, NewEffect,
     },
+    probe::{BakedProbes, SceneProbes},
     resources::AmbientColor,
     skinning::JointTransforms,
     tex::Texture,
     types::{Encoder, Factory},
-    vertex::{Attributes, Normal, Position, Separate, Tangent, TexCoord, VertexFormat},
+    vertex::{Attributes, Color, Normal, Position, Separate, Tangent, TexCoord, VertexFormat},
+    view_model::ViewModelCamera,
     visibility::Visibility,
     Rgba,
 };
@@ -43,6 +50,14 @@ static ATTRIBUTES: [Attributes<'static>; 4] = [
     Separate::<TexCoord>::ATTRIBUTES,
 ];
 
+static COLOR_ATTRIBUTES: [Attributes<'static>; 5] = [
+    Separate::<Position>::ATTRIBUTES,
+    Separate::<Normal>::ATTRIBUTES,
+    Separate::<Tangent>::ATTRIBUTES,
+    Separate::<TexCoord>::ATTRIBUTES,
+    Separate::<Color>::ATTRIBUTES,
+];
+
 /// Draw mesh with physically based lighting
 ///
 /// See the [crate level documentation](index.html) for information about interleaved and separate
@@ -50,7 +65,10 @@ static ATTRIBUTES: [Attributes<'static>; 4] = [
 #[derive(Default, Clone, Debug, PartialEq)]
 pub struct DrawPbmSeparate {
     skinning: bool,
+    vertex_colors: bool,
     transparency: Option<(ColorMask, Blend, Option<DepthMode>)>,
+    clusters: Option<LightClusters>,
+    probes: ProbeTextures,
 }
 
 impl DrawPbmSeparate {
@@ -65,6 +83,14 @@ impl DrawPbmSeparate {
         self
     }
 
+    /// Enable vertex colors, multiplied with the shaded color like the `Rgba` tint.
+    ///
+    /// Meshes without a `Color` buffer are not drawn by the pass.
+    pub fn with_vertex_colors(mut self) -> Self {
+        self.vertex_colors = true;
+        self
+    }
+
     /// Enable transparency
     pub fn with_transparency(
         mut self,
@@ -90,17 +116,26 @@ impl<'a> PassData<'a> for DrawPbmSeparate {
         ReadStorage<'a, HiddenPropagate>,
         ReadStorage<'a, MeshHandle>,
         ReadStorage<'a, Material>,
+        ReadStorage<'a, MaterialOverride>,
         ReadStorage<'a, GlobalTransform>,
         ReadStorage<'a, Light>,
+        ReadStorage<'a, LightCookie>,
         ReadStorage<'a, JointTransforms>,
         ReadStorage<'a, Rgba>,
+        ReadStorage<'a, RenderLayers>,
+        ReadStorage<'a, ViewModelCamera>,
+        Option<Read<'a, SceneProbes>>,
+        Read<'a, AssetStorage<BakedProbes>>,
     );
 }
 
 impl Pass for DrawPbmSeparate {
-    fn compile(&mut self, effect: NewEffect<'_>) -> Result<Effect> {
+    fn compile(&mut self, mut effect: NewEffect<'_>) -> Result<Effect> {
+        self.clusters = Some(LightClusters::new(&mut effect.factory)?);
         let mut builder = if self.skinning {
-            create_skinning_effect(effect, FRAG_SRC)
+            create_skinning_effect(effect, self.vertex_colors, FRAG_SRC)
+        } else if self.vertex_colors {
+            effect.simple(VERT_COLOR_SRC, FRAG_SRC)
         } else {
             effect.simple(VERT_SRC, FRAG_SRC)
         };
@@ -125,11 +160,22 @@ impl Pass for DrawPbmSeparate {
                 Separate::<TexCoord>::size() as ElemStride,
                 0,
             );
+        if self.vertex_colors {
+            builder.with_raw_vertex_buffer(
+                Separate::<Color>::ATTRIBUTES,
+                Separate::<Color>::size() as ElemStride,
+                0,
+            );
+        }
         if self.skinning {
             setup_skinning_buffers(&mut builder);
         }
+        builder.with_cull_face_variants();
         setup_vertex_args(&mut builder);
         setup_light_buffers(&mut builder);
+        setup_light_cookies(&mut builder);
+        LightClusters::setup(&mut builder);
+        ProbeTextures::setup(&mut builder);
         setup_textures(&mut builder, &TEXTURES);
         match self.transparency {
             Some((mask, blend, depth)) => builder.with_blended_output("color", mask, blend, depth),
@@ -142,7 +188,7 @@ impl Pass for DrawPbmSeparate {
         &'a mut self,
         encoder: &mut Encoder,
         effect: &mut Effect,
-        _factory: Factory,
+        mut factory: Factory,
         (
             active,
             camera,
@@ -155,29 +201,73 @@ impl Pass for DrawPbmSeparate {
             hidden_prop,
             mesh,
             material,
+            material_overrides,
             global,
             light,
+            cookies,
             joints,
             rgba,
+            render_layers,
+            view_models,
+            scene_probes,
+            probe_storage,
         ): <Self as PassData<'a>>::Data,
     ) {
+        let attributes: &[Attributes<'static>] = if self.vertex_colors {
+            &COLOR_ATTRIBUTES
+        } else {
+            &ATTRIBUTES
+        };
+        let cameras = PassCameras::new(&active, &camera, &global, &view_models);
         let camera = get_camera(active, &camera, &global);
 
-        set_light_args(effect, encoder, &light, &global, &ambient, camera);
+        set_light_args_with_textures(
+            effect,
+            encoder,
+            &light,
+            &global,
+            &ambient,
+            camera,
+            &LightTextures {
+                cookies: &cookies,
+                textures: &tex_storage,
+                fallback: &material_defaults.0.albedo,
+                clusters: self.clusters.as_ref(),
+            },
+        );
+        let scene_probes = scene_probes.as_ref().map(|probes| &**probes);
+        let probes = ProbeTextures::probes(scene_probes, &probe_storage);
+        self.probes.update(&mut factory, scene_probes, probes);
+        self.probes.bind(
+            effect,
+            encoder,
+            probes,
+            camera.map(|(_, transform)| transform),
+            tex_storage
+                .get(&material_defaults.0.albedo)
+                .expect("Texture missing in asset storage"),
+        );
 
         match visibility {
             None => {
-                for (joint, mesh, material, global, rgba, _, _) in (
+                for (joint, mesh, material, overrides, global, rgba, _, _, layers) in (
                     joints.maybe(),
                     &mesh,
                     &material,
+                    material_overrides.maybe(),
                     &global,
                     rgba.maybe(),
                     !&hidden,
                     !&hidden_prop,
+                    render_layers.maybe(),
                 )
                     .join()
                 {
+                    let camera = match cameras.select(layers) {
+                        Some(camera) => camera,
+                        None => continue,
+                    };
+                    ProbeTextures::set_irradiance(effect, encoder, probes, Some(global));
                     draw_mesh(
                         encoder,
                         effect,
@@ -186,26 +276,34 @@ impl Pass for DrawPbmSeparate {
                         joint,
                         &tex_storage,
                         Some(material),
+                        overrides,
                         &material_defaults,
                         rgba,
                         camera,
                         Some(global),
-                        &ATTRIBUTES,
+                        attributes,
                         &TEXTURES,
                     );
                 }
             }
             Some(ref visibility) => {
-                for (joint, mesh, material, global, rgba, _) in (
+                for (joint, mesh, material, overrides, global, rgba, _, layers) in (
                     joints.maybe(),
                     &mesh,
                     &material,
+                    material_overrides.maybe(),
                     &global,
                     rgba.maybe(),
                     &visibility.visible_unordered,
+                    render_layers.maybe(),
                 )
                     .join()
                 {
+                    let camera = match cameras.select(layers) {
+                        Some(camera) => camera,
+                        None => continue,
+                    };
+                    ProbeTextures::set_irradiance(effect, encoder, probes, Some(global));
                     draw_mesh(
                         encoder,
                         effect,
@@ -214,17 +312,23 @@ impl Pass for DrawPbmSeparate {
                         joint,
                         &tex_storage,
                         Some(material),
+                        overrides,
                         &material_defaults,
                         rgba,
                         camera,
                         Some(global),
-                        &ATTRIBUTES,
+                        attributes,
                         &TEXTURES,
                     );
                 }
 
                 for entity in &visibility.visible_ordered {
+                    let camera = match cameras.select(render_layers.get(*entity)) {
+                        Some(camera) => camera,
+                        None => continue,
+                    };
                     if let Some(mesh) = mesh.get(*entity) {
+                        ProbeTextures::set_irradiance(effect, encoder, probes, global.get(*entity));
                         draw_mesh(
                             encoder,
                             effect,
@@ -233,11 +337,12 @@ impl Pass for DrawPbmSeparate {
                             joints.get(*entity),
                             &tex_storage,
                             material.get(*entity),
+                            material_overrides.get(*entity),
                             &material_defaults,
                             rgba.get(*entity),
                             camera,
                             global.get(*entity),
-                            &ATTRIBUTES,
+                            attributes,
                             &TEXTURES,
                         );
                     }
@@ -0,0 +1,174 @@
+//! Lighting by the baked probes of `SceneProbes`.
+//!
+//! The environment of the probe closest to the camera stays bound for the whole frame, while the
+//! irradiance is looked up for every mesh.
+
+use std::{mem, ptr};
+
+use glsl_layout::*;
+
+use amethyst_assets::AssetStorage;
+use amethyst_core::{nalgebra::Vector3, GlobalTransform};
+
+use crate::{
+    error::Result,
+    pass::util::add_texture,
+    pipe::{Effect, EffectBuilder},
+    probe::{BakedProbes, BakedProbesHandle, EnvironmentMap, SceneProbes},
+    tex::{ChannelType, FilterMethod, SamplerInfo, SurfaceType, Texture, TextureBuilder, WrapMode},
+    types::{Encoder, Factory},
+};
+
+#[derive(Clone, Copy, Debug, Uniform)]
+pub(crate) struct ProbeArgs {
+    /// `1` when the `Irradiance` buffer replaces the ambient color.
+    irradiance: int,
+    /// `1` when the environment texture holds reflections.
+    reflection: int,
+    /// Number of roughness levels of the environment texture.
+    levels: int,
+}
+
+#[derive(Clone, Copy, Debug, Uniform)]
+pub(crate) struct IrradiancePod {
+    coefficient: vec4,
+}
+
+/// The environment textures of the loaded `BakedProbes`, one for each probe.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct ProbeTextures {
+    handle: Option<BakedProbesHandle>,
+    environments: Vec<Texture>,
+}
+
+impl ProbeTextures {
+    /// Adds the probe buffers and the environment texture, which has to come before the textures
+    /// of the materials, after the ones of `LightClusters::setup`.
+    pub(crate) fn setup(builder: &mut EffectBuilder<'_>) {
+        builder
+            .with_raw_constant_buffer(
+                "ProbeArgs",
+                mem::size_of::<<ProbeArgs as Uniform>::Std140>(),
+                1,
+            )
+            .with_raw_constant_buffer(
+                "Irradiance",
+                mem::size_of::<<IrradiancePod as Uniform>::Std140>(),
+                9,
+            )
+            .with_texture("environment");
+    }
+
+    /// Returns the probes of the scene, if they are loaded.
+    pub(crate) fn probes<'a>(
+        scene: Option<&SceneProbes>,
+        storage: &'a AssetStorage<BakedProbes>,
+    ) -> Option<&'a BakedProbes> {
+        scene.and_then(|scene| storage.get(&scene.probes))
+    }
+
+    /// Uploads the environments of the probes, if they changed since the last frame.
+    pub(crate) fn update(
+        &mut self,
+        factory: &mut Factory,
+        scene: Option<&SceneProbes>,
+        probes: Option<&BakedProbes>,
+    ) {
+        let handle = scene.map(|scene| scene.probes.clone());
+        if handle != self.handle {
+            self.environments.clear();
+            self.handle = None;
+        }
+        if let (Some(probes), true) = (probes, self.handle.is_none()) {
+            match probes
+                .probes
+                .iter()
+                .map(|probe| environment_texture(factory, &probe.environment))
+                .collect::<Result<Vec<_>>>()
+            {
+                Ok(environments) => {
+                    self.environments = environments;
+                    self.handle = handle;
+                }
+                Err(e) => error!("Failed to upload the environments of the probes: {}", e),
+            }
+        }
+    }
+
+    /// Binds the environment of the probe closest to the camera, or `fallback` without probes.
+    pub(crate) fn bind(
+        &self,
+        effect: &mut Effect,
+        encoder: &mut Encoder,
+        probes: Option<&BakedProbes>,
+        camera: Option<&GlobalTransform>,
+        fallback: &Texture,
+    ) {
+        let eye = camera
+            .map(|camera| camera.0.column(3).xyz())
+            .unwrap_or_else(Vector3::zeros);
+        let probe = probes.and_then(|probes| {
+            let probe = probes.probe_for(&eye)?;
+            let index = probes
+                .probes
+                .iter()
+                .position(|other| ptr::eq(other, probe))?;
+            Some((probe, self.environments.get(index)?))
+        });
+
+        let args = ProbeArgs {
+            irradiance: probes.map_or(false, |probes| {
+                !probes.probes.is_empty() || probes.grid.is_some()
+            }) as i32,
+            reflection: probe.is_some() as i32,
+            levels: probe.map_or(1, |(probe, _)| probe.environment.levels as i32),
+        };
+        effect.update_constant_buffer("ProbeArgs", &args.std140(), encoder);
+        add_texture(effect, probe.map_or(fallback, |(_, texture)| texture));
+        effect.keep_textures();
+    }
+
+    /// Sets the irradiance at the position of a mesh.
+    pub(crate) fn set_irradiance(
+        effect: &mut Effect,
+        encoder: &mut Encoder,
+        probes: Option<&BakedProbes>,
+        global: Option<&GlobalTransform>,
+    ) {
+        let (probes, global) = match (probes, global) {
+            (Some(probes), Some(global)) => (probes, global),
+            _ => return,
+        };
+        if let Some(irradiance) = probes.irradiance_at(&global.0.column(3).xyz()) {
+            let coefficients: Vec<_> = irradiance
+                .coefficients
+                .iter()
+                .map(|&[r, g, b]| {
+                    IrradiancePod {
+                        coefficient: [r, g, b, 0.0].into(),
+                    }
+                    .std140()
+                })
+                .collect();
+            effect.update_buffer("Irradiance", &coefficients[..], encoder);
+        }
+    }
+}
+
+/// Creates the texture of an environment map, with its levels on top of each other.
+fn environment_texture(factory: &mut Factory, environment: &EnvironmentMap) -> Result<Texture> {
+    let pixels: Vec<[f32; 4]> = environment
+        .pixels
+        .iter()
+        .map(|&[r, g, b]| [r, g, b, 1.0])
+        .collect();
+    TextureBuilder::new(pixels)
+        .with_format(SurfaceType::R32_G32_B32_A32)
+        .with_channel_type(ChannelType::Float)
+        .with_sampler(SamplerInfo::new(FilterMethod::Bilinear, WrapMode::Clamp))
+        .with_size(
+            environment.width as u16,
+            (environment.height * environment.levels) as u16,
+        )
+        .build(factory)
+}
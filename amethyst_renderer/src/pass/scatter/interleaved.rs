@@ -0,0 +1,266 @@
+//! Instanced scatter pass
+
+use std::marker::PhantomData;
+
+use gfx::{
+    buffer::Role,
+    memory::{Bind, Typed, Usage},
+    pso::buffer::ElemStride,
+};
+
+use amethyst_assets::AssetStorage;
+use amethyst_core::{
+    nalgebra::{Point3, Vector3},
+    specs::prelude::{Join, Read, ReadExpect, ReadStorage},
+    timing::Time,
+    transform::GlobalTransform,
+};
+
+use crate::{
+    cam::{ActiveCamera, Camera},
+    error::Result,
+    hidden::{Hidden, HiddenPropagate},
+    layers::RenderLayers,
+    light::Light,
+    mesh::Mesh,
+    mtl::{Material, MaterialDefaults, MaterialOverride},
+    pass::{
+        shaded_util::{set_light_args, setup_light_buffers},
+        util::{
+            add_textures, get_camera, set_attribute_buffers, set_vertex_args, setup_textures,
+            setup_vertex_args, PassCameras,
+        },
+    },
+    pipe::{
+        pass::{Pass, PassData},
+        DepthMode, Effect, NewEffect,
+    },
+    resources::AmbientColor,
+    scatter::Scatter,
+    tex::Texture,
+    types::{Encoder, Factory},
+    vertex::{Normal, Position, Query, TexCoord},
+    view_model::ViewModelCamera,
+    Rgba,
+};
+
+use super::*;
+
+/// Draw the instances of `Scatter`s with simple lighting, like `DrawShaded`
+///
+/// Needs a GPU which can draw instanced, see `GpuInfo::instancing`.
+///
+/// # Type Parameters:
+///
+/// * `V`: `VertexFormat`
+#[derive(Derivative, Clone, Debug, PartialEq)]
+#[derivative(Default(bound = "V: Query<(Position, Normal, TexCoord)>"))]
+pub struct DrawScatter<V> {
+    _pd: PhantomData<V>,
+    /// Holds the visible instances of one scatter at a time, grown when they don't fit.
+    instances: Option<(InstanceBuffer, usize)>,
+}
+
+impl<V> DrawScatter<V>
+where
+    V: Query<(Position, Normal, TexCoord)>,
+{
+    /// Create instance of `DrawScatter` pass
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns the instance buffer, holding at least `count` instances.
+    fn instance_buffer(&mut self, factory: &mut Factory, count: usize) -> Option<InstanceBuffer> {
+        match self.instances {
+            Some((ref buffer, capacity)) if capacity >= count => return Some(buffer.clone()),
+            _ => {}
+        }
+        let capacity = count.next_power_of_two().max(256);
+        match gfx::Factory::create_buffer(
+            factory,
+            capacity,
+            Role::Vertex,
+            Usage::Dynamic,
+            Bind::empty(),
+        ) {
+            Ok(buffer) => {
+                self.instances = Some((buffer, capacity));
+                self.instances
+                    .as_ref()
+                    .map(|&(ref buffer, _)| buffer.clone())
+            }
+            Err(e) => {
+                error!("Failed to create the instance buffer: {:?}", e);
+                None
+            }
+        }
+    }
+}
+
+impl<'a, V> PassData<'a> for DrawScatter<V>
+where
+    V: Query<(Position, Normal, TexCoord)>,
+{
+    type Data = (
+        Read<'a, ActiveCamera>,
+        ReadStorage<'a, Camera>,
+        Read<'a, AmbientColor>,
+        Read<'a, Time>,
+        Read<'a, AssetStorage<Mesh>>,
+        Read<'a, AssetStorage<Texture>>,
+        ReadExpect<'a, MaterialDefaults>,
+        ReadStorage<'a, Hidden>,
+        ReadStorage<'a, HiddenPropagate>,
+        ReadStorage<'a, Scatter>,
+        ReadStorage<'a, Material>,
+        ReadStorage<'a, MaterialOverride>,
+        ReadStorage<'a, GlobalTransform>,
+        ReadStorage<'a, Light>,
+        ReadStorage<'a, Rgba>,
+        ReadStorage<'a, RenderLayers>,
+        ReadStorage<'a, ViewModelCamera>,
+    );
+}
+
+impl<V> Pass for DrawScatter<V>
+where
+    V: Query<(Position, Normal, TexCoord)>,
+{
+    fn compile(&mut self, effect: NewEffect<'_>) -> Result<Effect> {
+        use std::mem;
+
+        let mut builder = effect.simple(VERT_SRC, FRAG_SRC);
+        builder
+            .with_raw_vertex_buffer(V::QUERIED_ATTRIBUTES, V::size() as ElemStride, 0)
+            .with_raw_vertex_buffer(
+                &INSTANCE_ATTRIBUTES,
+                mem::size_of::<InstancePod>() as ElemStride,
+                1,
+            );
+        builder.with_cull_face_variants();
+        setup_vertex_args(&mut builder);
+        setup_light_buffers(&mut builder);
+        setup_textures(&mut builder, &TEXTURES);
+        builder
+            .with_raw_global("wind")
+            .with_raw_global("wind_frequency")
+            .with_raw_global("time")
+            .with_output("color", Some(DepthMode::LessEqualWrite));
+        builder.build()
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        mut factory: Factory,
+        (
+            active,
+            camera,
+            ambient,
+            time,
+            mesh_storage,
+            tex_storage,
+            material_defaults,
+            hidden,
+            hidden_prop,
+            scatter,
+            material,
+            material_overrides,
+            global,
+            light,
+            rgba,
+            render_layers,
+            view_models,
+        ): <Self as PassData<'a>>::Data,
+    ) {
+        let cameras = PassCameras::new(&active, &camera, &global, &view_models);
+        let camera = get_camera(active, &camera, &global);
+
+        set_light_args(effect, encoder, &light, &global, &ambient, camera);
+        effect.update_global("time", time.absolute_time_seconds() as f32);
+
+        for (scatter, material, overrides, global, rgba, _, _, layers) in (
+            &scatter,
+            &material,
+            material_overrides.maybe(),
+            &global,
+            rgba.maybe(),
+            !&hidden,
+            !&hidden_prop,
+            render_layers.maybe(),
+        )
+            .join()
+        {
+            let camera = match cameras.select(layers) {
+                Some(camera) => camera,
+                None => continue,
+            };
+            let mesh = match mesh_storage.get(&scatter.mesh) {
+                Some(mesh) => mesh,
+                None => continue,
+            };
+            let eye = camera.map(|(_, transform)| Point3::from(transform.0.column(3).xyz()));
+            let frustum = camera.map(|(camera, transform)| camera.frustum(transform));
+            let instances: Vec<InstancePod> = scatter
+                .visible(&global.0, eye.as_ref(), frustum.as_ref())
+                .iter()
+                .map(Into::into)
+                .collect();
+            if instances.is_empty() {
+                continue;
+            }
+            let buffer = match self.instance_buffer(&mut factory, instances.len()) {
+                Some(buffer) => buffer,
+                None => return,
+            };
+            if let Err(e) = encoder.update_buffer(&buffer, &instances, 0) {
+                error!("Failed to upload the instances: {:?}", e);
+                continue;
+            }
+
+            if !set_attribute_buffers(effect, mesh, &[V::QUERIED_ATTRIBUTES]) {
+                effect.clear();
+                continue;
+            }
+            effect.data.vertex_bufs.push(buffer.raw().clone());
+
+            let rgba = rgba.cloned().unwrap_or(Rgba::WHITE);
+            set_vertex_args(
+                effect,
+                encoder,
+                camera,
+                global,
+                overrides.map_or(rgba, |overrides| overrides.tinted(rgba)),
+            );
+            effect.set_cull_face(material.cull_mode.into());
+            let (wind, frequency) = match scatter.wind {
+                Some(ref wind) => (
+                    wind.direction
+                        .try_normalize(0.0)
+                        .unwrap_or_else(Vector3::zeros)
+                        * wind.strength,
+                    wind.frequency,
+                ),
+                None => (Vector3::zeros(), 0.0),
+            };
+            effect.update_global("wind", Into::<[f32; 3]>::into(wind));
+            effect.update_global("wind_frequency", frequency);
+            add_textures(
+                effect,
+                encoder,
+                &tex_storage,
+                material,
+                overrides,
+                &material_defaults.0,
+                &TEXTURES,
+            );
+
+            let mut slice = mesh.slice().clone();
+            slice.instances = Some((instances.len() as u32, 0));
+            effect.draw(&slice, encoder);
+            effect.clear();
+        }
+    }
+}
@@ -0,0 +1,61 @@
+pub use self::interleaved::DrawScatter;
+
+use gfx::{
+    format::{ChannelType, Format, SurfaceType},
+    pso::buffer::Element,
+    traits::Pod,
+};
+
+use crate::{
+    pass::util::TextureType, scatter::ScatterInstance, types::Resources, vertex::AttributeFormat,
+};
+
+mod interleaved;
+
+static VERT_SRC: &[u8] = include_bytes!("../shaders/vertex/scatter.glsl");
+static FRAG_SRC: &[u8] = include_bytes!("../shaders/fragment/shaded.glsl");
+
+static TEXTURES: [TextureType; 2] = [TextureType::Albedo, TextureType::Emission];
+
+/// The attributes of the instances, which change once per instance instead of per vertex.
+static INSTANCE_ATTRIBUTES: [(&str, AttributeFormat); 2] = [
+    (
+        "instance_position",
+        Element {
+            format: Format(SurfaceType::R32_G32_B32_A32, ChannelType::Float),
+            offset: 0,
+        },
+    ),
+    (
+        "instance_rotation",
+        Element {
+            format: Format(SurfaceType::R32_G32_B32_A32, ChannelType::Float),
+            offset: 16,
+        },
+    ),
+];
+
+/// An instance in the instance buffer.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct InstancePod {
+    /// The position, and the scale in `w`.
+    position: [f32; 4],
+    /// The rotation quaternion.
+    rotation: [f32; 4],
+}
+
+unsafe impl Pod for InstancePod {}
+
+type InstanceBuffer = gfx::handle::Buffer<Resources, InstancePod>;
+
+impl<'a> From<&'a ScatterInstance> for InstancePod {
+    fn from(instance: &'a ScatterInstance) -> Self {
+        let p = instance.position;
+        let q = instance.rotation.coords;
+        InstancePod {
+            position: [p.x, p.y, p.z, instance.scale],
+            rotation: [q.x, q.y, q.z, q.w],
+        }
+    }
+}
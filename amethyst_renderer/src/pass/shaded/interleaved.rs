@@ -15,12 +15,13 @@ use crate::{
     cam::{ActiveCamera, Camera},
     error::Result,
     hidden::{Hidden, HiddenPropagate},
+    layers::RenderLayers,
     light::Light,
     mesh::{Mesh, MeshHandle},
-    mtl::{Material, MaterialDefaults},
+    mtl::{Material, MaterialDefaults, MaterialOverride},
     pass::{
         shaded_util::{set_light_args, setup_light_buffers},
-        util::{draw_mesh, get_camera, setup_textures, setup_vertex_args},
+        util::{draw_mesh, get_camera, setup_textures, setup_vertex_args, PassCameras},
     },
     pipe::{
         pass::{Pass, PassData},
@@ -29,7 +30,8 @@ use crate::{
     resources::AmbientColor,
     tex::Texture,
     types::{Encoder, Factory},
-    vertex::{Normal, Position, Query, TexCoord},
+    vertex::{Attributes, Color, Normal, Position, Query, TexCoord},
+    view_model::ViewModelCamera,
     visibility::Visibility,
     Rgba,
 };
@@ -48,6 +50,7 @@ use super::*;
 #[derivative(Default(bound = "V: Query<(Position, Normal, TexCoord)>"))]
 pub struct DrawShaded<V> {
     _pd: PhantomData<V>,
+    vertex_colors: Option<Attributes<'static>>,
     transparency: Option<(ColorMask, Blend, Option<DepthMode>)>,
 }
 
@@ -70,6 +73,24 @@ where
         self.transparency = Some((mask, blend, depth));
         self
     }
+
+    fn attributes(&self) -> Attributes<'static> {
+        self.vertex_colors.unwrap_or(V::QUERIED_ATTRIBUTES)
+    }
+}
+
+impl<V> DrawShaded<V>
+where
+    V: Query<(Position, Normal, TexCoord)> + Query<(Position, Color, Normal, TexCoord)>,
+{
+    /// Enable vertex colors, multiplied with the shaded color like the `Rgba` tint.
+    ///
+    /// Needs a vertex format with a `Color` attribute, e.g. `PosColorNormTex`.
+    pub fn with_vertex_colors(mut self) -> Self {
+        self.vertex_colors =
+            Some(<V as Query<(Position, Color, Normal, TexCoord)>>::QUERIED_ATTRIBUTES);
+        self
+    }
 }
 
 impl<'a, V> PassData<'a> for DrawShaded<V>
@@ -88,9 +109,12 @@ where
         ReadStorage<'a, HiddenPropagate>,
         ReadStorage<'a, MeshHandle>,
         ReadStorage<'a, Material>,
+        ReadStorage<'a, MaterialOverride>,
         ReadStorage<'a, GlobalTransform>,
         ReadStorage<'a, Light>,
         ReadStorage<'a, Rgba>,
+        ReadStorage<'a, RenderLayers>,
+        ReadStorage<'a, ViewModelCamera>,
     );
 }
 
@@ -99,8 +123,14 @@ where
     V: Query<(Position, Normal, TexCoord)>,
 {
     fn compile(&mut self, effect: NewEffect<'_>) -> Result<Effect> {
-        let mut builder = effect.simple(VERT_SRC, FRAG_SRC);
-        builder.with_raw_vertex_buffer(V::QUERIED_ATTRIBUTES, V::size() as ElemStride, 0);
+        let vert = if self.vertex_colors.is_some() {
+            VERT_COLOR_SRC
+        } else {
+            VERT_SRC
+        };
+        let mut builder = effect.simple(vert, FRAG_SRC);
+        builder.with_raw_vertex_buffer(self.attributes(), V::size() as ElemStride, 0);
+        builder.with_cull_face_variants();
         setup_vertex_args(&mut builder);
         setup_light_buffers(&mut builder);
         setup_textures(&mut builder, &TEXTURES);
@@ -128,27 +158,37 @@ where
             hidden_prop,
             mesh,
             material,
+            material_overrides,
             global,
             light,
             rgba,
+            render_layers,
+            view_models,
         ): <Self as PassData<'a>>::Data,
     ) {
+        let cameras = PassCameras::new(&active, &camera, &global, &view_models);
         let camera = get_camera(active, &camera, &global);
 
         set_light_args(effect, encoder, &light, &global, &ambient, camera);
 
         match visibility {
             None => {
-                for (mesh, material, global, rgba, _, _) in (
+                for (mesh, material, overrides, global, rgba, _, _, layers) in (
                     &mesh,
                     &material,
+                    material_overrides.maybe(),
                     &global,
                     rgba.maybe(),
                     !&hidden,
                     !&hidden_prop,
+                    render_layers.maybe(),
                 )
                     .join()
                 {
+                    let camera = match cameras.select(layers) {
+                        Some(camera) => camera,
+                        None => continue,
+                    };
                     draw_mesh(
                         encoder,
                         effect,
@@ -157,25 +197,32 @@ where
                         None,
                         &tex_storage,
                         Some(material),
+                        overrides,
                         &material_defaults,
                         rgba,
                         camera,
                         Some(global),
-                        &[V::QUERIED_ATTRIBUTES],
+                        &[self.attributes()],
                         &TEXTURES,
                     );
                 }
             }
             Some(ref visibility) => {
-                for (mesh, material, global, rgba, _) in (
+                for (mesh, material, overrides, global, rgba, _, layers) in (
                     &mesh,
                     &material,
+                    material_overrides.maybe(),
                     &global,
                     rgba.maybe(),
                     &visibility.visible_unordered,
+                    render_layers.maybe(),
                 )
                     .join()
                 {
+                    let camera = match cameras.select(layers) {
+                        Some(camera) => camera,
+                        None => continue,
+                    };
                     draw_mesh(
                         encoder,
                         effect,
@@ -184,16 +231,21 @@ where
                         None,
                         &tex_storage,
                         Some(material),
+                        overrides,
                         &material_defaults,
                         rgba,
                         camera,
                         Some(global),
-                        &[V::QUERIED_ATTRIBUTES],
+                        &[self.attributes()],
                         &TEXTURES,
                     );
                 }
 
                 for entity in &visibility.visible_ordered {
+                    let camera = match cameras.select(render_layers.get(*entity)) {
+                        Some(camera) => camera,
+                        None => continue,
+                    };
                     if let Some(mesh) = mesh.get(*entity) {
                         draw_mesh(
                             encoder,
@@ -203,11 +255,12 @@ where
                             None,
                             &tex_storage,
                             material.get(*entity),
+                            material_overrides.get(*entity),
                             &material_defaults,
                             rgba.get(*entity),
                             camera,
                             global.get(*entity),
-                            &[V::QUERIED_ATTRIBUTES],
+                            &[self.attributes()],
                             &TEXTURES,
                         );
                     }
@@ -6,6 +6,7 @@ mod separate;
 use crate::pass::util::TextureType;
 
 static VERT_SRC: &[u8] = include_bytes!("../shaders/vertex/basic.glsl");
+static VERT_COLOR_SRC: &[u8] = include_bytes!("../shaders/vertex/basic_color.glsl");
 static FRAG_SRC: &[u8] = include_bytes!("../shaders/fragment/shaded.glsl");
 
 static TEXTURES: [TextureType; 2] = [TextureType::Albedo, TextureType::Emission];
@@ -13,13 +13,14 @@ use crate::{
     cam::{ActiveCamera, Camera},
     error::Result,
     hidden::{Hidden, HiddenPropagate},
+    layers::RenderLayers,
     light::Light,
     mesh::{Mesh, MeshHandle},
-    mtl::{Material, MaterialDefaults},
+    mtl::{Material, MaterialDefaults, MaterialOverride},
     pass::{
         shaded_util::{set_light_args, setup_light_buffers},
         skinning::{create_skinning_effect, setup_skinning_buffers},
-        util::{draw_mesh, get_camera, setup_textures, setup_vertex_args},
+        util::{draw_mesh, get_camera, setup_textures, setup_vertex_args, PassCameras},
     },
     pipe::{
         pass::{Pass, PassData},
@@ -29,7 +30,8 @@ use crate::{
     skinning::JointTransforms,
     tex::Texture,
     types::{Encoder, Factory},
-    vertex::{Attributes, Normal, Position, Separate, TexCoord, VertexFormat},
+    vertex::{Attributes, Color, Normal, Position, Separate, TexCoord, VertexFormat},
+    view_model::ViewModelCamera,
     visibility::Visibility,
     Rgba,
 };
@@ -42,6 +44,13 @@ static ATTRIBUTES: [Attributes<'static>; 3] = [
     Separate::<TexCoord>::ATTRIBUTES,
 ];
 
+static COLOR_ATTRIBUTES: [Attributes<'static>; 4] = [
+    Separate::<Position>::ATTRIBUTES,
+    Separate::<Normal>::ATTRIBUTES,
+    Separate::<TexCoord>::ATTRIBUTES,
+    Separate::<Color>::ATTRIBUTES,
+];
+
 /// Draw mesh with simple lighting technique
 ///
 /// See the [crate level documentation](index.html) for information about interleaved and separate
@@ -49,6 +58,7 @@ static ATTRIBUTES: [Attributes<'static>; 3] = [
 #[derive(Default, Clone, Debug, PartialEq)]
 pub struct DrawShadedSeparate {
     skinning: bool,
+    vertex_colors: bool,
     transparency: Option<(ColorMask, Blend, Option<DepthMode>)>,
 }
 
@@ -64,6 +74,14 @@ impl DrawShadedSeparate {
         self
     }
 
+    /// Enable vertex colors, multiplied with the shaded color like the `Rgba` tint.
+    ///
+    /// Meshes without a `Color` buffer are not drawn by the pass.
+    pub fn with_vertex_colors(mut self) -> Self {
+        self.vertex_colors = true;
+        self
+    }
+
     /// Enable transparency
     pub fn with_transparency(
         mut self,
@@ -89,10 +107,13 @@ impl<'a> PassData<'a> for DrawShadedSeparate {
         ReadStorage<'a, HiddenPropagate>,
         ReadStorage<'a, MeshHandle>,
         ReadStorage<'a, Material>,
+        ReadStorage<'a, MaterialOverride>,
         ReadStorage<'a, GlobalTransform>,
         ReadStorage<'a, Light>,
         ReadStorage<'a, JointTransforms>,
         ReadStorage<'a, Rgba>,
+        ReadStorage<'a, RenderLayers>,
+        ReadStorage<'a, ViewModelCamera>,
     );
 }
 
@@ -100,7 +121,9 @@ impl Pass for DrawShadedSeparate {
     fn compile(&mut self, effect: NewEffect<'_>) -> Result<Effect> {
         debug!("Building shaded pass");
         let mut builder = if self.skinning {
-            create_skinning_effect(effect, FRAG_SRC)
+            create_skinning_effect(effect, self.vertex_colors, FRAG_SRC)
+        } else if self.vertex_colors {
+            effect.simple(VERT_COLOR_SRC, FRAG_SRC)
         } else {
             effect.simple(VERT_SRC, FRAG_SRC)
         };
@@ -121,9 +144,17 @@ impl Pass for DrawShadedSeparate {
                 Separate::<TexCoord>::size() as ElemStride,
                 0,
             );
+        if self.vertex_colors {
+            builder.with_raw_vertex_buffer(
+                Separate::<Color>::ATTRIBUTES,
+                Separate::<Color>::size() as ElemStride,
+                0,
+            );
+        }
         if self.skinning {
             setup_skinning_buffers(&mut builder);
         }
+        builder.with_cull_face_variants();
         setup_vertex_args(&mut builder);
         setup_light_buffers(&mut builder);
         setup_textures(&mut builder, &TEXTURES);
@@ -151,30 +182,45 @@ impl Pass for DrawShadedSeparate {
             hidden_prop,
             mesh,
             material,
+            material_overrides,
             global,
             light,
             joints,
             rgba,
+            render_layers,
+            view_models,
         ): <Self as PassData<'a>>::Data,
     ) {
+        let attributes: &[Attributes<'static>] = if self.vertex_colors {
+            &COLOR_ATTRIBUTES
+        } else {
+            &ATTRIBUTES
+        };
         trace!("Drawing shaded pass");
+        let cameras = PassCameras::new(&active, &camera, &global, &view_models);
         let camera = get_camera(active, &camera, &global);
 
         set_light_args(effect, encoder, &light, &global, &ambient, camera);
 
         match visibility {
             None => {
-                for (joint, mesh, material, global, rgba, _, _) in (
+                for (joint, mesh, material, overrides, global, rgba, _, _, layers) in (
                     joints.maybe(),
                     &mesh,
                     &material,
+                    material_overrides.maybe(),
                     &global,
                     rgba.maybe(),
                     !&hidden,
                     !&hidden_prop,
+                    render_layers.maybe(),
                 )
                     .join()
                 {
+                    let camera = match cameras.select(layers) {
+                        Some(camera) => camera,
+                        None => continue,
+                    };
                     draw_mesh(
                         encoder,
                         effect,
@@ -183,26 +229,33 @@ impl Pass for DrawShadedSeparate {
                         joint,
                         &tex_storage,
                         Some(material),
+                        overrides,
                         &material_defaults,
                         rgba,
                         camera,
                         Some(global),
-                        &ATTRIBUTES,
+                        attributes,
                         &TEXTURES,
                     );
                 }
             }
             Some(ref visibility) => {
-                for (joint, mesh, material, global, rgba, _) in (
+                for (joint, mesh, material, overrides, global, rgba, _, layers) in (
                     joints.maybe(),
                     &mesh,
                     &material,
+                    material_overrides.maybe(),
                     &global,
                     rgba.maybe(),
                     &visibility.visible_unordered,
+                    render_layers.maybe(),
                 )
                     .join()
                 {
+                    let camera = match cameras.select(layers) {
+                        Some(camera) => camera,
+                        None => continue,
+                    };
                     draw_mesh(
                         encoder,
                         effect,
@@ -211,16 +264,21 @@ impl Pass for DrawShadedSeparate {
                         joint,
                         &tex_storage,
                         Some(material),
+                        overrides,
                         &material_defaults,
                         rgba,
                         camera,
                         Some(global),
-                        &ATTRIBUTES,
+                        attributes,
                         &TEXTURES,
                     );
                 }
 
                 for entity in &visibility.visible_ordered {
+                    let camera = match cameras.select(render_layers.get(*entity)) {
+                        Some(camera) => camera,
+                        None => continue,
+                    };
                     if let Some(mesh) = mesh.get(*entity) {
                         draw_mesh(
                             encoder,
@@ -230,11 +288,12 @@ impl Pass for DrawShadedSeparate {
                             joints.get(*entity),
                             &tex_storage,
                             material.get(*entity),
+                            material_overrides.get(*entity),
                             &material_defaults,
                             rgba.get(*entity),
                             camera,
                             global.get(*entity),
-                            &ATTRIBUTES,
+                            attributes,
                             &TEXTURES,
                         );
                     }
@@ -1,25 +1,37 @@
-use std::mem;
+use std::{cmp::Ordering, mem};
 
 use glsl_layout::*;
 
+use amethyst_assets::AssetStorage;
 use amethyst_core::{
+    nalgebra::{Point3, Vector3},
     specs::prelude::{Join, ReadStorage},
     GlobalTransform,
 };
 
 use crate::{
     cam::Camera,
-    light::Light,
+    light::{AreaShape, Light, LightCookie},
+    pass::{light_clusters::LightClusters, util::add_texture},
     pipe::{Effect, EffectBuilder},
     resources::AmbientColor,
+    tex::{Texture, TextureHandle},
     types::Encoder,
 };
 
+/// Number of cookie textures, and of profile textures, bound by `setup_light_cookies`.
+const MAX_LIGHT_COOKIES: usize = 4;
+/// Number of point lights in the `PointLights` buffer, the closest to the camera are kept.
+const MAX_POINT_LIGHTS: usize = 192;
+/// Size of the `AreaLights` buffer of the PBM shader.
+const MAX_AREA_LIGHTS: usize = 16;
+
 #[derive(Clone, Copy, Debug, Uniform)]
 pub(crate) struct FragmentArgs {
     point_light_count: uint,
     directional_light_count: uint,
     spot_light_count: uint,
+    area_light_count: uint,
 }
 
 #[derive(Clone, Copy, Debug, Uniform)]
@@ -28,6 +40,10 @@ pub(crate) struct PointLightPod {
     color: vec3,
     pad: float,
     intensity: float,
+    axis: vec3,
+    profile: int,
+    radius: float,
+    smoothness: float,
 }
 
 #[derive(Clone, Copy, Debug, Uniform)]
@@ -45,6 +61,64 @@ pub(crate) struct SpotLightPod {
     intensity: float,
     range: float,
     smoothness: float,
+    cookie: int,
+    profile: int,
+}
+
+#[derive(Clone, Copy, Debug, Uniform)]
+pub(crate) struct AreaLightPod {
+    position: vec3,
+    color: vec3,
+    /// Half of the width or length of the shape, along its X axis.
+    right: vec3,
+    /// Half of the height of rectangles along their Y axis, zero for tubes.
+    up: vec3,
+    intensity: float,
+    radius: float,
+    smoothness: float,
+    tube_radius: float,
+    /// `0` for rectangles, `1` for tubes.
+    shape: int,
+}
+
+/// The cookies of the lights, the textures to read them from, and the clusters the point lights
+/// are assigned to.
+pub(crate) struct LightTextures<'a> {
+    pub(crate) cookies: &'a ReadStorage<'a, LightCookie>,
+    pub(crate) textures: &'a AssetStorage<Texture>,
+    /// Bound to the slots no light uses.
+    pub(crate) fallback: &'a TextureHandle,
+    /// The effect has to be built with `LightClusters::setup` too.
+    pub(crate) clusters: Option<&'a LightClusters>,
+}
+
+/// The cookie and profile textures bound for the lights, in slot order.
+#[derive(Default)]
+struct CookieSlots<'a> {
+    cookies: Vec<&'a Texture>,
+    profiles: Vec<&'a Texture>,
+}
+
+impl<'a> CookieSlots<'a> {
+    /// Returns the slot of a texture in `slots`, or `-1` if it isn't loaded or the slots are full.
+    fn assign(
+        slots: &mut Vec<&'a Texture>,
+        textures: &'a AssetStorage<Texture>,
+        handle: Option<&TextureHandle>,
+    ) -> i32 {
+        let texture = match handle.and_then(|handle| textures.get(handle)) {
+            Some(texture) => texture,
+            None => return -1,
+        };
+        match slots.iter().position(|slot| *slot == texture) {
+            Some(i) => i as i32,
+            None if slots.len() < MAX_LIGHT_COOKIES => {
+                slots.push(texture);
+                slots.len() as i32 - 1
+            }
+            None => -1,
+        }
+    }
 }
 
 pub(crate) fn set_light_args(
@@ -55,17 +129,113 @@ pub(crate) fn set_light_args(
     ambient: &AmbientColor,
     camera: Option<(&Camera, &GlobalTransform)>,
 ) {
-    let point_lights: Vec<_> = (light, global)
-        .join()
-        .filter_map(|(light, transform)| {
+    set_lights(effect, encoder, light, global, ambient, camera, None);
+}
+
+/// Sets the lights like `set_light_args`, and binds the textures of their cookies and clusters,
+/// which stay bound for every mesh. The effect has to be built with `setup_light_cookies`.
+pub(crate) fn set_light_args_with_textures(
+    effect: &mut Effect,
+    encoder: &mut Encoder,
+    light: &ReadStorage<'_, Light>,
+    global: &ReadStorage<'_, GlobalTransform>,
+    ambient: &AmbientColor,
+    camera: Option<(&Camera, &GlobalTransform)>,
+    textures: &LightTextures<'_>,
+) {
+    set_lights(
+        effect,
+        encoder,
+        light,
+        global,
+        ambient,
+        camera,
+        Some(textures),
+    );
+}
+
+fn set_lights(
+    effect: &mut Effect,
+    encoder: &mut Encoder,
+    light: &ReadStorage<'_, Light>,
+    global: &ReadStorage<'_, GlobalTransform>,
+    ambient: &AmbientColor,
+    camera: Option<(&Camera, &GlobalTransform)>,
+    textures: Option<&LightTextures<'_>>,
+) {
+    let lights: Vec<_> = match textures {
+        Some(textures) => (light, global, textures.cookies.maybe()).join().collect(),
+        None => (light, global)
+            .join()
+            .map(|(light, transform)| (light, transform, None))
+            .collect(),
+    };
+    // Lights whose radius of influence is outside of the view don't light anything drawn.
+    let frustum = camera.map(|(camera, transform)| camera.frustum(transform));
+    let in_view = |transform: &GlobalTransform, radius: f32| {
+        let position = Point3::from(transform.0.column(3).xyz());
+        radius <= 0.0
+            || frustum
+                .as_ref()
+                .map_or(true, |frustum| frustum.intersects_sphere(&position, radius))
+    };
+    let mut slots = CookieSlots::default();
+    let assign = |slots: &mut Vec<_>, handle: Option<&TextureHandle>| match textures {
+        Some(textures) => CookieSlots::assign(slots, textures.textures, handle),
+        None => -1,
+    };
+
+    let mut point_lights: Vec<_> = lights
+        .iter()
+        .filter(|&&(light, transform, _)| match *light {
+            Light::Point(ref light) => in_view(transform, light.radius),
+            _ => false,
+        })
+        .collect();
+    if point_lights.len() > MAX_POINT_LIGHTS {
+        if let Some((_, camera)) = camera {
+            let eye = Point3::from(camera.0.column(3).xyz());
+            let distance = |transform: &GlobalTransform| {
+                (Point3::from(transform.0.column(3).xyz()) - eye).norm_squared()
+            };
+            point_lights.sort_by(|a, b| {
+                distance(a.1)
+                    .partial_cmp(&distance(b.1))
+                    .unwrap_or(Ordering::Equal)
+            });
+        }
+        point_lights.truncate(MAX_POINT_LIGHTS);
+    }
+    let cluster_lights: Vec<_> = point_lights
+        .iter()
+        .filter_map(|&&(light, transform, _)| match *light {
+            Light::Point(ref light) => {
+                Some((Point3::from(transform.0.column(3).xyz()), light.radius))
+            }
+            _ => None,
+        })
+        .collect();
+    let point_lights: Vec<_> = point_lights
+        .into_iter()
+        .filter_map(|&(light, transform, cookie)| {
             if let Light::Point(ref light) = *light {
                 let position: [f32; 3] = transform.0.column(3).xyz().into();
+                // IES profiles are measured from straight down.
+                let axis: [f32; 3] = (-transform.0.column(1).xyz()).into();
+                let profile = assign(
+                    &mut slots.profiles,
+                    cookie.and_then(|cookie| cookie.profile.as_ref()),
+                );
                 Some(
                     PointLightPod {
                         position: position.into(),
                         color: light.color.into(),
                         intensity: light.intensity,
                         pad: 0.0,
+                        axis: axis.into(),
+                        profile,
+                        radius: light.radius,
+                        smoothness: light.smoothness,
                     }
                     .std140(),
                 )
@@ -92,11 +262,22 @@ pub(crate) fn set_light_args(
         })
         .collect();
 
-    let spot_lights: Vec<_> = (light, global)
-        .join()
-        .filter_map(|(light, transform)| {
+    let spot_lights: Vec<_> = lights
+        .iter()
+        .filter_map(|&(light, transform, cookie)| {
             if let Light::Spot(ref light) = *light {
+                if !in_view(transform, light.range) {
+                    return None;
+                }
                 let position: [f32; 3] = transform.0.column(3).xyz().into();
+                let cookie_slot = assign(
+                    &mut slots.cookies,
+                    cookie.and_then(|cookie| cookie.cookie.as_ref()),
+                );
+                let profile = assign(
+                    &mut slots.profiles,
+                    cookie.and_then(|cookie| cookie.profile.as_ref()),
+                );
                 Some(
                     SpotLightPod {
                         position: position.into(),
@@ -106,6 +287,48 @@ pub(crate) fn set_light_args(
                         intensity: light.intensity,
                         range: light.range,
                         smoothness: light.smoothness,
+                        cookie: cookie_slot,
+                        profile,
+                    }
+                    .std140(),
+                )
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    let area_lights: Vec<_> = lights
+        .iter()
+        .filter_map(|&(light, transform, _)| {
+            if let Light::Area(ref light) = *light {
+                if !in_view(transform, light.radius) {
+                    return None;
+                }
+                let position: [f32; 3] = transform.0.column(3).xyz().into();
+                let x_axis = transform.0.column(0).xyz().normalize();
+                let y_axis = transform.0.column(1).xyz().normalize();
+                let (right, up, tube_radius, shape) = match light.shape {
+                    AreaShape::Rectangle { width, height } => {
+                        (x_axis * width / 2.0, y_axis * height / 2.0, 0.0, 0)
+                    }
+                    AreaShape::Tube { length, radius } => {
+                        (x_axis * length / 2.0, Vector3::zeros(), radius, 1)
+                    }
+                };
+                let right: [f32; 3] = right.into();
+                let up: [f32; 3] = up.into();
+                Some(
+                    AreaLightPod {
+                        position: position.into(),
+                        color: light.color.into(),
+                        right: right.into(),
+                        up: up.into(),
+                        intensity: light.intensity,
+                        radius: light.radius,
+                        smoothness: light.smoothness,
+                        tube_radius,
+                        shape,
                     }
                     .std140(),
                 )
@@ -113,18 +336,21 @@ pub(crate) fn set_light_args(
                 None
             }
         })
+        .take(MAX_AREA_LIGHTS)
         .collect();
 
     let fragment_args = FragmentArgs {
         point_light_count: point_lights.len() as u32,
         directional_light_count: directional_lights.len() as u32,
         spot_light_count: spot_lights.len() as u32,
+        area_light_count: area_lights.len() as u32,
     };
 
     effect.update_constant_buffer("FragmentArgs", &fragment_args.std140(), encoder);
     effect.update_buffer("PointLights", &point_lights[..], encoder);
     effect.update_buffer("DirectionalLights", &directional_lights[..], encoder);
     effect.update_buffer("SpotLights", &spot_lights[..], encoder);
+    effect.update_buffer("AreaLights", &area_lights[..], encoder);
 
     effect.update_global("ambient_color", Into::<[f32; 3]>::into(*ambient.as_ref()));
 
@@ -135,6 +361,23 @@ pub(crate) fn set_light_args(
             .map(|&(_, ref trans)| trans.0.column(3).xyz().into())
             .unwrap_or([0.0; 3]),
     );
+
+    if let Some(textures) = textures {
+        let fallback = textures
+            .textures
+            .get(textures.fallback)
+            .expect("Texture missing in asset storage");
+        effect.clear_textures();
+        for slots in &[&slots.cookies, &slots.profiles] {
+            for i in 0..MAX_LIGHT_COOKIES {
+                add_texture(effect, slots.get(i).cloned().unwrap_or(fallback));
+            }
+        }
+        if let Some(clusters) = textures.clusters {
+            clusters.update(effect, encoder, &cluster_lights, camera);
+        }
+        effect.keep_textures();
+    }
 }
 
 pub(crate) fn setup_light_buffers(builder: &mut EffectBuilder<'_>) {
@@ -147,7 +390,7 @@ pub(crate) fn setup_light_buffers(builder: &mut EffectBuilder<'_>) {
         .with_raw_constant_buffer(
             "PointLights",
             mem::size_of::<<PointLightPod as Uniform>::Std140>(),
-            128,
+            MAX_POINT_LIGHTS,
         )
         .with_raw_constant_buffer(
             "DirectionalLights",
@@ -159,6 +402,25 @@ pub(crate) fn setup_light_buffers(builder: &mut EffectBuilder<'_>) {
             mem::size_of::<<SpotLightPod as Uniform>::Std140>(),
             128,
         )
+        .with_raw_constant_buffer(
+            "AreaLights",
+            mem::size_of::<<AreaLightPod as Uniform>::Std140>(),
+            16,
+        )
         .with_raw_global("ambient_color")
         .with_raw_global("camera_position");
 }
+
+/// Adds the cookie and profile textures of the lights, which have to come before the other
+/// textures of the effect.
+pub(crate) fn setup_light_cookies(builder: &mut EffectBuilder<'_>) {
+    builder
+        .with_texture("light_cookie0")
+        .with_texture("light_cookie1")
+        .with_texture("light_cookie2")
+        .with_texture("light_cookie3")
+        .with_texture("light_profile0")
+        .with_texture("light_profile1")
+        .with_texture("light_profile2")
+        .with_texture("light_profile3");
+}
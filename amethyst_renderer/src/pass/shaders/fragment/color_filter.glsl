@@ -0,0 +1,24 @@
+// Copies the frame drawn into a target, remapping its colors.
+
+#version 150 core
+
+layout (std140) uniform ColorFilterArgs {
+    // Columns of the color matrix.
+    vec4 red;
+    vec4 green;
+    vec4 blue;
+};
+
+uniform sampler2D frame;
+
+in VertexData {
+    vec2 tex_coord;
+} vertex;
+
+out vec4 out_color;
+
+void main() {
+    vec4 color = texture(frame, vertex.tex_coord);
+    vec3 filtered = mat3(red.rgb, green.rgb, blue.rgb) * color.rgb;
+    out_color = vec4(clamp(filtered, 0.0, 1.0), color.a);
+}
@@ -0,0 +1,70 @@
+// Colors the debug view: lines keep their color, triangles show their edges and texture
+// coordinates depending on `debug_mode`.
+
+#version 150 core
+
+uniform int debug_mode;
+uniform float checker_size;
+
+uniform sampler2D albedo;
+
+layout (std140) uniform AlbedoOffset {
+    vec2 u_offset;
+    vec2 v_offset;
+} albedo_offset;
+
+in VertexData {
+    vec2 tex_coord;
+    vec3 barycentric;
+    vec4 line_color;
+} vertex;
+
+out vec4 out_color;
+
+const vec3 MIP_COLORS[6] = vec3[6](
+    vec3(1.0, 0.0, 0.0),
+    vec3(1.0, 1.0, 0.0),
+    vec3(0.0, 1.0, 0.0),
+    vec3(0.0, 1.0, 1.0),
+    vec3(0.0, 0.0, 1.0),
+    vec3(1.0, 0.0, 1.0)
+);
+
+float tex_coord(float coord, vec2 offset) {
+    return offset.x + coord * (offset.y - offset.x);
+}
+
+vec2 tex_coords(vec2 coord, vec2 u, vec2 v) {
+    return vec2(tex_coord(coord.x, u), tex_coord(coord.y, v));
+}
+
+void main() {
+    if (vertex.line_color.a > 0.0) {
+        out_color = vertex.line_color;
+        return;
+    }
+
+    vec4 color = vec4(0.0);
+    if ((debug_mode & 4) != 0) {
+        vec2 cell = floor(vertex.tex_coord * checker_size);
+        float checker = mod(cell.x + cell.y, 2.0);
+        color = vec4(mix(vec3(0.2), vec3(0.8), checker), 1.0);
+    }
+    if ((debug_mode & 8) != 0) {
+        vec2 uv = tex_coords(vertex.tex_coord, albedo_offset.u_offset, albedo_offset.v_offset);
+        vec2 texel = uv * vec2(textureSize(albedo, 0));
+        float footprint = max(dot(dFdx(texel), dFdx(texel)), dot(dFdy(texel), dFdy(texel)));
+        float level = max(0.5 * log2(footprint), 0.0);
+        color = vec4(MIP_COLORS[min(int(level), 5)], 1.0);
+    }
+    if ((debug_mode & 1) != 0) {
+        vec3 width = fwidth(vertex.barycentric);
+        vec3 edges = smoothstep(vec3(0.0), width * 1.5, vertex.barycentric);
+        float edge = 1.0 - min(min(edges.x, edges.y), edges.z);
+        color = mix(color, vec4(0.0, 1.0, 0.3, 1.0), edge);
+    }
+    if (color.a <= 0.0) {
+        discard;
+    }
+    out_color = color;
+}
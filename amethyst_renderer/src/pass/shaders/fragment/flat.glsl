@@ -2,6 +2,8 @@
 
 #version 150 core
 
+uniform float alpha_cutoff;
+
 uniform sampler2D albedo;
 
 layout (std140) uniform AlbedoOffset {
@@ -28,5 +30,7 @@ vec2 tex_coords(vec2 coord, vec2 u, vec2 v) {
 }
 
 void main() {
-    color = texture(albedo, tex_coords(vertex.tex_coord, albedo_offset.u_offset, albedo_offset.v_offset)) * vertex.color;
+    vec4 albedo_color = texture(albedo, tex_coords(vertex.tex_coord, albedo_offset.u_offset, albedo_offset.v_offset));
+    if (albedo_color.a < alpha_cutoff) discard;
+    color = albedo_color * vertex.color;
 }
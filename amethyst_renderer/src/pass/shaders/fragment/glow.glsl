@@ -0,0 +1,40 @@
+// Adds the emission of the material, fading out towards the silhouette of the shell.
+
+#version 150 core
+
+uniform vec3 camera_position;
+uniform float glow_intensity;
+uniform float emission_intensity;
+
+uniform sampler2D emission;
+
+layout (std140) uniform EmissionOffset {
+    vec2 u_offset;
+    vec2 v_offset;
+} emission_offset;
+
+in VertexData {
+    vec3 position;
+    vec3 normal;
+    vec2 tex_coord;
+    vec4 color;
+} vertex;
+
+out vec4 color;
+
+float tex_coord(float coord, vec2 offset) {
+    return offset.x + coord * (offset.y - offset.x);
+}
+
+vec2 tex_coords(vec2 coord, vec2 u, vec2 v) {
+    return vec2(tex_coord(coord.x, u), tex_coord(coord.y, v));
+}
+
+void main() {
+    vec3 view_direction = normalize(camera_position - vertex.position);
+    // The back faces point away from the camera, the most in front of the center of the mesh.
+    float facing = clamp(-dot(normalize(vertex.normal), view_direction), 0.0, 1.0);
+    float strength = facing * facing * glow_intensity * emission_intensity;
+    vec3 emission_color = texture(emission, tex_coords(vertex.tex_coord, emission_offset.u_offset, emission_offset.v_offset)).rgb;
+    color = vec4(emission_color * vertex.color.rgb * strength, 1.0);
+}
@@ -6,6 +6,7 @@ layout (std140) uniform FragmentArgs {
     int point_light_count;
     int directional_light_count;
     int spot_light_count;
+    int area_light_count;
 };
 
 struct PointLight {
@@ -13,10 +14,14 @@ struct PointLight {
     vec3 color;
     float pad; // Workaround for bug in mac's implementation of opengl (loads garbage when accessing members of structures in arrays with dynamic indices).
     float intensity;
+    vec3 axis;
+    int profile;
+    float radius;
+    float smoothness;
 };
 
 layout (std140) uniform PointLights {
-    PointLight plight[128];
+    PointLight plight[192];
 };
 
 struct DirectionalLight {
@@ -36,16 +41,76 @@ struct SpotLight {
     float intensity;
     float range;
     float smoothness;
+    int cookie;
+    int profile;
 };
 
 layout (std140) uniform SpotLights {
     SpotLight slight[128];
 };
 
+struct AreaLight {
+    vec3 position;
+    vec3 color;
+    vec3 right;
+    vec3 up;
+    float intensity;
+    float radius;
+    float smoothness;
+    float tube_radius;
+    int shape;
+};
+
+layout (std140) uniform AreaLights {
+    AreaLight alight[16];
+};
+
+// The point lights assigned to the clusters of the view, see `light_clusters.rs`.
+layout (std140) uniform ClusterArgs {
+    mat4 proj;
+    mat4 view;
+    int clusters_x;
+    int clusters_y;
+    int clusters_z;
+    float near;
+    float far;
+    int clustered;
+} cluster_args;
+
+// The baked probes lighting the scene, see `probes.rs`.
+layout (std140) uniform ProbeArgs {
+    int irradiance;
+    int reflection;
+    int levels;
+} probe_args;
+
+struct IrradianceCoefficient {
+    vec4 coefficient;
+};
+
+layout (std140) uniform Irradiance {
+    IrradianceCoefficient irradiance[9];
+};
+
 uniform vec3 ambient_color;
 uniform vec3 camera_position;
 
 uniform float alpha_cutoff;
+uniform float emission_intensity;
+
+uniform sampler2D light_cookie0;
+uniform sampler2D light_cookie1;
+uniform sampler2D light_cookie2;
+uniform sampler2D light_cookie3;
+uniform sampler2D light_profile0;
+uniform sampler2D light_profile1;
+uniform sampler2D light_profile2;
+uniform sampler2D light_profile3;
+
+uniform usampler2D cluster_grid;
+uniform usampler2D cluster_lights;
+
+uniform sampler2D environment;
 
 uniform sampler2D albedo;
 uniform sampler2D emission;
@@ -165,6 +230,128 @@ vec3 compute_light(vec3 attenuation,
     return resulting_light;
 }
 
+// Windows the inverse square falloff, to reach zero at the radius of the light.
+float falloff_window(float distance, float radius, float smoothness) {
+    if (radius <= 0.0) {
+        return 1.0;
+    }
+    float window = clamp(1.0 - pow(distance / radius, smoothness), 0.0, 1.0);
+    return window * window;
+}
+
+// The point of a tube closest to `ray`, relative to the fragment.
+vec3 closest_on_tube(vec3 center, vec3 half_axis, float radius, vec3 ray) {
+    vec3 start = center - half_axis;
+    vec3 segment = 2.0 * half_axis;
+    float ray_dot_segment = dot(ray, segment);
+    float t = (dot(ray, start) * ray_dot_segment - dot(start, segment))
+        / max(dot(segment, segment) - ray_dot_segment * ray_dot_segment, 0.00001);
+    vec3 closest = start + segment * clamp(t, 0.0, 1.0);
+    // The point of the sphere around it closest to the ray.
+    vec3 to_ray = dot(closest, ray) * ray - closest;
+    return closest + to_ray * clamp(radius / max(length(to_ray), 0.00001), 0.0, 1.0);
+}
+
+// The point of a rectangle closest to `ray`, relative to the fragment in front of it.
+vec3 closest_on_rectangle(vec3 center, vec3 half_right, vec3 half_up, vec3 light_normal, vec3 ray) {
+    float ray_dot_normal = dot(ray, light_normal);
+    // Where the ray hits the plane of the rectangle, or the closest point of the plane when it
+    // points away from it.
+    vec3 on_plane = ray_dot_normal < -0.0001
+        ? ray * (dot(center, light_normal) / ray_dot_normal)
+        : light_normal * dot(center, light_normal);
+    vec3 local = on_plane - center;
+    float width = length(half_right);
+    float height = length(half_up);
+    vec3 x = half_right / max(width, 0.00001);
+    vec3 y = half_up / max(height, 0.00001);
+    return center + x * clamp(dot(local, x), -width, width) + y * clamp(dot(local, y), -height, height);
+}
+
+// Arrays of samplers can only be indexed with constants.
+vec3 light_cookie(int slot, vec2 coord) {
+    if (slot == 0) return texture(light_cookie0, coord).rgb;
+    if (slot == 1) return texture(light_cookie1, coord).rgb;
+    if (slot == 2) return texture(light_cookie2, coord).rgb;
+    if (slot == 3) return texture(light_cookie3, coord).rgb;
+    return vec3(1.0);
+}
+
+// The intensity of a light along `direction`, from the first texel of its profile along its
+// axis to the last pointing away from it.
+float light_profile(int slot, vec3 axis, vec3 direction) {
+    vec2 coord = vec2(acos(clamp(dot(normalize(axis), direction), -1.0, 1.0)) / PI, 0.5);
+    if (slot == 0) return texture(light_profile0, coord).r;
+    if (slot == 1) return texture(light_profile1, coord).r;
+    if (slot == 2) return texture(light_profile2, coord).r;
+    if (slot == 3) return texture(light_profile3, coord).r;
+    return 1.0;
+}
+
+// Projects `direction` onto the cone of a spot light, with the cosine of its opening angle.
+vec2 cookie_coord(vec3 spot_direction, float cos_angle, vec3 direction) {
+    vec3 up = abs(spot_direction.y) < 0.99 ? vec3(0.0, 1.0, 0.0) : vec3(1.0, 0.0, 0.0);
+    vec3 right = normalize(cross(spot_direction, up));
+    up = cross(right, spot_direction);
+    float tan_angle = sqrt(1.0 - cos_angle * cos_angle) / cos_angle;
+    vec2 plane = vec2(dot(direction, right), dot(direction, up)) / max(dot(direction, spot_direction), 0.00001);
+    return plane / tan_angle * 0.5 + 0.5;
+}
+
+// The rows of the textures are stored from the bottom.
+uvec2 fetch_row(usampler2D tex, int x, int row) {
+    return texelFetch(tex, ivec2(x, textureSize(tex, 0).y - 1 - row), 0).rg;
+}
+
+// The offset and count of the lights of the cluster of a position.
+uvec2 light_cluster(vec3 position) {
+    vec4 view_position = cluster_args.view * vec4(position, 1.0);
+    vec4 clip = cluster_args.proj * view_position;
+    vec2 tile = (clip.xy / clip.w * 0.5 + 0.5) * vec2(cluster_args.clusters_x, cluster_args.clusters_y);
+    float depth = max(-view_position.z, cluster_args.near);
+    float slice = log(depth / cluster_args.near) / log(cluster_args.far / cluster_args.near) * float(cluster_args.clusters_z);
+    int x = clamp(int(tile.x), 0, cluster_args.clusters_x - 1);
+    int y = clamp(int(tile.y), 0, cluster_args.clusters_y - 1);
+    int z = clamp(int(slice), 0, cluster_args.clusters_z - 1);
+    return fetch_row(cluster_grid, x + cluster_args.clusters_x * y, z);
+}
+
+// The index of a point light in `plight`.
+int cluster_light(int index) {
+    int width = textureSize(cluster_lights, 0).x;
+    return int(fetch_row(cluster_lights, index % width, index / width).r);
+}
+
+// The irradiance of the spherical harmonics for a normal.
+vec3 probe_irradiance(vec3 n) {
+    vec3 color = irradiance[0].coefficient.rgb * 0.282095
+        + irradiance[1].coefficient.rgb * 0.488603 * n.y
+        + irradiance[2].coefficient.rgb * 0.488603 * n.z
+        + irradiance[3].coefficient.rgb * 0.488603 * n.x
+        + irradiance[4].coefficient.rgb * 1.092548 * n.x * n.y
+        + irradiance[5].coefficient.rgb * 1.092548 * n.y * n.z
+        + irradiance[6].coefficient.rgb * 0.315392 * (3.0 * n.z * n.z - 1.0)
+        + irradiance[7].coefficient.rgb * 1.092548 * n.x * n.z
+        + irradiance[8].coefficient.rgb * 0.546274 * (n.x * n.x - n.y * n.y);
+    return max(color, vec3(0.0));
+}
+
+// The environment seen in a direction, blurred for the roughness. The levels of the
+// latitude-longitude image are stacked from the top, and the rows are stored from the bottom.
+vec3 probe_environment(vec3 direction, float roughness) {
+    float u = atan(direction.x, direction.z) / (2.0 * PI) + 0.5;
+    float v = acos(clamp(direction.y, -1.0, 1.0)) / PI;
+    float height = float(textureSize(environment, 0).y);
+    float level_height = height / float(probe_args.levels);
+    float row = clamp(v * level_height, 0.5, level_height - 0.5);
+    float level = roughness * float(probe_args.levels - 1);
+    float lower = floor(level);
+    float upper = min(lower + 1.0, float(probe_args.levels - 1));
+    vec3 sharp = texture(environment, vec2(u, 1.0 - (lower * level_height + row) / height)).rgb;
+    vec3 blurry = texture(environment, vec2(u, 1.0 - (upper * level_height + row) / height)).rgb;
+    return mix(sharp, blurry, level - lower);
+}
+
 void main() {
     vec4 albedo_alpha       = texture(albedo, tex_coords(vertex.tex_coord, albedo_offset.u_offset, albedo_offset.v_offset)).rgba;
 
@@ -172,7 +359,7 @@ void main() {
     if(alpha < alpha_cutoff) discard;
 
     vec3 albedo             = albedo_alpha.rgb;
-    vec3 emission           = texture(emission, tex_coords(vertex.tex_coord, emission_offset.u_offset, emission_offset.v_offset)).rgb;
+    vec3 emission           = texture(emission, tex_coords(vertex.tex_coord, emission_offset.u_offset, emission_offset.v_offset)).rgb * emission_intensity;
     vec3 normal             = texture(normal, tex_coords(vertex.tex_coord, normal_offset.u_offset, normal_offset.v_offset)).rgb;
     float metallic          = texture(metallic, tex_coords(vertex.tex_coord, metallic_offset.u_offset, metallic_offset.v_offset)).r;
     float roughness         = texture(roughness, tex_coords(vertex.tex_coord, roughness_offset.u_offset, roughness_offset.v_offset)).r;
@@ -186,6 +373,10 @@ void main() {
     vec3 fresnel_base = mix(vec3(0.04), albedo, metallic);
 
     vec3 vertex_normal = normalize(vertex.normal);
+    // Light the back of two sided faces as if their normals pointed the other way.
+    if (!gl_FrontFacing) {
+        vertex_normal = -vertex_normal;
+    }
     vec3 vertex_tangent = normalize(vertex.tangent - vertex_normal * dot(vertex_normal, vertex.tangent));
     vec3 vertex_bitangent = normalize(cross(vertex_normal, vertex_tangent));
     mat3 vertex_basis = mat3(vertex_tangent, vertex_bitangent, vertex_normal);
@@ -194,9 +385,23 @@ void main() {
 
     vec3 view_direction = normalize(camera_position - vertex.position);
     vec3 lighted = vec3(0.0);
-    for (int i = 0; i < point_light_count; i++) {
-        vec3 light_direction = normalize(plight[i].position - vertex.position);
-        float attenuation = plight[i].intensity / dot(light_direction, light_direction);
+    int cluster_offset = 0;
+    int cluster_count = point_light_count;
+    if (cluster_args.clustered != 0) {
+        uvec2 cluster = light_cluster(vertex.position);
+        cluster_offset = int(cluster.x);
+        cluster_count = int(cluster.y);
+    }
+    for (int n = 0; n < cluster_count; n++) {
+        int i = cluster_args.clustered != 0 ? cluster_light(cluster_offset + n) : n;
+        vec3 light_vec = plight[i].position - vertex.position;
+        float light_distance = length(light_vec);
+        vec3 light_direction = light_vec / max(light_distance, 0.00001);
+        float attenuation = plight[i].intensity / max(light_distance * light_distance, 0.0001)
+            * falloff_window(light_distance, plight[i].radius, plight[i].smoothness);
+        if (plight[i].profile >= 0) {
+            attenuation *= light_profile(plight[i].profile, plight[i].axis, -light_direction);
+        }
 
         vec3 light = compute_light(vec3(attenuation),
                                    plight[i].color,
@@ -267,8 +472,15 @@ void main() {
 
         // combine the attenuations and intensity
         float attenuation = range_attenuation * ring_attenuation * slight[i].intensity;
-
-        vec3 light = compute_light(vec3(attenuation),
+        vec3 shape = vec3(1.0);
+        if (slight[i].cookie >= 0) {
+            shape = light_cookie(slight[i].cookie, cookie_coord(spot_direction, spot_angle, -normalized_light_vec));
+        }
+        if (slight[i].profile >= 0) {
+            shape *= light_profile(slight[i].profile, spot_direction, -normalized_light_vec);
+        }
+
+        vec3 light = compute_light(attenuation * shape,
                                    slight[i].color,
                                    view_direction,
                                    normalize(light_vec),
@@ -280,7 +492,45 @@ void main() {
         lighted += light;
     }
 
-    vec3 ambient = ambient_color * albedo * ambient_occlusion;
+    vec3 reflected = reflect(-view_direction, normal);
+    for (int i = 0; i < area_light_count; i++) {
+        vec3 center = alight[i].position - vertex.position;
+        vec3 closest;
+        if (alight[i].shape == 1) {
+            closest = closest_on_tube(center, alight[i].right, alight[i].tube_radius, reflected);
+        } else {
+            vec3 light_normal = normalize(cross(alight[i].right, alight[i].up));
+            // Rectangles only light what's in front of them.
+            if (dot(center, light_normal) >= 0.0) {
+                continue;
+            }
+            closest = closest_on_rectangle(center, alight[i].right, alight[i].up, light_normal, reflected);
+        }
+        float light_distance = length(closest);
+        vec3 light_direction = closest / max(light_distance, 0.00001);
+        float attenuation = alight[i].intensity / max(light_distance * light_distance, 0.0001)
+            * falloff_window(light_distance, alight[i].radius, alight[i].smoothness);
+
+        vec3 light = compute_light(vec3(attenuation),
+                                   alight[i].color,
+                                   view_direction,
+                                   light_direction,
+                                   albedo,
+                                   normal,
+                                   roughness2,
+                                   metallic,
+                                   fresnel_base);
+        lighted += light;
+    }
+
+    vec3 ambient_light = probe_args.irradiance != 0 ? probe_irradiance(normal) : ambient_color;
+    vec3 ambient = ambient_light * albedo * ambient_occlusion;
+    if (probe_args.reflection != 0) {
+        float n_dot_v = max(dot(normal, view_direction), 0.0);
+        vec3 fresnel = fresnel_base + (max(vec3(1.0 - roughness), fresnel_base) - fresnel_base) * pow(1.0 - n_dot_v, 5.0);
+        ambient = ambient * (1.0 - fresnel) * (1.0 - metallic)
+            + probe_environment(reflected, roughness) * fresnel * ambient_occlusion;
+    }
     vec3 color = ambient + lighted + emission;
 
     out_color = vec4(color, alpha) * vertex.color;
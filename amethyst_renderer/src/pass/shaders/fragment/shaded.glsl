@@ -12,10 +12,14 @@ struct PointLight {
     vec3 color;
     float pad; // Workaround for bug in mac's implementation of opengl (loads garbage when accessing members of structures in arrays with dynamic indices).
     float intensity;
+    vec3 axis;
+    int profile;
+    float radius;
+    float smoothness;
 };
 
 layout (std140) uniform PointLights {
-    PointLight plight[128];
+    PointLight plight[192];
 };
 
 struct DirectionalLight {
@@ -30,6 +34,9 @@ layout (std140) uniform DirectionalLights {
 uniform vec3 ambient_color;
 uniform vec3 camera_position;
 
+uniform float alpha_cutoff;
+uniform float emission_intensity;
+
 uniform sampler2D albedo;
 uniform sampler2D emission;
 
@@ -61,11 +68,26 @@ vec2 tex_coords(vec2 coord, vec2 u, vec2 v) {
     return vec2(tex_coord(coord.x, u), tex_coord(coord.y, v));
 }
 
+// Windows the inverse square falloff, to reach zero at the radius of the light.
+float falloff_window(float distance, float radius, float smoothness) {
+    if (radius <= 0.0) {
+        return 1.0;
+    }
+    float window = clamp(1.0 - pow(distance / radius, smoothness), 0.0, 1.0);
+    return window * window;
+}
+
 void main() {
     vec4 color = texture(albedo, tex_coords(vertex.tex_coord, albedo_offset.u_offset, albedo_offset.v_offset));
+    if (color.a < alpha_cutoff) discard;
     vec4 ecolor = texture(emission, tex_coords(vertex.tex_coord, emission_offset.u_offset, emission_offset.v_offset));
+    ecolor.rgb *= emission_intensity;
     vec3 lighting = vec3(0.0);
     vec3 normal = normalize(vertex.normal);
+    // Light the back of two sided faces as if their normals pointed the other way.
+    if (!gl_FrontFacing) {
+        normal = -normal;
+    }
     for (uint i = 0u; i < point_light_count; i++) {
         // Calculate diffuse light
         vec3 light_dir = normalize(plight[i].position - vertex.position);
@@ -74,7 +96,7 @@ void main() {
         // Calculate attenuation
         vec3 dist = plight[i].position - vertex.position;
         float dist2 = dot(dist, dist);
-        float attenuation = (plight[i].intensity / dist2);
+        float attenuation = (plight[i].intensity / dist2) * falloff_window(sqrt(dist2), plight[i].radius, plight[i].smoothness);
         lighting += diffuse * attenuation;
     }
     for (uint i = 0u; i < directional_light_count; i++) {
@@ -0,0 +1,66 @@
+// Blends the last frame of the old state over the new frame.
+
+#version 150 core
+
+const int CROSSFADE = 0;
+const int FADE = 1;
+const int WIPE = 2;
+const int DISSOLVE = 3;
+
+layout (std140) uniform TransitionArgs {
+    vec4 color;
+    vec2 direction;
+    float progress;
+    float softness;
+    int kind;
+};
+
+uniform sampler2D previous;
+
+in VertexData {
+    vec2 tex_coord;
+} vertex;
+
+out vec4 out_color;
+
+float hash(vec2 p) {
+    return fract(sin(dot(p, vec2(12.9898, 78.233))) * 43758.5453);
+}
+
+// Value noise, between 0 and 1.
+float noise(vec2 p) {
+    vec2 i = floor(p);
+    vec2 f = fract(p);
+    vec2 s = f * f * (3.0 - 2.0 * f);
+    return mix(
+        mix(hash(i), hash(i + vec2(1.0, 0.0)), s.x),
+        mix(hash(i + vec2(0.0, 1.0)), hash(i + vec2(1.0, 1.0)), s.x),
+        s.y);
+}
+
+void main() {
+    // The rows of the copied frame start at the top.
+    vec3 last = texture(previous, vec2(vertex.tex_coord.x, 1.0 - vertex.tex_coord.y)).rgb;
+
+    if (kind == FADE) {
+        if (progress < 0.5) {
+            out_color = vec4(mix(last, color.rgb, progress * 2.0), 1.0);
+        } else {
+            out_color = vec4(color.rgb, 2.0 - progress * 2.0);
+        }
+    } else if (kind == WIPE) {
+        // From 0 where the edge starts to 1 where it stops, the new frame is behind it.
+        float along = dot(vertex.tex_coord - 0.5, direction) / (abs(direction.x) + abs(direction.y)) + 0.5;
+        float edge = mix(-softness, 1.0, progress);
+        out_color = vec4(last, smoothstep(edge, edge + softness, along));
+    } else if (kind == DISSOLVE) {
+        float n = 0.5 * noise(vertex.tex_coord * 12.0) + 0.5 * noise(vertex.tex_coord * 40.0);
+        float threshold = mix(-2.0 * softness, 1.0, progress);
+        float border = 1.0 - smoothstep(threshold + softness, threshold + 2.0 * softness, n);
+        out_color = vec4(
+            mix(last, color.rgb, border * color.a),
+            smoothstep(threshold, threshold + softness, n));
+    } else {
+        out_color = vec4(last, 1.0 - progress);
+    }
+}
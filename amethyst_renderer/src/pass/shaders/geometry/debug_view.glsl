@@ -0,0 +1,77 @@
+// Emits the triangle itself for the wireframe and texture coordinate views, and camera facing
+// quads along the vertex and face normals for the normals view.
+
+#version 150 core
+
+layout (std140) uniform VertexArgs {
+    uniform mat4 proj;
+    uniform mat4 view;
+    uniform mat4 model;
+    uniform vec4 color;
+};
+
+uniform int debug_mode;
+uniform vec3 camera_position;
+uniform float normal_length;
+uniform float line_width;
+
+in VertexData {
+    vec3 position;
+    vec3 normal;
+    vec2 tex_coord;
+} vertex_in[3];
+
+out VertexData {
+    vec2 tex_coord;
+    vec3 barycentric;
+    vec4 line_color;
+} vertex;
+
+layout (triangles) in;
+layout (triangle_strip, max_vertices = 19) out;
+
+void EmitLine(vec3 start, vec3 direction, vec4 color) {
+    vec3 cam_dir = normalize(start - camera_position);
+    vec3 width_vector = normalize(cross(cam_dir, direction)) * line_width * 0.5;
+    vec3 end = start + direction * normal_length;
+    vec3 corners[4] = vec3[4](
+        start - width_vector,
+        start + width_vector,
+        end - width_vector,
+        end + width_vector
+    );
+    for (int i = 0; i < 4; i++) {
+        vertex.tex_coord = vec2(0.0);
+        vertex.barycentric = vec3(1.0);
+        vertex.line_color = color;
+        gl_Position = proj * view * vec4(corners[i], 1.0);
+        EmitVertex();
+    }
+    EndPrimitive();
+}
+
+void main() {
+    if ((debug_mode & 13) != 0) {
+        for (int i = 0; i < 3; i++) {
+            vertex.tex_coord = vertex_in[i].tex_coord;
+            vertex.barycentric = vec3(0.0);
+            vertex.barycentric[i] = 1.0;
+            vertex.line_color = vec4(0.0);
+            gl_Position = proj * view * vec4(vertex_in[i].position, 1.0);
+            EmitVertex();
+        }
+        EndPrimitive();
+    }
+
+    if ((debug_mode & 2) != 0) {
+        for (int i = 0; i < 3; i++) {
+            EmitLine(vertex_in[i].position, vertex_in[i].normal, vec4(0.2, 0.4, 1.0, 1.0));
+        }
+        vec3 center = (vertex_in[0].position + vertex_in[1].position + vertex_in[2].position) / 3.0;
+        vec3 face_normal = normalize(cross(
+            vertex_in[1].position - vertex_in[0].position,
+            vertex_in[2].position - vertex_in[0].position
+        ));
+        EmitLine(center, face_normal, vec4(1.0, 0.9, 0.1, 1.0));
+    }
+}
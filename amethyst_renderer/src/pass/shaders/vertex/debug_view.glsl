@@ -0,0 +1,26 @@
+// Passes the world space mesh on to the debug view geometry shader.
+
+#version 150 core
+
+layout (std140) uniform VertexArgs {
+    uniform mat4 proj;
+    uniform mat4 view;
+    uniform mat4 model;
+    uniform vec4 color;
+};
+
+in vec3 position;
+in vec3 normal;
+in vec2 tex_coord;
+
+out VertexData {
+    vec3 position;
+    vec3 normal;
+    vec2 tex_coord;
+} vertex;
+
+void main() {
+    vertex.position = (model * vec4(position, 1.0)).xyz;
+    vertex.normal = normalize(mat3(model) * normal);
+    vertex.tex_coord = tex_coord;
+}
@@ -0,0 +1,33 @@
+// Grows the mesh along its normals into the shell of its glow.
+
+#version 150 core
+
+layout (std140) uniform VertexArgs {
+    uniform mat4 proj;
+    uniform mat4 view;
+    uniform mat4 model;
+    uniform vec4 color;
+};
+
+uniform float glow_radius;
+
+in vec3 position;
+in vec3 normal;
+in vec2 tex_coord;
+
+out VertexData {
+    vec3 position;
+    vec3 normal;
+    vec2 tex_coord;
+    vec4 color;
+} vertex;
+
+void main() {
+    vec3 world_normal = normalize(mat3(model) * normal);
+    vec4 vertex_position = model * vec4(position, 1.0) + vec4(world_normal * glow_radius, 0.0);
+    vertex.position = vertex_position.xyz;
+    vertex.normal = world_normal;
+    vertex.tex_coord = tex_coord;
+    vertex.color = color;
+    gl_Position = proj * view * vertex_position;
+}
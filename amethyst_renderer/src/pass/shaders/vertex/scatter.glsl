@@ -0,0 +1,51 @@
+// Places the vertices of the mesh of a scatter at its instances, swaying in the wind.
+
+#version 330 core
+
+layout (std140) uniform VertexArgs {
+    uniform mat4 proj;
+    uniform mat4 view;
+    uniform mat4 model;
+    uniform vec4 color;
+};
+
+// The direction of the wind, as long as the bend per unit of height.
+uniform vec3 wind;
+uniform float wind_frequency;
+uniform float time;
+
+in vec3 position;
+in vec3 normal;
+in vec2 tex_coord;
+in vec4 instance_position;
+in vec4 instance_rotation;
+
+out VertexData {
+    vec3 position;
+    vec3 normal;
+    vec3 tangent;
+    vec2 tex_coord;
+    vec4 color;
+} vertex;
+
+const float PI = 3.14159265359;
+
+vec3 rotate(vec4 q, vec3 v) {
+    return v + 2.0 * cross(q.xyz, cross(q.xyz, v) + q.w * v);
+}
+
+void main() {
+    float scale = instance_position.w;
+    vec3 local = rotate(instance_rotation, position * scale) + instance_position.xyz;
+    vec4 vertex_position = model * vec4(local, 1.0);
+    // The instances bend more the higher above their base, in waves rolling over the world.
+    float sway = 0.5 + 0.5 * sin(2.0 * PI * wind_frequency * time + dot(vertex_position.xz, vec2(0.37, 0.21)));
+    vertex_position.xyz += wind * sway * max(position.y, 0.0) * scale;
+
+    vertex.position = vertex_position.xyz;
+    vertex.normal = mat3(model) * rotate(instance_rotation, normal);
+    vertex.tangent = mat3(model) * rotate(instance_rotation, vec3(1.0, 0.0, 0.0));
+    vertex.tex_coord = tex_coord;
+    vertex.color = color;
+    gl_Position = proj * view * vertex_position;
+}
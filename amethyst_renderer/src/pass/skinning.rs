@@ -13,6 +13,7 @@ use crate::{
 };
 
 static VERT_SKIN_SRC: &[u8] = include_bytes!("shaders/vertex/skinned.glsl");
+static VERT_SKIN_COLOR_SRC: &[u8] = include_bytes!("shaders/vertex/skinned_color.glsl");
 static ATTRIBUTES: [Attributes<'static>; 2] = [
     Separate::<JointIds>::ATTRIBUTES,
     Separate::<JointWeights>::ATTRIBUTES,
@@ -20,9 +21,14 @@ static ATTRIBUTES: [Attributes<'static>; 2] = [
 
 pub(crate) fn create_skinning_effect<'a>(
     effect: NewEffect<'a>,
+    vertex_colors: bool,
     frag: &'a [u8],
 ) -> EffectBuilder<'a> {
-    effect.simple(VERT_SKIN_SRC, frag)
+    if vertex_colors {
+        effect.simple(VERT_SKIN_COLOR_SRC, frag)
+    } else {
+        effect.simple(VERT_SKIN_SRC, frag)
+    }
 }
 
 pub(crate) fn setup_skinning_buffers<'a>(builder: &mut EffectBuilder<'a>) {
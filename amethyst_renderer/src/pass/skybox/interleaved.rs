@@ -8,13 +8,14 @@ use amethyst_core::{
 
 use crate::{
     error::Result,
-    get_camera,
+    pass::util::PassCameras,
     pipe::{
         pass::{Pass, PassData},
         DepthMode, Effect, NewEffect,
     },
-    set_vertex_args, ActiveCamera, Camera, Encoder, Factory, Mesh, PosTex, Rgba, Shape,
-    VertexFormat,
+    set_vertex_args,
+    view_model::ViewModelCamera,
+    ActiveCamera, Camera, Encoder, Factory, Mesh, PosTex, Rgba, Shape, VertexFormat,
 };
 
 use gfx::pso::buffer::ElemStride;
@@ -48,6 +49,7 @@ impl<'a> PassData<'a> for DrawSkybox {
         Read<'a, ActiveCamera>,
         ReadStorage<'a, Camera>,
         ReadStorage<'a, GlobalTransform>,
+        ReadStorage<'a, ViewModelCamera>,
         Read<'a, SkyboxColor>,
     );
 }
@@ -78,9 +80,10 @@ impl Pass for DrawSkybox {
         encoder: &mut Encoder,
         effect: &mut Effect,
         mut _factory: Factory,
-        (active, camera, global, skybox_color): <Self as PassData<'a>>::Data,
+        (active, camera, global, view_models, skybox_color): <Self as PassData<'a>>::Data,
     ) {
-        let camera = get_camera(active, &camera, &global);
+        let cameras = PassCameras::new(&active, &camera, &global, &view_models);
+        let camera = cameras.main();
 
         let mesh = self
             .mesh
@@ -0,0 +1,120 @@
+//! Screen transition pass
+
+use gfx::pso::buffer::ElemStride;
+use gfx_core::state::ColorMask;
+use glsl_layout::*;
+
+use amethyst_core::specs::Read;
+
+use crate::{
+    error::Result,
+    mesh::Mesh,
+    pass::util::add_texture,
+    pipe::{
+        pass::{Pass, PassData},
+        Effect, NewEffect,
+    },
+    shape::Shape,
+    transition::{ScreenTransition, TransitionEffect, DISSOLVE_SOFTNESS, WIPE_SOFTNESS},
+    transparent::ALPHA,
+    types::{Encoder, Factory},
+    vertex::{PosTex, VertexFormat},
+};
+
+use super::{FRAG_SRC, VERT_SRC};
+
+#[derive(Clone, Copy, Debug, Uniform)]
+pub(crate) struct TransitionArgs {
+    color: vec4,
+    direction: vec2,
+    progress: float,
+    softness: float,
+    kind: int,
+}
+
+impl TransitionArgs {
+    fn new(effect: TransitionEffect, progress: f32) -> Self {
+        let (kind, color, direction, softness) = match effect {
+            TransitionEffect::Crossfade => (0, [0.0; 4], [0.0; 2], 0.0),
+            TransitionEffect::Fade(color) => (1, color.into(), [0.0; 2], 0.0),
+            TransitionEffect::Wipe(direction) => (2, [0.0; 4], direction.vector(), WIPE_SOFTNESS),
+            TransitionEffect::Dissolve(edge) => (3, edge.into(), [0.0; 2], DISSOLVE_SOFTNESS),
+        };
+        TransitionArgs {
+            color: color.into(),
+            direction: direction.into(),
+            progress,
+            softness,
+            kind,
+        }
+    }
+}
+
+/// Draws the running `ScreenTransition` over the screen.
+///
+/// Add it last, to the stage drawing to the screen, so it covers everything else.
+#[derive(Clone, Debug, Default)]
+pub struct DrawTransition {
+    mesh: Option<Mesh>,
+}
+
+impl DrawTransition {
+    /// Create instance of `DrawTransition` pass
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<'a> PassData<'a> for DrawTransition {
+    type Data = Read<'a, ScreenTransition>;
+}
+
+impl Pass for DrawTransition {
+    fn compile(&mut self, mut effect: NewEffect<'_>) -> Result<Effect> {
+        let verts = Shape::Plane(None).generate_vertices::<Vec<PosTex>>(None);
+        self.mesh = Some(Mesh::build(verts).build(&mut effect.factory)?);
+
+        effect
+            .simple(VERT_SRC, FRAG_SRC)
+            .without_back_face_culling()
+            .with_raw_constant_buffer(
+                "TransitionArgs",
+                std::mem::size_of::<<TransitionArgs as Uniform>::Std140>(),
+                1,
+            )
+            .with_raw_vertex_buffer(PosTex::ATTRIBUTES, PosTex::size() as ElemStride, 0)
+            .with_texture("previous")
+            .with_blended_output("color", ColorMask::all(), ALPHA, None)
+            .build()
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        _factory: Factory,
+        transition: <Self as PassData<'a>>::Data,
+    ) {
+        let (frame, progress) = match (transition.frame(), transition.progress()) {
+            (Some(frame), Some(progress)) => (frame, progress),
+            _ => return,
+        };
+        let mesh = self
+            .mesh
+            .as_ref()
+            .expect("Pass doesn't seem to be compiled.");
+
+        if let Some(vbuf) = mesh.buffer(PosTex::ATTRIBUTES) {
+            effect.data.vertex_bufs.push(vbuf.clone());
+        } else {
+            effect.clear();
+            return;
+        }
+
+        let args = TransitionArgs::new(transition.effect(), progress);
+        effect.update_constant_buffer("TransitionArgs", &args.std140(), encoder);
+        add_texture(effect, frame);
+        effect.draw(mesh.slice(), encoder);
+        effect.clear();
+    }
+}
@@ -0,0 +1,6 @@
+pub use self::interleaved::DrawTransition;
+
+mod interleaved;
+
+static VERT_SRC: &[u8] = include_bytes!("../shaders/vertex/transition.glsl");
+static FRAG_SRC: &[u8] = include_bytes!("../shaders/fragment/transition.glsl");
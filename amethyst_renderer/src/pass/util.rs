@@ -11,17 +11,23 @@ use amethyst_core::{
 
 use crate::{
     cam::{ActiveCamera, Camera},
+    layers::RenderLayers,
     mesh::Mesh,
-    mtl::{Material, MaterialDefaults, TextureOffset},
+    mtl::{
+        Material, MaterialDefaults, MaterialOverride, ShaderParams, TextureOffset,
+        MAX_SHADER_PARAMS,
+    },
     pass::set_skinning_buffers,
     pipe::{Effect, EffectBuilder},
     skinning::JointTransforms,
-    tex::Texture,
+    tex::{Texture, TextureHandle},
     types::Encoder,
     vertex::Attributes,
+    view_model::ViewModelCamera,
     Rgba,
 };
 
+#[derive(PartialEq)]
 pub(crate) enum TextureType {
     Albedo,
     Emission,
@@ -93,8 +99,13 @@ pub(crate) fn setup_textures(builder: &mut EffectBuilder<'_>, types: &[TextureTy
     use self::TextureType::*;
     for ty in types {
         match *ty {
-            Albedo => builder.with_texture("albedo"),
-            Emission => builder.with_texture("emission"),
+            // The alpha of the albedo map is compared to the material's cutoff.
+            Albedo => builder
+                .with_texture("albedo")
+                .with_raw_global("alpha_cutoff"),
+            Emission => builder
+                .with_texture("emission")
+                .with_raw_global("emission_intensity"),
             Normal => builder.with_texture("normal"),
             Metallic => builder.with_texture("metallic"),
             Roughness => builder.with_texture("roughness"),
@@ -110,37 +121,97 @@ pub(crate) fn add_textures(
     encoder: &mut Encoder,
     storage: &AssetStorage<Texture>,
     material: &Material,
+    overrides: Option<&MaterialOverride>,
     default: &Material,
     types: &[TextureType],
 ) {
-    use self::TextureType::*;
     for ty in types {
-        let texture = match *ty {
-            Albedo => storage
-                .get(&material.albedo)
-                .or_else(|| storage.get(&default.albedo)),
-            Emission => storage
-                .get(&material.emission)
-                .or_else(|| storage.get(&default.emission)),
-            Normal => storage
-                .get(&material.normal)
-                .or_else(|| storage.get(&default.normal)),
-            Metallic => storage
-                .get(&material.metallic)
-                .or_else(|| storage.get(&default.metallic)),
-            Roughness => storage
-                .get(&material.roughness)
-                .or_else(|| storage.get(&default.roughness)),
-            AmbientOcclusion => storage
-                .get(&material.ambient_occlusion)
-                .or_else(|| storage.get(&default.ambient_occlusion)),
-            Caveat => storage
-                .get(&material.caveat)
-                .or_else(|| storage.get(&default.caveat)),
-        };
+        let texture = storage
+            .get(material_texture(material, overrides, ty))
+            .or_else(|| storage.get(material_texture(default, None, ty)));
         add_texture(effect, texture.expect("Texture missing in asset storage"));
     }
-    set_texture_offsets(effect, encoder, material, types);
+    if types.contains(&TextureType::Albedo) {
+        effect.update_global("alpha_cutoff", material.alpha_cutoff);
+    }
+    if types.contains(&TextureType::Emission) {
+        let intensity = overrides
+            .and_then(|o| o.emission_intensity)
+            .unwrap_or(material.emission_intensity);
+        effect.update_global("emission_intensity", intensity);
+    }
+    set_texture_offsets(effect, encoder, material, overrides, types);
+}
+
+/// Returns the texture of the given type, from the overrides if they change it.
+fn material_texture<'a>(
+    material: &'a Material,
+    overrides: Option<&'a MaterialOverride>,
+    ty: &TextureType,
+) -> &'a TextureHandle {
+    use self::TextureType::*;
+    let (overridden, texture) = match *ty {
+        Albedo => (overrides.and_then(|o| o.albedo.as_ref()), &material.albedo),
+        Emission => (
+            overrides.and_then(|o| o.emission.as_ref()),
+            &material.emission,
+        ),
+        Normal => (overrides.and_then(|o| o.normal.as_ref()), &material.normal),
+        Metallic => (
+            overrides.and_then(|o| o.metallic.as_ref()),
+            &material.metallic,
+        ),
+        Roughness => (
+            overrides.and_then(|o| o.roughness.as_ref()),
+            &material.roughness,
+        ),
+        AmbientOcclusion => (
+            overrides.and_then(|o| o.ambient_occlusion.as_ref()),
+            &material.ambient_occlusion,
+        ),
+        Caveat => (overrides.and_then(|o| o.caveat.as_ref()), &material.caveat),
+    };
+    overridden.unwrap_or(texture)
+}
+
+/// Returns the texture offset of the given type, from the overrides if they change it.
+fn material_offset<'a>(
+    material: &'a Material,
+    overrides: Option<&'a MaterialOverride>,
+    ty: &TextureType,
+) -> &'a TextureOffset {
+    use self::TextureType::*;
+    let (overridden, offset) = match *ty {
+        Albedo => (
+            overrides.and_then(|o| o.albedo_offset.as_ref()),
+            &material.albedo_offset,
+        ),
+        Emission => (
+            overrides.and_then(|o| o.emission_offset.as_ref()),
+            &material.emission_offset,
+        ),
+        Normal => (
+            overrides.and_then(|o| o.normal_offset.as_ref()),
+            &material.normal_offset,
+        ),
+        Metallic => (
+            overrides.and_then(|o| o.metallic_offset.as_ref()),
+            &material.metallic_offset,
+        ),
+        Roughness => (
+            overrides.and_then(|o| o.roughness_offset.as_ref()),
+            &material.roughness_offset,
+        ),
+        AmbientOcclusion => (
+            overrides.and_then(|o| o.ambient_occlusion_offset.as_ref()),
+            &material.ambient_occlusion_offset,
+        ),
+        Caveat => (
+            overrides.and_then(|o| o.caveat_offset.as_ref()),
+            &material.caveat_offset,
+        ),
+    };
+    overridden.unwrap_or(offset)
 }
 
 pub(crate) fn setup_texture_offsets(builder: &mut EffectBuilder<'_>, types: &[TextureType]) {
@@ -190,47 +261,26 @@ pub(crate) fn set_texture_offsets(
     effect: &mut Effect,
     encoder: &mut Encoder,
     material: &Material,
+    overrides: Option<&MaterialOverride>,
     types: &[TextureType],
 ) {
     use self::TextureType::*;
     for ty in types {
-        match *ty {
-            Albedo => effect.update_constant_buffer(
-                "AlbedoOffset",
-                &TextureOffsetPod::from_offset(&material.albedo_offset).std140(),
-                encoder,
-            ),
-            Emission => effect.update_constant_buffer(
-                "EmissionOffset",
-                &TextureOffsetPod::from_offset(&material.emission_offset).std140(),
-                encoder,
-            ),
-            Normal => effect.update_constant_buffer(
-                "NormalOffset",
-                &TextureOffsetPod::from_offset(&material.normal_offset).std140(),
-                encoder,
-            ),
-            Metallic => effect.update_constant_buffer(
-                "MetallicOffset",
-                &TextureOffsetPod::from_offset(&material.metallic_offset).std140(),
-                encoder,
-            ),
-            Roughness => effect.update_constant_buffer(
-                "RoughnessOffset",
-                &TextureOffsetPod::from_offset(&material.roughness_offset).std140(),
-                encoder,
-            ),
-            AmbientOcclusion => effect.update_constant_buffer(
-                "AmbientOcclusionOffset",
-                &TextureOffsetPod::from_offset(&material.ambient_occlusion_offset).std140(),
-                encoder,
-            ),
-            Caveat => effect.update_constant_buffer(
-                "CaveatOffset",
-                &TextureOffsetPod::from_offset(&material.caveat_offset).std140(),
-                encoder,
-            ),
+        let name = match *ty {
+            Albedo => "AlbedoOffset",
+            Emission => "EmissionOffset",
+            Normal => "NormalOffset",
+            Metallic => "MetallicOffset",
+            Roughness => "RoughnessOffset",
+            AmbientOcclusion => "AmbientOcclusionOffset",
+            Caveat => "CaveatOffset",
         };
+        let offset = material_offset(material, overrides, ty);
+        effect.update_constant_buffer(
+            name,
+            &TextureOffsetPod::from_offset(offset).std140(),
+            encoder,
+        );
     }
 }
 
@@ -281,6 +331,29 @@ pub fn set_vertex_args(
     effect.update_constant_buffer("VertexArgs", &vertex_args.std140(), encoder);
 }
 
+/// Adds the `MaterialParams` uniform block of the `params` of materials to a custom pass.
+pub fn setup_material_params(builder: &mut EffectBuilder<'_>) {
+    builder.with_raw_constant_buffer(
+        "MaterialParams",
+        mem::size_of::<[f32; 4]>(),
+        MAX_SHADER_PARAMS,
+    );
+}
+
+/// Sets the `MaterialParams` of a material, with the values of the `ShaderParams` of the
+/// entity replacing the ones of the material.
+pub fn set_material_params(
+    effect: &mut Effect,
+    encoder: &mut Encoder,
+    material: &Material,
+    params: Option<&ShaderParams>,
+) {
+    let block = material.params.std140(params);
+    if !block.is_empty() {
+        effect.update_buffer("MaterialParams", &block[..], encoder);
+    }
+}
+
 pub fn set_view_args(
     effect: &mut Effect,
     encoder: &mut Encoder,
@@ -318,6 +391,7 @@ pub(crate) fn draw_mesh(
     joint: Option<&JointTransforms>,
     tex_storage: &AssetStorage<Texture>,
     material: Option<&Material>,
+    overrides: Option<&MaterialOverride>,
     material_defaults: &MaterialDefaults,
     rgba: Option<&Rgba>,
     camera: Option<(&Camera, &GlobalTransform)>,
@@ -339,13 +413,15 @@ pub(crate) fn draw_mesh(
         return;
     }
 
+    let rgba = rgba.cloned().unwrap_or(Rgba::WHITE);
     set_vertex_args(
         effect,
         encoder,
         camera,
         global,
-        rgba.cloned().unwrap_or(Rgba::WHITE),
+        overrides.map_or(rgba, |overrides| overrides.tinted(rgba)),
     );
+    effect.set_cull_face(material.cull_mode.into());
 
     if skinning {
         if let Some(joint) = joint {
@@ -358,6 +434,7 @@ pub(crate) fn draw_mesh(
         encoder,
         &tex_storage,
         material,
+        overrides,
         &material_defaults.0,
         textures,
     );
@@ -366,6 +443,99 @@ pub(crate) fn draw_mesh(
     effect.clear();
 }
 
+/// Returns the render layers seen by the camera, or all layers if there is no camera.
+pub(crate) fn camera_layers(camera: Option<(&Camera, &GlobalTransform)>) -> RenderLayers {
+    camera
+        .map(|(camera, _)| camera.layers)
+        .unwrap_or_else(RenderLayers::all)
+}
+
+/// The cameras a pass draws with: the main camera, and the view model camera if the main camera
+/// has a `ViewModelCamera`.
+pub(crate) struct PassCameras<'a> {
+    main: Option<(Camera, &'a GlobalTransform)>,
+    view_model: Option<(Camera, RenderLayers)>,
+}
+
+impl<'a> PassCameras<'a> {
+    pub(crate) fn new(
+        active: &ActiveCamera,
+        camera: &'a ReadStorage<'a, Camera>,
+        global: &'a ReadStorage<'a, GlobalTransform>,
+        view_models: &'a ReadStorage<'a, ViewModelCamera>,
+    ) -> Self {
+        let found = active
+            .entity
+            .and_then(|entity| {
+                camera
+                    .get(entity)
+                    .into_iter()
+                    .zip(global.get(entity))
+                    .next()
+                    .map(|(camera, global)| (camera, global, view_models.get(entity)))
+            })
+            .or_else(|| (camera, global, view_models.maybe()).join().next());
+        match found {
+            Some((camera, global, Some(view_model))) => {
+                let aspect = camera.proj[(1, 1)] / camera.proj[(0, 0)];
+                PassCameras {
+                    main: Some((
+                        Camera {
+                            proj: ViewModelCamera::scene_projection(&camera.proj),
+                            layers: camera.layers,
+                        },
+                        global,
+                    )),
+                    view_model: Some((
+                        Camera {
+                            proj: view_model.projection(aspect),
+                            layers: view_model.layers,
+                        },
+                        view_model.layers,
+                    )),
+                }
+            }
+            Some((camera, global, None)) => PassCameras {
+                main: Some((camera.clone(), global)),
+                view_model: None,
+            },
+            None => PassCameras {
+                main: None,
+                view_model: None,
+            },
+        }
+    }
+
+    /// Returns the main camera, with its depth behind the view models if it has a
+    /// `ViewModelCamera`, for the passes that don't draw view models.
+    pub(crate) fn main(&self) -> Option<(&Camera, &GlobalTransform)> {
+        self.main
+            .as_ref()
+            .map(|&(ref camera, global)| (camera, global))
+    }
+
+    /// Returns the camera to draw an entity on the given layers with, `Some(None)` to draw it
+    /// without camera, or `None` if the entity isn't visible.
+    pub(crate) fn select(
+        &self,
+        layers: Option<&RenderLayers>,
+    ) -> Option<Option<(&Camera, &GlobalTransform)>> {
+        let main = self.main();
+        if let (Some((view_model, view_model_layers)), Some((_, global))) =
+            (self.view_model.as_ref(), main)
+        {
+            if view_model_layers.sees(layers) {
+                return Some(Some((view_model, global)));
+            }
+        }
+        if camera_layers(main).sees(layers) {
+            Some(main)
+        } else {
+            None
+        }
+    }
+}
+
 /// Returns the main camera and its `GlobalTransform`
 pub fn get_camera<'a>(
     active: Read<'a, ActiveCamera>,
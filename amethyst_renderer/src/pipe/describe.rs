@@ -0,0 +1,201 @@
+//! Descriptions of the structure of a pipeline, to understand and debug a render setup.
+
+use std::fmt::Write;
+
+use gfx::format::Formatted;
+
+use crate::{
+    pipe::Target,
+    types::{ColorFormat, DepthFormat},
+};
+
+/// A render target of a `PipelineDescription`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TargetDescription {
+    /// The name of the target, empty for the backbuffer.
+    pub name: String,
+    /// The width and height of the target, in pixels.
+    pub size: (u32, u32),
+    /// The number of color buffers.
+    pub color_buffers: usize,
+    /// The format of the color buffers.
+    pub color_format: String,
+    /// The format of the depth buffer, if the target has one.
+    pub depth_format: Option<String>,
+}
+
+impl TargetDescription {
+    pub(crate) fn new(name: &str, target: &Target) -> Self {
+        let format = |format: gfx::format::Format| format!("{:?} {:?}", format.0, format.1);
+        TargetDescription {
+            name: name.to_owned(),
+            size: target.size(),
+            color_buffers: target.color_bufs().len(),
+            color_format: format(ColorFormat::get_format()),
+            depth_format: target
+                .depth_buf()
+                .map(|_| format(DepthFormat::get_format())),
+        }
+    }
+
+    fn label(&self) -> &str {
+        if self.name.is_empty() {
+            "backbuffer"
+        } else {
+            &self.name
+        }
+    }
+}
+
+/// A pass of a `StageDescription`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PassDescription {
+    /// The name of the pass, its type by default.
+    pub name: String,
+    /// The targets the pass reads, drawn by earlier stages.
+    pub inputs: Vec<String>,
+}
+
+/// A stage of a `PipelineDescription`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct StageDescription {
+    /// The name of the target the stage draws into, empty for the backbuffer.
+    pub target: String,
+    /// Whether the stage is enabled.
+    pub enabled: bool,
+    /// The color the target is cleared to before the passes.
+    pub clear_color: Option<[f32; 4]>,
+    /// The depth the target is cleared to before the passes.
+    pub clear_depth: Option<f32>,
+    /// The passes of the stage, in the order they are applied.
+    pub passes: Vec<PassDescription>,
+}
+
+/// The structure of a pipeline: its targets, and its stages with their passes, in the order
+/// they're applied.
+///
+/// Returned by `PolyPipeline::describe`, or written to a file with a `PipelineDump`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct PipelineDescription {
+    /// The targets, the backbuffer first and the others by name.
+    pub targets: Vec<TargetDescription>,
+    /// The stages, in the order they're applied.
+    pub stages: Vec<StageDescription>,
+}
+
+impl PipelineDescription {
+    /// Returns the description as a Graphviz graph, with the passes of each stage in a cluster,
+    /// arrows from the passes to the targets they draw and dashed ones from the targets to the
+    /// passes reading them.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        // Writing to a `String` can't fail.
+        let _ = self.write_dot(&mut dot);
+        dot
+    }
+
+    fn write_dot(&self, dot: &mut String) -> std::fmt::Result {
+        writeln!(dot, "digraph pipeline {{")?;
+        writeln!(dot, "    rankdir=LR;")?;
+        writeln!(dot, "    node [shape=box];")?;
+        for target in &self.targets {
+            let mut label = format!(
+                "{}\\n{}x{}\\n{} x {}",
+                target.label(),
+                target.size.0,
+                target.size.1,
+                target.color_buffers,
+                target.color_format
+            );
+            if let Some(ref depth) = target.depth_format {
+                write!(label, "\\ndepth {}", depth)?;
+            }
+            writeln!(
+                dot,
+                "    \"target:{}\" [shape=folder, label=\"{}\"];",
+                target.name, label
+            )?;
+        }
+        for (s, stage) in self.stages.iter().enumerate() {
+            writeln!(dot, "    subgraph cluster_{} {{", s)?;
+            let disabled = if stage.enabled { "" } else { " (disabled)" };
+            let target = self
+                .targets
+                .iter()
+                .find(|target| target.name == stage.target)
+                .map_or(stage.target.as_str(), TargetDescription::label);
+            writeln!(
+                dot,
+                "        label=\"stage {}: {}{}\";",
+                s, target, disabled
+            )?;
+            for (p, pass) in stage.passes.iter().enumerate() {
+                writeln!(dot, "        \"{}.{}\" [label=\"{}\"];", s, p, pass.name)?;
+                if p > 0 {
+                    writeln!(dot, "        \"{}.{}\" -> \"{}.{}\";", s, p - 1, s, p)?;
+                }
+            }
+            writeln!(dot, "    }}")?;
+            for (p, pass) in stage.passes.iter().enumerate() {
+                writeln!(dot, "    \"{}.{}\" -> \"target:{}\";", s, p, stage.target)?;
+                for input in &pass.inputs {
+                    writeln!(
+                        dot,
+                        "    \"target:{}\" -> \"{}.{}\" [style=dashed];",
+                        input, s, p
+                    )?;
+                }
+            }
+        }
+        writeln!(dot, "}}")
+    }
+
+    /// Returns the description as JSON.
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dot_links_passes_to_their_targets() {
+        let target = |name: &str| TargetDescription {
+            name: name.to_owned(),
+            size: (800, 600),
+            color_buffers: 1,
+            color_format: "R8_G8_B8_A8 Unorm".to_owned(),
+            depth_format: None,
+        };
+        let stage = |target: &str, passes: Vec<PassDescription>| StageDescription {
+            target: target.to_owned(),
+            enabled: true,
+            clear_color: None,
+            clear_depth: None,
+            passes,
+        };
+        let pass = |name: &str, inputs: &[&str]| PassDescription {
+            name: name.to_owned(),
+            inputs: inputs.iter().map(|input| input.to_string()).collect(),
+        };
+        let description = PipelineDescription {
+            targets: vec![target(""), target("scene")],
+            stages: vec![
+                stage("scene", vec![pass("DrawShaded", &[])]),
+                stage(
+                    "",
+                    vec![pass("DrawColorFilter", &["scene"]), pass("DrawUi", &[])],
+                ),
+            ],
+        };
+
+        let dot = description.to_dot();
+        assert!(dot.contains("label=\"stage 1: backbuffer\""));
+        assert!(dot.contains("\"0.0\" -> \"target:scene\";"));
+        assert!(dot.contains("\"target:scene\" -> \"1.0\" [style=dashed];"));
+        assert!(dot.contains("\"1.0\" -> \"1.1\";"));
+    }
+}
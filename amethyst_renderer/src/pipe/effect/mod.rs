@@ -71,6 +71,12 @@ pub struct Effect {
     pub data: Data,
     const_bufs: HashMap<String, usize>,
     globals: HashMap<String, usize>,
+    /// Variants of `pso` culling other faces, built by `with_cull_face_variants`.
+    cull_variants: Vec<(CullFace, PipelineState<Meta>)>,
+    /// Index of the variant drawn with, `pso` if `None`.
+    cull_variant: Option<usize>,
+    /// Number of textures which stay bound when the effect is cleared, see `keep_textures`.
+    kept_textures: usize,
 }
 
 impl Effect {
@@ -135,13 +141,40 @@ impl Effect {
     }
 
     pub fn clear(&mut self) {
+        self.data.textures.truncate(self.kept_textures);
+        self.data.samplers.truncate(self.kept_textures);
+        self.data.vertex_bufs.clear();
+    }
+
+    /// Keeps the textures added so far bound when the effect is cleared after a draw, e.g. the
+    /// textures of the lights, which are the same for every mesh. They have to come first in
+    /// the textures the effect was built with.
+    pub fn keep_textures(&mut self) {
+        self.kept_textures = self.data.textures.len();
+    }
+
+    /// Unbinds all textures, including the kept ones.
+    pub fn clear_textures(&mut self) {
+        self.kept_textures = 0;
         self.data.textures.clear();
         self.data.samplers.clear();
-        self.data.vertex_bufs.clear();
+    }
+
+    /// Sets the faces culled by the following draws, if the effect was built
+    /// `with_cull_face_variants`, otherwise the faces it was built with stay culled.
+    pub fn set_cull_face(&mut self, cull_face: CullFace) {
+        self.cull_variant = self
+            .cull_variants
+            .iter()
+            .position(|&(variant, _)| variant == cull_face);
     }
 
     pub fn draw(&mut self, slice: &Slice, enc: &mut Encoder) {
-        enc.draw(&slice, &self.pso, &self.data);
+        let pso = match self.cull_variant {
+            Some(i) => &self.cull_variants[i].1,
+            None => &self.pso,
+        };
+        enc.draw(&slice, pso, &self.data);
     }
 }
 
@@ -183,6 +216,7 @@ pub struct EffectBuilder<'a> {
     prim: Primitive,
     prog: ProgramSource<'a>,
     rast: Rasterizer,
+    cull_variants: bool,
     const_bufs: Vec<BufferInfo>,
 }
 
@@ -203,6 +237,7 @@ impl<'a> EffectBuilder<'a> {
             init: Init::default(),
             prim: Primitive::TriangleList,
             rast,
+            cull_variants: false,
             prog: src,
             const_bufs: Vec::new(),
         }
@@ -214,6 +249,13 @@ impl<'a> EffectBuilder<'a> {
         self
     }
 
+    /// Also builds pipeline states culling the other faces, to switch between per draw with
+    /// `Effect::set_cull_face`.
+    pub fn with_cull_face_variants(&mut self) -> &mut Self {
+        self.cull_variants = true;
+        self
+    }
+
     /// Adds a global constant to this `Effect`.
     pub fn with_raw_global(&mut self, name: &'a str) -> &mut Self {
         self.init.globals.push(name);
@@ -336,6 +378,18 @@ impl<'a> EffectBuilder<'a> {
 
         debug!("Creating pipeline state");
         let pso = fac.create_pipeline_state(&prog, self.prim, self.rast, self.init.clone())?;
+        let mut cull_variants = Vec::new();
+        if self.cull_variants {
+            for &cull_face in &[CullFace::Back, CullFace::Front, CullFace::Nothing] {
+                if cull_face != self.rast.cull_face {
+                    let mut rast = self.rast;
+                    rast.cull_face = cull_face;
+                    let variant =
+                        fac.create_pipeline_state(&prog, self.prim, rast, self.init.clone())?;
+                    cull_variants.push((cull_face, variant));
+                }
+            }
+        }
         let mut data = Data::default();
 
         debug!("Creating raw constant buffers");
@@ -391,6 +445,9 @@ impl<'a> EffectBuilder<'a> {
             data,
             const_bufs,
             globals,
+            cull_variants,
+            cull_variant: None,
+            kept_textures: 0,
         })
     }
 }
@@ -17,6 +17,7 @@
 //! ```
 
 pub use self::{
+    describe::{PassDescription, PipelineDescription, StageDescription, TargetDescription},
     effect::{Data, DepthMode, Effect, EffectBuilder, Init, Meta, NewEffect},
     pipe::{Pipeline, PipelineBuild, PipelineBuilder, PipelineData, PolyPipeline, PolyStages},
     stage::{PolyStage, Stage, StageBuilder},
@@ -25,6 +26,7 @@ pub use self::{
 
 pub mod pass;
 
+mod describe;
 mod effect;
 mod pipe;
 mod stage;
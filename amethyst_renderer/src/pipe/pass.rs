@@ -4,7 +4,7 @@ use amethyst_core::specs::prelude::SystemData;
 
 use crate::{
     error::Result,
-    pipe::{Effect, NewEffect, Target},
+    pipe::{Effect, NewEffect, PassDescription, Target, Targets},
     types::{Encoder, Factory},
 };
 
@@ -29,6 +29,25 @@ pub trait Pass: for<'a> PassData<'a> {
         factory: Factory,
         data: <Self as PassData<'b>>::Data,
     );
+
+    /// Called with all the targets of the pipeline after `compile`, and again whenever they're
+    /// recreated, so the pass can read the targets drawn by an earlier stage.
+    fn input_targets(&mut self, _targets: &Targets) {}
+
+    /// Returns the name of the pass in the `PipelineDescription`, its type by default when building
+    /// with Rust 1.38 or later, and `"unnamed pass"` before.
+    fn name(&self) -> &'static str {
+        #[cfg(type_name)]
+        return std::any::type_name::<Self>();
+        #[cfg(not(type_name))]
+        return "unnamed pass";
+    }
+
+    /// Returns the names of the targets the pass reads in `input_targets`, for the
+    /// `PipelineDescription`.
+    fn inputs(&self) -> Vec<String> {
+        Vec::new()
+    }
 }
 
 /// A compiled pass.  These are created and managed by the `Renderer`.  This should not be
@@ -47,9 +66,11 @@ where
         mut pass: P,
         fac: &mut Factory,
         out: &Target,
+        targets: &Targets,
         multisampling: u16,
     ) -> Result<Self> {
         let effect = pass.compile(NewEffect::new(fac, out, multisampling))?;
+        pass.input_targets(targets);
         Ok(CompiledPass {
             effect,
             inner: pass,
@@ -70,8 +91,24 @@ impl<P> CompiledPass<P> {
         self.inner.apply(encoder, &mut self.effect, factory, data)
     }
 
+    /// Describes the inner pass.
+    pub fn describe(&self) -> PassDescription
+    where
+        P: Pass,
+    {
+        PassDescription {
+            name: self.inner.name().to_owned(),
+            inputs: self.inner.inputs(),
+        }
+    }
+
     /// Distributes new target data to the pass.
-    pub fn new_target(&mut self, target: &Target) {
+    pub fn new_target(&mut self, target: &Target, targets: &Targets)
+    where
+        P: Pass,
+    {
+        self.inner.input_targets(targets);
+
         // Distribute new targets that don't blend.
         self.effect.data.out_colors.clear();
         self.effect
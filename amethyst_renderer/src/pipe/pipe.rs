@@ -8,7 +8,7 @@ use crate::{
     types::{Encoder, Factory},
 };
 
-use super::{stage::*, target::*};
+use super::{describe::*, stage::*, target::*};
 
 /// Defines how the rendering pipeline should be configured.
 #[derive(Clone, Debug)]
@@ -49,6 +49,9 @@ pub trait PolyStages: for<'a> StagesData<'a> {
 
     /// Distributes new targets
     fn new_targets(&mut self, new_targets: &HashMap<String, Target>);
+
+    /// Adds the descriptions of the stages
+    fn describe(&self, stages: &mut Vec<StageDescription>);
 }
 
 impl<'a, HS> StagesData<'a> for List<(HS, List<()>)>
@@ -76,6 +79,11 @@ where
         let List((ref mut hs, _)) = *self;
         HS::new_targets(hs, new_targets);
     }
+
+    fn describe(&self, stages: &mut Vec<StageDescription>) {
+        let List((ref hs, _)) = *self;
+        stages.push(hs.describe());
+    }
 }
 
 impl<'a, HS, TS> StagesData<'a> for List<(HS, TS)>
@@ -107,6 +115,12 @@ where
         HS::new_targets(hs, new_targets);
         TS::new_targets(ts, new_targets);
     }
+
+    fn describe(&self, stages: &mut Vec<StageDescription>) {
+        let List((ref hs, ref ts)) = *self;
+        stages.push(hs.describe());
+        ts.describe(stages);
+    }
 }
 
 /// The data requested from the `specs::World` by the Pipeline.
@@ -130,6 +144,9 @@ pub trait PolyPipeline: for<'a> PipelineData<'a> {
 
     /// Returns an immutable reference to all targets and their name strings.
     fn targets(&self) -> &HashMap<String, Target>;
+
+    /// Describes the targets, stages and passes, e.g. to dump them with a `PipelineDump`.
+    fn describe(&self) -> PipelineDescription;
 }
 
 impl<'a, L> PipelineData<'a> for Pipeline<L>
@@ -161,6 +178,18 @@ where
     fn targets(&self) -> &HashMap<String, Target> {
         self.targets()
     }
+
+    fn describe(&self) -> PipelineDescription {
+        let mut targets = self
+            .targets
+            .iter()
+            .map(|(name, target)| TargetDescription::new(name, target))
+            .collect::<Vec<_>>();
+        targets.sort_by(|a, b| a.name.cmp(&b.name));
+        let mut stages = Vec::new();
+        self.stages.describe(&mut stages);
+        PipelineDescription { targets, stages }
+    }
 }
 
 /// Constructs a new pipeline with the given render targets and layers.
@@ -9,7 +9,7 @@ use crate::{
     error::{Error, Result},
     pipe::{
         pass::{CompiledPass, Pass, PassData},
-        Target, Targets,
+        PassDescription, StageDescription, Target, Targets,
     },
     types::{Encoder, Factory},
 };
@@ -67,7 +67,10 @@ pub trait Passes: for<'a> PassesData<'a> {
     );
 
     /// Distributes new targets
-    fn new_target(&mut self, new_target: &Target);
+    fn new_target(&mut self, new_target: &Target, targets: &Targets);
+
+    /// Adds the descriptions of the passes
+    fn describe(&self, passes: &mut Vec<PassDescription>);
 }
 
 impl<'a, HP> PassesData<'a> for List<(CompiledPass<HP>, List<()>)>
@@ -91,9 +94,14 @@ where
         hp.apply(encoder, factory, hd);
     }
 
-    fn new_target(&mut self, new_target: &Target) {
+    fn new_target(&mut self, new_target: &Target, targets: &Targets) {
         let List((ref mut hp, _)) = *self;
-        hp.new_target(new_target);
+        hp.new_target(new_target, targets);
+    }
+
+    fn describe(&self, passes: &mut Vec<PassDescription>) {
+        let List((ref hp, _)) = *self;
+        passes.push(hp.describe());
     }
 }
 
@@ -121,10 +129,16 @@ where
         tp.apply(encoder, factory, td);
     }
 
-    fn new_target(&mut self, new_target: &Target) {
+    fn new_target(&mut self, new_target: &Target, targets: &Targets) {
         let List((ref mut hp, ref mut tp)) = *self;
-        hp.new_target(new_target);
-        tp.new_target(new_target);
+        hp.new_target(new_target, targets);
+        tp.new_target(new_target, targets);
+    }
+
+    fn describe(&self, passes: &mut Vec<PassDescription>) {
+        let List((ref hp, ref tp)) = *self;
+        passes.push(hp.describe());
+        tp.describe(passes);
     }
 }
 
@@ -145,6 +159,9 @@ pub trait PolyStage: for<'a> StageData<'a> {
 
     /// Distributes new targets
     fn new_targets(&mut self, new_targets: &HashMap<String, Target>);
+
+    /// Describes the stage and its passes.
+    fn describe(&self) -> StageDescription;
 }
 
 impl<'a, L> StageData<'a> for Stage<L>
@@ -179,13 +196,25 @@ where
         match new_targets.get(&self.target_name) {
             Some(target) => {
                 self.target = target.clone();
-                self.passes.new_target(target);
+                self.passes.new_target(target, new_targets);
             }
             None => {
                 error!("Target name {:?} not found!", self.target_name);
             }
         }
     }
+
+    fn describe(&self) -> StageDescription {
+        let mut passes = Vec::new();
+        self.passes.describe(&mut passes);
+        StageDescription {
+            target: self.target_name.clone(),
+            enabled: self.enabled,
+            clear_color: self.clear_color,
+            clear_depth: self.clear_depth,
+            passes,
+        }
+    }
 }
 
 /// Constructs a new rendering stage.
@@ -213,7 +242,7 @@ impl StageBuilder<Queue<()>> {
 }
 
 impl<Q> StageBuilder<Q> {
-    /// Clears the stage's target.
+    /// Clears the stage's target. The color is linear, like an `Rgba`.
     pub fn clear_target<R, C, D>(mut self, color_val: C, depth_val: D) -> Self
     where
         R: Into<[f32; 4]>,
@@ -253,7 +282,7 @@ impl<Q> StageBuilder<Q> {
         let passes = self
             .passes
             .into_list()
-            .fmap(CompilePass::new(fac, &out, multisampling))
+            .fmap(CompilePass::new(fac, &out, targets, multisampling))
             .r#try()?;
 
         Ok(Stage {
@@ -283,14 +312,21 @@ impl<Q> StageBuilder<Queue<Q>> {
 pub struct CompilePass<'a> {
     factory: &'a mut Factory,
     target: &'a Target,
+    targets: &'a Targets,
     multisampling: u16,
 }
 
 impl<'a> CompilePass<'a> {
-    fn new(factory: &'a mut Factory, target: &'a Target, multisampling: u16) -> Self {
+    fn new(
+        factory: &'a mut Factory,
+        target: &'a Target,
+        targets: &'a Targets,
+        multisampling: u16,
+    ) -> Self {
         CompilePass {
             factory,
             target,
+            targets,
             multisampling,
         }
     }
@@ -302,7 +338,13 @@ where
 {
     type Output = Result<CompiledPass<P>>;
     fn call_once(self, (pass,): (P,)) -> Result<CompiledPass<P>> {
-        CompiledPass::compile(pass, self.factory, self.target, self.multisampling)
+        CompiledPass::compile(
+            pass,
+            self.factory,
+            self.target,
+            self.targets,
+            self.multisampling,
+        )
     }
 }
 impl<'a, P> HetFnMut<(P,)> for CompilePass<'a>
@@ -310,6 +352,12 @@ where
     P: Pass,
 {
     fn call_mut(&mut self, (pass,): (P,)) -> Result<CompiledPass<P>> {
-        CompiledPass::compile(pass, self.factory, self.target, self.multisampling)
+        CompiledPass::compile(
+            pass,
+            self.factory,
+            self.target,
+            self.targets,
+            self.multisampling,
+        )
     }
 }
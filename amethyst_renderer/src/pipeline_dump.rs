@@ -0,0 +1,75 @@
+//! Dumps of the structure of the pipeline to files.
+
+use std::path::{Path, PathBuf};
+
+use crate::pipe::PipelineDescription;
+
+/// Resource used to write the structure of the pipeline to a file.
+///
+/// Call `request` and the `RenderSystem` will write the `PipelineDescription` of its pipeline on
+/// the next frame: as JSON when the path ends in `.json`, which needs the `json` feature, and as a
+/// Graphviz graph otherwise. The last description is also kept for `description`.
+#[derive(Debug, Default)]
+pub struct PipelineDump {
+    requests: Vec<PathBuf>,
+    latest: Option<PipelineDescription>,
+}
+
+impl PipelineDump {
+    /// Requests that the pipeline is written to the file at `path` on the next frame.
+    pub fn request<P: Into<PathBuf>>(&mut self, path: P) {
+        self.requests.push(path.into());
+    }
+
+    /// Returns true if a dump was requested but has not been written yet.
+    pub fn is_pending(&self) -> bool {
+        !self.requests.is_empty()
+    }
+
+    /// Returns the description of the pipeline written by the last dump.
+    pub fn description(&self) -> Option<&PipelineDescription> {
+        self.latest.as_ref()
+    }
+
+    pub(crate) fn complete(&mut self, description: PipelineDescription) {
+        for path in self.requests.drain(..) {
+            let contents = match encode(&description, &path) {
+                Some(contents) => contents,
+                None => continue,
+            };
+            match std::fs::write(&path, contents) {
+                Ok(()) => info!("Wrote the pipeline to {:?}", path),
+                Err(e) => error!("Failed to write the pipeline to {:?}: {}", path, e),
+            }
+        }
+        self.latest = Some(description);
+    }
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension()
+        .map_or(false, |extension| extension == "json")
+}
+
+#[cfg(feature = "json")]
+fn encode(description: &PipelineDescription, path: &Path) -> Option<String> {
+    if !is_json(path) {
+        return Some(description.to_dot());
+    }
+    description
+        .to_json()
+        .map_err(|e| error!("Failed to encode the pipeline as JSON: {}", e))
+        .ok()
+}
+
+#[cfg(not(feature = "json"))]
+fn encode(description: &PipelineDescription, path: &Path) -> Option<String> {
+    if is_json(path) {
+        error!(
+            "Can't write the pipeline to {:?}, JSON needs the `json` feature",
+            path
+        );
+        return None;
+    }
+    Some(description.to_dot())
+}
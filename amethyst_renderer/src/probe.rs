@@ -0,0 +1,813 @@
+//! Reflection probes and irradiance grids baked from the scene, lighting the PBM passes.
+//!
+//! A `ProbeBake` resource makes the `ProbeBakeSystem` capture the scene around each probe and
+//! grid point with the active camera, six frames each, and save the prefiltered results as a
+//! `BakedProbes` asset. Shipped levels load the asset and light `DrawPbm` with it through the
+//! `SceneProbes` resource.
+
+use std::{
+    cmp::Ordering,
+    f32::consts::PI,
+    fs,
+    io::{Error as IoError, ErrorKind as IoErrorKind, Result as IoResult},
+    path::{Path, PathBuf},
+};
+
+use amethyst_assets::{Asset, Handle, ProcessingState, Result as AssetsResult};
+use amethyst_core::{
+    nalgebra::Vector3,
+    specs::prelude::{
+        Entities, Entity, Join, Read, ReadExpect, System, VecStorage, Write, WriteStorage,
+    },
+    Transform,
+};
+
+use crate::{
+    cam::{ActiveCamera, Camera, Projection},
+    capture::{CapturedFrame, FrameCapture},
+    resources::ScreenDimensions,
+};
+
+/// Forward and up directions of the faces of a capture, in the order +X, -X, +Y, -Y, +Z, -Z.
+const FACES: [([f32; 3], [f32; 3]); 6] = [
+    ([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ([-1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ([0.0, 1.0, 0.0], [0.0, 0.0, 1.0]),
+    ([0.0, -1.0, 0.0], [0.0, 0.0, -1.0]),
+    ([0.0, 0.0, 1.0], [0.0, 1.0, 0.0]),
+    ([0.0, 0.0, -1.0], [0.0, 1.0, 0.0]),
+];
+/// Size of the faces the rough levels of the environments are filtered from.
+const FILTER_SIZE: usize = 16;
+/// Frames waited after moving the camera, for its transform to reach the renderer.
+const SETTLE_FRAMES: u32 = 2;
+
+/// The diffuse lighting arriving from all directions at a point, as second order spherical
+/// harmonics. Evaluated for a normal, it gives a color in the units of `AmbientColor`.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Irradiance {
+    /// The coefficients of the nine basis functions, for red, green and blue.
+    pub coefficients: [[f32; 3]; 9],
+}
+
+impl Irradiance {
+    /// Returns the lighting of a surface with the given normal.
+    pub fn evaluate(&self, normal: &Vector3<f32>) -> [f32; 3] {
+        let basis = sh_basis(&normal.normalize());
+        let mut color = [0.0; 3];
+        for (coefficient, weight) in self.coefficients.iter().zip(basis.iter()) {
+            for c in 0..3 {
+                color[c] += coefficient[c] * weight;
+            }
+        }
+        [color[0].max(0.0), color[1].max(0.0), color[2].max(0.0)]
+    }
+
+    /// Returns the weighted sum of several irradiances.
+    fn blend<'a>(weighted: impl IntoIterator<Item = (&'a Irradiance, f32)>) -> Self {
+        let mut coefficients = [[0.0; 3]; 9];
+        for (irradiance, weight) in weighted {
+            for (sum, coefficient) in coefficients.iter_mut().zip(irradiance.coefficients.iter()) {
+                for c in 0..3 {
+                    sum[c] += coefficient[c] * weight;
+                }
+            }
+        }
+        Irradiance { coefficients }
+    }
+}
+
+/// The prefiltered surroundings of a reflection probe, as a latitude-longitude image.
+///
+/// The image holds `levels` images of `width` by `height` pixels on top of each other, from the
+/// sharp reflections of smooth surfaces to the blurry ones of rough surfaces.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct EnvironmentMap {
+    /// Width of each level in pixels.
+    pub width: usize,
+    /// Height of each level in pixels.
+    pub height: usize,
+    /// Number of levels, for roughnesses spread evenly from `0.0` to `1.0`.
+    pub levels: usize,
+    /// Linear colors of the pixels, row by row from the top of the first level.
+    pub pixels: Vec<[f32; 3]>,
+}
+
+/// A baked reflection probe, lighting what's within its radius.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct ReflectionProbe {
+    /// Position the probe was captured from.
+    pub position: Vector3<f32>,
+    /// Radius of the area the probe lights.
+    pub radius: f32,
+    /// The reflections of the surroundings.
+    pub environment: EnvironmentMap,
+    /// The diffuse lighting at the position.
+    pub irradiance: Irradiance,
+}
+
+/// A regular grid of baked `Irradiance`, interpolated between the points.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct IrradianceGrid {
+    /// Position of the first point.
+    pub origin: Vector3<f32>,
+    /// Distance between the points along each axis.
+    pub spacing: Vector3<f32>,
+    /// Number of points along each axis.
+    pub counts: [usize; 3],
+    /// The irradiance of the points, X first, then Y, then Z.
+    pub cells: Vec<Irradiance>,
+}
+
+impl IrradianceGrid {
+    /// Returns the positions of the points of a grid, in the order of `cells`.
+    fn points(
+        origin: Vector3<f32>,
+        spacing: Vector3<f32>,
+        counts: [usize; 3],
+    ) -> Vec<Vector3<f32>> {
+        let mut points = Vec::with_capacity(counts[0] * counts[1] * counts[2]);
+        for z in 0..counts[2] {
+            for y in 0..counts[1] {
+                for x in 0..counts[0] {
+                    points.push(
+                        origin
+                            + Vector3::new(
+                                x as f32 * spacing.x,
+                                y as f32 * spacing.y,
+                                z as f32 * spacing.z,
+                            ),
+                    );
+                }
+            }
+        }
+        points
+    }
+
+    /// Returns the irradiance at a point, interpolated between the eight closest points of the
+    /// grid. Points outside of the grid get the irradiance of its border.
+    pub fn sample(&self, point: &Vector3<f32>) -> Option<Irradiance> {
+        if self.cells.len() != self.counts[0] * self.counts[1] * self.counts[2]
+            || self.cells.is_empty()
+        {
+            return None;
+        }
+        let mut lower = [0; 3];
+        let mut fraction = [0.0; 3];
+        for axis in 0..3 {
+            let last = self.counts[axis] - 1;
+            let t = if self.spacing[axis] > 0.0 {
+                (point[axis] - self.origin[axis]) / self.spacing[axis]
+            } else {
+                0.0
+            };
+            let t = t.max(0.0).min(last as f32);
+            lower[axis] = (t.floor() as usize).min(last.saturating_sub(1));
+            fraction[axis] = if last == 0 {
+                0.0
+            } else {
+                t - lower[axis] as f32
+            };
+        }
+
+        let mut weighted = Vec::with_capacity(8);
+        for corner in 0..8 {
+            let mut index = 0;
+            let mut stride = 1;
+            let mut weight = 1.0;
+            for axis in 0..3 {
+                let upper = (corner >> axis) & 1 == 1;
+                let i = (lower[axis] + upper as usize).min(self.counts[axis] - 1);
+                weight *= if upper {
+                    fraction[axis]
+                } else {
+                    1.0 - fraction[axis]
+                };
+                index += i * stride;
+                stride *= self.counts[axis];
+            }
+            if weight > 0.0 {
+                weighted.push((&self.cells[index], weight));
+            }
+        }
+        Some(Irradiance::blend(weighted))
+    }
+}
+
+/// The baked reflection probes and irradiance grid of a scene.
+///
+/// Loaded with the `RonFormat`, and processed by a `Processor::<BakedProbes>`.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct BakedProbes {
+    /// The reflection probes.
+    pub probes: Vec<ReflectionProbe>,
+    /// The irradiance grid, if one was baked.
+    pub grid: Option<IrradianceGrid>,
+}
+
+impl BakedProbes {
+    /// Returns the probe lighting a point: the smallest one reaching it, or the closest one.
+    pub fn probe_for(&self, point: &Vector3<f32>) -> Option<&ReflectionProbe> {
+        let distance = |probe: &ReflectionProbe| (probe.position - point).norm();
+        self.probes
+            .iter()
+            .filter(|probe| distance(probe) <= probe.radius)
+            .min_by(|a, b| a.radius.partial_cmp(&b.radius).unwrap_or(Ordering::Equal))
+            .or_else(|| {
+                self.probes.iter().min_by(|a, b| {
+                    distance(a)
+                        .partial_cmp(&distance(b))
+                        .unwrap_or(Ordering::Equal)
+                })
+            })
+    }
+
+    /// Returns the diffuse lighting at a point, from the grid or else from the probes.
+    pub fn irradiance_at(&self, point: &Vector3<f32>) -> Option<Irradiance> {
+        self.grid
+            .as_ref()
+            .and_then(|grid| grid.sample(point))
+            .or_else(|| self.probe_for(point).map(|probe| probe.irradiance.clone()))
+    }
+
+    /// Saves the probes as a RON file, which can be loaded with the `RonFormat`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> IoResult<()> {
+        use ron::ser::{to_string_pretty, PrettyConfig};
+
+        let ron = to_string_pretty(self, PrettyConfig::default())
+            .map_err(|e| IoError::new(IoErrorKind::Other, e.to_string()))?;
+        fs::write(path, ron)
+    }
+}
+
+impl Asset for BakedProbes {
+    const NAME: &'static str = "renderer::BakedProbes";
+    type Data = Self;
+    type HandleStorage = VecStorage<Handle<Self>>;
+}
+
+impl Into<AssetsResult<ProcessingState<BakedProbes>>> for BakedProbes {
+    fn into(self) -> AssetsResult<ProcessingState<BakedProbes>> {
+        Ok(ProcessingState::Loaded(self))
+    }
+}
+
+/// Handle to the baked probes of a scene.
+pub type BakedProbesHandle = Handle<BakedProbes>;
+
+/// Resource setting the baked probes lighting `DrawPbm` and `DrawPbmSeparate`.
+///
+/// While it's set, the irradiance replaces the `AmbientColor`, and the probe closest to the
+/// camera adds reflections.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SceneProbes {
+    /// The probes, from the `AssetStorage<BakedProbes>`.
+    pub probes: BakedProbesHandle,
+}
+
+/// Resource requesting the `ProbeBakeSystem` to bake the probes of the scene.
+///
+/// Meant for a bake mode of the game, e.g. behind a command line flag, which loads a level,
+/// adds this resource and quits once `is_finished` returns true.
+#[derive(Clone, Debug)]
+pub struct ProbeBake {
+    path: PathBuf,
+    probes: Vec<(Vector3<f32>, f32)>,
+    grid: Option<(Vector3<f32>, Vector3<f32>, [usize; 3])>,
+    environment_width: usize,
+    levels: usize,
+    finished: bool,
+}
+
+impl ProbeBake {
+    /// Creates a bake saving the `BakedProbes` to the given path.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        ProbeBake {
+            path: path.into(),
+            probes: Vec::new(),
+            grid: None,
+            environment_width: 64,
+            levels: 4,
+            finished: false,
+        }
+    }
+
+    /// Adds a reflection probe at the given position, lighting what's within the radius.
+    pub fn with_probe(mut self, position: Vector3<f32>, radius: f32) -> Self {
+        self.probes.push((position, radius));
+        self
+    }
+
+    /// Adds an irradiance grid of `counts` points, `spacing` apart, starting at `origin`.
+    pub fn with_irradiance_grid(
+        mut self,
+        origin: Vector3<f32>,
+        spacing: Vector3<f32>,
+        counts: [usize; 3],
+    ) -> Self {
+        self.grid = Some((origin, spacing, counts));
+        self
+    }
+
+    /// Sets the width of the environment maps, half of which is their height, and their number
+    /// of roughness levels. Defaults to 64 pixels and 4 levels.
+    pub fn with_environment(mut self, width: usize, levels: usize) -> Self {
+        self.environment_width = width.max(2);
+        self.levels = levels.max(1);
+        self
+    }
+
+    /// Checks whether every probe was captured and the file saved.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// The positions captured, the probes first and the points of the grid after them.
+    fn points(&self) -> Vec<Vector3<f32>> {
+        let mut points: Vec<_> = self.probes.iter().map(|&(position, _)| position).collect();
+        if let Some((origin, spacing, counts)) = self.grid {
+            points.extend(IrradianceGrid::points(origin, spacing, counts));
+        }
+        points
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BakePhase {
+    Place,
+    Settle(u32),
+    Capture,
+}
+
+/// Bakes the probes of the `ProbeBake` resource, by moving the active camera through their
+/// positions and reading back the frames with the `FrameCapture`.
+///
+/// The camera gets its transform and projection back once the bake is finished. Nothing else
+/// should move the camera during the bake.
+pub struct ProbeBakeSystem {
+    point: usize,
+    face: usize,
+    phase: BakePhase,
+    frames: Vec<CapturedFrame>,
+    probes: Vec<ReflectionProbe>,
+    cells: Vec<Irradiance>,
+    original: Option<(Option<Transform>, Camera)>,
+}
+
+impl ProbeBakeSystem {
+    /// Create new probe baking system
+    pub fn new() -> Self {
+        ProbeBakeSystem {
+            point: 0,
+            face: 0,
+            phase: BakePhase::Place,
+            frames: Vec::new(),
+            probes: Vec::new(),
+            cells: Vec::new(),
+            original: None,
+        }
+    }
+}
+
+impl Default for ProbeBakeSystem {
+    fn default() -> Self {
+        ProbeBakeSystem::new()
+    }
+}
+
+impl<'a> System<'a> for ProbeBakeSystem {
+    type SystemData = (
+        Entities<'a>,
+        Option<Write<'a, ProbeBake>>,
+        Write<'a, FrameCapture>,
+        Read<'a, ActiveCamera>,
+        ReadExpect<'a, ScreenDimensions>,
+        WriteStorage<'a, Camera>,
+        WriteStorage<'a, Transform>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, bake, mut capture, active, dimensions, mut cameras, mut transforms): Self::SystemData,
+    ) {
+        let mut bake = match bake {
+            Some(ref bake) if bake.finished => return,
+            Some(bake) => bake,
+            None => return,
+        };
+        let entity = match active
+            .entity
+            .filter(|&entity| cameras.contains(entity))
+            .or_else(|| (&*entities, &cameras).join().map(|(e, _)| e).next())
+        {
+            Some(entity) => entity,
+            None => {
+                warn!("The probes can't be baked without a camera");
+                return;
+            }
+        };
+        if self.original.is_none() {
+            self.original = Some((
+                transforms.get(entity).cloned(),
+                cameras.get(entity).cloned().unwrap(),
+            ));
+        }
+
+        let points = bake.points();
+        if self.point >= points.len() {
+            self.finish(&mut *bake, entity, &mut cameras, &mut transforms);
+            return;
+        }
+
+        match self.phase {
+            BakePhase::Place => {
+                let (forward, up) = FACES[self.face];
+                let position = points[self.point];
+                let mut transform = Transform::default();
+                transform.set_position(position);
+                transform.face_towards(position + Vector3::from(forward), Vector3::from(up));
+                if let Err(e) = transforms.insert(entity, transform) {
+                    error!("Failed to move the camera for the bake: {}", e);
+                    return;
+                }
+                // A field of view of 90 degrees along the shorter side of the screen.
+                let aspect = dimensions.aspect_ratio();
+                let fov = if aspect >= 1.0 {
+                    PI / 2.0
+                } else {
+                    2.0 * (1.0 / aspect).atan()
+                };
+                if let Some(camera) = cameras.get_mut(entity) {
+                    *camera = Camera::from(Projection::perspective(aspect, fov));
+                }
+                self.phase = BakePhase::Settle(SETTLE_FRAMES);
+            }
+            BakePhase::Settle(0) => {
+                capture.request();
+                self.phase = BakePhase::Capture;
+            }
+            BakePhase::Settle(frames) => self.phase = BakePhase::Settle(frames - 1),
+            BakePhase::Capture => {
+                let frame = match capture.take() {
+                    Some(frame) => frame,
+                    None => return,
+                };
+                self.frames.push(frame);
+                self.face += 1;
+                self.phase = BakePhase::Place;
+                if self.face < FACES.len() {
+                    return;
+                }
+
+                let cube = CubeCapture::from_frames(&self.frames);
+                if self.point < bake.probes.len() {
+                    let (position, radius) = bake.probes[self.point];
+                    self.probes.push(ReflectionProbe {
+                        position,
+                        radius,
+                        environment: cube.environment(bake.environment_width, bake.levels),
+                        irradiance: cube.irradiance(),
+                    });
+                } else {
+                    self.cells.push(cube.irradiance());
+                }
+                self.frames.clear();
+                self.face = 0;
+                self.point += 1;
+            }
+        }
+    }
+}
+
+impl ProbeBakeSystem {
+    fn finish(
+        &mut self,
+        bake: &mut ProbeBake,
+        entity: Entity,
+        cameras: &mut WriteStorage<'_, Camera>,
+        transforms: &mut WriteStorage<'_, Transform>,
+    ) {
+        let baked = BakedProbes {
+            probes: self.probes.drain(..).collect(),
+            grid: bake.grid.map(|(origin, spacing, counts)| IrradianceGrid {
+                origin,
+                spacing,
+                counts,
+                cells: self.cells.drain(..).collect(),
+            }),
+        };
+        match baked.save(&bake.path) {
+            Ok(()) => info!("Saved the baked probes to {:?}", bake.path),
+            Err(e) => error!("Failed to save the baked probes to {:?}: {}", bake.path, e),
+        }
+
+        if let Some((transform, camera)) = self.original.take() {
+            match transform {
+                Some(transform) => {
+                    if let Err(e) = transforms.insert(entity, transform) {
+                        error!("Failed to move the camera back after the bake: {}", e);
+                    }
+                }
+                None => {
+                    transforms.remove(entity);
+                }
+            }
+            if let Err(e) = cameras.insert(entity, camera) {
+                error!("Failed to restore the camera after the bake: {}", e);
+            }
+        }
+        bake.finished = true;
+        *self = ProbeBakeSystem::new();
+    }
+}
+
+/// The six faces of a capture, as linear colors with the top row first.
+struct CubeCapture {
+    size: usize,
+    faces: Vec<Vec<[f32; 3]>>,
+}
+
+impl CubeCapture {
+    /// Creates a capture of frames in the order of `FACES`, cropping them to the square in
+    /// their center.
+    fn from_frames(frames: &[CapturedFrame]) -> Self {
+        let size = frames
+            .iter()
+            .map(|frame| frame.width.min(frame.height) as usize)
+            .min()
+            .unwrap_or(0);
+        let faces = frames
+            .iter()
+            .map(|frame| {
+                let x0 = (frame.width as usize - size) / 2;
+                let y0 = (frame.height as usize - size) / 2;
+                let mut face = Vec::with_capacity(size * size);
+                for y in 0..size {
+                    for x in 0..size {
+                        let pixel = frame
+                            .pixel((x0 + x) as u32, (y0 + y) as u32)
+                            .unwrap_or([0; 4]);
+                        let linear = |c: u8| (f32::from(c) / 255.0).powf(2.2);
+                        face.push([linear(pixel[0]), linear(pixel[1]), linear(pixel[2])]);
+                    }
+                }
+                face
+            })
+            .collect();
+        CubeCapture { size, faces }
+    }
+
+    /// Returns the direction and solid angle of a texel.
+    fn texel(&self, face: usize, x: usize, y: usize) -> (Vector3<f32>, f32) {
+        let (forward, up) = FACES[face];
+        let (forward, up) = (Vector3::from(forward), Vector3::from(up));
+        let right = forward.cross(&up);
+        let size = self.size as f32;
+        let u = 2.0 * (x as f32 + 0.5) / size - 1.0;
+        let v = 1.0 - 2.0 * (y as f32 + 0.5) / size;
+        let direction = forward + right * u + up * v;
+        let length = direction.norm();
+        (
+            direction / length,
+            (2.0 / size) * (2.0 / size) / (length * length * length),
+        )
+    }
+
+    /// Returns the color seen in a direction.
+    fn sample(&self, direction: &Vector3<f32>) -> [f32; 3] {
+        if self.size == 0 {
+            return [0.0; 3];
+        }
+        let (face, &(forward, up)) = FACES
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| {
+                let dot = |f: [f32; 3]| Vector3::from(f).dot(direction);
+                dot(a.0).partial_cmp(&dot(b.0)).unwrap_or(Ordering::Equal)
+            })
+            .unwrap();
+        let (forward, up) = (Vector3::from(forward), Vector3::from(up));
+        let right = forward.cross(&up);
+        let depth = direction.dot(&forward);
+        let u = direction.dot(&right) / depth;
+        let v = direction.dot(&up) / depth;
+        let pixel = |t: f32| ((t * self.size as f32) as usize).min(self.size - 1);
+        let (x, y) = (pixel((u + 1.0) / 2.0), pixel((1.0 - v) / 2.0));
+        self.faces[face][x + y * self.size]
+    }
+
+    /// Returns the capture shrunk to faces of the given size, averaging the texels.
+    fn downsample(&self, size: usize) -> CubeCapture {
+        if size == 0 || self.size <= size {
+            return CubeCapture {
+                size: self.size,
+                faces: self.faces.clone(),
+            };
+        }
+        let block = self.size / size;
+        let faces = self
+            .faces
+            .iter()
+            .map(|face| {
+                let mut small = vec![[0.0; 3]; size * size];
+                for y in 0..size * block {
+                    for x in 0..size * block {
+                        let color = face[x + y * self.size];
+                        let texel = &mut small[x / block + y / block * size];
+                        for c in 0..3 {
+                            texel[c] += color[c] / (block * block) as f32;
+                        }
+                    }
+                }
+                small
+            })
+            .collect();
+        CubeCapture { size, faces }
+    }
+
+    /// Returns every texel with its direction and solid angle.
+    fn texels(&self) -> Vec<(Vector3<f32>, f32, [f32; 3])> {
+        let mut texels = Vec::with_capacity(self.faces.len() * self.size * self.size);
+        for (face, colors) in self.faces.iter().enumerate() {
+            for y in 0..self.size {
+                for x in 0..self.size {
+                    let (direction, solid_angle) = self.texel(face, x, y);
+                    texels.push((direction, solid_angle, colors[x + y * self.size]));
+                }
+            }
+        }
+        texels
+    }
+
+    /// Projects the capture on the spherical harmonics, convolved with the cosine lobe of
+    /// diffuse surfaces.
+    fn irradiance(&self) -> Irradiance {
+        let mut coefficients = [[0.0; 3]; 9];
+        for (direction, solid_angle, color) in self.downsample(32).texels() {
+            for (coefficient, weight) in coefficients.iter_mut().zip(sh_basis(&direction).iter()) {
+                for c in 0..3 {
+                    coefficient[c] += color[c] * weight * solid_angle;
+                }
+            }
+        }
+        // The cosine lobe of each band, divided by pi to match the units of the ambient color.
+        let bands = [
+            1.0,
+            2.0 / 3.0,
+            2.0 / 3.0,
+            2.0 / 3.0,
+            0.25,
+            0.25,
+            0.25,
+            0.25,
+            0.25,
+        ];
+        for (coefficient, band) in coefficients.iter_mut().zip(bands.iter()) {
+            for c in coefficient.iter_mut() {
+                *c *= band;
+            }
+        }
+        Irradiance { coefficients }
+    }
+
+    /// Prefilters the capture into an environment map, blurring each level more, with an
+    /// approximation of the specular lobe of its roughness.
+    fn environment(&self, width: usize, levels: usize) -> EnvironmentMap {
+        let height = (width / 2).max(1);
+        let filtered = self.downsample(FILTER_SIZE).texels();
+        let mut pixels = Vec::with_capacity(width * height * levels);
+        for level in 0..levels {
+            let roughness = if levels > 1 {
+                level as f32 / (levels - 1) as f32
+            } else {
+                0.0
+            };
+            let alpha = roughness * roughness;
+            let power = (2.0 / (alpha * alpha).max(0.0001) - 2.0).max(1.0);
+            for y in 0..height {
+                for x in 0..width {
+                    let direction = equirect_direction(x, y, width, height);
+                    if level == 0 {
+                        pixels.push(self.sample(&direction));
+                        continue;
+                    }
+                    let mut sum = [0.0; 3];
+                    let mut total = 0.0;
+                    for &(texel, solid_angle, color) in &filtered {
+                        let cos = texel.dot(&direction);
+                        if cos <= 0.0 {
+                            continue;
+                        }
+                        let weight = cos.powf(power) * solid_angle;
+                        for c in 0..3 {
+                            sum[c] += color[c] * weight;
+                        }
+                        total += weight;
+                    }
+                    pixels.push(if total > 0.0 {
+                        [sum[0] / total, sum[1] / total, sum[2] / total]
+                    } else {
+                        self.sample(&direction)
+                    });
+                }
+            }
+        }
+        EnvironmentMap {
+            width,
+            height,
+            levels,
+            pixels,
+        }
+    }
+}
+
+/// Returns the direction of a pixel of a latitude-longitude image, with the top row looking up
+/// and the center looking down the Z axis.
+fn equirect_direction(x: usize, y: usize, width: usize, height: usize) -> Vector3<f32> {
+    let longitude = ((x as f32 + 0.5) / width as f32 - 0.5) * 2.0 * PI;
+    let latitude = (y as f32 + 0.5) / height as f32 * PI;
+    Vector3::new(
+        latitude.sin() * longitude.sin(),
+        latitude.cos(),
+        latitude.sin() * longitude.cos(),
+    )
+}
+
+/// Returns the nine real spherical harmonics basis functions of the second order.
+fn sh_basis(d: &Vector3<f32>) -> [f32; 9] {
+    [
+        0.282_095,
+        0.488_603 * d.y,
+        0.488_603 * d.z,
+        0.488_603 * d.x,
+        1.092_548 * d.x * d.y,
+        1.092_548 * d.y * d.z,
+        0.315_392 * (3.0 * d.z * d.z - 1.0),
+        1.092_548 * d.x * d.z,
+        0.546_274 * (d.x * d.x - d.y * d.y),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uniform_cube(size: usize, color: [f32; 3]) -> CubeCapture {
+        CubeCapture {
+            size,
+            faces: vec![vec![color; size * size]; 6],
+        }
+    }
+
+    #[test]
+    fn uniform_irradiance() {
+        let irradiance = uniform_cube(8, [0.5, 1.0, 0.0]).irradiance();
+        for normal in &[Vector3::x(), -Vector3::y(), Vector3::new(1.0, 1.0, 1.0)] {
+            let color = irradiance.evaluate(normal);
+            assert!((color[0] - 0.5).abs() < 0.01);
+            assert!((color[1] - 1.0).abs() < 0.01);
+            assert!(color[2].abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn sample_faces() {
+        let mut cube = uniform_cube(4, [0.0; 3]);
+        for (i, face) in cube.faces.iter_mut().enumerate() {
+            for texel in face.iter_mut() {
+                *texel = [i as f32; 3];
+            }
+        }
+        for (i, &(forward, _)) in FACES.iter().enumerate() {
+            assert_eq!([i as f32; 3], cube.sample(&Vector3::from(forward)));
+        }
+        let (direction, _) = cube.texel(2, 0, 0);
+        assert_eq!([2.0; 3], cube.sample(&direction));
+    }
+
+    #[test]
+    fn environment_levels() {
+        let environment = uniform_cube(4, [1.0; 3]).environment(8, 3);
+        assert_eq!(8 * 4 * 3, environment.pixels.len());
+        for pixel in &environment.pixels {
+            assert!((pixel[0] - 1.0).abs() < 0.001);
+        }
+    }
+
+    #[test]
+    fn grid_interpolation() {
+        let cell = |value: f32| Irradiance {
+            coefficients: [[value; 3]; 9],
+        };
+        let grid = IrradianceGrid {
+            origin: Vector3::zeros(),
+            spacing: Vector3::new(2.0, 1.0, 1.0),
+            counts: [2, 1, 1],
+            cells: vec![cell(0.0), cell(1.0)],
+        };
+        let middle = grid.sample(&Vector3::new(1.0, 0.0, 0.0)).unwrap();
+        assert!((middle.coefficients[0][0] - 0.5).abs() < 0.001);
+        let outside = grid.sample(&Vector3::new(5.0, 3.0, -1.0)).unwrap();
+        assert!((outside.coefficients[0][0] - 1.0).abs() < 0.001);
+    }
+}
@@ -2,15 +2,21 @@ use fnv::FnvHashMap as HashMap;
 use gfx::memory::Pod;
 use winit::{dpi::LogicalSize, EventsLoop, Window as WinitWindow, WindowBuilder};
 
+use amethyst_assets::AssetStorage;
+
 use crate::{
+    backend::{GraphicsApi, RenderBackend},
+    capture::CapturedFrame,
+    compute::{self, ComputePasses, ComputeShader, ImageAccess},
     config::DisplayConfig,
     error::{Error, Result},
+    gpu::GpuInfo,
     mesh::{Mesh, MeshBuilder, VertexDataSet},
     pipe::{
         ColorBuffer, DepthBuffer, PipelineBuild, PipelineData, PolyPipeline, Target, TargetBuilder,
     },
     tex::{Texture, TextureBuilder},
-    types::{ColorFormat, DepthFormat, Device, Encoder, Factory, Window},
+    types::{ColorFormat, DepthFormat, Device, Encoder, Factory, RawTexture, Window},
 };
 
 /// Generic renderer.
@@ -21,11 +27,20 @@ pub struct Renderer {
     device: Device,
     encoder: Encoder,
     main_target: Target,
+    offscreen: Option<RawTexture>,
     window: Window,
     events: EventsLoop,
     multisampling: u16,
     cached_size: LogicalSize,
     cached_hidpi_factor: f64,
+    capture_requested: bool,
+    captured: Option<CapturedFrame>,
+    compute_programs: HashMap<usize, u32>,
+    backend: RenderBackend,
+    gpu: GpuInfo,
+    config: DisplayConfig,
+    winit_builder: WindowBuilder,
+    lost: bool,
 }
 
 impl Renderer {
@@ -61,6 +76,18 @@ impl Renderer {
         tb.build(&mut self.factory)
     }
 
+    /// Replaces the pixels of the rectangle of `size` at `offset` from the top left corner of a
+    /// mutable texture with `data`, uploaded before the next frame is drawn.
+    pub fn update_texture(
+        &mut self,
+        texture: &Texture,
+        offset: (u16, u16),
+        size: (u16, u16),
+        data: &[u8],
+    ) -> Result<()> {
+        texture.update(&mut self.encoder, offset, size, data)
+    }
+
     /// Builds a new renderer pipeline.
     pub fn create_pipe<B, P>(&mut self, pb: B) -> Result<P>
     where
@@ -80,7 +107,12 @@ impl Renderer {
         #[cfg(feature = "opengl")]
         use glutin::dpi::PhysicalSize;
 
-        if let Some(size) = self.window().get_inner_size() {
+        if self.lost {
+            return;
+        }
+
+        // The offscreen target of a headless renderer keeps the size it was created with.
+        if let (Some(size), None) = (self.window().get_inner_size(), self.offscreen.as_ref()) {
             let hidpi_factor = self.window().get_hidpi_factor();
 
             if size != self.cached_size || hidpi_factor != self.cached_hidpi_factor {
@@ -95,12 +127,259 @@ impl Renderer {
 
         pipe.apply(&mut self.encoder, self.factory.clone(), data);
         self.encoder.flush(&mut self.device);
+        if self.capture_requested {
+            self.capture_requested = false;
+            self.captured = self.read_main_target();
+        }
         self.device.cleanup();
 
         #[cfg(feature = "opengl")]
-        self.window
-            .swap_buffers()
-            .expect("OpenGL context has been lost");
+        match self.window.swap_buffers() {
+            Ok(()) => {}
+            Err(glutin::ContextError::ContextLost) => {
+                error!("OpenGL context has been lost");
+                self.lost = true;
+            }
+            Err(e) => error!("Failed to present frame: {:?}", e),
+        }
+    }
+
+    /// Checks whether the device was lost, in which case nothing is drawn until `recreate` is
+    /// called.
+    pub fn is_lost(&self) -> bool {
+        self.lost
+    }
+
+    /// Replaces the window and the device with new ones, after the device was lost.
+    ///
+    /// Every GPU resource made with the old `factory` is invalid and has to be built again,
+    /// starting with the pipeline.
+    pub fn recreate(&mut self) -> Result<()> {
+        let Backend(device, mut factory, main_target, window, backend, gpu) =
+            init_backend(self.winit_builder.clone(), &self.events, &self.config)?;
+        let (main_target, offscreen) =
+            headless_target(&mut factory, main_target, &window, &self.config)?;
+        self.cached_size = window.get_inner_size().ok_or(Error::WindowDestroyed)?;
+        self.cached_hidpi_factor = window.get_hidpi_factor();
+        self.encoder = factory.create_command_buffer().into();
+        self.device = device;
+        self.factory = factory;
+        self.main_target = main_target;
+        self.offscreen = offscreen;
+        self.window = window;
+        self.multisampling = backend.multisampling;
+        self.backend = backend;
+        self.gpu = gpu;
+        self.captured = None;
+        self.compute_programs.clear();
+        self.lost = false;
+        Ok(())
+    }
+
+    /// Dispatches the enabled compute passes, in order, before the next frame is drawn.
+    ///
+    /// Passes binding a texture which isn't loaded yet wait until it is.
+    #[cfg(feature = "opengl")]
+    pub fn dispatch_compute(&mut self, passes: &ComputePasses, textures: &AssetStorage<Texture>) {
+        use gfx::Device;
+        use gfx_gl as gl;
+
+        if self.lost || passes.is_empty() {
+            return;
+        }
+        if !self.backend.compute {
+            use std::sync::Once;
+
+            static WARNING: Once = Once::new();
+            WARNING.call_once(|| {
+                warn!(
+                    "Compute passes need OpenGL 4.3, but the context is {}",
+                    self.backend.version
+                )
+            });
+            return;
+        }
+        // Upload the queued texture updates before the shaders read the textures.
+        self.encoder.flush(&mut self.device);
+        for (name, pass) in passes.passes.iter().filter(|(_, pass)| pass.enabled) {
+            let program = match self.compute_program(&pass.shader) {
+                Some(program) => program,
+                None => continue,
+            };
+            let mut buffers = Vec::with_capacity(pass.buffers.len());
+            for (binding, buffer) in &pass.buffers {
+                match buffer.create(&mut self.factory) {
+                    Ok(raw) => buffers.push((*binding, *raw.resource())),
+                    Err(e) => error!(
+                        "Failed to create a buffer of the compute pass {:?}: {}",
+                        name, e
+                    ),
+                }
+            }
+            let mut images = Vec::with_capacity(pass.images.len());
+            for &(binding, ref handle, access) in &pass.images {
+                let texture = match textures.get(handle) {
+                    Some(texture) => texture,
+                    None => break,
+                };
+                let access = match access {
+                    ImageAccess::Read => gl::READ_ONLY,
+                    ImageAccess::Write => gl::WRITE_ONLY,
+                    ImageAccess::ReadWrite => gl::READ_WRITE,
+                };
+                match texture.image_binding() {
+                    Some((texture, format)) => images.push((binding, texture, format, access)),
+                    None => error!(
+                        "The format of an image of the compute pass {:?} can't be bound",
+                        name
+                    ),
+                }
+            }
+            if buffers.len() < pass.buffers.len() || images.len() < pass.images.len() {
+                continue;
+            }
+            let [x, y, z] = pass.groups;
+            unsafe {
+                self.device.with_gl(|gl| {
+                    gl.UseProgram(program);
+                    for &(binding, buffer) in &buffers {
+                        gl.BindBufferBase(gl::SHADER_STORAGE_BUFFER, binding, buffer);
+                    }
+                    for &(binding, texture, format, access) in &images {
+                        gl.BindImageTexture(binding, texture, 0, gl::FALSE, 0, access, format);
+                    }
+                    gl.DispatchCompute(x, y, z);
+                    gl.MemoryBarrier(gl::ALL_BARRIER_BITS);
+                    gl.UseProgram(0);
+                });
+            }
+        }
+    }
+
+    #[cfg(not(feature = "opengl"))]
+    pub fn dispatch_compute(&mut self, passes: &ComputePasses, _: &AssetStorage<Texture>) {
+        use std::sync::Once;
+
+        static WARNING: Once = Once::new();
+        if !passes.is_empty() {
+            WARNING.call_once(|| warn!("Compute passes are only supported by the OpenGL backend"));
+        }
+    }
+
+    /// Returns the program of a compute shader, compiling it the first time.
+    #[cfg(feature = "opengl")]
+    fn compute_program(&mut self, shader: &ComputeShader) -> Option<u32> {
+        if let Some(&program) = self.compute_programs.get(&shader.id()) {
+            return Some(program).filter(|&program| program != 0);
+        }
+        let mut result = Ok(0);
+        unsafe {
+            self.device
+                .with_gl(|gl| result = compute::compile(gl, shader.source()));
+        }
+        // Failed shaders are kept as 0, so they aren't compiled again every frame.
+        let program = result.unwrap_or_else(|log| {
+            error!("Failed to compile a compute shader: {}", log);
+            0
+        });
+        self.compute_programs.insert(shader.id(), program);
+        Some(program).filter(|&program| program != 0)
+    }
+
+    /// Requests that the next frame drawn is read back from the main render target.
+    ///
+    /// The frame can be retrieved with `take_capture` after the next call to `draw`.
+    pub fn request_capture(&mut self) {
+        self.capture_requested = true;
+    }
+
+    /// Takes the most recently captured frame, if any.
+    pub fn take_capture(&mut self) -> Option<CapturedFrame> {
+        self.captured.take()
+    }
+
+    /// Reads the pixels of the main render target before it is presented.
+    #[cfg(feature = "opengl")]
+    fn read_main_target(&mut self) -> Option<CapturedFrame> {
+        use gfx_device_gl::NewTexture;
+        use gfx_gl as gl;
+
+        let (width, height): (u32, u32) = match self.offscreen {
+            Some(ref texture) => {
+                let (width, height, _, _) = texture.get_info().kind.get_dimensions();
+                (u32::from(width), u32::from(height))
+            }
+            None => self
+                .cached_size
+                .to_physical(self.cached_hidpi_factor)
+                .into(),
+        };
+        let offscreen = match self.offscreen.as_ref().map(|texture| texture.resource()) {
+            Some(&NewTexture::Texture(name)) => Some(name),
+            Some(&NewTexture::Surface(_)) => {
+                error!("The offscreen target can't be read back");
+                return None;
+            }
+            None => None,
+        };
+        let mut pixels = vec![0u8; width as usize * height as usize * 4];
+        unsafe {
+            self.device.with_gl(|gl| {
+                // Read from the main target, whatever the passes left bound.
+                let mut previous = 0;
+                gl.GetIntegerv(gl::READ_FRAMEBUFFER_BINDING, &mut previous);
+                let mut framebuffer = 0;
+                match offscreen {
+                    Some(texture) => {
+                        gl.GenFramebuffers(1, &mut framebuffer);
+                        gl.BindFramebuffer(gl::READ_FRAMEBUFFER, framebuffer);
+                        gl.FramebufferTexture2D(
+                            gl::READ_FRAMEBUFFER,
+                            gl::COLOR_ATTACHMENT0,
+                            gl::TEXTURE_2D,
+                            texture,
+                            0,
+                        );
+                        gl.ReadBuffer(gl::COLOR_ATTACHMENT0);
+                    }
+                    None => {
+                        gl.BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+                        gl.ReadBuffer(gl::BACK);
+                    }
+                }
+                gl.PixelStorei(gl::PACK_ALIGNMENT, 1);
+                gl.ReadPixels(
+                    0,
+                    0,
+                    width as i32,
+                    height as i32,
+                    gl::RGBA,
+                    gl::UNSIGNED_BYTE,
+                    pixels.as_mut_ptr() as *mut _,
+                );
+                gl.BindFramebuffer(gl::READ_FRAMEBUFFER, previous as u32);
+                if framebuffer != 0 {
+                    gl.DeleteFramebuffers(1, &framebuffer);
+                }
+            });
+        }
+        Some(CapturedFrame::from_bottom_up(width, height, pixels))
+    }
+
+    #[cfg(not(feature = "opengl"))]
+    fn read_main_target(&mut self) -> Option<CapturedFrame> {
+        warn!("Frame capture is only supported by the OpenGL backend");
+        None
+    }
+
+    /// Returns the backend the renderer started with.
+    pub fn backend(&self) -> &RenderBackend {
+        &self.backend
+    }
+
+    /// Returns the GPU the renderer runs on.
+    pub fn gpu(&self) -> &GpuInfo {
+        &self.gpu
     }
 
     /// Retrieve a mutable borrow of the events loop
@@ -176,7 +455,7 @@ impl RendererBuilder {
         let mut wb = self.winit_builder.clone();
         wb = wb
             .with_title(self.config.title.clone())
-            .with_visibility(self.config.visibility);
+            .with_visibility(self.config.visibility && !self.config.headless);
 
         if self.config.fullscreen {
             wb = wb.with_fullscreen(Some(self.events.get_primary_monitor()));
@@ -208,8 +487,10 @@ impl RendererBuilder {
 
     /// Consumes the builder and creates the new `Renderer`.
     pub fn build(self) -> Result<Renderer> {
-        let Backend(device, mut factory, main_target, window) =
+        let Backend(device, mut factory, main_target, window, backend, gpu) =
             init_backend(self.winit_builder.clone(), &self.events, &self.config)?;
+        let (main_target, offscreen) =
+            headless_target(&mut factory, main_target, &window, &self.config)?;
 
         let cached_size = window
             .get_inner_size()
@@ -223,17 +504,69 @@ impl RendererBuilder {
             encoder,
             factory,
             main_target,
+            offscreen,
             window,
             events: self.events,
-            multisampling: self.config.multisampling,
+            multisampling: backend.multisampling,
             cached_size,
             cached_hidpi_factor,
+            capture_requested: false,
+            captured: None,
+            compute_programs: HashMap::default(),
+            backend,
+            gpu,
+            config: self.config,
+            winit_builder: self.winit_builder,
+            lost: false,
         })
     }
 }
 
+/// Replaces the window target with an offscreen target of the same size when the renderer is
+/// headless, returning the texture drawn into.
+fn headless_target(
+    factory: &mut Factory,
+    window_target: Target,
+    window: &Window,
+    config: &DisplayConfig,
+) -> Result<(Target, Option<RawTexture>)> {
+    use gfx::{memory::Typed, Factory};
+
+    if !config.headless {
+        return Ok((window_target, None));
+    }
+    let size: (u32, u32) = window
+        .get_inner_size()
+        .ok_or(Error::WindowDestroyed)?
+        .to_physical(window.get_hidpi_factor())
+        .into();
+    let (w, h) = (size.0 as u16, size.1 as u16);
+    let (texture, color_input, color_output) = factory.create_render_target::<ColorFormat>(w, h)?;
+    let (_, depth_input, depth_output) = factory.create_depth_stencil::<DepthFormat>(w, h)?;
+    let target = Target::new(
+        ColorBuffer {
+            as_input: Some(color_input),
+            as_output: color_output,
+        },
+        DepthBuffer {
+            as_input: Some(depth_input),
+            as_output: depth_output,
+        },
+        size,
+    );
+    info!("Rendering headless into an offscreen target of {:?}", size);
+    Ok((target, Some(texture.raw().clone())))
+}
+
 /// Represents a graphics backend for the renderer.
-struct Backend(pub Device, pub Factory, pub Target, pub Window);
+struct Backend(
+    pub Device,
+    pub Factory,
+    pub Target,
+    pub Window,
+    pub RenderBackend,
+    pub GpuInfo,
+);
 
 /// Creates the Direct3D 11 backend.
 #[cfg(all(feature = "d3d11", target_os = "windows"))]
@@ -241,8 +574,9 @@ fn init_backend(wb: WindowBuilder, el: &EventsLoop, config: &DisplayConfig) -> R
     use gfx_window_dxgi as win;
 
     // FIXME: vsync + multisampling from config
-    let (win, dev, mut fac, color) =
-        win::init::<ColorFormat>(wb, el).expect("Unable to initialize window (d3d11 backend)");
+    let (win, dev, mut fac, color) = win::init::<ColorFormat>(wb, el).map_err(|e| {
+        Error::BackendCreation(format!("Unable to initialize Direct3D 11: {:?}", e))
+    })?;
     let dev = gfx_device_dx11::Deferred::from(dev);
 
     let size = win.get_inner_size_points().ok_or(Error::WindowDestroyed)?;
@@ -259,8 +593,23 @@ fn init_backend(wb: WindowBuilder, el: &EventsLoop, config: &DisplayConfig) -> R
         },
         size,
     );
-
-    Ok(Backend(dev, fac, main_target, win))
+    let backend = RenderBackend {
+        api: GraphicsApi::Direct3D11,
+        version: String::from("11"),
+        device: String::new(),
+        multisampling: config.multisampling,
+        fallback: false,
+        compute: false,
+    };
+
+    Ok(Backend(
+        dev,
+        fac,
+        main_target,
+        win,
+        backend,
+        GpuInfo::default(),
+    ))
 }
 
 #[cfg(all(feature = "metal", target_os = "macos"))]
@@ -268,8 +617,8 @@ fn init_backend(wb: WindowBuilder, el: &EventsLoop, config: &DisplayConfig) -> R
     use gfx_window_metal as win;
 
     // FIXME: vsync + multisampling from config
-    let (win, dev, mut fac, color) =
-        win::init::<ColorFormat>(wb, el).expect("Unable to initialize window (metal backend)");
+    let (win, dev, mut fac, color) = win::init::<ColorFormat>(wb, el)
+        .map_err(|e| Error::BackendCreation(format!("Unable to initialize Metal: {:?}", e)))?;
 
     let size = win.get_inner_size_points().ok_or(Error::WindowDestroyed)?;
     let (w, h) = (size.0 as u16, size.1 as u16);
@@ -285,39 +634,198 @@ fn init_backend(wb: WindowBuilder, el: &EventsLoop, config: &DisplayConfig) -> R
         },
         size,
     );
-
-    Ok(Backend(dev, fac, main_target, win))
+    let backend = RenderBackend {
+        api: GraphicsApi::Metal,
+        version: String::new(),
+        device: String::new(),
+        multisampling: config.multisampling,
+        fallback: false,
+        compute: false,
+    };
+
+    Ok(Backend(
+        dev,
+        fac,
+        main_target,
+        win,
+        backend,
+        GpuInfo::default(),
+    ))
 }
 
 /// Creates the OpenGL backend.
+///
+/// Tries the context asked for by the config first, then simpler ones for older GPUs and
+/// drivers: without multisampling, then with OpenGL 3.2 core, the oldest version the shaders of
+/// the passes support.
 #[cfg(feature = "opengl")]
 fn init_backend(wb: WindowBuilder, el: &EventsLoop, config: &DisplayConfig) -> Result<Backend> {
     use gfx_window_glutin as win;
-    use glutin;
-    #[cfg(target_os = "macos")]
-    use glutin::{GlProfile, GlRequest};
-
-    let ctx = glutin::ContextBuilder::new()
-        .with_multisampling(config.multisampling)
-        .with_vsync(config.vsync);
-    #[cfg(target_os = "macos")]
-    let ctx = ctx
-        .with_gl_profile(GlProfile::Core)
-        .with_gl(GlRequest::Latest);
-
-    let (win, dev, fac, color, depth) = win::init::<ColorFormat, DepthFormat>(wb, ctx, el);
-    let size = win.get_inner_size().ok_or(Error::WindowDestroyed)?.into();
-    let main_target = Target::new(
-        ColorBuffer {
-            as_input: None,
-            as_output: color,
-        },
-        DepthBuffer {
-            as_input: None,
-            as_output: depth,
+    use glutin::{self, GlProfile, GlWindow};
+
+    config.gpu.apply();
+    let attempts = context_attempts(config.multisampling);
+
+    let mut errors = Vec::new();
+    for (index, &(multisampling, request)) in attempts.iter().enumerate() {
+        let ctx = glutin::ContextBuilder::new()
+            .with_multisampling(multisampling)
+            .with_vsync(config.vsync);
+        #[cfg(target_os = "macos")]
+        let ctx = ctx
+            .with_gl_profile(GlProfile::Core)
+            .with_gl(glutin::GlRequest::Latest);
+        let ctx = match request {
+            Some(request) => ctx.with_gl_profile(GlProfile::Core).with_gl(request),
+            None => ctx,
+        };
+
+        let win = match GlWindow::new(wb.clone(), ctx, el) {
+            Ok(win) => win,
+            Err(e) => {
+                warn!(
+                    "Failed to create OpenGL context (multisampling: {}, version: {:?}): {}",
+                    multisampling, request, e
+                );
+                errors.push(e.to_string());
+                continue;
+            }
+        };
+        let (mut dev, fac, color, depth) = win::init_existing::<ColorFormat, DepthFormat>(&win);
+        let size = win.get_inner_size().ok_or(Error::WindowDestroyed)?.into();
+        let main_target = Target::new(
+            ColorBuffer {
+                as_input: None,
+                as_output: color,
+            },
+            DepthBuffer {
+                as_input: None,
+                as_output: depth,
+            },
+            size,
+        );
+        let backend = {
+            let info = dev.get_info();
+            RenderBackend {
+                api: GraphicsApi::OpenGl,
+                version: format!("{}.{}", info.version.major, info.version.minor),
+                device: info.platform_name.renderer.to_string(),
+                multisampling,
+                fallback: index > 0,
+                compute: compute_support(&mut dev),
+            }
+        };
+        let gpu = gpu_info(&mut dev);
+        info!("Renderer started with {}", backend);
+
+        return Ok(Backend(dev, fac, main_target, win, backend, gpu));
+    }
+    Err(Error::BackendCreation(format!(
+        "No OpenGL context could be created: {}",
+        errors.join("; ")
+    )))
+}
+
+/// Returns the multisampling and the versions of the OpenGL contexts to try, in order, `None`
+/// leaving the version to the driver.
+#[cfg(feature = "opengl")]
+fn context_attempts(multisampling: u16) -> Vec<(u16, Option<glutin::GlRequest>)> {
+    use glutin::{Api, GlRequest};
+
+    let mut attempts = vec![(multisampling, None)];
+    if multisampling > 0 {
+        attempts.push((0, None));
+    }
+    attempts.push((0, Some(GlRequest::Specific(Api::OpenGl, (3, 2)))));
+    attempts
+}
+
+/// Returns whether the OpenGL context loaded the functions dispatching compute shaders.
+#[cfg(feature = "opengl")]
+fn compute_support(dev: &mut Device) -> bool {
+    let mut supported = false;
+    unsafe {
+        dev.with_gl(|gl| {
+            supported = gl.DispatchCompute.is_loaded()
+                && gl.BindImageTexture.is_loaded()
+                && gl.MemoryBarrier.is_loaded()
+                && gl.BindBufferBase.is_loaded();
+        });
+    }
+    supported
+}
+
+/// Queries the GPU the OpenGL context runs on.
+#[cfg(feature = "opengl")]
+fn gpu_info(dev: &mut Device) -> GpuInfo {
+    use gfx_gl as gl;
+
+    use crate::gpu::GpuKind;
+
+    /// From `GL_NVX_gpu_memory_info`, in kilobytes.
+    const DEDICATED_VIDMEM_NVX: gl::types::GLenum = 0x9047;
+
+    let (name, vendor, memory_extension) = {
+        let info = dev.get_info();
+        (
+            info.platform_name.renderer.to_string(),
+            info.platform_name.vendor.to_string(),
+            info.extensions.contains("GL_NVX_gpu_memory_info"),
+        )
+    };
+    let (instancing, srgb) = {
+        let caps = gfx::Device::get_capabilities(dev);
+        (caps.instance_call_supported, caps.srgb_color_supported)
+    };
+    let mut max_texture_size = 0;
+    let mut memory = 0;
+    unsafe {
+        dev.with_gl(|gl| {
+            gl.GetIntegerv(gl::MAX_TEXTURE_SIZE, &mut max_texture_size);
+            if memory_extension {
+                gl.GetIntegerv(DEDICATED_VIDMEM_NVX, &mut memory);
+            }
+        });
+    }
+    GpuInfo {
+        kind: GpuKind::guess(&vendor, &name),
+        name,
+        vendor,
+        memory: if memory > 0 {
+            Some(memory as u64 * 1024)
+        } else {
+            None
         },
-        size,
-    );
+        max_texture_size: max_texture_size.max(0) as u32,
+        instancing,
+        srgb,
+    }
+}
 
-    Ok(Backend(dev, fac, main_target, win))
+#[cfg(all(test, feature = "opengl"))]
+mod tests {
+    use glutin::{Api, GlRequest};
+
+    use super::*;
+
+    fn is_opengl_3_2(request: &Option<GlRequest>) -> bool {
+        match *request {
+            Some(GlRequest::Specific(Api::OpenGl, (3, 2))) => true,
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn contexts_fall_back_to_opengl_3_2_without_multisampling() {
+        let attempts = context_attempts(4);
+        let samples: Vec<_> = attempts.iter().map(|&(samples, _)| samples).collect();
+        assert_eq!(vec![4, 0, 0], samples);
+        assert!(attempts[0].1.is_none() && attempts[1].1.is_none());
+        assert!(is_opengl_3_2(&attempts[2].1));
+
+        let attempts = context_attempts(0);
+        assert_eq!(2, attempts.len());
+        assert!(attempts[0].1.is_none());
+        assert!(attempts[1].0 == 0 && is_opengl_3_2(&attempts[1].1));
+    }
 }
@@ -0,0 +1,500 @@
+//! Scattering of instanced meshes over surfaces, e.g. grass, rocks and trees.
+
+use std::f32::consts::PI;
+
+use amethyst_core::{
+    nalgebra::{Matrix4, Point3, UnitQuaternion, Vector2, Vector3},
+    specs::prelude::{Component, DenseVecStorage},
+    RngStream,
+};
+
+use crate::{cam::Frustum, formats::MeshData, mesh::MeshHandle};
+
+/// The density of the instances over the texture coordinates of a surface, from `0.0` for none
+/// to `1.0` for the full count of the `ScatterBuilder`.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct DensityMap {
+    width: usize,
+    height: usize,
+    values: Vec<f32>,
+}
+
+impl DensityMap {
+    /// Creates a map of `width` by `height` values, in rows from `v = 0.0` to `v = 1.0`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of values doesn't match the size.
+    pub fn new(width: usize, height: usize, values: Vec<f32>) -> Self {
+        assert_eq!(
+            width * height,
+            values.len(),
+            "Density map size mismatch: Expected {} values",
+            width * height
+        );
+        DensityMap {
+            width,
+            height,
+            values,
+        }
+    }
+
+    /// Creates a map of the same density everywhere.
+    pub fn uniform(density: f32) -> Self {
+        DensityMap::new(1, 1, vec![density])
+    }
+
+    /// Returns the density at texture coordinates, interpolated between the values.
+    pub fn sample(&self, tex_coord: Vector2<f32>) -> f32 {
+        let x = (tex_coord.x.max(0.0).min(1.0) * (self.width - 1) as f32).max(0.0);
+        let y = (tex_coord.y.max(0.0).min(1.0) * (self.height - 1) as f32).max(0.0);
+        let (x0, y0) = (x as usize, y as usize);
+        let (x1, y1) = ((x0 + 1).min(self.width - 1), (y0 + 1).min(self.height - 1));
+        let (tx, ty) = (x - x0 as f32, y - y0 as f32);
+        let value = |x: usize, y: usize| self.values[x + y * self.width];
+        let top = value(x0, y0) + (value(x1, y0) - value(x0, y0)) * tx;
+        let bottom = value(x0, y1) + (value(x1, y1) - value(x0, y1)) * tx;
+        top + (bottom - top) * ty
+    }
+}
+
+impl Default for DensityMap {
+    fn default() -> Self {
+        DensityMap::uniform(1.0)
+    }
+}
+
+/// A triangle of a `ScatterSurface`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScatterTriangle {
+    /// Positions of the corners, in the space of the entity.
+    pub positions: [Vector3<f32>; 3],
+    /// Texture coordinates of the corners, where the `DensityMap` is sampled.
+    pub tex_coords: [Vector2<f32>; 3],
+}
+
+impl ScatterTriangle {
+    fn area(&self) -> f32 {
+        let [a, b, c] = self.positions;
+        (b - a).cross(&(c - a)).norm() / 2.0
+    }
+
+    fn normal(&self) -> Vector3<f32> {
+        let [a, b, c] = self.positions;
+        (b - a)
+            .cross(&(c - a))
+            .try_normalize(0.0)
+            .unwrap_or_else(Vector3::y)
+    }
+}
+
+/// The surface a `ScatterBuilder` places the instances on.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScatterSurface {
+    /// A rectangle on the XZ plane of the entity, centered on its origin, with texture
+    /// coordinates from `(0.0, 0.0)` at its `-X`, `-Z` corner to `(1.0, 1.0)` at the other.
+    Rectangle {
+        /// Size along the X axis.
+        width: f32,
+        /// Size along the Z axis.
+        depth: f32,
+    },
+    /// Triangles of a mesh, e.g. a terrain.
+    Triangles(Vec<ScatterTriangle>),
+}
+
+impl ScatterSurface {
+    /// Takes the triangles of the vertices of a mesh. Meshes without texture coordinates get
+    /// the density of the `DensityMap` at `(0.0, 0.0)` everywhere.
+    ///
+    /// Returns `None` for `MeshData::Creator`, whose vertices can't be read.
+    pub fn from_mesh_data(data: &MeshData) -> Option<Self> {
+        fn triangles<V>(
+            vertices: &[V],
+            vertex: impl Fn(&V) -> (Vector3<f32>, Vector2<f32>),
+        ) -> ScatterSurface {
+            ScatterSurface::Triangles(
+                vertices
+                    .chunks(3)
+                    .filter(|corners| corners.len() == 3)
+                    .map(|corners| {
+                        let (a, ta) = vertex(&corners[0]);
+                        let (b, tb) = vertex(&corners[1]);
+                        let (c, tc) = vertex(&corners[2]);
+                        ScatterTriangle {
+                            positions: [a, b, c],
+                            tex_coords: [ta, tb, tc],
+                        }
+                    })
+                    .collect(),
+            )
+        }
+
+        Some(match *data {
+            MeshData::PosColor(ref v) => triangles(v, |v| (v.position, Vector2::zeros())),
+            MeshData::PosColorNorm(ref v) => triangles(v, |v| (v.position, Vector2::zeros())),
+            MeshData::PosColorNormTex(ref v) => triangles(v, |v| (v.position, v.tex_coord)),
+            MeshData::PosTex(ref v) => triangles(v, |v| (v.position, v.tex_coord)),
+            MeshData::PosNormTex(ref v) => triangles(v, |v| (v.position, v.tex_coord)),
+            MeshData::PosNormTangTex(ref v) => triangles(v, |v| (v.position, v.tex_coord)),
+            MeshData::Creator(_) => return None,
+        })
+    }
+
+    /// Returns a random point of the surface, its normal and its texture coordinates.
+    fn sample(
+        &self,
+        areas: &[f32],
+        rng: &mut RngStream,
+    ) -> Option<(Vector3<f32>, Vector3<f32>, Vector2<f32>)> {
+        match *self {
+            ScatterSurface::Rectangle { width, depth } => {
+                let tex_coord = Vector2::new(rng.next_f32(), rng.next_f32());
+                let position = Vector3::new(
+                    (tex_coord.x - 0.5) * width,
+                    0.0,
+                    (tex_coord.y - 0.5) * depth,
+                );
+                Some((position, Vector3::y(), tex_coord))
+            }
+            ScatterSurface::Triangles(ref triangles) => {
+                let total = *areas.last()?;
+                if total <= 0.0 {
+                    return None;
+                }
+                // Triangles are picked by their area, so the instances spread out evenly.
+                let target = rng.next_f32() * total;
+                let index = areas
+                    .iter()
+                    .position(|&area| area > target)
+                    .unwrap_or(areas.len() - 1);
+                let triangle = &triangles[index];
+                let (r1, r2) = (rng.next_f32().sqrt(), rng.next_f32());
+                let weights = [1.0 - r1, r1 * (1.0 - r2), r1 * r2];
+                let position = (0..3).fold(Vector3::zeros(), |sum, i| {
+                    sum + triangle.positions[i] * weights[i]
+                });
+                let tex_coord = (0..3).fold(Vector2::zeros(), |sum, i| {
+                    sum + triangle.tex_coords[i] * weights[i]
+                });
+                Some((position, triangle.normal(), tex_coord))
+            }
+        }
+    }
+
+    /// Returns the running total of the areas of the triangles.
+    fn areas(&self) -> Vec<f32> {
+        match *self {
+            ScatterSurface::Rectangle { .. } => Vec::new(),
+            ScatterSurface::Triangles(ref triangles) => triangles
+                .iter()
+                .scan(0.0, |total, triangle| {
+                    *total += triangle.area();
+                    Some(*total)
+                })
+                .collect(),
+        }
+    }
+}
+
+/// An instance of the mesh of a `Scatter`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScatterInstance {
+    /// Position in the space of the entity.
+    pub position: Vector3<f32>,
+    /// Rotation in the space of the entity.
+    pub rotation: UnitQuaternion<f32>,
+    /// Uniform scale.
+    pub scale: f32,
+}
+
+/// Generates the instances of a `Scatter` over a surface.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let grass = ScatterBuilder::new(ScatterSurface::Rectangle { width: 100.0, depth: 100.0 }, 20_000)
+///     .with_density(meadow_density)
+///     .with_scale(0.8, 1.2)
+///     .build(grass_mesh, &mut rng.fork("grass"))
+///     .with_fade(40.0, 60.0)
+///     .with_wind(Wind::new(Vector3::x(), 0.2, 1.5));
+///
+/// world
+///     .create_entity()
+///     .with(grass)
+///     .with(grass_material)
+///     .with(GlobalTransform::default())
+///     .build();
+/// ```
+#[derive(Clone, Debug)]
+pub struct ScatterBuilder {
+    surface: ScatterSurface,
+    count: usize,
+    density: DensityMap,
+    scale: (f32, f32),
+    align_to_surface: bool,
+}
+
+impl ScatterBuilder {
+    /// Creates a builder placing up to `count` instances on the surface, where the density is
+    /// `1.0`.
+    pub fn new(surface: ScatterSurface, count: usize) -> Self {
+        ScatterBuilder {
+            surface,
+            count,
+            density: DensityMap::default(),
+            scale: (1.0, 1.0),
+            align_to_surface: false,
+        }
+    }
+
+    /// Sets the density of the instances over the texture coordinates of the surface.
+    pub fn with_density(mut self, density: DensityMap) -> Self {
+        self.density = density;
+        self
+    }
+
+    /// Sets the range the scale of each instance is picked from.
+    pub fn with_scale(mut self, min: f32, max: f32) -> Self {
+        self.scale = (min, max);
+        self
+    }
+
+    /// Sets whether the instances are tilted along the normal of the surface, e.g. for rocks,
+    /// instead of standing upright along the Y axis, e.g. for trees.
+    pub fn with_surface_alignment(mut self, align: bool) -> Self {
+        self.align_to_surface = align;
+        self
+    }
+
+    /// Generates the instances, turned randomly around their up axis.
+    pub fn generate(&self, rng: &mut RngStream) -> Vec<ScatterInstance> {
+        let areas = self.surface.areas();
+        let mut instances = Vec::new();
+        for _ in 0..self.count {
+            let (position, normal, tex_coord) = match self.surface.sample(&areas, rng) {
+                Some(sample) => sample,
+                None => break,
+            };
+            let yaw =
+                UnitQuaternion::from_axis_angle(&Vector3::y_axis(), rng.range_f32(0.0, 2.0 * PI));
+            let scale = rng.range_f32(self.scale.0, self.scale.1);
+            if rng.next_f32() >= self.density.sample(tex_coord) {
+                continue;
+            }
+            let rotation = if self.align_to_surface {
+                UnitQuaternion::rotation_between(&Vector3::y(), &normal)
+                    .unwrap_or_else(UnitQuaternion::identity)
+                    * yaw
+            } else {
+                yaw
+            };
+            instances.push(ScatterInstance {
+                position,
+                rotation,
+                scale,
+            });
+        }
+        instances
+    }
+
+    /// Generates the instances of a `Scatter` of the mesh.
+    pub fn build(&self, mesh: MeshHandle, rng: &mut RngStream) -> Scatter {
+        Scatter::new(mesh, self.generate(rng))
+    }
+}
+
+/// The sway of the instances of a `Scatter` in the wind.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Wind {
+    /// Direction the wind blows towards, in world space.
+    pub direction: Vector3<f32>,
+    /// How far the instances bend per unit of their height.
+    pub strength: f32,
+    /// Sways per second.
+    pub frequency: f32,
+}
+
+impl Wind {
+    /// Creates a wind.
+    pub fn new(direction: Vector3<f32>, strength: f32, frequency: f32) -> Self {
+        Wind {
+            direction,
+            strength,
+            frequency,
+        }
+    }
+}
+
+/// Draws a mesh at many instances placed relative to the entity, with the `Material` of the
+/// entity, by `DrawScatter`.
+///
+/// Instances are culled outside of the view and beyond the fade distance, and shrink away
+/// before it.
+#[derive(Clone, Debug)]
+pub struct Scatter {
+    /// Mesh drawn at every instance.
+    pub mesh: MeshHandle,
+    /// The instances, see `ScatterBuilder`.
+    pub instances: Vec<ScatterInstance>,
+    /// Radius around the origin of the mesh holding all of its vertices, at a scale of `1.0`.
+    pub bounds: f32,
+    /// Distances from the camera between which the instances shrink to nothing.
+    pub fade: (f32, f32),
+    /// Sway of the instances, `None` to keep them still.
+    pub wind: Option<Wind>,
+}
+
+impl Scatter {
+    /// Creates a scatter of a mesh at the instances.
+    pub fn new(mesh: MeshHandle, instances: Vec<ScatterInstance>) -> Self {
+        Scatter {
+            mesh,
+            instances,
+            bounds: 1.0,
+            fade: (std::f32::MAX, std::f32::MAX),
+            wind: None,
+        }
+    }
+
+    /// Sets the radius of the mesh.
+    pub fn with_bounds(mut self, bounds: f32) -> Self {
+        self.bounds = bounds;
+        self
+    }
+
+    /// Sets the distances from the camera between which the instances fade out.
+    pub fn with_fade(mut self, start: f32, end: f32) -> Self {
+        self.fade = (start, end);
+        self
+    }
+
+    /// Sets the sway of the instances.
+    pub fn with_wind(mut self, wind: Wind) -> Self {
+        self.wind = Some(wind);
+        self
+    }
+
+    /// Returns the instances seen from `eye`, with their scale shrunk by the fade.
+    pub(crate) fn visible(
+        &self,
+        model: &Matrix4<f32>,
+        eye: Option<&Point3<f32>>,
+        frustum: Option<&Frustum>,
+    ) -> Vec<ScatterInstance> {
+        let model_scale = (0..3)
+            .map(|i| model.column(i).xyz().norm())
+            .fold(0.0, f32::max);
+        self.instances
+            .iter()
+            .filter_map(|instance| {
+                let position =
+                    Point3::from((model * Point3::from(instance.position).to_homogeneous()).xyz());
+                let fade = match eye {
+                    Some(eye) => fade(&self.fade, (position - eye).norm()),
+                    None => 1.0,
+                };
+                let scale = instance.scale * fade;
+                let radius = self.bounds * scale * model_scale;
+                if scale <= 0.0
+                    || frustum.map_or(false, |frustum| {
+                        !frustum.intersects_sphere(&position, radius)
+                    })
+                {
+                    return None;
+                }
+                Some(ScatterInstance {
+                    scale,
+                    ..instance.clone()
+                })
+            })
+            .collect()
+    }
+}
+
+impl Component for Scatter {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Returns the scale of instances at a distance, from `1.0` before the fade to `0.0` after it.
+fn fade(&(start, end): &(f32, f32), distance: f32) -> f32 {
+    if distance <= start {
+        1.0
+    } else if distance >= end {
+        0.0
+    } else {
+        1.0 - (distance - start) / (end - start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn surface() -> ScatterSurface {
+        ScatterSurface::Rectangle {
+            width: 10.0,
+            depth: 10.0,
+        }
+    }
+
+    #[test]
+    fn density_map_sample() {
+        let map = DensityMap::new(2, 1, vec![0.0, 1.0]);
+        assert_eq!(0.0, map.sample(Vector2::new(0.0, 0.5)));
+        assert_eq!(0.5, map.sample(Vector2::new(0.5, 0.5)));
+        assert_eq!(1.0, map.sample(Vector2::new(2.0, 0.5)));
+        assert_eq!(
+            0.25,
+            DensityMap::uniform(0.25).sample(Vector2::new(0.3, 0.8))
+        );
+    }
+
+    #[test]
+    fn generate_in_density() {
+        let builder = ScatterBuilder::new(surface(), 1000)
+            .with_density(DensityMap::new(2, 1, vec![1.0, 0.0]))
+            .with_scale(0.5, 2.0);
+        let instances = builder.generate(&mut RngStream::new(3));
+        assert!(!instances.is_empty() && instances.len() < 1000);
+        for instance in &instances {
+            assert!(instance.position.x.abs() <= 5.0 && instance.position.z.abs() <= 5.0);
+            assert!(instance.scale >= 0.5 && instance.scale < 2.0);
+        }
+        // The density falls from the -X edge to zero at the +X edge.
+        let left = instances.iter().filter(|i| i.position.x < 0.0).count();
+        assert!(left > instances.len() * 2 / 3);
+        assert_eq!(instances, builder.generate(&mut RngStream::new(3)));
+    }
+
+    #[test]
+    fn generate_on_triangles() {
+        let triangle = ScatterTriangle {
+            positions: [
+                Vector3::new(0.0, 0.0, 0.0),
+                Vector3::new(0.0, 1.0, 0.0),
+                Vector3::new(1.0, 0.0, 0.0),
+            ],
+            tex_coords: [Vector2::zeros(); 3],
+        };
+        let instances = ScatterBuilder::new(ScatterSurface::Triangles(vec![triangle]), 10)
+            .with_surface_alignment(true)
+            .generate(&mut RngStream::new(5));
+        assert_eq!(10, instances.len());
+        for instance in &instances {
+            assert!(instance.position.z.abs() < 0.0001);
+            assert!(instance.position.x + instance.position.y <= 1.0001);
+            // Up along the normal of the triangle.
+            let up = instance.rotation * Vector3::y();
+            assert!((up.z.abs() - 1.0).abs() < 0.0001);
+        }
+    }
+
+    #[test]
+    fn fade_distances() {
+        assert_eq!(1.0, fade(&(10.0, 20.0), 5.0));
+        assert_eq!(0.5, fade(&(10.0, 20.0), 15.0));
+        assert_eq!(0.0, fade(&(10.0, 20.0), 25.0));
+    }
+}
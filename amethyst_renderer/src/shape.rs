@@ -17,8 +17,8 @@ use amethyst_core::{
 };
 
 use crate::{
-    ComboMeshCreator, Mesh, MeshData, MeshHandle, Normal, PosNormTangTex, PosNormTex, PosTex,
-    Position, Separate, Tangent, TexCoord,
+    Color, ComboMeshCreator, Mesh, MeshData, MeshHandle, Normal, PosColorNormTex, PosNormTangTex,
+    PosNormTex, PosTex, Position, Separate, Tangent, TexCoord,
 };
 
 /// Prefab for generating `Mesh` from basic shapes
@@ -28,6 +28,7 @@ use crate::{
 /// `V`: Vertex format to use, must be one of:
 ///     * `Vec<PosTex>`
 ///     * `Vec<PosNormTex>`
+///     * `Vec<PosColorNormTex>`
 ///     * `Vec<PosNormTangTex>`
 ///     * `ComboMeshCreator`
 #[derive(Clone, Serialize, Deserialize)]
@@ -37,6 +38,9 @@ pub struct ShapePrefab<V> {
     shape: Shape,
     #[serde(default)]
     shape_scale: Option<(f32, f32, f32)>,
+    /// Vertex color of the shape, white by default.
+    #[serde(default)]
+    shape_color: Option<[f32; 4]>,
     #[serde(skip)]
     _m: PhantomData<V>,
 }
@@ -71,11 +75,11 @@ where
         system_data: &mut <Self as PrefabData<'_>>::SystemData,
     ) -> Result<bool, PrefabError> {
         let (loader, _, mesh_storage) = system_data;
-        self.handle = Some(loader.load_from_data(
-            self.shape.generate::<V>(self.shape_scale),
-            progress,
-            &mesh_storage,
-        ));
+        let data = match self.shape_color {
+            Some(color) => self.shape.generate_with_color::<V>(self.shape_scale, color),
+            None => self.shape.generate::<V>(self.shape_scale),
+        };
+        self.handle = Some(loader.load_from_data(data, progress, &mesh_storage));
         Ok(true)
     }
 }
@@ -113,7 +117,7 @@ pub type VertexFormat = ([f32; 3], [f32; 3], [f32; 2], [f32; 3]);
 
 /// Internal Shape, used for transformation from `genmesh` to `MeshData`
 #[derive(Debug)]
-pub struct InternalShape(Vec<VertexFormat>);
+pub struct InternalShape(Vec<VertexFormat>, Option<[f32; 4]>);
 
 impl Shape {
     /// Generate `Mesh` for the `Shape`, and convert it into a `MeshHandle`.
@@ -129,6 +133,7 @@ impl Shape {
     /// `V`: Vertex format to use, must to be one of:
     ///     * `Vec<PosTex>`
     ///     * `Vec<PosNormTex>`
+    ///     * `Vec<PosColorNormTex>`
     ///     * `Vec<PosNormTangTex>`
     ///     * `ComboMeshCreator`
     pub fn upload<V, P>(
@@ -157,6 +162,7 @@ impl Shape {
     /// `V`: Vertex format to use, must to be one of:
     ///     * `Vec<PosTex>`
     ///     * `Vec<PosNormTex>`
+    ///     * `Vec<PosColorNormTex>`
     ///     * `Vec<PosNormTangTex>`
     ///     * `ComboMeshCreator`
     pub fn generate<V>(&self, scale: Option<(f32, f32, f32)>) -> MeshData
@@ -166,6 +172,31 @@ impl Shape {
         V::from(self.generate_internal(scale)).into()
     }
 
+    /// Generate `MeshData` for the `Shape`, with the same vertex color for every vertex
+    ///
+    /// ### Parameters:
+    ///
+    /// - `scale`: Scale the shape by the given amounts along the x, y, z axes
+    /// - `color`: RGBA vertex color, used by the formats with a `Color` attribute
+    ///
+    /// ### Type parameters:
+    ///
+    /// `V`: Vertex format to use, must to be one of:
+    ///     * `Vec<PosColorNormTex>`
+    ///     * `ComboMeshCreator`
+    pub fn generate_with_color<V>(
+        &self,
+        scale: Option<(f32, f32, f32)>,
+        color: [f32; 4],
+    ) -> MeshData
+    where
+        V: From<InternalShape> + Into<MeshData>,
+    {
+        let mut shape = self.generate_internal(scale);
+        shape.1 = Some(color);
+        V::from(shape).into()
+    }
+
     /// Generate vertices for the `Shape`, in format `V`
     ///
     /// ### Parameters:
@@ -177,6 +208,7 @@ impl Shape {
     /// `V`: Vertex format to use, must to be one of:
     ///     * `Vec<PosTex>`
     ///     * `Vec<PosNormTex>`
+    ///     * `Vec<PosColorNormTex>`
     ///     * `Vec<PosNormTangTex>`
     ///     * `ComboMeshCreator`
     pub fn generate_vertices<V>(&self, scale: Option<(f32, f32, f32)>) -> V
@@ -216,7 +248,7 @@ impl Shape {
             ),
             Shape::Circle(u) => generate_vertices(Circle::new(u), scale),
         };
-        InternalShape(vertices)
+        InternalShape(vertices, None)
     }
 }
 
@@ -283,6 +315,22 @@ impl From<InternalShape> for Vec<PosNormTex> {
     }
 }
 
+impl From<InternalShape> for Vec<PosColorNormTex> {
+    fn from(shape: InternalShape) -> Self {
+        let color = shape.1.unwrap_or([1.0; 4]);
+        shape
+            .0
+            .iter()
+            .map(|v| PosColorNormTex {
+                position: Vector3::new(v.0[0], v.0[1], v.0[2]),
+                color,
+                normal: Vector3::new(v.1[0], v.1[1], v.1[2]),
+                tex_coord: Vector2::new(v.2[0], v.2[1]),
+            })
+            .collect()
+    }
+}
+
 impl From<InternalShape> for Vec<PosNormTangTex> {
     fn from(shape: InternalShape) -> Self {
         shape
@@ -306,7 +354,13 @@ impl From<InternalShape> for ComboMeshCreator {
                 .iter()
                 .map(|v| Separate::<Position>::new(v.0))
                 .collect(),
-            None,
+            shape.1.map(|color| {
+                shape
+                    .0
+                    .iter()
+                    .map(|_| Separate::<Color>::new(color))
+                    .collect()
+            }),
             Some(
                 shape
                     .0
@@ -336,6 +390,19 @@ impl From<InternalShape> for ComboMeshCreator {
 mod tests {
     use super::*;
 
+    #[test]
+    fn colored_vertices() {
+        let data =
+            Shape::Cube.generate_with_color::<Vec<PosColorNormTex>>(None, [1.0, 0.0, 0.0, 1.0]);
+        match data {
+            MeshData::PosColorNormTex(vertices) => {
+                assert!(!vertices.is_empty());
+                assert!(vertices.iter().all(|v| v.color == [1.0, 0.0, 0.0, 1.0]));
+            }
+            _ => panic!("Expected `PosColorNormTex` vertices"),
+        }
+    }
+
     #[test]
     fn test_plane() {
         println!(
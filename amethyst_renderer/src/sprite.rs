@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use ron::de::from_bytes as from_ron_bytes;
 
 use amethyst_assets::{
@@ -20,6 +22,36 @@ pub struct SpriteSheet {
     pub texture: Handle<Texture>,
     /// A list of sprites in this sprite sheet.
     pub sprites: Vec<Sprite>,
+    /// Indices of the sprites that have a name.
+    names: HashMap<String, usize>,
+}
+
+impl SpriteSheet {
+    /// Creates a sprite sheet without sprite names.
+    pub fn new(texture: Handle<Texture>, sprites: Vec<Sprite>) -> Self {
+        SpriteSheet {
+            texture,
+            sprites,
+            names: HashMap::new(),
+        }
+    }
+
+    /// Sets the names of the sprites, mapped to their indices in `sprites`.
+    pub fn with_names(mut self, names: HashMap<String, usize>) -> Self {
+        self.names = names;
+        self
+    }
+
+    /// Returns the index of the sprite with the given name, to use as the `sprite_number` of a
+    /// `SpriteRender`.
+    pub fn sprite_number(&self, name: &str) -> Option<usize> {
+        self.names.get(name).cloned()
+    }
+
+    /// Returns the sprite with the given name.
+    pub fn sprite(&self, name: &str) -> Option<&Sprite> {
+        self.sprite_number(name).map(|index| &self.sprites[index])
+    }
 }
 
 impl Asset for SpriteSheet {
@@ -145,6 +177,52 @@ impl Sprite {
             tex_coords,
         }
     }
+
+    /// Returns the offsets placing the entity at the pivot of a sprite of the given pixel size.
+    ///
+    /// # Parameters
+    ///
+    /// * `sprite_w`: Width of the sprite.
+    /// * `sprite_h`: Height of the sprite.
+    /// * `trim`: Where the sprite was in its frame before transparent pixels were trimmed off when
+    ///           packing the sprite sheet, or `None` if it wasn't trimmed.
+    /// * `pivot`: Position of the pivot in the untrimmed frame, normalized from 0.0 to 1.0 and
+    ///            starting from the top left. `[0.5, 0.5]` is the center.
+    pub fn pivot_offsets(
+        sprite_w: u32,
+        sprite_h: u32,
+        trim: Option<&SpriteTrim>,
+        pivot: [f32; 2],
+    ) -> [f32; 2] {
+        let (sprite_w, sprite_h) = (sprite_w as f32, sprite_h as f32);
+        let (left, top, frame_w, frame_h) = match trim {
+            Some(trim) => (
+                trim.x as f32,
+                trim.y as f32,
+                trim.source_width as f32,
+                trim.source_height as f32,
+            ),
+            None => (0., 0., sprite_w, sprite_h),
+        };
+        // The sprite is drawn centered on the entity minus the offsets, and pixel y goes down.
+        [
+            pivot[0] * frame_w - left - sprite_w / 2.,
+            top + sprite_h / 2. - pivot[1] * frame_h,
+        ]
+    }
+}
+
+/// Position of a trimmed sprite in its original frame, in pixels from the top left.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpriteTrim {
+    /// Horizontal position of the sprite in the frame
+    pub x: u32,
+    /// Vertical position of the sprite in the frame
+    pub y: u32,
+    /// Width of the frame
+    pub source_width: u32,
+    /// Height of the frame
+    pub source_height: u32,
 }
 
 impl From<((f32, f32), [f32; 4])> for Sprite {
@@ -216,6 +294,12 @@ struct SpritePosition {
     pub height: u32,
     /// Number of pixels to shift the sprite to the left and down relative to the entity holding it
     pub offsets: Option<[f32; 2]>,
+    /// Name to look the sprite up by
+    pub name: Option<String>,
+    /// Normalized position of the entity in the untrimmed sprite, used when there are no offsets
+    pub pivot: Option<[f32; 2]>,
+    /// Position of the sprite in its frame if it was trimmed
+    pub trim: Option<SpriteTrim>,
 }
 
 /// Structure acting as scaffolding for serde when loading a spritesheet file.
@@ -259,11 +343,22 @@ struct SerializedSpriteSheet {
 ///             y: 0.0,
 ///             width: 32.0,
 ///             height: 16.0,
+///             // Name to look the sprite up by with `SpriteSheet::sprite_number`, optional
+///             name: "door",
+///             // Position of the entity on the sprite when there are no offsets, normalized from
+///             // the top left corner, optional and defaults to the center (0.5, 0.5)
+///             pivot: (0.5, 1.0),
+///             // If the sprite was trimmed when packing the sheet, where it was in its original
+///             // frame and the size of that frame, optional
+///             trim: (x: 2, y: 0, source_width: 36, source_height: 16),
 ///         ),
 ///     ],
 /// )
 /// ```
 ///
+/// `name`, `pivot` and `trim` need `#![enable(implicit_some)]` at the top of the file to be
+/// written without `Some`.
+///
 /// Such a spritesheet description can be loaded using a `Loader` by passing it the handle of the corresponding loaded texture.
 /// ```rust,no_run
 /// # extern crate amethyst_assets;
@@ -308,7 +403,16 @@ impl SimpleFormat<SpriteSheet> for SpriteSheetFormat {
             ))
         })?;
         let mut sprites: Vec<Sprite> = Vec::with_capacity(sheet.sprites.len());
+        let mut names = HashMap::new();
         for sp in sheet.sprites {
+            let offsets = sp.offsets.unwrap_or_else(|| {
+                Sprite::pivot_offsets(
+                    sp.width,
+                    sp.height,
+                    sp.trim.as_ref(),
+                    sp.pivot.unwrap_or([0.5; 2]),
+                )
+            });
             let sprite = Sprite::from_pixel_values(
                 sheet.spritesheet_width as u32,
                 sheet.spritesheet_height as u32,
@@ -316,17 +420,194 @@ impl SimpleFormat<SpriteSheet> for SpriteSheetFormat {
                 sp.height as u32,
                 sp.x as u32,
                 sp.y as u32,
-                sp.offsets.unwrap_or([0.0; 2]),
+                offsets,
             );
+            if let Some(name) = sp.name {
+                if names.insert(name, sprites.len()).is_some() {
+                    return Err(AssetsError::from_kind(AssetsErrorKind::Format(
+                        "Duplicate sprite name in SpriteSheet",
+                    )));
+                }
+            }
             sprites.push(sprite);
         }
-        Ok(SpriteSheet { texture, sprites })
+        Ok(SpriteSheet {
+            texture,
+            sprites,
+            names,
+        })
+    }
+}
+
+/// Allows loading of sprite sheets in the JSON format of TexturePacker, which is also the format of
+/// the JSON data Aseprite exports with sprite sheets.
+///
+/// Both the "Hash" and the "Array" variants are supported. Sprites are numbered in the order of the
+/// file and named by their frame name, so they can be looked up with `SpriteSheet::sprite_number`.
+/// Trimmed sprites are placed as they were in their untrimmed frame, and the entity is at the pivot
+/// of the frame, or at its center if the file has no pivots. Rotated sprites aren't supported.
+///
+/// Like `SpriteSheetFormat`, it takes the handle of the loaded texture as options.
+#[cfg(feature = "json")]
+#[derive(Clone, Deserialize, Serialize)]
+pub struct TexturePackerFormat;
+
+#[cfg(feature = "json")]
+impl SimpleFormat<SpriteSheet> for TexturePackerFormat {
+    const NAME: &'static str = "TEXTURE_PACKER";
+
+    type Options = Handle<Texture>;
+
+    fn import(&self, bytes: Vec<u8>, texture: Self::Options) -> AssetsResult<SpriteSheet> {
+        let sheet: json::JsonSpriteSheet = serde_json::from_slice(&bytes).map_err(|_| {
+            AssetsError::from_kind(AssetsErrorKind::Format(
+                "Failed to parse Json file for SpriteSheet",
+            ))
+        })?;
+        sheet.into_sprite_sheet(texture)
+    }
+}
+
+/// Scaffolding for serde when loading a TexturePacker sprite sheet.
+#[cfg(feature = "json")]
+pub(crate) mod json {
+    use std::{collections::HashMap, fmt};
+
+    use serde::de::{Deserializer, MapAccess, SeqAccess, Visitor};
+
+    use amethyst_assets::{
+        Error as AssetsError, ErrorKind as AssetsErrorKind, Handle, Result as AssetsResult,
+    };
+
+    use super::{Sprite, SpriteSheet, SpriteTrim};
+    use crate::Texture;
+
+    #[derive(Clone, Copy, Debug, Deserialize)]
+    pub(crate) struct JsonRect {
+        pub x: u32,
+        pub y: u32,
+        pub w: u32,
+        pub h: u32,
+    }
+
+    #[derive(Clone, Copy, Debug, Deserialize)]
+    pub(crate) struct JsonSize {
+        pub w: u32,
+        pub h: u32,
+    }
+
+    #[derive(Clone, Copy, Debug, Deserialize)]
+    pub(crate) struct JsonPoint {
+        pub x: f32,
+        pub y: f32,
+    }
+
+    #[derive(Clone, Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub(crate) struct JsonFrame {
+        /// Only in the "Array" variant, the key of the frame in the "Hash" variant.
+        #[serde(default)]
+        pub filename: String,
+        pub frame: JsonRect,
+        #[serde(default)]
+        pub rotated: bool,
+        #[serde(default)]
+        pub trimmed: bool,
+        pub sprite_source_size: Option<JsonRect>,
+        pub source_size: Option<JsonSize>,
+        pub pivot: Option<JsonPoint>,
+    }
+
+    /// Frames in the order of the file, from either a map or a list.
+    #[derive(Clone, Debug)]
+    pub(crate) struct JsonFrames(pub Vec<JsonFrame>);
+
+    impl<'de> serde::Deserialize<'de> for JsonFrames {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct FramesVisitor;
+
+            impl<'de> Visitor<'de> for FramesVisitor {
+                type Value = JsonFrames;
+
+                fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    formatter.write_str("a map or a list of frames")
+                }
+
+                fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<JsonFrames, A::Error> {
+                    let mut frames = Vec::new();
+                    while let Some(frame) = seq.next_element()? {
+                        frames.push(frame);
+                    }
+                    Ok(JsonFrames(frames))
+                }
+
+                fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<JsonFrames, A::Error> {
+                    let mut frames = Vec::new();
+                    while let Some((filename, mut frame)) = map.next_entry::<String, JsonFrame>()? {
+                        frame.filename = filename;
+                        frames.push(frame);
+                    }
+                    Ok(JsonFrames(frames))
+                }
+            }
+
+            deserializer.deserialize_any(FramesVisitor)
+        }
+    }
+
+    #[derive(Clone, Debug, Deserialize)]
+    pub(crate) struct JsonMeta {
+        pub size: JsonSize,
+    }
+
+    #[derive(Clone, Debug, Deserialize)]
+    pub(crate) struct JsonSpriteSheet {
+        pub frames: JsonFrames,
+        pub meta: JsonMeta,
+    }
+
+    impl JsonSpriteSheet {
+        pub fn into_sprite_sheet(self, texture: Handle<Texture>) -> AssetsResult<SpriteSheet> {
+            let size = self.meta.size;
+            let mut sprites = Vec::with_capacity(self.frames.0.len());
+            let mut names = HashMap::new();
+            for frame in self.frames.0 {
+                if frame.rotated {
+                    return Err(AssetsError::from_kind(AssetsErrorKind::Format(
+                        "Rotated sprites are not supported in SpriteSheet",
+                    )));
+                }
+                let rect = frame.frame;
+                let trim = match (frame.trimmed, frame.sprite_source_size, frame.source_size) {
+                    (true, Some(source_rect), Some(source_size)) => Some(SpriteTrim {
+                        x: source_rect.x,
+                        y: source_rect.y,
+                        source_width: source_size.w,
+                        source_height: source_size.h,
+                    }),
+                    _ => None,
+                };
+                let pivot = frame.pivot.map_or([0.5; 2], |pivot| [pivot.x, pivot.y]);
+                let offsets = Sprite::pivot_offsets(rect.w, rect.h, trim.as_ref(), pivot);
+                if !frame.filename.is_empty() {
+                    names.insert(frame.filename, sprites.len());
+                }
+                sprites.push(Sprite::from_pixel_values(
+                    size.w, size.h, rect.w, rect.h, rect.x, rect.y, offsets,
+                ));
+            }
+            Ok(SpriteSheet {
+                texture,
+                sprites,
+                names,
+            })
+        }
     }
 }
 
 #[cfg(test)]
 mod test {
-    use super::{Sprite, TextureCoordinates};
+    use super::{Sprite, SpriteTrim, TextureCoordinates};
 
     #[test]
     fn texture_coordinates_from_tuple_maps_fields_correctly() {
@@ -411,4 +692,55 @@ mod test {
             )
         );
     }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn texture_packer_sheet_names_sprites() {
+        use std::sync::Arc;
+
+        use amethyst_assets::{AssetStorage, Loader, SimpleFormat};
+        use rayon::ThreadPoolBuilder;
+
+        use crate::{formats::TextureData, Texture, TextureMetadata};
+
+        use super::TexturePackerFormat;
+
+        let loader = Loader::new(".", Arc::new(ThreadPoolBuilder::default().build().unwrap()));
+        let storage = AssetStorage::<Texture>::new();
+        let meta = TextureMetadata::srgb().with_size(1, 1);
+        let texture = loader.load_from_data(TextureData::U8(vec![0; 4], meta), (), &storage);
+
+        let json = r#"{
+            "frames": {
+                "idle 0.png": { "frame": { "x": 0, "y": 0, "w": 16, "h": 16 } },
+                "idle 1.png": { "frame": { "x": 16, "y": 0, "w": 16, "h": 16 } }
+            },
+            "meta": { "size": { "w": 32, "h": 16 } }
+        }"#;
+        let sheet = TexturePackerFormat
+            .import(json.as_bytes().to_vec(), texture)
+            .unwrap();
+        assert_eq!(2, sheet.sprites.len());
+        assert_eq!(Some(1), sheet.sprite_number("idle 1.png"));
+        assert_eq!(Some(&sheet.sprites[0]), sheet.sprite("idle 0.png"));
+        assert_eq!(None, sheet.sprite_number("run 0.png"));
+    }
+
+    #[test]
+    fn pivot_offsets_account_for_trimmed_pixels() {
+        assert_eq!([0., 0.], Sprite::pivot_offsets(10, 20, None, [0.5, 0.5]));
+        assert_eq!([-5., -10.], Sprite::pivot_offsets(10, 20, None, [0., 1.]));
+
+        // A 10x10 sprite trimmed from the bottom right of a 20x20 frame, pivot at the bottom center.
+        let trim = SpriteTrim {
+            x: 10,
+            y: 10,
+            source_width: 20,
+            source_height: 20,
+        };
+        assert_eq!(
+            [-5., -5.],
+            Sprite::pivot_offsets(10, 10, Some(&trim), [0.5, 1.])
+        );
+    }
 }
@@ -5,7 +5,7 @@ use std::{mem, sync::Arc};
 
 use winit::{DeviceEvent, Event, WindowEvent};
 
-use amethyst_assets::{AssetStorage, HotReloadStrategy};
+use amethyst_assets::{AssetStorage, HotReloadStrategy, ProcessingState};
 use amethyst_core::{
     shrev::EventChannel,
     specs::prelude::{Read, ReadExpect, Resources, RunNow, SystemData, Write, WriteExpect},
@@ -13,16 +13,22 @@ use amethyst_core::{
 };
 
 use crate::{
+    backend::RenderEvent,
+    capture::{CapturedFrame, FrameCapture},
+    compute::ComputePasses,
     config::DisplayConfig,
+    dynamic_tex::TextureUpdates,
     error::Result,
     formats::{create_mesh_asset, create_texture_asset},
     mesh::Mesh,
-    mtl::{Material, MaterialDefaults},
+    mtl::{CullMode, Material, MaterialDefaults},
     pipe::{PipelineBuild, PipelineData, PolyPipeline},
+    pipeline_dump::PipelineDump,
     rayon::ThreadPool,
     renderer::Renderer,
     resources::{ScreenDimensions, WindowMessages},
-    tex::Texture,
+    tex::{Texture, TextureBuilder},
+    transition::ScreenTransition,
 };
 
 /// Rendering system.
@@ -36,6 +42,9 @@ pub struct RenderSystem<P> {
     // This only exists to allow the system to re-use a vec allocation
     // during event compression.  It's length 0 except during `fn render`.
     event_vec: Vec<Event>,
+    /// Builds the pipeline again after the device is lost, when device recovery is enabled.
+    #[derivative(Debug = "ignore")]
+    rebuild: Option<Box<dyn FnMut(&mut Renderer) -> Result<P>>>,
 }
 
 impl<P> RenderSystem<P>
@@ -84,9 +93,31 @@ where
             renderer,
             cached_size,
             event_vec: Vec::with_capacity(20),
+            rebuild: None,
         }
     }
 
+    /// Enables device recovery: when the device is lost, the renderer is recreated and the
+    /// pipeline built again with `rebuild`.
+    ///
+    /// The data of every `Mesh` and `Texture` asset loaded from now on is kept in memory to
+    /// upload them again.
+    pub fn with_device_recovery<F>(mut self, rebuild: F) -> Self
+    where
+        F: FnMut(&mut Renderer) -> Result<P> + 'static,
+    {
+        self.with_boxed_device_recovery(Box::new(rebuild))
+    }
+
+    /// Enables device recovery with a `rebuild` already boxed, see `with_device_recovery`.
+    pub(crate) fn with_boxed_device_recovery(
+        mut self,
+        rebuild: Box<dyn FnMut(&mut Renderer) -> Result<P>>,
+    ) -> Self {
+        self.rebuild = Some(rebuild);
+        self
+    }
+
     fn asset_loading(
         &mut self,
         (time, pool, strategy, mut mesh_storage, mut texture_storage): AssetLoadingData<'_>,
@@ -94,22 +125,117 @@ where
         use std::ops::Deref;
 
         let strategy = strategy.as_ref().map(Deref::deref);
+        let keep_sources = self.rebuild.is_some();
 
         mesh_storage.process(
-            |d| create_mesh_asset(d, &mut self.renderer),
+            |d| {
+                let source = if keep_sources { Some(d.clone()) } else { None };
+                let state = create_mesh_asset(d, &mut self.renderer)?;
+                Ok(match (state, source) {
+                    (ProcessingState::Loaded(mut mesh), Some(source)) => {
+                        mesh.set_source(source);
+                        ProcessingState::Loaded(mesh)
+                    }
+                    (state, _) => state,
+                })
+            },
             time.frame_number(),
             &**pool,
             strategy,
         );
 
         texture_storage.process(
-            |d| create_texture_asset(d, &mut self.renderer),
+            |d| {
+                let source = if keep_sources { Some(d.clone()) } else { None };
+                let state = create_texture_asset(d, &mut self.renderer)?;
+                Ok(match (state, source) {
+                    (ProcessingState::Loaded(mut texture), Some(source)) => {
+                        texture.set_source(source);
+                        ProcessingState::Loaded(texture)
+                    }
+                    (state, _) => state,
+                })
+            },
             time.frame_number(),
             &**pool,
             strategy,
         );
     }
 
+    /// Uploads the queued texture updates, keeping the ones of textures which aren't loaded yet.
+    fn texture_updates(&mut self, (texture_storage, mut updates): TextureUpdateData<'_>) {
+        if self.renderer.is_lost() {
+            return;
+        }
+        let renderer = &mut self.renderer;
+        updates
+            .updates
+            .retain(|update| match texture_storage.get(&update.texture) {
+                Some(texture) => {
+                    if let Err(e) =
+                        renderer.update_texture(texture, update.offset, update.size, &update.data)
+                    {
+                        error!("Failed to update texture: {}", e);
+                    }
+                    false
+                }
+                None => true,
+            });
+    }
+
+    /// Recreates the renderer after the device was lost, with the pipeline and the assets.
+    fn recover(
+        &mut self,
+        (mut events, mut mesh_storage, mut texture_storage, compute): RecoveryData<'_>,
+    ) {
+        if !self.renderer.is_lost() {
+            return;
+        }
+        let rebuild = match self.rebuild {
+            Some(ref mut rebuild) => rebuild,
+            None => return,
+        };
+        if let Err(e) = self.renderer.recreate() {
+            error!("Failed to recreate the renderer: {}", e);
+            return;
+        }
+        match rebuild(&mut self.renderer) {
+            Ok(pipe) => self.pipe = pipe,
+            Err(e) => {
+                error!("Failed to rebuild the pipeline: {}", e);
+                return;
+            }
+        }
+
+        compute.reset();
+        let renderer = &mut self.renderer;
+        for mesh in mesh_storage.iter_mut() {
+            if let Some(source) = mesh.source().cloned() {
+                match create_mesh_asset(source.clone(), renderer) {
+                    Ok(ProcessingState::Loaded(new)) => {
+                        *mesh = new;
+                        mesh.set_source(source);
+                    }
+                    Ok(ProcessingState::Loading(_)) => {}
+                    Err(e) => error!("Failed to upload mesh again: {}", e),
+                }
+            }
+        }
+        for texture in texture_storage.iter_mut() {
+            if let Some(source) = texture.source().cloned() {
+                match create_texture_asset(source.clone(), renderer) {
+                    Ok(ProcessingState::Loaded(new)) => {
+                        *texture = new;
+                        texture.set_source(source);
+                    }
+                    Ok(ProcessingState::Loading(_)) => {}
+                    Err(e) => error!("Failed to upload texture again: {}", e),
+                }
+            }
+        }
+        events.single_write(RenderEvent::DeviceRestored);
+    }
+
     fn window_management(&mut self, (mut window_messages, mut screen_dimensions): WindowData<'_>) {
         // Process window commands
         for mut command in window_messages.queue.drain() {
@@ -144,13 +270,71 @@ where
         screen_dimensions.update_hidpi_factor(hidpi);
     }
 
-    fn render(&mut self, (mut event_handler, data): RenderData<'_, P>) {
+    /// Moves the running screen transition on, and checks whether a new one needs the frame.
+    fn transition(&mut self, (time, mut transition): TransitionData<'_>) -> bool {
+        transition.advance(time.delta_real_seconds());
+        transition.needs_frame()
+    }
+
+    /// Starts drawing the screen transition over the copy of the frame.
+    fn begin_transition(
+        &mut self,
+        (_, mut transition): TransitionData<'_>,
+        frame: Option<CapturedFrame>,
+    ) {
+        let texture = frame.map(|frame| {
+            self.renderer.create_texture(
+                TextureBuilder::new(frame.pixels)
+                    .with_size(frame.width as u16, frame.height as u16),
+            )
+        });
+        match texture {
+            Some(Ok(texture)) => transition.begin(texture),
+            Some(Err(e)) => {
+                error!("Failed to keep the frame for the screen transition: {}", e);
+                transition.cancel();
+            }
+            None => transition.cancel(),
+        }
+    }
+
+    /// Dispatches the compute passes before the frame is drawn.
+    fn compute(&mut self, (textures, passes): ComputeData<'_>) {
+        self.renderer.dispatch_compute(&passes, &textures);
+    }
+
+    /// Writes the requested dumps of the pipeline.
+    fn dump(&mut self, mut dump: DumpData<'_>) {
+        if dump.is_pending() {
+            dump.complete(self.pipe.describe());
+        }
+    }
+
+    /// Draws the frame, returning a copy of it when `copy_frame` is set.
+    fn render(
+        &mut self,
+        (mut event_handler, mut render_events, mut capture, data): RenderData<'_, P>,
+        copy_frame: bool,
+    ) -> Option<CapturedFrame> {
+        let requested = capture.take_request();
+        if requested || copy_frame {
+            self.renderer.request_capture();
+        }
+        let was_lost = self.renderer.is_lost();
         self.renderer.draw(&mut self.pipe, data);
+        if self.renderer.is_lost() && !was_lost {
+            render_events.single_write(RenderEvent::DeviceLost);
+        }
+        let frame = self.renderer.take_capture();
+        if let (Some(frame), true) = (frame.as_ref(), requested) {
+            capture.complete(frame.clone());
+        }
         let events = &mut self.event_vec;
         self.renderer.events_mut().poll_events(|new_event| {
             compress_events(events, new_event);
         });
         event_handler.iter_write(events.drain(..));
+        frame.filter(|_| copy_frame)
     }
 }
 
@@ -162,10 +346,27 @@ type AssetLoadingData<'a> = (
     Write<'a, AssetStorage<Texture>>,
 );
 
+type TextureUpdateData<'a> = (Read<'a, AssetStorage<Texture>>, Write<'a, TextureUpdates>);
+
 type WindowData<'a> = (Write<'a, WindowMessages>, WriteExpect<'a, ScreenDimensions>);
 
+type RecoveryData<'a> = (
+    Write<'a, EventChannel<RenderEvent>>,
+    Write<'a, AssetStorage<Mesh>>,
+    Write<'a, AssetStorage<Texture>>,
+    Read<'a, ComputePasses>,
+);
+
+type TransitionData<'a> = (Read<'a, Time>, Write<'a, ScreenTransition>);
+
+type ComputeData<'a> = (Read<'a, AssetStorage<Texture>>, Read<'a, ComputePasses>);
+
+type DumpData<'a> = Write<'a, PipelineDump>;
+
 type RenderData<'a, P> = (
     Write<'a, EventChannel<Event>>,
+    Write<'a, EventChannel<RenderEvent>>,
+    Write<'a, FrameCapture>,
     <P as PipelineData<'a>>::Data,
 );
 
@@ -177,14 +378,27 @@ where
         #[cfg(feature = "profiler")]
         profile_scope!("render_system");
         self.asset_loading(AssetLoadingData::fetch(res));
+        self.texture_updates(TextureUpdateData::fetch(res));
         self.window_management(WindowData::fetch(res));
-        self.render(RenderData::<P>::fetch(res));
+        self.dump(DumpData::fetch(res));
+        let copy_frame = self.transition(TransitionData::fetch(res));
+        self.compute(ComputeData::fetch(res));
+        let frame = self.render(RenderData::<P>::fetch(res), copy_frame);
+        if copy_frame {
+            self.begin_transition(TransitionData::fetch(res), frame);
+        }
+        self.recover(RecoveryData::fetch(res));
     }
 
     fn setup(&mut self, res: &mut Resources) {
         AssetLoadingData::setup(res);
+        TextureUpdateData::setup(res);
         WindowData::setup(res);
+        TransitionData::setup(res);
+        ComputeData::setup(res);
+        DumpData::setup(res);
         RenderData::<P>::setup(res);
+        RecoveryData::setup(res);
 
         let mat = create_default_mat(res);
         res.insert(MaterialDefaults(mat));
@@ -196,11 +410,13 @@ where
             .into();
         let hidpi = self.renderer.window().get_hidpi_factor();
         res.insert(ScreenDimensions::new(width, height, hidpi));
+        res.insert(self.renderer.backend().clone());
+        res.insert(self.renderer.gpu().clone());
     }
 }
 
 fn create_default_mat(res: &mut Resources) -> Material {
-    use crate::mtl::TextureOffset;
+    use crate::{formats::TextureData, mtl::TextureOffset};
 
     use amethyst_assets::Loader;
 
@@ -208,11 +424,11 @@ fn create_default_mat(res: &mut Resources) -> Material {
 
     let albedo = [0.5, 0.5, 0.5, 1.0].into();
     let emission = [0.0; 4].into();
-    let normal = [0.5, 0.5, 1.0, 1.0].into();
-    let metallic = [0.0; 4].into();
-    let roughness = [0.5; 4].into();
-    let ambient_occlusion = [1.0; 4].into();
-    let caveat = [1.0; 4].into();
+    let normal = TextureData::linear([0.5, 0.5, 1.0, 1.0]);
+    let metallic = TextureData::linear([0.0; 4]);
+    let roughness = TextureData::linear([0.5; 4]);
+    let ambient_occlusion = TextureData::linear([1.0; 4]);
+    let caveat = TextureData::linear([1.0; 4]);
 
     let tex_storage = res.fetch();
 
@@ -226,10 +442,12 @@ fn create_default_mat(res: &mut Resources) -> Material {
 
     Material {
         alpha_cutoff: 0.01,
+        cull_mode: CullMode::Back,
         albedo,
         albedo_offset: TextureOffset::default(),
         emission,
         emission_offset: TextureOffset::default(),
+        emission_intensity: 1.0,
         normal,
         normal_offset: TextureOffset::default(),
         metallic,
@@ -240,6 +458,7 @@ fn create_default_mat(res: &mut Resources) -> Material {
         ambient_occlusion_offset: TextureOffset::default(),
         caveat,
         caveat_offset: TextureOffset::default(),
+        params: Default::default(),
     }
 }
 
@@ -8,28 +8,38 @@ pub use gfx::{
 use std::marker::PhantomData;
 
 use gfx::{
-    texture::{Info, Mipmap},
+    format::{Formatted, SurfaceTyped},
+    texture::{Info, Mipmap, NewImageInfo},
     traits::Pod,
 };
 
 use amethyst_assets::{Asset, Handle};
-use amethyst_core::specs::prelude::DenseVecStorage;
+use amethyst_core::{specs::prelude::DenseVecStorage, MemorySize, MemoryUsage};
 
 use crate::{
-    error::Result,
+    color::ColorSpace,
+    error::{Error, Result},
     formats::TextureData,
-    types::{ChannelFormat, Factory, RawShaderResourceView, RawTexture, Sampler, SurfaceFormat},
+    types::{
+        ChannelFormat, DepthFormat, DepthStencilView, Encoder, Factory, RawShaderResourceView,
+        RawTexture, RenderTargetView, Resources, Sampler, SurfaceFormat,
+    },
 };
 
 /// A handle to a `Texture` asset.
 pub type TextureHandle = Handle<Texture>;
 
 /// Handle to a GPU texture resource.
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[derive(Derivative)]
+#[derivative(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct Texture {
     sampler: Sampler,
     texture: RawTexture,
+    channel: ChannelType,
     view: RawShaderResourceView,
+    /// The data the texture was built from, kept to build it again if the device is lost.
+    #[derivative(Debug = "ignore", Hash = "ignore", PartialEq = "ignore")]
+    source: Option<TextureData>,
 }
 
 impl Texture {
@@ -53,11 +63,185 @@ impl Texture {
         &self.view
     }
 
+    /// Returns the color space of the texels, `Srgb` when they're decoded to linear on sampling.
+    pub fn color_space(&self) -> ColorSpace {
+        match self.channel {
+            ChannelType::Srgb => ColorSpace::Srgb,
+            _ => ColorSpace::Linear,
+        }
+    }
+
+    /// Returns the GL name and the image format of the texture, to bind it to an image unit of
+    /// a compute shader, if its format can be bound.
+    #[cfg(feature = "opengl")]
+    pub(crate) fn image_binding(&self) -> Option<(u32, u32)> {
+        use gfx_device_gl::NewTexture;
+        use gfx_gl as gl;
+
+        let format = match (self.texture.get_info().format, self.channel) {
+            (SurfaceType::R8_G8_B8_A8, ChannelType::Unorm) => gl::RGBA8,
+            (SurfaceType::R16_G16_B16_A16, ChannelType::Float) => gl::RGBA16F,
+            (SurfaceType::R32_G32_B32_A32, ChannelType::Float) => gl::RGBA32F,
+            _ => return None,
+        };
+        match *self.texture.resource() {
+            NewTexture::Texture(name) => Some((name, format)),
+            NewTexture::Surface(_) => None,
+        }
+    }
+
+    /// Returns the data the texture was built from, if it was kept.
+    pub(crate) fn source(&self) -> Option<&TextureData> {
+        self.source.as_ref()
+    }
+
+    /// Keeps the data the texture was built from.
+    pub(crate) fn set_source(&mut self, source: TextureData) {
+        self.source = Some(source);
+    }
+
+    /// Creates views to draw into the texture, with a new depth buffer of its size. The texture
+    /// has to be built as a render target with the `R8_G8_B8_A8` format and `Unorm` channel type
+    /// of the main target.
+    pub fn render_target_views(
+        &self,
+        factory: &mut Factory,
+    ) -> Result<(RenderTargetView, DepthStencilView)> {
+        use gfx::{memory::Typed, texture::RenderDesc, CombinedError, Factory};
+
+        let desc = RenderDesc {
+            channel: self.channel,
+            level: 0,
+            layer: None,
+        };
+        let view = factory
+            .view_texture_as_render_target_raw(&self.texture, desc)
+            .map_err(CombinedError::from)?;
+        let (w, h) = self.size();
+        let depth = factory.create_depth_stencil_view_only::<DepthFormat>(w as u16, h as u16)?;
+        Ok((Typed::new(view), depth))
+    }
+
     /// Returns the texture's dimensions ``(width, height)``
     pub fn size(&self) -> (usize, usize) {
         let (w, h, _, _) = self.texture.get_info().kind.get_dimensions();
         (w as usize, h as usize)
     }
+
+    /// Replaces the pixels of the rectangle of `size` at `offset` from the top left corner with
+    /// `data`, which holds its rows from the top in the format of the texture. Only textures with
+    /// 8 bits per channel in the `R8`, `R8_G8`, `R8_G8_B8_A8` or `B8_G8_R8_A8` formats, with
+    /// `Unorm` or `Srgb` channels for the latter two, can be updated.
+    pub(crate) fn update(
+        &self,
+        encoder: &mut Encoder,
+        offset: (u16, u16),
+        size: (u16, u16),
+        data: &[u8],
+    ) -> Result<()> {
+        use gfx::format::{Srgb, Unorm, B8_G8_R8_A8, R8, R8_G8, R8_G8_B8_A8};
+
+        if size.0 == 0 || size.1 == 0 {
+            return Ok(());
+        }
+        let info = self.texture.get_info();
+        let (width, height, _, _) = info.kind.get_dimensions();
+        let pixel_size = (info.format.get_total_bits() / 8) as usize;
+        let row_size = size.0 as usize * pixel_size;
+        if u32::from(offset.0) + u32::from(size.0) > u32::from(width)
+            || u32::from(offset.1) + u32::from(size.1) > u32::from(height)
+        {
+            let error = format!(
+                "Texture update out of bounds: {:?} at {:?} (texture size: {:?})",
+                size,
+                offset,
+                (width, height)
+            );
+            bail!(Error::TextureUpdate(error))
+        }
+        if row_size * size.1 as usize != data.len() {
+            let error = format!(
+                "Texture update size mismatch: Expected pixel data of length {:?} (actual: {:?})",
+                row_size * size.1 as usize,
+                data.len()
+            );
+            bail!(Error::TextureUpdate(error))
+        }
+
+        // The rows are stored from the bottom for OpenGL, see `TextureBuilder::build`.
+        let v_flip_buffer;
+        let (y, data) = if cfg!(feature = "opengl") {
+            v_flip_buffer = data
+                .chunks(row_size)
+                .rev()
+                .flat_map(|row| row.iter().cloned())
+                .collect::<Vec<_>>();
+            (height - offset.1 - size.1, &v_flip_buffer[..])
+        } else {
+            (offset.1, data)
+        };
+        let image = NewImageInfo {
+            xoffset: offset.0,
+            yoffset: y,
+            zoffset: 0,
+            width: size.0,
+            height: size.1,
+            depth: 1,
+            format: (),
+            mipmap: 0,
+        };
+        let texture = &self.texture;
+        match (info.format, self.channel) {
+            (SurfaceType::R8_G8_B8_A8, ChannelType::Unorm) => {
+                update_texture::<R8_G8_B8_A8, Unorm>(encoder, texture, image, data)
+            }
+            (SurfaceType::R8_G8_B8_A8, ChannelType::Srgb) => {
+                update_texture::<R8_G8_B8_A8, Srgb>(encoder, texture, image, data)
+            }
+            (SurfaceType::B8_G8_R8_A8, ChannelType::Unorm) => {
+                update_texture::<B8_G8_R8_A8, Unorm>(encoder, texture, image, data)
+            }
+            (SurfaceType::B8_G8_R8_A8, ChannelType::Srgb) => {
+                update_texture::<B8_G8_R8_A8, Srgb>(encoder, texture, image, data)
+            }
+            (SurfaceType::R8_G8, ChannelType::Unorm) => {
+                update_texture::<R8_G8, Unorm>(encoder, texture, image, data)
+            }
+            (SurfaceType::R8, ChannelType::Unorm) => {
+                update_texture::<R8, Unorm>(encoder, texture, image, data)
+            }
+            (surface, channel) => {
+                let error = format!(
+                    "Texture update of unsupported format: {:?} ({:?})",
+                    surface, channel
+                );
+                bail!(Error::TextureUpdate(error))
+            }
+        }
+    }
+}
+
+/// Uploads the pixels of `image` into a texture of the format `(S, C)`.
+fn update_texture<S, C>(
+    encoder: &mut Encoder,
+    texture: &RawTexture,
+    image: NewImageInfo,
+    data: &[u8],
+) -> Result<()>
+where
+    S: SurfaceTyped,
+    S::DataType: Pod + Copy,
+    (S, C): Formatted<Surface = S>,
+{
+    use gfx::{
+        handle,
+        memory::{cast_slice, Typed},
+    };
+
+    let texture: handle::Texture<Resources, S> = Typed::new(texture.clone());
+    encoder
+        .update_texture::<S, (S, C)>(&texture, None, image, cast_slice(data))
+        .map_err(|e| Error::TextureUpdate(format!("{:?}", e)))
 }
 
 impl Asset for Texture {
@@ -66,6 +250,25 @@ impl Asset for Texture {
     type HandleStorage = DenseVecStorage<TextureHandle>;
 }
 
+impl MemorySize for Texture {
+    /// Counts the depth of 3D textures as layers, which overestimates their mipmaps.
+    fn memory_usage(&self) -> MemoryUsage {
+        let info = self.texture.get_info();
+        let (width, height, layers, _) = info.kind.get_dimensions();
+        let texel_size = (info.format.get_total_bits() / 8) as usize;
+        let gpu = (0..info.levels.max(1))
+            .map(|level| {
+                let size = |dimension: u16| (dimension >> level).max(1) as usize;
+                size(width) * size(height) * layers as usize * texel_size
+            })
+            .sum();
+        MemoryUsage {
+            cpu: self.source.as_ref().map_or(0, TextureData::byte_size),
+            gpu,
+        }
+    }
+}
+
 /// Builds new textures.
 #[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub struct TextureBuilder<D, T> {
@@ -99,7 +302,7 @@ where
     /// Creates a new `TextureBuilder` with the given raw texture data.
     pub fn new(data: D) -> Self {
         use gfx::{
-            format::{ChannelTyped, SurfaceTyped},
+            format::ChannelTyped,
             memory::{Bind, Usage},
             texture::{AaMode, Kind},
         };
@@ -141,12 +344,21 @@ where
     }
 
     /// Sets whether the texture is mutable or not.
+    ///
+    /// The pixels of mutable textures can be replaced with `TextureUpdates`.
     pub fn dynamic(mut self, mutable: bool) -> Self {
         use gfx::memory::Usage;
         self.info.usage = if mutable { Usage::Dynamic } else { Usage::Data };
         self
     }
 
+    /// Sets whether passes can draw into the texture, see `Texture::render_target_views`.
+    pub fn render_target(mut self, target: bool) -> Self {
+        use gfx::memory::Bind;
+        self.info.bind.set(Bind::RENDER_TARGET, target);
+        self
+    }
+
     /// Sets the texture format
     pub fn with_format(mut self, format: SurfaceType) -> Self {
         self.info.format = format;
@@ -214,7 +426,9 @@ where
         Ok(Texture {
             sampler,
             texture: tex,
+            channel: self.channel_type,
             view,
+            source: None,
         })
     }
 }
@@ -0,0 +1,195 @@
+//! Full-screen transitions between the frames of two states.
+
+use crate::{color::Rgba, tex::Texture};
+
+/// Border blur of wipes, as a fraction of the screen.
+pub(crate) const WIPE_SOFTNESS: f32 = 0.05;
+
+/// Width of the edge of dissolves, as a fraction of the noise.
+pub(crate) const DISSOLVE_SOFTNESS: f32 = 0.1;
+
+/// The side of the screen a wipe moves towards.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Deserialize, Serialize)]
+pub enum WipeDirection {
+    /// From the right edge to the left edge.
+    Left,
+    /// From the left edge to the right edge.
+    Right,
+    /// From the bottom edge to the top edge.
+    Up,
+    /// From the top edge to the bottom edge.
+    Down,
+}
+
+impl WipeDirection {
+    /// The direction on the screen, with `y` pointing up.
+    pub(crate) fn vector(self) -> [f32; 2] {
+        match self {
+            WipeDirection::Left => [-1.0, 0.0],
+            WipeDirection::Right => [1.0, 0.0],
+            WipeDirection::Up => [0.0, 1.0],
+            WipeDirection::Down => [0.0, -1.0],
+        }
+    }
+}
+
+/// How `DrawTransition` hides the change from the last frame of the old state to the frames of
+/// the new one.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub enum TransitionEffect {
+    /// The last frame fades to the color over the first half, and the color fades to the new
+    /// frames over the second half.
+    Fade(Rgba),
+    /// The last frame fades into the new frames.
+    Crossfade,
+    /// An edge moves over the screen, uncovering the new frames behind it.
+    Wipe(WipeDirection),
+    /// The last frame falls apart into blotches of noise, with edges of the color.
+    Dissolve(Rgba),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Phase {
+    Idle,
+    Capture,
+    Running,
+}
+
+/// Resource to start a full-screen transition, drawn by the `DrawTransition` pass.
+///
+/// After `start`, the `RenderSystem` keeps a copy of the next frame it draws, and
+/// `DrawTransition` draws it over the following frames for `duration` seconds of real time,
+/// so it keeps going while the game is paused. Start it in the `update` of the state that
+/// transitions, as the frame of that update is still drawn before the transition, or use
+/// `Trans::with_screen_transition`.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+///     if self.level_finished {
+///         data.world
+///             .write_resource::<ScreenTransition>()
+///             .start(TransitionEffect::Fade(Rgba::BLACK), 1.0);
+///         return Trans::Switch(Box::new(NextLevel));
+///     }
+///     Trans::None
+/// }
+/// ```
+///
+/// Copying the frame is only supported by the OpenGL backend.
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct ScreenTransition {
+    effect: TransitionEffect,
+    duration: f32,
+    elapsed: f32,
+    phase: Phase,
+    #[derivative(Debug = "ignore")]
+    frame: Option<Texture>,
+}
+
+impl Default for ScreenTransition {
+    fn default() -> Self {
+        ScreenTransition {
+            effect: TransitionEffect::Crossfade,
+            duration: 0.0,
+            elapsed: 0.0,
+            phase: Phase::Idle,
+            frame: None,
+        }
+    }
+}
+
+impl ScreenTransition {
+    /// Starts a transition from the next drawn frame, replacing the running one.
+    pub fn start(&mut self, effect: TransitionEffect, duration: f32) {
+        self.effect = effect;
+        self.duration = duration.max(0.0);
+        self.elapsed = 0.0;
+        self.phase = Phase::Capture;
+    }
+
+    /// Stops the running transition, showing the new frames right away.
+    pub fn cancel(&mut self) {
+        self.phase = Phase::Idle;
+        self.frame = None;
+    }
+
+    /// Checks whether a transition was started and isn't finished.
+    pub fn is_active(&self) -> bool {
+        self.phase != Phase::Idle
+    }
+
+    /// Returns the effect of the last started transition.
+    pub fn effect(&self) -> TransitionEffect {
+        self.effect
+    }
+
+    /// Returns how far the transition is drawn, from `0.0` to `1.0`, or `None` while the frame
+    /// isn't copied yet or without a transition.
+    pub fn progress(&self) -> Option<f32> {
+        match self.phase {
+            Phase::Running if self.duration > 0.0 => Some((self.elapsed / self.duration).min(1.0)),
+            Phase::Running => Some(1.0),
+            _ => None,
+        }
+    }
+
+    /// Checks whether the next frame has to be copied.
+    pub(crate) fn needs_frame(&self) -> bool {
+        self.phase == Phase::Capture
+    }
+
+    /// Starts drawing the transition over the copied frame.
+    pub(crate) fn begin(&mut self, frame: Texture) {
+        self.frame = Some(frame);
+        self.phase = Phase::Running;
+    }
+
+    /// Moves the transition on by `delta` seconds, dropping the frame once it's finished.
+    pub(crate) fn advance(&mut self, delta: f32) {
+        if self.phase != Phase::Running {
+            return;
+        }
+        if self.elapsed >= self.duration {
+            self.cancel();
+        } else {
+            self.elapsed += delta;
+        }
+    }
+
+    /// Returns the copied frame while the transition is drawn.
+    pub(crate) fn frame(&self) -> Option<&Texture> {
+        match self.phase {
+            Phase::Running => self.frame.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_until_finished() {
+        let mut transition = ScreenTransition::default();
+        transition.start(TransitionEffect::Crossfade, 2.0);
+        assert!(transition.needs_frame());
+        assert_eq!(None, transition.progress());
+
+        // Without a frame, e.g. when the backend can't copy it, nothing is drawn.
+        transition.phase = Phase::Running;
+        transition.advance(1.0);
+        assert_eq!(Some(0.5), transition.progress());
+        transition.advance(1.5);
+        assert_eq!(Some(1.0), transition.progress());
+        assert!(transition.is_active());
+
+        // The last frame is drawn fully transitioned before it stops.
+        transition.advance(0.1);
+        assert!(!transition.is_active());
+        assert_eq!(None, transition.progress());
+    }
+}
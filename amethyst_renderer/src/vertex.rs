@@ -225,6 +225,59 @@ impl With<Normal> for PosColorNorm {
     };
 }
 
+/// Vertex format with position, RGBA8 color, normal and UV texture coordinate attributes.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PosColorNormTex {
+    /// Position of the vertex in 3D space.
+    pub position: Vector3<f32>,
+    /// RGBA color value of the vertex.
+    pub color: [f32; 4],
+    /// Normal vector of the vertex.
+    pub normal: Vector3<f32>,
+    /// UV texture coordinates used by the vertex.
+    pub tex_coord: Vector2<f32>,
+}
+
+unsafe impl Pod for PosColorNormTex {}
+
+impl VertexFormat for PosColorNormTex {
+    const ATTRIBUTES: Attributes<'static> = &[
+        (Position::NAME, <Self as With<Position>>::FORMAT),
+        (Color::NAME, <Self as With<Color>>::FORMAT),
+        (Normal::NAME, <Self as With<Normal>>::FORMAT),
+        (TexCoord::NAME, <Self as With<TexCoord>>::FORMAT),
+    ];
+}
+
+impl With<Position> for PosColorNormTex {
+    const FORMAT: AttributeFormat = Element {
+        offset: 0,
+        format: Position::FORMAT,
+    };
+}
+
+impl With<Color> for PosColorNormTex {
+    const FORMAT: AttributeFormat = Element {
+        offset: Position::SIZE,
+        format: Color::FORMAT,
+    };
+}
+
+impl With<Normal> for PosColorNormTex {
+    const FORMAT: AttributeFormat = Element {
+        offset: Position::SIZE + Color::SIZE,
+        format: Normal::FORMAT,
+    };
+}
+
+impl With<TexCoord> for PosColorNormTex {
+    const FORMAT: AttributeFormat = Element {
+        offset: Position::SIZE + Color::SIZE + Normal::SIZE,
+        format: TexCoord::FORMAT,
+    };
+}
+
 /// Vertex format with position and UV texture coordinate attributes.
 #[repr(C)]
 #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
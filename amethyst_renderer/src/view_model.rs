@@ -0,0 +1,115 @@
+//! Rendering of first-person view models, like hands and weapons.
+
+use amethyst_core::{
+    nalgebra::{Matrix4, Perspective3},
+    specs::prelude::{Component, HashMapStorage},
+};
+
+use crate::layers::RenderLayers;
+
+/// Depth range in normalized device coordinates reserved for view models. The rest of the scene
+/// is drawn behind it.
+const VIEW_MODEL_DEPTH: f32 = -0.8;
+
+/// Add this to the camera entity to draw entities on the given layers as view models.
+///
+/// View models are drawn with their own field of view and clipping planes, from the position of
+/// the camera, in front of everything else in the scene, so first-person hands and weapons never
+/// clip into walls. The mesh passes (`DrawFlat`, `DrawShaded`, `DrawPbm` and their separate
+/// variants) draw view models, and the other passes draw the scene behind them.
+///
+/// # Examples
+///
+/// ```rust
+/// # extern crate amethyst_renderer;
+/// use amethyst_renderer::{RenderLayers, ViewModelCamera};
+///
+/// let weapon_layer = RenderLayers::layer(1);
+/// // Put this on the camera, and `weapon_layer` on the weapon entities.
+/// let view_model = ViewModelCamera::new(weapon_layer).with_fov(1.2);
+/// ```
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ViewModelCamera {
+    /// The layers of the view model entities.
+    pub layers: RenderLayers,
+    /// Vertical field of view in radians.
+    pub fov: f32,
+    /// Distance of the near clipping plane.
+    pub znear: f32,
+    /// Distance of the far clipping plane.
+    pub zfar: f32,
+}
+
+impl ViewModelCamera {
+    /// Draws the entities on the given layers as view models, with a field of view of π/3
+    /// radians (60 degrees) and clipping planes at 0.01 and 10 units.
+    pub fn new(layers: RenderLayers) -> Self {
+        ViewModelCamera {
+            layers,
+            fov: std::f32::consts::FRAC_PI_3,
+            znear: 0.01,
+            zfar: 10.,
+        }
+    }
+
+    /// Sets the vertical field of view in radians.
+    pub fn with_fov(mut self, fov: f32) -> Self {
+        self.fov = fov;
+        self
+    }
+
+    /// Sets the distances of the clipping planes.
+    pub fn with_clipping(mut self, znear: f32, zfar: f32) -> Self {
+        self.znear = znear;
+        self.zfar = zfar;
+        self
+    }
+
+    /// Returns the projection of the view models for the given aspect ratio, in front of the
+    /// depth range of the scene.
+    pub(crate) fn projection(&self, aspect: f32) -> Matrix4<f32> {
+        let proj = Perspective3::new(aspect, self.fov, self.znear, self.zfar).to_homogeneous();
+        depth_range(&proj, -1., VIEW_MODEL_DEPTH)
+    }
+
+    /// Moves the projection of the scene behind the depth range of the view models.
+    pub(crate) fn scene_projection(proj: &Matrix4<f32>) -> Matrix4<f32> {
+        depth_range(proj, VIEW_MODEL_DEPTH, 1.)
+    }
+}
+
+impl Component for ViewModelCamera {
+    type Storage = HashMapStorage<Self>;
+}
+
+/// Maps the depth of the projection from -1..1 to `near..far` in normalized device coordinates.
+fn depth_range(proj: &Matrix4<f32>, near: f32, far: f32) -> Matrix4<f32> {
+    let scale = (far - near) / 2.;
+    let offset = (far + near) / 2.;
+    let mut result = *proj;
+    for column in 0..4 {
+        result[(2, column)] = scale * proj[(2, column)] + offset * proj[(3, column)];
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_core::nalgebra::Vector4;
+
+    use super::*;
+
+    #[test]
+    fn view_models_are_in_front() {
+        let view_model = ViewModelCamera::new(RenderLayers::layer(1));
+        let scene = Perspective3::new(1., 1., 0.1, 100.).to_homogeneous();
+        let depth = |proj: Matrix4<f32>, z: f32| {
+            let clip = proj * Vector4::new(0., 0., z, 1.);
+            clip.z / clip.w
+        };
+        let far_view_model = depth(view_model.projection(1.), -10.);
+        let near_scene = depth(ViewModelCamera::scene_projection(&scene), -0.1);
+        assert!(far_view_model <= near_scene + 1.0e-5);
+        assert!(depth(view_model.projection(1.), -0.01) >= -1. - 1.0e-5);
+    }
+}
@@ -11,6 +11,7 @@ use amethyst_core::{
 use crate::{
     cam::{ActiveCamera, Camera},
     hidden::{Hidden, HiddenPropagate},
+    occlusion::{Occluder, OcclusionBounds, OcclusionBuffer},
     transparent::Transparent,
 };
 
@@ -27,11 +28,15 @@ pub struct Visibility {
 /// Determine what entities are visible to the camera, and which are not. Will also sort transparent
 /// entities back to front based on distance from camera.
 ///
+/// With occlusion culling enabled, entities with `OcclusionBounds` hidden behind `Occluder`s are
+/// left out too.
+///
 /// Note that this should run after `GlobalTransform` has been updated for the current frame, and
 /// before rendering occurs.
 pub struct VisibilitySortingSystem {
     centroids: Vec<Internals>,
     transparent: Vec<Internals>,
+    occlusion: Option<OcclusionBuffer>,
 }
 
 #[derive(Clone)]
@@ -49,8 +54,16 @@ impl VisibilitySortingSystem {
         VisibilitySortingSystem {
             centroids: Vec::default(),
             transparent: Vec::default(),
+            occlusion: None,
         }
     }
+
+    /// Enables occlusion culling, rasterizing the occluders into a depth buffer of the given
+    /// size. A small buffer, like 256 by 128, is usually enough.
+    pub fn with_occlusion_culling(mut self, width: usize, height: usize) -> Self {
+        self.occlusion = Some(OcclusionBuffer::new(width, height));
+        self
+    }
 }
 
 impl<'a> System<'a> for VisibilitySortingSystem {
@@ -63,18 +76,47 @@ impl<'a> System<'a> for VisibilitySortingSystem {
         ReadStorage<'a, Camera>,
         ReadStorage<'a, Transparent>,
         ReadStorage<'a, GlobalTransform>,
+        ReadStorage<'a, Occluder>,
+        ReadStorage<'a, OcclusionBounds>,
     );
 
     fn run(
         &mut self,
-        (entities, mut visibility, hidden, hidden_prop, active, camera, transparent, global): Self::SystemData,
+        (
+            entities,
+            mut visibility,
+            hidden,
+            hidden_prop,
+            active,
+            camera,
+            transparent,
+            global,
+            occluders,
+            bounds,
+        ): Self::SystemData,
     ) {
         let origin = Point3::origin();
 
-        let camera: Option<&GlobalTransform> = active
+        let camera: Option<(&Camera, &GlobalTransform)> = active
             .entity
-            .and_then(|entity| global.get(entity))
-            .or_else(|| (&camera, &global).join().map(|cg| cg.1).next());
+            .and_then(|entity| Some((camera.get(entity)?, global.get(entity)?)))
+            .or_else(|| (&camera, &global).join().next());
+        let occlusion = match (self.occlusion.as_mut(), camera) {
+            (Some(buffer), Some((camera, transform))) => match transform.0.try_inverse() {
+                Some(view) => {
+                    buffer.clear(camera.proj * view);
+                    for (occluder, global, _, _) in
+                        (&occluders, &global, !&hidden, !&hidden_prop).join()
+                    {
+                        buffer.rasterize(occluder, &global.0);
+                    }
+                    Some((&*buffer, camera.proj, view))
+                }
+                None => None,
+            },
+            _ => None,
+        };
+        let camera = camera.map(|(_, transform)| transform);
         let camera_backward = camera
             .map(|c| c.0.column(2).xyz())
             .unwrap_or_else(Vector3::z);
@@ -86,6 +128,15 @@ impl<'a> System<'a> for VisibilitySortingSystem {
         self.centroids.extend(
             (&*entities, &global, !&hidden, !&hidden_prop)
                 .join()
+                .filter(
+                    |&(entity, global, _, _)| match (occlusion, bounds.get(entity)) {
+                        (Some((buffer, ref proj, ref view)), Some(bounds)) => {
+                            let (center, radius) = bounds.world(&global.0);
+                            !buffer.occludes(view, proj, &center, radius)
+                        }
+                        _ => true,
+                    },
+                )
                 .map(|(entity, global, _, _)| (entity, global.0.transform_point(&origin)))
                 .map(|(entity, centroid)| Internals {
                     entity,
@@ -7,14 +7,16 @@ use amethyst_audio::AudioFormat;
 use amethyst_core::{
     bundle::{Result, SystemBundle},
     specs::prelude::DispatcherBuilder,
+    ComponentMemorySystem, MemoryCategory,
 };
 use amethyst_renderer::{BlinkSystem, TextureFormat};
 
 use crate::{
-    CacheSelectionOrderSystem, FontAsset, FontFormat, NoCustomUi, ResizeSystem,
+    CacheSelectionOrderSystem, FontAsset, FontFormat, NoCustomUi, ResizeSystem, SdfFont,
     SelectionKeyboardSystem, SelectionMouseSystem, TextEditingInputSystem, TextEditingMouseSystem,
-    ToNativeWidget, UiButtonActionRetriggerSystem, UiButtonSystem, UiLoaderSystem, UiMouseSystem,
-    UiSoundRetriggerSystem, UiSoundSystem, UiTransformSystem,
+    ToNativeWidget, UiButtonActionRetriggerSystem, UiButtonSystem, UiCaptionSystem, UiLoaderSystem,
+    UiMouseSystem, UiSemanticTreeSystem, UiSoundRetriggerSystem, UiSoundSystem, UiSurfaceSystem,
+    UiText, UiTransform, UiTransformSystem,
 };
 
 /// UI bundle
@@ -52,11 +54,13 @@ where
             "ui_transform",
             &["transform_system"],
         );
+        builder.add(UiSurfaceSystem::default(), "ui_surface", &[]);
         builder.add(
             Processor::<FontAsset>::new(),
             "font_processor",
             &["ui_loader"],
         );
+        builder.add(Processor::<SdfFont>::new(), "sdf_font_processor", &[]);
         builder.add(
             CacheSelectionOrderSystem::<G>::new(),
             "selection_order_cache",
@@ -102,12 +106,33 @@ where
             &["ui_button_system"],
         );
         builder.add(UiSoundSystem::new(), "ui_sound_system", &[]);
+        builder.add(UiCaptionSystem, "ui_caption_system", &[]);
+        builder.add(
+            UiSemanticTreeSystem::default(),
+            "ui_semantic_tree_system",
+            &[
+                "ui_transform",
+                "ui_mouse_selection",
+                "ui_keyboard_selection",
+            ],
+        );
         builder.add(
             UiSoundRetriggerSystem::new(),
             "ui_sound_retrigger_system",
             &["ui_sound_system"],
         );
 
+        builder.add(
+            ComponentMemorySystem::<UiTransform>::new(MemoryCategory::Ui, "UiTransform"),
+            "ui_transform_memory",
+            &[],
+        );
+        builder.add(
+            ComponentMemorySystem::<UiText>::new(MemoryCategory::Ui, "UiText"),
+            "ui_text_memory",
+            &[],
+        );
+
         // Required for text editing. You want the cursor image to blink.
         builder.add(BlinkSystem, "blink_system", &[]);
 
@@ -0,0 +1,107 @@
+//! Widget showing the captions of the sounds.
+
+use amethyst_assets::AssetStorage;
+use amethyst_audio::Captions;
+use amethyst_core::specs::prelude::{
+    Component, DenseVecStorage, Entities, Entity, Join, Read, ReadStorage, System, Write,
+    WriteStorage,
+};
+use amethyst_locale::{Locale, LocaleDiagnostics, LocaleHandle};
+use amethyst_renderer::Hidden;
+
+use crate::UiText;
+
+/// Put on an entity with a `UiText` to show the caption of the `Captions` on it, e.g. at the
+/// bottom of the screen, hiding it while there's none.
+///
+/// The text of the captions is looked up as a key in the `locale`, and used as it is without a
+/// locale or when the key is missing, which is reported to the `LocaleDiagnostics`.
+#[derive(Clone, Debug, Default)]
+pub struct UiCaption {
+    /// The locale with the texts of the captions.
+    pub locale: Option<LocaleHandle>,
+    /// An entity with a `UiText` showing the name of the speaker, e.g. in its own color. Without
+    /// it the name is put in front of the text, like "Guard: Halt!".
+    pub speaker: Option<Entity>,
+}
+
+impl Component for UiCaption {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Shows the caption of the `Captions` on the `UiCaption`s.
+#[derive(Default)]
+pub struct UiCaptionSystem;
+
+impl<'a> System<'a> for UiCaptionSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, Captions>,
+        Read<'a, AssetStorage<Locale>>,
+        Write<'a, LocaleDiagnostics>,
+        ReadStorage<'a, UiCaption>,
+        WriteStorage<'a, UiText>,
+        WriteStorage<'a, Hidden>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            captions,
+            locales,
+            mut diagnostics,
+            ui_captions,
+            mut texts,
+            mut hidden,
+        ): Self::SystemData,
+    ) {
+        let caption = captions.current();
+        for (entity, ui_caption) in (&*entities, &ui_captions).join() {
+            let (speaker, text) = match caption {
+                Some(caption) => {
+                    let text = ui_caption
+                        .locale
+                        .as_ref()
+                        .and_then(|locale| locales.get(locale))
+                        .map(|locale| locale.text(&caption.text, &mut diagnostics))
+                        .unwrap_or_else(|| caption.text.clone());
+                    match (caption.speaker.as_ref(), ui_caption.speaker) {
+                        (Some(speaker), Some(_)) => (speaker.clone(), text),
+                        (Some(speaker), None) => (String::new(), format!("{}: {}", speaker, text)),
+                        (None, _) => (String::new(), text),
+                    }
+                }
+                None => (String::new(), String::new()),
+            };
+
+            show(entity, text, &mut texts, &mut hidden);
+            if let Some(speaker_entity) = ui_caption.speaker {
+                show(speaker_entity, speaker, &mut texts, &mut hidden);
+            }
+        }
+    }
+}
+
+/// Puts the text on the entity, hiding it while the text is empty.
+fn show(
+    entity: Entity,
+    text: String,
+    texts: &mut WriteStorage<'_, UiText>,
+    hidden: &mut WriteStorage<'_, Hidden>,
+) {
+    if text.is_empty() {
+        if !hidden.contains(entity) {
+            if let Err(err) = hidden.insert(entity, Hidden) {
+                error!("Failed to hide the caption: {}", err);
+            }
+        }
+    } else if hidden.contains(entity) {
+        hidden.remove(entity);
+    }
+    if let Some(ui_text) = texts.get_mut(entity) {
+        if ui_text.text != text {
+            ui_text.text = text;
+        }
+    }
+}
@@ -1,17 +1,21 @@
 use std::{hash::Hash, marker::PhantomData};
 
 use amethyst_core::{
-    nalgebra::Vector2,
+    nalgebra::{Point2, Vector2},
     shrev::EventChannel,
     specs::prelude::{
         Component, Entities, Entity, Join, Read, ReadExpect, ReadStorage, System, Write,
     },
     specs::storage::NullStorage,
+    GlobalTransform, Parent,
 };
 use amethyst_input::InputHandler;
-use amethyst_renderer::{MouseButton, ScreenDimensions};
+use amethyst_renderer::{get_camera, ActiveCamera, Camera, MouseButton, ScreenDimensions};
 
-use crate::transform::UiTransform;
+use crate::{
+    surface::{surface_of, UiSurface, UiSurfaceRoot},
+    transform::UiTransform,
+};
 
 pub trait TargetedEvent {
     fn get_target(&self) -> Entity;
@@ -80,6 +84,9 @@ impl Component for Interactable {
 
 /// The system that generates events for `Interactable` enabled entities.
 /// The generic types A and B represent the A and B generic parameter of the InputHandler<A,B>.
+///
+/// Where the mouse isn't over the UI of the screen, it's cast from the active camera onto the
+/// `UiSurface`s, to target the UI of the closest one it hits.
 pub struct UiMouseSystem<A, B> {
     was_down: bool,
     click_started_on: Option<Entity>,
@@ -111,11 +118,30 @@ where
         Read<'a, InputHandler<A, B>>,
         ReadExpect<'a, ScreenDimensions>,
         Write<'a, EventChannel<UiEvent>>,
+        ReadStorage<'a, Parent>,
+        ReadStorage<'a, UiSurface>,
+        ReadStorage<'a, UiSurfaceRoot>,
+        Read<'a, ActiveCamera>,
+        ReadStorage<'a, Camera>,
+        ReadStorage<'a, GlobalTransform>,
     );
 
     fn run(
         &mut self,
-        (entities, transform, react, input, screen_dimensions, mut events): Self::SystemData,
+        (
+            entities,
+            transform,
+            react,
+            input,
+            screen_dimensions,
+            mut events,
+            parents,
+            surfaces,
+            surface_roots,
+            active_camera,
+            cameras,
+            globals,
+        ): Self::SystemData,
     ) {
         let down = input.mouse_button_is_down(MouseButton::Left);
 
@@ -127,7 +153,40 @@ where
             let x = pos_x as f32;
             let y = screen_dimensions.height() - pos_y as f32;
 
-            let target = targeted((x, y), (&*entities, &transform, react.maybe()).join());
+            let on_surface = |entity| surface_of(entity, &parents, &surface_roots);
+            let target = match topmost(
+                (x, y),
+                (&*entities, &transform, react.maybe())
+                    .join()
+                    .filter(|&(entity, _, _)| on_surface(entity).is_none()),
+            ) {
+                Some((entity, interactable)) => interactable.map(|_| entity),
+                None => get_camera(active_camera, &cameras, &globals).and_then(
+                    |(camera, camera_transform)| {
+                        let ray = camera.screen_to_world_ray(
+                            Point2::new(pos_x as f32, pos_y as f32),
+                            &screen_dimensions,
+                            camera_transform,
+                        );
+                        let (_, surface, position) = (&*entities, &surfaces, &globals)
+                            .join()
+                            .filter_map(|(entity, surface, global)| {
+                                surface
+                                    .hit(&ray, global)
+                                    .map(|(distance, position)| (distance, entity, position))
+                            })
+                            .min_by(|(d1, _, _), (d2, _, _)| {
+                                d1.partial_cmp(d2).expect("Unexpected NaN")
+                            })?;
+                        targeted(
+                            position,
+                            (&*entities, &transform, react.maybe())
+                                .join()
+                                .filter(|&(entity, _, _)| on_surface(entity) == Some(surface)),
+                        )
+                    },
+                ),
+            };
             if target != self.last_target {
                 if let Some(last_target) = self.last_target {
                     events.single_write(UiEvent::new(UiEventType::HoverStop, last_target));
@@ -169,6 +228,14 @@ where
 /// If you have a non-interactable entity over an interactable entity, it will consider the interactable one blocked, depending
 /// on if `pos` is over the non-interactable one or not.
 pub fn targeted<'a, I>(pos: (f32, f32), transforms: I) -> Option<Entity>
+where
+    I: Iterator<Item = (Entity, &'a UiTransform, Option<&'a Interactable>)> + 'a,
+{
+    topmost(pos, transforms).and_then(|(e, m)| m.map(|_m| e))
+}
+
+/// Returns the opaque entity on top at the position `pos`, interactable or not.
+fn topmost<'a, I>(pos: (f32, f32), transforms: I) -> Option<(Entity, Option<&'a Interactable>)>
 where
     I: Iterator<Item = (Entity, &'a UiTransform, Option<&'a Interactable>)> + 'a,
 {
@@ -179,5 +246,5 @@ where
                 .partial_cmp(&t2.global_z)
                 .expect("Unexpected NaN")
         })
-        .and_then(|(e, _, m)| m.map(|_m| e))
+        .map(|(e, _, m)| (e, m))
 }
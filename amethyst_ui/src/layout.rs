@@ -5,14 +5,14 @@ use thread_profiler::profile_scope;
 
 use amethyst_core::{
     specs::prelude::{
-        BitSet, ComponentEvent, Join, ReadExpect, ReadStorage, ReaderId, Resources, System,
-        WriteStorage,
+        BitSet, ComponentEvent, Entities, Join, Read, ReadExpect, ReadStorage, ReaderId, Resources,
+        System, WriteStorage,
     },
-    HierarchyEvent, Parent, ParentHierarchy,
+    GlobalTransform, HierarchyEvent, Parent, ParentHierarchy,
 };
-use amethyst_renderer::ScreenDimensions;
+use amethyst_renderer::{get_camera, ActiveCamera, Camera, HiddenPropagate, ScreenDimensions};
 
-use super::UiTransform;
+use super::{UiSurface, UiSurfaceRoot, UiTransform, UiWorldAnchor};
 
 /// Indicates if the position and margins should be calculated in pixel or
 /// relative to their parent size.
@@ -97,6 +97,19 @@ impl Anchor {
     }
 }
 
+/// Resource scaling the whole UI, so players can make text and widgets bigger.
+///
+/// The sizes, positions and margins in pixels of the `UiTransform`s and the font sizes of the
+/// `UiText`s are multiplied with it, while percentages keep following their parent.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub struct UiScale(pub f32);
+
+impl Default for UiScale {
+    fn default() -> Self {
+        UiScale(1.0)
+    }
+}
+
 /// Indicates if a component should be stretched.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub enum Stretch {
@@ -121,6 +134,24 @@ pub enum Stretch {
     },
 }
 
+impl Stretch {
+    /// Returns the size stretched to the parent, and whether each axis is stretched.
+    fn size(&self, size: (f32, f32), parent: (f32, f32), scale: f32) -> ((f32, f32), (bool, bool)) {
+        match *self {
+            Stretch::NoStretch => (size, (false, false)),
+            Stretch::X { x_margin } => ((parent.0 - x_margin * scale * 2.0, size.1), (true, false)),
+            Stretch::Y { y_margin } => ((size.0, parent.1 - y_margin * scale * 2.0), (false, true)),
+            Stretch::XY { x_margin, y_margin } => (
+                (
+                    parent.0 - x_margin * scale * 2.0,
+                    parent.1 - y_margin * scale * 2.0,
+                ),
+                (true, true),
+            ),
+        }
+    }
+}
+
 /// Manages the `Parent` component on entities having `UiTransform`
 /// It does almost the same as the `TransformSystem`, but with some differences,
 /// like `UiTransform` alignment and stretching.
@@ -133,17 +164,42 @@ pub struct UiTransformSystem {
     parent_events_id: Option<ReaderId<HierarchyEvent>>,
 
     screen_size: (f32, f32),
+
+    scale: f32,
 }
 
 impl<'a> System<'a> for UiTransformSystem {
     type SystemData = (
+        Entities<'a>,
         WriteStorage<'a, UiTransform>,
         ReadStorage<'a, Parent>,
         ReadExpect<'a, ScreenDimensions>,
         ReadExpect<'a, ParentHierarchy>,
+        WriteStorage<'a, UiWorldAnchor>,
+        WriteStorage<'a, HiddenPropagate>,
+        Read<'a, ActiveCamera>,
+        ReadStorage<'a, Camera>,
+        ReadStorage<'a, GlobalTransform>,
+        WriteStorage<'a, UiSurfaceRoot>,
+        ReadStorage<'a, UiSurface>,
+        Read<'a, UiScale>,
     );
     fn run(&mut self, data: Self::SystemData) {
-        let (mut transforms, parents, screen_dim, hierarchy) = data;
+        let (
+            entities,
+            mut transforms,
+            parents,
+            screen_dim,
+            hierarchy,
+            mut anchors,
+            mut hidden,
+            active_camera,
+            cameras,
+            globals,
+            mut surface_roots,
+            surfaces,
+            ui_scale,
+        ) = data;
         #[cfg(feature = "profiler")]
         profile_scope!("ui_parent_system");
 
@@ -179,22 +235,77 @@ impl<'a> System<'a> for UiTransformSystem {
         }
 
         let current_screen_size = (screen_dim.width(), screen_dim.height());
-        let screen_resized = current_screen_size != self.screen_size;
+        let scale = ui_scale.0;
+        // Everything is laid out again when the screen or the scale changes.
+        let screen_resized = current_screen_size != self.screen_size || scale != self.scale;
         self.screen_size = current_screen_size;
-        if screen_resized {
-            process_root_iter(
-                (&mut transforms, !&parents).join().map(|i| i.0),
-                &*screen_dim,
-            );
-        } else {
-            // Immutable borrow
-            let self_transform_modified = &*self_transform_modified;
-            process_root_iter(
-                (&mut transforms, !&parents, self_transform_modified)
-                    .join()
-                    .map(|i| i.0),
-                &*screen_dim,
-            );
+        self.scale = scale;
+        // Roots on a surface are laid out on its pixels, again when it's resized.
+        let dirty_roots = (&*entities, &transforms, !&parents)
+            .join()
+            .filter_map(|(entity, _, _)| {
+                let (size, resized) = match surface_roots.get_mut(entity) {
+                    Some(root) => {
+                        let size = surfaces
+                            .get(root.surface)
+                            .map(|surface| (surface.width as f32, surface.height as f32))?;
+                        let resized = root.laid_out != Some(size);
+                        root.laid_out = Some(size);
+                        (size, resized)
+                    }
+                    None => (current_screen_size, screen_resized),
+                };
+                if resized || self_transform_modified.contains(entity.id()) {
+                    Some((entity, size))
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        for (entity, size) in dirty_roots {
+            if let Some(transform) = transforms.get_mut(entity) {
+                process_root(transform, size, scale);
+            }
+            self_transform_modified.add(entity.id());
+        }
+
+        // Follow world entities, every frame since the camera may move.
+        let camera = get_camera(active_camera, &cameras, &globals);
+        for (entity, transform, anchor, _) in
+            (&*entities, &mut transforms, &mut anchors, !&parents).join()
+        {
+            let position = match (camera, globals.get(anchor.entity)) {
+                (Some(camera), Some(target)) => anchor.screen_position(target, camera, &screen_dim),
+                _ => None,
+            };
+            anchor.on_screen = position.map_or(false, |(_, _, direction)| direction.is_none());
+            anchor.direction = position.and_then(|(_, _, direction)| direction);
+            match position {
+                Some((x, y, _)) => {
+                    let (local_x, local_y) = match transform.scale_mode {
+                        ScaleMode::Pixel => (transform.local_x * scale, transform.local_y * scale),
+                        ScaleMode::Percent => (
+                            transform.local_x * screen_dim.width(),
+                            transform.local_y * screen_dim.height(),
+                        ),
+                    };
+                    transform.pixel_x = x + local_x;
+                    transform.pixel_y = y + local_y;
+                    self_transform_modified.add(entity.id());
+                    if anchor.hidden {
+                        anchor.hidden = false;
+                        hidden.remove(entity);
+                    }
+                }
+                None => {
+                    if !anchor.hidden && !hidden.contains(entity) {
+                        anchor.hidden = true;
+                        if let Err(e) = hidden.insert(entity, HiddenPropagate) {
+                            error!("Failed to hide off-screen UI element: {}", e);
+                        }
+                    }
+                }
+            }
         }
 
         // Populate the modifications we just did.
@@ -235,29 +346,24 @@ impl<'a> System<'a> for UiTransformSystem {
                         parent_transform_copy.pixel_y + parent_transform_copy.pixel_height * norm.1;
                     transform.global_z = parent_transform_copy.global_z + transform.local_z;
 
-                    let new_size = match transform.stretch {
-                        Stretch::NoStretch => (transform.width, transform.height),
-                        Stretch::X { x_margin } => (
-                            parent_transform_copy.pixel_width - x_margin * 2.0,
-                            transform.height,
-                        ),
-                        Stretch::Y { y_margin } => (
-                            transform.width,
-                            parent_transform_copy.pixel_height - y_margin * 2.0,
-                        ),
-                        Stretch::XY { x_margin, y_margin } => (
-                            parent_transform_copy.pixel_width - x_margin * 2.0,
-                            parent_transform_copy.pixel_height - y_margin * 2.0,
+                    let (new_size, stretched) = transform.stretch.size(
+                        (transform.width, transform.height),
+                        (
+                            parent_transform_copy.pixel_width,
+                            parent_transform_copy.pixel_height,
                         ),
-                    };
+                        scale,
+                    );
                     transform.width = new_size.0;
                     transform.height = new_size.1;
                     match transform.scale_mode {
                         ScaleMode::Pixel => {
-                            transform.pixel_x += transform.local_x;
-                            transform.pixel_y += transform.local_y;
-                            transform.pixel_width = transform.width;
-                            transform.pixel_height = transform.height;
+                            transform.pixel_x += transform.local_x * scale;
+                            transform.pixel_y += transform.local_y * scale;
+                            transform.pixel_width =
+                                transform.width * if stretched.0 { 1.0 } else { scale };
+                            transform.pixel_height =
+                                transform.height * if stretched.1 { 1.0 } else { scale };
                         }
                         ScaleMode::Percent => {
                             transform.pixel_x +=
@@ -304,40 +410,30 @@ impl<'a> System<'a> for UiTransformSystem {
     }
 }
 
-fn process_root_iter<'a, I>(iter: I, screen_dim: &ScreenDimensions)
-where
-    I: Iterator<Item = &'a mut UiTransform>,
-{
-    for transform in iter {
-        let norm = transform.anchor.norm_offset();
-        transform.pixel_x = screen_dim.width() / 2.0 + screen_dim.width() * norm.0;
-        transform.pixel_y = screen_dim.height() / 2.0 + screen_dim.height() * norm.1;
-        transform.global_z = transform.local_z;
-
-        let new_size = match transform.stretch {
-            Stretch::NoStretch => (transform.width, transform.height),
-            Stretch::X { x_margin } => (screen_dim.width() - x_margin * 2.0, transform.height),
-            Stretch::Y { y_margin } => (transform.width, screen_dim.height() - y_margin * 2.0),
-            Stretch::XY { x_margin, y_margin } => (
-                screen_dim.width() - x_margin * 2.0,
-                screen_dim.height() - y_margin * 2.0,
-            ),
-        };
-        transform.width = new_size.0;
-        transform.height = new_size.1;
-        match transform.scale_mode {
-            ScaleMode::Pixel => {
-                transform.pixel_x += transform.local_x;
-                transform.pixel_y += transform.local_y;
-                transform.pixel_width = transform.width;
-                transform.pixel_height = transform.height;
-            }
-            ScaleMode::Percent => {
-                transform.pixel_x += transform.local_x * screen_dim.width();
-                transform.pixel_y += transform.local_y * screen_dim.height();
-                transform.pixel_width = transform.width * screen_dim.width();
-                transform.pixel_height = transform.height * screen_dim.height();
-            }
+fn process_root(transform: &mut UiTransform, (width, height): (f32, f32), scale: f32) {
+    let norm = transform.anchor.norm_offset();
+    transform.pixel_x = width / 2.0 + width * norm.0;
+    transform.pixel_y = height / 2.0 + height * norm.1;
+    transform.global_z = transform.local_z;
+
+    let (new_size, stretched) =
+        transform
+            .stretch
+            .size((transform.width, transform.height), (width, height), scale);
+    transform.width = new_size.0;
+    transform.height = new_size.1;
+    match transform.scale_mode {
+        ScaleMode::Pixel => {
+            transform.pixel_x += transform.local_x * scale;
+            transform.pixel_y += transform.local_y * scale;
+            transform.pixel_width = transform.width * if stretched.0 { 1.0 } else { scale };
+            transform.pixel_height = transform.height * if stretched.1 { 1.0 } else { scale };
+        }
+        ScaleMode::Percent => {
+            transform.pixel_x += transform.local_x * width;
+            transform.pixel_y += transform.local_y * height;
+            transform.pixel_width = transform.width * width;
+            transform.pixel_height = transform.height * height;
         }
     }
 }
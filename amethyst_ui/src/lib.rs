@@ -6,6 +6,7 @@
 use amethyst_assets;
 use amethyst_audio;
 use amethyst_core;
+use amethyst_locale;
 
 use amethyst_renderer;
 use clipboard;
@@ -36,6 +37,7 @@ use unicode_segmentation;
 
 mod bundle;
 mod button;
+mod caption;
 mod event;
 mod event_retrigger;
 mod font;
@@ -44,12 +46,17 @@ mod layout;
 mod pass;
 mod prefab;
 mod resize;
+mod sdf;
 mod selection;
 mod selection_order_cache;
+mod semantics;
 mod sound;
+mod surface;
 mod text;
 mod text_editing;
 mod transform;
+mod world_anchor;
+mod world_text;
 
 pub use self::{
     bundle::UiBundle,
@@ -57,6 +64,7 @@ pub use self::{
         UiButton, UiButtonAction, UiButtonActionRetrigger, UiButtonActionRetriggerSystem,
         UiButtonActionType, UiButtonBuilder, UiButtonBuilderResources, UiButtonSystem,
     },
+    caption::{UiCaption, UiCaptionSystem},
     event::{targeted, Interactable, UiEvent, UiEventType, UiMouseSystem},
     event_retrigger::{EventReceiver, EventRetriggerSystem},
     font::{
@@ -64,17 +72,25 @@ pub use self::{
         systemfont::{default_system_font, get_all_font_handles, list_system_font_families},
     },
     format::{FontAsset, FontFormat, FontHandle, OtfFormat, TtfFormat},
-    layout::{Anchor, ScaleMode, Stretch, UiTransformSystem},
+    layout::{Anchor, ScaleMode, Stretch, UiScale, UiTransformSystem},
     pass::DrawUi,
     prefab::{
         NoCustomUi, ToNativeWidget, UiCreator, UiFormat, UiImagePrefab, UiLoader, UiLoaderSystem,
         UiPrefab, UiTextBuilder, UiTransformBuilder, UiWidget,
     },
     resize::{ResizeSystem, UiResize},
+    sdf::{SdfFont, SdfFontFormat, SdfFontHandle, SdfGlyph, SdfOptions},
     selection::{Selectable, Selected, SelectionKeyboardSystem, SelectionMouseSystem},
     selection_order_cache::{CacheSelectionOrderSystem, CachedSelectionOrder},
+    semantics::{
+        ScreenReaderBridge, UiAccessible, UiRole, UiScreenReader, UiSemanticNode, UiSemanticTree,
+        UiSemanticTreeSystem,
+    },
     sound::{UiPlaySoundAction, UiSoundRetrigger, UiSoundRetriggerSystem, UiSoundSystem},
+    surface::{UiSurface, UiSurfaceRoot, UiSurfaceSystem},
     text::{LineMode, TextEditing, TextEditingMouseSystem, UiText},
     text_editing::TextEditingInputSystem,
     transform::{UiFinder, UiTransform},
+    world_anchor::UiWorldAnchor,
+    world_text::{DrawWorldText, WorldText},
 };
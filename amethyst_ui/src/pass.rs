@@ -19,8 +19,9 @@ use hibitset::BitSet;
 use unicode_segmentation::UnicodeSegmentation;
 
 use amethyst_assets::{AssetStorage, Handle, Loader};
-use amethyst_core::specs::prelude::{
-    Entities, Entity, Join, Read, ReadExpect, ReadStorage, WriteStorage,
+use amethyst_core::{
+    specs::prelude::{Entities, Entity, Join, Read, ReadExpect, ReadStorage, WriteStorage},
+    Parent,
 };
 use amethyst_renderer::{
     error::Result,
@@ -28,11 +29,12 @@ use amethyst_renderer::{
         pass::{Pass, PassData},
         Effect, NewEffect,
     },
-    Encoder, Factory, Hidden, HiddenPropagate, Mesh, PosTex, Resources, Rgba, ScreenDimensions,
-    Shape, Texture, TextureData, TextureHandle, TextureMetadata, VertexFormat,
+    DepthStencilView, Encoder, Factory, Hidden, HiddenPropagate, Mesh, PosTex, RenderTargetView,
+    Resources, Rgba, ScreenDimensions, Shape, Texture, TextureData, TextureHandle, VertexFormat,
 };
 
 use super::*;
+use crate::surface::surface_of;
 
 const VERT_SRC: &[u8] = include_bytes!("shaders/vertex.glsl");
 const FRAG_SRC: &[u8] = include_bytes!("shaders/frag.glsl");
@@ -75,6 +77,8 @@ impl Hash for KeyColor {
 
 #[derive(new)]
 /// Draw Ui elements.  UI won't display without this.  It's recommended this be your last pass.
+///
+/// The elements on a `UiSurface` are drawn into its texture first.
 pub struct DrawUi {
     #[new(default)]
     mesh: Option<Mesh>,
@@ -86,6 +90,8 @@ pub struct DrawUi {
     glyph_brushes: GlyphBrushCache,
     #[new(default)]
     next_brush_cache_id: u64,
+    #[new(default)]
+    surface_targets: HashMap<TextureHandle, (RenderTargetView, DepthStencilView)>,
 }
 
 type GlyphBrushCache = HashMap<u64, GlyphBrush<'static, Resources, Factory>>;
@@ -105,6 +111,10 @@ impl<'a> PassData<'a> for DrawUi {
         ReadStorage<'a, HiddenPropagate>,
         ReadStorage<'a, Selected>,
         ReadStorage<'a, Rgba>,
+        ReadStorage<'a, Parent>,
+        ReadStorage<'a, UiSurface>,
+        ReadStorage<'a, UiSurfaceRoot>,
+        Read<'a, UiScale>,
     );
 }
 
@@ -134,7 +144,7 @@ impl Pass for DrawUi {
         &'a mut self,
         encoder: &mut Encoder,
         effect: &mut Effect,
-        factory: Factory,
+        mut factory: Factory,
         (
             entities,
             loader,
@@ -149,6 +159,10 @@ impl Pass for DrawUi {
             hidden_prop,
             selecteds,
             rgba,
+            parents,
+            surfaces,
+            surface_roots,
+            ui_scale,
         ): <Self as PassData<'_>>::Data,
     ) {
         // Populate and update the draw order cache.
@@ -203,11 +217,52 @@ impl Pass for DrawUi {
             .cache
             .sort_unstable_by(|&(z1, _), &(z2, _)| z1.partial_cmp(&z2).unwrap_or(Ordering::Equal));
 
-        // Inverted screen dimensions. Used to scale from pixel coordinates to the opengl coordinates in the vertex shader.
-        let invert_window_size = [
-            1. / screen_dimensions.width(),
-            1. / screen_dimensions.height(),
-        ];
+        // Draw the surfaces first, with views into their textures, then the screen.
+        let mut targets = Vec::new();
+        {
+            let surface_targets = &mut self.surface_targets;
+            surface_targets.retain(|handle, _| {
+                (&surfaces,)
+                    .join()
+                    .any(|(surface,)| surface.texture() == Some(handle))
+            });
+            for (entity, surface) in (&*entities, &surfaces).join() {
+                let (handle, texture) = match surface
+                    .texture()
+                    .and_then(|handle| tex_storage.get(handle).map(|texture| (handle, texture)))
+                {
+                    Some(texture) => texture,
+                    None => continue,
+                };
+                let views = match surface_targets.get(handle) {
+                    Some(views) => views.clone(),
+                    None => match texture.render_target_views(&mut factory) {
+                        Ok(views) => {
+                            surface_targets.insert(handle.clone(), views.clone());
+                            views
+                        }
+                        Err(e) => {
+                            error!("Failed to draw into the texture of a `UiSurface`: {}", e);
+                            continue;
+                        }
+                    },
+                };
+                let (width, height) = texture.size();
+                targets.push((Some(entity), Some(views), width as f32, height as f32));
+            }
+        }
+        targets.push((
+            None,
+            None,
+            screen_dimensions.width(),
+            screen_dimensions.height(),
+        ));
+        let surface_of_entity = self
+            .cached_draw_order
+            .cache
+            .iter()
+            .map(|&(_z, entity)| surface_of(entity, &parents, &surface_roots))
+            .collect::<Vec<_>>();
 
         let mesh = self
             .mesh
@@ -219,6 +274,10 @@ impl Pass for DrawUi {
             None => return,
         };
         effect.data.vertex_bufs.push(vbuf);
+        let screen_target = (
+            effect.data.out_blends[0].clone(),
+            effect.data.out_depth.clone(),
+        );
 
         //Gather unused glyph brushes
         //These that are currently in use will be removed from this set.
@@ -232,329 +291,246 @@ impl Pass for DrawUi {
             .join()
             .map(|t| t.0.global_z)
             .fold(1.0, |highest, current| current.abs().max(highest));
-        for &(_z, entity) in &self.cached_draw_order.cache {
-            // Do not render hidden entities.
-            if hidden.contains(entity) || hidden_prop.contains(entity) {
-                ui_text
-                    .get_mut(entity)
-                    .and_then(|ui_text| ui_text.brush_id)
-                    .map(|brush_id| unused_glyph_brushes.remove(&brush_id));
-                continue;
-            }
-            let ui_transform = ui_transform
-                .get(entity)
-                .expect("Unreachable: Entity is guaranteed to be present based on earlier actions");
-            let rgba: [f32; 4] = rgba.get(entity).cloned().unwrap_or(Rgba::WHITE).into();
-            if let Some(image) = ui_image
-                .get(entity)
-                .and_then(|image| tex_storage.get(&image))
-            {
-                let vertex_args = VertexArgs {
-                    invert_window_size: invert_window_size.into(),
-                    // Coordinates are middle centered. It makes it easier to do layouting in most cases.
-                    coord: [ui_transform.pixel_x, ui_transform.pixel_y].into(),
-                    dimension: [ui_transform.pixel_width, ui_transform.pixel_height].into(),
-                    color: rgba.into(),
-                };
-
-                effect.update_constant_buffer("VertexArgs", &vertex_args.std140(), encoder);
-                effect.data.textures.push(image.view().clone());
-                effect.data.samplers.push(image.sampler().clone());
-                effect.draw(mesh.slice(), encoder);
-                effect.data.textures.clear();
-                effect.data.samplers.clear();
-            }
+        for (surface_entity, views, target_width, target_height) in targets {
+            let hidpi = match views {
+                Some((color, depth)) => {
+                    encoder.clear(&color, [0.0; 4]);
+                    encoder.clear_depth(&depth, 1.0);
+                    effect.data.out_blends[0] = color;
+                    effect.data.out_depth = Some((depth, (0, 0)));
+                    1.0
+                }
+                None => {
+                    effect.data.out_blends[0] = screen_target.0.clone();
+                    effect.data.out_depth = screen_target.1.clone();
+                    screen_dimensions.hidpi_factor() as f32
+                }
+            };
+            // Inverted target dimensions. Used to scale from pixel coordinates to the opengl coordinates in the vertex shader.
+            let invert_window_size = [1. / target_width, 1. / target_height];
 
-            if let Some(ui_text) = ui_text.get_mut(entity) {
-                // Maintain glyph brushes.
-                if ui_text.brush_id.is_none() || ui_text.font != ui_text.cached_font {
-                    let font = match font_storage.get(&ui_text.font) {
-                        Some(font) => font,
-                        None => continue,
+            for (&(_z, entity), &surface) in
+                self.cached_draw_order.cache.iter().zip(&surface_of_entity)
+            {
+                if surface != surface_entity {
+                    continue;
+                }
+                // Do not render hidden entities.
+                if hidden.contains(entity) || hidden_prop.contains(entity) {
+                    ui_text
+                        .get_mut(entity)
+                        .and_then(|ui_text| ui_text.brush_id)
+                        .map(|brush_id| unused_glyph_brushes.remove(&brush_id));
+                    continue;
+                }
+                let ui_transform = ui_transform.get(entity).expect(
+                    "Unreachable: Entity is guaranteed to be present based on earlier actions",
+                );
+                let rgba: [f32; 4] = rgba.get(entity).cloned().unwrap_or(Rgba::WHITE).into();
+                if let Some(image) = ui_image
+                    .get(entity)
+                    .and_then(|image| tex_storage.get(&image))
+                {
+                    let vertex_args = VertexArgs {
+                        invert_window_size: invert_window_size.into(),
+                        // Coordinates are middle centered. It makes it easier to do layouting in most cases.
+                        coord: [ui_transform.pixel_x, ui_transform.pixel_y].into(),
+                        dimension: [ui_transform.pixel_width, ui_transform.pixel_height].into(),
+                        color: rgba.into(),
                     };
 
-                    self.glyph_brushes.insert(
-                        self.next_brush_cache_id,
-                        GlyphBrushBuilder::using_font(font.0.clone()).build(factory.clone()),
-                    );
-
-                    ui_text.brush_id = Some(self.next_brush_cache_id);
-                    ui_text.cached_font = ui_text.font.clone();
-                    self.next_brush_cache_id += 1;
-                } else if let Some(brush_id) = ui_text.brush_id {
-                    unused_glyph_brushes.remove(&brush_id);
+                    effect.update_constant_buffer("VertexArgs", &vertex_args.std140(), encoder);
+                    effect.data.textures.push(image.view().clone());
+                    effect.data.samplers.push(image.sampler().clone());
+                    effect.draw(mesh.slice(), encoder);
+                    effect.data.textures.clear();
+                    effect.data.samplers.clear();
                 }
 
-                // Build text sections.
-                let editing = editing.get(entity);
-                let password_string = if ui_text.password {
-                    // Build a string composed of black dot characters.
-                    let mut ret = String::with_capacity(ui_text.text.len());
-                    for _grapheme in ui_text.text.graphemes(true) {
-                        ret.push('\u{2022}');
+                if let Some(ui_text) = ui_text.get_mut(entity) {
+                    // Maintain glyph brushes.
+                    if ui_text.brush_id.is_none() || ui_text.font != ui_text.cached_font {
+                        let font = match font_storage.get(&ui_text.font) {
+                            Some(font) => font,
+                            None => continue,
+                        };
+
+                        self.glyph_brushes.insert(
+                            self.next_brush_cache_id,
+                            GlyphBrushBuilder::using_font(font.0.clone()).build(factory.clone()),
+                        );
+
+                        ui_text.brush_id = Some(self.next_brush_cache_id);
+                        ui_text.cached_font = ui_text.font.clone();
+                        self.next_brush_cache_id += 1;
+                    } else if let Some(brush_id) = ui_text.brush_id {
+                        unused_glyph_brushes.remove(&brush_id);
                     }
-                    Some(ret)
-                } else {
-                    None
-                };
-                let rendered_string = password_string.as_ref().unwrap_or(&ui_text.text);
-                let hidpi = screen_dimensions.hidpi_factor() as f32;
-                let size = ui_text.font_size;
-                let scale = Scale::uniform(size);
-                let text = editing
-                    .and_then(|editing| {
-                        if editing.highlight_vector == 0 {
-                            return None;
+
+                    // Build text sections.
+                    let editing = editing.get(entity);
+                    let password_string = if ui_text.password {
+                        // Build a string composed of black dot characters.
+                        let mut ret = String::with_capacity(ui_text.text.len());
+                        for _grapheme in ui_text.text.graphemes(true) {
+                            ret.push('\u{2022}');
                         }
-                        let start = editing
-                            .cursor_position
-                            .min(editing.cursor_position + editing.highlight_vector)
-                            as usize;
-                        let end = editing
-                            .cursor_position
-                            .max(editing.cursor_position + editing.highlight_vector)
-                            as usize;
-                        let start_byte = rendered_string
-                            .grapheme_indices(true)
-                            .nth(start)
-                            .map(|i| i.0);
-                        let end_byte = rendered_string
-                            .grapheme_indices(true)
-                            .nth(end)
-                            .map(|i| i.0)
-                            .unwrap_or_else(|| rendered_string.len());
-                        start_byte.map(|start_byte| (editing, (start_byte, end_byte)))
-                    })
-                    .map(|(editing, (start_byte, end_byte))| {
-                        let base_color = multiply_colors(ui_text.color, rgba);
-                        vec![
-                            SectionText {
-                                text: &((rendered_string)[0..start_byte]),
-                                scale: scale,
-                                color: base_color,
-                                font_id: FontId(0),
-                            },
-                            SectionText {
-                                text: &((rendered_string)[start_byte..end_byte]),
-                                scale: scale,
-                                color: multiply_colors(editing.selected_text_color, rgba),
-                                font_id: FontId(0),
-                            },
-                            SectionText {
-                                text: &((rendered_string)[end_byte..]),
+                        Some(ret)
+                    } else {
+                        None
+                    };
+                    let rendered_string = password_string.as_ref().unwrap_or(&ui_text.text);
+                    let font_size = ui_text.font_size * ui_scale.0;
+                    let scale = Scale::uniform(font_size);
+                    let text = editing
+                        .and_then(|editing| {
+                            if editing.highlight_vector == 0 {
+                                return None;
+                            }
+                            let start = editing
+                                .cursor_position
+                                .min(editing.cursor_position + editing.highlight_vector)
+                                as usize;
+                            let end = editing
+                                .cursor_position
+                                .max(editing.cursor_position + editing.highlight_vector)
+                                as usize;
+                            let start_byte = rendered_string
+                                .grapheme_indices(true)
+                                .nth(start)
+                                .map(|i| i.0);
+                            let end_byte = rendered_string
+                                .grapheme_indices(true)
+                                .nth(end)
+                                .map(|i| i.0)
+                                .unwrap_or_else(|| rendered_string.len());
+                            start_byte.map(|start_byte| (editing, (start_byte, end_byte)))
+                        })
+                        .map(|(editing, (start_byte, end_byte))| {
+                            let base_color = multiply_colors(ui_text.color, rgba);
+                            vec![
+                                SectionText {
+                                    text: &((rendered_string)[0..start_byte]),
+                                    scale: scale,
+                                    color: base_color,
+                                    font_id: FontId(0),
+                                },
+                                SectionText {
+                                    text: &((rendered_string)[start_byte..end_byte]),
+                                    scale: scale,
+                                    color: multiply_colors(editing.selected_text_color, rgba),
+                                    font_id: FontId(0),
+                                },
+                                SectionText {
+                                    text: &((rendered_string)[end_byte..]),
+                                    scale: scale,
+                                    color: base_color,
+                                    font_id: FontId(0),
+                                },
+                            ]
+                        })
+                        .unwrap_or_else(|| {
+                            vec![SectionText {
+                                text: rendered_string,
                                 scale: scale,
-                                color: base_color,
+                                color: multiply_colors(ui_text.color, rgba),
                                 font_id: FontId(0),
-                            },
-                        ]
-                    })
-                    .unwrap_or_else(|| {
-                        vec![SectionText {
-                            text: rendered_string,
-                            scale: scale,
-                            color: multiply_colors(ui_text.color, rgba),
-                            font_id: FontId(0),
-                        }]
-                    });
-
-                let layout = match ui_text.line_mode {
-                    LineMode::Single => Layout::SingleLine {
-                        line_breaker: BuiltInLineBreaker::UnicodeLineBreaker,
-                        h_align: ui_text.align.horizontal_align(),
-                        v_align: ui_text.align.vertical_align(),
-                    },
-                    LineMode::Wrap => Layout::Wrap {
-                        line_breaker: BuiltInLineBreaker::UnicodeLineBreaker,
-                        h_align: ui_text.align.horizontal_align(),
-                        v_align: ui_text.align.vertical_align(),
-                    },
-                };
+                            }]
+                        });
+
+                    let layout = match ui_text.line_mode {
+                        LineMode::Single => Layout::SingleLine {
+                            line_breaker: BuiltInLineBreaker::UnicodeLineBreaker,
+                            h_align: ui_text.align.horizontal_align(),
+                            v_align: ui_text.align.vertical_align(),
+                        },
+                        LineMode::Wrap => Layout::Wrap {
+                            line_breaker: BuiltInLineBreaker::UnicodeLineBreaker,
+                            h_align: ui_text.align.horizontal_align(),
+                            v_align: ui_text.align.vertical_align(),
+                        },
+                    };
 
-                let section = VariedSection {
-                    // Needs a recenter because we are using [-0.5,0.5] for the mesh
-                    // instead of the expected [0,1]
-                    screen_position: (
-                        (ui_transform.pixel_x
-                            + ui_transform.pixel_width * ui_text.align.norm_offset().0),
-                        // invert y because gfx-glyph inverts it back
-                        (screen_dimensions.height()
-                            - ui_transform.pixel_y
-                            - ui_transform.pixel_height * ui_text.align.norm_offset().1),
-                    ),
-                    bounds: (ui_transform.pixel_width, ui_transform.pixel_height),
-                    // Invert z because of gfx-glyph using z+ forward
-                    z: ui_transform.global_z / highest_abs_z,
-                    layout,
-                    text,
-                };
+                    let section = VariedSection {
+                        // Needs a recenter because we are using [-0.5,0.5] for the mesh
+                        // instead of the expected [0,1]
+                        screen_position: (
+                            (ui_transform.pixel_x
+                                + ui_transform.pixel_width * ui_text.align.norm_offset().0),
+                            // invert y because gfx-glyph inverts it back
+                            (target_height
+                                - ui_transform.pixel_y
+                                - ui_transform.pixel_height * ui_text.align.norm_offset().1),
+                        ),
+                        bounds: (ui_transform.pixel_width, ui_transform.pixel_height),
+                        // Invert z because of gfx-glyph using z+ forward
+                        z: ui_transform.global_z / highest_abs_z,
+                        layout,
+                        text,
+                    };
 
-                // Render background highlight
-                let brush = &mut self
+                    // Render background highlight
+                    let brush = &mut self
                     .glyph_brushes
                     .get_mut(&ui_text.brush_id
                         .expect("Unreachable: `ui_text.brush_id` is guarenteed to be set earlier in this function")
                     ).expect("Unable to get brush from `glyph_brushes`-map");
 
-                // Maintain the glyph cache (used by the input code).
-                ui_text.cached_glyphs.clear();
-                ui_text
-                    .cached_glyphs
-                    .extend(brush.glyphs(&section).cloned());
-                let cache = &mut self.cached_color_textures;
-
-                // Render text selection
-                if let Some((texture, (start, end))) = editing.and_then(|ed| {
-                    let start = ed
-                        .cursor_position
-                        .min(ed.cursor_position + ed.highlight_vector)
-                        as usize;
-                    let end = ed
-                        .cursor_position
-                        .max(ed.cursor_position + ed.highlight_vector)
-                        as usize;
-                    let color = multiply_colors(
-                        if selecteds.contains(entity) {
-                            ed.selected_background_color
-                        } else {
-                            multiply_colors(ed.selected_background_color, [0.5, 0.5, 0.5, 0.5])
-                        },
-                        rgba,
-                    );
-                    tex_storage
-                        .get(&cached_color_texture(cache, color, &loader, &tex_storage))
-                        .map(|tex| (tex, (start, end)))
-                }) {
-                    // Text selection rendering
-
-                    effect.data.textures.push(texture.view().clone());
-                    effect.data.samplers.push(texture.sampler().clone());
-                    let ascent = brush
-                        .fonts()
-                        .get(0)
-                        .expect("Unable to get first font of brush")
-                        .v_metrics(Scale::uniform(ui_text.font_size))
-                        .ascent;
-                    for glyph in brush
-                        .glyphs(&section)
-                        .enumerate()
-                        .filter(|&(i, _g)| start <= i && i < end)
-                        .map(|(_i, g)| g)
-                    {
-                        let height = glyph.scale().y / hidpi;
-                        let width = glyph.unpositioned().h_metrics().advance_width / hidpi;
-                        let mut pos = glyph.position();
-                        pos.x /= hidpi;
-                        pos.y /= hidpi;
-                        let vertex_args = VertexArgs {
-                            invert_window_size: invert_window_size.into(),
-                            // gfx-glyph uses y down so we need to convert to y up
-                            coord: [
-                                pos.x + width / 2.0,
-                                screen_dimensions.height() - pos.y + ascent / 2.0,
-                            ]
-                            .into(),
-                            dimension: [width, height].into(),
-                            color: rgba.into(),
-                        };
-                        effect.update_constant_buffer("VertexArgs", &vertex_args.std140(), encoder);
-                        effect.draw(mesh.slice(), encoder);
-                    }
-                    effect.data.textures.clear();
-                    effect.data.samplers.clear();
-                }
-                // Render text
-                brush.queue(section.clone());
-                if let Err(err) = brush.draw_queued(
-                    encoder,
-                    &effect.data.out_blends[0],
-                    &effect
-                        .data
-                        .out_depth
-                        .as_ref()
-                        .expect("Unable to get depth of effect")
-                        .0,
-                ) {
-                    error!("Unable to draw text! Error: {:?}", err);
-                }
-                // Render cursor
-                if selecteds.contains(entity) {
-                    if let Some((texture, editing)) = editing.as_ref().and_then(|ed| {
-                        tex_storage
-                            .get(&cached_color_texture(
-                                cache,
-                                multiply_colors(ui_text.color, rgba),
-                                &loader,
-                                &tex_storage,
-                            ))
-                            .map(|tex| (tex, ed))
-                    }) {
-                        let blink_on = editing.cursor_blink_timer < 0.25;
-                        if editing.use_block_cursor || blink_on {
-                            effect.data.textures.push(texture.view().clone());
-                            effect.data.samplers.push(texture.sampler().clone());
-                            // Calculate the width of a space for use with the block cursor.
-                            let space_width = if editing.use_block_cursor {
-                                brush
-                                    .fonts()
-                                    .get(0)
-                                    .expect("Unable to get first font of brush")
-                                    .glyph(' ')
-                                    .scaled(Scale::uniform(ui_text.font_size))
-                                    .h_metrics()
-                                    .advance_width
-                            } else {
-                                // If we aren't using the block cursor, don't bother.
-                                0.0
-                            };
-                            let ascent = brush
-                                .fonts()
-                                .get(0)
-                                .expect("Unable to get first font of brush")
-                                .v_metrics(Scale::uniform(ui_text.font_size))
-                                .ascent;
-                            let glyph_len = brush.glyphs(&section).count();
-                            let (glyph, at_end) = if editing.cursor_position as usize >= glyph_len {
-                                (brush.glyphs(&section).last(), true)
-                            } else {
-                                (
-                                    brush.glyphs(&section).nth(editing.cursor_position as usize),
-                                    false,
-                                )
-                            };
-                            let (height, width) = if editing.use_block_cursor {
-                                let height = if blink_on {
-                                    ui_text.font_size
-                                } else {
-                                    ui_text.font_size / 10.0
-                                };
+                    // Maintain the glyph cache (used by the input code).
+                    ui_text.cached_glyphs.clear();
+                    ui_text
+                        .cached_glyphs
+                        .extend(brush.glyphs(&section).cloned());
+                    let cache = &mut self.cached_color_textures;
 
-                                (height, space_width)
+                    // Render text selection
+                    if let Some((texture, (start, end))) = editing.and_then(|ed| {
+                        let start = ed
+                            .cursor_position
+                            .min(ed.cursor_position + ed.highlight_vector)
+                            as usize;
+                        let end = ed
+                            .cursor_position
+                            .max(ed.cursor_position + ed.highlight_vector)
+                            as usize;
+                        let color = multiply_colors(
+                            if selecteds.contains(entity) {
+                                ed.selected_background_color
                             } else {
-                                (ui_text.font_size, 2.0)
-                            };
-
-                            let mut pos = glyph.map(|g| g.position()).unwrap_or(Point {
-                                x: ui_transform.pixel_x
-                                    + ui_transform.width * ui_text.align.norm_offset().0,
-                                y: 0.0,
-                            });
-                            // gfx-glyph uses y down so we need to convert to y up
-                            pos.y =
-                                screen_dimensions.height() - ui_transform.pixel_y + ascent / 2.0;
-
-                            let mut x = pos.x;
-                            if let Some(glyph) = glyph {
-                                if at_end {
-                                    x += glyph.unpositioned().h_metrics().advance_width;
-                                }
-                            }
-                            let mut y = pos.y;
-                            if editing.use_block_cursor && !blink_on {
-                                y -= ui_text.font_size * 0.9;
-                            }
+                                multiply_colors(ed.selected_background_color, [0.5, 0.5, 0.5, 0.5])
+                            },
+                            rgba,
+                        );
+                        tex_storage
+                            .get(&cached_color_texture(cache, color, &loader, &tex_storage))
+                            .map(|tex| (tex, (start, end)))
+                    }) {
+                        // Text selection rendering
+
+                        effect.data.textures.push(texture.view().clone());
+                        effect.data.samplers.push(texture.sampler().clone());
+                        let ascent = brush
+                            .fonts()
+                            .get(0)
+                            .expect("Unable to get first font of brush")
+                            .v_metrics(Scale::uniform(font_size))
+                            .ascent;
+                        for glyph in brush
+                            .glyphs(&section)
+                            .enumerate()
+                            .filter(|&(i, _g)| start <= i && i < end)
+                            .map(|(_i, g)| g)
+                        {
+                            let height = glyph.scale().y / hidpi;
+                            let width = glyph.unpositioned().h_metrics().advance_width / hidpi;
+                            let mut pos = glyph.position();
+                            pos.x /= hidpi;
+                            pos.y /= hidpi;
                             let vertex_args = VertexArgs {
                                 invert_window_size: invert_window_size.into(),
-                                coord: [x, screen_dimensions.height() - y + ascent / 2.0].into(),
+                                // gfx-glyph uses y down so we need to convert to y up
+                                coord: [pos.x + width / 2.0, target_height - pos.y + ascent / 2.0]
+                                    .into(),
                                 dimension: [width, height].into(),
                                 color: rgba.into(),
                             };
@@ -568,6 +544,115 @@ impl Pass for DrawUi {
                         effect.data.textures.clear();
                         effect.data.samplers.clear();
                     }
+                    // Render text
+                    brush.queue(section.clone());
+                    if let Err(err) = brush.draw_queued(
+                        encoder,
+                        &effect.data.out_blends[0],
+                        &effect
+                            .data
+                            .out_depth
+                            .as_ref()
+                            .expect("Unable to get depth of effect")
+                            .0,
+                    ) {
+                        error!("Unable to draw text! Error: {:?}", err);
+                    }
+                    // Render cursor
+                    if selecteds.contains(entity) {
+                        if let Some((texture, editing)) = editing.as_ref().and_then(|ed| {
+                            tex_storage
+                                .get(&cached_color_texture(
+                                    cache,
+                                    multiply_colors(ui_text.color, rgba),
+                                    &loader,
+                                    &tex_storage,
+                                ))
+                                .map(|tex| (tex, ed))
+                        }) {
+                            let blink_on = editing.cursor_blink_timer < 0.25;
+                            if editing.use_block_cursor || blink_on {
+                                effect.data.textures.push(texture.view().clone());
+                                effect.data.samplers.push(texture.sampler().clone());
+                                // Calculate the width of a space for use with the block cursor.
+                                let space_width = if editing.use_block_cursor {
+                                    brush
+                                        .fonts()
+                                        .get(0)
+                                        .expect("Unable to get first font of brush")
+                                        .glyph(' ')
+                                        .scaled(Scale::uniform(font_size))
+                                        .h_metrics()
+                                        .advance_width
+                                } else {
+                                    // If we aren't using the block cursor, don't bother.
+                                    0.0
+                                };
+                                let ascent = brush
+                                    .fonts()
+                                    .get(0)
+                                    .expect("Unable to get first font of brush")
+                                    .v_metrics(Scale::uniform(font_size))
+                                    .ascent;
+                                let glyph_len = brush.glyphs(&section).count();
+                                let (glyph, at_end) =
+                                    if editing.cursor_position as usize >= glyph_len {
+                                        (brush.glyphs(&section).last(), true)
+                                    } else {
+                                        (
+                                            brush
+                                                .glyphs(&section)
+                                                .nth(editing.cursor_position as usize),
+                                            false,
+                                        )
+                                    };
+                                let (height, width) = if editing.use_block_cursor {
+                                    let height = if blink_on {
+                                        font_size
+                                    } else {
+                                        font_size / 10.0
+                                    };
+
+                                    (height, space_width)
+                                } else {
+                                    (font_size, 2.0 * ui_scale.0)
+                                };
+
+                                let mut pos = glyph.map(|g| g.position()).unwrap_or(Point {
+                                    x: ui_transform.pixel_x
+                                        + ui_transform.width * ui_text.align.norm_offset().0,
+                                    y: 0.0,
+                                });
+                                // gfx-glyph uses y down so we need to convert to y up
+                                pos.y = target_height - ui_transform.pixel_y + ascent / 2.0;
+
+                                let mut x = pos.x;
+                                if let Some(glyph) = glyph {
+                                    if at_end {
+                                        x += glyph.unpositioned().h_metrics().advance_width;
+                                    }
+                                }
+                                let mut y = pos.y;
+                                if editing.use_block_cursor && !blink_on {
+                                    y -= font_size * 0.9;
+                                }
+                                let vertex_args = VertexArgs {
+                                    invert_window_size: invert_window_size.into(),
+                                    coord: [x, target_height - y + ascent / 2.0].into(),
+                                    dimension: [width, height].into(),
+                                    color: rgba.into(),
+                                };
+                                effect.update_constant_buffer(
+                                    "VertexArgs",
+                                    &vertex_args.std140(),
+                                    encoder,
+                                );
+                                effect.draw(mesh.slice(), encoder);
+                            }
+                            effect.data.textures.clear();
+                            effect.data.samplers.clear();
+                        }
+                    }
                 }
             }
         }
@@ -591,18 +676,12 @@ fn cached_color_texture(
     fn to_u8(input: f32) -> u8 {
         (input * 255.0).min(255.0) as u8
     }
-    let key = KeyColor([
-        to_u8(color[0]),
-        to_u8(color[1]),
-        to_u8(color[2]),
-        to_u8(color[3]),
-    ]);
+    // The colors of the UI are linear, like the ones the text is drawn with, while the texture
+    // is sRGB.
+    let srgb = Rgba::from(color).to_srgb();
+    let key = KeyColor([to_u8(srgb.0), to_u8(srgb.1), to_u8(srgb.2), to_u8(srgb.3)]);
     cache
         .entry(key)
-        .or_insert_with(|| {
-            let meta = TextureMetadata::srgb();
-            let texture_data = TextureData::Rgba(color, meta);
-            loader.load_from_data(texture_data, (), storage)
-        })
+        .or_insert_with(|| loader.load_from_data(TextureData::from(srgb), (), storage))
         .clone()
 }
@@ -0,0 +1,409 @@
+//! Signed distance field fonts, for text drawn in the world at any scale.
+
+use fnv::FnvHashMap as HashMap;
+use gfx_glyph::{rusttype::point, Font, Scale};
+
+use amethyst_assets::{Asset, Error, Handle, ProcessingState, ResultExt, SimpleFormat};
+use amethyst_core::specs::prelude::VecStorage;
+use amethyst_renderer::{FilterMethod, TextureData, TextureMetadata};
+
+/// A distance far larger than any in a glyph, for the pixels the transform starts without.
+const FAR: f32 = 1.0e20;
+
+/// How the glyphs of a `SdfFont` are generated.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SdfOptions {
+    /// The size the glyphs are rasterized at, in pixels per em.
+    ///
+    /// Larger sizes keep sharper corners when the text is scaled up, but take more memory.
+    pub size: f32,
+    /// How far the distance field reaches from the outlines, in pixels.
+    pub spread: f32,
+    /// The characters to generate glyphs for. Other characters aren't drawn.
+    pub characters: String,
+}
+
+impl Default for SdfOptions {
+    fn default() -> Self {
+        SdfOptions {
+            size: 48.,
+            spread: 6.,
+            characters: (' '..='~').chain('\u{a0}'..='\u{ff}').collect(),
+        }
+    }
+}
+
+/// A glyph of a `SdfFont`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SdfGlyph {
+    /// The distance from this glyph to the next one on the baseline, in ems.
+    pub advance: f32,
+    /// The quad of the glyph relative to its position on the baseline, in ems with y going up,
+    /// as min x, min y, max x and max y. It's empty for glyphs without outline, like spaces.
+    pub bounds: [f32; 4],
+    /// The texture coordinates of the quad in the atlas, as min u, min v, max u and max v.
+    pub uv: [f32; 4],
+}
+
+impl SdfGlyph {
+    /// Checks whether the glyph has nothing to draw.
+    pub fn is_empty(&self) -> bool {
+        self.bounds[0] >= self.bounds[2] || self.bounds[1] >= self.bounds[3]
+    }
+}
+
+/// A font with the distance fields of its glyphs packed in an atlas, drawn by `DrawWorldText`.
+///
+/// The distance fields are generated when the font is loaded with the `SdfFontFormat`, and stay
+/// sharp at any scale, unlike the glyphs `DrawUi` rasterizes at the size of the text.
+#[derive(Clone)]
+pub struct SdfFont {
+    font: Font<'static>,
+    glyphs: HashMap<char, SdfGlyph>,
+    ascent: f32,
+    descent: f32,
+    line_gap: f32,
+    atlas: TextureData,
+    atlas_size: (u32, u32),
+}
+
+/// A handle to a `SdfFont`.
+pub type SdfFontHandle = Handle<SdfFont>;
+
+impl SdfFont {
+    /// Generates the distance fields of the glyphs of a font.
+    pub fn generate(font: Font<'static>, options: &SdfOptions) -> Self {
+        let scale = Scale::uniform(options.size);
+        let padding = options.spread.ceil().max(1.) as i32;
+        let mut glyphs = HashMap::default();
+        let mut fields = Vec::new();
+        for character in options.characters.chars() {
+            if glyphs.contains_key(&character) {
+                continue;
+            }
+            let glyph = font.glyph(character);
+            if glyph.id().0 == 0 {
+                // The font has no glyph for the character.
+                continue;
+            }
+            let glyph = glyph.scaled(scale);
+            let advance = glyph.h_metrics().advance_width / options.size;
+            let glyph = glyph.positioned(point(0., 0.));
+            let bounding_box = match glyph.pixel_bounding_box() {
+                Some(bounding_box) => bounding_box,
+                None => {
+                    glyphs.insert(
+                        character,
+                        SdfGlyph {
+                            advance,
+                            ..Default::default()
+                        },
+                    );
+                    continue;
+                }
+            };
+            let width = (bounding_box.width() + 2 * padding) as usize;
+            let height = (bounding_box.height() + 2 * padding) as usize;
+            let mut coverage = vec![0.; width * height];
+            let offset = padding as usize;
+            glyph.draw(|x, y, value| {
+                coverage[(y as usize + offset) * width + x as usize + offset] = value;
+            });
+            // Pixel bounding boxes have y going down from the baseline.
+            let bounds = [
+                (bounding_box.min.x - padding) as f32 / options.size,
+                -(bounding_box.max.y + padding) as f32 / options.size,
+                (bounding_box.max.x + padding) as f32 / options.size,
+                -(bounding_box.min.y - padding) as f32 / options.size,
+            ];
+            glyphs.insert(
+                character,
+                SdfGlyph {
+                    advance,
+                    bounds,
+                    uv: [0.; 4],
+                },
+            );
+            let field = distance_field(&coverage, width, height, options.spread);
+            fields.push((character, width, height, field));
+        }
+
+        let (atlas, atlas_size) = pack(&mut glyphs, fields);
+        let v_metrics = font.v_metrics(Scale::uniform(1.));
+        SdfFont {
+            font,
+            glyphs,
+            ascent: v_metrics.ascent,
+            descent: v_metrics.descent,
+            line_gap: v_metrics.line_gap,
+            atlas,
+            atlas_size,
+        }
+    }
+
+    /// Returns the glyph of a character, if it was generated.
+    pub fn glyph(&self, character: char) -> Option<&SdfGlyph> {
+        self.glyphs.get(&character)
+    }
+
+    /// Returns the kerning between two characters, in ems.
+    pub fn kerning(&self, first: char, second: char) -> f32 {
+        self.font.pair_kerning(Scale::uniform(1.), first, second)
+    }
+
+    /// Returns the height of the font above the baseline, in ems.
+    pub fn ascent(&self) -> f32 {
+        self.ascent
+    }
+
+    /// Returns the depth of the font below the baseline, in ems. It's negative.
+    pub fn descent(&self) -> f32 {
+        self.descent
+    }
+
+    /// Returns the distance between the baselines of two lines, in ems.
+    pub fn line_height(&self) -> f32 {
+        self.ascent - self.descent + self.line_gap
+    }
+
+    /// Returns the width and height of the atlas, in pixels.
+    pub fn atlas_size(&self) -> (u32, u32) {
+        self.atlas_size
+    }
+
+    /// Returns the texture data of the atlas, with the distance fields in the alpha channel.
+    pub fn atlas(&self) -> &TextureData {
+        &self.atlas
+    }
+}
+
+impl Asset for SdfFont {
+    const NAME: &'static str = "ui::SdfFont";
+    type Data = SdfFontData;
+    type HandleStorage = VecStorage<Handle<Self>>;
+}
+
+#[derive(Clone)]
+pub struct SdfFontData(SdfFont);
+
+impl Into<Result<ProcessingState<SdfFont>, Error>> for SdfFontData {
+    fn into(self) -> Result<ProcessingState<SdfFont>, Error> {
+        Ok(ProcessingState::Loaded(self.0))
+    }
+}
+
+/// Loads TrueType and **some** OpenType files, generating the distance fields of the glyphs
+/// with the `SdfOptions`.
+#[derive(Clone)]
+pub struct SdfFontFormat;
+
+impl SimpleFormat<SdfFont> for SdfFontFormat {
+    const NAME: &'static str = "SDF_TTF";
+    type Options = SdfOptions;
+
+    fn import(&self, bytes: Vec<u8>, options: SdfOptions) -> Result<SdfFontData, Error> {
+        let font = Font::from_bytes(bytes).chain_err(|| "Font parsing error")?;
+        Ok(SdfFontData(SdfFont::generate(font, &options)))
+    }
+}
+
+/// Computes the signed distance field of the coverage of a glyph.
+///
+/// Distances are mapped from `spread` pixels outside of the outline to `spread` pixels inside of
+/// it onto 0 to 255, so the outline is at 128.
+fn distance_field(coverage: &[f32], width: usize, height: usize, spread: f32) -> Vec<u8> {
+    let inside = |value: f32| value >= 0.5;
+    let mut outside_distance = coverage
+        .iter()
+        .map(|&value| if inside(value) { 0. } else { FAR })
+        .collect::<Vec<_>>();
+    let mut inside_distance = coverage
+        .iter()
+        .map(|&value| if inside(value) { FAR } else { 0. })
+        .collect::<Vec<_>>();
+    transform(&mut outside_distance, width, height);
+    transform(&mut inside_distance, width, height);
+
+    outside_distance
+        .iter()
+        .zip(&inside_distance)
+        .map(|(&outside, &inside)| {
+            // The outline lies halfway between the pixels on both of its sides.
+            let distance = (outside.sqrt() - 0.5).max(0.) - (inside.sqrt() - 0.5).max(0.);
+            let value = 0.5 - distance / (2. * spread.max(1.));
+            (value.max(0.).min(1.) * 255.).round() as u8
+        })
+        .collect()
+}
+
+/// Replaces the values of a grid by the squared distance to the nearest zero, with the exact
+/// Euclidean distance transform of Felzenszwalb and Huttenlocher.
+fn transform(grid: &mut [f32], width: usize, height: usize) {
+    let length = width.max(height);
+    let mut values = vec![0.; length];
+    let mut distances = vec![0.; length];
+    let mut parabolas = vec![0; length];
+    let mut boundaries = vec![0.; length + 1];
+    for x in 0..width {
+        for y in 0..height {
+            values[y] = grid[y * width + x];
+        }
+        transform_line(
+            &values[..height],
+            &mut distances,
+            &mut parabolas,
+            &mut boundaries,
+        );
+        for y in 0..height {
+            grid[y * width + x] = distances[y];
+        }
+    }
+    for y in 0..height {
+        values[..width].copy_from_slice(&grid[y * width..(y + 1) * width]);
+        transform_line(
+            &values[..width],
+            &mut distances,
+            &mut parabolas,
+            &mut boundaries,
+        );
+        grid[y * width..(y + 1) * width].copy_from_slice(&distances[..width]);
+    }
+}
+
+/// The distance transform of one line, as the lower envelope of the parabolas rooted at each
+/// value.
+fn transform_line(
+    values: &[f32],
+    distances: &mut [f32],
+    parabolas: &mut [usize],
+    boundaries: &mut [f32],
+) {
+    let intersection = |q: usize, p: usize| {
+        let (qf, pf) = (q as f32, p as f32);
+        ((values[q] + qf * qf) - (values[p] + pf * pf)) / (2. * (qf - pf))
+    };
+    let mut k = 0;
+    parabolas[0] = 0;
+    boundaries[0] = -FAR;
+    boundaries[1] = FAR;
+    for q in 1..values.len() {
+        let mut s = intersection(q, parabolas[k]);
+        while s <= boundaries[k] {
+            k -= 1;
+            s = intersection(q, parabolas[k]);
+        }
+        k += 1;
+        parabolas[k] = q;
+        boundaries[k] = s;
+        boundaries[k + 1] = FAR;
+    }
+    k = 0;
+    for (q, distance) in distances.iter_mut().enumerate().take(values.len()) {
+        while boundaries[k + 1] < q as f32 {
+            k += 1;
+        }
+        let offset = q as f32 - parabolas[k] as f32;
+        *distance = offset * offset + values[parabolas[k]];
+    }
+}
+
+/// Packs the distance fields into rows of an atlas, tallest first, and sets the texture
+/// coordinates of the glyphs.
+fn pack(
+    glyphs: &mut HashMap<char, SdfGlyph>,
+    mut fields: Vec<(char, usize, usize, Vec<u8>)>,
+) -> (TextureData, (u32, u32)) {
+    const GAP: usize = 1;
+
+    fields.sort_by(|a, b| b.2.cmp(&a.2).then(a.0.cmp(&b.0)));
+    let area = fields
+        .iter()
+        .map(|&(_, width, height, _)| (width + GAP) * (height + GAP))
+        .sum::<usize>();
+    let widest = fields.iter().map(|field| field.1 + GAP).max().unwrap_or(1);
+    let atlas_width = ((area as f32).sqrt().ceil() as usize)
+        .max(widest)
+        .next_power_of_two();
+
+    let mut positions = Vec::with_capacity(fields.len());
+    let (mut x, mut y, mut row_height) = (0, 0, 0);
+    for &(_, width, height, _) in &fields {
+        if x + width > atlas_width {
+            x = 0;
+            y += row_height + GAP;
+            row_height = 0;
+        }
+        positions.push((x, y));
+        x += width + GAP;
+        row_height = row_height.max(height);
+    }
+    let atlas_height = (y + row_height).max(1).next_power_of_two();
+
+    // White texels, with the distance in the alpha channel.
+    let mut texels = vec![255; atlas_width * atlas_height * 4];
+    for texel in texels.chunks_mut(4) {
+        texel[3] = 0;
+    }
+    let (w, h) = (atlas_width as f32, atlas_height as f32);
+    for (&(character, width, height, ref field), &(x, y)) in fields.iter().zip(&positions) {
+        for row in 0..height {
+            for column in 0..width {
+                texels[((y + row) * atlas_width + x + column) * 4 + 3] =
+                    field[row * width + column];
+            }
+        }
+        // Rows go down in the texture data, while v goes up.
+        if let Some(glyph) = glyphs.get_mut(&character) {
+            glyph.uv = [
+                x as f32 / w,
+                1. - (y + height) as f32 / h,
+                (x + width) as f32 / w,
+                1. - y as f32 / h,
+            ];
+        }
+    }
+
+    let metadata = TextureMetadata::unorm()
+        .with_size(atlas_width as u16, atlas_height as u16)
+        .with_filter(FilterMethod::Bilinear);
+    (
+        TextureData::U8(texels, metadata),
+        (atlas_width as u32, atlas_height as u32),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distance_field_is_half_on_the_outline() {
+        // The left half of the grid is inside the glyph.
+        let coverage = (0..64)
+            .map(|i| if i % 8 < 4 { 1. } else { 0. })
+            .collect::<Vec<_>>();
+        let field = distance_field(&coverage, 8, 8, 4.);
+        let row = &field[3 * 8..4 * 8];
+        assert!(row.windows(2).all(|pair| pair[0] >= pair[1]));
+        assert!(row[0] > row[3] && row[3] > 128 && row[4] < 128);
+        assert_eq!(255 - row[3], row[4]);
+    }
+
+    #[test]
+    fn glyphs_are_packed_into_the_atlas() {
+        let font = Font::from_bytes(include_bytes!("font/square.ttf").to_vec()).unwrap();
+        let options = SdfOptions {
+            characters: "AB A".to_string(),
+            ..Default::default()
+        };
+        let font = SdfFont::generate(font, &options);
+
+        assert!(font.glyph(' ').unwrap().is_empty());
+        let (a, b) = (font.glyph('A').unwrap(), font.glyph('B').unwrap());
+        assert!(!a.is_empty() && a.advance > 0.);
+        assert_ne!(a.uv, b.uv);
+        assert!(a.uv.iter().chain(&b.uv).all(|&uv| uv >= 0. && uv <= 1.));
+        assert!(font.glyph('C').is_none());
+    }
+}
@@ -0,0 +1,352 @@
+//! Tree of the meaning of the UI elements, for screen readers.
+
+use std::cmp::Ordering;
+
+use fnv::FnvHashMap as HashMap;
+
+use amethyst_core::{
+    specs::prelude::{
+        Component, DenseVecStorage, Entities, Entity, Join, ReadStorage, System, Write,
+    },
+    Parent,
+};
+use amethyst_renderer::{Hidden, HiddenPropagate};
+
+use crate::{Selected, TextEditing, UiButton, UiText, UiTransform};
+
+/// What a UI element is for a screen reader.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Deserialize, Serialize)]
+pub enum UiRole {
+    /// Does something when clicked.
+    Button,
+    /// Switched on and off when clicked.
+    CheckBox,
+    /// A picture, only announced with a label.
+    Image,
+    /// Elements belonging together, like a menu.
+    Group,
+    /// Chooses a value in a range.
+    Slider,
+    /// Text to read.
+    Text,
+    /// Text the player edits.
+    TextInput,
+}
+
+impl UiRole {
+    /// The name of the role, as announced after the label.
+    pub fn name(self) -> &'static str {
+        match self {
+            UiRole::Button => "button",
+            UiRole::CheckBox => "check box",
+            UiRole::Image => "image",
+            UiRole::Group => "group",
+            UiRole::Slider => "slider",
+            UiRole::Text => "text",
+            UiRole::TextInput => "text field",
+        }
+    }
+}
+
+/// Describes a UI element to screen readers.
+///
+/// Entities with a `UiButton`, a `TextEditing` or a `UiText` are in the `UiSemanticTree` without
+/// it, as buttons, text fields and texts labelled with their text. Add it to give other elements
+/// a role, or to give any element a label or a value.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct UiAccessible {
+    /// What the element is.
+    pub role: UiRole,
+    /// The name of the element, by default the text of its `UiText` or of the `UiText` of its first
+    /// child with one.
+    #[serde(default)]
+    pub label: Option<String>,
+    /// The value shown by the element, like "50%" for a slider or "checked" for a check box.
+    #[serde(default)]
+    pub value: Option<String>,
+    /// Whether the element can't be used right now.
+    #[serde(default)]
+    pub disabled: bool,
+}
+
+impl UiAccessible {
+    /// Describes an element with the role, labelled with its text.
+    pub fn new(role: UiRole) -> Self {
+        UiAccessible {
+            role,
+            label: None,
+            value: None,
+            disabled: false,
+        }
+    }
+
+    /// Sets the label of the element.
+    pub fn with_label<S: Into<String>>(mut self, label: S) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Sets the value of the element.
+    pub fn with_value<S: Into<String>>(mut self, value: S) -> Self {
+        self.value = Some(value.into());
+        self
+    }
+
+    /// Sets whether the element can't be used right now.
+    pub fn with_disabled(mut self, disabled: bool) -> Self {
+        self.disabled = disabled;
+        self
+    }
+}
+
+impl Component for UiAccessible {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// An element of the `UiSemanticTree`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UiSemanticNode {
+    /// The entity of the element.
+    pub entity: Entity,
+    /// What the element is.
+    pub role: UiRole,
+    /// The name of the element.
+    pub label: String,
+    /// The value shown by the element.
+    pub value: Option<String>,
+    /// Whether the element can't be used right now.
+    pub disabled: bool,
+    /// Whether the element is selected.
+    pub focused: bool,
+    /// The indices of the children of the element in the tree, in reading order.
+    pub children: Vec<usize>,
+}
+
+impl UiSemanticNode {
+    /// The text a screen reader announces for the element, like "Volume, slider, 50%".
+    pub fn description(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.label.is_empty() {
+            parts.push(self.label.as_str());
+        }
+        if self.role != UiRole::Text || self.label.is_empty() {
+            parts.push(self.role.name());
+        }
+        if let Some(ref value) = self.value {
+            parts.push(value);
+        }
+        if self.disabled {
+            parts.push("disabled");
+        }
+        parts.join(", ")
+    }
+}
+
+/// Resource with the visible UI elements, their roles, labels and states, nested like the UI.
+#[derive(Clone, Debug, Default)]
+pub struct UiSemanticTree {
+    /// All elements in the tree.
+    pub nodes: Vec<UiSemanticNode>,
+    /// The indices of the elements without parents in the tree, in reading order.
+    pub roots: Vec<usize>,
+    /// The index of the selected element.
+    pub focused: Option<usize>,
+}
+
+impl UiSemanticTree {
+    /// Returns the element of the entity.
+    pub fn node(&self, entity: Entity) -> Option<&UiSemanticNode> {
+        self.nodes.iter().find(|node| node.entity == entity)
+    }
+}
+
+/// Bridge to the accessibility API of the platform, sending the UI to screen readers.
+///
+/// The engine doesn't bind any platform API, so games put an implementation into the
+/// `UiScreenReader` resource.
+pub trait ScreenReaderBridge: Send + Sync {
+    /// Called with the tree whenever it changes.
+    fn update(&mut self, tree: &UiSemanticTree);
+
+    /// Reads the text out, e.g. the description of the element that was just selected.
+    fn announce(&mut self, text: &str);
+}
+
+/// Resource holding the `ScreenReaderBridge` the `UiSemanticTreeSystem` talks to, if any.
+#[derive(Default)]
+pub struct UiScreenReader(pub Option<Box<dyn ScreenReaderBridge>>);
+
+/// Builds the `UiSemanticTree` every frame and sends it to the `UiScreenReader`, announcing
+/// the elements when they get selected.
+#[derive(Default)]
+pub struct UiSemanticTreeSystem {
+    focused: Option<Entity>,
+}
+
+impl<'a> System<'a> for UiSemanticTreeSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, UiTransform>,
+        ReadStorage<'a, Parent>,
+        ReadStorage<'a, UiAccessible>,
+        ReadStorage<'a, UiButton>,
+        ReadStorage<'a, TextEditing>,
+        ReadStorage<'a, UiText>,
+        ReadStorage<'a, Selected>,
+        ReadStorage<'a, Hidden>,
+        ReadStorage<'a, HiddenPropagate>,
+        Write<'a, UiSemanticTree>,
+        Write<'a, UiScreenReader>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            transforms,
+            parents,
+            accessibles,
+            buttons,
+            editings,
+            texts,
+            selected,
+            hidden,
+            hidden_propagates,
+            mut tree,
+            mut screen_reader,
+        ) = data;
+
+        let first_child_text = |entity: Entity| {
+            (&*entities, &parents, &texts)
+                .join()
+                .find(|(_, parent, _)| parent.entity == entity)
+                .map(|(_, _, text)| text.text.clone())
+        };
+
+        let mut nodes = Vec::new();
+        for (entity, _, _, _) in (&*entities, &transforms, !&hidden, !&hidden_propagates).join() {
+            let role = match accessibles.get(entity) {
+                Some(accessible) => accessible.role,
+                None if buttons.contains(entity) => UiRole::Button,
+                None if editings.contains(entity) => UiRole::TextInput,
+                None if texts
+                    .get(entity)
+                    .map_or(false, |text| !text.text.is_empty()) =>
+                {
+                    // The text of a button already is its label.
+                    let labels = parents.get(entity).map_or(false, |parent| {
+                        buttons.contains(parent.entity) && texts.get(parent.entity).is_none()
+                    });
+                    if labels {
+                        continue;
+                    }
+                    UiRole::Text
+                }
+                None => continue,
+            };
+            let accessible = accessibles.get(entity);
+            let label = accessible
+                .and_then(|accessible| accessible.label.clone())
+                .or_else(|| match texts.get(entity) {
+                    // Don't read out what players type into password fields.
+                    Some(text) if text.password => None,
+                    Some(text) => Some(text.text.clone()),
+                    None => first_child_text(entity),
+                })
+                .unwrap_or_default();
+            nodes.push(UiSemanticNode {
+                entity,
+                role,
+                label,
+                value: accessible.and_then(|accessible| accessible.value.clone()),
+                disabled: accessible.map_or(false, |accessible| accessible.disabled),
+                focused: selected.contains(entity),
+                children: Vec::new(),
+            });
+        }
+
+        // Reading order: from the top to the bottom, then from the left to the right.
+        nodes.sort_by(|a, b| {
+            let a = transforms.get(a.entity).unwrap();
+            let b = transforms.get(b.entity).unwrap();
+            b.pixel_y()
+                .partial_cmp(&a.pixel_y())
+                .unwrap_or(Ordering::Equal)
+                .then(
+                    a.pixel_x()
+                        .partial_cmp(&b.pixel_x())
+                        .unwrap_or(Ordering::Equal),
+                )
+        });
+        let indices = nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| (node.entity, index))
+            .collect::<HashMap<_, _>>();
+        // Elements are nested in the closest ancestor that is in the tree.
+        let parent_indices = nodes
+            .iter()
+            .map(|node| {
+                let mut ancestor = parents.get(node.entity);
+                while let Some(parent) = ancestor {
+                    if let Some(&index) = indices.get(&parent.entity) {
+                        return Some(index);
+                    }
+                    ancestor = parents.get(parent.entity);
+                }
+                None
+            })
+            .collect::<Vec<_>>();
+        let mut roots = Vec::new();
+        for (index, parent_index) in parent_indices.into_iter().enumerate() {
+            match parent_index {
+                Some(parent_index) => nodes[parent_index].children.push(index),
+                None => roots.push(index),
+            }
+        }
+
+        let focused = nodes.iter().position(|node| node.focused);
+        let changed = tree.nodes != nodes || tree.roots != roots;
+        tree.nodes = nodes;
+        tree.roots = roots;
+        tree.focused = focused;
+
+        let focused_entity = focused.map(|index| tree.nodes[index].entity);
+        if let Some(ref mut bridge) = screen_reader.0 {
+            if changed {
+                bridge.update(&tree);
+            }
+            if focused_entity != self.focused {
+                if let Some(index) = focused {
+                    bridge.announce(&tree.nodes[index].description());
+                }
+            }
+        }
+        self.focused = focused_entity;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use amethyst_core::specs::prelude::{Builder, World};
+
+    #[test]
+    fn descriptions_mention_role_value_and_state() {
+        let entity = World::new().create_entity().build();
+        let mut node = UiSemanticNode {
+            entity,
+            role: UiRole::Slider,
+            label: "Volume".to_string(),
+            value: Some("50%".to_string()),
+            disabled: true,
+            focused: false,
+            children: Vec::new(),
+        };
+        assert_eq!("Volume, slider, 50%, disabled", node.description());
+        node.role = UiRole::Text;
+        node.value = None;
+        node.disabled = false;
+        assert_eq!("Volume", node.description());
+    }
+}
@@ -0,0 +1,22 @@
+#version 150 core
+
+// Distance fields of the glyphs in the alpha channel, with the outlines at 0.5.
+uniform sampler2D sdf;
+
+in VertexData {
+    vec2 tex_coord;
+    vec4 color;
+} vertex;
+
+out vec4 color;
+
+void main() {
+    float distance = texture(sdf, vertex.tex_coord).a;
+    // Smooth over about a pixel on screen, so the edges stay crisp at any scale.
+    float width = max(fwidth(distance) * 0.7, 1.0e-4);
+    float alpha = smoothstep(0.5 - width, 0.5 + width, distance);
+    if (alpha <= 0.0) {
+        discard;
+    }
+    color = vec4(vertex.color.rgb, vertex.color.a * alpha);
+}
@@ -0,0 +1,23 @@
+#version 150 core
+
+layout (std140) uniform WorldTextArgs {
+    uniform mat4 proj;
+    uniform mat4 view;
+    uniform mat4 model;
+    uniform vec4 color;
+};
+
+// Glyph quads in the local units of the entity.
+in vec3 position;
+in vec2 tex_coord;
+
+out VertexData {
+    vec2 tex_coord;
+    vec4 color;
+} vertex;
+
+void main() {
+    vertex.tex_coord = tex_coord;
+    vertex.color = color;
+    gl_Position = proj * view * model * vec4(position, 1.0);
+}
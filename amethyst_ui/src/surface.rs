@@ -0,0 +1,180 @@
+//! UI drawn on surfaces in the world.
+
+use amethyst_assets::{AssetStorage, Loader};
+use amethyst_core::{
+    nalgebra::{Point3, Vector2, Vector4},
+    specs::prelude::{
+        Component, DenseVecStorage, Entities, Entity, Join, Read, ReadExpect, ReadStorage, System,
+        WriteStorage,
+    },
+    GlobalTransform, Parent,
+};
+use amethyst_renderer::{Material, Ray, Texture, TextureData, TextureHandle, TextureMetadata};
+
+/// A rectangle in the world showing UI, like the screen of a computer in the game or a menu
+/// floating in front of the player.
+///
+/// The UI elements below a `UiSurfaceRoot` of the entity are laid out on `width` by `height`
+/// pixels, and `DrawUi` draws them into the texture of the surface instead of onto the screen.
+/// The `UiSurfaceSystem` creates the texture and puts it into the albedo of the `Material` of
+/// the entity, so give it a mesh like `Shape::Plane` to show it. As the world is drawn before the
+/// UI, the surface shows the UI of the previous frame.
+///
+/// The surface is the rectangle of `size` centered on the origin of the entity in its local XY
+/// plane, facing +Z. The `UiMouseSystem` casts the mouse from the active camera onto the
+/// surfaces, so the elements on them are hovered and clicked like on the screen.
+#[derive(Clone, Debug)]
+pub struct UiSurface {
+    /// Width of the texture, in UI pixels.
+    pub width: u32,
+    /// Height of the texture, in UI pixels.
+    pub height: u32,
+    /// Size of the rectangle in the world, in the local units of the entity.
+    pub size: Vector2<f32>,
+    pub(crate) texture: Option<(TextureHandle, (u32, u32))>,
+}
+
+impl UiSurface {
+    /// Creates a surface of `width` by `height` UI pixels, covering `size` in the world.
+    pub fn new(width: u32, height: u32, size: Vector2<f32>) -> Self {
+        UiSurface {
+            width,
+            height,
+            size,
+            texture: None,
+        }
+    }
+
+    /// Returns the texture the UI is drawn into, once the `UiSurfaceSystem` created it.
+    pub fn texture(&self) -> Option<&TextureHandle> {
+        self.texture.as_ref().map(|&(ref handle, _)| handle)
+    }
+
+    /// Returns the distance along the ray to the surface, and the UI position it hits, with `y`
+    /// going up like the positions of `UiTransform`s.
+    pub(crate) fn hit(&self, ray: &Ray, global: &GlobalTransform) -> Option<(f32, (f32, f32))> {
+        let inverse = global.0.try_inverse()?;
+        let origin = inverse.transform_point(&ray.origin);
+        let direction =
+            inverse * Vector4::new(ray.direction.x, ray.direction.y, ray.direction.z, 0.0);
+        // Only the front of the surface is hit.
+        if direction.z >= 0.0 {
+            return None;
+        }
+        let distance = -origin.z / direction.z;
+        if distance < 0.0 {
+            return None;
+        }
+        let hit = origin + direction.xyz() * distance;
+        self.ui_position(&hit).map(|position| (distance, position))
+    }
+
+    /// Maps a local point on the plane of the surface to UI pixels, `None` outside the surface.
+    fn ui_position(&self, point: &Point3<f32>) -> Option<(f32, f32)> {
+        let u = point.x / self.size.x + 0.5;
+        let v = point.y / self.size.y + 0.5;
+        if u < 0.0 || u > 1.0 || v < 0.0 || v > 1.0 {
+            return None;
+        }
+        Some((u * self.width as f32, v * self.height as f32))
+    }
+}
+
+impl Component for UiSurface {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Put on a root `UiTransform` to lay out and draw it and its children on a `UiSurface`,
+/// instead of on the screen.
+#[derive(Clone, Debug)]
+pub struct UiSurfaceRoot {
+    /// The entity with the `UiSurface`.
+    pub surface: Entity,
+    pub(crate) laid_out: Option<(f32, f32)>,
+}
+
+impl UiSurfaceRoot {
+    /// Draws the UI element and its children on the surface of the entity.
+    pub fn new(surface: Entity) -> Self {
+        UiSurfaceRoot {
+            surface,
+            laid_out: None,
+        }
+    }
+}
+
+impl Component for UiSurfaceRoot {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Returns the surface the UI element is drawn on, `None` for the screen.
+pub(crate) fn surface_of(
+    mut entity: Entity,
+    parents: &ReadStorage<'_, Parent>,
+    roots: &ReadStorage<'_, UiSurfaceRoot>,
+) -> Option<Entity> {
+    loop {
+        if let Some(root) = roots.get(entity) {
+            return Some(root.surface);
+        }
+        entity = parents.get(entity)?.entity;
+    }
+}
+
+/// Creates the textures of the `UiSurface`s, again when their size changes, and shows them
+/// with the `Material` of the surface.
+#[derive(Default)]
+pub struct UiSurfaceSystem;
+
+impl<'a> System<'a> for UiSurfaceSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Loader>,
+        Read<'a, AssetStorage<Texture>>,
+        WriteStorage<'a, UiSurface>,
+        WriteStorage<'a, Material>,
+    );
+
+    fn run(&mut self, (entities, loader, textures, mut surfaces, mut materials): Self::SystemData) {
+        for (entity, surface) in (&*entities, &mut surfaces).join() {
+            let size = (surface.width.max(1), surface.height.max(1));
+            if surface
+                .texture
+                .as_ref()
+                .map_or(true, |&(_, old)| old != size)
+            {
+                let metadata = TextureMetadata::unorm()
+                    .with_size(size.0 as u16, size.1 as u16)
+                    .render_target(true);
+                let pixels = vec![0; size.0 as usize * size.1 as usize * 4];
+                let handle =
+                    loader.load_from_data(TextureData::U8(pixels, metadata), (), &textures);
+                surface.texture = Some((handle, size));
+            }
+            if let (Some(material), Some(handle)) = (materials.get_mut(entity), surface.texture()) {
+                if material.albedo != *handle {
+                    material.albedo = handle.clone();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ui_position_has_y_up() {
+        let surface = UiSurface::new(200, 100, Vector2::new(2.0, 1.0));
+        assert_eq!(
+            Some((100.0, 50.0)),
+            surface.ui_position(&Point3::new(0.0, 0.0, 0.0))
+        );
+        assert_eq!(
+            Some((0.0, 100.0)),
+            surface.ui_position(&Point3::new(-1.0, 0.5, 0.0))
+        );
+        assert_eq!(None, surface.ui_position(&Point3::new(1.5, 0.0, 0.0)));
+    }
+}
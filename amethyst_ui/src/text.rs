@@ -33,7 +33,8 @@ pub struct UiText {
     pub text: String,
     /// The height of a line of text in pixels.
     pub font_size: f32,
-    /// The color of the rendered text, using a range of 0.0 to 1.0 per channel.
+    /// The color of the rendered text, using a range of 0.0 to 1.0 per channel, in linear space
+    /// like an `Rgba`.
     pub color: [f32; 4],
     /// The font used for rendering.
     #[serde(skip)]
@@ -96,7 +97,7 @@ pub struct TextEditing {
     pub highlight_vector: isize,
     /// The color of the text itself when highlighted.
     pub selected_text_color: [f32; 4],
-    /// The text background color when highlighted.
+    /// The text background color when highlighted, in linear space like an `Rgba`.
     pub selected_background_color: [f32; 4],
     /// If this is true the text will use a block cursor for editing.  Otherwise this uses a
     /// standard line cursor.  This is not recommended if your font is not monospace.
@@ -0,0 +1,147 @@
+use amethyst_core::{
+    nalgebra::{Vector2, Vector3, Vector4},
+    specs::prelude::{Component, DenseVecStorage, Entity},
+    GlobalTransform,
+};
+use amethyst_renderer::{Camera, ScreenDimensions};
+
+/// Component that positions a root `UiTransform` at the screen position of an entity in the
+/// world, e.g. for health bars and nameplates.
+///
+/// Every frame, the `UiTransformSystem` projects the global position of the entity, plus the
+/// offset, through the active camera. The `local_x` and `local_y` of the `UiTransform` are added
+/// to that position, and its `anchor` is ignored. Children of the element are laid out relative
+/// to it as usual.
+///
+/// When the entity is off screen, the element is hidden by adding `HiddenPropagate`, unless a
+/// clamp margin is set, in which case the element stays at the edge of the screen pointing towards
+/// the entity, e.g. for an indicator turned with `off_screen_direction`.
+#[derive(Clone, Debug)]
+pub struct UiWorldAnchor {
+    /// The entity in the world to follow.
+    pub entity: Entity,
+    /// Offset from the entity's position in world units, e.g. to place a health bar above a head.
+    pub offset: Vector3<f32>,
+    /// Distance from the screen edges in pixels to keep elements of off-screen entities at, or
+    /// `None` to hide them.
+    pub clamp_margin: Option<f32>,
+    pub(crate) on_screen: bool,
+    pub(crate) direction: Option<Vector2<f32>>,
+    pub(crate) hidden: bool,
+}
+
+impl UiWorldAnchor {
+    /// Creates an anchor following `entity` at the given world offset.
+    pub fn new(entity: Entity, offset: Vector3<f32>) -> Self {
+        UiWorldAnchor {
+            entity,
+            offset,
+            clamp_margin: None,
+            on_screen: false,
+            direction: None,
+            hidden: false,
+        }
+    }
+
+    /// Keeps the element at the edge of the screen, `margin` pixels away from it, while the entity
+    /// is off screen, instead of hiding it.
+    pub fn with_clamp_margin(mut self, margin: f32) -> Self {
+        self.clamp_margin = Some(margin);
+        self
+    }
+
+    /// Checks whether the entity was on screen in the last layout, e.g. to switch between a
+    /// nameplate and an off-screen indicator.
+    pub fn is_on_screen(&self) -> bool {
+        self.on_screen
+    }
+
+    /// Returns the direction from the center of the screen towards the entity, in UI pixels
+    /// with y going up and normalized, if the entity was off screen in the last layout, e.g. to
+    /// rotate an arrow clamped to the edge.
+    pub fn off_screen_direction(&self) -> Option<Vector2<f32>> {
+        self.direction
+    }
+
+    /// Returns the position of the element in UI pixels, with y going up, and the direction
+    /// towards the entity if it's off screen, or `None` if the element isn't shown.
+    pub(crate) fn screen_position(
+        &self,
+        target: &GlobalTransform,
+        camera: (&Camera, &GlobalTransform),
+        screen: &ScreenDimensions,
+    ) -> Option<(f32, f32, Option<Vector2<f32>>)> {
+        let view = (camera.1).0.try_inverse()?;
+        let position = target.0.column(3).xyz() + self.offset;
+        let clip = camera.0.proj * view * Vector4::new(position.x, position.y, position.z, 1.);
+        let mut ndc = if clip.w > 0. {
+            Vector2::new(clip.x, clip.y) / clip.w
+        } else {
+            // Behind the camera, only the direction matters.
+            Vector2::new(clip.x, clip.y)
+        };
+        let on_screen = clip.w > 0. && ndc.x.abs() <= 1. && ndc.y.abs() <= 1.;
+        if !on_screen {
+            let margin = self.clamp_margin?;
+            ndc /= ndc.x.abs().max(ndc.y.abs()).max(1.0e-6);
+            let (width, height) = (screen.width(), screen.height());
+            let direction = Vector2::new(ndc.x * width, ndc.y * height)
+                .try_normalize(1.0e-6)
+                .unwrap_or_else(Vector2::y);
+            let x = ((ndc.x + 1.) / 2. * width).max(margin).min(width - margin);
+            let y = ((ndc.y + 1.) / 2. * height)
+                .max(margin)
+                .min(height - margin);
+            return Some((x, y, Some(direction)));
+        }
+        Some((
+            (ndc.x + 1.) / 2. * screen.width(),
+            (ndc.y + 1.) / 2. * screen.height(),
+            None,
+        ))
+    }
+}
+
+impl Component for UiWorldAnchor {
+    type Storage = DenseVecStorage<Self>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use amethyst_core::{
+        nalgebra::Matrix4,
+        specs::prelude::{Builder, World},
+    };
+
+    #[test]
+    fn off_screen_entities_clamp_towards_them() {
+        let mut world = World::new();
+        let entity = world.create_entity().build();
+        let anchor = UiWorldAnchor::new(entity, Vector3::zeros()).with_clamp_margin(10.);
+        let camera = Camera::standard_3d(800., 600.);
+        let camera_transform = GlobalTransform::default();
+        let screen = ScreenDimensions::new(800, 600, 1.);
+        let at = |x, y, z| GlobalTransform(Matrix4::new_translation(&Vector3::new(x, y, z)));
+
+        let (x, y, direction) = anchor
+            .screen_position(&at(0., 0., -10.), (&camera, &camera_transform), &screen)
+            .unwrap();
+        assert!((x - 400.).abs() < 1.0e-3 && (y - 300.).abs() < 1.0e-3);
+        assert_eq!(None, direction);
+
+        let (x, y, direction) = anchor
+            .screen_position(&at(100., 0., -10.), (&camera, &camera_transform), &screen)
+            .unwrap();
+        assert!((x - 790.).abs() < 1.0e-3 && (y - 300.).abs() < 1.0e-3);
+        let direction = direction.unwrap();
+        assert!((direction - Vector2::x()).norm() < 1.0e-5);
+
+        let hidden = UiWorldAnchor::new(entity, Vector3::zeros());
+        assert_eq!(
+            None,
+            hidden.screen_position(&at(100., 0., -10.), (&camera, &camera_transform), &screen)
+        );
+    }
+}
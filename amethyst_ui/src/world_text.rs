@@ -0,0 +1,338 @@
+//! Text drawn in the world with signed distance field fonts.
+
+use fnv::FnvHashMap as HashMap;
+use gfx::{preset::blend, pso::buffer::ElemStride, state::ColorMask};
+use glsl_layout::{mat4, vec4, Uniform};
+
+use amethyst_assets::{AssetStorage, Loader};
+use amethyst_core::{
+    nalgebra::{Matrix4, Vector2, Vector3, Vector4},
+    specs::prelude::{
+        Component, DenseVecStorage, Entities, Entity, Join, Read, ReadExpect, ReadStorage,
+    },
+    GlobalTransform,
+};
+use amethyst_renderer::{
+    error::Result,
+    get_camera,
+    pipe::{
+        pass::{Pass, PassData},
+        DepthMode, Effect, NewEffect,
+    },
+    ActiveCamera, Camera, Encoder, Factory, Hidden, HiddenPropagate, Mesh, PosTex, Texture,
+    TextureHandle, VertexFormat,
+};
+
+use crate::{
+    layout::Anchor,
+    sdf::{SdfFont, SdfFontHandle, SdfGlyph},
+};
+
+const VERT_SRC: &[u8] = include_bytes!("shaders/sdf_vertex.glsl");
+const FRAG_SRC: &[u8] = include_bytes!("shaders/sdf_frag.glsl");
+
+/// Component drawing text in the world with a `SdfFont`, like a sign or a label above a unit.
+///
+/// The text lies in the local XY plane of the entity facing +Z, or faces the camera when it's a
+/// billboard. It's drawn by `DrawWorldText`, not by `DrawUi`, and is hidden by `Hidden` and
+/// `HiddenPropagate`.
+#[derive(Clone, Debug)]
+pub struct WorldText {
+    /// The text, with a new line for every `\n`.
+    pub text: String,
+    /// The font.
+    pub font: SdfFontHandle,
+    /// The color of the text, in linear space.
+    pub color: [f32; 4],
+    /// The height of an em, in the local units of the entity.
+    pub size: f32,
+    /// The point of the text at the origin of the entity, e.g. `Anchor::BottomMiddle` for a
+    /// label standing above it.
+    pub anchor: Anchor,
+    /// Whether the text turns to face the camera.
+    pub billboard: bool,
+}
+
+impl WorldText {
+    /// Creates text centered on the origin of the entity.
+    pub fn new(font: SdfFontHandle, text: String, color: [f32; 4], size: f32) -> Self {
+        WorldText {
+            text,
+            font,
+            color,
+            size,
+            anchor: Anchor::Middle,
+            billboard: false,
+        }
+    }
+
+    /// Sets the point of the text at the origin of the entity.
+    pub fn with_anchor(mut self, anchor: Anchor) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    /// Turns the text to face the camera.
+    pub fn with_billboard(mut self, billboard: bool) -> Self {
+        self.billboard = billboard;
+        self
+    }
+}
+
+impl Component for WorldText {
+    type Storage = DenseVecStorage<Self>;
+}
+
+#[derive(Copy, Clone, Debug, Uniform)]
+#[allow(dead_code)] // This is used by the shaders
+#[repr(C)]
+struct WorldTextArgs {
+    proj: mat4,
+    view: mat4,
+    model: mat4,
+    color: vec4,
+}
+
+/// The glyph quads of a `WorldText`, kept until its text, font or anchor change.
+struct CachedText {
+    text: String,
+    font: SdfFontHandle,
+    anchor: Anchor,
+    mesh: Option<Mesh>,
+}
+
+impl CachedText {
+    fn is_for(&self, text: &WorldText) -> bool {
+        self.text == text.text && self.font == text.font && self.anchor == text.anchor
+    }
+}
+
+/// Draws the `WorldText`s with their `SdfFont`s, sharp at any distance and scale.
+///
+/// The text is blended over what was drawn before and is hidden behind it, without writing
+/// depth, so add the pass after the passes drawing the world, and before `DrawUi`.
+#[derive(Default)]
+pub struct DrawWorldText {
+    atlases: HashMap<SdfFontHandle, TextureHandle>,
+    texts: HashMap<Entity, CachedText>,
+}
+
+impl DrawWorldText {
+    /// Creates the pass.
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<'a> PassData<'a> for DrawWorldText {
+    type Data = (
+        Entities<'a>,
+        ReadExpect<'a, Loader>,
+        Read<'a, ActiveCamera>,
+        ReadStorage<'a, Camera>,
+        Read<'a, AssetStorage<SdfFont>>,
+        Read<'a, AssetStorage<Texture>>,
+        ReadStorage<'a, GlobalTransform>,
+        ReadStorage<'a, WorldText>,
+        ReadStorage<'a, Hidden>,
+        ReadStorage<'a, HiddenPropagate>,
+    );
+}
+
+impl Pass for DrawWorldText {
+    fn compile(&mut self, effect: NewEffect<'_>) -> Result<Effect> {
+        use std::mem;
+
+        effect
+            .simple(VERT_SRC, FRAG_SRC)
+            .without_back_face_culling()
+            .with_raw_constant_buffer(
+                "WorldTextArgs",
+                mem::size_of::<<WorldTextArgs as Uniform>::Std140>(),
+                1,
+            )
+            .with_raw_vertex_buffer(PosTex::ATTRIBUTES, PosTex::size() as ElemStride, 0)
+            .with_texture("sdf")
+            .with_blended_output(
+                "color",
+                ColorMask::all(),
+                blend::ALPHA,
+                Some(DepthMode::LessEqualTest),
+            )
+            .build()
+    }
+
+    fn apply<'a, 'b: 'a>(
+        &'a mut self,
+        encoder: &mut Encoder,
+        effect: &mut Effect,
+        mut factory: Factory,
+        (
+            entities,
+            loader,
+            active,
+            camera,
+            fonts,
+            textures,
+            globals,
+            texts,
+            hidden,
+            hidden_prop,
+        ): <Self as PassData<'a>>::Data,
+    ) {
+        self.texts.retain(|entity, _| texts.contains(*entity));
+        self.atlases
+            .retain(|handle, _| (&texts,).join().any(|(text,)| text.font == *handle));
+
+        let (camera, camera_transform) = match get_camera(active, &camera, &globals) {
+            Some(camera) => camera,
+            None => return,
+        };
+        let view = match camera_transform.0.try_inverse() {
+            Some(view) => view,
+            None => return,
+        };
+        let proj: [[f32; 4]; 4] = camera.proj.into();
+        let view: [[f32; 4]; 4] = view.into();
+
+        for (entity, text, global, _, _) in
+            (&*entities, &texts, &globals, !&hidden, !&hidden_prop).join()
+        {
+            let font = match fonts.get(&text.font) {
+                Some(font) => font,
+                None => continue,
+            };
+            let atlas = self
+                .atlases
+                .entry(text.font.clone())
+                .or_insert_with(|| loader.load_from_data(font.atlas().clone(), (), &textures));
+            let atlas = match textures.get(atlas) {
+                Some(atlas) => atlas,
+                None => continue,
+            };
+
+            if !self
+                .texts
+                .get(&entity)
+                .map_or(false, |cached| cached.is_for(text))
+            {
+                let vertices = layout(font, text);
+                let mesh = if vertices.is_empty() {
+                    None
+                } else {
+                    Mesh::build(vertices)
+                        .build(&mut factory)
+                        .map_err(|e| error!("Failed to build the glyphs of a `WorldText`: {}", e))
+                        .ok()
+                };
+                let cached = CachedText {
+                    text: text.text.clone(),
+                    font: text.font.clone(),
+                    anchor: text.anchor.clone(),
+                    mesh,
+                };
+                self.texts.insert(entity, cached);
+            }
+            let mesh = match self
+                .texts
+                .get(&entity)
+                .and_then(|cached| cached.mesh.as_ref())
+            {
+                Some(mesh) => mesh,
+                None => continue,
+            };
+            let vbuf = match mesh.buffer(PosTex::ATTRIBUTES) {
+                Some(vbuf) => vbuf.clone(),
+                None => continue,
+            };
+
+            let model = if text.billboard {
+                billboard(global, camera_transform)
+            } else {
+                global.0
+            };
+            let model: [[f32; 4]; 4] = (model * Matrix4::new_scaling(text.size)).into();
+            let args = WorldTextArgs {
+                proj: proj.into(),
+                view: view.into(),
+                model: model.into(),
+                color: text.color.into(),
+            };
+            effect.update_constant_buffer("WorldTextArgs", &args.std140(), encoder);
+            effect.data.vertex_bufs.push(vbuf);
+            effect.data.textures.push(atlas.view().clone());
+            effect.data.samplers.push(atlas.sampler().clone());
+            effect.draw(mesh.slice(), encoder);
+            effect.clear();
+        }
+    }
+}
+
+/// Returns the transform of an entity, rotated like the camera so its XY plane faces it.
+fn billboard(global: &GlobalTransform, camera: &GlobalTransform) -> Matrix4<f32> {
+    let mut model = global.0;
+    for column in 0..3 {
+        let scale = global.0.column(column).xyz().norm();
+        let axis = camera.0.column(column).xyz().normalize() * scale;
+        model.set_column(column, &Vector4::new(axis.x, axis.y, axis.z, 0.));
+    }
+    model
+}
+
+/// Returns the glyphs of a line with their positions on the baseline, and the width of the
+/// line, in ems.
+fn line_glyphs<'a>(font: &'a SdfFont, line: &str) -> (Vec<(f32, &'a SdfGlyph)>, f32) {
+    let mut glyphs = Vec::with_capacity(line.len());
+    let mut x = 0.;
+    let mut previous = None;
+    for character in line.chars() {
+        let glyph = match font.glyph(character) {
+            Some(glyph) => glyph,
+            None => continue,
+        };
+        if let Some(previous) = previous {
+            x += font.kerning(previous, character);
+        }
+        glyphs.push((x, glyph));
+        x += glyph.advance;
+        previous = Some(character);
+    }
+    (glyphs, x)
+}
+
+/// Lays the glyphs of the text out in quads, in ems, with the anchor of the text at the origin.
+fn layout(font: &SdfFont, text: &WorldText) -> Vec<PosTex> {
+    let lines = text.text.split('\n').collect::<Vec<_>>();
+    let (anchor_x, anchor_y) = text.anchor.norm_offset();
+    let height = font.ascent() - font.descent() + (lines.len() - 1) as f32 * font.line_height();
+    let top = (0.5 - anchor_y) * height;
+
+    let vertex = |x: f32, y: f32, u: f32, v: f32| PosTex {
+        position: Vector3::new(x, y, 0.),
+        tex_coord: Vector2::new(u, v),
+    };
+    let mut vertices = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        let (glyphs, width) = line_glyphs(font, line);
+        let left = -(anchor_x + 0.5) * width;
+        let baseline = top - font.ascent() - index as f32 * font.line_height();
+        for (x, glyph) in glyphs {
+            if glyph.is_empty() {
+                continue;
+            }
+            let [min_x, min_y, max_x, max_y] = glyph.bounds;
+            let [min_u, min_v, max_u, max_v] = glyph.uv;
+            let (x0, x1) = (left + x + min_x, left + x + max_x);
+            let (y0, y1) = (baseline + min_y, baseline + max_y);
+            vertices.extend_from_slice(&[
+                vertex(x0, y0, min_u, min_v),
+                vertex(x1, y0, max_u, min_v),
+                vertex(x1, y1, max_u, max_v),
+                vertex(x1, y1, max_u, max_v),
+                vertex(x0, y1, min_u, max_v),
+                vertex(x0, y0, min_u, min_v),
+            ]);
+        }
+    }
+    vertices
+}
@@ -0,0 +1,224 @@
+//! Per-camera handling of window aspect ratios that differ from the one the game was designed for.
+
+use amethyst_assets::{PrefabData, PrefabError};
+use amethyst_core::{
+    nalgebra::{Matrix4, Vector4},
+    shrev::EventChannel,
+    specs::{
+        Component, DenseVecStorage, Entities, Entity, Join, ReadExpect, System, Write, WriteStorage,
+    },
+};
+use amethyst_renderer::{Camera, ScreenDimensions};
+
+/// How a camera reacts when the aspect ratio of the window differs from the aspect ratio its
+/// projection was made for.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum AspectPolicy {
+    /// Keeps the projection as it is, stretching the image to the window.
+    Stretch,
+    /// Shows exactly the designed view with the given aspect ratio, centered in the window, and
+    /// reports the bars around it in the `Viewport` of the camera.
+    ///
+    /// The renderer doesn't clip to the viewport, so whatever lies outside the designed view is
+    /// visible in the bars. Cover them, e.g. with UI images laid out from `AspectEvent`s.
+    Letterbox {
+        /// The aspect ratio the projection was made for.
+        aspect: f32,
+    },
+    /// Always shows the whole designed view with the given aspect ratio, and shows more of the
+    /// scene along the longer side of the window.
+    Expand {
+        /// The aspect ratio the projection was made for.
+        aspect: f32,
+    },
+}
+
+impl Default for AspectPolicy {
+    fn default() -> Self {
+        AspectPolicy::Expand { aspect: 16. / 9. }
+    }
+}
+
+impl AspectPolicy {
+    /// Returns the scale applied to the x and y axes of the designed projection, and the area of
+    /// the window showing the designed view.
+    pub fn apply(self, width: f32, height: f32) -> ((f32, f32), Viewport) {
+        let window = Viewport {
+            x: 0.,
+            y: 0.,
+            width,
+            height,
+        };
+        let target = match self {
+            AspectPolicy::Stretch => return ((1., 1.), window),
+            AspectPolicy::Letterbox { aspect } | AspectPolicy::Expand { aspect } => aspect,
+        };
+        let window_aspect = width / height;
+        let (scale, viewport) = if window_aspect > target {
+            let bar_width = width * target / window_aspect;
+            (
+                (target / window_aspect, 1.),
+                Viewport {
+                    x: (width - bar_width) / 2.,
+                    width: bar_width,
+                    ..window
+                },
+            )
+        } else {
+            let bar_height = height * window_aspect / target;
+            (
+                (1., window_aspect / target),
+                Viewport {
+                    y: (height - bar_height) / 2.,
+                    height: bar_height,
+                    ..window
+                },
+            )
+        };
+        match self {
+            AspectPolicy::Letterbox { .. } => (scale, viewport),
+            _ => (scale, window),
+        }
+    }
+}
+
+/// Area of the window in pixels, with the origin at the top left corner.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Viewport {
+    /// Left edge.
+    pub x: f32,
+    /// Top edge.
+    pub y: f32,
+    /// Width of the area.
+    pub width: f32,
+    /// Height of the area.
+    pub height: f32,
+}
+
+/// Event sent by the `CameraAspectSystem` on an `EventChannel<AspectEvent>`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AspectEvent {
+    /// The viewport of the camera changed, e.g. because the window was resized.
+    ViewportChanged {
+        /// The camera entity.
+        entity: Entity,
+        /// The new viewport of the camera. Everything outside of it is a letterbox bar.
+        viewport: Viewport,
+    },
+}
+
+/// Component applying an `AspectPolicy` to the `Camera` of the same entity.
+///
+/// The projection of the camera when the component is first processed is the designed
+/// projection. Call `reset` after replacing the projection of the camera.
+///
+/// You must add the `CameraAspectSystem` to your dispatcher for this to take effect.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, PrefabData, Serialize)]
+#[prefab(Component)]
+pub struct CameraAspect {
+    /// The policy of the camera.
+    pub policy: AspectPolicy,
+    #[serde(skip)]
+    base: Option<Matrix4<f32>>,
+    #[serde(skip)]
+    viewport: Option<Viewport>,
+}
+
+impl CameraAspect {
+    /// Creates a component with the given policy.
+    pub fn new(policy: AspectPolicy) -> Self {
+        CameraAspect {
+            policy,
+            base: None,
+            viewport: None,
+        }
+    }
+
+    /// Returns the viewport of the camera, or `None` if the system didn't process it yet.
+    pub fn viewport(&self) -> Option<Viewport> {
+        self.viewport
+    }
+
+    /// Takes the current projection of the camera as the designed projection in the next frame.
+    pub fn reset(&mut self) {
+        self.base = None;
+        self.viewport = None;
+    }
+}
+
+impl Component for CameraAspect {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// System applying the `CameraAspect` of cameras when they are added and when the window is
+/// resized, and sending `AspectEvent`s for changed viewports.
+#[derive(Default)]
+pub struct CameraAspectSystem {
+    dimensions: (f32, f32),
+}
+
+impl<'a> System<'a> for CameraAspectSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, ScreenDimensions>,
+        WriteStorage<'a, Camera>,
+        WriteStorage<'a, CameraAspect>,
+        Write<'a, EventChannel<AspectEvent>>,
+    );
+
+    #[cfg_attr(feature = "cargo-clippy", allow(float_cmp))] // cmp just used to recognize change
+    fn run(
+        &mut self,
+        (entities, dimensions, mut cameras, mut aspects, mut events): Self::SystemData,
+    ) {
+        let (width, height) = (dimensions.width(), dimensions.height());
+        let resized = (width, height) != self.dimensions;
+        self.dimensions = (width, height);
+        if width <= 0. || height <= 0. {
+            // Minimized
+            return;
+        }
+
+        for (entity, camera, aspect) in (&*entities, &mut cameras, &mut aspects).join() {
+            if !resized && aspect.base.is_some() {
+                continue;
+            }
+            let base = *aspect.base.get_or_insert(camera.proj);
+            let ((x, y), viewport) = aspect.policy.apply(width, height);
+            camera.proj = Matrix4::from_diagonal(&Vector4::new(x, y, 1., 1.)) * base;
+            if aspect.viewport != Some(viewport) {
+                aspect.viewport = Some(viewport);
+                events.single_write(AspectEvent::ViewportChanged { entity, viewport });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn letterbox_bars() {
+        let policy = AspectPolicy::Letterbox { aspect: 2. };
+        let (scale, viewport) = policy.apply(400., 100.);
+        assert_eq!((0.5, 1.), scale);
+        assert_eq!(
+            Viewport {
+                x: 100.,
+                y: 0.,
+                width: 200.,
+                height: 100.
+            },
+            viewport
+        );
+
+        let (scale, viewport) = policy.apply(100., 100.);
+        assert_eq!((1., 0.5), scale);
+        assert_eq!((25., 50.), (viewport.y, viewport.height));
+
+        let expand = AspectPolicy::Expand { aspect: 2. };
+        let (_, viewport) = expand.apply(100., 100.);
+        assert_eq!((0., 100.), (viewport.y, viewport.height));
+    }
+}
@@ -0,0 +1,232 @@
+//! Named game variables shared by dialogues, quests and scripts.
+
+use std::collections::HashMap;
+
+/// A value in the `Blackboard`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum Value {
+    /// A flag.
+    Bool(bool),
+    /// A number, like a counter or an amount of gold.
+    Number(f64),
+    /// A text, like the name the player picked.
+    Text(String),
+}
+
+impl Value {
+    /// Returns the value of the same type that missing variables are compared with: `false`,
+    /// `0` or the empty text.
+    fn default_like(&self) -> Value {
+        match *self {
+            Value::Bool(_) => Value::Bool(false),
+            Value::Number(_) => Value::Number(0.),
+            Value::Text(_) => Value::Text(String::new()),
+        }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Number(value)
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Text(value)
+    }
+}
+
+impl<'a> From<&'a str> for Value {
+    fn from(value: &'a str) -> Self {
+        Value::Text(value.to_string())
+    }
+}
+
+/// Resource holding the variables of the game by name, read by the conditions and written by
+/// the effects of dialogues and quests.
+///
+/// It's `Serialize`, so it can be saved with the rest of the game.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Blackboard {
+    values: HashMap<String, Value>,
+}
+
+impl Blackboard {
+    /// Creates an empty blackboard.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns the value of the variable.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.values.get(name)
+    }
+
+    /// Sets the variable, returning its previous value.
+    pub fn set<N: Into<String>, V: Into<Value>>(&mut self, name: N, value: V) -> Option<Value> {
+        self.values.insert(name.into(), value.into())
+    }
+
+    /// Removes the variable, returning its value.
+    pub fn remove(&mut self, name: &str) -> Option<Value> {
+        self.values.remove(name)
+    }
+
+    /// Returns whether the variable is `Bool(true)`.
+    pub fn flag(&self, name: &str) -> bool {
+        self.get(name) == Some(&Value::Bool(true))
+    }
+
+    /// Returns the number of the variable, `0` if it isn't a number.
+    pub fn number(&self, name: &str) -> f64 {
+        match self.get(name) {
+            Some(Value::Number(number)) => *number,
+            _ => 0.,
+        }
+    }
+
+    /// Iterates over the variables and their values.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.values
+            .iter()
+            .map(|(name, value)| (name.as_str(), value))
+    }
+}
+
+/// How a `Condition` compares the variable with its value.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Comparison {
+    /// The variable equals the value.
+    Equal,
+    /// The variable differs from the value.
+    NotEqual,
+    /// The variable is a number less than the value.
+    Less,
+    /// The variable is a number less than or equal to the value.
+    LessOrEqual,
+    /// The variable is a number greater than the value.
+    Greater,
+    /// The variable is a number greater than or equal to the value.
+    GreaterOrEqual,
+}
+
+/// Compares a variable of the `Blackboard` with a value.
+///
+/// Missing variables are compared as `false`, `0` or the empty text, following the type of the
+/// value.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Condition {
+    /// Name of the variable.
+    pub variable: String,
+    /// How the variable is compared.
+    pub comparison: Comparison,
+    /// The value compared with.
+    pub value: Value,
+}
+
+impl Condition {
+    /// Creates a condition comparing the variable with the value.
+    pub fn new<N: Into<String>, V: Into<Value>>(
+        variable: N,
+        comparison: Comparison,
+        value: V,
+    ) -> Self {
+        Condition {
+            variable: variable.into(),
+            comparison,
+            value: value.into(),
+        }
+    }
+
+    /// Checks the condition against the blackboard.
+    pub fn check(&self, blackboard: &Blackboard) -> bool {
+        let default = self.value.default_like();
+        let variable = blackboard.get(&self.variable).unwrap_or(&default);
+        match (self.comparison, variable, &self.value) {
+            (Comparison::Equal, a, b) => a == b,
+            (Comparison::NotEqual, a, b) => a != b,
+            (comparison, Value::Number(a), Value::Number(b)) => match comparison {
+                Comparison::Less => a < b,
+                Comparison::LessOrEqual => a <= b,
+                Comparison::Greater => a > b,
+                _ => a >= b,
+            },
+            _ => false,
+        }
+    }
+}
+
+/// Changes a variable of the `Blackboard`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum Effect {
+    /// Sets the variable to the value.
+    Set {
+        /// Name of the variable.
+        variable: String,
+        /// The new value.
+        value: Value,
+    },
+    /// Adds the amount to the number of the variable, counting from `0`.
+    Add {
+        /// Name of the variable.
+        variable: String,
+        /// The amount added, negative to subtract.
+        amount: f64,
+    },
+    /// Removes the variable.
+    Remove(String),
+}
+
+impl Effect {
+    /// Applies the effect to the blackboard.
+    pub fn apply(&self, blackboard: &mut Blackboard) {
+        match *self {
+            Effect::Set {
+                ref variable,
+                ref value,
+            } => {
+                blackboard.set(variable.clone(), value.clone());
+            }
+            Effect::Add {
+                ref variable,
+                amount,
+            } => {
+                let number = blackboard.number(variable) + amount;
+                blackboard.set(variable.clone(), number);
+            }
+            Effect::Remove(ref variable) => {
+                blackboard.remove(variable);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conditions_compare_missing_variables_as_defaults() {
+        let mut blackboard = Blackboard::new();
+        let gold = Condition::new("gold", Comparison::GreaterOrEqual, 10.);
+        let met = Condition::new("met_guard", Comparison::Equal, false);
+        assert!(!gold.check(&blackboard));
+        assert!(met.check(&blackboard));
+
+        Effect::Add {
+            variable: "gold".to_string(),
+            amount: 12.,
+        }
+        .apply(&mut blackboard);
+        blackboard.set("met_guard", true);
+        assert!(gold.check(&blackboard));
+        assert!(!met.check(&blackboard));
+    }
+}
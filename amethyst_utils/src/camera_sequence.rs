@@ -0,0 +1,485 @@
+//! Keyframed camera sequences for cutscenes.
+//!
+//! A `CameraSequence` is an asset, usually loaded from a RON file with `RonFormat`, that
+//! describes camera moves as keyframes, cuts between named cameras and named markers on a
+//! timeline. Adding a `CameraSequencePlayer` to a camera entity plays the sequence on it.
+//!
+//! ```ron
+//! (
+//!     keyframes: [
+//!         (time: 0.0, position: (0.0, 2.0, 10.0), rotation: (0.0, 0.0, 0.0), fov: Some(60.0)),
+//!         (time: 3.0, position: (5.0, 3.0, 5.0), rotation: (-10.0, 45.0, 0.0), easing: EaseInOut),
+//!         (time: 5.0, position: (5.0, 1.0, 0.0), rotation: (0.0, 90.0, 0.0), fov: Some(40.0)),
+//!     ],
+//!     cuts: [(time: 5.0, camera: "door_camera")],
+//!     markers: [(time: 2.0, name: "explosion")],
+//! )
+//! ```
+
+use std::cmp::Ordering;
+
+use amethyst_assets::{Asset, AssetStorage, Handle, ProcessingState, Processor, Result};
+use amethyst_core::{
+    bundle::{Result as BundleResult, SystemBundle},
+    nalgebra::{Perspective3, UnitQuaternion, Vector3},
+    shrev::EventChannel,
+    specs::prelude::{
+        Component, DenseVecStorage, DispatcherBuilder, Entities, Entity, Join, Read, ReadStorage,
+        System, VecStorage, Write, WriteStorage,
+    },
+    timing::Time,
+    transform::Transform,
+    Named,
+};
+use amethyst_renderer::{ActiveCamera, Camera};
+
+/// Easing of the movement between two keyframes.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum Easing {
+    /// Constant speed.
+    Linear,
+    /// Starts slow and speeds up.
+    EaseIn,
+    /// Starts fast and slows down.
+    EaseOut,
+    /// Starts and ends slow.
+    EaseInOut,
+    /// Stays at the first keyframe, then jumps to the next one.
+    Step,
+}
+
+impl Default for Easing {
+    fn default() -> Self {
+        Easing::Linear
+    }
+}
+
+impl Easing {
+    /// Maps the linear progress `t` between two keyframes, from 0 to 1, to the eased progress.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.max(0.).min(1.);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2. - t),
+            Easing::EaseInOut => t * t * (3. - 2. * t),
+            Easing::Step => {
+                if t < 1. {
+                    0.
+                } else {
+                    1.
+                }
+            }
+        }
+    }
+}
+
+/// Position, rotation and field of view of the camera at a point of the sequence.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct CameraKeyframe {
+    /// Time of the keyframe in seconds from the start of the sequence.
+    pub time: f32,
+    /// Position of the camera.
+    pub position: [f32; 3],
+    /// Rotation of the camera as euler angles around the x, y and z axes, in degrees.
+    pub rotation: [f32; 3],
+    /// Vertical field of view in degrees, or `None` to keep the camera's projection.
+    #[serde(default)]
+    pub fov: Option<f32>,
+    /// Easing of the movement from this keyframe to the next one.
+    #[serde(default)]
+    pub easing: Easing,
+}
+
+impl CameraKeyframe {
+    fn rotation(&self) -> UnitQuaternion<f32> {
+        let [x, y, z] = self.rotation;
+        UnitQuaternion::from_euler_angles(x.to_radians(), y.to_radians(), z.to_radians())
+    }
+}
+
+/// Switch of the `ActiveCamera` to the entity with the given `Named` name.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct CameraCut {
+    /// Time of the cut in seconds from the start of the sequence.
+    pub time: f32,
+    /// Name of the camera entity to cut to.
+    pub camera: String,
+}
+
+/// Named point on the timeline, sent as a `CameraSequenceEvent::Marker` when it is reached.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct TimelineMarker {
+    /// Time of the marker in seconds from the start of the sequence.
+    pub time: f32,
+    /// Name of the marker.
+    pub name: String,
+}
+
+/// Pose of the camera sampled from a `CameraSequence`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CameraPose {
+    /// Position of the camera.
+    pub position: Vector3<f32>,
+    /// Rotation of the camera.
+    pub rotation: UnitQuaternion<f32>,
+    /// Vertical field of view in radians, if the keyframes set one.
+    pub fov: Option<f32>,
+}
+
+/// A camera sequence, see the [module documentation](index.html).
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct CameraSequence {
+    /// The keyframes of the camera, ordered by time.
+    pub keyframes: Vec<CameraKeyframe>,
+    /// Cuts to other cameras, ordered by time.
+    #[serde(default)]
+    pub cuts: Vec<CameraCut>,
+    /// Markers on the timeline, ordered by time.
+    #[serde(default)]
+    pub markers: Vec<TimelineMarker>,
+    /// Whether the sequence starts over when it reached its end.
+    #[serde(default)]
+    pub looping: bool,
+}
+
+impl CameraSequence {
+    /// Returns the length of the sequence in seconds, the time of its last keyframe, cut or
+    /// marker.
+    pub fn duration(&self) -> f32 {
+        let keyframes = self.keyframes.iter().map(|k| k.time);
+        let cuts = self.cuts.iter().map(|c| c.time);
+        let markers = self.markers.iter().map(|m| m.time);
+        keyframes.chain(cuts).chain(markers).fold(0., f32::max)
+    }
+
+    /// Returns the pose of the camera at the given time, or `None` if there are no keyframes.
+    ///
+    /// Positions follow a Catmull-Rom spline through the keyframes, rotations are interpolated
+    /// spherically and the field of view linearly.
+    pub fn sample(&self, time: f32) -> Option<CameraPose> {
+        let keys = &self.keyframes;
+        let next = keys.iter().position(|k| k.time > time);
+        let (i, t) = match next {
+            None => (keys.len().checked_sub(1)?, 0.),
+            Some(0) => (0, 0.),
+            Some(next) => {
+                let (a, b) = (&keys[next - 1], &keys[next]);
+                (
+                    next - 1,
+                    a.easing.apply((time - a.time) / (b.time - a.time)),
+                )
+            }
+        };
+
+        let k1 = &keys[i];
+        let k2 = keys.get(i + 1).unwrap_or(k1);
+        let p = |k: &CameraKeyframe| Vector3::from(k.position);
+        let (p1, p2) = (p(k1), p(k2));
+        // Past the ends, the spline continues in the direction of the first and last segments.
+        let p0 = if i > 0 { p(&keys[i - 1]) } else { p1 * 2. - p2 };
+        let p3 = keys.get(i + 2).map_or_else(|| p2 * 2. - p1, p);
+        let position = catmull_rom(&p0, &p1, &p2, &p3, t);
+        let rotation = k1.rotation().slerp(&k2.rotation(), t);
+        let fov = match (k1.fov, k2.fov) {
+            (Some(a), Some(b)) => Some(a + (b - a) * t),
+            (fov, None) | (None, fov) => fov,
+        }
+        .map(f32::to_radians);
+
+        Some(CameraPose {
+            position,
+            rotation,
+            fov,
+        })
+    }
+}
+
+/// Evaluates the Catmull-Rom spline segment from `p1` to `p2` at `t`.
+fn catmull_rom(
+    p0: &Vector3<f32>,
+    p1: &Vector3<f32>,
+    p2: &Vector3<f32>,
+    p3: &Vector3<f32>,
+    t: f32,
+) -> Vector3<f32> {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    (p1 * 2.
+        + (p2 - p0) * t
+        + (p0 * 2. - p1 * 5. + p2 * 4. - p3) * t2
+        + (p1 * 3. - p0 - p2 * 3. + p3) * t3)
+        * 0.5
+}
+
+impl Asset for CameraSequence {
+    const NAME: &'static str = "utils::CameraSequence";
+    type Data = Self;
+    type HandleStorage = VecStorage<Handle<Self>>;
+}
+
+impl Into<Result<ProcessingState<CameraSequence>>> for CameraSequence {
+    fn into(mut self) -> Result<ProcessingState<CameraSequence>> {
+        let times = self.keyframes.iter().map(|k| k.time);
+        let times = times
+            .chain(self.cuts.iter().map(|c| c.time))
+            .chain(self.markers.iter().map(|m| m.time));
+        for time in times {
+            if !time.is_finite() {
+                return Err(format!("Camera sequence has an invalid time {}", time).into());
+            }
+        }
+
+        // The files don't have to list the keyframes, cuts and markers in order.
+        let by_time = |a: f32, b: f32| a.partial_cmp(&b).unwrap_or(Ordering::Equal);
+        self.keyframes.sort_by(|a, b| by_time(a.time, b.time));
+        self.cuts.sort_by(|a, b| by_time(a.time, b.time));
+        self.markers.sort_by(|a, b| by_time(a.time, b.time));
+        Ok(ProcessingState::Loaded(self))
+    }
+}
+
+/// Plays a `CameraSequence` on the camera entity it is attached to.
+#[derive(Clone, Debug)]
+pub struct CameraSequencePlayer {
+    /// The sequence to play.
+    pub sequence: Handle<CameraSequence>,
+    /// Current time in the sequence in seconds.
+    pub time: f32,
+    /// Whether the sequence is playing.
+    pub playing: bool,
+    /// Playback speed, 1 being normal speed.
+    pub speed: f32,
+}
+
+impl CameraSequencePlayer {
+    /// Creates a player that starts playing the sequence from the beginning.
+    pub fn new(sequence: Handle<CameraSequence>) -> Self {
+        CameraSequencePlayer {
+            sequence,
+            time: 0.,
+            playing: true,
+            speed: 1.,
+        }
+    }
+}
+
+impl Component for CameraSequencePlayer {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Event sent by the `CameraSequenceSystem` on an `EventChannel<CameraSequenceEvent>`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CameraSequenceEvent {
+    /// A marker of the sequence played by the entity was reached.
+    Marker {
+        /// The entity playing the sequence.
+        entity: Entity,
+        /// Name of the marker.
+        name: String,
+    },
+    /// The sequence played by the entity reached its end. Not sent for looping sequences.
+    Finished {
+        /// The entity playing the sequence.
+        entity: Entity,
+    },
+}
+
+/// System playing `CameraSequencePlayer`s.
+///
+/// Moves the camera along the keyframes, changes its field of view, switches the
+/// `ActiveCamera` at cuts and sends `CameraSequenceEvent`s for markers and finished sequences.
+#[derive(Default)]
+pub struct CameraSequenceSystem;
+
+impl<'a> System<'a> for CameraSequenceSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, Time>,
+        Read<'a, AssetStorage<CameraSequence>>,
+        WriteStorage<'a, CameraSequencePlayer>,
+        WriteStorage<'a, Transform>,
+        WriteStorage<'a, Camera>,
+        ReadStorage<'a, Named>,
+        Write<'a, ActiveCamera>,
+        Write<'a, EventChannel<CameraSequenceEvent>>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            time,
+            sequences,
+            mut players,
+            mut transforms,
+            mut cameras,
+            names,
+            mut active_camera,
+            mut events,
+        ): Self::SystemData,
+    ) {
+        for (entity, player) in (&*entities, &mut players).join() {
+            if !player.playing {
+                continue;
+            }
+            let sequence = match sequences.get(&player.sequence) {
+                Some(sequence) => sequence,
+                None => continue,
+            };
+
+            let duration = sequence.duration();
+            let from = player.time;
+            let mut to = from + time.delta_seconds() * player.speed;
+            // Time ranges crossed this frame, and whether they include their end.
+            let mut crossed = vec![(from, to.min(duration), to >= duration)];
+            if to >= duration {
+                if sequence.looping && duration > 0. {
+                    to %= duration;
+                    crossed.push((0., to, false));
+                } else {
+                    to = duration;
+                    player.playing = false;
+                }
+            }
+            player.time = to;
+
+            for &(start, end, inclusive) in &crossed {
+                let reached = |t: f32| start <= t && (t < end || (inclusive && t <= end));
+                for marker in sequence.markers.iter().filter(|m| reached(m.time)) {
+                    events.single_write(CameraSequenceEvent::Marker {
+                        entity,
+                        name: marker.name.clone(),
+                    });
+                }
+                for cut in sequence.cuts.iter().filter(|c| reached(c.time)) {
+                    match (&*entities, &names)
+                        .join()
+                        .find(|(_, named)| named.name == cut.camera)
+                    {
+                        Some((camera, _)) => active_camera.entity = Some(camera),
+                        None => error!("Camera sequence cuts to unknown camera {}", cut.camera),
+                    }
+                }
+            }
+
+            if let Some(pose) = sequence.sample(to) {
+                if let Some(transform) = transforms.get_mut(entity) {
+                    transform.set_position(pose.position);
+                    transform.set_rotation(pose.rotation);
+                }
+                if let (Some(fov), Some(camera)) = (pose.fov, cameras.get_mut(entity)) {
+                    set_fov(camera, fov);
+                }
+            }
+
+            if !player.playing {
+                events.single_write(CameraSequenceEvent::Finished { entity });
+            }
+        }
+    }
+}
+
+/// Sets the vertical field of view of a camera with a perspective projection, keeping its aspect
+/// ratio and clipping planes. Orthographic cameras are left unchanged.
+fn set_fov(camera: &mut Camera, fov: f32) {
+    // The last row of a perspective projection is (0, 0, -1, 0).
+    if camera.proj[(3, 3)].abs() > ::std::f32::EPSILON {
+        return;
+    }
+    let mut projection = Perspective3::from_matrix_unchecked(camera.proj);
+    projection.set_fovy(fov);
+    camera.proj = projection.to_homogeneous();
+}
+
+/// Adds the `Processor` for `CameraSequence` assets and the `CameraSequenceSystem`.
+#[derive(Default)]
+pub struct CameraSequenceBundle;
+
+impl<'a, 'b> SystemBundle<'a, 'b> for CameraSequenceBundle {
+    fn build(self, builder: &mut DispatcherBuilder<'a, 'b>) -> BundleResult<()> {
+        builder.add(
+            Processor::<CameraSequence>::new(),
+            "camera_sequence_processor",
+            &[],
+        );
+        builder.add(
+            CameraSequenceSystem,
+            "camera_sequence",
+            &["camera_sequence_processor"],
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_renderer::Projection;
+
+    use super::*;
+
+    fn keyframe(time: f32, x: f32) -> CameraKeyframe {
+        CameraKeyframe {
+            time,
+            position: [x, 0., 0.],
+            rotation: [0., 0., 0.],
+            fov: None,
+            easing: Easing::Linear,
+        }
+    }
+
+    #[test]
+    fn sample_passes_through_keyframes() {
+        let sequence = CameraSequence {
+            keyframes: vec![keyframe(0., 0.), keyframe(1., 1.), keyframe(2., 2.)],
+            ..Default::default()
+        };
+        assert_eq!(2., sequence.duration());
+        for &(time, x) in &[(-1., 0.), (0., 0.), (0.5, 0.5), (1., 1.), (3., 2.)] {
+            let pose = sequence.sample(time).unwrap();
+            assert!((pose.position.x - x).abs() < 1.0e-5);
+        }
+        assert_eq!(None, CameraSequence::default().sample(0.));
+    }
+
+    #[test]
+    fn step_jumps_at_the_next_keyframe() {
+        assert_eq!(0., Easing::Step.apply(0.99));
+        assert_eq!(1., Easing::Step.apply(1.));
+    }
+
+    #[test]
+    fn loading_sorts_by_time_and_rejects_nan() {
+        let sequence = CameraSequence {
+            keyframes: vec![keyframe(1., 1.), keyframe(0., 0.)],
+            ..Default::default()
+        };
+        let loaded: Result<ProcessingState<CameraSequence>> = sequence.into();
+        match loaded {
+            Ok(ProcessingState::Loaded(sequence)) => {
+                assert_eq!(vec![keyframe(0., 0.), keyframe(1., 1.)], sequence.keyframes);
+            }
+            _ => panic!("Expected the sequence to load"),
+        }
+
+        let sequence = CameraSequence {
+            keyframes: vec![keyframe(::std::f32::NAN, 0.)],
+            ..Default::default()
+        };
+        let loaded: Result<ProcessingState<CameraSequence>> = sequence.into();
+        assert!(loaded.is_err());
+    }
+
+    #[test]
+    fn fov_keeps_the_aspect_ratio() {
+        let mut camera = Camera::from(Projection::perspective(2., 1.));
+        set_fov(&mut camera, 0.5);
+        let expected = Perspective3::new(2., 0.5, 0.1, 2000.).to_homogeneous();
+        assert!((camera.proj - expected).iter().all(|d| d.abs() < 1.0e-5));
+
+        let mut camera = Camera::standard_2d();
+        let proj = camera.proj;
+        set_fov(&mut camera, 0.5);
+        assert_eq!(proj, camera.proj);
+    }
+}
@@ -0,0 +1,328 @@
+//! Checkpoints the players respawn at.
+//!
+//! A `Checkpoint` is a trigger volume: when an entity with a `CheckpointPlayer` enters it, the
+//! checkpoint becomes the latest one of that player and the `CheckpointSystem` sends
+//! a `CheckpointEvent`. Checkpoints have an order, so going back to an earlier one doesn't undo
+//! the progress.
+//!
+//! The components to restore on respawn, e.g. the `Transform` and the `Stats` of the player, are
+//! registered by adding a `CheckpointComponentSystem` for each of them, after the
+//! `CheckpointSystem`. They snapshot the component into a `CheckpointSnapshot` when the player
+//! spawns and when it reaches a checkpoint, and put the snapshot back when the game calls
+//! `CheckpointPlayer::respawn`, e.g. when the player dies:
+//!
+//! ```rust,ignore
+//! let game_data = GameDataBuilder::default()
+//!     .with(CheckpointSystem, "checkpoint_system", &["transform_system"])
+//!     .with(
+//!         CheckpointComponentSystem::<Transform>::new(),
+//!         "checkpoint_transform_system",
+//!         &["checkpoint_system"],
+//!     )
+//!     .with(
+//!         CheckpointComponentSystem::<Stats>::new(),
+//!         "checkpoint_stats_system",
+//!         &["checkpoint_system"],
+//!     );
+//! ```
+
+use std::marker::PhantomData;
+
+use amethyst_assets::{PrefabData, PrefabError};
+use amethyst_core::{
+    nalgebra::Point3,
+    shrev::EventChannel,
+    specs::prelude::{
+        Component, DenseVecStorage, Entities, Entity, Join, ReadStorage, System, Write,
+        WriteStorage,
+    },
+    GlobalTransform,
+};
+
+use crate::force_field::FieldShape;
+
+/// A trigger volume saving the progress of the players entering it.
+#[derive(Clone, Debug, Deserialize, PartialEq, PrefabData, Serialize)]
+#[prefab(Component)]
+#[serde(default)]
+pub struct Checkpoint {
+    /// The volume, in the local space of the entity.
+    pub shape: FieldShape,
+    /// The order of the checkpoint along the level. A player reaching it after a checkpoint of
+    /// a higher order keeps the other one.
+    pub order: u32,
+}
+
+impl Default for Checkpoint {
+    fn default() -> Self {
+        Checkpoint {
+            shape: FieldShape::Sphere { radius: 2. },
+            order: 0,
+        }
+    }
+}
+
+impl Checkpoint {
+    /// Creates a checkpoint of the order with the volume.
+    pub fn new(shape: FieldShape, order: u32) -> Self {
+        Checkpoint { shape, order }
+    }
+}
+
+impl Component for Checkpoint {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// A player reaching checkpoints and respawning at them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CheckpointPlayer {
+    latest: Option<(Entity, u32)>,
+    reached: u32,
+    respawns: u32,
+}
+
+impl CheckpointPlayer {
+    /// Creates a player without any checkpoint, respawning where they spawned.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns the latest checkpoint reached by the player.
+    pub fn latest(&self) -> Option<Entity> {
+        self.latest.map(|(checkpoint, _)| checkpoint)
+    }
+
+    /// Puts the registered components of the player back as they were at the latest checkpoint,
+    /// on the next run of the `CheckpointComponentSystem`s.
+    pub fn respawn(&mut self) {
+        self.respawns = self.respawns.wrapping_add(1);
+    }
+}
+
+impl Component for CheckpointPlayer {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Event sent by the `CheckpointSystem` on an `EventChannel<CheckpointEvent>` when a player
+/// reaches a checkpoint.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CheckpointEvent {
+    /// The entity of the `CheckpointPlayer`.
+    pub player: Entity,
+    /// The entity of the `Checkpoint`.
+    pub checkpoint: Entity,
+}
+
+/// Finds the `Checkpoint`s the `CheckpointPlayer`s enter and sends `CheckpointEvent`s.
+#[derive(Default)]
+pub struct CheckpointSystem;
+
+impl<'a> System<'a> for CheckpointSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, GlobalTransform>,
+        ReadStorage<'a, Checkpoint>,
+        WriteStorage<'a, CheckpointPlayer>,
+        Write<'a, EventChannel<CheckpointEvent>>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, globals, checkpoints, mut players, mut channel): Self::SystemData,
+    ) {
+        let volumes = (&*entities, &globals, &checkpoints)
+            .join()
+            .filter_map(|(entity, global, checkpoint)| {
+                Some((entity, global.0.try_inverse()?, checkpoint))
+            })
+            .collect::<Vec<_>>();
+
+        for (entity, global, player) in (&*entities, &globals, &mut players).join() {
+            let position = Point3::from(global.0.column(3).xyz());
+            let reached = volumes
+                .iter()
+                .filter(|(_, inverse, checkpoint)| {
+                    checkpoint
+                        .shape
+                        .contains(&inverse.transform_point(&position))
+                })
+                .filter(|(checkpoint, _, _)| player.latest() != Some(*checkpoint))
+                .filter(|(_, _, checkpoint)| {
+                    player
+                        .latest
+                        .map_or(true, |(_, order)| checkpoint.order >= order)
+                })
+                .max_by_key(|(_, _, checkpoint)| checkpoint.order);
+            if let Some(&(checkpoint, _, volume)) = reached {
+                player.latest = Some((checkpoint, volume.order));
+                player.reached = player.reached.wrapping_add(1);
+                channel.single_write(CheckpointEvent {
+                    player: entity,
+                    checkpoint,
+                });
+            }
+        }
+    }
+}
+
+/// The component `T` of a `CheckpointPlayer` at its latest checkpoint.
+#[derive(Clone, Debug)]
+pub struct CheckpointSnapshot<T> {
+    value: T,
+    reached: u32,
+    respawns: u32,
+}
+
+impl<T> CheckpointSnapshot<T> {
+    /// Returns the component as it was at the checkpoint.
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: Send + Sync + 'static> Component for CheckpointSnapshot<T> {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Snapshots the component `T` of the `CheckpointPlayer`s when they spawn and reach checkpoints,
+/// and restores it when they respawn.
+pub struct CheckpointComponentSystem<T> {
+    marker: PhantomData<T>,
+}
+
+impl<T> CheckpointComponentSystem<T> {
+    /// Creates the system.
+    pub fn new() -> Self {
+        CheckpointComponentSystem {
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Default for CheckpointComponentSystem<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, T: Component + Clone + Send + Sync> System<'a> for CheckpointComponentSystem<T> {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, CheckpointPlayer>,
+        WriteStorage<'a, T>,
+        WriteStorage<'a, CheckpointSnapshot<T>>,
+    );
+
+    fn run(&mut self, (entities, players, mut components, mut snapshots): Self::SystemData) {
+        for (entity, player) in (&*entities, &players).join() {
+            let snapshot = match snapshots.get_mut(entity) {
+                Some(snapshot) => snapshot,
+                None => {
+                    if let Some(component) = components.get(entity) {
+                        let snapshot = CheckpointSnapshot {
+                            value: component.clone(),
+                            reached: player.reached,
+                            respawns: player.respawns,
+                        };
+                        if let Err(err) = snapshots.insert(entity, snapshot) {
+                            error!("Failed to snapshot the player: {}", err);
+                        }
+                    }
+                    continue;
+                }
+            };
+            if snapshot.reached != player.reached {
+                if let Some(component) = components.get(entity) {
+                    snapshot.value = component.clone();
+                }
+                snapshot.reached = player.reached;
+            }
+            if snapshot.respawns != player.respawns {
+                if let Err(err) = components.insert(entity, snapshot.value.clone()) {
+                    error!("Failed to respawn the player: {}", err);
+                }
+                snapshot.respawns = player.respawns;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use amethyst_core::{
+        nalgebra::Vector3,
+        specs::prelude::{Builder, RunNow, World},
+        Transform,
+    };
+
+    #[test]
+    fn players_respawn_at_the_latest_checkpoint() {
+        let mut world = World::new();
+        world.register::<GlobalTransform>();
+        world.register::<Transform>();
+        world.register::<Checkpoint>();
+        world.register::<CheckpointPlayer>();
+        world.register::<CheckpointSnapshot<Transform>>();
+        world.add_resource(EventChannel::<CheckpointEvent>::new());
+
+        let player = world
+            .create_entity()
+            .with(GlobalTransform::default())
+            .with(Transform::default())
+            .with(CheckpointPlayer::new())
+            .build();
+        let checkpoints = [(10., 1), (20., 0)]
+            .iter()
+            .map(|&(x, order)| {
+                let mut transform = Transform::default();
+                transform.set_x(x);
+                world
+                    .create_entity()
+                    .with(GlobalTransform(transform.matrix()))
+                    .with(Checkpoint::new(FieldShape::Sphere { radius: 2. }, order))
+                    .build()
+            })
+            .collect::<Vec<_>>();
+
+        let run = |world: &mut World, x: f32| {
+            world
+                .write_storage::<Transform>()
+                .get_mut(player)
+                .unwrap()
+                .set_x(x);
+            let matrix = world
+                .read_storage::<Transform>()
+                .get(player)
+                .unwrap()
+                .matrix();
+            *world
+                .write_storage::<GlobalTransform>()
+                .get_mut(player)
+                .unwrap() = GlobalTransform(matrix);
+            CheckpointSystem.run_now(&world.res);
+            CheckpointComponentSystem::<Transform>::new().run_now(&world.res);
+        };
+        run(&mut world, 0.);
+        run(&mut world, 10.5);
+        // The second checkpoint has a lower order, so the first one stays the latest.
+        run(&mut world, 20.);
+        let latest = world
+            .read_storage::<CheckpointPlayer>()
+            .get(player)
+            .unwrap()
+            .latest();
+        assert_eq!(Some(checkpoints[0]), latest);
+
+        world
+            .write_storage::<CheckpointPlayer>()
+            .get_mut(player)
+            .unwrap()
+            .respawn();
+        run(&mut world, 30.);
+        let transforms = world.read_storage::<Transform>();
+        let position = transforms.get(player).unwrap().translation();
+        assert!((position - Vector3::new(10.5, 0., 0.)).norm() < 1e-4);
+    }
+}
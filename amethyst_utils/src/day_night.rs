@@ -0,0 +1,421 @@
+//! Day and night cycle, moving the sun and changing the colors of the sky over the day.
+//!
+//! Insert a `DayNightCycle` resource, add the `DayNightSun` component to the entity with the
+//! directional light or sun light standing for the sun, and add the `DayNightSystem` to the
+//! dispatcher. The system then drives the light, the `AmbientColor` and the `SkyboxColor`, and
+//! sends a `DayNightEvent` at dawn and dusk.
+
+use std::{cmp::Ordering, f32::consts::PI};
+
+use amethyst_core::{
+    shrev::EventChannel,
+    specs::prelude::{
+        Component, Join, NullStorage, Read, ReadStorage, System, Write, WriteStorage,
+    },
+    timing::Time,
+};
+use amethyst_renderer::{AmbientColor, Light, Rgba, SkyboxColor};
+
+/// Colors of the sky and the sun at a time of the day.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct SkyKey {
+    /// Time of the day, as a fraction of the day from midnight: 0.5 is noon.
+    pub time: f32,
+    /// Color temperature of the sun light, in kelvin.
+    pub sun_temperature: f32,
+    /// Brightness of the sun light, multiplying its color.
+    pub sun_intensity: f32,
+    /// Color of the ambient light.
+    pub ambient: Rgba,
+    /// Color of the sky above the viewer.
+    pub zenith: Rgba,
+    /// Color of the sky below the viewer.
+    pub nadir: Rgba,
+    /// Color of the fog, usually close to the sky at the horizon.
+    pub fog: Rgba,
+}
+
+/// The sun and sky of the current time of the day, see `DayNightCycle::sky`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SkyState {
+    /// Direction the sun light points to.
+    pub sun_direction: [f32; 3],
+    /// Color of the sun light.
+    pub sun_color: Rgba,
+    /// Color of the ambient light.
+    pub ambient: Rgba,
+    /// Color of the sky above the viewer.
+    pub zenith: Rgba,
+    /// Color of the sky below the viewer.
+    pub nadir: Rgba,
+    /// Color of the fog.
+    pub fog: Rgba,
+}
+
+/// Events sent by the `DayNightSystem`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DayNightEvent {
+    /// The sun rose.
+    Dawn,
+    /// The sun set.
+    Dusk,
+}
+
+/// Resource holding the time of the day and how the sky looks over the day.
+///
+/// The sun rises in the east, along `+x`, at `dawn`, culminates halfway through the day and sets
+/// in the west at `dusk`, in a plane tilted from the vertical towards `-z` by `tilt`. Between the
+/// keys, the colors of the sky are interpolated linearly, wrapping around midnight.
+///
+/// No fog pass exists in the renderer, so the fog color is not applied by the system; passes
+/// drawing fog read it from `sky`, so it matches the sky.
+#[derive(Clone, Debug)]
+pub struct DayNightCycle {
+    /// Length of a day in seconds.
+    pub day_length: f32,
+    /// Speed the day goes by at, 0 stopping it.
+    pub speed: f32,
+    /// Time of the sunrise, as a fraction of the day.
+    pub dawn: f32,
+    /// Time of the sunset, as a fraction of the day.
+    pub dusk: f32,
+    /// Angle in radians of the path of the sun from the vertical.
+    pub tilt: f32,
+    /// Colors of the sky over the day, by increasing time.
+    pub keys: Vec<SkyKey>,
+    time: f32,
+    sky: SkyState,
+}
+
+impl Default for DayNightCycle {
+    fn default() -> Self {
+        DayNightCycle::new(600.)
+    }
+}
+
+impl DayNightCycle {
+    /// Creates a cycle of days of the given length in seconds, starting at noon, with the sun
+    /// rising at 6:00 and setting at 18:00.
+    pub fn new(day_length: f32) -> Self {
+        DayNightCycle {
+            day_length,
+            speed: 1.,
+            dawn: 0.25,
+            dusk: 0.75,
+            tilt: 0.3,
+            keys: vec![
+                sky_key(
+                    0.,
+                    4000.,
+                    0.,
+                    [0.02, 0.02, 0.05],
+                    [0.01, 0.01, 0.05],
+                    [0., 0., 0.02],
+                    [0.02, 0.02, 0.05],
+                ),
+                sky_key(
+                    0.25,
+                    2000.,
+                    0.5,
+                    [0.2, 0.16, 0.16],
+                    [0.35, 0.4, 0.6],
+                    [0.9, 0.5, 0.3],
+                    [0.8, 0.55, 0.4],
+                ),
+                sky_key(
+                    0.5,
+                    6500.,
+                    1.,
+                    [0.4, 0.4, 0.45],
+                    [0.75, 1., 1.],
+                    [0.1, 0.3, 0.35],
+                    [0.7, 0.8, 0.9],
+                ),
+                sky_key(
+                    0.75,
+                    2500.,
+                    0.5,
+                    [0.2, 0.12, 0.12],
+                    [0.3, 0.25, 0.45],
+                    [0.9, 0.4, 0.2],
+                    [0.7, 0.4, 0.3],
+                ),
+            ],
+            time: 0.5,
+            sky: SkyState::default(),
+        }
+    }
+
+    /// Sets the time of the sunrise and sunset, as fractions of the day.
+    ///
+    /// The keys are left as they are, so they usually need to be moved too.
+    pub fn with_dawn_dusk(mut self, dawn: f32, dusk: f32) -> Self {
+        self.dawn = dawn;
+        self.dusk = dusk;
+        self
+    }
+
+    /// Replaces the colors of the sky over the day.
+    pub fn with_keys(mut self, mut keys: Vec<SkyKey>) -> Self {
+        keys.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap_or(Ordering::Equal));
+        self.keys = keys;
+        self
+    }
+
+    /// Sets the time of the day the cycle starts at.
+    pub fn with_time_of_day(mut self, time: f32) -> Self {
+        self.set_time_of_day(time);
+        self
+    }
+
+    /// Returns the time of the day, as a fraction of the day from midnight.
+    pub fn time_of_day(&self) -> f32 {
+        self.time
+    }
+
+    /// Jumps to a time of the day, as a fraction of the day from midnight.
+    ///
+    /// No `DayNightEvent` is sent for the dawn or dusk skipped over.
+    pub fn set_time_of_day(&mut self, time: f32) {
+        self.time = wrap(time);
+    }
+
+    /// Checks whether the sun is up.
+    pub fn is_day(&self) -> bool {
+        in_range(self.time, self.dawn, self.dusk)
+    }
+
+    /// Returns the sun and sky at the current time of the day, as of the last run of the
+    /// `DayNightSystem`.
+    pub fn sky(&self) -> &SkyState {
+        &self.sky
+    }
+
+    /// Returns the angle of the sun above the eastern horizon, `PI` at the western horizon and
+    /// beyond `PI` at night.
+    fn sun_angle(&self) -> f32 {
+        let day = wrap(self.dusk - self.dawn);
+        let since_dawn = wrap(self.time - self.dawn);
+        if since_dawn < day {
+            PI * since_dawn / day
+        } else {
+            PI + PI * (since_dawn - day) / (1. - day)
+        }
+    }
+
+    fn update_sky(&mut self) {
+        let angle = self.sun_angle();
+        let (sin, cos) = angle.sin_cos();
+        let sun_direction = [-cos, -sin * self.tilt.cos(), sin * self.tilt.sin()];
+
+        let key = match self.keys.len() {
+            0 => {
+                self.sky.sun_direction = sun_direction;
+                return;
+            }
+            1 => self.keys[0].clone(),
+            len => {
+                let next = self
+                    .keys
+                    .iter()
+                    .position(|key| key.time > self.time)
+                    .unwrap_or(0);
+                let previous = (next + len - 1) % len;
+                let (a, b) = (&self.keys[previous], &self.keys[next]);
+                let span = wrap(b.time - a.time);
+                let t = if span > 0. {
+                    wrap(self.time - a.time) / span
+                } else {
+                    0.
+                };
+                SkyKey {
+                    time: self.time,
+                    sun_temperature: lerp(a.sun_temperature, b.sun_temperature, t),
+                    sun_intensity: lerp(a.sun_intensity, b.sun_intensity, t),
+                    ambient: lerp_color(a.ambient, b.ambient, t),
+                    zenith: lerp_color(a.zenith, b.zenith, t),
+                    nadir: lerp_color(a.nadir, b.nadir, t),
+                    fog: lerp_color(a.fog, b.fog, t),
+                }
+            }
+        };
+        let Rgba(r, g, b, _) = temperature_color(key.sun_temperature);
+        let i = key.sun_intensity;
+        self.sky = SkyState {
+            sun_direction,
+            sun_color: Rgba(r * i, g * i, b * i, 1.),
+            ambient: key.ambient,
+            zenith: key.zenith,
+            nadir: key.nadir,
+            fog: key.fog,
+        };
+    }
+}
+
+/// Returns the color of the light of a black body at the given temperature in kelvin, e.g. 2000
+/// for candle light or 6500 for daylight.
+///
+/// This is the usual fit of the blackbody color curve, good between 1000 and 40000 kelvin.
+pub fn temperature_color(kelvin: f32) -> Rgba {
+    let t = kelvin.max(1000.).min(40_000.) / 100.;
+    let r = if t <= 66. {
+        255.
+    } else {
+        329.698_7 * (t - 60.).powf(-0.133_204_76)
+    };
+    let g = if t <= 66. {
+        99.470_8 * t.ln() - 161.119_57
+    } else {
+        288.122_16 * (t - 60.).powf(-0.075_514_85)
+    };
+    let b = if t >= 66. {
+        255.
+    } else if t <= 19. {
+        0.
+    } else {
+        138.517_73 * (t - 10.).ln() - 305.044_8
+    };
+    let channel = |value: f32| value.max(0.).min(255.) / 255.;
+    Rgba(channel(r), channel(g), channel(b), 1.)
+}
+
+/// Component marking the light the `DayNightSystem` moves as the sun.
+///
+/// The light must be a `Light::Directional` or a `Light::Sun`.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct DayNightSun;
+
+impl Component for DayNightSun {
+    type Storage = NullStorage<Self>;
+}
+
+/// System advancing the `DayNightCycle`, and applying it to the sun, the `AmbientColor` and the
+/// `SkyboxColor`.
+#[derive(Default)]
+pub struct DayNightSystem;
+
+impl<'a> System<'a> for DayNightSystem {
+    type SystemData = (
+        Read<'a, Time>,
+        Write<'a, DayNightCycle>,
+        Write<'a, EventChannel<DayNightEvent>>,
+        Write<'a, AmbientColor>,
+        Write<'a, SkyboxColor>,
+        ReadStorage<'a, DayNightSun>,
+        WriteStorage<'a, Light>,
+    );
+
+    fn run(
+        &mut self,
+        (time, mut cycle, mut events, mut ambient, mut skybox, suns, mut lights): Self::SystemData,
+    ) {
+        if cycle.day_length > 0. {
+            let from = cycle.time;
+            let to = from + time.delta_seconds() * cycle.speed / cycle.day_length;
+            cycle.time = wrap(to);
+            if to - from < 1. {
+                if crossed(from, to, cycle.dawn) {
+                    events.single_write(DayNightEvent::Dawn);
+                }
+                if crossed(from, to, cycle.dusk) {
+                    events.single_write(DayNightEvent::Dusk);
+                }
+            }
+        }
+        cycle.update_sky();
+
+        let sky = cycle.sky();
+        ambient.0 = sky.ambient;
+        skybox.zenith = sky.zenith;
+        skybox.nadir = sky.nadir;
+        for (_, light) in (&suns, &mut lights).join() {
+            match light {
+                Light::Directional(light) => {
+                    light.color = sky.sun_color;
+                    light.direction = sky.sun_direction;
+                }
+                Light::Sun(light) => {
+                    light.color = sky.sun_color;
+                    light.direction = sky.sun_direction;
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Checks whether `time` lies in `[from, to)`, wrapping around midnight.
+fn in_range(time: f32, from: f32, to: f32) -> bool {
+    if from <= to {
+        from <= time && time < to
+    } else {
+        from <= time || time < to
+    }
+}
+
+/// Checks whether `time`, or the same time of the next day, lies in `(from, to]`.
+fn crossed(from: f32, to: f32, time: f32) -> bool {
+    (from < time && time <= to) || (from < time + 1. && time + 1. <= to)
+}
+
+/// Wraps a time into `[0, 1)`.
+fn wrap(time: f32) -> f32 {
+    time - time.floor()
+}
+
+fn sky_key(
+    time: f32,
+    sun_temperature: f32,
+    sun_intensity: f32,
+    ambient: [f32; 3],
+    zenith: [f32; 3],
+    nadir: [f32; 3],
+    fog: [f32; 3],
+) -> SkyKey {
+    SkyKey {
+        time,
+        sun_temperature,
+        sun_intensity,
+        ambient: ambient.into(),
+        zenith: zenith.into(),
+        nadir: nadir.into(),
+        fog: fog.into(),
+    }
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+fn lerp_color(a: Rgba, b: Rgba, t: f32) -> Rgba {
+    Rgba(
+        lerp(a.0, b.0, t),
+        lerp(a.1, b.1, t),
+        lerp(a.2, b.2, t),
+        lerp(a.3, b.3, t),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sun_follows_dawn_and_dusk() {
+        let mut cycle = DayNightCycle::new(100.).with_dawn_dusk(0.2, 0.7);
+        cycle.set_time_of_day(0.45);
+        cycle.update_sky();
+        // Halfway between dawn and dusk the sun points straight down the tilted plane.
+        assert!(cycle.sky().sun_direction[0].abs() < 1e-5);
+        assert!(cycle.sky().sun_direction[1] < -0.9);
+        assert!(cycle.is_day());
+
+        cycle.set_time_of_day(0.95);
+        cycle.update_sky();
+        assert!(cycle.sky().sun_direction[1] > 0.9);
+        assert!(!cycle.is_day());
+
+        assert!(crossed(0.9, 1.3, 0.2) && !crossed(0.1, 0.2, 0.1));
+        assert_eq!(Rgba(1., 1., 1., 1.), temperature_color(6600.));
+    }
+}
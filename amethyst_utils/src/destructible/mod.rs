@@ -0,0 +1,14 @@
+//! Destruction of sprites and meshes at runtime.
+//!
+//! A `DestructibleTerrain` is a sprite made of solid and empty pixels that circles and polygons
+//! are carved out of, like the terrain of artillery games, with collision edges following what
+//! is left. A `ConvexPiece` is a convex mesh that splits in two along a plane, each half with the
+//! points of its convex collider.
+//!
+//! The engine has no physics, so colliders are returned as plain geometry to feed to the physics
+//! engine of the game.
+
+pub use self::{slice::*, terrain::*};
+
+mod slice;
+mod terrain;
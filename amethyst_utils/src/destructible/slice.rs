@@ -0,0 +1,354 @@
+use std::cmp::Ordering;
+
+use amethyst_assets::{AssetStorage, Loader};
+use amethyst_core::{
+    nalgebra::{Point3, Vector2, Vector3},
+    shrev::{EventChannel, ReaderId},
+    specs::prelude::{
+        Component, DenseVecStorage, Entities, Entity, Read, ReadExpect, ReadStorage, Resources,
+        System, Write, WriteStorage,
+    },
+    GlobalTransform, Parent, Transform,
+};
+use amethyst_renderer::{Material, Mesh, MeshData, MeshHandle, Plane, PosNormTex};
+
+/// A convex mesh, as a list of triangles, that can be split in two along a plane.
+///
+/// Positions are in the local space of the entity. The mesh must be closed and convex for the
+/// cut to be capped correctly, which also makes its vertices the points of its convex collider.
+#[derive(Clone, Debug)]
+pub struct ConvexPiece {
+    /// Vertices of the triangles, three by three
+    pub vertices: Vec<PosNormTex>,
+}
+
+impl ConvexPiece {
+    /// Creates a piece from the vertices of its triangles.
+    pub fn new(vertices: Vec<PosNormTex>) -> Self {
+        ConvexPiece { vertices }
+    }
+
+    /// Splits the piece along the plane, returning the part on the side the normal points to and
+    /// the part behind it, or `None` if the plane doesn't cross the piece.
+    ///
+    /// The cut is closed by a cap, with texture coordinates projected on the plane.
+    pub fn split(&self, plane: &Plane) -> Option<(ConvexPiece, ConvexPiece)> {
+        let mut front = Vec::new();
+        let mut back = Vec::new();
+        let mut section = Vec::new();
+        for triangle in self.vertices.chunks(3).filter(|t| t.len() == 3) {
+            let distances = [
+                plane.signed_distance(&Point3::from(triangle[0].position)),
+                plane.signed_distance(&Point3::from(triangle[1].position)),
+                plane.signed_distance(&Point3::from(triangle[2].position)),
+            ];
+            clip(triangle, &distances, 1., &mut front, &mut section);
+            clip(triangle, &distances, -1., &mut back, &mut section);
+        }
+        if front.is_empty() || back.is_empty() {
+            return None;
+        }
+        let points = section
+            .iter()
+            .map(|v: &PosNormTex| v.position)
+            .collect::<Vec<_>>();
+        cap(&points, -plane.normal, &mut front);
+        cap(&points, plane.normal, &mut back);
+        Some((ConvexPiece::new(front), ConvexPiece::new(back)))
+    }
+
+    /// Returns the distinct vertex positions, the points of the convex collider of the piece.
+    pub fn hull_points(&self) -> Vec<Point3<f32>> {
+        let mut points: Vec<Point3<f32>> = Vec::new();
+        for vertex in &self.vertices {
+            let point = Point3::from(vertex.position);
+            if points
+                .iter()
+                .all(|other| (other - point).norm_squared() > 1e-10)
+            {
+                points.push(point);
+            }
+        }
+        points
+    }
+
+    /// Returns the average of the hull points, to move each piece of a split apart.
+    pub fn center(&self) -> Point3<f32> {
+        let points = self.hull_points();
+        if points.is_empty() {
+            return Point3::origin();
+        }
+        let sum = points
+            .iter()
+            .fold(Vector3::zeros(), |sum, point| sum + point.coords);
+        Point3::from(sum / points.len() as f32)
+    }
+
+    /// Returns the mesh data drawing the piece.
+    pub fn mesh_data(&self) -> MeshData {
+        MeshData::PosNormTex(self.vertices.clone())
+    }
+}
+
+impl Component for ConvexPiece {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Keeps the part of the triangle on one side of the plane, `side` being 1 for the side the
+/// normal points to and -1 for the other, adding its triangles to `out` and the points on the
+/// plane to `section`.
+fn clip(
+    triangle: &[PosNormTex],
+    distances: &[f32; 3],
+    side: f32,
+    out: &mut Vec<PosNormTex>,
+    section: &mut Vec<PosNormTex>,
+) {
+    let mut polygon = Vec::with_capacity(4);
+    for i in 0..3 {
+        let j = (i + 1) % 3;
+        let (da, db) = (distances[i] * side, distances[j] * side);
+        if da >= 0. {
+            polygon.push(triangle[i].clone());
+            if side > 0. && da <= std::f32::EPSILON {
+                section.push(triangle[i].clone());
+            }
+        }
+        if (da >= 0.) != (db >= 0.) {
+            let vertex = lerp_vertex(&triangle[i], &triangle[j], da / (da - db));
+            if side > 0. {
+                section.push(vertex.clone());
+            }
+            polygon.push(vertex);
+        }
+    }
+    // Fan triangulation keeps the winding of the triangle.
+    for i in 1..polygon.len().saturating_sub(1) {
+        out.push(polygon[0].clone());
+        out.push(polygon[i].clone());
+        out.push(polygon[i + 1].clone());
+    }
+}
+
+/// Closes a cut with a fan of triangles facing along `normal`.
+fn cap(points: &[Vector3<f32>], normal: Vector3<f32>, out: &mut Vec<PosNormTex>) {
+    if points.len() < 3 {
+        return;
+    }
+    let center = points.iter().fold(Vector3::zeros(), |sum, p| sum + p) / points.len() as f32;
+    // Basis of the plane, to sort the points by angle and project the texture.
+    let helper = if normal.x.abs() < 0.9 {
+        Vector3::x()
+    } else {
+        Vector3::y()
+    };
+    let u = normal.cross(&helper).normalize();
+    let v = normal.cross(&u);
+    let mut ring = points
+        .iter()
+        .map(|p| {
+            let offset = p - center;
+            (offset.dot(&v).atan2(offset.dot(&u)), *p)
+        })
+        .collect::<Vec<_>>();
+    ring.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+    ring.dedup_by(|a, b| (a.1 - b.1).norm_squared() < 1e-10);
+
+    let vertex = |position: Vector3<f32>| PosNormTex {
+        position,
+        normal,
+        tex_coord: Vector2::new(position.dot(&u), position.dot(&v)),
+    };
+    // The section of a convex piece is convex, and the angles grow from `u` towards `v`, so a fan
+    // is counter-clockwise around `normal`.
+    for i in 1..ring.len().saturating_sub(1) {
+        out.push(vertex(ring[0].1));
+        out.push(vertex(ring[i].1));
+        out.push(vertex(ring[i + 1].1));
+    }
+}
+
+fn lerp_vertex(a: &PosNormTex, b: &PosNormTex, t: f32) -> PosNormTex {
+    PosNormTex {
+        position: a.position + (b.position - a.position) * t,
+        normal: (a.normal + (b.normal - a.normal) * t).normalize(),
+        tex_coord: a.tex_coord + (b.tex_coord - a.tex_coord) * t,
+    }
+}
+
+/// Event asking the `ConvexSplitSystem` to split an entity with a `ConvexPiece`.
+#[derive(Clone, Debug)]
+pub struct SplitPiece {
+    /// The entity to split.
+    pub entity: Entity,
+    /// The plane to split it along, in world space.
+    pub plane: Plane,
+    /// Distance each half is moved away from the plane, so they don't overlap.
+    pub separation: f32,
+}
+
+/// Event sent by the `ConvexSplitSystem` once an entity is split.
+#[derive(Clone, Debug)]
+pub struct PieceSplit {
+    /// The entity that was split, now deleted.
+    pub entity: Entity,
+    /// The new entities, in front of and behind the plane, with the `Transform` and `Parent` of
+    /// the split entity.
+    pub pieces: (Entity, Entity),
+}
+
+/// System splitting entities with a `ConvexPiece` as asked by `SplitPiece` events.
+///
+/// Each half gets a copy of the components drawing the entity, its mesh loaded from the new
+/// vertices, and `PieceSplit` events tell the game to add their colliders and to copy its own
+/// components.
+#[derive(Default)]
+pub struct ConvexSplitSystem {
+    reader: Option<ReaderId<SplitPiece>>,
+}
+
+impl<'a> System<'a> for ConvexSplitSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, EventChannel<SplitPiece>>,
+        Write<'a, EventChannel<PieceSplit>>,
+        ReadExpect<'a, Loader>,
+        Read<'a, AssetStorage<Mesh>>,
+        ReadStorage<'a, GlobalTransform>,
+        WriteStorage<'a, ConvexPiece>,
+        WriteStorage<'a, Transform>,
+        WriteStorage<'a, Parent>,
+        WriteStorage<'a, MeshHandle>,
+        WriteStorage<'a, Material>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            requests,
+            mut splits,
+            loader,
+            mesh_storage,
+            globals,
+            mut pieces,
+            mut transforms,
+            mut parents,
+            mut meshes,
+            mut materials,
+        ): Self::SystemData,
+    ) {
+        let reader = self
+            .reader
+            .as_mut()
+            .expect("`ConvexSplitSystem::setup` was not called before `ConvexSplitSystem::run`");
+        for request in requests.read(reader) {
+            let (piece, global, transform) = match (
+                pieces.get(request.entity),
+                globals.get(request.entity),
+                transforms.get(request.entity),
+            ) {
+                (Some(piece), Some(global), Some(transform)) => (piece, global, transform.clone()),
+                _ => continue,
+            };
+            // Normals go to local space with the transpose of the local to world matrix.
+            let local_point = match global.0.try_inverse() {
+                Some(inverse) => inverse.transform_point(&Point3::from(
+                    request.plane.normal * -request.plane.distance,
+                )),
+                None => continue,
+            };
+            let local_normal = global.0.transpose().transform_vector(&request.plane.normal);
+            let plane = Plane::new(local_normal, local_point);
+            let (front, back) = match piece.split(&plane) {
+                Some(halves) => halves,
+                None => continue,
+            };
+            let material = materials.get(request.entity).cloned();
+            let parent = parents.get(request.entity).cloned();
+
+            let mut spawn = |half: ConvexPiece, direction: f32| {
+                let mut transform = transform.clone();
+                let offset = plane.normal * (request.separation * direction);
+                let offset = transform.rotation() * offset;
+                *transform.translation_mut() += offset;
+                let mesh = loader.load_from_data(half.mesh_data(), (), &mesh_storage);
+                let mut builder = entities
+                    .build_entity()
+                    .with(transform, &mut transforms)
+                    .with(mesh, &mut meshes)
+                    .with(half, &mut pieces);
+                if let Some(ref material) = material {
+                    builder = builder.with(material.clone(), &mut materials);
+                }
+                if let Some(ref parent) = parent {
+                    builder = builder.with(parent.clone(), &mut parents);
+                }
+                builder.build()
+            };
+            let pieces_spawned = (spawn(front, 1.), spawn(back, -1.));
+            if let Err(e) = entities.delete(request.entity) {
+                error!("Failed to delete split entity: {}", e);
+            }
+            splits.single_write(PieceSplit {
+                entity: request.entity,
+                pieces: pieces_spawned,
+            });
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        use amethyst_core::specs::prelude::SystemData;
+        Self::SystemData::setup(res);
+        self.reader = Some(
+            res.fetch_mut::<EventChannel<SplitPiece>>()
+                .register_reader(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A closed tetrahedron, the simplest convex mesh.
+    fn tetrahedron() -> ConvexPiece {
+        let corners = [
+            Vector3::new(0., 0., 0.),
+            Vector3::new(1., 0., 0.),
+            Vector3::new(0., 1., 0.),
+            Vector3::new(0., 0., 1.),
+        ];
+        let faces = [[0, 2, 1], [0, 1, 3], [0, 3, 2], [1, 2, 3]];
+        let vertices = faces
+            .iter()
+            .flat_map(|face| face.iter())
+            .map(|&i| PosNormTex {
+                position: corners[i],
+                normal: Vector3::z(),
+                tex_coord: Vector2::zeros(),
+            })
+            .collect();
+        ConvexPiece::new(vertices)
+    }
+
+    #[test]
+    fn split_caps_both_halves() {
+        let plane = Plane::new(Vector3::x(), Point3::new(0.5, 0., 0.));
+        let (front, back) = tetrahedron().split(&plane).unwrap();
+        // The front is the corner at x = 1, cut off by a triangle.
+        assert_eq!(4, front.hull_points().len());
+        assert!(front.hull_points().iter().all(|p| p.x >= 0.5 - 1e-6));
+        assert_eq!(6, back.hull_points().len());
+        assert!(back.hull_points().iter().all(|p| p.x <= 0.5 + 1e-6));
+        let cap_normals = front
+            .vertices
+            .iter()
+            .filter(|v| (v.normal + Vector3::x()).norm() < 1e-6)
+            .count();
+        assert_eq!(3, cap_normals);
+
+        let outside = Plane::new(Vector3::x(), Point3::new(2., 0., 0.));
+        assert!(tetrahedron().split(&outside).is_none());
+    }
+}
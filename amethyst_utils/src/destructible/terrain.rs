@@ -0,0 +1,346 @@
+use amethyst_assets::{AssetStorage, Loader};
+use amethyst_core::specs::prelude::{
+    Component, DenseVecStorage, Entities, Join, Read, ReadExpect, System, Write, WriteStorage,
+};
+use amethyst_renderer::{
+    Sprite, SpriteRender, SpriteSheet, Texture, TextureData, TextureHandle, TextureMetadata,
+    TextureUpdates,
+};
+
+/// Alpha from which a pixel is solid.
+const SOLID_ALPHA: u8 = 128;
+
+/// A sprite that holes can be carved into, with collision edges around its solid pixels.
+///
+/// Positions are in the local space of the entity, in pixels with `y` up, the sprite being
+/// centered on the entity like the sprites drawn by `DrawFlat2D`. The
+/// `DestructibleTerrainSystem` loads a dynamic texture of the pixels and shows it with a
+/// `SpriteRender` on the entity, then replaces the carved pixels in that texture with
+/// `TextureUpdates`.
+#[derive(Clone, Debug)]
+pub struct DestructibleTerrain {
+    width: u32,
+    height: u32,
+    /// RGBA pixels, row by row from the top.
+    pixels: Vec<u8>,
+    edges: Vec<[[f32; 2]; 2]>,
+    /// Pixels changed since the last upload, as `[left, top, right, bottom]` with the right
+    /// and bottom excluded.
+    dirty: Option<[u32; 4]>,
+    texture: Option<TextureHandle>,
+}
+
+impl DestructibleTerrain {
+    /// Creates a terrain from RGBA pixels, row by row from the top. Pixels with an alpha of at
+    /// least one half are solid.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are not `width * height * 4` bytes.
+    pub fn from_rgba(width: u32, height: u32, pixels: Vec<u8>) -> Self {
+        assert_eq!(
+            (width * height * 4) as usize,
+            pixels.len(),
+            "Pixels don't match the size of the terrain"
+        );
+        let mut terrain = DestructibleTerrain {
+            width,
+            height,
+            pixels,
+            edges: Vec::new(),
+            dirty: Some([0, 0, width, height]),
+            texture: None,
+        };
+        terrain.update_edges();
+        terrain
+    }
+
+    /// Returns the width and height of the terrain in pixels.
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Returns the RGBA pixels, row by row from the top.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Checks whether the terrain is solid at a position.
+    pub fn is_solid(&self, position: [f32; 2]) -> bool {
+        let (x, y) = self.to_pixel(position);
+        x >= 0. && y >= 0. && self.solid(x as i64, y as i64)
+    }
+
+    /// Makes the pixels in the circle empty, returning how many solid pixels were removed.
+    pub fn carve_circle(&mut self, center: [f32; 2], radius: f32) -> usize {
+        let min = [center[0] - radius, center[1] - radius];
+        let max = [center[0] + radius, center[1] + radius];
+        self.carve(min, max, |[x, y]| {
+            let (dx, dy) = (x - center[0], y - center[1]);
+            dx * dx + dy * dy <= radius * radius
+        })
+    }
+
+    /// Makes the pixels in the polygon empty, returning how many solid pixels were removed.
+    ///
+    /// The polygon may be concave; its edges must not cross each other.
+    pub fn carve_polygon(&mut self, points: &[[f32; 2]]) -> usize {
+        if points.len() < 3 {
+            return 0;
+        }
+        let mut min = points[0];
+        let mut max = points[0];
+        for point in points {
+            min = [min[0].min(point[0]), min[1].min(point[1])];
+            max = [max[0].max(point[0]), max[1].max(point[1])];
+        }
+        self.carve(min, max, |point| contains(points, point))
+    }
+
+    /// Returns the edges between solid and empty pixels, as segments in local space.
+    ///
+    /// Use them as the segments of a static collider; they are recomputed after every carve.
+    pub fn collider_edges(&self) -> &[[[f32; 2]; 2]] {
+        &self.edges
+    }
+
+    /// Returns the texture data of the current pixels, for a texture that can be updated.
+    pub fn texture_data(&self) -> TextureData {
+        TextureData::U8(
+            self.pixels.clone(),
+            TextureMetadata::srgb()
+                .with_size(self.width as u16, self.height as u16)
+                .dynamic(true),
+        )
+    }
+
+    /// Returns the RGBA pixels of a rectangle, row by row from the top.
+    fn region(&self, [left, top, right, bottom]: [u32; 4]) -> Vec<u8> {
+        let row = (self.width * 4) as usize;
+        self.pixels
+            .chunks(row)
+            .skip(top as usize)
+            .take((bottom - top) as usize)
+            .flat_map(|pixels| &pixels[left as usize * 4..right as usize * 4])
+            .cloned()
+            .collect()
+    }
+
+    fn carve<F>(&mut self, min: [f32; 2], max: [f32; 2], inside: F) -> usize
+    where
+        F: Fn([f32; 2]) -> bool,
+    {
+        // `max.y` is the top of the area, so the smallest row.
+        let (left, top) = self.to_pixel([min[0], max[1]]);
+        let (right, bottom) = self.to_pixel([max[0], min[1]]);
+        let clamp = |value: f32, limit: u32| value.max(0.).min(limit as f32) as u32;
+        let mut removed = 0;
+        let mut changed = [self.width, self.height, 0, 0];
+        for y in clamp(top.floor(), self.height)..clamp(bottom.ceil(), self.height) {
+            for x in clamp(left.floor(), self.width)..clamp(right.ceil(), self.width) {
+                if !self.solid(i64::from(x), i64::from(y)) {
+                    continue;
+                }
+                if inside(self.to_local(x as f32 + 0.5, y as f32 + 0.5)) {
+                    let index = ((y * self.width + x) * 4) as usize;
+                    self.pixels[index..index + 4].copy_from_slice(&[0, 0, 0, 0]);
+                    removed += 1;
+                    changed = [
+                        changed[0].min(x),
+                        changed[1].min(y),
+                        changed[2].max(x + 1),
+                        changed[3].max(y + 1),
+                    ];
+                }
+            }
+        }
+        if removed > 0 {
+            self.update_edges();
+            self.dirty = Some(match self.dirty {
+                Some(dirty) => [
+                    dirty[0].min(changed[0]),
+                    dirty[1].min(changed[1]),
+                    dirty[2].max(changed[2]),
+                    dirty[3].max(changed[3]),
+                ],
+                None => changed,
+            });
+        }
+        removed
+    }
+
+    /// Finds the edges with marching squares over the pixel centers, the outside being empty.
+    fn update_edges(&mut self) {
+        self.edges.clear();
+        for y in -1..i64::from(self.height) {
+            for x in -1..i64::from(self.width) {
+                let case = (self.solid(x, y) as u8) << 3
+                    | (self.solid(x + 1, y) as u8) << 2
+                    | (self.solid(x + 1, y + 1) as u8) << 1
+                    | self.solid(x, y + 1) as u8;
+                // Middles of the sides of the square between four pixel centers.
+                let (cx, cy) = (x as f32 + 0.5, y as f32 + 0.5);
+                let top = self.to_local(cx + 0.5, cy);
+                let right = self.to_local(cx + 1., cy + 0.5);
+                let bottom = self.to_local(cx + 0.5, cy + 1.);
+                let left = self.to_local(cx, cy + 0.5);
+                let segments: &[[[f32; 2]; 2]] = match case {
+                    1 | 14 => &[[left, bottom]],
+                    2 | 13 => &[[bottom, right]],
+                    3 | 12 => &[[left, right]],
+                    4 | 11 => &[[top, right]],
+                    5 => &[[left, top], [bottom, right]],
+                    6 | 9 => &[[top, bottom]],
+                    7 | 8 => &[[left, top]],
+                    10 => &[[top, right], [left, bottom]],
+                    _ => &[],
+                };
+                self.edges.extend_from_slice(segments);
+            }
+        }
+    }
+
+    fn solid(&self, x: i64, y: i64) -> bool {
+        if x < 0 || y < 0 || x >= i64::from(self.width) || y >= i64::from(self.height) {
+            return false;
+        }
+        self.pixels[((y as u32 * self.width + x as u32) * 4 + 3) as usize] >= SOLID_ALPHA
+    }
+
+    /// Converts a local position to pixel coordinates, from the top left corner.
+    fn to_pixel(&self, [x, y]: [f32; 2]) -> (f32, f32) {
+        (x + self.width as f32 / 2., self.height as f32 / 2. - y)
+    }
+
+    /// Converts pixel coordinates, from the top left corner, to a local position.
+    fn to_local(&self, x: f32, y: f32) -> [f32; 2] {
+        [x - self.width as f32 / 2., self.height as f32 / 2. - y]
+    }
+}
+
+impl Component for DestructibleTerrain {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Checks whether a point lies in a polygon, with the even-odd rule.
+fn contains(polygon: &[[f32; 2]], [x, y]: [f32; 2]) -> bool {
+    let mut inside = false;
+    let mut previous = polygon[polygon.len() - 1];
+    for &point in polygon {
+        if (point[1] > y) != (previous[1] > y)
+            && x < (previous[0] - point[0]) * (y - point[1]) / (previous[1] - point[1]) + point[0]
+        {
+            inside = !inside;
+        }
+        previous = point;
+    }
+    inside
+}
+
+/// System uploading the pixels of the `DestructibleTerrain`s that changed.
+///
+/// The first time, it loads a texture of the terrain and shows it with a new `SpriteRender`,
+/// then it queues `TextureUpdates` of the carved pixels.
+#[derive(Default)]
+pub struct DestructibleTerrainSystem;
+
+impl<'a> System<'a> for DestructibleTerrainSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Loader>,
+        Read<'a, AssetStorage<Texture>>,
+        Read<'a, AssetStorage<SpriteSheet>>,
+        Write<'a, TextureUpdates>,
+        WriteStorage<'a, DestructibleTerrain>,
+        WriteStorage<'a, SpriteRender>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            loader,
+            textures,
+            sprite_sheets,
+            mut updates,
+            mut terrains,
+            mut sprites,
+        ): Self::SystemData,
+    ) {
+        for (entity, terrain) in (&*entities, &mut terrains).join() {
+            let dirty = match terrain.dirty.take() {
+                Some(dirty) => dirty,
+                None => continue,
+            };
+            if let Some(ref texture) = terrain.texture {
+                let [left, top, right, bottom] = dirty;
+                updates.update(
+                    texture,
+                    (left as u16, top as u16),
+                    ((right - left) as u16, (bottom - top) as u16),
+                    terrain.region(dirty),
+                );
+                continue;
+            }
+
+            let (width, height) = terrain.size();
+            let texture = loader.load_from_data(terrain.texture_data(), (), &textures);
+            let sprite = Sprite::from_pixel_values(width, height, width, height, 0, 0, [0., 0.]);
+            let sprite_sheet = loader.load_from_data(
+                SpriteSheet::new(texture.clone(), vec![sprite]),
+                (),
+                &sprite_sheets,
+            );
+            let render = SpriteRender {
+                sprite_sheet,
+                sprite_number: 0,
+            };
+            if let Err(e) = sprites.insert(entity, render) {
+                error!("Failed to show destructible terrain: {}", e);
+            }
+            terrain.texture = Some(texture);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn carving_removes_pixels_and_edges() {
+        let mut terrain = DestructibleTerrain::from_rgba(8, 8, vec![255; 8 * 8 * 4]);
+        // The outline of a full square: 8 pixel centers on each side.
+        assert_eq!(4 * 8, terrain.collider_edges().len());
+        assert!(terrain.is_solid([0., 0.]));
+
+        assert_eq!(4, terrain.carve_circle([0., 0.], 1.));
+        assert!(!terrain.is_solid([0., 0.]) && terrain.is_solid([3., 3.]));
+        // The hole adds an outline around its 2x2 pixels.
+        assert_eq!(4 * 8 + 8, terrain.collider_edges().len());
+
+        // The top left quarter, but for the pixel already carved.
+        let quarter = [[-4., 0.], [0., 0.], [0., 4.], [-4., 4.]];
+        assert_eq!(15, terrain.carve_polygon(&quarter));
+        assert!(!terrain.is_solid([-3., 3.]) && terrain.is_solid([3., -3.]));
+    }
+
+    #[test]
+    fn carving_marks_the_changed_pixels() {
+        let mut terrain = DestructibleTerrain::from_rgba(8, 8, vec![255; 8 * 8 * 4]);
+        assert_eq!(Some([0, 0, 8, 8]), terrain.dirty.take());
+
+        terrain.carve_circle([0., 0.], 1.);
+        terrain.carve_circle([2.5, -2.5], 0.4);
+        assert_eq!(Some([3, 3, 7, 7]), terrain.dirty);
+        assert_eq!(0, terrain.carve_circle([2.5, -2.5], 0.4));
+        assert_eq!(Some([3, 3, 7, 7]), terrain.dirty);
+
+        let region = terrain.region([3, 3, 7, 7]);
+        assert_eq!(4 * 4 * 4, region.len());
+        // The holes at the top left and bottom right corners of the region, solid in between.
+        assert_eq!(&[0, 0, 0, 0], &region[..4]);
+        assert_eq!(&[255, 255, 255, 255], &region[8..12]);
+        assert_eq!(&[0, 0, 0, 0], &region[region.len() - 4..]);
+    }
+}
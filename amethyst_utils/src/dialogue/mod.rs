@@ -0,0 +1,459 @@
+//! Branching dialogues.
+//!
+//! A [`DialogueGraph`](struct.DialogueGraph.html) is an asset of named nodes, each with a line
+//! said by a speaker, gated by conditions on the
+//! [`Blackboard`](../blackboard/struct.Blackboard.html), with effects on it and choices leading to
+//! other nodes. It's loaded from RON with `RonFormat`,
+//! or imported from Yarn with [`YarnFormat`](struct.YarnFormat.html) and from Twine's Twee with
+//! [`TweeFormat`](struct.TweeFormat.html).
+//!
+//! A [`Dialogue`](struct.Dialogue.html) component walks a graph, and the `DialogueSystem` sends
+//! a [`DialogueEvent`](enum.DialogueEvent.html) for every line, ready to be shown by the UI. The
+//! texts are keys into the locale of the game, or the texts themselves.
+//!
+//! ```ron
+//! (
+//!     start: "greeting",
+//!     nodes: {
+//!         "greeting": (
+//!             speaker: Some("Guard"),
+//!             text: Some("guard-halt"),
+//!             choices: [
+//!                 (text: "answer-friend", target: Some("friend")),
+//!                 (
+//!                     text: "answer-bribe",
+//!                     target: Some("bribe"),
+//!                     conditions: [
+//!                         (variable: "gold", comparison: GreaterOrEqual, value: Number(10.0)),
+//!                     ],
+//!                 ),
+//!             ],
+//!         ),
+//!         "friend": (speaker: Some("Guard"), text: Some("guard-pass")),
+//!         "bribe": (
+//!             speaker: Some("Guard"),
+//!             text: Some("guard-bribed"),
+//!             effects: [Add(variable: "gold", amount: -10.0)],
+//!             events: ["open_gate"],
+//!         ),
+//!     },
+//! )
+//! ```
+
+pub use self::{twee::TweeFormat, yarn::YarnFormat};
+
+use std::collections::HashMap;
+
+use amethyst_assets::{
+    Asset, AssetStorage, Handle, ProcessingState, Processor, Result as AssetsResult,
+};
+use amethyst_core::{
+    bundle::{Result, SystemBundle},
+    shrev::EventChannel,
+    specs::prelude::{
+        Component, DenseVecStorage, DispatcherBuilder, Entities, Entity, Join, Read, System,
+        VecStorage, Write, WriteStorage,
+    },
+};
+
+use crate::blackboard::{Blackboard, Condition, Effect, Value};
+
+mod twee;
+mod yarn;
+
+/// The most nodes walked through without a line before the walk is given up, in case the nodes
+/// loop.
+const MAX_STEPS: usize = 256;
+
+/// A choice of the player at a `DialogueNode`.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct DialogueChoice {
+    /// The text of the choice, or its key in the locale.
+    pub text: String,
+    /// The node the choice leads to, `None` to end the dialogue.
+    pub target: Option<String>,
+    /// The choice is only offered while all conditions hold.
+    pub conditions: Vec<Condition>,
+    /// Applied to the `Blackboard` when the choice is picked.
+    pub effects: Vec<Effect>,
+}
+
+/// A node of a `DialogueGraph`.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct DialogueNode {
+    /// Who says the line.
+    pub speaker: Option<String>,
+    /// The line, or its key in the locale. Nodes without a line only apply their effects and go
+    /// on to the next node, unless they offer choices.
+    pub text: Option<String>,
+    /// The node is only entered while all conditions hold, otherwise the dialogue goes on to the
+    /// next node.
+    pub conditions: Vec<Condition>,
+    /// Applied to the `Blackboard` when the node is entered.
+    pub effects: Vec<Effect>,
+    /// Names of the `DialogueEvent::Event`s sent when the node is entered.
+    pub events: Vec<String>,
+    /// The choices of the player after the line.
+    pub choices: Vec<DialogueChoice>,
+    /// The node following the line when there are no choices, `None` to end the dialogue.
+    pub next: Option<String>,
+}
+
+/// A branching dialogue, see the [module documentation](index.html).
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct DialogueGraph {
+    /// The node the dialogue starts at.
+    pub start: String,
+    /// The nodes by name.
+    pub nodes: HashMap<String, DialogueNode>,
+}
+
+impl DialogueGraph {
+    /// Returns the choices of the node offered with the blackboard.
+    pub fn choices<'a>(
+        &'a self,
+        node: &str,
+        blackboard: &'a Blackboard,
+    ) -> impl Iterator<Item = &'a DialogueChoice> + 'a {
+        self.nodes
+            .get(node)
+            .into_iter()
+            .flat_map(|node| node.choices.iter())
+            .filter(move |choice| choice.conditions.iter().all(|c| c.check(blackboard)))
+    }
+
+    /// Walks from the node to the next line, applying the effects of the nodes on the way, and
+    /// sending their events and the line. Returns the node of the line, or `None` when the
+    /// dialogue ended.
+    fn walk(
+        &self,
+        entity: Entity,
+        mut node: Option<String>,
+        blackboard: &mut Blackboard,
+        events: &mut Vec<DialogueEvent>,
+    ) -> Option<String> {
+        for _ in 0..MAX_STEPS {
+            let name = node?;
+            let current = match self.nodes.get(&name) {
+                Some(current) => current,
+                None => {
+                    error!("Dialogue goes to unknown node {}", name);
+                    return None;
+                }
+            };
+            if !current.conditions.iter().all(|c| c.check(blackboard)) {
+                node = current.next.clone();
+                continue;
+            }
+            for effect in &current.effects {
+                effect.apply(blackboard);
+            }
+            for event in &current.events {
+                events.push(DialogueEvent::Event {
+                    entity,
+                    name: event.clone(),
+                });
+            }
+            let choices = self
+                .choices(&name, blackboard)
+                .map(|choice| choice.text.clone())
+                .collect::<Vec<_>>();
+            if current.text.is_some() || !choices.is_empty() {
+                events.push(DialogueEvent::Line {
+                    entity,
+                    node: name.clone(),
+                    speaker: current.speaker.clone(),
+                    text: current.text.clone(),
+                    choices,
+                });
+                return Some(name);
+            }
+            node = current.next.clone();
+        }
+        error!("Dialogue walked {} nodes without a line", MAX_STEPS);
+        None
+    }
+}
+
+impl Asset for DialogueGraph {
+    const NAME: &'static str = "utils::DialogueGraph";
+    type Data = Self;
+    type HandleStorage = VecStorage<Handle<Self>>;
+}
+
+impl Into<AssetsResult<ProcessingState<DialogueGraph>>> for DialogueGraph {
+    fn into(self) -> AssetsResult<ProcessingState<DialogueGraph>> {
+        Ok(ProcessingState::Loaded(self))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum DialogueInput {
+    Start(Option<String>),
+    Continue,
+    Choose(usize),
+}
+
+/// Walks a `DialogueGraph`, put on the entity talking, e.g. the character the player talks to.
+///
+/// The dialogue starts when the component is added. After each `DialogueEvent::Line`, call
+/// `advance` to go on, or `choose` to pick one of its choices.
+#[derive(Clone, Debug)]
+pub struct Dialogue {
+    /// The graph walked.
+    pub graph: Handle<DialogueGraph>,
+    node: Option<String>,
+    input: Option<DialogueInput>,
+}
+
+impl Dialogue {
+    /// Creates a dialogue starting at the start node of the graph.
+    pub fn new(graph: Handle<DialogueGraph>) -> Self {
+        Dialogue {
+            graph,
+            node: None,
+            input: Some(DialogueInput::Start(None)),
+        }
+    }
+
+    /// Creates a dialogue starting at the node.
+    pub fn starting_at<S: Into<String>>(graph: Handle<DialogueGraph>, node: S) -> Self {
+        Dialogue {
+            input: Some(DialogueInput::Start(Some(node.into()))),
+            ..Dialogue::new(graph)
+        }
+    }
+
+    /// Returns the node of the line shown right now.
+    pub fn node(&self) -> Option<&str> {
+        self.node.as_ref().map(String::as_str)
+    }
+
+    /// Returns whether the dialogue ended.
+    pub fn is_finished(&self) -> bool {
+        self.node.is_none() && self.input.is_none()
+    }
+
+    /// Goes on after a line without choices.
+    pub fn advance(&mut self) {
+        self.input = Some(DialogueInput::Continue);
+    }
+
+    /// Picks a choice of the last line, by its index in `DialogueEvent::Line::choices`.
+    pub fn choose(&mut self, index: usize) {
+        self.input = Some(DialogueInput::Choose(index));
+    }
+}
+
+impl Component for Dialogue {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Event sent by the `DialogueSystem` on an `EventChannel<DialogueEvent>`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DialogueEvent {
+    /// A line to show.
+    Line {
+        /// The entity with the `Dialogue`.
+        entity: Entity,
+        /// Name of the node of the line.
+        node: String,
+        /// Who says the line.
+        speaker: Option<String>,
+        /// The line, or its key in the locale.
+        text: Option<String>,
+        /// The texts of the choices offered, or their keys in the locale.
+        choices: Vec<String>,
+    },
+    /// An event of a node, for the game to react to.
+    Event {
+        /// The entity with the `Dialogue`.
+        entity: Entity,
+        /// Name of the event.
+        name: String,
+    },
+    /// The dialogue ended.
+    Ended {
+        /// The entity with the `Dialogue`.
+        entity: Entity,
+    },
+}
+
+/// Walks the `Dialogue`s on their input, sending `DialogueEvent`s.
+#[derive(Default)]
+pub struct DialogueSystem;
+
+impl<'a> System<'a> for DialogueSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, AssetStorage<DialogueGraph>>,
+        WriteStorage<'a, Dialogue>,
+        Write<'a, Blackboard>,
+        Write<'a, EventChannel<DialogueEvent>>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, graphs, mut dialogues, mut blackboard, mut channel): Self::SystemData,
+    ) {
+        let mut events = Vec::new();
+        for (entity, dialogue) in (&*entities, &mut dialogues).join() {
+            let graph = match (dialogue.input.as_ref(), graphs.get(&dialogue.graph)) {
+                (Some(_), Some(graph)) => graph,
+                _ => continue,
+            };
+            let next = match dialogue.input.take() {
+                Some(DialogueInput::Start(node)) => {
+                    Some(node.unwrap_or_else(|| graph.start.clone()))
+                }
+                Some(DialogueInput::Continue) => match dialogue.node {
+                    Some(ref node) if graph.choices(node, &blackboard).next().is_some() => {
+                        warn!("Dialogue can't go on from {} without a choice", node);
+                        continue;
+                    }
+                    Some(ref node) => graph.nodes.get(node).and_then(|node| node.next.clone()),
+                    None => None,
+                },
+                Some(DialogueInput::Choose(index)) => {
+                    let choice = dialogue
+                        .node
+                        .as_ref()
+                        .and_then(|node| graph.choices(node, &blackboard).nth(index))
+                        .cloned();
+                    match choice {
+                        Some(choice) => {
+                            for effect in &choice.effects {
+                                effect.apply(&mut blackboard);
+                            }
+                            choice.target
+                        }
+                        None => {
+                            warn!("Dialogue has no choice {}", index);
+                            continue;
+                        }
+                    }
+                }
+                None => continue,
+            };
+            dialogue.node = graph.walk(entity, next, &mut blackboard, &mut events);
+            if dialogue.node.is_none() {
+                events.push(DialogueEvent::Ended { entity });
+            }
+        }
+        channel.iter_write(events);
+    }
+}
+
+/// Adds the `Processor` for `DialogueGraph` assets and the `DialogueSystem`.
+#[derive(Default)]
+pub struct DialogueBundle;
+
+impl<'a, 'b> SystemBundle<'a, 'b> for DialogueBundle {
+    fn build(self, builder: &mut DispatcherBuilder<'a, 'b>) -> Result<()> {
+        builder.add(Processor::<DialogueGraph>::new(), "dialogue_processor", &[]);
+        builder.add(DialogueSystem, "dialogue_system", &["dialogue_processor"]);
+        Ok(())
+    }
+}
+
+/// Splits "Speaker: line" into the speaker and the line.
+fn split_speaker(line: &str) -> (Option<String>, String) {
+    match line.find(": ") {
+        Some(index) if !line[..index].contains(|c| c == '"' || c == '[' || c == '<') => (
+            Some(line[..index].trim().to_string()),
+            line[index + 2..].trim().to_string(),
+        ),
+        _ => (None, line.trim().to_string()),
+    }
+}
+
+/// Parses "$variable to value" or "$variable = value".
+fn parse_set(command: &str) -> Option<Effect> {
+    let (variable, value) = match command.find(" to ") {
+        Some(index) => (&command[..index], &command[index + " to ".len()..]),
+        None => {
+            let index = command.find('=')?;
+            (&command[..index], &command[index + 1..])
+        }
+    };
+    let variable = variable.trim().trim_start_matches('$').to_string();
+    let value = value.trim();
+    let value = match value {
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') => {
+            Value::Text(value[1..value.len() - 1].to_string())
+        }
+        _ => Value::Number(value.parse().ok()?),
+    };
+    Some(Effect::Set { variable, value })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use amethyst_core::specs::prelude::{Builder, World};
+
+    use crate::blackboard::Comparison;
+
+    #[test]
+    fn walk_skips_nodes_failing_their_conditions() {
+        let mut graph = DialogueGraph {
+            start: "start".to_string(),
+            nodes: HashMap::new(),
+        };
+        graph.nodes.insert(
+            "start".to_string(),
+            DialogueNode {
+                conditions: vec![Condition::new("met", Comparison::Equal, true)],
+                text: Some("welcome-back".to_string()),
+                next: Some("first".to_string()),
+                ..Default::default()
+            },
+        );
+        graph.nodes.insert(
+            "first".to_string(),
+            DialogueNode {
+                effects: vec![Effect::Set {
+                    variable: "met".to_string(),
+                    value: true.into(),
+                }],
+                next: Some("hello".to_string()),
+                ..Default::default()
+            },
+        );
+        graph.nodes.insert(
+            "hello".to_string(),
+            DialogueNode {
+                speaker: Some("Guard".to_string()),
+                text: Some("hello".to_string()),
+                ..Default::default()
+            },
+        );
+
+        let entity = World::new().create_entity().build();
+        let mut blackboard = Blackboard::new();
+        let mut events = Vec::new();
+        let node = graph.walk(
+            entity,
+            Some("start".to_string()),
+            &mut blackboard,
+            &mut events,
+        );
+        assert_eq!(Some("hello".to_string()), node);
+        assert!(blackboard.flag("met"));
+        assert_eq!(1, events.len());
+    }
+
+    #[test]
+    fn speakers_are_split_from_lines() {
+        assert_eq!(
+            (Some("Guard".to_string()), "Halt!".to_string()),
+            split_speaker("Guard: Halt!")
+        );
+        assert_eq!((None, "Halt!".to_string()), split_speaker("Halt!"));
+    }
+}
@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+
+use amethyst_assets::{
+    Error as AssetsError, ErrorKind as AssetsErrorKind, Result as AssetsResult, SimpleFormat,
+};
+
+use super::{parse_set, split_speaker, DialogueChoice, DialogueGraph, DialogueNode};
+
+/// Imports `DialogueGraph`s from the [Twee 3](https://github.com/iftechfoundation/twine-specs)
+/// files of Twine 2 stories.
+///
+/// Every passage is a `DialogueNode` named after the passage, its text being the line. Its links,
+/// with `[[text->Target]]`, `[[Target<-text]]`, `[[text|Target]]` or `[[Target]]`, are the choices.
+/// Lines with only a `(set: $variable to value)` or `<<set $variable to value>>` macro are
+/// effects, other macros are kept in the text.
+///
+/// The `StoryTitle` and `StoryData` passages are skipped, and the dialogue starts at the passage
+/// named "Start", or else at the first passage.
+#[derive(Clone, Debug, Default)]
+pub struct TweeFormat;
+
+impl SimpleFormat<DialogueGraph> for TweeFormat {
+    const NAME: &'static str = "TWEE";
+
+    type Options = ();
+
+    fn import(&self, bytes: Vec<u8>, _: ()) -> AssetsResult<DialogueGraph> {
+        let text = String::from_utf8(bytes)?;
+        parse(&text).ok_or_else(|| {
+            AssetsError::from_kind(AssetsErrorKind::Format("Failed to parse Twee file"))
+        })
+    }
+}
+
+fn parse(text: &str) -> Option<DialogueGraph> {
+    let mut nodes = HashMap::new();
+    let mut start = None;
+    let mut passage: Option<(String, DialogueNode, Vec<String>)> = None;
+    let mut finish = |passage: Option<(String, DialogueNode, Vec<String>)>| {
+        if let Some((name, mut node, lines)) = passage {
+            let text = lines.join("\n");
+            let text = text.trim();
+            if !text.is_empty() {
+                let (speaker, text) = split_speaker(text);
+                node.speaker = speaker;
+                node.text = Some(text);
+            }
+            nodes.insert(name, node);
+        }
+    };
+
+    for line in text.lines() {
+        if line.starts_with("::") {
+            finish(passage.take());
+            let name = passage_name(&line[2..]);
+            if name == "StoryTitle" || name == "StoryData" {
+                continue;
+            }
+            if start.is_none() || name == "Start" {
+                start = Some(name.clone());
+            }
+            passage = Some((name, DialogueNode::default(), Vec::new()));
+            continue;
+        }
+        let (_, node, lines) = match passage {
+            Some(ref mut passage) => passage,
+            None => continue,
+        };
+
+        let trimmed = line.trim();
+        let set = if trimmed.starts_with("(set:") && trimmed.ends_with(')') {
+            Some(&trimmed["(set:".len()..trimmed.len() - 1])
+        } else if trimmed.starts_with("<<set ") && trimmed.ends_with(">>") {
+            Some(&trimmed["<<set ".len()..trimmed.len() - 2])
+        } else {
+            None
+        };
+        if let Some(set) = set {
+            match parse_set(set) {
+                Some(effect) => node.effects.push(effect),
+                None => warn!("Skipping unsupported Twee macro {}", trimmed),
+            }
+            continue;
+        }
+
+        let mut rest = line;
+        let mut text = String::new();
+        while let Some(open) = rest.find("[[") {
+            let close = match rest[open..].find("]]") {
+                Some(close) => open + close,
+                None => break,
+            };
+            text.push_str(&rest[..open]);
+            node.choices.push(parse_link(&rest[open + 2..close]));
+            rest = &rest[close + 2..];
+        }
+        text.push_str(rest);
+        if !text.trim().is_empty() {
+            lines.push(text.trim().to_string());
+        }
+    }
+    finish(passage);
+
+    start.map(|start| DialogueGraph { start, nodes })
+}
+
+/// Returns the name in a passage header, without its tags and metadata.
+fn passage_name(header: &str) -> String {
+    let end = header
+        .find(|c| c == '[' || c == '{')
+        .unwrap_or(header.len());
+    header[..end].trim().to_string()
+}
+
+fn parse_link(link: &str) -> DialogueChoice {
+    let (text, target) = if let Some(index) = link.find("->") {
+        (&link[..index], &link[index + 2..])
+    } else if let Some(index) = link.find("<-") {
+        (&link[index + 2..], &link[..index])
+    } else if let Some(index) = link.find('|') {
+        (&link[..index], &link[index + 1..])
+    } else {
+        (link, link)
+    };
+    DialogueChoice {
+        text: text.trim().to_string(),
+        target: Some(target.trim().to_string()),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passages_are_nodes_and_links_are_choices() {
+        let graph = parse(
+            ":: StoryTitle\n\
+             The Gate\n\
+             \n\
+             :: Gate [guard] {\"position\":\"100,100\"}\n\
+             Guard: Halt! Who goes there?\n\
+             (set: $met to true)\n\
+             [[A friend->Friend]] or [[Run<-Flee]]\n\
+             \n\
+             :: Friend\n\
+             Guard: Pass.\n",
+        )
+        .unwrap();
+        assert_eq!("Gate", graph.start);
+        assert_eq!(2, graph.nodes.len());
+
+        let gate = &graph.nodes["Gate"];
+        assert_eq!(Some("Guard".to_string()), gate.speaker);
+        assert_eq!(Some("Halt! Who goes there?\nor".to_string()), gate.text);
+        assert_eq!(1, gate.effects.len());
+        let choices = gate
+            .choices
+            .iter()
+            .map(|choice| (choice.text.as_str(), choice.target.clone().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                ("A friend", "Friend".to_string()),
+                ("Flee", "Run".to_string())
+            ],
+            choices
+        );
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+
+use amethyst_assets::{
+    Error as AssetsError, ErrorKind as AssetsErrorKind, Result as AssetsResult, SimpleFormat,
+};
+
+use super::{parse_set, split_speaker, DialogueChoice, DialogueGraph, DialogueNode};
+
+/// Imports `DialogueGraph`s from [Yarn](https://yarnspinner.dev) files.
+///
+/// Every Yarn node is made of a `DialogueNode` for each of its lines, named after the title of
+/// the node for the first line, and `title#1`, `title#2`... for the next ones. Options, with
+/// `[[text|Target]]`, `[[Target]]` or `-> text` followed by an indented `<<jump Target>>`, are the
+/// choices of the last line. `<<jump Target>>` and `<<set $variable to value>>` are supported,
+/// other commands and expressions are skipped with a warning.
+///
+/// The dialogue starts at the node titled "Start", or else at the first node.
+#[derive(Clone, Debug, Default)]
+pub struct YarnFormat;
+
+impl SimpleFormat<DialogueGraph> for YarnFormat {
+    const NAME: &'static str = "YARN";
+
+    type Options = ();
+
+    fn import(&self, bytes: Vec<u8>, _: ()) -> AssetsResult<DialogueGraph> {
+        let text = String::from_utf8(bytes)?;
+        parse(&text).ok_or_else(|| {
+            AssetsError::from_kind(AssetsErrorKind::Format("Failed to parse Yarn file"))
+        })
+    }
+}
+
+/// Builds the `DialogueNode`s of one Yarn node.
+struct NodeBuilder<'a> {
+    title: &'a str,
+    name: String,
+    node: DialogueNode,
+    count: usize,
+    nodes: &'a mut HashMap<String, DialogueNode>,
+}
+
+impl<'a> NodeBuilder<'a> {
+    /// Returns the node the next line or effect goes to, chaining a new one after the current
+    /// one once it has a line or choices.
+    fn open(&mut self) -> &mut DialogueNode {
+        if self.node.text.is_some() || !self.node.choices.is_empty() {
+            self.count += 1;
+            let name = format!("{}#{}", self.title, self.count);
+            if self.node.choices.is_empty() && self.node.next.is_none() {
+                self.node.next = Some(name.clone());
+            }
+            let node = std::mem::replace(&mut self.node, DialogueNode::default());
+            self.nodes
+                .insert(std::mem::replace(&mut self.name, name), node);
+        }
+        &mut self.node
+    }
+
+    fn finish(self) {
+        self.nodes.insert(self.name, self.node);
+    }
+}
+
+fn parse(text: &str) -> Option<DialogueGraph> {
+    let mut nodes = HashMap::new();
+    let mut start = None;
+    let mut lines = text.lines();
+    loop {
+        // The header, up to "---".
+        let mut title = None;
+        for line in &mut lines {
+            let line = line.trim();
+            if line == "---" {
+                break;
+            }
+            if line.starts_with("title:") {
+                title = Some(line["title:".len()..].trim());
+            }
+        }
+        let title = match title {
+            Some(title) => title,
+            None => break,
+        };
+        if start.is_none() || title == "Start" {
+            start = Some(title.to_string());
+        }
+
+        let mut builder = NodeBuilder {
+            title,
+            name: title.to_string(),
+            node: DialogueNode::default(),
+            count: 0,
+            nodes: &mut nodes,
+        };
+        // Whether the indented lines belong to an option started with "->".
+        let mut in_option = false;
+        for raw in &mut lines {
+            let line = raw.trim();
+            if line == "===" {
+                break;
+            }
+            if line.is_empty() || line.starts_with("//") {
+                continue;
+            }
+            let indented = raw.starts_with(|c: char| c.is_whitespace());
+            in_option = in_option && indented;
+
+            if line.starts_with("[[") && line.ends_with("]]") {
+                let link = &line[2..line.len() - 2];
+                let (text, target) = match link.find('|') {
+                    Some(index) => (&link[..index], &link[index + 1..]),
+                    None => (link, link),
+                };
+                builder.node.choices.push(DialogueChoice {
+                    text: text.trim().to_string(),
+                    target: Some(target.trim().to_string()),
+                    ..Default::default()
+                });
+            } else if line.starts_with("->") {
+                builder.node.choices.push(DialogueChoice {
+                    text: line[2..].trim().to_string(),
+                    ..Default::default()
+                });
+                in_option = true;
+            } else if line.starts_with("<<") && line.ends_with(">>") {
+                let command = line[2..line.len() - 2].trim();
+                if command.starts_with("jump ") {
+                    let target = command["jump ".len()..].trim().to_string();
+                    match builder.node.choices.last_mut() {
+                        Some(choice) if in_option => choice.target = Some(target),
+                        _ => builder.node.next = Some(target),
+                    }
+                } else if command.starts_with("set ") {
+                    match parse_set(&command["set ".len()..]) {
+                        Some(effect) => builder.open().effects.push(effect),
+                        None => warn!("Skipping unsupported Yarn command <<{}>>", command),
+                    }
+                } else if command == "stop" {
+                    builder.node.next = None;
+                } else {
+                    warn!("Skipping unsupported Yarn command <<{}>>", command);
+                }
+            } else {
+                let (speaker, text) = split_speaker(line);
+                let node = builder.open();
+                node.speaker = speaker;
+                node.text = Some(text);
+            }
+        }
+        builder.finish();
+    }
+
+    start.map(|start| DialogueGraph { start, nodes })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::blackboard::{Effect, Value};
+
+    #[test]
+    fn lines_are_chained_and_options_are_choices() {
+        let graph = parse(
+            "title: Gate\n\
+             ---\n\
+             Guard: Halt!\n\
+             <<set $met to true>>\n\
+             Guard: Who goes there?\n\
+             [[A friend|Friend]]\n\
+             -> Run\n    <<jump Away>>\n\
+             ===\n\
+             title: Start\n\
+             ---\n\
+             <<jump Gate>>\n\
+             ===\n",
+        )
+        .unwrap();
+        assert_eq!("Start", graph.start);
+        assert_eq!(Some("Gate".to_string()), graph.nodes["Start"].next);
+
+        let halt = &graph.nodes["Gate"];
+        assert_eq!(Some("Guard".to_string()), halt.speaker);
+        assert_eq!(Some("Halt!".to_string()), halt.text);
+        assert_eq!(Some("Gate#1".to_string()), halt.next);
+
+        let question = &graph.nodes["Gate#1"];
+        assert_eq!(Some("Who goes there?".to_string()), question.text);
+        assert_eq!(
+            vec![Effect::Set {
+                variable: "met".to_string(),
+                value: Value::Bool(true),
+            }],
+            question.effects
+        );
+        let targets = question
+            .choices
+            .iter()
+            .map(|choice| choice.target.clone().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["Friend".to_string(), "Away".to_string()], targets);
+    }
+}
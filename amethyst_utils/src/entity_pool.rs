@@ -0,0 +1,250 @@
+//! Recycling of entities created from a prefab, e.g. for bullets and particles.
+
+use std::marker::PhantomData;
+
+use amethyst_assets::{Handle, Prefab};
+use amethyst_core::specs::prelude::{
+    Component, Entities, Entity, NullStorage, System, WriteExpect, WriteStorage,
+};
+use amethyst_renderer::HiddenPropagate;
+
+/// Marker of the pooled entities waiting to be reused.
+///
+/// They keep their components, so systems acting on them, e.g. moving bullets, should skip
+/// entities with this marker. They are also hidden with `HiddenPropagate`.
+#[derive(Clone, Debug, Default)]
+pub struct Inactive;
+
+impl Component for Inactive {
+    type Storage = NullStorage<Self>;
+}
+
+/// Resource holding entities created from a prefab, to activate and deactivate them instead of
+/// creating and deleting them, which fragments the storages when thousands of short lived
+/// entities come and go every second.
+///
+/// The `EntityPoolSystem` creates the entities up front, and the prefab is added to them by the
+/// `PrefabLoaderSystem` once loaded. Use `EntityPoolData` to take and give back entities.
+/// Entities coming out of the pool keep the components they had when they went back in, so set
+/// the ones that change, e.g. their `Transform`, after taking them.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let bullet_prefab = world.exec(|loader: PrefabLoader<'_, BulletPrefab>| {
+///     loader.load("prefab/bullet.ron", RonFormat, (), ())
+/// });
+/// world.add_resource(EntityPool::new(bullet_prefab, 1000).with_growth(100));
+/// ```
+pub struct EntityPool<T>
+where
+    T: Send + Sync + 'static,
+{
+    prefab: Handle<Prefab<T>>,
+    capacity: usize,
+    growth: usize,
+    spawned: usize,
+    free: Vec<Entity>,
+}
+
+impl<T> EntityPool<T>
+where
+    T: Send + Sync + 'static,
+{
+    /// Creates a pool of `capacity` entities from the prefab, which doesn't grow once they're
+    /// all active.
+    pub fn new(prefab: Handle<Prefab<T>>, capacity: usize) -> Self {
+        EntityPool {
+            prefab,
+            capacity,
+            growth: 0,
+            spawned: 0,
+            free: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Creates `growth` more entities whenever one is asked for while they're all active. Their
+    /// prefab is only added the next time the `PrefabLoaderSystem` runs.
+    pub fn with_growth(mut self, growth: usize) -> Self {
+        self.growth = growth;
+        self
+    }
+
+    /// Returns the number of entities the pool holds once filled.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of entities created by the pool.
+    pub fn spawned(&self) -> usize {
+        self.spawned
+    }
+
+    /// Returns the number of entities waiting to be reused.
+    pub fn available(&self) -> usize {
+        self.free.len()
+    }
+
+    /// Returns the number of entities taken out of the pool.
+    pub fn active(&self) -> usize {
+        self.spawned - self.free.len()
+    }
+}
+
+/// System data to take entities from an `EntityPool` and give them back.
+#[derive(SystemData)]
+pub struct EntityPoolData<'a, T>
+where
+    T: Send + Sync + 'static,
+{
+    entities: Entities<'a>,
+    pool: WriteExpect<'a, EntityPool<T>>,
+    prefabs: WriteStorage<'a, Handle<Prefab<T>>>,
+    inactive: WriteStorage<'a, Inactive>,
+    hidden: WriteStorage<'a, HiddenPropagate>,
+}
+
+impl<'a, T> EntityPoolData<'a, T>
+where
+    T: Send + Sync + 'static,
+{
+    /// Returns the pool.
+    pub fn pool(&self) -> &EntityPool<T> {
+        &self.pool
+    }
+
+    /// Creates entities until the pool holds its capacity.
+    pub fn fill(&mut self) {
+        while self.pool.spawned < self.pool.capacity {
+            self.spawn();
+        }
+    }
+
+    /// Takes an inactive entity out of the pool, growing it if needed, or returns `None` if
+    /// they're all active and the pool doesn't grow.
+    pub fn acquire(&mut self) -> Option<Entity> {
+        loop {
+            let entity = match self.pool.free.pop() {
+                Some(entity) => entity,
+                None if self.pool.growth > 0 => {
+                    self.pool.capacity += self.pool.growth;
+                    self.fill();
+                    continue;
+                }
+                None => return None,
+            };
+            // Entities deleted while in the pool are forgotten.
+            if !self.entities.is_alive(entity) {
+                self.pool.spawned -= 1;
+                continue;
+            }
+            self.inactive.remove(entity);
+            self.hidden.remove(entity);
+            return Some(entity);
+        }
+    }
+
+    /// Gives an entity taken with `acquire` back to the pool, deactivating it.
+    ///
+    /// Entities that are already inactive or weren't created by the pool are left alone.
+    pub fn release(&mut self, entity: Entity) {
+        let pooled = self
+            .prefabs
+            .get(entity)
+            .map_or(false, |handle| *handle == self.pool.prefab);
+        if !pooled || self.inactive.contains(entity) || !self.entities.is_alive(entity) {
+            return;
+        }
+        self.deactivate(entity);
+        self.pool.free.push(entity);
+    }
+
+    fn spawn(&mut self) {
+        let entity = self.entities.create();
+        if let Err(e) = self.prefabs.insert(entity, self.pool.prefab.clone()) {
+            error!("Failed to add prefab to pooled entity: {}", e);
+        }
+        self.deactivate(entity);
+        self.pool.spawned += 1;
+        self.pool.free.push(entity);
+    }
+
+    fn deactivate(&mut self, entity: Entity) {
+        self.inactive.insert(entity, Inactive).ok();
+        self.hidden.insert(entity, HiddenPropagate).ok();
+    }
+}
+
+/// System creating the entities of the `EntityPool<T>` resource, so they are ready before they
+/// are needed.
+///
+/// Add it before the `PrefabLoaderSystem<T>`, for the prefab to be added to the new entities on
+/// the same frame.
+pub struct EntityPoolSystem<T> {
+    _m: PhantomData<T>,
+}
+
+impl<T> Default for EntityPoolSystem<T> {
+    fn default() -> Self {
+        EntityPoolSystem { _m: PhantomData }
+    }
+}
+
+impl<'a, T> System<'a> for EntityPoolSystem<T>
+where
+    T: Send + Sync + 'static,
+{
+    type SystemData = EntityPoolData<'a, T>;
+
+    fn run(&mut self, mut data: Self::SystemData) {
+        data.fill();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rayon::ThreadPoolBuilder;
+
+    use amethyst_assets::{AssetStorage, Loader};
+    use amethyst_core::specs::prelude::{RunNow, World};
+
+    use super::*;
+
+    #[test]
+    fn entities_are_reused_and_the_pool_grows() {
+        let mut world = World::new();
+        let pool = Arc::new(ThreadPoolBuilder::default().build().unwrap());
+        world.add_resource(Loader::new(".", pool));
+        world.add_resource(AssetStorage::<Prefab<()>>::new());
+        let prefab = world.read_resource::<Loader>().load_from_data(
+            Prefab::new(),
+            (),
+            &world.read_resource::<AssetStorage<Prefab<()>>>(),
+        );
+        world.add_resource(EntityPool::new(prefab, 2).with_growth(1));
+        let mut system = EntityPoolSystem::<()>::default();
+        RunNow::setup(&mut system, &mut world.res);
+        system.run_now(&world.res);
+        world.maintain();
+
+        world.exec(|mut data: EntityPoolData<'_, ()>| {
+            assert_eq!((2, 2), (data.pool().spawned(), data.pool().available()));
+            let first = data.acquire().unwrap();
+            let second = data.acquire().unwrap();
+            assert!(!data.inactive.contains(first) && !data.hidden.contains(first));
+            // All active, so the pool grows by one.
+            let third = data.acquire().unwrap();
+            assert_eq!((3, 3), (data.pool().spawned(), data.pool().active()));
+
+            data.release(second);
+            assert!(data.inactive.contains(second) && data.hidden.contains(second));
+            assert_eq!(Some(second), data.acquire());
+            data.release(first);
+            data.release(first);
+            assert_eq!(1, data.pool().available());
+            assert_ne!(first, third);
+        });
+    }
+}
@@ -0,0 +1,485 @@
+//! Fog of war, for strategy games where teams only see around their units.
+//!
+//! The `FogOfWar` resource splits the map in a coarse grid of cells. On every frame the
+//! `FogOfWarSystem` finds the cells each team sees from its `VisionSource`s, the ones in the
+//! line of sight of the source when cells are blocked by the terrain or by `VisionBlocker`s.
+//! Cells seen once stay explored. AI and UI ask the resource what a team sees or has explored,
+//! and the `FogMaskSystem` shows the fog with a mask texture put on the `Material` of the
+//! entities with a `FogMask`, e.g. a quad over the map.
+
+use amethyst_assets::{AssetStorage, Loader};
+use amethyst_core::{
+    bundle::{Result as BundleResult, SystemBundle},
+    specs::prelude::{
+        Component, DenseVecStorage, DispatcherBuilder, Join, Read, ReadExpect, ReadStorage, System,
+        Write, WriteStorage,
+    },
+    GlobalTransform,
+};
+use amethyst_renderer::{Material, Texture, TextureData, TextureHandle, TextureMetadata};
+
+/// The plane of the map the grid of the `FogOfWar` lies on.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum FogPlane {
+    /// The `x` and `y` axes, for 2D games.
+    Xy,
+    /// The `x` and `z` axes, for 3D games with `y` up.
+    Xz,
+}
+
+/// How much of a cell a team knows.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Visibility {
+    /// The team never saw the cell.
+    Hidden,
+    /// The team saw the cell, but doesn't see it now.
+    Explored,
+    /// The team sees the cell.
+    Visible,
+}
+
+#[derive(Clone, Debug)]
+struct TeamFog {
+    visible: Vec<bool>,
+    explored: Vec<bool>,
+    /// Changes when the visibility of a cell changes, for the masks to be updated.
+    revision: u64,
+}
+
+/// Resource with the visibility of the cells of the map for every team, see the
+/// [module documentation](index.html).
+///
+/// Cells are counted from the corner of the map at `origin`, along the axes of the plane.
+#[derive(Clone, Debug)]
+pub struct FogOfWar {
+    width: u32,
+    height: u32,
+    cell_size: f32,
+    origin: [f32; 2],
+    plane: FogPlane,
+    terrain: Vec<bool>,
+    blocked: Vec<bool>,
+    teams: Vec<TeamFog>,
+    /// Alpha of the mask over explored cells.
+    explored_alpha: u8,
+}
+
+impl FogOfWar {
+    /// Creates the fog over a map of `width` by `height` cells, for that many teams.
+    pub fn new(width: u32, height: u32, cell_size: f32, teams: usize) -> Self {
+        let cells = (width * height) as usize;
+        FogOfWar {
+            width,
+            height,
+            cell_size,
+            origin: [0., 0.],
+            plane: FogPlane::Xz,
+            terrain: vec![false; cells],
+            blocked: vec![false; cells],
+            teams: vec![
+                TeamFog {
+                    visible: vec![false; cells],
+                    explored: vec![false; cells],
+                    revision: 0,
+                };
+                teams
+            ],
+            explored_alpha: 160,
+        }
+    }
+
+    /// Sets the position of the corner of the first cell.
+    pub fn with_origin(mut self, origin: [f32; 2]) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    /// Sets the plane of the grid, `Xz` by default.
+    pub fn with_plane(mut self, plane: FogPlane) -> Self {
+        self.plane = plane;
+        self
+    }
+
+    /// Sets how dark the mask is over explored cells, from `0` for not at all to `255` for as
+    /// dark as hidden cells.
+    pub fn with_explored_alpha(mut self, alpha: u8) -> Self {
+        self.explored_alpha = alpha;
+        self
+    }
+
+    /// Returns the width and height of the grid, in cells.
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Returns the number of teams.
+    pub fn teams(&self) -> usize {
+        self.teams.len()
+    }
+
+    /// Returns the cell at the position, if it's on the map.
+    pub fn cell(&self, position: [f32; 3]) -> Option<(u32, u32)> {
+        let (x, y) = match self.plane {
+            FogPlane::Xy => (position[0], position[1]),
+            FogPlane::Xz => (position[0], position[2]),
+        };
+        let x = ((x - self.origin[0]) / self.cell_size).floor();
+        let y = ((y - self.origin[1]) / self.cell_size).floor();
+        if x < 0. || y < 0. || x >= self.width as f32 || y >= self.height as f32 {
+            return None;
+        }
+        Some((x as u32, y as u32))
+    }
+
+    fn index(&self, (x, y): (u32, u32)) -> Option<usize> {
+        if x < self.width && y < self.height {
+            Some((y * self.width + x) as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Makes the terrain of the cell block the line of sight, e.g. for walls and cliffs.
+    pub fn set_blocking(&mut self, cell: (u32, u32), blocking: bool) {
+        if let Some(index) = self.index(cell) {
+            self.terrain[index] = blocking;
+        }
+    }
+
+    /// Returns whether the cell blocks the line of sight, by its terrain or a `VisionBlocker`.
+    pub fn is_blocking(&self, cell: (u32, u32)) -> bool {
+        self.index(cell)
+            .map_or(false, |index| self.terrain[index] || self.blocked[index])
+    }
+
+    /// Returns how much of the cell the team knows.
+    pub fn visibility(&self, team: usize, cell: (u32, u32)) -> Visibility {
+        match (self.teams.get(team), self.index(cell)) {
+            (Some(fog), Some(index)) if fog.visible[index] => Visibility::Visible,
+            (Some(fog), Some(index)) if fog.explored[index] => Visibility::Explored,
+            _ => Visibility::Hidden,
+        }
+    }
+
+    /// Returns whether the team sees the position, e.g. for the AI to only target the units it
+    /// sees or the UI to hide them.
+    pub fn is_visible(&self, team: usize, position: [f32; 3]) -> bool {
+        self.cell(position).map_or(false, |cell| {
+            self.visibility(team, cell) == Visibility::Visible
+        })
+    }
+
+    /// Returns whether the team saw the position once.
+    pub fn is_explored(&self, team: usize, position: [f32; 3]) -> bool {
+        self.cell(position).map_or(false, |cell| {
+            self.visibility(team, cell) != Visibility::Hidden
+        })
+    }
+
+    /// Returns the fraction of the map the team explored.
+    pub fn explored_fraction(&self, team: usize) -> f32 {
+        self.teams.get(team).map_or(0., |fog| {
+            let explored = fog.explored.iter().filter(|&&explored| explored).count();
+            explored as f32 / fog.explored.len().max(1) as f32
+        })
+    }
+
+    /// Makes the whole map explored for the team, e.g. for a revealed map.
+    pub fn explore_all(&mut self, team: usize) {
+        if let Some(fog) = self.teams.get_mut(team) {
+            fog.explored
+                .iter_mut()
+                .for_each(|explored| *explored = true);
+            fog.revision += 1;
+        }
+    }
+
+    /// Returns the mask of the team, black and opaque over hidden cells, translucent over
+    /// explored ones and transparent over visible ones, with a pixel per cell.
+    pub fn mask_data(&self, team: usize) -> TextureData {
+        let mut pixels = Vec::with_capacity((self.width * self.height * 4) as usize);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let alpha = match self.visibility(team, (x, y)) {
+                    Visibility::Hidden => 255,
+                    Visibility::Explored => self.explored_alpha,
+                    Visibility::Visible => 0,
+                };
+                pixels.extend_from_slice(&[0, 0, 0, alpha]);
+            }
+        }
+        TextureData::U8(
+            pixels,
+            TextureMetadata::unorm().with_size(self.width as u16, self.height as u16),
+        )
+    }
+
+    fn revision(&self, team: usize) -> Option<u64> {
+        self.teams.get(team).map(|fog| fog.revision)
+    }
+
+    /// Makes the cells in the radius of the cell visible for the team, in the line of sight if
+    /// asked.
+    fn reveal(&mut self, team: usize, (cx, cy): (u32, u32), radius: f32, line_of_sight: bool) {
+        let reach = (radius / self.cell_size).ceil() as i64;
+        let cells = radius / self.cell_size;
+        let (cx, cy) = (i64::from(cx), i64::from(cy));
+        for y in (cy - reach).max(0)..=(cy + reach).min(i64::from(self.height) - 1) {
+            for x in (cx - reach).max(0)..=(cx + reach).min(i64::from(self.width) - 1) {
+                let (dx, dy) = ((x - cx) as f32, (y - cy) as f32);
+                if dx * dx + dy * dy > cells * cells {
+                    continue;
+                }
+                if line_of_sight && !self.in_sight((cx, cy), (x, y)) {
+                    continue;
+                }
+                let index = (y * i64::from(self.width) + x) as usize;
+                self.teams[team].visible[index] = true;
+            }
+        }
+    }
+
+    /// Whether no cell between the two, both excluded, blocks the line of sight.
+    fn in_sight(&self, from: (i64, i64), to: (i64, i64)) -> bool {
+        // Bresenham's line.
+        let (dx, dy) = ((to.0 - from.0).abs(), -(to.1 - from.1).abs());
+        let (sx, sy) = ((to.0 - from.0).signum(), (to.1 - from.1).signum());
+        let (mut x, mut y, mut error) = (from.0, from.1, dx + dy);
+        loop {
+            if (x, y) == to {
+                return true;
+            }
+            if (x, y) != from && self.is_blocking((x as u32, y as u32)) {
+                return false;
+            }
+            let double = 2 * error;
+            if double >= dy {
+                error += dy;
+                x += sx;
+            }
+            if double <= dx {
+                error += dx;
+                y += sy;
+            }
+        }
+    }
+}
+
+/// Lets its team see the cells around the entity.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct VisionSource {
+    /// The team seeing.
+    pub team: usize,
+    /// How far the team sees, in units of the map.
+    pub radius: f32,
+    /// Whether the cells blocking the line of sight hide the ones behind them.
+    #[serde(default = "default_line_of_sight")]
+    pub line_of_sight: bool,
+}
+
+fn default_line_of_sight() -> bool {
+    true
+}
+
+impl VisionSource {
+    /// Creates a source for the team, seeing in the line of sight.
+    pub fn new(team: usize, radius: f32) -> Self {
+        VisionSource {
+            team,
+            radius,
+            line_of_sight: true,
+        }
+    }
+}
+
+impl Component for VisionSource {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Blocks the line of sight across the cells around the entity, e.g. for buildings.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct VisionBlocker {
+    /// The radius of the blocked area, `0` for only the cell of the entity.
+    pub radius: f32,
+}
+
+impl Component for VisionBlocker {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Shows the mask of the `FogOfWar` of the team as the texture of the `Material` of the entity.
+#[derive(Clone, Debug, Default)]
+pub struct FogMask {
+    /// The team whose fog is shown, usually the one of the player.
+    pub team: usize,
+    texture: Option<(TextureHandle, u64)>,
+}
+
+impl FogMask {
+    /// Creates a mask showing the fog of the team.
+    pub fn new(team: usize) -> Self {
+        FogMask {
+            team,
+            texture: None,
+        }
+    }
+}
+
+impl Component for FogMask {
+    type Storage = DenseVecStorage<Self>;
+}
+
+fn position(global: &GlobalTransform) -> [f32; 3] {
+    [global.0[(0, 3)], global.0[(1, 3)], global.0[(2, 3)]]
+}
+
+/// Updates the visible and explored cells of the `FogOfWar` from the `VisionSource`s and
+/// `VisionBlocker`s.
+#[derive(Default)]
+pub struct FogOfWarSystem;
+
+impl<'a> System<'a> for FogOfWarSystem {
+    type SystemData = (
+        Option<Write<'a, FogOfWar>>,
+        ReadStorage<'a, VisionSource>,
+        ReadStorage<'a, VisionBlocker>,
+        ReadStorage<'a, GlobalTransform>,
+    );
+
+    fn run(&mut self, (fog, sources, blockers, globals): Self::SystemData) {
+        let mut fog = match fog {
+            Some(fog) => fog,
+            None => return,
+        };
+        let fog = &mut *fog;
+
+        fog.blocked.iter_mut().for_each(|blocked| *blocked = false);
+        for (blocker, global) in (&blockers, &globals).join() {
+            let position = position(global);
+            let cells = (blocker.radius / fog.cell_size).ceil() as i64;
+            if let Some((cx, cy)) = fog.cell(position) {
+                let (cx, cy) = (i64::from(cx), i64::from(cy));
+                for y in cy - cells..=cy + cells {
+                    for x in cx - cells..=cx + cells {
+                        let (dx, dy) = ((x - cx) as f32, (y - cy) as f32);
+                        let inside = (dx * dx + dy * dy).sqrt() * fog.cell_size <= blocker.radius;
+                        if x < 0 || y < 0 || !inside {
+                            continue;
+                        }
+                        if let Some(index) = fog.index((x as u32, y as u32)) {
+                            fog.blocked[index] = true;
+                        }
+                    }
+                }
+            }
+        }
+
+        let previous = fog
+            .teams
+            .iter_mut()
+            .map(|team| {
+                let visible = team.visible.clone();
+                team.visible.iter_mut().for_each(|visible| *visible = false);
+                visible
+            })
+            .collect::<Vec<_>>();
+        for (source, global) in (&sources, &globals).join() {
+            if source.team >= fog.teams.len() {
+                warn!(
+                    "Vision source of a team without fog of war: {}",
+                    source.team
+                );
+                continue;
+            }
+            if let Some(cell) = fog.cell(position(global)) {
+                fog.reveal(source.team, cell, source.radius, source.line_of_sight);
+            }
+        }
+        for (team, previous) in fog.teams.iter_mut().zip(previous) {
+            if team.visible != previous {
+                team.revision += 1;
+            }
+            for (explored, &visible) in team.explored.iter_mut().zip(&team.visible) {
+                *explored |= visible;
+            }
+        }
+    }
+}
+
+/// Puts the mask of the `FogOfWar` on the `Material`s of the entities with a `FogMask`, again
+/// when the visibility of their team changed.
+#[derive(Default)]
+pub struct FogMaskSystem;
+
+impl<'a> System<'a> for FogMaskSystem {
+    type SystemData = (
+        Option<Read<'a, FogOfWar>>,
+        ReadExpect<'a, Loader>,
+        Read<'a, AssetStorage<Texture>>,
+        WriteStorage<'a, FogMask>,
+        WriteStorage<'a, Material>,
+    );
+
+    fn run(&mut self, (fog, loader, textures, mut masks, mut materials): Self::SystemData) {
+        let fog = match fog {
+            Some(fog) => fog,
+            None => return,
+        };
+        for (mask, material) in (&mut masks, &mut materials).join() {
+            let revision = match fog.revision(mask.team) {
+                Some(revision) => revision,
+                None => continue,
+            };
+            if mask
+                .texture
+                .as_ref()
+                .map_or(true, |&(_, old)| old != revision)
+            {
+                let handle = loader.load_from_data(fog.mask_data(mask.team), (), &textures);
+                mask.texture = Some((handle, revision));
+            }
+            if let Some((ref handle, _)) = mask.texture {
+                if material.albedo != *handle {
+                    material.albedo = handle.clone();
+                }
+            }
+        }
+    }
+}
+
+/// Adds the `FogOfWarSystem`, and the `FogMaskSystem` depending on it.
+///
+/// The fog is computed from the `GlobalTransform`s, so the bundle is added after the
+/// `TransformBundle`.
+#[derive(Default)]
+pub struct FogOfWarBundle;
+
+impl<'a, 'b> SystemBundle<'a, 'b> for FogOfWarBundle {
+    fn build(self, builder: &mut DispatcherBuilder<'a, 'b>) -> BundleResult<()> {
+        builder.add(FogOfWarSystem, "fog_of_war_system", &[]);
+        builder.add(FogMaskSystem, "fog_mask_system", &["fog_of_war_system"]);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walls_hide_cells_behind_them_and_seen_cells_stay_explored() {
+        let mut fog = FogOfWar::new(10, 10, 1., 1);
+        fog.set_blocking((5, 2), true);
+        fog.reveal(0, (2, 2), 5., true);
+        fog.teams[0].explored = fog.teams[0].visible.clone();
+        assert_eq!(Visibility::Visible, fog.visibility(0, (4, 2)));
+        assert_eq!(Visibility::Visible, fog.visibility(0, (5, 2)));
+        assert_eq!(Visibility::Hidden, fog.visibility(0, (6, 2)));
+        assert_eq!(Visibility::Visible, fog.visibility(0, (6, 3)));
+        assert_eq!(Visibility::Hidden, fog.visibility(0, (8, 2)));
+
+        fog.teams[0].visible.iter_mut().for_each(|v| *v = false);
+        assert_eq!(Visibility::Explored, fog.visibility(0, (4, 2)));
+        assert!(fog.is_explored(0, [4.5, 0., 2.5]) && !fog.is_visible(0, [4.5, 0., 2.5]));
+    }
+}
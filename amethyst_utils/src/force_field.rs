@@ -0,0 +1,396 @@
+//! Force fields pushing the bodies and particles inside them: wind volumes, explosions and
+//! water.
+//!
+//! A `ForceField` is a shape around its entity with a kind of force. On every frame the
+//! `ForceFieldSystem` adds up the forces of the fields on the `ForceBody`s inside them. The
+//! engine has no physics, so a body only holds the force and impulse for the physics engine of
+//! the game to apply to it, and the velocity the game gives it back. `FieldParticle`s, like the
+//! leaves blown by the wind, are light enough to be moved by the system itself.
+//!
+//! All three components can be defined in prefabs.
+
+use amethyst_assets::{PrefabData, PrefabError};
+use amethyst_core::{
+    nalgebra::{Matrix4, Point3, Vector3},
+    specs::prelude::{
+        Component, DenseVecStorage, Entity, Join, Read, ReadStorage, System, WriteStorage,
+    },
+    timing::Time,
+    GlobalTransform, Transform,
+};
+
+use crate::influence::Falloff;
+
+/// The volume of a `ForceField`, in the local space of its entity.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum FieldShape {
+    /// A sphere around the entity.
+    Sphere {
+        /// The radius of the sphere.
+        radius: f32,
+    },
+    /// A box around the entity.
+    Box {
+        /// Half the size of the box along each axis.
+        half_extents: [f32; 3],
+    },
+    /// The whole scene.
+    Everywhere,
+}
+
+impl FieldShape {
+    /// Returns whether the point, in the local space of the field, is inside.
+    pub fn contains(&self, point: &Point3<f32>) -> bool {
+        match *self {
+            FieldShape::Sphere { radius } => point.coords.norm() <= radius,
+            FieldShape::Box { half_extents } => {
+                point.x.abs() <= half_extents[0]
+                    && point.y.abs() <= half_extents[1]
+                    && point.z.abs() <= half_extents[2]
+            }
+            FieldShape::Everywhere => true,
+        }
+    }
+
+    /// Returns how far from the center the point is, as a fraction of the distance to the
+    /// border along the same direction.
+    fn fraction(&self, point: &Point3<f32>) -> f32 {
+        match *self {
+            FieldShape::Sphere { radius } => point.coords.norm() / radius.max(std::f32::EPSILON),
+            FieldShape::Box { half_extents } => (0..3)
+                .map(|i| point[i].abs() / half_extents[i].max(std::f32::EPSILON))
+                .fold(0., f32::max),
+            FieldShape::Everywhere => 0.,
+        }
+    }
+}
+
+/// The force of a `ForceField`.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum FieldKind {
+    /// Wind blowing at the velocity, in the local space of the field, dragging the bodies until
+    /// they move with it.
+    Wind {
+        /// The velocity of the wind.
+        velocity: [f32; 3],
+        /// The force per unit of speed between the wind and the body.
+        drag: f32,
+    },
+    /// A push away from the entity, or towards it for a negative strength, like a blast,
+    /// falling off up to the border of the field.
+    Radial {
+        /// The force at the center.
+        strength: f32,
+        /// How the force falls off.
+        falloff: Falloff,
+        /// Whether the force is given once as an impulse, for explosions, instead of on every
+        /// frame.
+        impulse: bool,
+    },
+    /// A fluid below the plane `y = surface` of the field, lifting the bodies by the weight of
+    /// the fluid they displace, like water.
+    Buoyancy {
+        /// The height of the surface, in the local space of the field.
+        surface: f32,
+        /// The mass of the fluid per unit of volume.
+        density: f32,
+        /// The acceleration of gravity, pulling along the negative `y` axis of the field.
+        gravity: f32,
+        /// The force per unit of speed slowing the submerged bodies down.
+        drag: f32,
+    },
+}
+
+/// A volume pushing the `ForceBody`s and `FieldParticle`s inside it.
+#[derive(Clone, Debug, Deserialize, PartialEq, PrefabData, Serialize)]
+#[prefab(Component)]
+pub struct ForceField {
+    /// The volume of the field.
+    pub shape: FieldShape,
+    /// The force of the field.
+    pub kind: FieldKind,
+    /// Whether the field pushes, e.g. to turn a fan on and off.
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(skip)]
+    spent: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl ForceField {
+    /// Creates an enabled field.
+    pub fn new(shape: FieldShape, kind: FieldKind) -> Self {
+        ForceField {
+            shape,
+            kind,
+            enabled: true,
+            spent: false,
+        }
+    }
+
+    /// Returns the force on a body, or the impulse for `Radial` fields with `impulse`, or `None`
+    /// if the body isn't inside. The position and velocity are in world space, the field being
+    /// placed by its global matrix.
+    pub fn force_on(
+        &self,
+        field: &Matrix4<f32>,
+        position: &Point3<f32>,
+        velocity: &Vector3<f32>,
+        body: &ForceBody,
+    ) -> Option<Vector3<f32>> {
+        let local = field.try_inverse()?.transform_point(position);
+        if !self.shape.contains(&local) {
+            return None;
+        }
+        let force = match self.kind {
+            FieldKind::Wind {
+                velocity: wind,
+                drag,
+            } => {
+                let wind = field.transform_vector(&Vector3::from(wind));
+                (wind - velocity) * drag
+            }
+            FieldKind::Radial {
+                strength, falloff, ..
+            } => {
+                let center = field.transform_point(&Point3::origin());
+                let direction = (position - center)
+                    .try_normalize(std::f32::EPSILON)
+                    .unwrap_or_else(Vector3::zeros);
+                direction * strength * falloff.factor(self.shape.fraction(&local))
+            }
+            FieldKind::Buoyancy {
+                surface,
+                density,
+                gravity,
+                drag,
+            } => {
+                // The part of the body below the surface, the body spanning its height around
+                // its position.
+                let height = body.height.max(std::f32::EPSILON);
+                let depth = surface - (local.y - height / 2.);
+                let submerged = (depth / height).max(0.).min(1.);
+                let up = field
+                    .transform_vector(&Vector3::y())
+                    .try_normalize(std::f32::EPSILON)
+                    .unwrap_or_else(Vector3::y);
+                up * density * body.volume * submerged * gravity - velocity * drag * submerged
+            }
+        };
+        Some(force)
+    }
+
+    fn is_impulse(&self) -> bool {
+        match self.kind {
+            FieldKind::Radial { impulse, .. } => impulse,
+            _ => false,
+        }
+    }
+}
+
+impl Component for ForceField {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// A body pushed by the `ForceField`s it's in.
+///
+/// The `ForceFieldSystem` sets the `force` and `impulse` on every frame; the game applies them
+/// to the physics body of the entity and keeps the `velocity` up to date from it.
+#[derive(Clone, Debug, Deserialize, PartialEq, PrefabData, Serialize)]
+#[prefab(Component)]
+#[serde(default)]
+pub struct ForceBody {
+    /// The volume of the body, for buoyancy.
+    pub volume: f32,
+    /// The height of the body, for how much of it is submerged.
+    pub height: f32,
+    /// The velocity of the body, in world space.
+    pub velocity: [f32; 3],
+    /// The force of the fields on the body, for this frame.
+    #[serde(skip)]
+    pub force: Vector3<f32>,
+    /// The impulse of the fields on the body, for this frame.
+    #[serde(skip)]
+    pub impulse: Vector3<f32>,
+}
+
+impl Default for ForceBody {
+    fn default() -> Self {
+        ForceBody {
+            volume: 1.,
+            height: 1.,
+            velocity: [0.; 3],
+            force: Vector3::zeros(),
+            impulse: Vector3::zeros(),
+        }
+    }
+}
+
+impl Component for ForceBody {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// A particle moved by the `ForceField`s it's in, e.g. a leaf or a spark.
+///
+/// The `ForceFieldSystem` moves its `Transform` by its velocity, changed by the forces of the
+/// fields and slowed by the drag. Particles are moved by their local transform, so they shouldn't
+/// have a parent.
+#[derive(Clone, Debug, Deserialize, PartialEq, PrefabData, Serialize)]
+#[prefab(Component)]
+#[serde(default)]
+pub struct FieldParticle {
+    /// The mass of the particle.
+    pub mass: f32,
+    /// The fraction of its velocity the particle loses per second.
+    pub drag: f32,
+    /// The velocity of the particle.
+    pub velocity: [f32; 3],
+}
+
+impl Default for FieldParticle {
+    fn default() -> Self {
+        FieldParticle {
+            mass: 0.1,
+            drag: 0.5,
+            velocity: [0.; 3],
+        }
+    }
+}
+
+impl Component for FieldParticle {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// The body the particles are, for the fields.
+fn particle_body(particle: &FieldParticle) -> ForceBody {
+    ForceBody {
+        volume: 0.,
+        height: 0.,
+        velocity: particle.velocity,
+        force: Vector3::zeros(),
+        impulse: Vector3::zeros(),
+    }
+}
+
+fn position(global: &GlobalTransform) -> Point3<f32> {
+    Point3::new(global.0[(0, 3)], global.0[(1, 3)], global.0[(2, 3)])
+}
+
+/// Sets the forces of the `ForceField`s on the `ForceBody`s, and moves the `FieldParticle`s.
+#[derive(Default)]
+pub struct ForceFieldSystem;
+
+impl<'a> System<'a> for ForceFieldSystem {
+    type SystemData = (
+        Read<'a, Time>,
+        WriteStorage<'a, ForceField>,
+        WriteStorage<'a, ForceBody>,
+        WriteStorage<'a, FieldParticle>,
+        ReadStorage<'a, GlobalTransform>,
+        WriteStorage<'a, Transform>,
+    );
+
+    fn run(
+        &mut self,
+        (time, mut fields, mut bodies, mut particles, globals, mut transforms): Self::SystemData,
+    ) {
+        let fields_now = (&fields, &globals)
+            .join()
+            .filter(|(field, _)| field.enabled && !field.spent)
+            .map(|(field, global)| (field.clone(), global.0))
+            .collect::<Vec<_>>();
+
+        for (body, global) in (&mut bodies, &globals).join() {
+            body.force = Vector3::zeros();
+            body.impulse = Vector3::zeros();
+            let position = position(global);
+            let velocity = Vector3::from(body.velocity);
+            for (field, matrix) in &fields_now {
+                if let Some(force) = field.force_on(matrix, &position, &velocity, body) {
+                    if field.is_impulse() {
+                        body.impulse += force;
+                    } else {
+                        body.force += force;
+                    }
+                }
+            }
+        }
+
+        let seconds = time.delta_seconds();
+        for (particle, transform) in (&mut particles, &mut transforms).join() {
+            let position = Point3::from(*transform.translation());
+            let mut velocity = Vector3::from(particle.velocity);
+            let body = particle_body(particle);
+            let mass = particle.mass.max(std::f32::EPSILON);
+            for (field, matrix) in &fields_now {
+                if let Some(force) = field.force_on(matrix, &position, &velocity, &body) {
+                    if field.is_impulse() {
+                        velocity += force / mass;
+                    } else {
+                        velocity += force / mass * seconds;
+                    }
+                }
+            }
+            velocity *= (1. - particle.drag.max(0.).min(1.)).powf(seconds);
+            particle.velocity = velocity.into();
+            *transform.translation_mut() += velocity * seconds;
+        }
+
+        for field in (&mut fields).join() {
+            if field.enabled && field.is_impulse() {
+                field.spent = true;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use amethyst_core::nalgebra::Translation3;
+
+    #[test]
+    fn buoyancy_lifts_by_submerged_volume_and_blasts_push_away() {
+        let water = ForceField::new(
+            FieldShape::Box {
+                half_extents: [10., 10., 10.],
+            },
+            FieldKind::Buoyancy {
+                surface: 0.,
+                density: 2.,
+                gravity: 10.,
+                drag: 0.,
+            },
+        );
+        let body = ForceBody::default();
+        let at_rest = Vector3::zeros();
+        let matrix = Matrix4::identity();
+        // Half under the surface, then all of it.
+        let half = water.force_on(&matrix, &Point3::origin(), &at_rest, &body);
+        assert_eq!(Some(Vector3::new(0., 10., 0.)), half);
+        let deep = water.force_on(&matrix, &Point3::new(0., -3., 0.), &at_rest, &body);
+        assert_eq!(Some(Vector3::new(0., 20., 0.)), deep);
+        assert_eq!(
+            None,
+            water.force_on(&matrix, &Point3::new(0., 11., 0.), &at_rest, &body)
+        );
+
+        let blast = ForceField::new(
+            FieldShape::Sphere { radius: 4. },
+            FieldKind::Radial {
+                strength: 8.,
+                falloff: Falloff::Linear,
+                impulse: true,
+            },
+        );
+        let matrix = Translation3::new(1., 0., 0.).to_homogeneous();
+        let push = blast
+            .force_on(&matrix, &Point3::new(3., 0., 0.), &at_rest, &body)
+            .unwrap();
+        assert!((push - Vector3::new(4., 0., 0.)).norm() < 1e-4);
+    }
+}
@@ -0,0 +1,507 @@
+//! Hitboxes and hurtboxes for melee attacks and other hits of action games.
+//!
+//! A `Hitbox` is a shape dealing hits, like the blade of a sword, and a `Hurtbox` a shape taking
+//! them, like the head of a character. Both are attached to an entity with a `GlobalTransform`,
+//! which can be a bone of a skinned model to follow the animations, and belong to an owner, the
+//! attacker or victim, which is the entity itself by default.
+//!
+//! An attack `start`s the hitbox, which is then active during its `HitWindow`s, e.g. the frames
+//! of a swing. The `HitSystem` checks the active hitboxes against the hurtboxes and sends a
+//! `HitEvent` for every victim hit, at most once per attack, on the part with the highest damage
+//! multiplier. It advances the attacks by `Time::fixed_seconds`, so it's meant to be dispatched
+//! from `State::fixed_update`, like the rest of the gameplay driven at a fixed rate.
+
+use amethyst_assets::{PrefabData, PrefabError};
+use amethyst_core::{
+    nalgebra::{Matrix4, Point3, Vector3},
+    shrev::EventChannel,
+    specs::prelude::{
+        Component, DenseVecStorage, Entities, Entity, Join, Read, ReadStorage, System, Write,
+        WriteStorage,
+    },
+    timing::Time,
+    GlobalTransform,
+};
+
+/// The shape of a `Hitbox` or `Hurtbox`, centered on its offset.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum HitShape {
+    /// A sphere.
+    Sphere {
+        /// The radius of the sphere.
+        radius: f32,
+    },
+    /// A capsule along the `y` axis, e.g. for limbs and blades.
+    Capsule {
+        /// The radius of the capsule.
+        radius: f32,
+        /// Half the distance between the centers of the ends of the capsule.
+        half_height: f32,
+    },
+}
+
+impl HitShape {
+    /// Returns the radius of the shape.
+    pub fn radius(&self) -> f32 {
+        match *self {
+            HitShape::Sphere { radius } | HitShape::Capsule { radius, .. } => radius,
+        }
+    }
+
+    /// Returns the segment at the core of the shape, in world space.
+    fn segment(&self, matrix: &Matrix4<f32>, offset: &[f32; 3]) -> (Point3<f32>, Point3<f32>) {
+        let half_height = match *self {
+            HitShape::Sphere { .. } => 0.,
+            HitShape::Capsule { half_height, .. } => half_height,
+        };
+        let center = Point3::from(Vector3::from(*offset));
+        let axis = Vector3::y() * half_height;
+        (
+            matrix.transform_point(&(center - axis)),
+            matrix.transform_point(&(center + axis)),
+        )
+    }
+}
+
+/// A window of time during which a `Hitbox` is active, in seconds since the start of the attack.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct HitWindow {
+    /// When the window opens.
+    pub start: f32,
+    /// When the window closes.
+    pub end: f32,
+}
+
+/// A shape dealing hits during attacks, see the [module documentation](index.html).
+#[derive(Clone, Debug, Deserialize, PartialEq, PrefabData, Serialize)]
+#[prefab(Component)]
+#[serde(default)]
+pub struct Hitbox {
+    /// The shape.
+    pub shape: HitShape,
+    /// The center of the shape, in the local space of the entity.
+    pub offset: [f32; 3],
+    /// The damage of a hit, before the multiplier of the hurtbox.
+    pub damage: f32,
+    /// The strength of the knockback of a hit, pushing the victim away from the hitbox.
+    pub knockback: f32,
+    /// The windows of the attack during which the hitbox is active. Without any, the hitbox is
+    /// active from the start of the attack until it's stopped.
+    pub windows: Vec<HitWindow>,
+    /// The team of the hitbox, which doesn't hit the hurtboxes of the same team.
+    pub team: Option<u32>,
+    /// The attacker, the entity of the hitbox when `None`.
+    #[serde(skip)]
+    pub owner: Option<Entity>,
+    #[serde(skip)]
+    time: Option<f32>,
+    #[serde(skip)]
+    hit: Vec<Entity>,
+}
+
+impl Default for Hitbox {
+    fn default() -> Self {
+        Hitbox {
+            shape: HitShape::Sphere { radius: 0.5 },
+            offset: [0.; 3],
+            damage: 1.,
+            knockback: 0.,
+            windows: Vec::new(),
+            team: None,
+            owner: None,
+            time: None,
+            hit: Vec::new(),
+        }
+    }
+}
+
+impl Hitbox {
+    /// Creates a hitbox of the shape.
+    pub fn new(shape: HitShape) -> Self {
+        Hitbox {
+            shape,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the center of the shape, in the local space of the entity.
+    pub fn with_offset(mut self, offset: [f32; 3]) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Sets the damage of a hit.
+    pub fn with_damage(mut self, damage: f32) -> Self {
+        self.damage = damage;
+        self
+    }
+
+    /// Sets the strength of the knockback of a hit.
+    pub fn with_knockback(mut self, knockback: f32) -> Self {
+        self.knockback = knockback;
+        self
+    }
+
+    /// Adds a window of the attack during which the hitbox is active.
+    pub fn with_window(mut self, start: f32, end: f32) -> Self {
+        self.windows.push(HitWindow { start, end });
+        self
+    }
+
+    /// Sets the team of the hitbox.
+    pub fn with_team(mut self, team: u32) -> Self {
+        self.team = Some(team);
+        self
+    }
+
+    /// Sets the attacker.
+    pub fn with_owner(mut self, owner: Entity) -> Self {
+        self.owner = Some(owner);
+        self
+    }
+
+    /// Starts an attack, which can hit again the victims of the previous one.
+    pub fn start(&mut self) {
+        self.time = Some(0.);
+        self.hit.clear();
+    }
+
+    /// Stops the attack.
+    pub fn stop(&mut self) {
+        self.time = None;
+    }
+
+    /// Returns the seconds since the start of the attack, if there is one.
+    pub fn time(&self) -> Option<f32> {
+        self.time
+    }
+
+    /// Returns whether the hitbox deals hits now.
+    pub fn is_active(&self) -> bool {
+        self.time.map_or(false, |time| {
+            self.windows.is_empty()
+                || self
+                    .windows
+                    .iter()
+                    .any(|window| time >= window.start && time < window.end)
+        })
+    }
+
+    /// Returns the victims hit during the attack.
+    pub fn victims(&self) -> &[Entity] {
+        &self.hit
+    }
+
+    /// Returns whether the hitbox is active, then advances the attack by the seconds, ending it
+    /// after its last window.
+    fn advance(&mut self, seconds: f32) -> bool {
+        let active = self.is_active();
+        if let Some(time) = self.time {
+            let end = self
+                .windows
+                .iter()
+                .map(|window| window.end)
+                .fold(std::f32::NEG_INFINITY, f32::max);
+            self.time = if !self.windows.is_empty() && time + seconds >= end {
+                None
+            } else {
+                Some(time + seconds)
+            };
+        }
+        active
+    }
+}
+
+impl Component for Hitbox {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// A shape taking hits, see the [module documentation](index.html).
+#[derive(Clone, Debug, Deserialize, PartialEq, PrefabData, Serialize)]
+#[prefab(Component)]
+#[serde(default)]
+pub struct Hurtbox {
+    /// The shape.
+    pub shape: HitShape,
+    /// The center of the shape, in the local space of the entity.
+    pub offset: [f32; 3],
+    /// The name of the part, e.g. `"head"`.
+    pub part: String,
+    /// The factor of the damage of the hits on the part.
+    pub multiplier: f32,
+    /// Whether the hurtbox takes hits, e.g. `false` during invincibility frames.
+    pub enabled: bool,
+    /// The team of the hurtbox, which doesn't take hits from the hitboxes of the same team.
+    pub team: Option<u32>,
+    /// The victim, the entity of the hurtbox when `None`.
+    #[serde(skip)]
+    pub owner: Option<Entity>,
+}
+
+impl Default for Hurtbox {
+    fn default() -> Self {
+        Hurtbox {
+            shape: HitShape::Sphere { radius: 0.5 },
+            offset: [0.; 3],
+            part: String::new(),
+            multiplier: 1.,
+            enabled: true,
+            team: None,
+            owner: None,
+        }
+    }
+}
+
+impl Hurtbox {
+    /// Creates a hurtbox of the shape for the part.
+    pub fn new<S: Into<String>>(shape: HitShape, part: S) -> Self {
+        Hurtbox {
+            shape,
+            part: part.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Sets the center of the shape, in the local space of the entity.
+    pub fn with_offset(mut self, offset: [f32; 3]) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Sets the factor of the damage of the hits on the part.
+    pub fn with_multiplier(mut self, multiplier: f32) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Sets the team of the hurtbox.
+    pub fn with_team(mut self, team: u32) -> Self {
+        self.team = Some(team);
+        self
+    }
+
+    /// Sets the victim.
+    pub fn with_owner(mut self, owner: Entity) -> Self {
+        self.owner = Some(owner);
+        self
+    }
+}
+
+impl Component for Hurtbox {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Event sent by the `HitSystem` on an `EventChannel<HitEvent>` when a hitbox hits a victim.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HitEvent {
+    /// The owner of the hitbox.
+    pub attacker: Entity,
+    /// The owner of the hurtbox.
+    pub victim: Entity,
+    /// The entity of the hitbox.
+    pub hitbox: Entity,
+    /// The entity of the hurtbox.
+    pub hurtbox: Entity,
+    /// The part hit.
+    pub part: String,
+    /// The damage of the hitbox, times the multiplier of the part.
+    pub damage: f32,
+    /// Where the shapes touch, in world space.
+    pub point: Point3<f32>,
+    /// The knockback on the victim, in world space.
+    pub knockback: Vector3<f32>,
+}
+
+/// Advances the attacks of the `Hitbox`es and sends `HitEvent`s for the `Hurtbox`es they hit.
+#[derive(Default)]
+pub struct HitSystem {
+    events: Vec<HitEvent>,
+}
+
+impl<'a> System<'a> for HitSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, Time>,
+        ReadStorage<'a, GlobalTransform>,
+        WriteStorage<'a, Hitbox>,
+        ReadStorage<'a, Hurtbox>,
+        Write<'a, EventChannel<HitEvent>>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, time, globals, mut hitboxes, hurtboxes, mut channel): Self::SystemData,
+    ) {
+        let targets = (&*entities, &globals, &hurtboxes)
+            .join()
+            .filter(|(_, _, hurtbox)| hurtbox.enabled)
+            .map(|(entity, global, hurtbox)| {
+                let segment = hurtbox.shape.segment(&global.0, &hurtbox.offset);
+                (entity, hurtbox.owner.unwrap_or(entity), segment, hurtbox)
+            })
+            .collect::<Vec<_>>();
+
+        for (entity, global, hitbox) in (&*entities, &globals, &mut hitboxes).join() {
+            if !hitbox.advance(time.fixed_seconds()) {
+                continue;
+            }
+            let attacker = hitbox.owner.unwrap_or(entity);
+            let (start, end) = hitbox.shape.segment(&global.0, &hitbox.offset);
+            let first = self.events.len();
+            for &(hurtbox_entity, victim, (hurt_start, hurt_end), hurtbox) in &targets {
+                let allies = hitbox.team.is_some() && hitbox.team == hurtbox.team;
+                if victim == attacker || allies || hitbox.hit.contains(&victim) {
+                    continue;
+                }
+                let (a, b) = closest_points(&start, &end, &hurt_start, &hurt_end);
+                let reach = hitbox.shape.radius() + hurtbox.shape.radius();
+                if (b - a).norm() > reach {
+                    continue;
+                }
+                let point = a + (b - a) * (hitbox.shape.radius() / reach.max(std::f32::EPSILON));
+                let direction = (b - a)
+                    .try_normalize(std::f32::EPSILON)
+                    .unwrap_or_else(Vector3::zeros);
+                let event = HitEvent {
+                    attacker,
+                    victim,
+                    hitbox: entity,
+                    hurtbox: hurtbox_entity,
+                    part: hurtbox.part.clone(),
+                    damage: hitbox.damage * hurtbox.multiplier,
+                    point,
+                    knockback: direction * hitbox.knockback,
+                };
+                // A victim is hit once, on the part taking the most damage.
+                match self.events[first..]
+                    .iter_mut()
+                    .find(|other| other.victim == victim)
+                {
+                    Some(other) => {
+                        if event.damage > other.damage {
+                            *other = event;
+                        }
+                    }
+                    None => self.events.push(event),
+                }
+            }
+            hitbox
+                .hit
+                .extend(self.events[first..].iter().map(|event| event.victim));
+        }
+        channel.iter_write(self.events.drain(..));
+    }
+}
+
+/// Returns the closest points between the segments `p1`-`q1` and `p2`-`q2`.
+fn closest_points(
+    p1: &Point3<f32>,
+    q1: &Point3<f32>,
+    p2: &Point3<f32>,
+    q2: &Point3<f32>,
+) -> (Point3<f32>, Point3<f32>) {
+    let (d1, d2, r) = (q1 - p1, q2 - p2, p1 - p2);
+    let (a, e, f) = (d1.dot(&d1), d2.dot(&d2), d2.dot(&r));
+    let clamp = |x: f32| x.max(0.).min(1.);
+    let (s, t) = if a <= std::f32::EPSILON && e <= std::f32::EPSILON {
+        (0., 0.)
+    } else if a <= std::f32::EPSILON {
+        (0., clamp(f / e))
+    } else {
+        let c = d1.dot(&r);
+        if e <= std::f32::EPSILON {
+            (clamp(-c / a), 0.)
+        } else {
+            let b = d1.dot(&d2);
+            let denominator = a * e - b * b;
+            let s = if denominator > std::f32::EPSILON {
+                clamp((b * f - c * e) / denominator)
+            } else {
+                0.
+            };
+            let t = (b * s + f) / e;
+            if t < 0. {
+                (clamp(-c / a), 0.)
+            } else if t > 1. {
+                (clamp((b - c) / a), 1.)
+            } else {
+                (s, t)
+            }
+        }
+    };
+    (p1 + d1 * s, p2 + d2 * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use amethyst_core::{
+        nalgebra::Translation3,
+        specs::prelude::{Builder, RunNow, World},
+    };
+
+    #[test]
+    fn hitboxes_hit_each_victim_once_on_the_best_part() {
+        let mut world = World::new();
+        world.register::<GlobalTransform>();
+        world.register::<Hitbox>();
+        world.register::<Hurtbox>();
+        let mut time = Time::default();
+        time.set_fixed_seconds(0.1);
+        world.add_resource(time);
+        world.add_resource(EventChannel::<HitEvent>::new());
+        let mut reader = world
+            .write_resource::<EventChannel<HitEvent>>()
+            .register_reader();
+
+        let at = |x: f32| GlobalTransform(Translation3::new(x, 0., 0.).to_homogeneous());
+        let sword = HitShape::Capsule {
+            radius: 0.1,
+            half_height: 1.,
+        };
+        let attacker = world
+            .create_entity()
+            .with(at(0.))
+            .with(Hitbox::new(sword).with_damage(10.).with_window(0.1, 0.3))
+            .build();
+        let victim = world
+            .create_entity()
+            .with(at(0.5))
+            .with(Hurtbox::new(HitShape::Sphere { radius: 0.5 }, "body"))
+            .build();
+        world
+            .create_entity()
+            .with(at(0.5))
+            .with(
+                Hurtbox::new(HitShape::Sphere { radius: 0.5 }, "head")
+                    .with_offset([0., 0.8, 0.])
+                    .with_multiplier(2.)
+                    .with_owner(victim),
+            )
+            .build();
+
+        world
+            .write_storage::<Hitbox>()
+            .get_mut(attacker)
+            .unwrap()
+            .start();
+        let mut events = Vec::new();
+        for _ in 0..5 {
+            HitSystem::default().run_now(&world.res);
+            let channel = world.read_resource::<EventChannel<HitEvent>>();
+            events.extend(channel.read(&mut reader).cloned());
+        }
+
+        assert_eq!(1, events.len());
+        assert_eq!(victim, events[0].victim);
+        assert_eq!("head", events[0].part);
+        assert!((events[0].damage - 20.).abs() < 1e-4);
+        assert!(world
+            .read_storage::<Hitbox>()
+            .get(attacker)
+            .unwrap()
+            .time()
+            .is_none());
+    }
+}
@@ -0,0 +1,580 @@
+//! Influence maps, grids of values telling the AI how much of something there is around the
+//! map, like the threat of the enemy units or the resources left.
+//!
+//! The `InfluenceMaps` resource holds named layers. On every frame the `InfluenceSystem` fades
+//! the influence of each layer with its decay, puts the influence of the `InfluenceEmitter`s on
+//! it and spreads it with its blur. An emitter keeps the cells around it at least at its
+//! strength, or at most for negative strengths, so influence lingers where units went and fades
+//! over time.
+//!
+//! Layers are combined with arithmetic, e.g. the threat of the enemies minus the one of the
+//! allies to find the frontline, and the best cells are found by scoring them. The operations on
+//! whole grids run in parallel.
+
+use std::{cmp::Ordering, collections::HashMap};
+
+use amethyst_core::{
+    specs::prelude::{Component, DenseVecStorage, Join, Read, ReadStorage, System, Write},
+    timing::Time,
+    GlobalTransform,
+};
+use rayon::prelude::*;
+
+use crate::fog_of_war::FogPlane;
+
+/// How the influence of an emission falls off with the distance.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Falloff {
+    /// As strong up to the radius.
+    Constant,
+    /// Down to nothing at the radius, linearly.
+    Linear,
+    /// Down to nothing at the radius, quickly at first.
+    Quadratic,
+}
+
+impl Falloff {
+    /// Returns the factor at the distance, as a fraction of the radius.
+    pub fn factor(self, fraction: f32) -> f32 {
+        if fraction > 1. {
+            return 0.;
+        }
+        match self {
+            Falloff::Constant => 1.,
+            Falloff::Linear => 1. - fraction,
+            Falloff::Quadratic => (1. - fraction) * (1. - fraction),
+        }
+    }
+}
+
+/// A grid of values laid over the map.
+///
+/// Cells are counted from the corner of the map at `origin`, along the axes of the plane, like
+/// the cells of the `FogOfWar`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InfluenceMap {
+    width: u32,
+    height: u32,
+    cell_size: f32,
+    origin: [f32; 2],
+    plane: FogPlane,
+    values: Vec<f32>,
+}
+
+impl InfluenceMap {
+    /// Creates a map of `width` by `height` cells at 0.
+    pub fn new(width: u32, height: u32, cell_size: f32) -> Self {
+        InfluenceMap {
+            width,
+            height,
+            cell_size,
+            origin: [0., 0.],
+            plane: FogPlane::Xz,
+            values: vec![0.; (width * height) as usize],
+        }
+    }
+
+    /// Creates a map of the same size at 0, e.g. for the result of a combination.
+    pub fn empty_like(other: &InfluenceMap) -> Self {
+        InfluenceMap {
+            values: vec![0.; other.values.len()],
+            ..other.clone()
+        }
+    }
+
+    /// Sets the position of the corner of the first cell.
+    pub fn with_origin(mut self, origin: [f32; 2]) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    /// Sets the plane of the grid, `Xz` by default.
+    pub fn with_plane(mut self, plane: FogPlane) -> Self {
+        self.plane = plane;
+        self
+    }
+
+    /// Returns the width and height of the grid, in cells.
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Returns the values, row by row from the first cell.
+    pub fn values(&self) -> &[f32] {
+        &self.values
+    }
+
+    fn coordinates(&self, position: [f32; 3]) -> (f32, f32) {
+        let (x, y) = match self.plane {
+            FogPlane::Xy => (position[0], position[1]),
+            FogPlane::Xz => (position[0], position[2]),
+        };
+        (
+            (x - self.origin[0]) / self.cell_size,
+            (y - self.origin[1]) / self.cell_size,
+        )
+    }
+
+    /// Returns the cell at the position, if it's on the map.
+    pub fn cell(&self, position: [f32; 3]) -> Option<(u32, u32)> {
+        let (x, y) = self.coordinates(position);
+        if x < 0. || y < 0. || x >= self.width as f32 || y >= self.height as f32 {
+            return None;
+        }
+        Some((x as u32, y as u32))
+    }
+
+    /// Returns the position of the center of the cell, on the plane.
+    pub fn cell_center(&self, (x, y): (u32, u32)) -> [f32; 3] {
+        let x = self.origin[0] + (x as f32 + 0.5) * self.cell_size;
+        let y = self.origin[1] + (y as f32 + 0.5) * self.cell_size;
+        match self.plane {
+            FogPlane::Xy => [x, y, 0.],
+            FogPlane::Xz => [x, 0., y],
+        }
+    }
+
+    fn index(&self, (x, y): (u32, u32)) -> Option<usize> {
+        if x < self.width && y < self.height {
+            Some((y * self.width + x) as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the value of the cell, `0` outside of the map.
+    pub fn get(&self, cell: (u32, u32)) -> f32 {
+        self.index(cell).map_or(0., |index| self.values[index])
+    }
+
+    /// Sets the value of the cell.
+    pub fn set(&mut self, cell: (u32, u32), value: f32) {
+        if let Some(index) = self.index(cell) {
+            self.values[index] = value;
+        }
+    }
+
+    /// Returns the value at the position, `0` outside of the map.
+    pub fn value_at(&self, position: [f32; 3]) -> f32 {
+        self.cell(position).map_or(0., |cell| self.get(cell))
+    }
+
+    /// Sets all the cells to the value.
+    pub fn fill(&mut self, value: f32) {
+        self.values.par_iter_mut().for_each(|v| *v = value);
+    }
+
+    /// Calls the function on the cells within the radius of the position, with their factor.
+    fn stamp<F>(&mut self, position: [f32; 3], radius: f32, falloff: Falloff, mut f: F)
+    where
+        F: FnMut(&mut f32, f32),
+    {
+        let (cx, cy) = self.coordinates(position);
+        let reach = radius / self.cell_size;
+        let clamp = |value: f32, limit: u32| value.max(0.).min(limit as f32) as u32;
+        let rows =
+            clamp((cy - reach).floor(), self.height)..clamp((cy + reach).ceil(), self.height);
+        let columns =
+            clamp((cx - reach).floor(), self.width)..clamp((cx + reach).ceil(), self.width);
+        for y in rows {
+            for x in columns.clone() {
+                let (dx, dy) = (x as f32 + 0.5 - cx, y as f32 + 0.5 - cy);
+                let fraction = (dx * dx + dy * dy).sqrt() / reach.max(std::f32::EPSILON);
+                if fraction <= 1. {
+                    let index = (y * self.width + x) as usize;
+                    f(&mut self.values[index], falloff.factor(fraction));
+                }
+            }
+        }
+    }
+
+    /// Adds the strength around the position, falling off up to the radius.
+    pub fn add_influence(
+        &mut self,
+        position: [f32; 3],
+        strength: f32,
+        radius: f32,
+        falloff: Falloff,
+    ) {
+        self.stamp(position, radius, falloff, |value, factor| {
+            *value += strength * factor
+        });
+    }
+
+    /// Keeps the cells around the position at least at the strength falling off up to the
+    /// radius, or at most for a negative strength.
+    pub fn hold_influence(
+        &mut self,
+        position: [f32; 3],
+        strength: f32,
+        radius: f32,
+        falloff: Falloff,
+    ) {
+        self.stamp(position, radius, falloff, |value, factor| {
+            let held = strength * factor;
+            *value = if strength >= 0. {
+                value.max(held)
+            } else {
+                value.min(held)
+            };
+        });
+    }
+
+    /// Multiplies all the cells by the factor.
+    pub fn scale(&mut self, factor: f32) {
+        self.values.par_iter_mut().for_each(|v| *v *= factor);
+    }
+
+    /// Fades the values by the fraction lost per second, over the seconds.
+    pub fn decay(&mut self, rate: f32, seconds: f32) {
+        let rate = rate.max(0.).min(1.);
+        if rate > 0. {
+            self.scale((1. - rate).powf(seconds));
+        }
+    }
+
+    /// Spreads the values to the neighboring cells, blending every cell with the mean of its
+    /// neighbors by the amount, from `0` for not at all to `1` for only the mean.
+    pub fn blur(&mut self, amount: f32) {
+        if amount <= 0. || self.values.is_empty() {
+            return;
+        }
+        let (width, height) = (self.width as usize, self.height as usize);
+        let source = self.values.clone();
+        self.values
+            .par_chunks_mut(width)
+            .enumerate()
+            .for_each(|(y, row)| {
+                for (x, value) in row.iter_mut().enumerate() {
+                    let (mut sum, mut count) = (0., 0.);
+                    for ny in y.saturating_sub(1)..(y + 2).min(height) {
+                        for nx in x.saturating_sub(1)..(x + 2).min(width) {
+                            if (nx, ny) != (x, y) {
+                                sum += source[ny * width + nx];
+                                count += 1.;
+                            }
+                        }
+                    }
+                    if count > 0. {
+                        *value = *value * (1. - amount) + sum / count * amount;
+                    }
+                }
+            });
+    }
+
+    /// Sets every cell to the function of its value and the value of the cell of the other map.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the maps don't have the same size.
+    pub fn combine<F>(&mut self, other: &InfluenceMap, f: F)
+    where
+        F: Fn(f32, f32) -> f32 + Sync + Send,
+    {
+        assert_eq!(
+            self.size(),
+            other.size(),
+            "Combining influence maps of different sizes"
+        );
+        self.values
+            .par_iter_mut()
+            .zip(other.values.par_iter())
+            .for_each(|(value, &other)| *value = f(*value, other));
+    }
+
+    /// Adds the other map times the weight.
+    pub fn add_map(&mut self, other: &InfluenceMap, weight: f32) {
+        self.combine(other, |a, b| a + b * weight);
+    }
+
+    /// Multiplies by the other map, e.g. by a mask of the reachable cells.
+    pub fn multiply_map(&mut self, other: &InfluenceMap) {
+        self.combine(other, |a, b| a * b);
+    }
+
+    /// Keeps the highest of the values of both maps.
+    pub fn max_map(&mut self, other: &InfluenceMap) {
+        self.combine(other, f32::max);
+    }
+
+    /// Keeps the lowest of the values of both maps.
+    pub fn min_map(&mut self, other: &InfluenceMap) {
+        self.combine(other, f32::min);
+    }
+
+    /// Scales the values between `0` and `1`, from the lowest to the highest.
+    pub fn normalize(&mut self) {
+        let (min, max) = self
+            .values
+            .par_iter()
+            .fold(
+                || (std::f32::INFINITY, std::f32::NEG_INFINITY),
+                |(min, max), &v| (min.min(v), max.max(v)),
+            )
+            .reduce(
+                || (std::f32::INFINITY, std::f32::NEG_INFINITY),
+                |a, b| (a.0.min(b.0), a.1.max(b.1)),
+            );
+        let range = max - min;
+        if range > std::f32::EPSILON {
+            self.values
+                .par_iter_mut()
+                .for_each(|v| *v = (*v - min) / range);
+        }
+    }
+
+    /// Returns the cell with the highest score, scoring every cell from its coordinates and
+    /// value, e.g. to trade the influence of a cell against its distance.
+    pub fn best_cell_by<F>(&self, score: F) -> Option<((u32, u32), f32)>
+    where
+        F: Fn((u32, u32), f32) -> Option<f32> + Sync + Send,
+    {
+        let width = self.width.max(1);
+        self.values
+            .par_iter()
+            .enumerate()
+            .filter_map(|(index, &value)| {
+                let cell = (index as u32 % width, index as u32 / width);
+                score(cell, value).map(|score| (cell, score))
+            })
+            .reduce_with(|a, b| {
+                // The lowest index wins ties, whatever the order of the threads.
+                let first = |c: (u32, u32)| (c.1, c.0);
+                match b.1.partial_cmp(&a.1) {
+                    Some(Ordering::Greater) => b,
+                    Some(Ordering::Equal) if first(b.0) < first(a.0) => b,
+                    _ => a,
+                }
+            })
+    }
+
+    /// Returns the cell with the highest value.
+    pub fn best_cell(&self) -> Option<((u32, u32), f32)> {
+        self.best_cell_by(|_, value| Some(value))
+    }
+
+    /// Returns the cell with the highest value within the radius of the position, e.g. the
+    /// safest place a unit can reach.
+    pub fn best_in_radius(&self, position: [f32; 3], radius: f32) -> Option<((u32, u32), f32)> {
+        let (cx, cy) = self.coordinates(position);
+        let reach = radius / self.cell_size;
+        self.best_cell_by(|(x, y), value| {
+            let (dx, dy) = (x as f32 + 0.5 - cx, y as f32 + 0.5 - cy);
+            if dx * dx + dy * dy <= reach * reach {
+                Some(value)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+/// A layer of the `InfluenceMaps`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InfluenceLayer {
+    /// The map.
+    pub map: InfluenceMap,
+    /// The fraction of the influence lost per second.
+    pub decay: f32,
+    /// How much the influence spreads to the neighboring cells on each update.
+    pub blur: f32,
+}
+
+impl InfluenceLayer {
+    /// Creates a layer without decay or blur.
+    pub fn new(map: InfluenceMap) -> Self {
+        InfluenceLayer {
+            map,
+            decay: 0.,
+            blur: 0.,
+        }
+    }
+
+    /// Sets the fraction of the influence lost per second.
+    pub fn with_decay(mut self, decay: f32) -> Self {
+        self.decay = decay;
+        self
+    }
+
+    /// Sets how much the influence spreads on each update.
+    pub fn with_blur(mut self, blur: f32) -> Self {
+        self.blur = blur;
+        self
+    }
+}
+
+/// Resource with the named layers updated by the `InfluenceSystem`.
+#[derive(Clone, Debug, Default)]
+pub struct InfluenceMaps {
+    layers: HashMap<String, InfluenceLayer>,
+}
+
+impl InfluenceMaps {
+    /// Creates the resource without layers.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds the layer.
+    pub fn with_layer<S: Into<String>>(mut self, name: S, layer: InfluenceLayer) -> Self {
+        self.insert(name, layer);
+        self
+    }
+
+    /// Adds or replaces the layer.
+    pub fn insert<S: Into<String>>(&mut self, name: S, layer: InfluenceLayer) {
+        self.layers.insert(name.into(), layer);
+    }
+
+    /// Returns the map of the layer.
+    pub fn map(&self, name: &str) -> Option<&InfluenceMap> {
+        self.layers.get(name).map(|layer| &layer.map)
+    }
+
+    /// Returns the layer.
+    pub fn layer(&self, name: &str) -> Option<&InfluenceLayer> {
+        self.layers.get(name)
+    }
+
+    /// Returns the layer to change it.
+    pub fn layer_mut(&mut self, name: &str) -> Option<&mut InfluenceLayer> {
+        self.layers.get_mut(name)
+    }
+
+    /// Returns a map adding the maps of the layers times their weights, e.g. `[("enemies",
+    /// 1.), ("allies", -1.)]` for the threat of the enemies over the allies. Missing layers are
+    /// skipped; `None` if none is found.
+    pub fn weighted_sum(&self, layers: &[(&str, f32)]) -> Option<InfluenceMap> {
+        let mut sum: Option<InfluenceMap> = None;
+        for &(name, weight) in layers {
+            let map = match self.map(name) {
+                Some(map) => map,
+                None => continue,
+            };
+            match sum {
+                Some(ref mut sum) if sum.size() == map.size() => sum.add_map(map, weight),
+                Some(_) => warn!("Influence layer of another size: {}", name),
+                None => {
+                    let mut first = map.clone();
+                    first.scale(weight);
+                    sum = Some(first);
+                }
+            }
+        }
+        sum
+    }
+}
+
+/// An emission of an `InfluenceEmitter`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Emission {
+    /// The layer emitted to.
+    pub layer: String,
+    /// The influence at the entity, negative to lower the values of the layer.
+    pub strength: f32,
+    /// The distance the influence reaches.
+    pub radius: f32,
+    /// How the influence falls off up to the radius.
+    #[serde(default = "default_falloff")]
+    pub falloff: Falloff,
+}
+
+fn default_falloff() -> Falloff {
+    Falloff::Linear
+}
+
+/// Puts influence on layers of the `InfluenceMaps` around the entity.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct InfluenceEmitter {
+    /// The emissions, to any number of layers.
+    pub emissions: Vec<Emission>,
+}
+
+impl InfluenceEmitter {
+    /// Creates an emitter without emissions.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds an emission to the layer, falling off linearly.
+    pub fn with<S: Into<String>>(mut self, layer: S, strength: f32, radius: f32) -> Self {
+        self.emissions.push(Emission {
+            layer: layer.into(),
+            strength,
+            radius,
+            falloff: Falloff::Linear,
+        });
+        self
+    }
+}
+
+impl Component for InfluenceEmitter {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Decays, emits and blurs the layers of the `InfluenceMaps`, see the
+/// [module documentation](index.html).
+#[derive(Default)]
+pub struct InfluenceSystem;
+
+impl<'a> System<'a> for InfluenceSystem {
+    type SystemData = (
+        Read<'a, Time>,
+        Write<'a, InfluenceMaps>,
+        ReadStorage<'a, InfluenceEmitter>,
+        ReadStorage<'a, GlobalTransform>,
+    );
+
+    fn run(&mut self, (time, mut maps, emitters, globals): Self::SystemData) {
+        let seconds = time.delta_seconds();
+        for layer in maps.layers.values_mut() {
+            layer.map.decay(layer.decay, seconds);
+        }
+        for (emitter, global) in (&emitters, &globals).join() {
+            let position = [global.0[(0, 3)], global.0[(1, 3)], global.0[(2, 3)]];
+            for emission in &emitter.emissions {
+                if let Some(layer) = maps.layers.get_mut(&emission.layer) {
+                    layer.map.hold_influence(
+                        position,
+                        emission.strength,
+                        emission.radius,
+                        emission.falloff,
+                    );
+                }
+            }
+        }
+        for layer in maps.layers.values_mut() {
+            layer.map.blur(layer.blur);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn influence_combines_spreads_and_scores() {
+        let mut enemies = InfluenceMap::new(8, 8, 1.);
+        enemies.hold_influence([1.5, 0., 1.5], 4., 2., Falloff::Linear);
+        assert!((enemies.get((1, 1)) - 4.).abs() < 1e-4);
+        assert!((enemies.get((2, 1)) - 2.).abs() < 1e-4);
+        assert!(enemies.get((4, 1)).abs() < 1e-4);
+
+        let mut allies = InfluenceMap::empty_like(&enemies);
+        allies.add_influence([6.5, 0., 6.5], 3., 1., Falloff::Constant);
+        let mut safety = allies.clone();
+        safety.add_map(&enemies, -1.);
+        // The lowest of the cells at 3.
+        assert_eq!(Some(((6, 5), 3.)), safety.best_cell());
+        assert_eq!(
+            Some(((3, 1), 0.)),
+            safety.best_in_radius([1.5, 0., 1.5], 2.)
+        );
+
+        enemies.decay(0.5, 1.);
+        assert!((enemies.get((1, 1)) - 2.).abs() < 1e-4);
+        enemies.blur(1.);
+        assert!(enemies.get((1, 1)) < 1. && enemies.get((3, 1)) > 0.);
+    }
+}
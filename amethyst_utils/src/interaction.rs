@@ -0,0 +1,408 @@
+//! Objects the players interact with, like doors, levers and characters to talk to.
+//!
+//! Every player-controlled entity gets an `Interactor`, and every object an `Interactable`. The
+//! `InteractionSystem` selects the best object in front of each interactor, within the range and
+//! angle of the object, and sends an `InteractionEvent` when the player presses the use action.
+//!
+//! The `InteractionPromptSystem` shows the prompt of the selected object on a UI element with an
+//! `InteractionPrompt` and a `UiText`, anchoring the element on the object with a
+//! `UiWorldAnchor`, and hides the element while nothing is selected.
+
+use std::{hash::Hash, marker::PhantomData};
+
+use amethyst_assets::{AssetStorage, PrefabData, PrefabError};
+use amethyst_core::{
+    nalgebra::Vector3,
+    shrev::EventChannel,
+    specs::prelude::{
+        Component, DenseVecStorage, Entities, Entity, Join, Read, ReadStorage, System, Write,
+        WriteStorage,
+    },
+    GlobalTransform,
+};
+use amethyst_input::InputHandler;
+use amethyst_locale::{Locale, LocaleDiagnostics, LocaleHandle};
+use amethyst_renderer::Hidden;
+use amethyst_ui::{UiText, UiWorldAnchor};
+
+/// An object the players can interact with.
+#[derive(Clone, Debug, Deserialize, PartialEq, PrefabData, Serialize)]
+#[prefab(Component)]
+#[serde(default)]
+pub struct Interactable {
+    /// The key of the prompt text in the locale, e.g. `"prompt-open-door"`.
+    pub prompt: String,
+    /// How close the interactor has to be.
+    pub range: f32,
+    /// How far from the forward direction of the interactor the object can be, in radians.
+    pub angle: f32,
+    /// Objects with a higher priority are selected over the others in range, e.g. to pick
+    /// a character over the crate they stand next to.
+    pub priority: i32,
+    /// Where the prompt is shown, in world space from the position of the object.
+    pub prompt_offset: [f32; 3],
+    /// Whether the object can be interacted with now.
+    pub enabled: bool,
+}
+
+impl Default for Interactable {
+    fn default() -> Self {
+        Interactable {
+            prompt: String::new(),
+            range: 2.,
+            angle: std::f32::consts::FRAC_PI_3,
+            priority: 0,
+            prompt_offset: [0., 1., 0.],
+            enabled: true,
+        }
+    }
+}
+
+impl Interactable {
+    /// Creates an object with the prompt text key.
+    pub fn new<S: Into<String>>(prompt: S) -> Self {
+        Interactable {
+            prompt: prompt.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Sets how close the interactor has to be.
+    pub fn with_range(mut self, range: f32) -> Self {
+        self.range = range;
+        self
+    }
+
+    /// Sets how far from the forward direction of the interactor the object can be, in radians.
+    pub fn with_angle(mut self, angle: f32) -> Self {
+        self.angle = angle;
+        self
+    }
+
+    /// Sets the priority of the object.
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Sets where the prompt is shown, from the position of the object.
+    pub fn with_prompt_offset(mut self, offset: [f32; 3]) -> Self {
+        self.prompt_offset = offset;
+        self
+    }
+
+    /// Returns how well the object at the distance and angle from the interactor fits, or
+    /// `None` when it's out of range.
+    fn score(&self, distance: f32, angle: f32) -> Option<f32> {
+        if !self.enabled || distance > self.range || angle > self.angle {
+            return None;
+        }
+        let distance = distance / self.range.max(std::f32::EPSILON);
+        let angle = angle / self.angle.max(std::f32::EPSILON);
+        Some(2. - distance - angle)
+    }
+}
+
+/// A player-controlled entity interacting with the `Interactable`s in front of it, the negative
+/// `z` axis of its `GlobalTransform`, with the use action `AC` of the `InputHandler`.
+#[derive(Clone, Debug)]
+pub struct Interactor<AC> {
+    /// The use action.
+    pub action: AC,
+    selected: Option<Entity>,
+    was_down: bool,
+}
+
+impl<AC> Interactor<AC> {
+    /// Creates an interactor using objects with the action.
+    pub fn new(action: AC) -> Self {
+        Interactor {
+            action,
+            selected: None,
+            was_down: false,
+        }
+    }
+
+    /// Returns the object selected by the interactor.
+    pub fn selected(&self) -> Option<Entity> {
+        self.selected
+    }
+}
+
+impl Component for Interactable {
+    type Storage = DenseVecStorage<Self>;
+}
+
+impl<AC: Send + Sync + 'static> Component for Interactor<AC> {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Event sent by the `InteractionSystem` on an `EventChannel<InteractionEvent>` when a player
+/// uses an object.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InteractionEvent {
+    /// The entity of the `Interactor`.
+    pub interactor: Entity,
+    /// The entity of the `Interactable`.
+    pub target: Entity,
+    /// The prompt text key of the object, e.g. to tell apart the kinds of interactions.
+    pub prompt: String,
+}
+
+/// Selects the best `Interactable` of each `Interactor` and sends `InteractionEvent`s when they
+/// press their use action.
+///
+/// Among the objects in range, the one with the highest priority is selected, then the one
+/// closest to the interactor and to its forward direction.
+pub struct InteractionSystem<AX, AC> {
+    marker: PhantomData<(AX, AC)>,
+}
+
+impl<AX, AC> InteractionSystem<AX, AC> {
+    /// Creates the system.
+    pub fn new() -> Self {
+        InteractionSystem {
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<AX, AC> Default for InteractionSystem<AX, AC> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, AX, AC> System<'a> for InteractionSystem<AX, AC>
+where
+    AX: Hash + Eq + Clone + Send + Sync + 'static,
+    AC: Hash + Eq + Clone + Send + Sync + 'static,
+{
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, InputHandler<AX, AC>>,
+        ReadStorage<'a, GlobalTransform>,
+        ReadStorage<'a, Interactable>,
+        WriteStorage<'a, Interactor<AC>>,
+        Write<'a, EventChannel<InteractionEvent>>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, input, globals, interactables, mut interactors, mut channel): Self::SystemData,
+    ) {
+        for (entity, global, interactor) in (&*entities, &globals, &mut interactors).join() {
+            let position = global.0.column(3).xyz();
+            let forward = global.0.transform_vector(&-Vector3::z());
+            interactor.selected = (&*entities, &globals, &interactables)
+                .join()
+                .filter(|&(target, _, _)| target != entity)
+                .filter_map(|(target, target_global, interactable)| {
+                    let to = target_global.0.column(3).xyz() - position;
+                    let angle = if to.norm() > std::f32::EPSILON {
+                        forward.angle(&to)
+                    } else {
+                        0.
+                    };
+                    let score = interactable.score(to.norm(), angle)?;
+                    Some((target, interactable.priority, score))
+                })
+                .max_by(|a, b| {
+                    a.1.cmp(&b.1)
+                        .then(a.2.partial_cmp(&b.2).unwrap_or(std::cmp::Ordering::Equal))
+                })
+                .map(|(target, _, _)| target);
+
+            let down = input.action_is_down(&interactor.action).unwrap_or(false);
+            if down && !interactor.was_down {
+                if let Some(target) = interactor.selected {
+                    channel.single_write(InteractionEvent {
+                        interactor: entity,
+                        target,
+                        prompt: interactables.get(target).unwrap().prompt.clone(),
+                    });
+                }
+            }
+            interactor.was_down = down;
+        }
+    }
+}
+
+/// Put on an entity with a `UiText` to show the prompt of the object selected by an
+/// `Interactor`, e.g. "Press E to open".
+///
+/// The prompt text key is looked up in the `locale`, and used as it is without a locale or when
+/// the key is missing, which is reported to the `LocaleDiagnostics`.
+#[derive(Clone, Debug)]
+pub struct InteractionPrompt {
+    /// The entity of the `Interactor`.
+    pub interactor: Entity,
+    /// The locale with the texts of the prompts.
+    pub locale: Option<LocaleHandle>,
+}
+
+impl InteractionPrompt {
+    /// Creates a prompt for the interactor.
+    pub fn new(interactor: Entity) -> Self {
+        InteractionPrompt {
+            interactor,
+            locale: None,
+        }
+    }
+
+    /// Sets the locale with the texts of the prompts.
+    pub fn with_locale(mut self, locale: LocaleHandle) -> Self {
+        self.locale = Some(locale);
+        self
+    }
+}
+
+impl Component for InteractionPrompt {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Shows the prompts of the objects selected by the `Interactor`s on the `InteractionPrompt`s.
+///
+/// It places the prompts with their `UiWorldAnchor`, so it runs before the `UiTransformSystem`.
+pub struct InteractionPromptSystem<AC> {
+    marker: PhantomData<AC>,
+}
+
+impl<AC> InteractionPromptSystem<AC> {
+    /// Creates the system.
+    pub fn new() -> Self {
+        InteractionPromptSystem {
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<AC> Default for InteractionPromptSystem<AC> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, AC: Send + Sync + 'static> System<'a> for InteractionPromptSystem<AC> {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, AssetStorage<Locale>>,
+        Write<'a, LocaleDiagnostics>,
+        ReadStorage<'a, Interactor<AC>>,
+        ReadStorage<'a, Interactable>,
+        ReadStorage<'a, InteractionPrompt>,
+        WriteStorage<'a, UiWorldAnchor>,
+        WriteStorage<'a, UiText>,
+        WriteStorage<'a, Hidden>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            locales,
+            mut diagnostics,
+            interactors,
+            interactables,
+            prompts,
+            mut anchors,
+            mut texts,
+            mut hidden,
+        ): Self::SystemData,
+    ) {
+        for (entity, prompt) in (&*entities, &prompts).join() {
+            let selected = interactors
+                .get(prompt.interactor)
+                .and_then(Interactor::selected)
+                .and_then(|target| Some((target, interactables.get(target)?)));
+            let (target, interactable) = match selected {
+                Some(selected) => selected,
+                None => {
+                    if !hidden.contains(entity) {
+                        if let Err(err) = hidden.insert(entity, Hidden) {
+                            error!("Failed to hide the interaction prompt: {}", err);
+                        }
+                    }
+                    continue;
+                }
+            };
+            hidden.remove(entity);
+
+            let offset = Vector3::from(interactable.prompt_offset);
+            match anchors.get_mut(entity) {
+                Some(anchor) => {
+                    anchor.entity = target;
+                    anchor.offset = offset;
+                }
+                None => {
+                    if let Err(err) = anchors.insert(entity, UiWorldAnchor::new(target, offset)) {
+                        error!("Failed to anchor the interaction prompt: {}", err);
+                    }
+                }
+            }
+
+            let text = prompt
+                .locale
+                .as_ref()
+                .and_then(|locale| locales.get(locale))
+                .map(|locale| locale.text(&interactable.prompt, &mut diagnostics))
+                .unwrap_or_else(|| interactable.prompt.clone());
+            if let Some(ui_text) = texts.get_mut(entity) {
+                if ui_text.text != text {
+                    ui_text.text = text;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use amethyst_core::{
+        nalgebra::Translation3,
+        specs::prelude::{Builder, RunNow, World},
+    };
+
+    #[test]
+    fn selects_the_object_in_front_with_the_highest_priority() {
+        let mut world = World::new();
+        world.register::<GlobalTransform>();
+        world.register::<Interactable>();
+        world.register::<Interactor<String>>();
+        world.add_resource(InputHandler::<String, String>::new());
+        world.add_resource(EventChannel::<InteractionEvent>::new());
+
+        let at = |x: f32, z: f32| GlobalTransform(Translation3::new(x, 0., z).to_homogeneous());
+        let player = world
+            .create_entity()
+            .with(at(0., 0.))
+            .with(Interactor::new(String::from("use")))
+            .build();
+        world
+            .create_entity()
+            .with(at(0., 1.))
+            .with(Interactable::new("behind"))
+            .build();
+        world
+            .create_entity()
+            .with(at(0., -1.))
+            .with(Interactable::new("crate"))
+            .build();
+        let door = world
+            .create_entity()
+            .with(at(0.5, -1.5))
+            .with(Interactable::new("door").with_priority(1))
+            .build();
+        world
+            .create_entity()
+            .with(at(0., -5.))
+            .with(Interactable::new("far").with_priority(2))
+            .build();
+
+        InteractionSystem::<String, String>::new().run_now(&world.res);
+        let interactors = world.read_storage::<Interactor<String>>();
+        assert_eq!(Some(door), interactors.get(player).unwrap().selected());
+    }
+}
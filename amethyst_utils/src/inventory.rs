@@ -0,0 +1,470 @@
+//! Items and inventories.
+//!
+//! An `ItemSet` is an asset, loaded from a RON file with `ItemSetFormat`, defining the items of
+//! the game: how many of them fit in a slot, their tags, their icons on a sprite sheet and any
+//! other property the game needs. An `Inventory` component holds stacks of these items in slots,
+//! and the `InventorySystem` sends an `InventoryEvent` when a slot changes, e.g. for the UI to
+//! show it.
+//!
+//! ```ron
+//! (
+//!     items: {
+//!         "potion": (
+//!             name: "item-potion",
+//!             max_stack: 10,
+//!             tags: ["consumable"],
+//!             icon: Some(3),
+//!             properties: {"heal": Number(25.0)},
+//!         ),
+//!         "sword": (name: "item-sword", tags: ["weapon"], icon: Some(0)),
+//!     },
+//! )
+//! ```
+
+use std::collections::HashMap;
+
+use amethyst_assets::{
+    Asset, AssetStorage, Handle, ProcessingState, Processor, Result, RonFormat, SimpleFormat,
+};
+use amethyst_core::{
+    bundle::{Result as BundleResult, SystemBundle},
+    shrev::EventChannel,
+    specs::prelude::{
+        Component, DenseVecStorage, DispatcherBuilder, Entities, Entity, Join, System, VecStorage,
+        Write, WriteStorage,
+    },
+};
+use amethyst_renderer::{SpriteRender, SpriteSheetHandle};
+
+use crate::blackboard::Value;
+
+/// An item of an `ItemSet`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct ItemDefinition {
+    /// The name of the item, or its key in the locale.
+    pub name: String,
+    /// Describes the item, or its key in the locale.
+    pub description: String,
+    /// How many of the item fit in one slot, at least 1.
+    pub max_stack: u32,
+    /// The tags of the item, like "weapon", restricting which inventories take it.
+    pub tags: Vec<String>,
+    /// Number of the icon of the item on the sprite sheet of the `ItemSet`.
+    pub icon: Option<usize>,
+    /// Other properties of the item, like how much health a potion gives.
+    pub properties: HashMap<String, Value>,
+}
+
+impl Default for ItemDefinition {
+    fn default() -> Self {
+        ItemDefinition {
+            name: String::new(),
+            description: String::new(),
+            max_stack: 1,
+            tags: Vec::new(),
+            icon: None,
+            properties: HashMap::new(),
+        }
+    }
+}
+
+impl ItemDefinition {
+    /// Returns whether the item has the tag.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
+    /// Returns the property.
+    pub fn property(&self, name: &str) -> Option<&Value> {
+        self.properties.get(name)
+    }
+}
+
+/// Asset with the items of the game by id, see the [module documentation](index.html).
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ItemSet {
+    /// The sprite sheet with the icons of the items.
+    #[serde(skip)]
+    pub icons: Option<SpriteSheetHandle>,
+    /// The items by id.
+    pub items: HashMap<String, ItemDefinition>,
+}
+
+impl ItemSet {
+    /// Returns the item.
+    pub fn item(&self, id: &str) -> Option<&ItemDefinition> {
+        self.items.get(id)
+    }
+
+    /// Returns the icon of the item, to put on a UI element or an entity in the world.
+    pub fn icon(&self, id: &str) -> Option<SpriteRender> {
+        let sprite_number = self.item(id)?.icon?;
+        self.icons.as_ref().map(|sheet| SpriteRender {
+            sprite_sheet: sheet.clone(),
+            sprite_number,
+        })
+    }
+
+    /// Returns how many of the item fit in one slot, 0 for unknown items.
+    fn max_stack(&self, id: &str) -> u32 {
+        self.item(id).map_or(0, |item| item.max_stack.max(1))
+    }
+}
+
+impl Asset for ItemSet {
+    const NAME: &'static str = "utils::ItemSet";
+    type Data = Self;
+    type HandleStorage = VecStorage<Handle<Self>>;
+}
+
+impl Into<Result<ProcessingState<ItemSet>>> for ItemSet {
+    fn into(self) -> Result<ProcessingState<ItemSet>> {
+        Ok(ProcessingState::Loaded(self))
+    }
+}
+
+/// Loads an `ItemSet` from RON, with the sprite sheet of the icons as options.
+#[derive(Clone, Debug, Default)]
+pub struct ItemSetFormat;
+
+impl SimpleFormat<ItemSet> for ItemSetFormat {
+    const NAME: &'static str = "ITEM_SET";
+
+    type Options = SpriteSheetHandle;
+
+    fn import(&self, bytes: Vec<u8>, icons: SpriteSheetHandle) -> Result<ItemSet> {
+        let mut set: ItemSet = SimpleFormat::<ItemSet>::import(&RonFormat, bytes, ())?;
+        set.icons = Some(icons);
+        Ok(set)
+    }
+}
+
+/// A stack of items in a slot of an `Inventory`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ItemStack {
+    /// Id of the item in the `ItemSet`.
+    pub item: String,
+    /// How many of the item are in the stack.
+    pub count: u32,
+}
+
+/// Slots holding stacks of items, e.g. the backpack of the player or a chest.
+///
+/// All changes check the capacity rules: items only go in the slots of the inventory, up to
+/// their `max_stack`, and when `tags` isn't empty only items with one of these tags go in.
+/// The slots changed are sent as `InventoryEvent`s by the `InventorySystem`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Inventory {
+    slots: Vec<Option<ItemStack>>,
+    /// Only items with one of these tags go in the inventory, any item if empty.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(skip)]
+    changed: Vec<usize>,
+}
+
+impl Inventory {
+    /// Creates an empty inventory with that many slots.
+    pub fn new(capacity: usize) -> Self {
+        Inventory {
+            slots: vec![None; capacity],
+            ..Default::default()
+        }
+    }
+
+    /// Only lets items with one of the tags in.
+    pub fn with_tags<S: Into<String>>(mut self, tags: Vec<S>) -> Self {
+        self.tags = tags.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Returns the number of slots.
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Returns the stack in the slot.
+    pub fn slot(&self, slot: usize) -> Option<&ItemStack> {
+        self.slots.get(slot).and_then(Option::as_ref)
+    }
+
+    /// Iterates over the slots.
+    pub fn slots(&self) -> impl Iterator<Item = Option<&ItemStack>> {
+        self.slots.iter().map(Option::as_ref)
+    }
+
+    /// Returns how many of the item are in the inventory.
+    pub fn count(&self, item: &str) -> u32 {
+        self.slots()
+            .flatten()
+            .filter(|stack| stack.item == item)
+            .map(|stack| stack.count)
+            .sum()
+    }
+
+    /// Returns whether the item may go in the inventory.
+    pub fn accepts(&self, set: &ItemSet, item: &str) -> bool {
+        set.item(item).map_or(false, |definition| {
+            self.tags.is_empty() || self.tags.iter().any(|tag| definition.has_tag(tag))
+        })
+    }
+
+    /// Adds the items, first to the stacks of the item, then to empty slots. Returns how many
+    /// didn't fit.
+    pub fn add(&mut self, set: &ItemSet, item: &str, count: u32) -> u32 {
+        if !self.accepts(set, item) {
+            return count;
+        }
+        let max_stack = set.max_stack(item);
+        let mut left = count;
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            if let Some(ref mut stack) = slot {
+                if stack.item == item && stack.count < max_stack && left > 0 {
+                    let added = left.min(max_stack - stack.count);
+                    stack.count += added;
+                    left -= added;
+                    self.changed.push(index);
+                }
+            }
+        }
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            if slot.is_none() && left > 0 {
+                let added = left.min(max_stack);
+                *slot = Some(ItemStack {
+                    item: item.to_string(),
+                    count: added,
+                });
+                left -= added;
+                self.changed.push(index);
+            }
+        }
+        left
+    }
+
+    /// Removes the items, from the last stacks first. Returns `false` without removing any if
+    /// there aren't that many.
+    pub fn remove(&mut self, item: &str, count: u32) -> bool {
+        if self.count(item) < count {
+            return false;
+        }
+        let mut left = count;
+        for (index, slot) in self.slots.iter_mut().enumerate().rev() {
+            if left == 0 {
+                break;
+            }
+            let emptied = match slot {
+                Some(ref mut stack) if stack.item == item => {
+                    let removed = left.min(stack.count);
+                    stack.count -= removed;
+                    left -= removed;
+                    self.changed.push(index);
+                    stack.count == 0
+                }
+                _ => false,
+            };
+            if emptied {
+                *slot = None;
+            }
+        }
+        true
+    }
+
+    /// Takes the stack out of the slot.
+    pub fn take(&mut self, slot: usize) -> Option<ItemStack> {
+        let stack = self.slots.get_mut(slot)?.take();
+        if stack.is_some() {
+            self.changed.push(slot);
+        }
+        stack
+    }
+
+    /// Moves the stack of a slot to another one, merging it with a stack of the same item as much
+    /// as it fits, or else swapping the two. Returns whether anything moved.
+    pub fn move_stack(&mut self, set: &ItemSet, from: usize, to: usize) -> bool {
+        if from == to || from >= self.slots.len() || to >= self.slots.len() {
+            return false;
+        }
+        let (source, target) = if from < to {
+            let (left, right) = self.slots.split_at_mut(to);
+            (&mut left[from], &mut right[0])
+        } else {
+            let (left, right) = self.slots.split_at_mut(from);
+            (&mut right[0], &mut left[to])
+        };
+        if move_between(set, source, target) {
+            self.changed.push(from);
+            self.changed.push(to);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Moves the stack of a slot to a slot of another inventory, like `move_stack`, checking the
+    /// items are accepted by the inventory they go in. Returns whether anything moved.
+    pub fn transfer(
+        &mut self,
+        set: &ItemSet,
+        from: usize,
+        other: &mut Inventory,
+        to: usize,
+    ) -> bool {
+        let accepted = |inventory: &Inventory, stack: &Option<ItemStack>| {
+            stack
+                .as_ref()
+                .map_or(true, |stack| inventory.accepts(set, &stack.item))
+        };
+        let moved = match (self.slots.get(from), other.slots.get(to)) {
+            (Some(source), Some(target)) => {
+                let merges = match (source, target) {
+                    (Some(source), Some(target)) => source.item == target.item,
+                    _ => false,
+                };
+                accepted(other, source) && (merges || accepted(&*self, target))
+            }
+            _ => false,
+        };
+        if moved && move_between(set, &mut self.slots[from], &mut other.slots[to]) {
+            self.changed.push(from);
+            other.changed.push(to);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl Component for Inventory {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Moves the source stack onto the target slot, merging or swapping them.
+fn move_between(
+    set: &ItemSet,
+    source: &mut Option<ItemStack>,
+    target: &mut Option<ItemStack>,
+) -> bool {
+    let merges = match (source.as_ref(), target.as_ref()) {
+        (None, _) => return false,
+        (Some(source), Some(target)) => source.item == target.item,
+        _ => false,
+    };
+    if !merges {
+        std::mem::swap(source, target);
+        return true;
+    }
+    if let (Some(source), Some(target)) = (source.as_mut(), target.as_mut()) {
+        let space = set.max_stack(&source.item).saturating_sub(target.count);
+        let moved = source.count.min(space);
+        if moved == 0 {
+            return false;
+        }
+        source.count -= moved;
+        target.count += moved;
+    }
+    if source.as_ref().map_or(false, |stack| stack.count == 0) {
+        *source = None;
+    }
+    true
+}
+
+/// Event sent by the `InventorySystem` on an `EventChannel<InventoryEvent>`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum InventoryEvent {
+    /// The stack in a slot of an inventory changed.
+    SlotChanged {
+        /// The entity with the `Inventory`.
+        entity: Entity,
+        /// The slot that changed.
+        slot: usize,
+    },
+}
+
+/// Sends an `InventoryEvent` for every slot of the `Inventory`s that changed since the last
+/// frame.
+#[derive(Default)]
+pub struct InventorySystem;
+
+impl<'a> System<'a> for InventorySystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, Inventory>,
+        Write<'a, EventChannel<InventoryEvent>>,
+    );
+
+    fn run(&mut self, (entities, mut inventories, mut channel): Self::SystemData) {
+        for (entity, inventory) in (&*entities, &mut inventories).join() {
+            if inventory.changed.is_empty() {
+                continue;
+            }
+            inventory.changed.sort();
+            inventory.changed.dedup();
+            channel.iter_write(
+                inventory
+                    .changed
+                    .drain(..)
+                    .map(|slot| InventoryEvent::SlotChanged { entity, slot }),
+            );
+        }
+    }
+}
+
+/// Adds the `Processor` for `ItemSet` assets and the `InventorySystem`.
+#[derive(Default)]
+pub struct InventoryBundle;
+
+impl<'a, 'b> SystemBundle<'a, 'b> for InventoryBundle {
+    fn build(self, builder: &mut DispatcherBuilder<'a, 'b>) -> BundleResult<()> {
+        builder.add(Processor::<ItemSet>::new(), "item_set_processor", &[]);
+        builder.add(InventorySystem, "inventory_system", &[]);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item_set() -> ItemSet {
+        let mut set = ItemSet::default();
+        set.items.insert(
+            "potion".to_string(),
+            ItemDefinition {
+                max_stack: 5,
+                tags: vec!["consumable".to_string()],
+                ..Default::default()
+            },
+        );
+        set.items
+            .insert("sword".to_string(), ItemDefinition::default());
+        set
+    }
+
+    #[test]
+    fn items_fill_stacks_then_empty_slots() {
+        let set = item_set();
+        let mut inventory = Inventory::new(3);
+        assert_eq!(0, inventory.add(&set, "potion", 3));
+        assert_eq!(0, inventory.add(&set, "sword", 1));
+        assert_eq!(1, inventory.add(&set, "potion", 8));
+        assert_eq!(10, inventory.count("potion"));
+        assert_eq!(Some(5), inventory.slot(2).map(|stack| stack.count));
+
+        assert!(!inventory.remove("potion", 11));
+        assert!(inventory.remove("potion", 6));
+        assert_eq!(None, inventory.slot(2));
+        assert_eq!(Some(4), inventory.slot(0).map(|stack| stack.count));
+
+        assert!(inventory.move_stack(&set, 0, 1));
+        assert_eq!(
+            Some("potion"),
+            inventory.slot(1).map(|stack| stack.item.as_str())
+        );
+
+        let mut belt = Inventory::new(2).with_tags(vec!["consumable"]);
+        assert!(!inventory.transfer(&set, 0, &mut belt, 0));
+        assert!(inventory.transfer(&set, 1, &mut belt, 0));
+        assert_eq!(4, belt.count("potion"));
+    }
+}
@@ -12,12 +12,39 @@ use shred;
 extern crate shred_derive;
 
 pub mod app_root_dir;
+pub mod aspect_policy;
 pub mod auto_fov;
+pub mod blackboard;
+pub mod camera_sequence;
+pub mod checkpoint;
 pub mod circular_buffer;
+pub mod day_night;
+pub mod destructible;
+pub mod dialogue;
+pub mod entity_pool;
+pub mod fog_of_war;
+pub mod force_field;
 pub mod fps_counter;
+pub mod hitbox;
+pub mod influence;
+pub mod interaction;
+pub mod inventory;
 pub mod ortho_camera;
+pub mod proc_gen;
+pub mod projectile;
+pub mod projection_blend;
+pub mod quest;
+pub mod raycast;
 pub mod removal;
 pub mod scene;
+pub mod stats;
+pub mod status_effect;
+pub mod steering;
+pub mod streaming;
 pub mod tag;
+pub mod tile_editor;
 pub mod time_destroy;
+pub mod vehicle;
+pub mod weather;
+pub mod world_bar;
 pub use self::app_root_dir::*;
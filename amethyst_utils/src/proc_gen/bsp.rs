@@ -0,0 +1,219 @@
+use amethyst_core::RngStream;
+
+use super::Layout;
+
+/// A rectangle of cells.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CellRect {
+    /// Column of the left edge
+    pub x: u32,
+    /// Row of the bottom edge
+    pub y: u32,
+    /// Number of columns
+    pub width: u32,
+    /// Number of rows
+    pub height: u32,
+}
+
+impl CellRect {
+    /// Creates a rectangle.
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        CellRect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Returns the cell at the center of the rectangle.
+    pub fn center(&self) -> (u32, u32) {
+        (self.x + self.width / 2, self.y + self.height / 2)
+    }
+
+    /// Checks whether the cell lies in the rectangle.
+    pub fn contains(&self, (x, y): (u32, u32)) -> bool {
+        x >= self.x && y >= self.y && x < self.x + self.width && y < self.y + self.height
+    }
+
+    /// Checks whether the rectangles share a cell.
+    pub fn intersects(&self, other: &CellRect) -> bool {
+        self.x < other.x + other.width
+            && other.x < self.x + self.width
+            && self.y < other.y + other.height
+            && other.y < self.y + self.height
+    }
+}
+
+/// Generates dungeons of rectangular rooms connected by corridors, by splitting the level in two
+/// recursively, placing a room in each part and connecting the rooms of sibling parts.
+///
+/// Every room can be reached from every other room.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BspGenerator {
+    /// Minimum width and height of the parts the level is split into, walls included.
+    pub min_leaf_size: u32,
+    /// Minimum width and height of a room.
+    pub min_room_size: u32,
+    /// Maximum number of times the level is split in two, limiting the rooms to
+    /// `2^max_depth`.
+    pub max_depth: u32,
+}
+
+impl Default for BspGenerator {
+    fn default() -> Self {
+        BspGenerator {
+            min_leaf_size: 8,
+            min_room_size: 4,
+            max_depth: 6,
+        }
+    }
+}
+
+/// A dungeon generated by the `BspGenerator`.
+#[derive(Clone, Debug)]
+pub struct BspDungeon {
+    /// The floor of the rooms and corridors
+    pub layout: Layout,
+    /// The rooms, in the order the parts of the level were split
+    pub rooms: Vec<CellRect>,
+}
+
+impl BspGenerator {
+    /// Creates a generator with the default settings.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets the minimum size of the parts the level is split into.
+    pub fn with_min_leaf_size(mut self, size: u32) -> Self {
+        self.min_leaf_size = size;
+        self
+    }
+
+    /// Sets the minimum size of the rooms.
+    pub fn with_min_room_size(mut self, size: u32) -> Self {
+        self.min_room_size = size;
+        self
+    }
+
+    /// Sets the maximum number of splits.
+    pub fn with_max_depth(mut self, depth: u32) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Generates a dungeon of the given number of columns and rows.
+    ///
+    /// Rooms stay one cell away from the edges of their part, so the dungeon is surrounded by
+    /// walls. A level too small for a single room has no floor.
+    pub fn generate(&self, size: (u32, u32), rng: &mut RngStream) -> BspDungeon {
+        let mut dungeon = BspDungeon {
+            layout: Layout::new(size),
+            rooms: Vec::new(),
+        };
+        self.split(CellRect::new(0, 0, size.0, size.1), 0, rng, &mut dungeon);
+        dungeon
+    }
+
+    /// Fills the part with rooms, returning their indices.
+    fn split(
+        &self,
+        leaf: CellRect,
+        depth: u32,
+        rng: &mut RngStream,
+        dungeon: &mut BspDungeon,
+    ) -> Vec<usize> {
+        let min = self.min_leaf_size.max(self.min_room_size + 2).max(1);
+        let can_split_x = leaf.width >= 2 * min;
+        let can_split_y = leaf.height >= 2 * min;
+        if depth < self.max_depth && (can_split_x || can_split_y) {
+            // Prefer cutting across the longer side, so parts don't get thin.
+            let split_x = if can_split_x && can_split_y {
+                if leaf.width * 4 >= leaf.height * 5 {
+                    true
+                } else if leaf.height * 4 >= leaf.width * 5 {
+                    false
+                } else {
+                    rng.chance(0.5)
+                }
+            } else {
+                can_split_x
+            };
+            let (first, second) = if split_x {
+                let cut = rng.range_u32(min, leaf.width - min + 1);
+                (
+                    CellRect::new(leaf.x, leaf.y, cut, leaf.height),
+                    CellRect::new(leaf.x + cut, leaf.y, leaf.width - cut, leaf.height),
+                )
+            } else {
+                let cut = rng.range_u32(min, leaf.height - min + 1);
+                (
+                    CellRect::new(leaf.x, leaf.y, leaf.width, cut),
+                    CellRect::new(leaf.x, leaf.y + cut, leaf.width, leaf.height - cut),
+                )
+            };
+            let mut first = self.split(first, depth + 1, rng, dungeon);
+            let second = self.split(second, depth + 1, rng, dungeon);
+            if !first.is_empty() && !second.is_empty() {
+                let a = first[rng.range_u32(0, first.len() as u32) as usize];
+                let b = second[rng.range_u32(0, second.len() as u32) as usize];
+                let (from, to) = (dungeon.rooms[a].center(), dungeon.rooms[b].center());
+                dig_corridor(&mut dungeon.layout, from, to, rng.chance(0.5));
+            }
+            first.extend(second);
+            return first;
+        }
+
+        if leaf.width < self.min_room_size + 2 || leaf.height < self.min_room_size + 2 {
+            return Vec::new();
+        }
+        let width = rng.range_u32(self.min_room_size, leaf.width - 1);
+        let height = rng.range_u32(self.min_room_size, leaf.height - 1);
+        let x = leaf.x + rng.range_u32(1, leaf.width - width);
+        let y = leaf.y + rng.range_u32(1, leaf.height - height);
+        let room = CellRect::new(x, y, width, height);
+        for cy in y..y + height {
+            for cx in x..x + width {
+                dungeon.layout.set_floor(cx, cy, true);
+            }
+        }
+        dungeon.rooms.push(room);
+        vec![dungeon.rooms.len() - 1]
+    }
+}
+
+/// Digs an L shaped corridor between two cells, horizontally first or vertically first.
+fn dig_corridor(layout: &mut Layout, from: (u32, u32), to: (u32, u32), horizontal_first: bool) {
+    let corner = if horizontal_first {
+        (to.0, from.1)
+    } else {
+        (from.0, to.1)
+    };
+    for &(a, b) in &[(from, corner), (corner, to)] {
+        for x in a.0.min(b.0)..=a.0.max(b.0) {
+            for y in a.1.min(b.1)..=a.1.max(b.1) {
+                layout.set_floor(x, y, true);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rooms_are_separate_and_connected() {
+        let dungeon = BspGenerator::new().generate((64, 48), &mut RngStream::new(11));
+        assert!(dungeon.rooms.len() > 4);
+        for (i, room) in dungeon.rooms.iter().enumerate() {
+            assert!(room.x > 0 && room.y > 0);
+            assert!(room.x + room.width < 64 && room.y + room.height < 48);
+            assert!(dungeon.rooms[i + 1..]
+                .iter()
+                .all(|other| !room.intersects(other)));
+        }
+        assert_eq!(1, dungeon.layout.regions().len());
+    }
+}
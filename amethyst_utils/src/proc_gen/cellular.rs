@@ -0,0 +1,116 @@
+use amethyst_core::RngStream;
+
+use super::Layout;
+
+/// Generates caves with a cellular automaton: cells start as random walls, and each iteration
+/// turns cells surrounded by enough walls into walls, smoothing the noise into caverns.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CaveGenerator {
+    /// Probability of a cell to start as a wall.
+    pub wall_probability: f64,
+    /// Number of smoothing iterations.
+    pub iterations: u32,
+    /// Number of walls among its 8 neighbours turning a floor cell into a wall.
+    pub birth_limit: u32,
+    /// Number of walls among its 8 neighbours keeping a wall cell a wall.
+    pub survival_limit: u32,
+    /// Whether to fill all the floor regions but the largest, so the whole cave can be reached.
+    pub keep_largest_region: bool,
+}
+
+impl Default for CaveGenerator {
+    fn default() -> Self {
+        CaveGenerator {
+            wall_probability: 0.45,
+            iterations: 5,
+            birth_limit: 5,
+            survival_limit: 4,
+            keep_largest_region: true,
+        }
+    }
+}
+
+impl CaveGenerator {
+    /// Creates a generator with the default settings.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets the probability of a cell to start as a wall.
+    pub fn with_wall_probability(mut self, probability: f64) -> Self {
+        self.wall_probability = probability;
+        self
+    }
+
+    /// Sets the number of smoothing iterations.
+    pub fn with_iterations(mut self, iterations: u32) -> Self {
+        self.iterations = iterations;
+        self
+    }
+
+    /// Sets the numbers of neighbouring walls making and keeping a cell a wall.
+    pub fn with_limits(mut self, birth: u32, survival: u32) -> Self {
+        self.birth_limit = birth;
+        self.survival_limit = survival;
+        self
+    }
+
+    /// Sets whether to only keep the largest floor region.
+    pub fn with_keep_largest_region(mut self, keep: bool) -> Self {
+        self.keep_largest_region = keep;
+        self
+    }
+
+    /// Generates a cave of the given number of columns and rows.
+    ///
+    /// Cells outside the level count as walls, so the cave is closed.
+    pub fn generate(&self, size: (u32, u32), rng: &mut RngStream) -> Layout {
+        let (width, height) = size;
+        let mut layout = Layout::new(size);
+        for y in 0..height {
+            for x in 0..width {
+                layout.set_floor(x, y, !rng.chance(self.wall_probability));
+            }
+        }
+
+        for _ in 0..self.iterations {
+            let previous = layout.clone();
+            for y in 0..height {
+                for x in 0..width {
+                    let walls = wall_neighbours(&previous, x, y);
+                    let wall = if previous.is_floor(x, y) {
+                        walls >= self.birth_limit
+                    } else {
+                        walls >= self.survival_limit
+                    };
+                    layout.set_floor(x, y, !wall);
+                }
+            }
+        }
+
+        if self.keep_largest_region {
+            for region in layout.regions().iter().skip(1) {
+                for &(x, y) in region {
+                    layout.set_floor(x, y, false);
+                }
+            }
+        }
+        layout
+    }
+}
+
+fn wall_neighbours(layout: &Layout, x: u32, y: u32) -> u32 {
+    let mut walls = 0;
+    for dy in -1i64..=1 {
+        for dx in -1i64..=1 {
+            if dx == 0 && dy == 0 {
+                continue;
+            }
+            let (nx, ny) = (i64::from(x) + dx, i64::from(y) + dy);
+            if nx < 0 || ny < 0 || !layout.is_floor(nx as u32, ny as u32) {
+                walls += 1;
+            }
+        }
+    }
+    walls
+}
@@ -0,0 +1,99 @@
+//! Procedural generation of levels on grids of tiles: seeded noise, rooms split by binary space
+//! partitioning, caves grown by cellular automata and Wave Function Collapse over tile sets.
+//!
+//! All generators draw their numbers from an `RngStream`, so a level can be regenerated from its
+//! seed, and write their result into any `TileGrid`.
+
+pub use self::{bsp::*, cellular::*, noise::*, wfc::*};
+
+mod bsp;
+mod cellular;
+mod noise;
+mod wfc;
+
+use crate::tile_editor::TileGrid;
+
+/// Floor and wall cells of a generated level.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Layout {
+    width: u32,
+    height: u32,
+    floor: Vec<bool>,
+}
+
+impl Layout {
+    /// Creates a layout of the given number of columns and rows, made of walls only.
+    pub fn new((width, height): (u32, u32)) -> Self {
+        Layout {
+            width,
+            height,
+            floor: vec![false; (width * height) as usize],
+        }
+    }
+
+    /// Returns the number of columns and rows of the layout.
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Checks whether the cell is floor. Cells outside the layout are walls.
+    pub fn is_floor(&self, x: u32, y: u32) -> bool {
+        x < self.width && y < self.height && self.floor[(y * self.width + x) as usize]
+    }
+
+    /// Makes the cell floor or wall. Cells outside the layout are ignored.
+    pub fn set_floor(&mut self, x: u32, y: u32, floor: bool) {
+        if x < self.width && y < self.height {
+            self.floor[(y * self.width + x) as usize] = floor;
+        }
+    }
+
+    /// Returns the connected regions of floor cells, largest first.
+    pub fn regions(&self) -> Vec<Vec<(u32, u32)>> {
+        let mut visited = vec![false; self.floor.len()];
+        let mut regions = Vec::new();
+        for start in 0..self.floor.len() {
+            if visited[start] || !self.floor[start] {
+                continue;
+            }
+            visited[start] = true;
+            let mut region = Vec::new();
+            let mut stack = vec![start];
+            while let Some(index) = stack.pop() {
+                let (x, y) = (index as u32 % self.width, index as u32 / self.width);
+                region.push((x, y));
+                let neighbours = [
+                    (x.wrapping_sub(1), y),
+                    (x + 1, y),
+                    (x, y.wrapping_sub(1)),
+                    (x, y + 1),
+                ];
+                for &(nx, ny) in &neighbours {
+                    let next = (ny.wrapping_mul(self.width).wrapping_add(nx)) as usize;
+                    if self.is_floor(nx, ny) && !visited[next] {
+                        visited[next] = true;
+                        stack.push(next);
+                    }
+                }
+            }
+            regions.push(region);
+        }
+        regions.sort_by(|a, b| b.len().cmp(&a.len()));
+        regions
+    }
+
+    /// Writes the layout into the grid, from its cell `(0, 0)` on. `None` empties the cells.
+    pub fn emit<G: TileGrid>(&self, grid: &mut G, floor: Option<G::Tile>, wall: Option<G::Tile>) {
+        let (width, height) = grid.size();
+        for y in 0..self.height.min(height) {
+            for x in 0..self.width.min(width) {
+                let tile = if self.is_floor(x, y) {
+                    floor.clone()
+                } else {
+                    wall.clone()
+                };
+                grid.set_tile(x, y, tile);
+            }
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use amethyst_core::RngStream;
+
+use crate::tile_editor::TileGrid;
+
+const F2: f32 = 0.366_025_4; // (sqrt(3) - 1) / 2
+const G2: f32 = 0.211_324_9; // (3 - sqrt(3)) / 6
+
+const GRADIENTS: [(f32, f32); 8] = [
+    (1., 1.),
+    (-1., 1.),
+    (1., -1.),
+    (-1., -1.),
+    (1., 0.),
+    (-1., 0.),
+    (0., 1.),
+    (0., -1.),
+];
+
+/// Kind of gradient noise.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum NoiseKind {
+    /// Classic Perlin noise, interpolated on a square lattice
+    Perlin,
+    /// Simplex noise, cheaper and with fewer directional artifacts than Perlin noise
+    Simplex,
+}
+
+/// Seeded two dimensional gradient noise.
+///
+/// The noise is implemented here, like the `Rng`, so that the terrain generated from a seed never
+/// changes with dependency updates. Values are roughly in `[-1, 1]` and vary smoothly over about
+/// one unit.
+#[derive(Clone, Debug)]
+pub struct Noise {
+    permutation: Vec<u8>,
+}
+
+impl Noise {
+    /// Creates the noise for a seed.
+    pub fn new(seed: u64) -> Self {
+        Noise::from_rng(&mut RngStream::new(seed))
+    }
+
+    /// Creates the noise from numbers drawn from the stream.
+    pub fn from_rng(rng: &mut RngStream) -> Self {
+        let mut table = [0u8; 256];
+        for (i, value) in table.iter_mut().enumerate() {
+            *value = i as u8;
+        }
+        rng.shuffle(&mut table);
+        Noise {
+            permutation: table.iter().chain(table.iter()).cloned().collect(),
+        }
+    }
+
+    /// Samples the noise at a point.
+    pub fn sample(&self, kind: NoiseKind, x: f32, y: f32) -> f32 {
+        match kind {
+            NoiseKind::Perlin => self.perlin(x, y),
+            NoiseKind::Simplex => self.simplex(x, y),
+        }
+    }
+
+    /// Samples fractal noise, made of `octaves` layers of noise of increasing frequency and
+    /// decreasing amplitude, normalized back to roughly `[-1, 1]`.
+    ///
+    /// Each octave multiplies the frequency by `lacunarity` and the amplitude by `gain`; 2 and
+    /// 0.5 are common choices.
+    pub fn fractal(
+        &self,
+        kind: NoiseKind,
+        x: f32,
+        y: f32,
+        octaves: u32,
+        lacunarity: f32,
+        gain: f32,
+    ) -> f32 {
+        let (mut sum, mut total, mut frequency, mut amplitude) = (0., 0., 1., 1.);
+        for _ in 0..octaves {
+            sum += amplitude * self.sample(kind, x * frequency, y * frequency);
+            total += amplitude;
+            frequency *= lacunarity;
+            amplitude *= gain;
+        }
+        if total > 0. {
+            sum / total
+        } else {
+            0.
+        }
+    }
+
+    /// Samples Perlin noise at a point.
+    pub fn perlin(&self, x: f32, y: f32) -> f32 {
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (ix, iy) = (x0 as i32 & 255, y0 as i32 & 255);
+        let corner = |dx: i32, dy: i32| {
+            let hash = self.hash(ix + dx, iy + dy);
+            let (gx, gy) = GRADIENTS[hash & 7];
+            gx * (fx - dx as f32) + gy * (fy - dy as f32)
+        };
+        let (u, v) = (fade(fx), fade(fy));
+        let bottom = lerp(corner(0, 0), corner(1, 0), u);
+        let top = lerp(corner(0, 1), corner(1, 1), u);
+        lerp(bottom, top, v)
+    }
+
+    /// Samples simplex noise at a point.
+    pub fn simplex(&self, x: f32, y: f32) -> f32 {
+        // Skew the input to find the simplex cell, a triangle, containing the point.
+        let s = (x + y) * F2;
+        let (i, j) = ((x + s).floor(), (y + s).floor());
+        let t = (i + j) * G2;
+        let (x0, y0) = (x - (i - t), y - (j - t));
+        let (i1, j1) = if x0 > y0 { (1, 0) } else { (0, 1) };
+        let corners = [
+            (0, 0, x0, y0),
+            (i1, j1, x0 - i1 as f32 + G2, y0 - j1 as f32 + G2),
+            (1, 1, x0 - 1. + 2. * G2, y0 - 1. + 2. * G2),
+        ];
+        let (ii, jj) = (i as i32 & 255, j as i32 & 255);
+        let sum: f32 = corners
+            .iter()
+            .map(|&(di, dj, cx, cy)| {
+                let falloff = 0.5 - cx * cx - cy * cy;
+                if falloff < 0. {
+                    0.
+                } else {
+                    let (gx, gy) = GRADIENTS[self.hash(ii + di, jj + dj) & 7];
+                    falloff.powi(4) * (gx * cx + gy * cy)
+                }
+            })
+            .sum();
+        70. * sum
+    }
+
+    /// Writes tiles chosen from the noise into the grid.
+    ///
+    /// Cell `(x, y)` samples the noise at `(x * scale, y * scale)`, and `tile` maps the sample to
+    /// the tile of the cell, e.g. water below zero and grass above.
+    pub fn emit<G, F>(&self, grid: &mut G, kind: NoiseKind, scale: f32, mut tile: F)
+    where
+        G: TileGrid,
+        F: FnMut(f32) -> Option<G::Tile>,
+    {
+        let (width, height) = grid.size();
+        for y in 0..height {
+            for x in 0..width {
+                let value = self.sample(kind, x as f32 * scale, y as f32 * scale);
+                grid.set_tile(x, y, tile(value));
+            }
+        }
+    }
+
+    fn hash(&self, x: i32, y: i32) -> usize {
+        let x = self.permutation[(x & 255) as usize] as i32;
+        self.permutation[((x + y) & 511) as usize] as usize
+    }
+}
+
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6. - 15.) + 10.)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn noise_is_seeded_and_bounded() {
+        let (a, b) = (Noise::new(3), Noise::new(3));
+        for i in 0..200 {
+            let (x, y) = (i as f32 * 0.37, i as f32 * 0.11 - 5.);
+            for &kind in &[NoiseKind::Perlin, NoiseKind::Simplex] {
+                let value = a.sample(kind, x, y);
+                assert!((value - b.sample(kind, x, y)).abs() < 1e-6);
+                assert!(value.abs() <= 1.1);
+            }
+        }
+        // Lattice points are zeros of Perlin noise.
+        assert!(a.perlin(4., -2.).abs() < 1e-6);
+    }
+}
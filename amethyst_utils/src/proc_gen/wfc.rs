@@ -0,0 +1,406 @@
+use std::collections::HashSet;
+
+use amethyst_core::RngStream;
+
+use crate::tile_editor::TileGrid;
+
+/// A side of a cell. Rows go up with `y`.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum Side {
+    /// Towards smaller `x`
+    Left,
+    /// Towards larger `x`
+    Right,
+    /// Towards smaller `y`
+    Down,
+    /// Towards larger `y`
+    Up,
+}
+
+impl Side {
+    /// All the sides.
+    pub const ALL: [Side; 4] = [Side::Left, Side::Right, Side::Down, Side::Up];
+
+    /// Returns the side facing this one.
+    pub fn opposite(self) -> Side {
+        match self {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+            Side::Down => Side::Up,
+            Side::Up => Side::Down,
+        }
+    }
+
+    /// Returns the neighbour of a cell on this side, if it lies in a grid of the given size.
+    pub fn neighbour(self, (x, y): (u32, u32), (width, height): (u32, u32)) -> Option<(u32, u32)> {
+        match self {
+            Side::Left if x > 0 => Some((x - 1, y)),
+            Side::Right if x + 1 < width => Some((x + 1, y)),
+            Side::Down if y > 0 => Some((x, y - 1)),
+            Side::Up if y + 1 < height => Some((x, y + 1)),
+            _ => None,
+        }
+    }
+}
+
+/// Tiles and the rules of which tiles may be next to each other, to fill grids with Wave Function
+/// Collapse.
+///
+/// Each cell starts out able to hold any tile. The cell with the fewest options is collapsed to
+/// one tile, picked by weight, and the options of its neighbours are narrowed down by the rules,
+/// until every cell holds a tile.
+///
+/// ```rust
+/// # extern crate amethyst_core;
+/// # extern crate amethyst_utils;
+/// use amethyst_core::RngStream;
+/// use amethyst_utils::proc_gen::{Side, WfcTileSet};
+///
+/// let mut tiles = WfcTileSet::new();
+/// let water = tiles.add_tile("water", 1.);
+/// let sand = tiles.add_tile("sand", 0.5);
+/// let grass = tiles.add_tile("grass", 1.);
+/// for &side in &Side::ALL {
+///     // Sand always lies between water and grass.
+///     tiles.allow(water, side, water).allow(water, side, sand);
+///     tiles.allow(sand, side, sand).allow(sand, side, grass);
+///     tiles.allow(grass, side, grass);
+/// }
+/// let cells = tiles.collapse((16, 16), &mut RngStream::new(5), 10).unwrap();
+/// assert_eq!(256, cells.len());
+/// ```
+#[derive(Clone, Debug)]
+pub struct WfcTileSet<T> {
+    tiles: Vec<T>,
+    weights: Vec<f32>,
+    rules: HashSet<(usize, Side, usize)>,
+}
+
+impl<T> Default for WfcTileSet<T> {
+    fn default() -> Self {
+        WfcTileSet {
+            tiles: Vec::new(),
+            weights: Vec::new(),
+            rules: HashSet::new(),
+        }
+    }
+}
+
+impl<T: Clone + PartialEq> WfcTileSet<T> {
+    /// Creates an empty tile set.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds a tile, returning its index. Tiles with larger weights are picked more often.
+    pub fn add_tile(&mut self, tile: T, weight: f32) -> usize {
+        self.tiles.push(tile);
+        self.weights.push(weight);
+        self.tiles.len() - 1
+    }
+
+    /// Allows tile `b` on the given side of tile `a`, and so `a` on the opposite side of `b`.
+    pub fn allow(&mut self, a: usize, side: Side, b: usize) -> &mut Self {
+        self.rules.insert((a, side, b));
+        self.rules.insert((b, side.opposite(), a));
+        self
+    }
+
+    /// Learns the tiles and rules from an example grid: every tile of the example is added,
+    /// weighted by how often it appears, and every pair of neighbouring tiles is allowed.
+    pub fn learn<G: TileGrid<Tile = T>>(&mut self, example: &G) {
+        let size = example.size();
+        for y in 0..size.1 {
+            for x in 0..size.0 {
+                let tile = match example.tile(x, y) {
+                    Some(tile) => tile,
+                    None => continue,
+                };
+                let a = match self.index(&tile) {
+                    Some(a) => {
+                        self.weights[a] += 1.;
+                        a
+                    }
+                    None => self.add_tile(tile, 1.),
+                };
+                for &side in &[Side::Right, Side::Up] {
+                    let other = side
+                        .neighbour((x, y), size)
+                        .and_then(|(nx, ny)| example.tile(nx, ny));
+                    if let Some(other) = other {
+                        let b = self
+                            .index(&other)
+                            .unwrap_or_else(|| self.add_tile(other, 0.));
+                        self.allow(a, side, b);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the tiles, by index.
+    pub fn tiles(&self) -> &[T] {
+        &self.tiles
+    }
+
+    /// Returns the index of the tile.
+    pub fn index(&self, tile: &T) -> Option<usize> {
+        self.tiles.iter().position(|t| t == tile)
+    }
+
+    /// Returns the indices of the tiles of a grid of the given size, row by row, or `None` if
+    /// every attempt ran into a cell no tile fits in.
+    pub fn collapse(
+        &self,
+        size: (u32, u32),
+        rng: &mut RngStream,
+        attempts: u32,
+    ) -> Option<Vec<usize>> {
+        self.solve(size, &vec![None; (size.0 * size.1) as usize], rng, attempts)
+    }
+
+    /// Fills the grid with tiles of the set.
+    ///
+    /// Cells that already hold a tile of the set keep it and constrain their neighbours, so parts
+    /// of a level can be drawn by hand and the rest generated around them. Returns `false`, leaving
+    /// the grid unchanged, if no tiling was found in the given number of attempts.
+    pub fn fill<G: TileGrid<Tile = T>>(
+        &self,
+        grid: &mut G,
+        rng: &mut RngStream,
+        attempts: u32,
+    ) -> bool {
+        let (width, height) = grid.size();
+        let mut fixed = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                fixed.push(grid.tile(x, y).and_then(|tile| self.index(&tile)));
+            }
+        }
+        match self.solve((width, height), &fixed, rng, attempts) {
+            Some(cells) => {
+                for (i, tile) in cells.into_iter().enumerate() {
+                    let (x, y) = (i as u32 % width, i as u32 / width);
+                    grid.set_tile(x, y, Some(self.tiles[tile].clone()));
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn solve(
+        &self,
+        size: (u32, u32),
+        fixed: &[Option<usize>],
+        rng: &mut RngStream,
+        attempts: u32,
+    ) -> Option<Vec<usize>> {
+        let count = self.tiles.len();
+        if count == 0 {
+            return None;
+        }
+        // compatible[a][side * count + b] is whether `b` may be on `side` of `a`.
+        let mut compatible = vec![vec![false; 4 * count]; count];
+        for &(a, side, b) in &self.rules {
+            if a < count && b < count {
+                compatible[a][side as usize * count + b] = true;
+            }
+        }
+
+        for _ in 0..attempts {
+            let mut wave = Wave::new(size, count);
+            let fixed_cells = fixed
+                .iter()
+                .enumerate()
+                .filter_map(|(cell, tile)| tile.map(|tile| (cell, tile)))
+                .collect::<Vec<_>>();
+            for &(cell, tile) in &fixed_cells {
+                wave.collapse(cell, tile);
+            }
+            let stack = fixed_cells.iter().map(|&(cell, _)| cell).collect();
+            if !wave.propagate(stack, &compatible) {
+                // The fixed cells contradict each other, so no attempt can succeed.
+                return None;
+            }
+            loop {
+                let cell = match wave.lowest_entropy(&self.weights, rng) {
+                    Some(cell) => cell,
+                    None => return Some(wave.result()),
+                };
+                let tile = wave.pick(cell, &self.weights, rng);
+                wave.collapse(cell, tile);
+                if !wave.propagate(vec![cell], &compatible) {
+                    break;
+                }
+            }
+        }
+        None
+    }
+}
+
+/// The tiles each cell may still hold.
+struct Wave {
+    size: (u32, u32),
+    count: usize,
+    possible: Vec<bool>,
+    options: Vec<usize>,
+}
+
+impl Wave {
+    fn new(size: (u32, u32), count: usize) -> Self {
+        let cells = (size.0 * size.1) as usize;
+        Wave {
+            size,
+            count,
+            possible: vec![true; cells * count],
+            options: vec![count; cells],
+        }
+    }
+
+    fn collapse(&mut self, cell: usize, tile: usize) {
+        for t in 0..self.count {
+            self.possible[cell * self.count + t] = t == tile;
+        }
+        self.options[cell] = 1;
+    }
+
+    /// Removes the options neighbours of the cells can't have, returning `false` if a cell has
+    /// none left.
+    fn propagate(&mut self, mut stack: Vec<usize>, compatible: &[Vec<bool>]) -> bool {
+        let (width, count) = (self.size.0 as usize, self.count);
+        while let Some(cell) = stack.pop() {
+            let position = ((cell % width) as u32, (cell / width) as u32);
+            for &side in &Side::ALL {
+                let (nx, ny) = match side.neighbour(position, self.size) {
+                    Some(neighbour) => neighbour,
+                    None => continue,
+                };
+                let neighbour = ny as usize * width + nx as usize;
+                let mut changed = false;
+                for b in 0..count {
+                    if !self.possible[neighbour * count + b] {
+                        continue;
+                    }
+                    let supported = (0..count).any(|a| {
+                        self.possible[cell * count + a] && compatible[a][side as usize * count + b]
+                    });
+                    if !supported {
+                        self.possible[neighbour * count + b] = false;
+                        self.options[neighbour] -= 1;
+                        changed = true;
+                    }
+                }
+                if self.options[neighbour] == 0 {
+                    return false;
+                }
+                if changed {
+                    stack.push(neighbour);
+                }
+            }
+        }
+        true
+    }
+
+    /// Returns the undecided cell with the lowest entropy, with random tie breaks.
+    fn lowest_entropy(&self, weights: &[f32], rng: &mut RngStream) -> Option<usize> {
+        let mut best = None;
+        let mut lowest = std::f32::INFINITY;
+        for cell in 0..self.options.len() {
+            if self.options[cell] <= 1 {
+                continue;
+            }
+            let (mut sum, mut sum_log) = (0., 0.);
+            for (t, &weight) in weights.iter().enumerate() {
+                if self.possible[cell * self.count + t] && weight > 0. {
+                    sum += weight;
+                    sum_log += weight * weight.ln();
+                }
+            }
+            let mut entropy = rng.next_f32() * 1e-3;
+            if sum > 0. {
+                entropy += sum.ln() - sum_log / sum;
+            }
+            if entropy < lowest {
+                lowest = entropy;
+                best = Some(cell);
+            }
+        }
+        best
+    }
+
+    /// Picks one of the options of the cell by weight.
+    fn pick(&self, cell: usize, weights: &[f32], rng: &mut RngStream) -> usize {
+        let options = (0..self.count)
+            .filter(|&t| self.possible[cell * self.count + t])
+            .collect::<Vec<_>>();
+        let total: f32 = options.iter().map(|&t| weights[t].max(0.)).sum();
+        if total <= 0. {
+            return options[rng.range_u32(0, options.len() as u32) as usize];
+        }
+        let mut target = rng.range_f32(0., total);
+        for &t in &options {
+            target -= weights[t].max(0.);
+            if target < 0. {
+                return t;
+            }
+        }
+        options[options.len() - 1]
+    }
+
+    fn result(&self) -> Vec<usize> {
+        (0..self.options.len())
+            .map(|cell| {
+                (0..self.count)
+                    .find(|&t| self.possible[cell * self.count + t])
+                    .expect("Unreachable: Every cell keeps at least one option")
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Grid(Vec<Option<char>>);
+
+    impl TileGrid for Grid {
+        type Tile = char;
+
+        fn size(&self) -> (u32, u32) {
+            (4, 4)
+        }
+
+        fn tile(&self, x: u32, y: u32) -> Option<char> {
+            self.0[(y * 4 + x) as usize]
+        }
+
+        fn set_tile(&mut self, x: u32, y: u32, tile: Option<char>) {
+            self.0[(y * 4 + x) as usize] = tile;
+        }
+    }
+
+    #[test]
+    fn fill_follows_rules_and_fixed_cells() {
+        let mut tiles = WfcTileSet::new();
+        let (black, white) = (tiles.add_tile('b', 1.), tiles.add_tile('w', 1.));
+        for &side in &Side::ALL {
+            tiles.allow(black, side, white);
+        }
+        let mut grid = Grid(vec![None; 16]);
+        grid.set_tile(1, 0, Some('w'));
+        assert!(tiles.fill(&mut grid, &mut RngStream::new(2), 1));
+        for y in 0..4 {
+            for x in 0..4 {
+                let expected = if (x + y) % 2 == 0 { 'b' } else { 'w' };
+                assert_eq!(Some(expected), grid.tile(x, y));
+            }
+        }
+
+        // A white cell next to another one can't be tiled.
+        grid.set_tile(0, 0, Some('w'));
+        assert!(!tiles.fill(&mut grid, &mut RngStream::new(2), 3));
+        assert_eq!(Some('w'), grid.tile(0, 0));
+    }
+}
@@ -0,0 +1,364 @@
+//! Projectiles like bullets, arrows and grenades.
+//!
+//! A `Projectile` flies with its velocity, pulled by gravity, until it hits something or its
+//! lifetime runs out. Instead of checking where it lands every frame, it sweeps the whole path of
+//! the frame against the scene with the `Raycast` resource of the game, so fast bullets don't go
+//! through thin walls between two frames. Projectiles with a radius sweep a sphere instead of
+//! a ray.
+//!
+//! Every surface hit sends a `ProjectileHit`, with the point, normal and surface of the hit to
+//! place decals and play sounds. Its `Penetration` rules decide whether the projectile goes
+//! through the surface, losing some speed, or stops there.
+
+use std::marker::PhantomData;
+
+use amethyst_assets::{PrefabData, PrefabError};
+use amethyst_core::{
+    nalgebra::{Point3, Vector3},
+    shrev::EventChannel,
+    specs::prelude::{
+        Component, DenseVecStorage, Entities, Entity, Join, Read, ReadExpect, System, Write,
+        WriteStorage,
+    },
+    timing::Time,
+    Transform,
+};
+
+use crate::raycast::{Raycast, RaycastHit};
+
+/// How far past a surface a projectile going through it restarts its sweep.
+const SKIN: f32 = 1e-3;
+
+/// The rules of a `Projectile` to go through the surfaces it hits.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct Penetration {
+    /// How many surfaces the projectile goes through before stopping.
+    pub max_surfaces: u32,
+    /// The fraction of its speed the projectile keeps through each surface.
+    pub speed_kept: f32,
+    /// The surfaces the projectile goes through, any when empty. Entities without a surface
+    /// stop it unless this is empty.
+    pub surfaces: Vec<String>,
+}
+
+impl Default for Penetration {
+    fn default() -> Self {
+        Penetration {
+            max_surfaces: 0,
+            speed_kept: 0.7,
+            surfaces: Vec::new(),
+        }
+    }
+}
+
+impl Penetration {
+    /// Returns whether the rules let a projectile through the surface.
+    pub fn allows(&self, surface: Option<&str>) -> bool {
+        self.surfaces.is_empty()
+            || surface.map_or(false, |surface| self.surfaces.iter().any(|s| s == surface))
+    }
+}
+
+/// A projectile, moved by the `ProjectileSystem`.
+///
+/// It's launched along the forward direction of its `Transform`, the negative `z` axis, unless
+/// its velocity is set. It moves its `Transform`, so it shouldn't have a `Parent`.
+#[derive(Clone, Debug, Deserialize, PartialEq, PrefabData, Serialize)]
+#[prefab(Component)]
+#[serde(default)]
+pub struct Projectile {
+    /// The speed of the projectile on launch.
+    pub speed: f32,
+    /// The acceleration of gravity on the projectile.
+    pub gravity: [f32; 3],
+    /// How long the projectile flies before it's deleted, in seconds.
+    pub lifetime: f32,
+    /// The radius of the sphere swept by the projectile, `0` to sweep a ray.
+    pub radius: f32,
+    /// The speed under which the projectile stops, e.g. after going through surfaces.
+    pub min_speed: f32,
+    /// The rules to go through surfaces.
+    pub penetration: Penetration,
+    /// The entity that shot the projectile, which it doesn't hit.
+    #[serde(skip)]
+    pub owner: Option<Entity>,
+    /// The velocity of the projectile, set on launch when it's `None`.
+    #[serde(skip)]
+    pub velocity: Option<Vector3<f32>>,
+    #[serde(skip)]
+    age: f32,
+    #[serde(skip)]
+    penetrated: u32,
+}
+
+impl Default for Projectile {
+    fn default() -> Self {
+        Projectile {
+            speed: 100.,
+            gravity: [0., -9.81, 0.],
+            lifetime: 5.,
+            radius: 0.,
+            min_speed: 1.,
+            penetration: Penetration::default(),
+            owner: None,
+            velocity: None,
+            age: 0.,
+            penetrated: 0,
+        }
+    }
+}
+
+impl Projectile {
+    /// Creates a projectile launched at the speed.
+    pub fn new(speed: f32) -> Self {
+        Projectile {
+            speed,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the acceleration of gravity on the projectile.
+    pub fn with_gravity(mut self, gravity: [f32; 3]) -> Self {
+        self.gravity = gravity;
+        self
+    }
+
+    /// Sets how long the projectile flies, in seconds.
+    pub fn with_lifetime(mut self, lifetime: f32) -> Self {
+        self.lifetime = lifetime;
+        self
+    }
+
+    /// Sets the radius of the sphere swept by the projectile.
+    pub fn with_radius(mut self, radius: f32) -> Self {
+        self.radius = radius;
+        self
+    }
+
+    /// Sets the rules to go through surfaces.
+    pub fn with_penetration(mut self, penetration: Penetration) -> Self {
+        self.penetration = penetration;
+        self
+    }
+
+    /// Sets the entity that shot the projectile, which it doesn't hit.
+    pub fn with_owner(mut self, owner: Entity) -> Self {
+        self.owner = Some(owner);
+        self
+    }
+
+    /// Launches the projectile at its speed along the direction, if it wasn't yet.
+    pub fn launch(&mut self, direction: &Vector3<f32>) {
+        if self.velocity.is_none() {
+            let direction = direction
+                .try_normalize(std::f32::EPSILON)
+                .unwrap_or_else(|| -Vector3::z());
+            self.velocity = Some(direction * self.speed);
+        }
+    }
+
+    /// Returns how long the projectile has been flying, in seconds.
+    pub fn age(&self) -> f32 {
+        self.age
+    }
+
+    /// Returns how many surfaces the projectile went through.
+    pub fn penetrated(&self) -> u32 {
+        self.penetrated
+    }
+
+    /// Moves the projectile from the position for the seconds, sweeping its path against the
+    /// scene and adding the hits to `hits`. Returns whether the projectile still flies.
+    pub fn step(
+        &mut self,
+        entity: Entity,
+        position: &mut Vector3<f32>,
+        scene: &dyn Raycast,
+        seconds: f32,
+        hits: &mut Vec<ProjectileHit>,
+    ) -> bool {
+        self.launch(&-Vector3::z());
+        let mut velocity =
+            self.velocity.unwrap_or_else(Vector3::zeros) + Vector3::from(self.gravity) * seconds;
+        self.age += seconds;
+        let mut alive = self.age < self.lifetime;
+
+        let mut origin = Point3::from(*position);
+        let mut remaining = velocity.norm() * seconds;
+        while remaining > 0. {
+            let direction = match velocity.try_normalize(std::f32::EPSILON) {
+                Some(direction) => direction,
+                None => break,
+            };
+            let hit = if self.radius > 0. {
+                scene.spherecast(&origin, &direction, self.radius, remaining)
+            } else {
+                scene.raycast(&origin, &direction, remaining)
+            };
+            let hit = match hit {
+                Some(hit) => hit,
+                None => {
+                    origin += direction * remaining;
+                    break;
+                }
+            };
+
+            let center = origin + direction * hit.distance;
+            remaining -= hit.distance + SKIN;
+            if hit.entity.is_some() && hit.entity == self.owner {
+                origin = center + direction * SKIN;
+                continue;
+            }
+
+            let penetrated = self.penetrated < self.penetration.max_surfaces
+                && self
+                    .penetration
+                    .allows(hit.surface.as_ref().map(String::as_str));
+            hits.push(ProjectileHit {
+                projectile: entity,
+                owner: self.owner,
+                hit,
+                velocity,
+                penetrated,
+            });
+            if !penetrated {
+                origin = center;
+                alive = false;
+                break;
+            }
+            self.penetrated += 1;
+            velocity *= self.penetration.speed_kept;
+            remaining *= self.penetration.speed_kept;
+            origin = center + direction * SKIN;
+            if velocity.norm() < self.min_speed {
+                alive = false;
+                break;
+            }
+        }
+
+        *position = origin.coords;
+        self.velocity = Some(velocity);
+        alive
+    }
+}
+
+impl Component for Projectile {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Event sent by the `ProjectileSystem` on an `EventChannel<ProjectileHit>` when a projectile
+/// hits a surface.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProjectileHit {
+    /// The projectile.
+    pub projectile: Entity,
+    /// The entity that shot the projectile.
+    pub owner: Option<Entity>,
+    /// Where the projectile hit, and what.
+    pub hit: RaycastHit,
+    /// The velocity of the projectile on the hit.
+    pub velocity: Vector3<f32>,
+    /// Whether the projectile went through the surface.
+    pub penetrated: bool,
+}
+
+/// Moves the `Projectile`s, sweeping their paths with the `Raycast` resource `R`, sends
+/// `ProjectileHit`s and deletes the projectiles that stopped or ran out of lifetime.
+pub struct ProjectileSystem<R> {
+    hits: Vec<ProjectileHit>,
+    marker: PhantomData<R>,
+}
+
+impl<R> ProjectileSystem<R> {
+    /// Creates the system.
+    pub fn new() -> Self {
+        ProjectileSystem {
+            hits: Vec::new(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<R> Default for ProjectileSystem<R> {
+    fn default() -> Self {
+        ProjectileSystem::new()
+    }
+}
+
+impl<'a, R: Raycast> System<'a> for ProjectileSystem<R> {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, Time>,
+        ReadExpect<'a, R>,
+        WriteStorage<'a, Transform>,
+        WriteStorage<'a, Projectile>,
+        Write<'a, EventChannel<ProjectileHit>>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, time, scene, mut transforms, mut projectiles, mut channel): Self::SystemData,
+    ) {
+        for (entity, transform, projectile) in
+            (&*entities, &mut transforms, &mut projectiles).join()
+        {
+            projectile.launch(&(transform.rotation() * -Vector3::z()));
+            let alive = projectile.step(
+                entity,
+                transform.translation_mut(),
+                &*scene,
+                time.delta_seconds(),
+                &mut self.hits,
+            );
+            if !alive {
+                if let Err(err) = entities.delete(entity) {
+                    error!("Failed to delete entity: {:?}", err);
+                }
+            }
+        }
+        channel.iter_write(self.hits.drain(..));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use amethyst_core::specs::prelude::{Builder, World};
+
+    use crate::raycast::FlatGround;
+
+    #[test]
+    fn fast_projectiles_hit_and_go_through_surfaces() {
+        let mut world = World::new();
+        let entity = world.create_entity().build();
+        let ground = FlatGround::default();
+        let mut hits = Vec::new();
+
+        // Falls 100 below in a single step, but stops on the ground.
+        let mut bullet = Projectile::new(100.).with_gravity([0.; 3]);
+        bullet.launch(&-Vector3::y());
+        let mut position = Vector3::new(0., 10., 0.);
+        assert!(!bullet.step(entity, &mut position, &ground, 1., &mut hits));
+        assert!(position.norm() < 1e-3);
+        assert_eq!(hits.len(), 1);
+        assert!(!hits[0].penetrated);
+
+        hits.clear();
+        let mut bullet = Projectile::new(100.)
+            .with_gravity([0.; 3])
+            .with_penetration(Penetration {
+                max_surfaces: 1,
+                speed_kept: 0.5,
+                ..Default::default()
+            });
+        bullet.launch(&-Vector3::y());
+        let mut position = Vector3::new(0., 10., 0.);
+        assert!(bullet.step(entity, &mut position, &ground, 1., &mut hits));
+        assert!(hits[0].penetrated);
+        // 10 to the ground, then half of the other 90 below it.
+        assert!((position.y - -45.).abs() < 1e-2);
+        assert!((bullet.velocity.unwrap().norm() - 50.).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,228 @@
+//! Smooth transitions of cameras between perspective and orthographic projections.
+
+use amethyst_assets::{PrefabData, PrefabError};
+use amethyst_core::{
+    nalgebra::{Matrix4, Orthographic3, Perspective3},
+    specs::{Component, DenseVecStorage, Entity, Join, Read, ReadExpect, System, WriteStorage},
+    timing::Time,
+};
+use amethyst_renderer::{Camera, ScreenDimensions};
+
+/// Returns the projection matrix blended between a perspective projection, at `t = 0`, and an
+/// orthographic projection, at `t = 1`.
+///
+/// The orthographic projection is sized so that objects at `focus_distance` in front of the camera
+/// have the same size on screen in both projections, and they keep that size during the whole
+/// blend.
+///
+/// `fovy` is the vertical field of view of the perspective projection, in radians.
+pub fn blend_projection(
+    aspect: f32,
+    fovy: f32,
+    focus_distance: f32,
+    znear: f32,
+    zfar: f32,
+    t: f32,
+) -> Matrix4<f32> {
+    let half_height = focus_distance * (fovy / 2.).tan();
+    let half_width = half_height * aspect;
+    let perspective = Perspective3::new(aspect, fovy, znear, zfar).to_homogeneous();
+    let orthographic = Orthographic3::new(
+        -half_width,
+        half_width,
+        -half_height,
+        half_height,
+        znear,
+        zfar,
+    )
+    .to_homogeneous();
+    // Blending the matrices element-wise also blends `w` between the depth and 1, which keeps
+    // the focus plane at the same size.
+    let t = t.max(0.).min(1.);
+    perspective * (1. - t) + orthographic * t
+}
+
+/// Component that blends the projection of the camera on the same entity between perspective and
+/// orthographic.
+///
+/// Call `set_orthographic` to start a transition. The `ProjectionBlendSystem` then updates the
+/// `Camera` each frame, so don't combine this component with `AutoFov` or `CameraOrtho`.
+///
+/// # Examples
+///
+/// ```rust
+/// # extern crate amethyst_utils;
+/// use amethyst_utils::projection_blend::ProjectionBlend;
+///
+/// // Starts in perspective, and keeps things 10 units away at the same size when switching.
+/// let mut blend = ProjectionBlend::new(std::f32::consts::FRAC_PI_3, 10.);
+/// blend.set_orthographic(true);
+/// assert!(blend.is_orthographic());
+/// ```
+#[derive(Clone, Debug, Deserialize, PartialEq, PrefabData, Serialize)]
+#[prefab(Component)]
+#[serde(default)]
+pub struct ProjectionBlend {
+    /// Vertical field of view of the perspective projection, in radians.
+    pub fovy: f32,
+    /// Distance from the camera at which both projections show objects at the same size.
+    pub focus_distance: f32,
+    /// Distance of the near clipping plane.
+    pub znear: f32,
+    /// Distance of the far clipping plane.
+    pub zfar: f32,
+    /// Duration of a transition in seconds.
+    pub duration: f32,
+    orthographic: bool,
+    #[serde(skip)]
+    blend: Option<f32>,
+}
+
+impl Default for ProjectionBlend {
+    fn default() -> Self {
+        ProjectionBlend::new(std::f32::consts::FRAC_PI_3, 10.)
+    }
+}
+
+impl ProjectionBlend {
+    /// Creates a blend that starts in perspective, with transitions of half a second.
+    pub fn new(fovy: f32, focus_distance: f32) -> Self {
+        ProjectionBlend {
+            fovy,
+            focus_distance,
+            znear: 0.1,
+            zfar: 2000.,
+            duration: 0.5,
+            orthographic: false,
+            blend: None,
+        }
+    }
+
+    /// Sets the duration of transitions in seconds.
+    pub fn with_duration(mut self, duration: f32) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Returns whether the camera is, or is transitioning to, orthographic.
+    pub fn is_orthographic(&self) -> bool {
+        self.orthographic
+    }
+
+    /// Starts a transition to the orthographic projection if `true`, or to the perspective
+    /// projection if `false`.
+    pub fn set_orthographic(&mut self, orthographic: bool) {
+        // Starts from the current projection, which isn't stored before the first transition.
+        self.blend = Some(self.blend());
+        self.orthographic = orthographic;
+    }
+
+    /// Switches to the other projection without a transition.
+    pub fn snap_orthographic(&mut self, orthographic: bool) {
+        self.orthographic = orthographic;
+        self.blend = Some(self.target());
+    }
+
+    /// Returns the current blend factor, 0 being perspective and 1 orthographic.
+    pub fn blend(&self) -> f32 {
+        self.blend.unwrap_or_else(|| self.target())
+    }
+
+    /// Returns whether a transition is in progress.
+    #[cfg_attr(feature = "cargo-clippy", allow(float_cmp))] // the blend is clamped to the target
+    pub fn is_transitioning(&self) -> bool {
+        self.blend() != self.target()
+    }
+
+    fn target(&self) -> f32 {
+        if self.orthographic {
+            1.
+        } else {
+            0.
+        }
+    }
+
+    fn advance(&mut self, delta_seconds: f32) -> f32 {
+        let target = self.target();
+        let step = if self.duration > 0. {
+            delta_seconds / self.duration
+        } else {
+            1.
+        };
+        let blend = self.blend();
+        let blend = if blend < target {
+            (blend + step).min(target)
+        } else {
+            (blend - step).max(target)
+        };
+        self.blend = Some(blend);
+        blend
+    }
+}
+
+impl Component for ProjectionBlend {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// System that updates the projection of cameras with a `ProjectionBlend`.
+///
+/// Transitions use real time, so they keep running while the game is paused.
+#[derive(Default)]
+pub struct ProjectionBlendSystem;
+
+impl<'a> System<'a> for ProjectionBlendSystem {
+    type SystemData = (
+        Read<'a, Time>,
+        ReadExpect<'a, ScreenDimensions>,
+        WriteStorage<'a, Camera>,
+        WriteStorage<'a, ProjectionBlend>,
+    );
+
+    fn run(&mut self, (time, dimensions, mut cameras, mut blends): Self::SystemData) {
+        let aspect = dimensions.aspect_ratio();
+        for (camera, blend) in (&mut cameras, &mut blends).join() {
+            let t = blend.advance(time.delta_real_seconds());
+            // Smoothstep, so the transition eases in and out.
+            let t = t * t * (3. - 2. * t);
+            camera.proj = blend_projection(
+                aspect,
+                blend.fovy,
+                blend.focus_distance,
+                blend.znear,
+                blend.zfar,
+                t,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_core::nalgebra::Vector4;
+
+    use super::*;
+
+    #[test]
+    fn focus_plane_keeps_its_size() {
+        let point = Vector4::new(2., 1., -10., 1.);
+        let project = |t| {
+            let clip = blend_projection(1.5, 1., 10., 0.1, 100., t) * point;
+            (clip.x / clip.w, clip.y / clip.w)
+        };
+        let (x, y) = project(0.);
+        for &t in &[0.25, 0.5, 1.] {
+            let (bx, by) = project(t);
+            assert!((bx - x).abs() < 1.0e-5 && (by - y).abs() < 1.0e-5);
+        }
+    }
+
+    #[test]
+    fn transition_reaches_target() {
+        let mut blend = ProjectionBlend::default().with_duration(1.);
+        blend.set_orthographic(true);
+        assert!(blend.is_transitioning());
+        assert!((blend.advance(0.5) - 0.5).abs() < 1.0e-5);
+        assert!((blend.advance(1.) - 1.).abs() < 1.0e-5);
+        assert!(!blend.is_transitioning());
+    }
+}
@@ -0,0 +1,536 @@
+//! Quests and their objectives.
+//!
+//! A `QuestSet` is an asset, usually loaded from a RON file with `RonFormat`, defining the quests
+//! of the game: their objectives, the quests to complete before and the conditions on the
+//! [`Blackboard`](../blackboard/struct.Blackboard.html) to meet before they can start, and their
+//! rewards. The `QuestLog` resource tracks the progress of the player through them, and the
+//! `QuestSystem` sends a `QuestEvent` whenever it changes.
+//!
+//! ```ron
+//! (
+//!     quests: {
+//!         "wolves": (
+//!             title: "quest-wolves",
+//!             description: "quest-wolves-description",
+//!             prerequisites: ["meet_hunter"],
+//!             auto_start: true,
+//!             objectives: [
+//!                 (id: "hunt", description: "objective-hunt", kind: Counter(5)),
+//!                 (
+//!                     id: "report",
+//!                     description: "objective-report",
+//!                     kind: Condition((
+//!                         variable: "talked_to_hunter",
+//!                         comparison: Equal,
+//!                         value: Bool(true),
+//!                     )),
+//!                 ),
+//!             ],
+//!             rewards: ["wolf_pelt"],
+//!             effects: [Add(variable: "gold", amount: 50.0)],
+//!         ),
+//!     },
+//! )
+//! ```
+
+use std::collections::HashMap;
+
+use amethyst_assets::{Asset, AssetStorage, Handle, ProcessingState, Processor, Result};
+use amethyst_core::{
+    bundle::{Result as BundleResult, SystemBundle},
+    shrev::EventChannel,
+    specs::prelude::{DispatcherBuilder, Read, System, VecStorage, Write},
+};
+
+use crate::blackboard::{Blackboard, Condition, Effect};
+
+/// How an objective is reached.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum ObjectiveKind {
+    /// Reached once progressed by that many, e.g. 5 wolves hunted.
+    Counter(u32),
+    /// Reached once completed with `QuestLog::complete_objective`.
+    Flag,
+    /// Reached once the condition holds on the `Blackboard`.
+    Condition(Condition),
+}
+
+impl ObjectiveKind {
+    /// Returns the progress reaching the objective.
+    pub fn required(&self) -> u32 {
+        match *self {
+            ObjectiveKind::Counter(count) => count,
+            ObjectiveKind::Flag | ObjectiveKind::Condition(_) => 1,
+        }
+    }
+}
+
+/// An objective of a `QuestDefinition`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ObjectiveDefinition {
+    /// Identifies the objective in its quest.
+    pub id: String,
+    /// Describes the objective, or its key in the locale.
+    #[serde(default)]
+    pub description: String,
+    /// How the objective is reached.
+    pub kind: ObjectiveKind,
+}
+
+/// A quest of a `QuestSet`.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct QuestDefinition {
+    /// The title of the quest, or its key in the locale.
+    pub title: String,
+    /// Describes the quest, or its key in the locale.
+    pub description: String,
+    /// The quests to complete before this one can start.
+    pub prerequisites: Vec<String>,
+    /// The quest can only start while all conditions hold.
+    pub conditions: Vec<Condition>,
+    /// Whether the quest starts by itself as soon as it can, instead of with `QuestLog::start`.
+    pub auto_start: bool,
+    /// The quest is completed once all objectives are reached.
+    pub objectives: Vec<ObjectiveDefinition>,
+    /// Names of the `QuestEvent::Reward`s sent when the quest is completed.
+    pub rewards: Vec<String>,
+    /// Applied to the `Blackboard` when the quest is completed.
+    pub effects: Vec<Effect>,
+}
+
+impl QuestDefinition {
+    /// Returns the objective.
+    pub fn objective(&self, id: &str) -> Option<&ObjectiveDefinition> {
+        self.objectives.iter().find(|objective| objective.id == id)
+    }
+}
+
+/// Asset with the quests of the game by id, see the [module documentation](index.html).
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct QuestSet {
+    /// The quests by id.
+    pub quests: HashMap<String, QuestDefinition>,
+}
+
+impl Asset for QuestSet {
+    const NAME: &'static str = "utils::QuestSet";
+    type Data = Self;
+    type HandleStorage = VecStorage<Handle<Self>>;
+}
+
+impl Into<Result<ProcessingState<QuestSet>>> for QuestSet {
+    fn into(self) -> Result<ProcessingState<QuestSet>> {
+        Ok(ProcessingState::Loaded(self))
+    }
+}
+
+/// Where the player is with a quest.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum QuestState {
+    /// Started and not completed yet.
+    Active,
+    /// All objectives were reached.
+    Completed,
+    /// Failed with `QuestLog::fail`.
+    Failed,
+}
+
+/// The progress of the player through a quest.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct QuestProgress {
+    /// Where the player is with the quest.
+    pub state: QuestState,
+    /// The progress through the objectives by id, missing ones having none.
+    pub objectives: HashMap<String, u32>,
+}
+
+impl QuestProgress {
+    /// Returns the progress through the objective.
+    pub fn objective(&self, id: &str) -> u32 {
+        self.objectives.get(id).cloned().unwrap_or(0)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum QuestCommand {
+    Start(String),
+    Progress(String, String, u32),
+    Fail(String),
+}
+
+/// Resource tracking the progress of the player through the quests of a `QuestSet`.
+///
+/// Changes are applied by the `QuestSystem`, which checks them against the definitions of the
+/// quests and sends the `QuestEvent`s. Only the progress is serialized, so the log is saved and
+/// loaded with the rest of the game and keeps working when the `QuestSet` changes, e.g. with an
+/// update of the game; set `quests` again after loading it.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct QuestLog {
+    /// The quests tracked.
+    #[serde(skip)]
+    pub quests: Option<Handle<QuestSet>>,
+    progress: HashMap<String, QuestProgress>,
+    #[serde(skip)]
+    commands: Vec<QuestCommand>,
+}
+
+impl QuestLog {
+    /// Creates an empty log tracking the quests.
+    pub fn new(quests: Handle<QuestSet>) -> Self {
+        QuestLog {
+            quests: Some(quests),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the progress through the quest, `None` if it didn't start.
+    pub fn quest(&self, quest: &str) -> Option<&QuestProgress> {
+        self.progress.get(quest)
+    }
+
+    /// Returns where the player is with the quest, `None` if it didn't start.
+    pub fn state(&self, quest: &str) -> Option<QuestState> {
+        self.quest(quest).map(|progress| progress.state)
+    }
+
+    /// Returns whether the quest is completed.
+    pub fn is_completed(&self, quest: &str) -> bool {
+        self.state(quest) == Some(QuestState::Completed)
+    }
+
+    /// Iterates over the quests started, with their progress.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &QuestProgress)> {
+        self.progress
+            .iter()
+            .map(|(quest, progress)| (quest.as_str(), progress))
+    }
+
+    /// Starts the quest, if its prerequisites are completed and its conditions hold.
+    pub fn start<S: Into<String>>(&mut self, quest: S) {
+        self.commands.push(QuestCommand::Start(quest.into()));
+    }
+
+    /// Progresses an objective of an active quest by the amount.
+    pub fn progress<Q, O>(&mut self, quest: Q, objective: O, amount: u32)
+    where
+        Q: Into<String>,
+        O: Into<String>,
+    {
+        self.commands.push(QuestCommand::Progress(
+            quest.into(),
+            objective.into(),
+            amount,
+        ));
+    }
+
+    /// Reaches an objective of an active quest.
+    pub fn complete_objective<Q, O>(&mut self, quest: Q, objective: O)
+    where
+        Q: Into<String>,
+        O: Into<String>,
+    {
+        self.progress(quest, objective, u32::max_value());
+    }
+
+    /// Fails an active quest.
+    pub fn fail<S: Into<String>>(&mut self, quest: S) {
+        self.commands.push(QuestCommand::Fail(quest.into()));
+    }
+
+    /// Returns whether the quest can start.
+    fn is_available(&self, set: &QuestSet, blackboard: &Blackboard, quest: &str) -> bool {
+        !self.progress.contains_key(quest)
+            && set.quests.get(quest).map_or(false, |definition| {
+                definition
+                    .prerequisites
+                    .iter()
+                    .all(|prerequisite| self.is_completed(prerequisite))
+                    && definition.conditions.iter().all(|c| c.check(blackboard))
+            })
+    }
+
+    fn begin(&mut self, quest: &str, events: &mut Vec<QuestEvent>) {
+        self.progress.insert(
+            quest.to_string(),
+            QuestProgress {
+                state: QuestState::Active,
+                objectives: HashMap::new(),
+            },
+        );
+        events.push(QuestEvent::Started {
+            quest: quest.to_string(),
+        });
+    }
+
+    fn advance(
+        &mut self,
+        set: &QuestSet,
+        quest: &str,
+        objective: &str,
+        amount: u32,
+        events: &mut Vec<QuestEvent>,
+    ) {
+        let required = match set.quests.get(quest).and_then(|q| q.objective(objective)) {
+            Some(definition) => definition.kind.required(),
+            None => {
+                warn!("Quest {} has no objective {}", quest, objective);
+                return;
+            }
+        };
+        let progress = match self.progress.get_mut(quest) {
+            Some(progress) if progress.state == QuestState::Active => progress,
+            _ => return,
+        };
+        let count = progress
+            .objectives
+            .entry(objective.to_string())
+            .or_insert(0);
+        if *count >= required {
+            return;
+        }
+        *count = count.saturating_add(amount).min(required);
+        events.push(QuestEvent::Progressed {
+            quest: quest.to_string(),
+            objective: objective.to_string(),
+            count: *count,
+            required,
+        });
+    }
+
+    /// Applies the changes, starts the quests starting by themselves, and completes the quests
+    /// with all objectives reached.
+    fn update(
+        &mut self,
+        set: &QuestSet,
+        blackboard: &mut Blackboard,
+        events: &mut Vec<QuestEvent>,
+    ) {
+        for command in std::mem::replace(&mut self.commands, Vec::new()) {
+            match command {
+                QuestCommand::Start(quest) => {
+                    if self.is_available(set, blackboard, &quest) {
+                        self.begin(&quest, events);
+                    } else {
+                        warn!("Quest {} can't start", quest);
+                    }
+                }
+                QuestCommand::Progress(quest, objective, amount) => {
+                    self.advance(set, &quest, &objective, amount, events)
+                }
+                QuestCommand::Fail(quest) => match self.progress.get_mut(&quest) {
+                    Some(progress) if progress.state == QuestState::Active => {
+                        progress.state = QuestState::Failed;
+                        events.push(QuestEvent::Failed { quest });
+                    }
+                    _ => warn!("Quest {} isn't active and can't fail", quest),
+                },
+            }
+        }
+
+        // Sorted, so events are sent in the same order every time.
+        let mut waiting = set
+            .quests
+            .iter()
+            .filter(|(_, definition)| definition.auto_start)
+            .map(|(quest, _)| quest.as_str())
+            .collect::<Vec<_>>();
+        waiting.sort();
+        for quest in waiting {
+            if self.is_available(set, blackboard, quest) {
+                self.begin(quest, events);
+            }
+        }
+
+        let mut active = self
+            .progress
+            .iter()
+            .filter(|(_, progress)| progress.state == QuestState::Active)
+            .map(|(quest, _)| quest.clone())
+            .collect::<Vec<_>>();
+        active.sort();
+        for quest in active {
+            let definition = match set.quests.get(&quest) {
+                Some(definition) => definition,
+                None => continue,
+            };
+            for objective in &definition.objectives {
+                if let ObjectiveKind::Condition(ref condition) = objective.kind {
+                    if condition.check(blackboard) {
+                        self.advance(set, &quest, &objective.id, 1, events);
+                    }
+                }
+            }
+
+            let progress = self.progress.get_mut(&quest).unwrap();
+            let reached = definition
+                .objectives
+                .iter()
+                .all(|objective| progress.objective(&objective.id) >= objective.kind.required());
+            if reached {
+                progress.state = QuestState::Completed;
+                for effect in &definition.effects {
+                    effect.apply(blackboard);
+                }
+                events.push(QuestEvent::Completed {
+                    quest: quest.clone(),
+                });
+                for reward in &definition.rewards {
+                    events.push(QuestEvent::Reward {
+                        quest: quest.clone(),
+                        name: reward.clone(),
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Event sent by the `QuestSystem` on an `EventChannel<QuestEvent>`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum QuestEvent {
+    /// The quest started.
+    Started {
+        /// Id of the quest.
+        quest: String,
+    },
+    /// An objective progressed, being reached once `count` is `required`.
+    Progressed {
+        /// Id of the quest.
+        quest: String,
+        /// Id of the objective.
+        objective: String,
+        /// The progress through the objective.
+        count: u32,
+        /// The progress reaching the objective.
+        required: u32,
+    },
+    /// All objectives of the quest were reached.
+    Completed {
+        /// Id of the quest.
+        quest: String,
+    },
+    /// The quest failed.
+    Failed {
+        /// Id of the quest.
+        quest: String,
+    },
+    /// A reward of a completed quest, for the game to hand out.
+    Reward {
+        /// Id of the quest.
+        quest: String,
+        /// Name of the reward.
+        name: String,
+    },
+}
+
+/// Updates the `QuestLog` once its `QuestSet` is loaded, sending `QuestEvent`s.
+#[derive(Default)]
+pub struct QuestSystem;
+
+impl<'a> System<'a> for QuestSystem {
+    type SystemData = (
+        Read<'a, AssetStorage<QuestSet>>,
+        Write<'a, QuestLog>,
+        Write<'a, Blackboard>,
+        Write<'a, EventChannel<QuestEvent>>,
+    );
+
+    fn run(&mut self, (sets, mut log, mut blackboard, mut channel): Self::SystemData) {
+        let set = match log.quests.as_ref().and_then(|quests| sets.get(quests)) {
+            Some(set) => set,
+            None => return,
+        };
+        let mut events = Vec::new();
+        log.update(set, &mut blackboard, &mut events);
+        channel.iter_write(events);
+    }
+}
+
+/// Adds the `Processor` for `QuestSet` assets and the `QuestSystem`.
+#[derive(Default)]
+pub struct QuestBundle;
+
+impl<'a, 'b> SystemBundle<'a, 'b> for QuestBundle {
+    fn build(self, builder: &mut DispatcherBuilder<'a, 'b>) -> BundleResult<()> {
+        builder.add(Processor::<QuestSet>::new(), "quest_processor", &[]);
+        builder.add(QuestSystem, "quest_system", &["quest_processor"]);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::blackboard::Comparison;
+
+    fn objective(id: &str, kind: ObjectiveKind) -> ObjectiveDefinition {
+        ObjectiveDefinition {
+            id: id.to_string(),
+            description: String::new(),
+            kind,
+        }
+    }
+
+    #[test]
+    fn quests_start_after_prerequisites_and_complete_with_objectives() {
+        let mut set = QuestSet::default();
+        set.quests.insert(
+            "meet".to_string(),
+            QuestDefinition {
+                objectives: vec![objective("talk", ObjectiveKind::Flag)],
+                ..Default::default()
+            },
+        );
+        set.quests.insert(
+            "wolves".to_string(),
+            QuestDefinition {
+                prerequisites: vec!["meet".to_string()],
+                auto_start: true,
+                objectives: vec![
+                    objective("hunt", ObjectiveKind::Counter(2)),
+                    objective(
+                        "report",
+                        ObjectiveKind::Condition(Condition::new(
+                            "reported",
+                            Comparison::Equal,
+                            true,
+                        )),
+                    ),
+                ],
+                rewards: vec!["pelt".to_string()],
+                ..Default::default()
+            },
+        );
+
+        let mut log = QuestLog::default();
+        let mut blackboard = Blackboard::new();
+        let mut events = Vec::new();
+        log.start("wolves");
+        log.start("meet");
+        log.update(&set, &mut blackboard, &mut events);
+        assert_eq!(None, log.state("wolves"));
+        assert_eq!(Some(QuestState::Active), log.state("meet"));
+
+        log.complete_objective("meet", "talk");
+        log.update(&set, &mut blackboard, &mut events);
+        assert!(log.is_completed("meet"));
+        log.update(&set, &mut blackboard, &mut events);
+        assert_eq!(Some(QuestState::Active), log.state("wolves"));
+
+        log.progress("wolves", "hunt", 5);
+        blackboard.set("reported", true);
+        events.clear();
+        log.update(&set, &mut blackboard, &mut events);
+        assert!(log.is_completed("wolves"));
+        assert_eq!(2, log.quest("wolves").unwrap().objective("hunt"));
+        assert_eq!(
+            Some(&QuestEvent::Reward {
+                quest: "wolves".to_string(),
+                name: "pelt".to_string(),
+            }),
+            events.last()
+        );
+    }
+}
@@ -0,0 +1,96 @@
+//! Ray and sphere casts against the scene, for the helpers needing them, like vehicles and
+//! projectiles.
+//!
+//! The engine has no physics, so the game implements `Raycast` for a resource querying its
+//! physics engine, and gives the type of the resource to the systems casting rays.
+
+use amethyst_core::{
+    nalgebra::{Point3, Vector3},
+    specs::prelude::Entity,
+};
+
+/// Where a ray hit the scene.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RaycastHit {
+    /// The distance along the ray.
+    pub distance: f32,
+    /// The point hit, in world space.
+    pub point: Point3<f32>,
+    /// The normal of the surface at the point.
+    pub normal: Vector3<f32>,
+    /// The entity hit, if it's one.
+    pub entity: Option<Entity>,
+    /// The surface hit, e.g. `"metal"` to pick decals and sounds, if the scene has them.
+    pub surface: Option<String>,
+}
+
+/// Resource casting rays against the scene.
+pub trait Raycast: Send + Sync + 'static {
+    /// Returns the first hit along the ray within the distance, the direction being normalized.
+    fn raycast(
+        &self,
+        origin: &Point3<f32>,
+        direction: &Vector3<f32>,
+        max_distance: f32,
+    ) -> Option<RaycastHit>;
+
+    /// Returns the first hit of a sphere swept along the ray within the distance, the distance
+    /// of the hit being the one of the center of the sphere.
+    ///
+    /// The default casts a ray from the center, for scenes without swept shapes.
+    fn spherecast(
+        &self,
+        origin: &Point3<f32>,
+        direction: &Vector3<f32>,
+        _radius: f32,
+        max_distance: f32,
+    ) -> Option<RaycastHit> {
+        self.raycast(origin, direction, max_distance)
+    }
+}
+
+/// A flat, infinite ground at `y = height` with `y` up, e.g. for tests and prototypes.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FlatGround {
+    /// The height of the ground.
+    pub height: f32,
+}
+
+impl Raycast for FlatGround {
+    fn raycast(
+        &self,
+        origin: &Point3<f32>,
+        direction: &Vector3<f32>,
+        max_distance: f32,
+    ) -> Option<RaycastHit> {
+        if direction.y >= 0. || origin.y < self.height {
+            return None;
+        }
+        let distance = (origin.y - self.height) / -direction.y;
+        if distance > max_distance {
+            return None;
+        }
+        Some(RaycastHit {
+            distance,
+            point: origin + direction * distance,
+            normal: Vector3::y(),
+            entity: None,
+            surface: None,
+        })
+    }
+
+    fn spherecast(
+        &self,
+        origin: &Point3<f32>,
+        direction: &Vector3<f32>,
+        radius: f32,
+        max_distance: f32,
+    ) -> Option<RaycastHit> {
+        let raised = FlatGround {
+            height: self.height + radius,
+        };
+        let mut hit = raised.raycast(origin, direction, max_distance)?;
+        hit.point.y = self.height;
+        Some(hit)
+    }
+}
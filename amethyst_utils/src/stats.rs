@@ -0,0 +1,336 @@
+//! Stats like health or strength, changed by modifiers.
+//!
+//! The value of a stat is computed from its base value, the stats it derives from and its
+//! modifiers, always in the same order:
+//!
+//! ```text
+//! value = (base + derived + flat) * (1 + percent) * multipliers
+//! ```
+//!
+//! where `derived` sums the values of the other stats times their factors, `flat` sums the
+//! `Flat` modifiers, `percent` sums the `Percent` modifiers and `multipliers` multiplies the
+//! `Multiply` modifiers. The value is then clamped between the minimum and maximum of the stat.
+
+use std::collections::HashMap;
+
+use amethyst_core::{
+    shrev::EventChannel,
+    specs::prelude::{
+        Component, DenseVecStorage, Entities, Entity, Join, Read, System, Write, WriteStorage,
+    },
+    timing::Time,
+};
+
+/// How deep stats derive from stats deriving from other stats; deeper ones, e.g. in a cycle, are
+/// left out.
+const MAX_DEPTH: usize = 8;
+
+/// How a `Modifier` changes a stat.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum ModifierKind {
+    /// Adds the amount to the base value.
+    Flat(f32),
+    /// Adds the fraction of the value, e.g. 0.1 for 10% more. Summed with the other
+    /// percentages before being applied.
+    Percent(f32),
+    /// Multiplies the value by the factor, after the percentages.
+    Multiply(f32),
+}
+
+/// A change of a stat, e.g. from an item or a spell.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Modifier {
+    /// Name of the stat changed.
+    pub stat: String,
+    /// How the stat is changed.
+    pub kind: ModifierKind,
+    /// What the modifier comes from, to remove it with `Stats::remove_source`.
+    pub source: String,
+    /// Seconds left until the modifier expires, `None` to keep it until it's removed.
+    pub duration: Option<f32>,
+}
+
+impl Modifier {
+    /// Creates a modifier of the stat, from the source.
+    pub fn new<N, S>(stat: N, kind: ModifierKind, source: S) -> Self
+    where
+        N: Into<String>,
+        S: Into<String>,
+    {
+        Modifier {
+            stat: stat.into(),
+            kind,
+            source: source.into(),
+            duration: None,
+        }
+    }
+
+    /// Makes the modifier expire after that many seconds.
+    pub fn with_duration(mut self, duration: f32) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+}
+
+/// A stat of a `Stats` component.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Stat {
+    /// The value without modifiers.
+    pub base: f32,
+    /// The lowest value of the stat.
+    #[serde(default)]
+    pub min: Option<f32>,
+    /// The highest value of the stat.
+    #[serde(default)]
+    pub max: Option<f32>,
+    /// The stats added to the base value, with their factors, e.g. 10 health per vitality.
+    #[serde(default)]
+    pub derived_from: Vec<(String, f32)>,
+}
+
+impl Stat {
+    /// Creates a stat with the base value.
+    pub fn new(base: f32) -> Self {
+        Stat {
+            base,
+            min: None,
+            max: None,
+            derived_from: Vec::new(),
+        }
+    }
+
+    /// Sets the lowest and highest values of the stat.
+    pub fn with_range(mut self, min: f32, max: f32) -> Self {
+        self.min = Some(min);
+        self.max = Some(max);
+        self
+    }
+
+    /// Adds the value of the other stat times the factor to the base value.
+    pub fn with_derived<S: Into<String>>(mut self, stat: S, factor: f32) -> Self {
+        self.derived_from.push((stat.into(), factor));
+        self
+    }
+}
+
+/// The stats of an entity and their modifiers, see the [module documentation](index.html).
+///
+/// The `StatsSystem` expires the modifiers and sends a `StatEvent` when a value changes.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Stats {
+    stats: HashMap<String, Stat>,
+    modifiers: Vec<Modifier>,
+    #[serde(skip)]
+    values: HashMap<String, f32>,
+}
+
+impl Stats {
+    /// Creates stats without any stat.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds the stat.
+    pub fn with_stat<S: Into<String>>(mut self, name: S, stat: Stat) -> Self {
+        self.insert(name, stat);
+        self
+    }
+
+    /// Adds or replaces the stat.
+    pub fn insert<S: Into<String>>(&mut self, name: S, stat: Stat) {
+        self.stats.insert(name.into(), stat);
+    }
+
+    /// Returns the stat.
+    pub fn stat(&self, name: &str) -> Option<&Stat> {
+        self.stats.get(name)
+    }
+
+    /// Returns the stat to change it.
+    pub fn stat_mut(&mut self, name: &str) -> Option<&mut Stat> {
+        self.stats.get_mut(name)
+    }
+
+    /// Sets the base value of the stat, adding it if it's missing.
+    pub fn set_base<S: Into<String>>(&mut self, name: S, base: f32) {
+        self.stats
+            .entry(name.into())
+            .and_modify(|stat| stat.base = base)
+            .or_insert_with(|| Stat::new(base));
+    }
+
+    /// Returns the value of the stat with its modifiers, `0` if it's missing.
+    pub fn value(&self, name: &str) -> f32 {
+        self.compute(name, 0)
+    }
+
+    fn compute(&self, name: &str, depth: usize) -> f32 {
+        let stat = match self.stats.get(name) {
+            Some(stat) => stat,
+            None => return 0.,
+        };
+        let mut base = stat.base;
+        if depth < MAX_DEPTH {
+            for (other, factor) in &stat.derived_from {
+                base += factor * self.compute(other, depth + 1);
+            }
+        }
+        let (mut flat, mut percent, mut multiplier) = (0., 0., 1.);
+        for modifier in self.modifiers.iter().filter(|m| m.stat == name) {
+            match modifier.kind {
+                ModifierKind::Flat(amount) => flat += amount,
+                ModifierKind::Percent(fraction) => percent += fraction,
+                ModifierKind::Multiply(factor) => multiplier *= factor,
+            }
+        }
+        let mut value = (base + flat) * (1. + percent) * multiplier;
+        if let Some(min) = stat.min {
+            value = value.max(min);
+        }
+        if let Some(max) = stat.max {
+            value = value.min(max);
+        }
+        value
+    }
+
+    /// Iterates over the names of the stats.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.stats.keys().map(String::as_str)
+    }
+
+    /// Returns the modifiers.
+    pub fn modifiers(&self) -> &[Modifier] {
+        &self.modifiers
+    }
+
+    /// Adds the modifier.
+    pub fn add_modifier(&mut self, modifier: Modifier) {
+        self.modifiers.push(modifier);
+    }
+
+    /// Removes the modifiers from the source, returning how many there were.
+    pub fn remove_source(&mut self, source: &str) -> usize {
+        let count = self.modifiers.len();
+        self.modifiers.retain(|modifier| modifier.source != source);
+        count - self.modifiers.len()
+    }
+
+    /// Expires the modifiers, adding the ones expired to `expired`.
+    fn expire(&mut self, seconds: f32, expired: &mut Vec<Modifier>) {
+        let mut index = 0;
+        while index < self.modifiers.len() {
+            let left = self.modifiers[index].duration.map(|left| left - seconds);
+            self.modifiers[index].duration = left;
+            if left.map_or(false, |left| left <= 0.) {
+                expired.push(self.modifiers.remove(index));
+            } else {
+                index += 1;
+            }
+        }
+    }
+}
+
+impl Component for Stats {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Event sent by the `StatsSystem` on an `EventChannel<StatEvent>`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StatEvent {
+    /// The value of a stat changed, or the stat was added.
+    Changed {
+        /// The entity with the `Stats`.
+        entity: Entity,
+        /// Name of the stat.
+        stat: String,
+        /// The value before, `None` for new stats.
+        old: Option<f32>,
+        /// The value now.
+        new: f32,
+    },
+    /// A modifier expired.
+    Expired {
+        /// The entity with the `Stats`.
+        entity: Entity,
+        /// The modifier.
+        modifier: Modifier,
+    },
+}
+
+/// Expires the modifiers of the `Stats` and sends `StatEvent`s when their values change.
+#[derive(Default)]
+pub struct StatsSystem;
+
+impl<'a> System<'a> for StatsSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, Time>,
+        WriteStorage<'a, Stats>,
+        Write<'a, EventChannel<StatEvent>>,
+    );
+
+    fn run(&mut self, (entities, time, mut stats, mut channel): Self::SystemData) {
+        let mut expired = Vec::new();
+        let mut events = Vec::new();
+        for (entity, stats) in (&*entities, &mut stats).join() {
+            stats.expire(time.delta_seconds(), &mut expired);
+            events.extend(
+                expired
+                    .drain(..)
+                    .map(|modifier| StatEvent::Expired { entity, modifier }),
+            );
+
+            let values = stats
+                .stats
+                .keys()
+                .map(|name| (name.clone(), stats.value(name)))
+                .collect::<Vec<_>>();
+            for (name, value) in values {
+                let old = stats.values.insert(name.clone(), value);
+                if old.map_or(true, |old| (old - value).abs() > std::f32::EPSILON) {
+                    events.push(StatEvent::Changed {
+                        entity,
+                        stat: name,
+                        old,
+                        new: value,
+                    });
+                }
+            }
+            let current = &stats.stats;
+            stats.values.retain(|name, _| current.contains_key(name));
+        }
+        channel.iter_write(events);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modifiers_apply_in_order_on_derived_values() {
+        let mut stats = Stats::new().with_stat("vitality", Stat::new(5.)).with_stat(
+            "health",
+            Stat::new(50.)
+                .with_derived("vitality", 10.)
+                .with_range(0., 500.),
+        );
+        assert!((stats.value("health") - 100.).abs() < 1e-4);
+
+        stats.add_modifier(Modifier::new("health", ModifierKind::Multiply(2.), "curse"));
+        stats.add_modifier(Modifier::new("health", ModifierKind::Percent(0.5), "ring"));
+        stats.add_modifier(Modifier::new("health", ModifierKind::Percent(0.5), "ring"));
+        stats.add_modifier(
+            Modifier::new("vitality", ModifierKind::Flat(5.), "potion").with_duration(1.),
+        );
+        // (50 + 10 * 10) * (1 + 1) * 2, clamped.
+        assert!((stats.value("health") - 500.).abs() < 1e-4);
+        assert_eq!(2, stats.remove_source("ring"));
+        assert!((stats.value("health") - 300.).abs() < 1e-4);
+
+        let mut expired = Vec::new();
+        stats.expire(1.5, &mut expired);
+        assert_eq!(1, expired.len());
+        assert!((stats.value("health") - 200.).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,375 @@
+//! Status effects like poison or haste.
+//!
+//! A `StatusEffect` lasts for a while, changes [`Stats`](../stats/struct.Stats.html) with its
+//! modifiers, and can tick periodically, e.g. for poison to deal damage every second. Effects are
+//! applied to the `StatusEffects` component of an entity, following their `Stacking` rule when
+//! they're already active.
+//!
+//! The `StatusEffectSystem` advances the effects by `Time::fixed_seconds`, so it's meant to be
+//! dispatched from `State::fixed_update`, like the rest of the gameplay driven at a fixed rate.
+
+use amethyst_core::{
+    shrev::EventChannel,
+    specs::prelude::{
+        Component, DenseVecStorage, Entities, Entity, Join, Read, System, Write, WriteStorage,
+    },
+    timing::Time,
+};
+
+use crate::stats::{Modifier, ModifierKind, Stats};
+
+/// What happens when a `StatusEffect` is applied while it's already active.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Stacking {
+    /// The effect starts its duration over.
+    Refresh,
+    /// The effect gets one more stack, up to its `max_stacks`, and starts its duration over.
+    Stack,
+    /// The effect keeps going as it is.
+    Ignore,
+}
+
+impl Default for Stacking {
+    fn default() -> Self {
+        Stacking::Refresh
+    }
+}
+
+/// A status effect, usually loaded with the rest of the game data.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct StatusEffect {
+    /// Identifies the effect.
+    pub id: String,
+    /// Seconds the effect lasts, `None` to keep it until it's removed.
+    pub duration: Option<f32>,
+    /// Seconds between two `StatusEvent::Tick`s, `None` for effects that don't tick.
+    pub tick_interval: Option<f32>,
+    /// The stats changed by the effect, applied once per stack.
+    pub modifiers: Vec<(String, ModifierKind)>,
+    /// What happens when the effect is applied again.
+    pub stacking: Stacking,
+    /// The most stacks of the effect with `Stacking::Stack`.
+    pub max_stacks: u32,
+}
+
+impl Default for StatusEffect {
+    fn default() -> Self {
+        StatusEffect {
+            id: String::new(),
+            duration: None,
+            tick_interval: None,
+            modifiers: Vec::new(),
+            stacking: Stacking::Refresh,
+            max_stacks: 1,
+        }
+    }
+}
+
+impl StatusEffect {
+    /// Creates an effect without modifiers, ticks or end.
+    pub fn new<S: Into<String>>(id: S) -> Self {
+        StatusEffect {
+            id: id.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Makes the effect end after that many seconds.
+    pub fn with_duration(mut self, duration: f32) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    /// Makes the effect tick every that many seconds.
+    pub fn with_ticks(mut self, interval: f32) -> Self {
+        self.tick_interval = Some(interval);
+        self
+    }
+
+    /// Adds a modifier of the stat.
+    pub fn with_modifier<S: Into<String>>(mut self, stat: S, kind: ModifierKind) -> Self {
+        self.modifiers.push((stat.into(), kind));
+        self
+    }
+
+    /// Sets what happens when the effect is applied again, and the most stacks it gets.
+    pub fn with_stacking(mut self, stacking: Stacking, max_stacks: u32) -> Self {
+        self.stacking = stacking;
+        self.max_stacks = max_stacks;
+        self
+    }
+}
+
+/// A `StatusEffect` active on an entity.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ActiveStatusEffect {
+    /// The effect.
+    pub effect: StatusEffect,
+    /// How many times the effect is stacked.
+    pub stacks: u32,
+    /// Seconds until the effect ends.
+    pub remaining: Option<f32>,
+    /// Seconds until the next tick.
+    next_tick: f32,
+}
+
+/// The status effects on an entity.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct StatusEffects {
+    active: Vec<ActiveStatusEffect>,
+    #[serde(skip)]
+    applied: Vec<StatusEffect>,
+    #[serde(skip)]
+    removed: Vec<String>,
+}
+
+impl StatusEffects {
+    /// Creates a component without effects.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Applies the effect on the next run of the `StatusEffectSystem`.
+    pub fn apply(&mut self, effect: StatusEffect) {
+        self.applied.push(effect);
+    }
+
+    /// Removes the effect on the next run of the `StatusEffectSystem`.
+    pub fn remove<S: Into<String>>(&mut self, id: S) {
+        self.removed.push(id.into());
+    }
+
+    /// Returns the effect, if it's active.
+    pub fn get(&self, id: &str) -> Option<&ActiveStatusEffect> {
+        self.active.iter().find(|active| active.effect.id == id)
+    }
+
+    /// Returns the stacks of the effect, `0` if it isn't active.
+    pub fn stacks(&self, id: &str) -> u32 {
+        self.get(id).map_or(0, |active| active.stacks)
+    }
+
+    /// Iterates over the active effects.
+    pub fn iter(&self) -> impl Iterator<Item = &ActiveStatusEffect> {
+        self.active.iter()
+    }
+
+    /// Applies and removes the effects, then advances them by the seconds. Returns the ids of
+    /// the effects whose modifiers changed.
+    fn update(
+        &mut self,
+        entity: Entity,
+        seconds: f32,
+        events: &mut Vec<StatusEvent>,
+    ) -> Vec<String> {
+        let mut changed = Vec::new();
+        for effect in self.applied.drain(..) {
+            let position = self.active.iter().position(|a| a.effect.id == effect.id);
+            let active = match position {
+                Some(index) => {
+                    let active = &mut self.active[index];
+                    let stacks = match effect.stacking {
+                        Stacking::Ignore => continue,
+                        Stacking::Refresh => active.stacks,
+                        Stacking::Stack => (active.stacks + 1).min(active.effect.max_stacks.max(1)),
+                    };
+                    active.remaining = effect.duration;
+                    active.effect = effect;
+                    active.stacks = stacks;
+                    active
+                }
+                None => {
+                    self.active.push(ActiveStatusEffect {
+                        remaining: effect.duration,
+                        next_tick: effect.tick_interval.unwrap_or(0.),
+                        effect,
+                        stacks: 1,
+                    });
+                    self.active.last_mut().unwrap()
+                }
+            };
+            changed.push(active.effect.id.clone());
+            events.push(StatusEvent::Applied {
+                entity,
+                id: active.effect.id.clone(),
+                stacks: active.stacks,
+            });
+        }
+
+        for id in self.removed.drain(..) {
+            if let Some(index) = self.active.iter().position(|a| a.effect.id == id) {
+                self.active.remove(index);
+                events.push(StatusEvent::Removed {
+                    entity,
+                    id: id.clone(),
+                    expired: false,
+                });
+                changed.push(id);
+            }
+        }
+
+        let mut index = 0;
+        while index < self.active.len() {
+            let active = &mut self.active[index];
+            if let Some(interval) = active.effect.tick_interval.filter(|&i| i > 0.) {
+                active.next_tick -= seconds;
+                while active.next_tick <= 0. {
+                    active.next_tick += interval;
+                    events.push(StatusEvent::Tick {
+                        entity,
+                        id: active.effect.id.clone(),
+                        stacks: active.stacks,
+                    });
+                }
+            }
+            let expired = match active.remaining {
+                Some(ref mut remaining) => {
+                    *remaining -= seconds;
+                    *remaining <= 0.
+                }
+                None => false,
+            };
+            if expired {
+                let id = self.active.remove(index).effect.id;
+                events.push(StatusEvent::Removed {
+                    entity,
+                    id: id.clone(),
+                    expired: true,
+                });
+                changed.push(id);
+            } else {
+                index += 1;
+            }
+        }
+        changed
+    }
+
+    /// Puts the modifiers of the effect on the stats, once per stack.
+    fn sync(&self, id: &str, stats: &mut Stats) {
+        let source = source(id);
+        stats.remove_source(&source);
+        if let Some(active) = self.get(id) {
+            for _ in 0..active.stacks {
+                for (stat, kind) in &active.effect.modifiers {
+                    stats.add_modifier(Modifier::new(stat.clone(), *kind, source.clone()));
+                }
+            }
+        }
+    }
+}
+
+/// The source of the modifiers of the effect on the `Stats`.
+fn source(id: &str) -> String {
+    format!("status:{}", id)
+}
+
+impl Component for StatusEffects {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Event sent by the `StatusEffectSystem` on an `EventChannel<StatusEvent>`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StatusEvent {
+    /// The effect was applied, or applied again.
+    Applied {
+        /// The entity with the `StatusEffects`.
+        entity: Entity,
+        /// Id of the effect.
+        id: String,
+        /// The stacks of the effect now.
+        stacks: u32,
+    },
+    /// The effect ticked, e.g. for poison to deal damage.
+    Tick {
+        /// The entity with the `StatusEffects`.
+        entity: Entity,
+        /// Id of the effect.
+        id: String,
+        /// The stacks of the effect, e.g. to deal more damage with more stacks.
+        stacks: u32,
+    },
+    /// The effect ended.
+    Removed {
+        /// The entity with the `StatusEffects`.
+        entity: Entity,
+        /// Id of the effect.
+        id: String,
+        /// Whether the effect ended by itself, instead of being removed.
+        expired: bool,
+    },
+}
+
+/// Applies, ticks and ends the `StatusEffects`, keeping their modifiers on the `Stats` of their
+/// entities, and sends `StatusEvent`s.
+#[derive(Default)]
+pub struct StatusEffectSystem;
+
+impl<'a> System<'a> for StatusEffectSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, Time>,
+        WriteStorage<'a, StatusEffects>,
+        WriteStorage<'a, Stats>,
+        Write<'a, EventChannel<StatusEvent>>,
+    );
+
+    fn run(&mut self, (entities, time, mut effects, mut stats, mut channel): Self::SystemData) {
+        let mut events = Vec::new();
+        for (entity, effects, stats) in (&*entities, &mut effects, (&mut stats).maybe()).join() {
+            let changed = effects.update(entity, time.fixed_seconds(), &mut events);
+            if let Some(stats) = stats {
+                for id in changed {
+                    effects.sync(&id, stats);
+                }
+            }
+        }
+        channel.iter_write(events);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use amethyst_core::specs::prelude::{Builder, World};
+
+    use crate::stats::Stat;
+
+    #[test]
+    fn effects_stack_tick_and_expire() {
+        let entity = World::new().create_entity().build();
+        let poison = StatusEffect::new("poison")
+            .with_duration(2.)
+            .with_ticks(1.)
+            .with_modifier("speed", ModifierKind::Percent(-0.25))
+            .with_stacking(Stacking::Stack, 2);
+        let mut effects = StatusEffects::new();
+        let mut stats = Stats::new().with_stat("speed", Stat::new(4.));
+        let mut events = Vec::new();
+
+        for _ in 0..3 {
+            effects.apply(poison.clone());
+        }
+        for id in effects.update(entity, 0.5, &mut events) {
+            effects.sync(&id, &mut stats);
+        }
+        assert_eq!(2, effects.stacks("poison"));
+        assert!((stats.value("speed") - 2.).abs() < 1e-4);
+
+        events.clear();
+        for id in effects.update(entity, 1.5, &mut events) {
+            effects.sync(&id, &mut stats);
+        }
+        let ticks = events
+            .iter()
+            .filter(|event| match event {
+                StatusEvent::Tick { stacks: 2, .. } => true,
+                _ => false,
+            })
+            .count();
+        assert_eq!(2, ticks);
+        assert_eq!(0, effects.stacks("poison"));
+        assert!((stats.value("speed") - 4.).abs() < 1e-4);
+    }
+}
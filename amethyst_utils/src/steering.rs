@@ -0,0 +1,571 @@
+//! Steering behaviors and flocking, moving entities at their own speed towards, away from or
+//! around targets.
+//!
+//! An entity steers with a `SteeringAgent`, holding its velocity and limits, and combines the
+//! weighted behaviors of its `SteeringBehaviors`, like seeking a point or pursuing another
+//! entity. Entities with `Flocking` move as a group with the neighbors of their flock: they keep
+//! apart from them (separation), head the same way (alignment) and stay together (cohesion).
+//!
+//! The `SteeringSystem` adds up the steering forces, changes the velocities and moves the
+//! `Transform`s. Agents are moved by their local transform, so they shouldn't have a parent. A
+//! game moving its entities with a physics engine can instead read the velocity of the agents
+//! and give it to their bodies, running the system before the one copying the bodies back.
+
+use std::collections::HashMap;
+
+use amethyst_core::{
+    nalgebra::Vector3,
+    specs::prelude::{
+        Component, DenseVecStorage, Entities, Entity, Join, Read, ReadStorage, System, Write,
+        WriteStorage,
+    },
+    timing::Time,
+    Rng, RngStream, Transform,
+};
+
+/// An entity steered by the `SteeringSystem`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SteeringAgent {
+    /// The velocity, in units per second.
+    pub velocity: Vector3<f32>,
+    /// The highest speed, in units per second.
+    pub max_speed: f32,
+    /// The highest steering force, in units per second squared.
+    pub max_force: f32,
+    /// Whether the agent turns for its forward direction to follow its velocity.
+    pub face_velocity: bool,
+    /// The up direction of the agent, keeping it upright when it faces its velocity. Wandering
+    /// happens around it.
+    pub up: Vector3<f32>,
+}
+
+impl SteeringAgent {
+    /// Creates an agent at rest.
+    pub fn new(max_speed: f32, max_force: f32) -> Self {
+        SteeringAgent {
+            velocity: Vector3::zeros(),
+            max_speed,
+            max_force,
+            face_velocity: false,
+            up: Vector3::y(),
+        }
+    }
+
+    /// Makes the agent face its velocity, upright along `up`.
+    pub fn with_facing(mut self, up: Vector3<f32>) -> Self {
+        self.face_velocity = true;
+        self.up = up;
+        self
+    }
+}
+
+impl Component for SteeringAgent {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// What a steering behavior steers to or from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SteeringTarget {
+    /// A point.
+    Point(Vector3<f32>),
+    /// The position of an entity with a `Transform`.
+    Entity(Entity),
+}
+
+/// A steering behavior of `SteeringBehaviors`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SteeringBehavior {
+    /// Heads to the target at full speed.
+    Seek(SteeringTarget),
+    /// Runs away from the target while it's closer than the distance.
+    Flee {
+        /// The target.
+        target: SteeringTarget,
+        /// The distance under which the agent flees.
+        panic_distance: f32,
+    },
+    /// Heads to the target, slowing down to stop on it.
+    Arrive {
+        /// The target.
+        target: SteeringTarget,
+        /// The distance from which the agent slows down.
+        slowing_radius: f32,
+    },
+    /// Wanders around, heading to a point moving randomly on a circle in front of the agent.
+    Wander {
+        /// Radius of the circle.
+        radius: f32,
+        /// Distance of the circle in front of the agent.
+        distance: f32,
+        /// The most the point moves on the circle in a second, in radians.
+        jitter: f32,
+    },
+    /// Heads to where the entity is going to be, following its velocity if it's an agent.
+    Pursuit(Entity),
+    /// Runs away from where the entity is going to be, while it's closer than the distance.
+    Evade {
+        /// The pursuer.
+        entity: Entity,
+        /// The distance under which the agent evades.
+        panic_distance: f32,
+    },
+}
+
+/// The weighted steering behaviors of an agent, added together.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SteeringBehaviors {
+    behaviors: Vec<(SteeringBehavior, f32)>,
+    wander_angle: f32,
+}
+
+impl SteeringBehaviors {
+    /// Creates a component without behaviors.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds the behavior with its weight.
+    pub fn with(mut self, behavior: SteeringBehavior, weight: f32) -> Self {
+        self.add(behavior, weight);
+        self
+    }
+
+    /// Adds the behavior with its weight.
+    pub fn add(&mut self, behavior: SteeringBehavior, weight: f32) {
+        self.behaviors.push((behavior, weight));
+    }
+
+    /// Removes the behaviors matching the predicate, e.g. to stop pursuing an entity.
+    pub fn remove<F>(&mut self, mut predicate: F)
+    where
+        F: FnMut(&SteeringBehavior) -> bool,
+    {
+        self.behaviors.retain(|(behavior, _)| !predicate(behavior));
+    }
+
+    /// Returns the behaviors with their weights.
+    pub fn behaviors(&self) -> &[(SteeringBehavior, f32)] {
+        &self.behaviors
+    }
+}
+
+impl Component for SteeringBehaviors {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Makes an agent flock with the agents of the same flock around it.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct Flocking {
+    /// The flock of the agent.
+    pub flock: u32,
+    /// The distance under which other agents are neighbors.
+    pub radius: f32,
+    /// Weight of keeping apart from the neighbors.
+    pub separation: f32,
+    /// Weight of heading the same way as the neighbors.
+    pub alignment: f32,
+    /// Weight of moving to the center of the neighbors.
+    pub cohesion: f32,
+}
+
+impl Default for Flocking {
+    fn default() -> Self {
+        Flocking {
+            flock: 0,
+            radius: 5.,
+            separation: 1.5,
+            alignment: 1.,
+            cohesion: 1.,
+        }
+    }
+}
+
+impl Component for Flocking {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Buckets the flocking agents in cubes, for the neighbor queries to only look at the nearby
+/// ones.
+struct NeighborGrid {
+    size: f32,
+    cells: HashMap<(i32, i32, i32), Vec<usize>>,
+}
+
+impl NeighborGrid {
+    fn new(size: f32, positions: impl Iterator<Item = (usize, Vector3<f32>)>) -> Self {
+        let mut grid = NeighborGrid {
+            size: size.max(std::f32::EPSILON),
+            cells: HashMap::new(),
+        };
+        for (index, position) in positions {
+            let cell = grid.cell(&position);
+            grid.cells.entry(cell).or_insert_with(Vec::new).push(index);
+        }
+        grid
+    }
+
+    fn cell(&self, position: &Vector3<f32>) -> (i32, i32, i32) {
+        let cell = position / self.size;
+        (
+            cell.x.floor() as i32,
+            cell.y.floor() as i32,
+            cell.z.floor() as i32,
+        )
+    }
+
+    /// Iterates over the agents in the cells within the radius of the position.
+    fn near<'a>(
+        &'a self,
+        position: &Vector3<f32>,
+        radius: f32,
+    ) -> impl Iterator<Item = usize> + 'a {
+        let reach = (radius / self.size).ceil() as i32;
+        let (x, y, z) = self.cell(position);
+        (x - reach..=x + reach).flat_map(move |x| {
+            (y - reach..=y + reach).flat_map(move |y| {
+                (z - reach..=z + reach).flat_map(move |z| {
+                    self.cells
+                        .get(&(x, y, z))
+                        .into_iter()
+                        .flat_map(|cell| cell.iter().cloned())
+                })
+            })
+        })
+    }
+}
+
+/// Returns the vector, shortened to the length if it's longer.
+fn truncate(vector: Vector3<f32>, length: f32) -> Vector3<f32> {
+    let norm = vector.norm();
+    if norm > length && norm > 0. {
+        vector * (length / norm)
+    } else {
+        vector
+    }
+}
+
+/// Returns the force steering the velocity to the given one at full speed.
+fn steer_to(direction: Vector3<f32>, agent: &SteeringAgent) -> Vector3<f32> {
+    match direction.try_normalize(std::f32::EPSILON) {
+        Some(direction) => direction * agent.max_speed - agent.velocity,
+        None => Vector3::zeros(),
+    }
+}
+
+fn arrive(offset: Vector3<f32>, slowing_radius: f32, agent: &SteeringAgent) -> Vector3<f32> {
+    let distance = offset.norm();
+    if distance <= std::f32::EPSILON {
+        return -agent.velocity;
+    }
+    let speed = agent.max_speed * (distance / slowing_radius.max(std::f32::EPSILON)).min(1.);
+    offset / distance * speed - agent.velocity
+}
+
+/// The position and velocity of an agent, before they're changed.
+struct AgentState {
+    entity: Entity,
+    position: Vector3<f32>,
+    velocity: Vector3<f32>,
+}
+
+struct Steering<'s, 'a> {
+    agents: &'s [AgentState],
+    indices: &'s HashMap<Entity, usize>,
+    transforms: &'s WriteStorage<'a, Transform>,
+}
+
+impl<'s, 'a> Steering<'s, 'a> {
+    fn position(&self, target: SteeringTarget) -> Option<Vector3<f32>> {
+        match target {
+            SteeringTarget::Point(point) => Some(point),
+            SteeringTarget::Entity(entity) => self.transforms.get(entity).map(|t| *t.translation()),
+        }
+    }
+
+    /// Returns where the entity is going to be when the agent reaches it.
+    fn predict(&self, entity: Entity, position: &Vector3<f32>, speed: f32) -> Option<Vector3<f32>> {
+        let target = self.position(SteeringTarget::Entity(entity))?;
+        let velocity = self
+            .indices
+            .get(&entity)
+            .map_or_else(Vector3::zeros, |&index| self.agents[index].velocity);
+        let time = (target - position).norm() / speed.max(std::f32::EPSILON);
+        Some(target + velocity * time)
+    }
+
+    fn behavior(
+        &self,
+        behavior: SteeringBehavior,
+        wander_angle: &mut f32,
+        agent: &SteeringAgent,
+        position: &Vector3<f32>,
+        seconds: f32,
+        rng: &mut RngStream,
+    ) -> Vector3<f32> {
+        let flee = |threat: Vector3<f32>, panic_distance: f32| {
+            let offset = position - threat;
+            if offset.norm() < panic_distance {
+                steer_to(offset, agent)
+            } else {
+                Vector3::zeros()
+            }
+        };
+        let force = match behavior {
+            SteeringBehavior::Seek(target) => self
+                .position(target)
+                .map(|target| steer_to(target - position, agent)),
+            SteeringBehavior::Flee {
+                target,
+                panic_distance,
+            } => self
+                .position(target)
+                .map(|target| flee(target, panic_distance)),
+            SteeringBehavior::Arrive {
+                target,
+                slowing_radius,
+            } => self
+                .position(target)
+                .map(|target| arrive(target - position, slowing_radius, agent)),
+            SteeringBehavior::Wander {
+                radius,
+                distance,
+                jitter,
+            } => {
+                let jitter = jitter * seconds;
+                *wander_angle += rng.range_f32(-jitter, jitter);
+                let up = agent
+                    .up
+                    .try_normalize(std::f32::EPSILON)
+                    .unwrap_or_else(Vector3::y);
+                let forward = (agent.velocity - up * up.dot(&agent.velocity))
+                    .try_normalize(std::f32::EPSILON)
+                    .or_else(|| up.cross(&Vector3::x()).try_normalize(std::f32::EPSILON))
+                    .unwrap_or_else(Vector3::z);
+                let side = up.cross(&forward);
+                let point = forward * distance
+                    + (forward * wander_angle.cos() + side * wander_angle.sin()) * radius;
+                Some(steer_to(point, agent))
+            }
+            SteeringBehavior::Pursuit(entity) => self
+                .predict(entity, position, agent.max_speed)
+                .map(|target| steer_to(target - position, agent)),
+            SteeringBehavior::Evade {
+                entity,
+                panic_distance,
+            } => self
+                .predict(entity, position, agent.max_speed)
+                .map(|target| flee(target, panic_distance)),
+        };
+        force.unwrap_or_else(Vector3::zeros)
+    }
+
+    fn flocking(
+        &self,
+        index: usize,
+        flocking: &Flocking,
+        agent: &SteeringAgent,
+        flocks: &[Option<u32>],
+        grid: &NeighborGrid,
+    ) -> Vector3<f32> {
+        let position = self.agents[index].position;
+        let (mut separation, mut heading, mut center, mut count) =
+            (Vector3::zeros(), Vector3::zeros(), Vector3::zeros(), 0);
+        for other in grid.near(&position, flocking.radius) {
+            if other == index || flocks[other] != Some(flocking.flock) {
+                continue;
+            }
+            let neighbor = &self.agents[other];
+            let offset = position - neighbor.position;
+            let distance = offset.norm();
+            if distance >= flocking.radius {
+                continue;
+            }
+            // Closer neighbors push harder.
+            if distance > std::f32::EPSILON {
+                separation += offset / (distance * distance);
+            }
+            heading += neighbor.velocity;
+            center += neighbor.position;
+            count += 1;
+        }
+        if count == 0 {
+            return Vector3::zeros();
+        }
+        let center = center / count as f32;
+        steer_to(separation, agent) * flocking.separation
+            + steer_to(heading, agent) * flocking.alignment
+            + steer_to(center - position, agent) * flocking.cohesion
+    }
+}
+
+/// Adds up the steering forces of the `SteeringAgent`s, changes their velocities and moves their
+/// `Transform`s.
+#[derive(Default)]
+pub struct SteeringSystem;
+
+impl<'a> System<'a> for SteeringSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, Time>,
+        Write<'a, Rng>,
+        WriteStorage<'a, SteeringAgent>,
+        WriteStorage<'a, SteeringBehaviors>,
+        ReadStorage<'a, Flocking>,
+        WriteStorage<'a, Transform>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            time,
+            mut rng,
+            mut agents,
+            mut behaviors,
+            flocking,
+            mut transforms,
+        ): Self::SystemData,
+    ) {
+        let seconds = time.delta_seconds();
+        if seconds <= 0. {
+            return;
+        }
+        let rng = rng.stream("steering_system");
+
+        let states = (&*entities, &agents, &transforms)
+            .join()
+            .map(|(entity, agent, transform)| AgentState {
+                entity,
+                position: *transform.translation(),
+                velocity: agent.velocity,
+            })
+            .collect::<Vec<_>>();
+        let indices = states
+            .iter()
+            .enumerate()
+            .map(|(index, state)| (state.entity, index))
+            .collect::<HashMap<_, _>>();
+        let flocks = states
+            .iter()
+            .map(|state| flocking.get(state.entity).map(|f| f.flock))
+            .collect::<Vec<_>>();
+        let radius = (&flocking, &agents)
+            .join()
+            .map(|(flocking, _)| flocking.radius)
+            .fold(0., f32::max);
+        let grid = NeighborGrid::new(
+            radius,
+            states
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| flocks[*index].is_some())
+                .map(|(index, state)| (index, state.position)),
+        );
+
+        let forces = {
+            let steering = Steering {
+                agents: &states,
+                indices: &indices,
+                transforms: &transforms,
+            };
+            let mut forces = Vec::with_capacity(states.len());
+            for (index, state) in states.iter().enumerate() {
+                let agent = agents.get(state.entity).expect("Agent of a steering state");
+                let mut force = Vector3::zeros();
+                if let Some(component) = behaviors.get_mut(state.entity) {
+                    for &(behavior, weight) in &component.behaviors {
+                        force += steering.behavior(
+                            behavior,
+                            &mut component.wander_angle,
+                            agent,
+                            &state.position,
+                            seconds,
+                            rng,
+                        ) * weight;
+                    }
+                }
+                if let Some(flocking) = flocking.get(state.entity) {
+                    force += steering.flocking(index, flocking, agent, &flocks, &grid);
+                }
+                forces.push(truncate(force, agent.max_force));
+            }
+            forces
+        };
+
+        for (state, force) in states.iter().zip(forces) {
+            let agent = agents
+                .get_mut(state.entity)
+                .expect("Agent of a steering state");
+            agent.velocity = truncate(agent.velocity + force * seconds, agent.max_speed);
+            let transform = transforms
+                .get_mut(state.entity)
+                .expect("Transform of a steering state");
+            let position = state.position + agent.velocity * seconds;
+            transform.set_position(position);
+            if agent.face_velocity && agent.velocity.norm() > std::f32::EPSILON {
+                transform.face_towards(position + agent.velocity, agent.up);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use amethyst_core::specs::prelude::{Builder, RunNow, World};
+
+    #[test]
+    fn flocking_keeps_neighbors_apart_and_arrive_stops_on_target() {
+        let mut world = World::new();
+        world.register::<SteeringAgent>();
+        world.register::<SteeringBehaviors>();
+        world.register::<Flocking>();
+        world.register::<Transform>();
+        let mut time = Time::default();
+        time.set_delta_seconds(0.1);
+        world.add_resource(time);
+        world.add_resource(Rng::new(1));
+
+        let boids = [-0.5, 0.5]
+            .iter()
+            .map(|&x| {
+                let mut transform = Transform::default();
+                transform.set_x(x);
+                world
+                    .create_entity()
+                    .with(SteeringAgent::new(2., 10.))
+                    .with(Flocking {
+                        alignment: 0.,
+                        cohesion: 0.,
+                        ..Default::default()
+                    })
+                    .with(transform)
+                    .build()
+            })
+            .collect::<Vec<_>>();
+        let target = Vector3::new(0., 0., 4.);
+        let arriving = world
+            .create_entity()
+            .with(SteeringAgent::new(2., 10.))
+            .with(SteeringBehaviors::new().with(
+                SteeringBehavior::Arrive {
+                    target: SteeringTarget::Point(target),
+                    slowing_radius: 2.,
+                },
+                1.,
+            ))
+            .with(Transform::default())
+            .build();
+
+        for _ in 0..100 {
+            SteeringSystem.run_now(&world.res);
+        }
+        let transforms = world.read_storage::<Transform>();
+        let x = |entity| transforms.get(entity).unwrap().translation().x;
+        assert!(x(boids[1]) - x(boids[0]) > 4.);
+        let position = transforms.get(arriving).unwrap().translation();
+        assert!((position - target).norm() < 0.1);
+    }
+}
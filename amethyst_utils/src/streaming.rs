@@ -0,0 +1,376 @@
+//! Streaming of large worlds divided in sections.
+//!
+//! The world is divided in `WorldSection`s, each filled by a prefab and bounded by a box. The
+//! `LevelStreamingSystem` loads the prefab of a section when an entity with a `StreamingAnchor`,
+//! usually the player or the camera, comes within the `load_distance` of its bounds, and unloads
+//! the section once every anchor is farther than the `unload_distance`. Prefabs and their assets
+//! are loaded on the background threads of the `Loader`, and unloading deletes the entities of
+//! the section, which frees the assets nothing else uses.
+//!
+//! A `SectionEvent` is sent whenever the state of a section changes, so gameplay can wait for
+//! a section to be resident, e.g. before opening the door leading into it:
+//!
+//! ```rust,ignore
+//! let streaming = LevelStreaming::new(100., 150.)
+//!     .with_section(WorldSection::new(
+//!         "harbour",
+//!         "sections/harbour.ron",
+//!         Vector3::new(0., -10., 0.),
+//!         Vector3::new(200., 50., 200.),
+//!     ));
+//! world.add_resource(streaming);
+//!
+//! let game_data = GameDataBuilder::default()
+//!     .with(PrefabLoaderSystem::<MyPrefabData>::default(), "prefab_loader", &[])
+//!     .with(
+//!         LevelStreamingSystem::<MyPrefabData>::new(),
+//!         "level_streaming",
+//!         &["prefab_loader", "transform_system"],
+//!     );
+//! ```
+
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+
+use amethyst_assets::{
+    AssetStorage, Handle, Loader, Prefab, PrefabTag, ProgressCounter, RonFormat,
+};
+use amethyst_core::{
+    nalgebra::{self, Vector3},
+    shrev::EventChannel,
+    specs::prelude::{
+        Component, Entities, Entity, Join, NullStorage, Read, ReadExpect, ReadStorage, System,
+        Write, WriteStorage,
+    },
+    GlobalTransform,
+};
+
+/// A section of the world, filled by a prefab.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct WorldSection {
+    /// The name of the section in the `SectionEvent`s.
+    pub name: String,
+    /// The path of the prefab, loaded with the `RonFormat`.
+    pub prefab: String,
+    /// The corner of the bounds with the lowest coordinates, in world space.
+    pub min: Vector3<f32>,
+    /// The corner of the bounds with the highest coordinates, in world space.
+    pub max: Vector3<f32>,
+}
+
+impl WorldSection {
+    /// Creates a section filled by the prefab at the path, within the bounds.
+    pub fn new<N, P>(name: N, prefab: P, min: Vector3<f32>, max: Vector3<f32>) -> Self
+    where
+        N: Into<String>,
+        P: Into<String>,
+    {
+        WorldSection {
+            name: name.into(),
+            prefab: prefab.into(),
+            min,
+            max,
+        }
+    }
+
+    /// Returns the distance from the point to the bounds, zero inside them.
+    pub fn distance(&self, point: &Vector3<f32>) -> f32 {
+        let clamped = nalgebra::inf(&nalgebra::sup(point, &self.min), &self.max);
+        (point - clamped).norm()
+    }
+}
+
+/// The state of a `WorldSection`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum SectionState {
+    /// The section is not loaded.
+    Unloaded,
+    /// The prefab of the section is loading.
+    Loading,
+    /// The entities of the section are created.
+    Resident,
+    /// The prefab failed to load. The section is loaded again after every anchor left it.
+    Failed,
+}
+
+/// Event sent by the `LevelStreamingSystem` on an `EventChannel<SectionEvent>` when the state of
+/// a section changes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SectionEvent {
+    /// The name of the section.
+    pub section: String,
+    /// The new state of the section.
+    pub state: SectionState,
+}
+
+/// Marks the entities sections are streamed around, like the player or the camera.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StreamingAnchor;
+
+impl Component for StreamingAnchor {
+    type Storage = NullStorage<Self>;
+}
+
+struct StreamedSection {
+    section: WorldSection,
+    state: SectionState,
+    root: Option<Entity>,
+    progress: ProgressCounter,
+}
+
+/// Resource holding the sections of the world and their state.
+///
+/// The `unload_distance` should be larger than the `load_distance`, so sections on the edge
+/// aren't loaded and unloaded again as the anchors move back and forth.
+pub struct LevelStreaming {
+    /// How close to its bounds an anchor has to be to load a section.
+    pub load_distance: f32,
+    /// How far from its bounds every anchor has to be to unload a section.
+    pub unload_distance: f32,
+    sections: Vec<StreamedSection>,
+}
+
+impl Default for LevelStreaming {
+    fn default() -> Self {
+        LevelStreaming::new(100., 150.)
+    }
+}
+
+impl LevelStreaming {
+    /// Creates a streaming resource without sections.
+    pub fn new(load_distance: f32, unload_distance: f32) -> Self {
+        LevelStreaming {
+            load_distance,
+            unload_distance,
+            sections: Vec::new(),
+        }
+    }
+
+    /// Adds a section.
+    pub fn with_section(mut self, section: WorldSection) -> Self {
+        self.add_section(section);
+        self
+    }
+
+    /// Adds a section, unloaded until an anchor comes near it.
+    pub fn add_section(&mut self, section: WorldSection) {
+        self.sections.push(StreamedSection {
+            section,
+            state: SectionState::Unloaded,
+            root: None,
+            progress: ProgressCounter::new(),
+        });
+    }
+
+    /// Returns the sections with their state.
+    pub fn sections(&self) -> impl Iterator<Item = (&WorldSection, SectionState)> {
+        self.sections
+            .iter()
+            .map(|streamed| (&streamed.section, streamed.state))
+    }
+
+    /// Returns the state of the section with the name, if there is one.
+    pub fn state(&self, name: &str) -> Option<SectionState> {
+        self.find(name).map(|streamed| streamed.state)
+    }
+
+    /// Returns whether the entities of the section with the name are created.
+    pub fn is_resident(&self, name: &str) -> bool {
+        self.state(name) == Some(SectionState::Resident)
+    }
+
+    /// Returns the entity holding the prefab of the section with the name, while it's loading or
+    /// resident.
+    pub fn root(&self, name: &str) -> Option<Entity> {
+        self.find(name).and_then(|streamed| streamed.root)
+    }
+
+    fn find(&self, name: &str) -> Option<&StreamedSection> {
+        self.sections
+            .iter()
+            .find(|streamed| streamed.section.name == name)
+    }
+
+    /// Returns the state the section moves to at the distance from the closest anchor.
+    fn next_state(&self, state: SectionState, distance: f32) -> SectionState {
+        match state {
+            SectionState::Unloaded if distance <= self.load_distance => SectionState::Loading,
+            SectionState::Loading | SectionState::Resident | SectionState::Failed
+                if distance > self.unload_distance.max(self.load_distance) =>
+            {
+                SectionState::Unloaded
+            }
+            state => state,
+        }
+    }
+}
+
+/// Loads and unloads the `WorldSection`s of the `LevelStreaming` around the `StreamingAnchor`s,
+/// and sends the `SectionEvent`s.
+///
+/// Sections don't change while there's no anchor. Add the system after the
+/// `PrefabLoaderSystem<T>`, so sections are resident once their entities are created.
+pub struct LevelStreamingSystem<T> {
+    anchors: Vec<Vector3<f32>>,
+    deleted: Vec<Entity>,
+    marker: PhantomData<T>,
+}
+
+impl<T> LevelStreamingSystem<T> {
+    /// Creates the system.
+    pub fn new() -> Self {
+        LevelStreamingSystem {
+            anchors: Vec::new(),
+            deleted: Vec::new(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Default for LevelStreamingSystem<T> {
+    fn default() -> Self {
+        LevelStreamingSystem::new()
+    }
+}
+
+impl<'a, T> System<'a> for LevelStreamingSystem<T>
+where
+    T: DeserializeOwned + Send + Sync + 'static,
+{
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Loader>,
+        Read<'a, AssetStorage<Prefab<T>>>,
+        ReadStorage<'a, StreamingAnchor>,
+        ReadStorage<'a, GlobalTransform>,
+        ReadStorage<'a, PrefabTag<T>>,
+        WriteStorage<'a, Handle<Prefab<T>>>,
+        Write<'a, LevelStreaming>,
+        Write<'a, EventChannel<SectionEvent>>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            loader,
+            prefabs,
+            anchors,
+            globals,
+            tags,
+            mut handles,
+            mut streaming,
+            mut channel,
+        ): Self::SystemData,
+    ) {
+        self.anchors.clear();
+        self.anchors.extend(
+            (&anchors, &globals)
+                .join()
+                .map(|(_, global)| global.0.column(3).xyz()),
+        );
+
+        let streaming = &mut *streaming;
+        for index in 0..streaming.sections.len() {
+            let streamed = &streaming.sections[index];
+            let mut state = streamed.state;
+            if state == SectionState::Loading {
+                let handle = streamed.root.and_then(|root| handles.get(root));
+                if handle.map_or(false, |handle| prefabs.get(handle).is_some()) {
+                    state = SectionState::Resident;
+                } else if streamed.progress.num_failed() > 0 {
+                    error!(
+                        "Failed to load the section {:?}: {:?}",
+                        streamed.section.name,
+                        streamed.progress.errors()
+                    );
+                    state = SectionState::Failed;
+                }
+            }
+            if !self.anchors.is_empty() {
+                let distance = self
+                    .anchors
+                    .iter()
+                    .map(|anchor| streamed.section.distance(anchor))
+                    .fold(std::f32::INFINITY, f32::min);
+                state = streaming.next_state(state, distance);
+            }
+
+            let streamed = &mut streaming.sections[index];
+            if state == streamed.state {
+                continue;
+            }
+            match state {
+                SectionState::Loading => {
+                    let root = entities.create();
+                    streamed.progress = ProgressCounter::new();
+                    let handle = loader.load(
+                        streamed.section.prefab.as_str(),
+                        RonFormat,
+                        (),
+                        &mut streamed.progress,
+                        &prefabs,
+                    );
+                    // The entity was just created, so inserting can't fail.
+                    handles.insert(root, handle).ok();
+                    streamed.root = Some(root);
+                }
+                SectionState::Unloaded => {
+                    if let Some(root) = streamed.root.take() {
+                        let tag = handles
+                            .get(root)
+                            .and_then(|handle| prefabs.get(handle))
+                            .and_then(Prefab::tag);
+                        self.deleted.clear();
+                        self.deleted.push(root);
+                        if let Some(tag) = tag {
+                            self.deleted.extend(
+                                (&*entities, &tags)
+                                    .join()
+                                    .filter(|(_, prefab_tag)| prefab_tag.tag() == tag)
+                                    .map(|(entity, _)| entity),
+                            );
+                        }
+                        for entity in &self.deleted {
+                            if let Err(e) = entities.delete(*entity) {
+                                error!("Failed to delete an entity of a section: {}", e);
+                            }
+                        }
+                    }
+                }
+                SectionState::Resident | SectionState::Failed => {}
+            }
+            streamed.state = state;
+            channel.single_write(SectionEvent {
+                section: streamed.section.name.clone(),
+                state,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sections_load_and_unload_with_hysteresis() {
+        let section = WorldSection::new(
+            "harbour",
+            "harbour.ron",
+            Vector3::new(0., 0., 0.),
+            Vector3::new(10., 10., 10.),
+        );
+        assert!(section.distance(&Vector3::new(5., 5., 5.)) < 1e-6);
+        assert!((section.distance(&Vector3::new(13., 14., 5.)) - 5.).abs() < 1e-6);
+
+        let streaming = LevelStreaming::new(20., 30.);
+        let next = |state, distance| streaming.next_state(state, distance);
+        assert_eq!(SectionState::Loading, next(SectionState::Unloaded, 20.));
+        assert_eq!(SectionState::Unloaded, next(SectionState::Unloaded, 25.));
+        assert_eq!(SectionState::Resident, next(SectionState::Resident, 25.));
+        assert_eq!(SectionState::Unloaded, next(SectionState::Resident, 31.));
+        assert_eq!(SectionState::Unloaded, next(SectionState::Failed, 31.));
+    }
+}
@@ -0,0 +1,474 @@
+//! Building blocks for in-game level editors working on grids of tiles: a cursor snapping to the
+//! grid under the mouse, paint, erase and fill operations, and an undo/redo history of the edits.
+//!
+//! The operations work on any type implementing `TileGrid`, so they can be used with the tile map
+//! of your game.
+
+use std::{collections::HashSet, hash::Hash, marker::PhantomData};
+
+use amethyst_core::{
+    nalgebra::{Point2, Point3, Vector3},
+    specs::{Component, HashMapStorage, Join, Read, ReadExpect, ReadStorage, System, WriteStorage},
+    GlobalTransform, Transform,
+};
+use amethyst_input::InputHandler;
+use amethyst_renderer::{get_camera, ActiveCamera, Camera, Plane, ScreenDimensions};
+
+/// A grid of tiles that can be edited.
+///
+/// Cells are addressed by column and row, from `(0, 0)` to `size() - (1, 1)`.
+pub trait TileGrid {
+    /// The type of tile in the cells.
+    type Tile: Clone + PartialEq;
+
+    /// Returns the number of columns and rows of the grid.
+    fn size(&self) -> (u32, u32);
+
+    /// Returns the tile in the cell, or `None` if the cell is empty.
+    fn tile(&self, x: u32, y: u32) -> Option<Self::Tile>;
+
+    /// Sets the tile in the cell, `None` emptying it.
+    fn set_tile(&mut self, x: u32, y: u32, tile: Option<Self::Tile>);
+}
+
+/// Maps world positions on the plane of a grid to its cells.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct GridSnap {
+    /// Width and height of a cell in world units.
+    pub cell_size: [f32; 2],
+    /// World position of the corner of cell `(0, 0)`.
+    pub origin: [f32; 2],
+}
+
+impl GridSnap {
+    /// Creates a grid with the given cell size, with its origin at the world origin.
+    pub fn new(cell_width: f32, cell_height: f32) -> Self {
+        GridSnap {
+            cell_size: [cell_width, cell_height],
+            origin: [0., 0.],
+        }
+    }
+
+    /// Moves the corner of cell `(0, 0)` to the given world position.
+    pub fn with_origin(mut self, x: f32, y: f32) -> Self {
+        self.origin = [x, y];
+        self
+    }
+
+    /// Returns the cell containing the world position. Cells outside the grid have negative or
+    /// too large indices.
+    pub fn cell(&self, x: f32, y: f32) -> (i32, i32) {
+        (
+            ((x - self.origin[0]) / self.cell_size[0]).floor() as i32,
+            ((y - self.origin[1]) / self.cell_size[1]).floor() as i32,
+        )
+    }
+
+    /// Returns the world position of the center of the cell.
+    pub fn cell_center(&self, (x, y): (i32, i32)) -> (f32, f32) {
+        (
+            self.origin[0] + (x as f32 + 0.5) * self.cell_size[0],
+            self.origin[1] + (y as f32 + 0.5) * self.cell_size[1],
+        )
+    }
+
+    /// Returns the center of the cell containing the world position.
+    pub fn snap(&self, x: f32, y: f32) -> (f32, f32) {
+        self.cell_center(self.cell(x, y))
+    }
+}
+
+/// Converts a cell to grid coordinates, if it lies in a grid of the given size.
+pub fn in_grid((x, y): (i32, i32), (width, height): (u32, u32)) -> Option<(u32, u32)> {
+    if x >= 0 && y >= 0 && (x as u32) < width && (y as u32) < height {
+        Some((x as u32, y as u32))
+    } else {
+        None
+    }
+}
+
+/// Component of an entity following the mouse in steps of a grid, e.g. a highlight of the tile
+/// to edit.
+///
+/// The `TileCursorSystem` finds the cell under the mouse on the plane of the grid, at depth
+/// `plane_z` in the world, and moves the `Transform` of the entity, if any, to its center.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TileCursor {
+    /// The grid to snap to.
+    pub grid: GridSnap,
+    /// Depth of the plane of the grid in the world.
+    pub plane_z: f32,
+    #[serde(skip)]
+    cell: Option<(i32, i32)>,
+}
+
+impl TileCursor {
+    /// Creates a cursor on the given grid, on the plane at depth zero.
+    pub fn new(grid: GridSnap) -> Self {
+        TileCursor {
+            grid,
+            plane_z: 0.,
+            cell: None,
+        }
+    }
+
+    /// Returns the cell under the mouse, or `None` if the mouse isn't on the plane of the grid.
+    pub fn cell(&self) -> Option<(i32, i32)> {
+        self.cell
+    }
+}
+
+impl Component for TileCursor {
+    type Storage = HashMapStorage<Self>;
+}
+
+/// System updating the `TileCursor`s from the mouse position and the active camera.
+///
+/// ### Type parameters:
+///
+/// - `AX`: The axis type of the `InputHandler`
+/// - `AC`: The action type of the `InputHandler`
+pub struct TileCursorSystem<AX, AC> {
+    _marker: PhantomData<(AX, AC)>,
+}
+
+impl<AX, AC> TileCursorSystem<AX, AC> {
+    /// Creates a new `TileCursorSystem`.
+    pub fn new() -> Self {
+        TileCursorSystem {
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<AX, AC> Default for TileCursorSystem<AX, AC> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, AX, AC> System<'a> for TileCursorSystem<AX, AC>
+where
+    AX: Hash + Eq + Clone + Send + Sync + 'static,
+    AC: Hash + Eq + Clone + Send + Sync + 'static,
+{
+    type SystemData = (
+        Read<'a, InputHandler<AX, AC>>,
+        ReadExpect<'a, ScreenDimensions>,
+        Read<'a, ActiveCamera>,
+        ReadStorage<'a, Camera>,
+        ReadStorage<'a, GlobalTransform>,
+        WriteStorage<'a, TileCursor>,
+        WriteStorage<'a, Transform>,
+    );
+
+    fn run(
+        &mut self,
+        (input, dimensions, active, cameras, globals, mut cursors, mut transforms): Self::SystemData,
+    ) {
+        let ray = match (
+            input.mouse_position(),
+            get_camera(active, &cameras, &globals),
+        ) {
+            (Some((x, y)), Some((camera, camera_transform))) => Some(camera.screen_to_world_ray(
+                Point2::new(x as f32, y as f32),
+                &dimensions,
+                camera_transform,
+            )),
+            _ => None,
+        };
+
+        for (cursor, transform) in (&mut cursors, (&mut transforms).maybe()).join() {
+            let plane = Plane::new(Vector3::z(), Point3::new(0., 0., cursor.plane_z));
+            let point = ray
+                .as_ref()
+                .and_then(|ray| Some(ray.at(ray.intersect_plane(&plane)?)));
+            cursor.cell = point.map(|point| cursor.grid.cell(point.x, point.y));
+            if let (Some(cell), Some(transform)) = (cursor.cell, transform) {
+                let (x, y) = cursor.grid.cell_center(cell);
+                transform.set_x(x).set_y(y);
+            }
+        }
+    }
+}
+
+/// Returns the cells of a square brush of the given radius around a cell, clipped to the grid.
+///
+/// A radius of zero is the cell alone.
+pub fn brush_cells(center: (u32, u32), radius: u32, size: (u32, u32)) -> Vec<(u32, u32)> {
+    let (width, height) = size;
+    let x_range = center.0.saturating_sub(radius)..(center.0 + radius + 1).min(width);
+    let y_range = center.1.saturating_sub(radius)..(center.1 + radius + 1).min(height);
+    y_range
+        .flat_map(|y| x_range.clone().map(move |x| (x, y)))
+        .collect()
+}
+
+/// A set of tile changes that can be undone and redone.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TileEdit<T> {
+    /// The changed cells, with the tile before and after the change.
+    pub changes: Vec<((u32, u32), Option<T>, Option<T>)>,
+}
+
+impl<T> Default for TileEdit<T> {
+    fn default() -> Self {
+        TileEdit {
+            changes: Vec::new(),
+        }
+    }
+}
+
+impl<T: Clone + PartialEq> TileEdit<T> {
+    fn revert<G: TileGrid<Tile = T>>(&self, grid: &mut G) {
+        for ((x, y), before, _) in self.changes.iter().rev() {
+            grid.set_tile(*x, *y, before.clone());
+        }
+    }
+
+    fn apply<G: TileGrid<Tile = T>>(&self, grid: &mut G) {
+        for ((x, y), _, after) in &self.changes {
+            grid.set_tile(*x, *y, after.clone());
+        }
+    }
+}
+
+/// Edits tile grids, recording the edits for undo and redo.
+///
+/// Each operation is an edit of its own, unless it is part of a stroke: all operations between
+/// `begin_stroke` and `end_stroke`, e.g. while a mouse button is held, are undone together.
+#[derive(Clone, Debug)]
+pub struct TileEditHistory<T> {
+    undo: Vec<TileEdit<T>>,
+    redo: Vec<TileEdit<T>>,
+    stroke: Option<TileEdit<T>>,
+    limit: usize,
+}
+
+impl<T: Clone + PartialEq> Default for TileEditHistory<T> {
+    fn default() -> Self {
+        TileEditHistory::new(100)
+    }
+}
+
+impl<T: Clone + PartialEq> TileEditHistory<T> {
+    /// Creates a history keeping at most `limit` edits to undo.
+    pub fn new(limit: usize) -> Self {
+        TileEditHistory {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            stroke: None,
+            limit,
+        }
+    }
+
+    /// Starts a stroke, grouping the following operations in one edit.
+    pub fn begin_stroke(&mut self) {
+        self.end_stroke();
+        self.stroke = Some(TileEdit::default());
+    }
+
+    /// Ends the current stroke, if any.
+    pub fn end_stroke(&mut self) {
+        if let Some(stroke) = self.stroke.take() {
+            self.push(stroke);
+        }
+    }
+
+    /// Sets the tile of the cells.
+    pub fn paint<G, I>(&mut self, grid: &mut G, cells: I, tile: T)
+    where
+        G: TileGrid<Tile = T>,
+        I: IntoIterator<Item = (u32, u32)>,
+    {
+        self.set(grid, cells, Some(tile));
+    }
+
+    /// Empties the cells.
+    pub fn erase<G, I>(&mut self, grid: &mut G, cells: I)
+    where
+        G: TileGrid<Tile = T>,
+        I: IntoIterator<Item = (u32, u32)>,
+    {
+        self.set(grid, cells, None);
+    }
+
+    /// Sets the tile of the cell and of all the cells connected to it that had the same tile,
+    /// like a paint bucket. `None` erases them.
+    pub fn fill<G>(&mut self, grid: &mut G, start: (u32, u32), tile: Option<T>)
+    where
+        G: TileGrid<Tile = T>,
+    {
+        let (width, height) = grid.size();
+        if start.0 >= width || start.1 >= height {
+            return;
+        }
+        let target = grid.tile(start.0, start.1);
+        if target == tile {
+            return;
+        }
+        let mut cells = Vec::new();
+        let mut visited = HashSet::new();
+        let mut stack = vec![start];
+        while let Some((x, y)) = stack.pop() {
+            if !visited.insert((x, y)) || grid.tile(x, y) != target {
+                continue;
+            }
+            cells.push((x, y));
+            if x > 0 {
+                stack.push((x - 1, y));
+            }
+            if y > 0 {
+                stack.push((x, y - 1));
+            }
+            if x + 1 < width {
+                stack.push((x + 1, y));
+            }
+            if y + 1 < height {
+                stack.push((x, y + 1));
+            }
+        }
+        self.set(grid, cells, tile);
+    }
+
+    /// Reverts the last edit, returning `false` if there was none.
+    pub fn undo<G: TileGrid<Tile = T>>(&mut self, grid: &mut G) -> bool {
+        self.end_stroke();
+        match self.undo.pop() {
+            Some(edit) => {
+                edit.revert(grid);
+                self.redo.push(edit);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Applies the last undone edit again, returning `false` if there was none.
+    pub fn redo<G: TileGrid<Tile = T>>(&mut self, grid: &mut G) -> bool {
+        self.end_stroke();
+        match self.redo.pop() {
+            Some(edit) => {
+                edit.apply(grid);
+                self.undo.push(edit);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Checks whether there is an edit to undo.
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+            || self
+                .stroke
+                .as_ref()
+                .map_or(false, |s| !s.changes.is_empty())
+    }
+
+    /// Checks whether there is an edit to redo.
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Forgets all edits.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.stroke = None;
+    }
+
+    fn set<G, I>(&mut self, grid: &mut G, cells: I, tile: Option<T>)
+    where
+        G: TileGrid<Tile = T>,
+        I: IntoIterator<Item = (u32, u32)>,
+    {
+        let (width, height) = grid.size();
+        let mut edit = TileEdit::default();
+        for (x, y) in cells {
+            if x >= width || y >= height {
+                continue;
+            }
+            let before = grid.tile(x, y);
+            if before != tile {
+                grid.set_tile(x, y, tile.clone());
+                edit.changes.push(((x, y), before, tile.clone()));
+            }
+        }
+        if edit.changes.is_empty() {
+            return;
+        }
+        match self.stroke {
+            Some(ref mut stroke) => stroke.changes.extend(edit.changes),
+            None => self.push(edit),
+        }
+    }
+
+    fn push(&mut self, edit: TileEdit<T>) {
+        if edit.changes.is_empty() {
+            return;
+        }
+        self.redo.clear();
+        self.undo.push(edit);
+        if self.undo.len() > self.limit {
+            self.undo.remove(0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Grid(Vec<Option<u8>>);
+
+    impl TileGrid for Grid {
+        type Tile = u8;
+
+        fn size(&self) -> (u32, u32) {
+            (3, 3)
+        }
+
+        fn tile(&self, x: u32, y: u32) -> Option<u8> {
+            self.0[(y * 3 + x) as usize]
+        }
+
+        fn set_tile(&mut self, x: u32, y: u32, tile: Option<u8>) {
+            self.0[(y * 3 + x) as usize] = tile;
+        }
+    }
+
+    #[test]
+    fn fill_and_undo_stroke() {
+        let mut grid = Grid(vec![None; 9]);
+        let mut history = TileEditHistory::default();
+        // Wall across the middle column
+        history.paint(&mut grid, vec![(1, 0), (1, 1), (1, 2)], 1);
+        history.begin_stroke();
+        history.fill(&mut grid, (0, 0), Some(2));
+        history.erase(&mut grid, brush_cells((1, 1), 0, (3, 3)));
+        history.end_stroke();
+        assert_eq!(
+            vec![
+                Some(2),
+                Some(1),
+                None,
+                Some(2),
+                None,
+                None,
+                Some(2),
+                Some(1),
+                None
+            ],
+            grid.0
+        );
+
+        assert!(history.undo(&mut grid));
+        assert_eq!(vec![None, Some(1), None], grid.0[3..6].to_vec());
+        assert!(history.redo(&mut grid));
+        assert_eq!(Some(2), grid.tile(0, 1));
+        assert!(history.undo(&mut grid) && history.undo(&mut grid));
+        assert_eq!(vec![None; 9], grid.0);
+        assert!(!history.can_undo());
+    }
+}
@@ -0,0 +1,328 @@
+//! Arcade vehicles driving on raycast wheels.
+//!
+//! A `RaycastVehicle` has no wheel bodies: every wheel casts a ray down from where it's mounted,
+//! and pushes the vehicle up with a spring and damper where the ray hits the ground. The wheels
+//! touching the ground drive, brake and grip along it, with a simple tire friction limited by
+//! the load on the wheel.
+//!
+//! The engine has no physics, so the `RaycastVehicleSystem` only computes the force and torque
+//! on the vehicle, casting rays with the `Raycast` resource of the game. The game applies them
+//! to the physics body of the entity, at its position, and gives the velocities of the body
+//! back to the vehicle.
+
+use std::marker::PhantomData;
+
+use amethyst_assets::{PrefabData, PrefabError};
+use amethyst_core::{
+    nalgebra::{Matrix4, Point3, Unit, UnitQuaternion, Vector3},
+    specs::prelude::{
+        Component, DenseVecStorage, Entity, Join, Read, ReadExpect, ReadStorage, System,
+        WriteStorage,
+    },
+    timing::Time,
+    GlobalTransform,
+};
+
+use crate::raycast::Raycast;
+
+/// A wheel of a `RaycastVehicle`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct Wheel {
+    /// Where the suspension is mounted, in the local space of the vehicle.
+    pub position: [f32; 3],
+    /// The radius of the wheel.
+    pub radius: f32,
+    /// The length of the suspension at rest.
+    pub suspension_length: f32,
+    /// The force of the spring per unit of compression.
+    pub stiffness: f32,
+    /// The force of the damper per unit of speed of the suspension.
+    pub damping: f32,
+    /// The sideways force per unit of sideways speed, before the grip limits it.
+    pub cornering: f32,
+    /// The most force the tire gives along the ground, as a factor of the load on the wheel.
+    pub grip: f32,
+    /// Whether the wheel turns with the steering.
+    pub steered: bool,
+    /// Whether the engine drives the wheel.
+    pub driven: bool,
+}
+
+impl Default for Wheel {
+    fn default() -> Self {
+        Wheel {
+            position: [0.; 3],
+            radius: 0.4,
+            suspension_length: 0.5,
+            stiffness: 20_000.,
+            damping: 2_000.,
+            cornering: 5_000.,
+            grip: 1.,
+            steered: false,
+            driven: false,
+        }
+    }
+}
+
+impl Wheel {
+    /// Creates a wheel mounted at the position, with the default suspension and tire.
+    pub fn new(position: [f32; 3], steered: bool, driven: bool) -> Self {
+        Wheel {
+            position,
+            steered,
+            driven,
+            ..Default::default()
+        }
+    }
+}
+
+/// The state of a `Wheel` on the last update, e.g. to place and spin the model of the wheel.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WheelState {
+    /// Whether the wheel touches the ground.
+    pub contact: bool,
+    /// How much the suspension is compressed.
+    pub compression: f32,
+    /// The force pushing the wheel on the ground.
+    pub load: f32,
+    /// The angle the wheel is steered by, in radians around the up axis of the vehicle.
+    pub steer_angle: f32,
+    /// The angle the wheel rolled by, in radians.
+    pub spin: f32,
+}
+
+/// The controls of a `RaycastVehicle`, set by the game from the input of the driver.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct VehicleInput {
+    /// The throttle, from `-1` for full reverse to `1` for full forward.
+    pub throttle: f32,
+    /// The brake, from `0` to `1`.
+    pub brake: f32,
+    /// The steering, from `-1` for full left to `1` for full right.
+    pub steering: f32,
+}
+
+/// A vehicle on raycast wheels, see the [module documentation](index.html).
+///
+/// The forward direction of the vehicle is its negative `z` axis, like the one of `Transform`,
+/// and its up direction the `y` axis.
+#[derive(Clone, Debug, Deserialize, PartialEq, PrefabData, Serialize)]
+#[prefab(Component)]
+#[serde(default)]
+pub struct RaycastVehicle {
+    /// The wheels.
+    pub wheels: Vec<Wheel>,
+    /// The force of the engine at full throttle, shared by the driven wheels.
+    pub engine_force: f32,
+    /// The force of the brakes at full brake, shared by all the wheels.
+    pub brake_force: f32,
+    /// The angle of the steered wheels at full steering, in radians.
+    pub max_steering_angle: f32,
+    /// The controls.
+    #[serde(skip)]
+    pub input: VehicleInput,
+    /// The velocity of the vehicle, in world space, given by the game.
+    #[serde(skip)]
+    pub velocity: Vector3<f32>,
+    /// The angular velocity of the vehicle, in world space, given by the game.
+    #[serde(skip)]
+    pub angular_velocity: Vector3<f32>,
+    /// The force of the wheels on the vehicle, in world space, for this frame.
+    #[serde(skip)]
+    pub force: Vector3<f32>,
+    /// The torque of the wheels on the vehicle around its position, for this frame.
+    #[serde(skip)]
+    pub torque: Vector3<f32>,
+    #[serde(skip)]
+    states: Vec<WheelState>,
+}
+
+impl Default for RaycastVehicle {
+    fn default() -> Self {
+        RaycastVehicle {
+            wheels: Vec::new(),
+            engine_force: 8_000.,
+            brake_force: 12_000.,
+            max_steering_angle: 0.6,
+            input: VehicleInput::default(),
+            velocity: Vector3::zeros(),
+            angular_velocity: Vector3::zeros(),
+            force: Vector3::zeros(),
+            torque: Vector3::zeros(),
+            states: Vec::new(),
+        }
+    }
+}
+
+impl RaycastVehicle {
+    /// Creates a vehicle on the wheels.
+    pub fn new(wheels: Vec<Wheel>) -> Self {
+        RaycastVehicle {
+            wheels,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the states of the wheels, in the order of the wheels.
+    pub fn wheel_states(&self) -> &[WheelState] {
+        &self.states
+    }
+
+    /// Casts the rays of the wheels and computes the force and torque on the vehicle, placed by
+    /// its global matrix.
+    pub fn update(&mut self, matrix: &Matrix4<f32>, ground: &dyn Raycast, seconds: f32) {
+        self.states.resize(self.wheels.len(), WheelState::default());
+        self.force = Vector3::zeros();
+        self.torque = Vector3::zeros();
+
+        let center = matrix.transform_point(&Point3::origin());
+        let up = matrix
+            .transform_vector(&Vector3::y())
+            .try_normalize(std::f32::EPSILON)
+            .unwrap_or_else(Vector3::y);
+        let forward = matrix.transform_vector(&-Vector3::z());
+        let driven = self
+            .wheels
+            .iter()
+            .filter(|wheel| wheel.driven)
+            .count()
+            .max(1) as f32;
+        let wheels = self.wheels.len().max(1) as f32;
+
+        for (wheel, state) in self.wheels.iter().zip(&mut self.states) {
+            state.steer_angle = if wheel.steered {
+                -self.input.steering * self.max_steering_angle
+            } else {
+                0.
+            };
+            let mount = matrix.transform_point(&Point3::from(Vector3::from(wheel.position)));
+            let reach = wheel.suspension_length + wheel.radius;
+            let hit = match ground.raycast(&mount, &-up, reach) {
+                Some(hit) => hit,
+                None => {
+                    state.contact = false;
+                    state.compression = 0.;
+                    state.load = 0.;
+                    continue;
+                }
+            };
+
+            let offset = hit.point - center;
+            let velocity = self.velocity + self.angular_velocity.cross(&offset);
+            let compression = (reach - hit.distance).max(0.);
+            let load = (wheel.stiffness * compression - wheel.damping * velocity.dot(&up)).max(0.);
+
+            // The directions of the tire along the ground.
+            let steering =
+                UnitQuaternion::from_axis_angle(&Unit::new_unchecked(up), state.steer_angle);
+            let normal = hit.normal;
+            let heading = steering * forward;
+            let heading = (heading - normal * normal.dot(&heading))
+                .try_normalize(std::f32::EPSILON)
+                .unwrap_or(heading);
+            let side = heading.cross(&normal);
+            let (forward_speed, side_speed) = (velocity.dot(&heading), velocity.dot(&side));
+
+            let mut along = 0.;
+            if wheel.driven {
+                along += self.input.throttle * self.engine_force / driven;
+            }
+            // The brakes fade out near a standstill, so they don't push back and forth.
+            let brake = self.input.brake * self.brake_force / wheels;
+            along -= forward_speed.signum() * brake * forward_speed.abs().min(1.);
+            let across = -side_speed * wheel.cornering;
+            let grip = wheel.grip * load;
+            let mut tire = heading * along + side * across;
+            if tire.norm() > grip {
+                tire *= grip / tire.norm();
+            }
+
+            let wheel_force = up * load + tire;
+            self.force += wheel_force;
+            self.torque += offset.cross(&wheel_force);
+            state.contact = true;
+            state.compression = compression;
+            state.load = load;
+            state.spin += forward_speed / wheel.radius.max(std::f32::EPSILON) * seconds;
+        }
+    }
+}
+
+impl Component for RaycastVehicle {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Computes the forces and torques of the `RaycastVehicle`s, casting their rays with the
+/// `Raycast` resource `R`.
+pub struct RaycastVehicleSystem<R> {
+    marker: PhantomData<R>,
+}
+
+impl<R> RaycastVehicleSystem<R> {
+    /// Creates the system.
+    pub fn new() -> Self {
+        RaycastVehicleSystem {
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<R> Default for RaycastVehicleSystem<R> {
+    fn default() -> Self {
+        RaycastVehicleSystem::new()
+    }
+}
+
+impl<'a, R: Raycast> System<'a> for RaycastVehicleSystem<R> {
+    type SystemData = (
+        Read<'a, Time>,
+        ReadExpect<'a, R>,
+        ReadStorage<'a, GlobalTransform>,
+        WriteStorage<'a, RaycastVehicle>,
+    );
+
+    fn run(&mut self, (time, ground, globals, mut vehicles): Self::SystemData) {
+        for (vehicle, global) in (&mut vehicles, &globals).join() {
+            vehicle.update(&global.0, &*ground, time.delta_seconds());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use amethyst_core::nalgebra::Translation3;
+
+    use crate::raycast::FlatGround;
+
+    #[test]
+    fn suspension_holds_the_vehicle_up_and_the_engine_drives_it() {
+        let wheels = [
+            (-1., -1.5, true),
+            (1., -1.5, true),
+            (-1., 1.5, false),
+            (1., 1.5, false),
+        ]
+        .iter()
+        .map(|&(x, z, front)| Wheel::new([x, 0., z], front, !front))
+        .collect();
+        let mut vehicle = RaycastVehicle::new(wheels);
+        let ground = FlatGround::default();
+        // The rays hit 0.7 below the mounts, compressing the suspensions by 0.2.
+        let matrix = Translation3::new(0., 0.7, 0.).to_homogeneous();
+
+        vehicle.update(&matrix, &ground, 0.1);
+        assert!((vehicle.force - Vector3::new(0., 16_000., 0.)).norm() < 1e-1);
+        assert!(vehicle.torque.norm() < 1e-1);
+        assert!(vehicle.wheel_states().iter().all(|state| state.contact));
+
+        vehicle.input.throttle = 1.;
+        vehicle.input.steering = 1.;
+        vehicle.update(&matrix, &ground, 0.1);
+        assert!((vehicle.force.z - -8_000.).abs() < 1e-1);
+        // The steered front wheels turn right, but there is no force on them yet.
+        assert!(vehicle.wheel_states()[0].steer_angle < 0.);
+    }
+}
@@ -0,0 +1,648 @@
+//! Rain, snow, wind and wet surfaces, controlled by the `Weather` resource.
+//!
+//! Scripts change the weather with `Weather::change`, e.g. from a state or a trigger, and the
+//! systems of the `WeatherBundle` fade the precipitation in and out, move the particles of the
+//! `PrecipitationEmitter`s, darken the `Wettable` surfaces while they are wet and sway the
+//! `WindSway` scatters, like vegetation, with the wind.
+
+use std::f32::consts::PI;
+
+use amethyst_core::{
+    bundle::{Result as BundleResult, SystemBundle},
+    nalgebra::{Matrix4, Point3, UnitQuaternion, Vector3},
+    specs::prelude::{
+        Component, DenseVecStorage, DispatcherBuilder, Entities, Join, Read, ReadStorage, System,
+        Write, WriteStorage,
+    },
+    timing::Time,
+    GlobalTransform, Rng, RngStream,
+};
+use amethyst_renderer::{MeshHandle, Rgba, Scatter, ScatterInstance, Wind as ScatterWind};
+
+/// Kind of precipitation.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Precipitation {
+    /// No precipitation
+    Clear,
+    /// Rain, which wets surfaces
+    Rain,
+    /// Snow
+    Snow,
+}
+
+/// Wind blowing over the scene, with gusts.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct Wind {
+    /// Direction the wind blows to. Only its horizontal part is used.
+    pub direction: [f32; 3],
+    /// Mean speed of the wind, in units per second.
+    pub speed: f32,
+    /// Speed the gusts add at their strongest.
+    pub gust_strength: f32,
+    /// Number of gusts per second.
+    pub gust_frequency: f32,
+}
+
+impl Default for Wind {
+    fn default() -> Self {
+        Wind {
+            direction: [1., 0., 0.],
+            speed: 0.,
+            gust_strength: 0.,
+            gust_frequency: 0.2,
+        }
+    }
+}
+
+impl Wind {
+    /// Returns the velocity of the wind at the given time in seconds.
+    pub fn velocity(&self, time: f32) -> Vector3<f32> {
+        let direction = Vector3::new(self.direction[0], 0., self.direction[2]);
+        let length = direction.norm();
+        if length <= std::f32::EPSILON {
+            return Vector3::zeros();
+        }
+        // Two detuned waves make gusts that don't repeat too obviously.
+        let phase = 2. * PI * self.gust_frequency * time;
+        let gust = 0.5 + 0.3 * phase.sin() + 0.2 * (2.3 * phase + 1.7).sin();
+        direction / length * (self.speed + self.gust_strength * gust)
+    }
+}
+
+/// Resource holding the current weather.
+///
+/// Changes made with `change` fade in over the given duration. When the kind of precipitation
+/// changes, the old one first fades out, then the new one fades in.
+#[derive(Clone, Debug)]
+pub struct Weather {
+    /// The wind.
+    pub wind: Wind,
+    /// Wetness gained per second of full rain.
+    pub wetting_rate: f32,
+    /// Wetness lost per second without rain.
+    pub drying_rate: f32,
+    precipitation: Precipitation,
+    intensity: f32,
+    target: (Precipitation, f32),
+    fade_rate: f32,
+    wetness: f32,
+    time: f32,
+    wind_velocity: Vector3<f32>,
+}
+
+impl Default for Weather {
+    fn default() -> Self {
+        Weather {
+            wind: Wind::default(),
+            wetting_rate: 0.2,
+            drying_rate: 0.02,
+            precipitation: Precipitation::Clear,
+            intensity: 0.,
+            target: (Precipitation::Clear, 0.),
+            fade_rate: 1.,
+            wetness: 0.,
+            time: 0.,
+            wind_velocity: Vector3::zeros(),
+        }
+    }
+}
+
+impl Weather {
+    /// Creates clear weather without wind.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets the wind.
+    pub fn with_wind(mut self, wind: Wind) -> Self {
+        self.wind = wind;
+        self
+    }
+
+    /// Starts with the given precipitation, without fading it in.
+    pub fn with_precipitation(mut self, precipitation: Precipitation, intensity: f32) -> Self {
+        self.precipitation = precipitation;
+        self.intensity = intensity.max(0.).min(1.);
+        self.target = (precipitation, self.intensity);
+        self
+    }
+
+    /// Changes the precipitation to the given kind and intensity, between 0 and 1, over
+    /// `duration` seconds.
+    pub fn change(&mut self, precipitation: Precipitation, intensity: f32, duration: f32) {
+        self.target = (precipitation, intensity.max(0.).min(1.));
+        self.fade_rate = if duration > 0. {
+            1. / duration
+        } else {
+            std::f32::MAX
+        };
+    }
+
+    /// Returns the current kind of precipitation.
+    pub fn precipitation(&self) -> Precipitation {
+        self.precipitation
+    }
+
+    /// Returns the current intensity of the precipitation, between 0 and 1.
+    pub fn intensity(&self) -> f32 {
+        self.intensity
+    }
+
+    /// Returns the intensity of the given kind of precipitation, which is 0 if it isn't the
+    /// current one.
+    pub fn intensity_of(&self, precipitation: Precipitation) -> f32 {
+        if self.precipitation == precipitation {
+            self.intensity
+        } else {
+            0.
+        }
+    }
+
+    /// Returns how wet surfaces are, between 0 and 1.
+    pub fn wetness(&self) -> f32 {
+        self.wetness
+    }
+
+    /// Sets how wet surfaces are, e.g. to start a level after the rain.
+    pub fn set_wetness(&mut self, wetness: f32) {
+        self.wetness = wetness.max(0.).min(1.);
+    }
+
+    /// Returns the velocity of the wind, gusts included, as of the last run of the
+    /// `WeatherSystem`.
+    pub fn wind_velocity(&self) -> Vector3<f32> {
+        self.wind_velocity
+    }
+
+    fn update(&mut self, delta: f32) {
+        self.time += delta;
+        self.wind_velocity = self.wind.velocity(self.time);
+
+        let step = self.fade_rate * delta;
+        let (precipitation, intensity) = self.target;
+        if precipitation != self.precipitation && precipitation != Precipitation::Clear {
+            // Fade the old precipitation out before the new one comes in.
+            self.intensity = (self.intensity - step).max(0.);
+            if self.intensity <= 0. {
+                self.precipitation = precipitation;
+            }
+        } else {
+            let target = if precipitation == Precipitation::Clear {
+                0.
+            } else {
+                intensity
+            };
+            if self.intensity < target {
+                self.intensity = (self.intensity + step).min(target);
+            } else {
+                self.intensity = (self.intensity - step).max(target);
+            }
+            if self.intensity <= 0. {
+                self.precipitation = precipitation;
+            }
+        }
+
+        let rain = self.intensity_of(Precipitation::Rain);
+        self.wetness = if rain > 0. {
+            (self.wetness + self.wetting_rate * rain * delta).min(1.)
+        } else {
+            (self.wetness - self.drying_rate * delta).max(0.)
+        };
+    }
+}
+
+/// Settings of the particles of a `PrecipitationEmitter`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct PrecipitationPreset {
+    /// Kind of precipitation the particles show.
+    pub precipitation: Precipitation,
+    /// Number of particles at full intensity.
+    pub max_particles: usize,
+    /// Half width and half depth of the area particles fall in, around the emitter.
+    pub area: [f32; 2],
+    /// Height above the emitter particles start at. They disappear once below the emitter.
+    pub height: f32,
+    /// Falling speed, in units per second.
+    pub fall_speed: f32,
+    /// Random variation of the falling speed, as a fraction of it.
+    pub speed_variation: f32,
+    /// How much the wind carries the particles, from 0 to 1.
+    pub wind_factor: f32,
+    /// Side to side motion of the particles, in units, e.g. for fluttering snow flakes.
+    pub flutter: f32,
+    /// Uniform scale of the mesh of the particles, which gives them their shape.
+    pub scale: f32,
+}
+
+impl PrecipitationPreset {
+    /// Fast rain drops, for a mesh of a thin drop one unit long.
+    pub fn rain() -> Self {
+        PrecipitationPreset {
+            precipitation: Precipitation::Rain,
+            max_particles: 1500,
+            area: [15., 15.],
+            height: 12.,
+            fall_speed: 14.,
+            speed_variation: 0.2,
+            wind_factor: 0.3,
+            flutter: 0.,
+            scale: 0.3,
+        }
+    }
+
+    /// Slow snow flakes drifting with the wind, for a mesh of a flake one unit wide.
+    pub fn snow() -> Self {
+        PrecipitationPreset {
+            precipitation: Precipitation::Snow,
+            max_particles: 1000,
+            area: [15., 15.],
+            height: 10.,
+            fall_speed: 1.2,
+            speed_variation: 0.4,
+            wind_factor: 0.8,
+            flutter: 0.3,
+            scale: 0.04,
+        }
+    }
+}
+
+struct Particle {
+    position: Vector3<f32>,
+    speed: f32,
+    phase: f32,
+}
+
+/// Component emitting rain drops or snow flakes around its entity, drawn with the given mesh.
+///
+/// Put it on an entity following the camera, at ground level, so precipitation only needs to be
+/// simulated where it is seen. The particles move in world space, and are drawn in a single
+/// draw call as the instances of a `Scatter` the `PrecipitationSystem` keeps on the entity, with
+/// the `Material` of the entity. Add `DrawScatter` to the pipeline to see them.
+pub struct PrecipitationEmitter {
+    /// Settings of the particles
+    pub preset: PrecipitationPreset,
+    /// Mesh of a particle
+    pub mesh: MeshHandle,
+    particles: Vec<Particle>,
+}
+
+impl PrecipitationEmitter {
+    /// Creates an emitter drawing particles with the given mesh.
+    pub fn new(preset: PrecipitationPreset, mesh: MeshHandle) -> Self {
+        PrecipitationEmitter {
+            preset,
+            mesh,
+            particles: Vec::new(),
+        }
+    }
+
+    /// Returns the number of particles shown.
+    pub fn active_particles(&self) -> usize {
+        self.particles.len()
+    }
+}
+
+impl Component for PrecipitationEmitter {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Component darkening the `Rgba` tint of its entity as the `Weather` makes surfaces wet, like
+/// a wet PBR material absorbing more light.
+///
+/// The tint of the entity when dry is its `Rgba`, white if it has none. It can still be changed
+/// while the entity is wet, and is then darkened in turn.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct Wettable {
+    /// Fraction of the tint removed when fully wet.
+    pub darkening: f32,
+    /// The dry tint, and the darkened tint last written by the `WetnessSystem`.
+    #[serde(skip)]
+    tints: Option<(Rgba, Rgba)>,
+}
+
+impl Wettable {
+    /// Creates a surface losing the given fraction of its tint when fully wet.
+    pub fn new(darkening: f32) -> Self {
+        Wettable {
+            darkening,
+            tints: None,
+        }
+    }
+}
+
+impl Default for Wettable {
+    fn default() -> Self {
+        Wettable::new(0.4)
+    }
+}
+
+impl Component for Wettable {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Component swaying the instances of the `Scatter` of its entity with the wind of the
+/// `Weather`, e.g. for grass, bushes and trees.
+///
+/// The `WindSwaySystem` sets the `Scatter::wind` the vertex shader of `DrawScatter` bends the
+/// vertices with, the more the higher they are above the base of their instance.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct WindSway {
+    /// Bend per unit of height, per unit of wind speed.
+    pub bend: f32,
+    /// Largest bend per unit of height.
+    pub max_bend: f32,
+    /// Sways per second.
+    pub frequency: f32,
+}
+
+impl Default for WindSway {
+    fn default() -> Self {
+        WindSway {
+            bend: 0.02,
+            max_bend: 0.4,
+            frequency: 1.,
+        }
+    }
+}
+
+impl Component for WindSway {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// System fading the precipitation of the `Weather`, updating its wind and the wetness of
+/// surfaces.
+#[derive(Default)]
+pub struct WeatherSystem;
+
+impl<'a> System<'a> for WeatherSystem {
+    type SystemData = (Read<'a, Time>, Write<'a, Weather>);
+
+    fn run(&mut self, (time, mut weather): Self::SystemData) {
+        weather.update(time.delta_seconds());
+    }
+}
+
+/// System moving the particles of the `PrecipitationEmitter`s, and writing them to the
+/// `Scatter` of the emitter.
+#[derive(Default)]
+pub struct PrecipitationSystem;
+
+impl<'a> System<'a> for PrecipitationSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, Time>,
+        Read<'a, Weather>,
+        Write<'a, Rng>,
+        ReadStorage<'a, GlobalTransform>,
+        WriteStorage<'a, PrecipitationEmitter>,
+        WriteStorage<'a, Scatter>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, time, weather, mut rng, globals, mut emitters, mut scatters): Self::SystemData,
+    ) {
+        let rng = rng.stream("precipitation_system");
+        let delta = time.delta_seconds();
+        let wind = weather.wind_velocity();
+        for (entity, emitter, global) in (&*entities, &mut emitters, &globals).join() {
+            let preset = emitter.preset.clone();
+            let origin = Vector3::new(global.0[(0, 3)], global.0[(1, 3)], global.0[(2, 3)]);
+            let wanted = (preset.max_particles as f32 * weather.intensity_of(preset.precipitation))
+                .round() as usize;
+
+            while emitter.particles.len() < wanted {
+                // Spread the first particles over the whole height, so they don't fall in a
+                // single sheet.
+                let mut particle = Particle {
+                    position: Vector3::zeros(),
+                    speed: 0.,
+                    phase: 0.,
+                };
+                respawn(&mut particle, &preset, origin, rng);
+                particle.position.y = origin.y + rng.range_f32(0., preset.height);
+                emitter.particles.push(particle);
+            }
+            emitter.particles.truncate(wanted);
+
+            let velocity = wind * preset.wind_factor;
+            for particle in &mut emitter.particles {
+                particle.position += velocity * delta;
+                particle.position.y -= particle.speed * delta;
+                particle.phase += delta;
+                let offset = origin - particle.position;
+                if particle.position.y < origin.y
+                    || offset.x.abs() > preset.area[0]
+                    || offset.z.abs() > preset.area[1]
+                {
+                    respawn(particle, &preset, origin, rng);
+                }
+            }
+
+            // The instances of a scatter are placed relative to its entity.
+            let to_local = global.0.try_inverse().unwrap_or_else(Matrix4::identity);
+            let instances = emitter
+                .particles
+                .iter()
+                .map(|particle| {
+                    let flutter = preset.flutter * (particle.phase * 2.).sin();
+                    let position = Point3::new(
+                        particle.position.x + flutter,
+                        particle.position.y,
+                        particle.position.z + flutter * 0.5,
+                    );
+                    ScatterInstance {
+                        position: (to_local * position.to_homogeneous()).xyz(),
+                        rotation: UnitQuaternion::identity(),
+                        scale: preset.scale,
+                    }
+                })
+                .collect();
+            match scatters.get_mut(entity) {
+                Some(scatter) => {
+                    scatter.mesh = emitter.mesh.clone();
+                    scatter.instances = instances;
+                }
+                None => {
+                    if let Err(e) =
+                        scatters.insert(entity, Scatter::new(emitter.mesh.clone(), instances))
+                    {
+                        error!(
+                            "Failed to add the particles of a precipitation emitter: {}",
+                            e
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Moves a particle back to the top of the emitter's area, at a random place.
+fn respawn(
+    particle: &mut Particle,
+    preset: &PrecipitationPreset,
+    origin: Vector3<f32>,
+    rng: &mut RngStream,
+) {
+    particle.position = Vector3::new(
+        origin.x + rng.range_f32(-preset.area[0], preset.area[0]),
+        origin.y + preset.height,
+        origin.z + rng.range_f32(-preset.area[1], preset.area[1]),
+    );
+    let variation = rng.range_f32(-preset.speed_variation, preset.speed_variation);
+    particle.speed = preset.fall_speed * (1. + variation);
+    particle.phase = rng.range_f32(0., 2. * PI);
+}
+
+/// System darkening the `Wettable` entities with the wetness of the `Weather`.
+#[derive(Default)]
+pub struct WetnessSystem;
+
+impl<'a> System<'a> for WetnessSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, Weather>,
+        WriteStorage<'a, Wettable>,
+        WriteStorage<'a, Rgba>,
+    );
+
+    fn run(&mut self, (entities, weather, mut wettables, mut tints): Self::SystemData) {
+        let wetness = weather.wetness();
+        for (entity, wettable) in (&*entities, &mut wettables).join() {
+            let current = tints.get(entity).cloned().unwrap_or(Rgba::WHITE);
+            // A tint other than the one written last frame was set by the game.
+            let dry = match wettable.tints {
+                Some((dry, applied)) if applied == current => dry,
+                _ => current,
+            };
+            let factor = 1. - wettable.darkening * wetness;
+            let Rgba(r, g, b, a) = dry;
+            let tint = Rgba(r * factor, g * factor, b * factor, a);
+            wettable.tints = Some((dry, tint));
+            if tint != current {
+                if let Err(e) = tints.insert(entity, tint) {
+                    error!("Failed to darken wet entity: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// System setting the wind of the `Scatter`s of the `WindSway` entities from the `Weather`.
+#[derive(Default)]
+pub struct WindSwaySystem;
+
+impl<'a> System<'a> for WindSwaySystem {
+    type SystemData = (
+        Read<'a, Weather>,
+        ReadStorage<'a, WindSway>,
+        WriteStorage<'a, Scatter>,
+    );
+
+    fn run(&mut self, (weather, sways, mut scatters): Self::SystemData) {
+        let wind = weather.wind_velocity();
+        let speed = wind.norm();
+        for (sway, scatter) in (&sways, &mut scatters).join() {
+            scatter.wind = if speed > std::f32::EPSILON {
+                let strength = (sway.bend * speed).min(sway.max_bend);
+                Some(ScatterWind::new(wind / speed, strength, sway.frequency))
+            } else {
+                None
+            };
+        }
+    }
+}
+
+/// Adds the `WeatherSystem`, and the `PrecipitationSystem`, `WetnessSystem` and
+/// `WindSwaySystem` depending on it.
+#[derive(Default)]
+pub struct WeatherBundle;
+
+impl<'a, 'b> SystemBundle<'a, 'b> for WeatherBundle {
+    fn build(self, builder: &mut DispatcherBuilder<'a, 'b>) -> BundleResult<()> {
+        builder.add(WeatherSystem, "weather_system", &[]);
+        builder.add(
+            PrecipitationSystem,
+            "precipitation_system",
+            &["weather_system"],
+        );
+        builder.add(WetnessSystem, "wetness_system", &["weather_system"]);
+        builder.add(WindSwaySystem, "wind_sway_system", &["weather_system"]);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use amethyst_core::specs::prelude::{Builder, RunNow, World};
+
+    use super::*;
+
+    #[test]
+    fn precipitation_fades_between_kinds() {
+        let mut weather = Weather::new().with_precipitation(Precipitation::Rain, 1.);
+        weather.change(Precipitation::Snow, 0.5, 2.);
+        weather.update(1.);
+        assert_eq!(
+            (Precipitation::Rain, 0.5),
+            (weather.precipitation(), weather.intensity())
+        );
+        assert!(weather.intensity_of(Precipitation::Snow) <= 0.);
+        weather.update(1.);
+        weather.update(0.5);
+        assert_eq!(
+            (Precipitation::Snow, 0.25),
+            (weather.precipitation(), weather.intensity())
+        );
+        assert!(weather.wetness() > 0.);
+        weather.update(1.);
+        assert_eq!(
+            (Precipitation::Snow, 0.5),
+            (weather.precipitation(), weather.intensity())
+        );
+    }
+
+    #[test]
+    fn wetness_darkens_the_tint_set_by_the_game() {
+        let mut world = World::new();
+        world.register::<Wettable>();
+        world.register::<Rgba>();
+        let mut weather = Weather::new();
+        weather.set_wetness(1.);
+        world.add_resource(weather);
+        let entity = world
+            .create_entity()
+            .with(Wettable::new(0.5))
+            .with(Rgba(1., 0.5, 0., 1.))
+            .build();
+
+        let mut system = WetnessSystem;
+        system.run_now(&world.res);
+        system.run_now(&world.res);
+        assert_eq!(
+            Some(&Rgba(0.5, 0.25, 0., 1.)),
+            world.read_storage::<Rgba>().get(entity)
+        );
+
+        world
+            .write_storage::<Rgba>()
+            .insert(entity, Rgba(0., 1., 0., 0.5))
+            .unwrap();
+        system.run_now(&world.res);
+        assert_eq!(
+            Some(&Rgba(0., 0.5, 0., 0.5)),
+            world.read_storage::<Rgba>().get(entity)
+        );
+
+        world.write_resource::<Weather>().set_wetness(0.);
+        system.run_now(&world.res);
+        assert_eq!(
+            Some(&Rgba(0., 1., 0., 0.5)),
+            world.read_storage::<Rgba>().get(entity)
+        );
+    }
+}
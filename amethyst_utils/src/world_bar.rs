@@ -0,0 +1,399 @@
+//! Health bars and progress bars floating over entities in the world.
+
+use amethyst_assets::AssetStorage;
+use amethyst_core::{
+    nalgebra::{UnitQuaternion, Vector3},
+    specs::prelude::{
+        Component, DenseVecStorage, Entities, Entity, Join, Read, ReadStorage, System, WriteStorage,
+    },
+    timing::Time,
+    GlobalTransform, Parent, Transform,
+};
+use amethyst_renderer::{
+    get_camera, ActiveCamera, Camera, Hidden, Material, MeshHandle, Rgba, SpriteRender,
+    SpriteSheet, Transparent,
+};
+
+/// Distance the foreground is drawn in front of the background, so they don't fight for depth.
+const FOREGROUND_DEPTH: f32 = 0.001;
+
+/// What the background or the foreground of a `WorldBar` is drawn with.
+#[derive(Clone)]
+pub enum BarGraphic {
+    /// A quad one unit wide and high centered on its origin and facing `+z`, e.g. generated with
+    /// `Shape::Plane(None).generate::<Vec<PosNormTex>>(Some((0.5, 0.5, 1.)))`, drawn by the mesh
+    /// passes.
+    Mesh {
+        /// The quad.
+        mesh: MeshHandle,
+        /// Its material, usually with a white albedo so the color of the bar shows.
+        material: Material,
+    },
+    /// A sprite drawn by `DrawFlat2D`, stretched to the size of the bar.
+    Sprite(SpriteRender),
+}
+
+/// A bar over an entity in the world, e.g. a health bar or the progress of a building, without
+/// going through the UI.
+///
+/// Put it on its own entity: every frame the `WorldBarSystem` moves that entity to the target
+/// with the offset, turns it to face the active camera, and creates or updates two children
+/// drawing the background and the foreground, the foreground filling the bar from the left by
+/// `value`. Both are `Transparent`, so draw them with a pass with transparency enabled.
+///
+/// The bar can fade out once full or empty, e.g. to hide the health bars of unhurt units. It is
+/// deleted along with its children once the target is deleted.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let bar = WorldBar::new(enemy, background, foreground)
+///     .with_offset(Vector3::new(0., 2., 0.))
+///     .with_size(1., 0.1)
+///     .with_fade(true, false);
+/// world.create_entity().with(bar).build();
+/// ```
+#[derive(Clone)]
+pub struct WorldBar {
+    /// The entity the bar floats over.
+    pub target: Entity,
+    /// Offset from the position of the target in world units, e.g. to place the bar above a head.
+    pub offset: Vector3<f32>,
+    /// Width of the bar in world units.
+    pub width: f32,
+    /// Height of the bar in world units.
+    pub height: f32,
+    /// Drawn behind the whole bar.
+    pub background: BarGraphic,
+    /// Drawn over the filled part of the bar.
+    pub foreground: BarGraphic,
+    /// Tint of the background.
+    pub background_color: Rgba,
+    /// Tint of the foreground.
+    pub foreground_color: Rgba,
+    /// Fades the bar out when its value is 1.
+    pub fade_when_full: bool,
+    /// Fades the bar out when its value is 0.
+    pub fade_when_empty: bool,
+    /// Seconds the bar stays full or empty before it starts fading out.
+    pub fade_delay: f32,
+    /// Seconds it takes to fade out, and back in once the value changes.
+    pub fade_duration: f32,
+    value: f32,
+    fade: Fade,
+    parts: Option<(Entity, Entity)>,
+}
+
+impl WorldBar {
+    /// Creates a full bar over `target`, one unit wide and a tenth of a unit high, with a
+    /// translucent black background. It doesn't fade.
+    pub fn new(target: Entity, background: BarGraphic, foreground: BarGraphic) -> Self {
+        WorldBar {
+            target,
+            offset: Vector3::zeros(),
+            width: 1.,
+            height: 0.1,
+            background,
+            foreground,
+            background_color: Rgba(0., 0., 0., 0.5),
+            foreground_color: Rgba::WHITE,
+            fade_when_full: false,
+            fade_when_empty: false,
+            fade_delay: 1.,
+            fade_duration: 0.5,
+            value: 1.,
+            fade: Fade {
+                alpha: 1.,
+                idle: 0.,
+            },
+            parts: None,
+        }
+    }
+
+    /// Sets the offset from the position of the target.
+    pub fn with_offset(mut self, offset: Vector3<f32>) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Sets the size of the bar in world units.
+    pub fn with_size(mut self, width: f32, height: f32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Sets the value of the bar.
+    pub fn with_value(mut self, value: f32) -> Self {
+        self.set_value(value);
+        self
+    }
+
+    /// Sets the tints of the background and the foreground.
+    pub fn with_colors(mut self, background: Rgba, foreground: Rgba) -> Self {
+        self.background_color = background;
+        self.foreground_color = foreground;
+        self
+    }
+
+    /// Sets whether the bar fades out when full and when empty.
+    pub fn with_fade(mut self, when_full: bool, when_empty: bool) -> Self {
+        self.fade_when_full = when_full;
+        self.fade_when_empty = when_empty;
+        self
+    }
+
+    /// Sets how long the bar waits before fading out, and how long fading takes, in seconds.
+    pub fn with_fade_timing(mut self, delay: f32, duration: f32) -> Self {
+        self.fade_delay = delay;
+        self.fade_duration = duration;
+        self
+    }
+
+    /// Returns the value of the bar, between 0 and 1.
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    /// Sets the value of the bar, clamped between 0 and 1. A bar that faded out shows again if
+    /// the value changes.
+    pub fn set_value(&mut self, value: f32) {
+        let value = value.max(0.).min(1.);
+        if (value - self.value).abs() > std::f32::EPSILON {
+            self.fade.idle = 0.;
+        }
+        self.value = value;
+    }
+
+    /// Returns the current opacity of the bar, from 0 once faded out to 1.
+    pub fn alpha(&self) -> f32 {
+        self.fade.alpha
+    }
+
+    /// Returns the entities drawing the background and the foreground, once created.
+    pub fn parts(&self) -> Option<(Entity, Entity)> {
+        self.parts
+    }
+
+    /// Advances the fade by `delta` seconds.
+    fn update_fade(&mut self, delta: f32) {
+        let fading =
+            (self.fade_when_full && self.value >= 1.) || (self.fade_when_empty && self.value <= 0.);
+        self.fade
+            .update(fading, self.fade_delay, self.fade_duration, delta);
+    }
+}
+
+impl Component for WorldBar {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Opacity of a bar, and how long it has been full or empty.
+#[derive(Clone, Debug)]
+struct Fade {
+    alpha: f32,
+    idle: f32,
+}
+
+impl Fade {
+    fn update(&mut self, fading: bool, delay: f32, duration: f32, delta: f32) {
+        if fading {
+            self.idle += delta;
+        } else {
+            self.idle = 0.;
+        }
+        let target = if fading && self.idle >= delay { 0. } else { 1. };
+        if duration <= 0. {
+            self.alpha = target;
+        } else {
+            let step = delta / duration;
+            self.alpha = if target > self.alpha {
+                (self.alpha + step).min(target)
+            } else {
+                (self.alpha - step).max(target)
+            };
+        }
+    }
+}
+
+/// System placing the `WorldBar`s over their targets, facing the active camera, and updating
+/// the entities drawing them.
+///
+/// It reads the `GlobalTransform`s of the last frame, so add it before the `TransformSystem`
+/// for the bars to be drawn where it placed them.
+#[derive(Default)]
+pub struct WorldBarSystem;
+
+impl<'a> System<'a> for WorldBarSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, Time>,
+        Read<'a, ActiveCamera>,
+        ReadStorage<'a, Camera>,
+        ReadStorage<'a, GlobalTransform>,
+        Read<'a, AssetStorage<SpriteSheet>>,
+        WriteStorage<'a, WorldBar>,
+        WriteStorage<'a, Transform>,
+        WriteStorage<'a, Parent>,
+        WriteStorage<'a, MeshHandle>,
+        WriteStorage<'a, Material>,
+        WriteStorage<'a, SpriteRender>,
+        WriteStorage<'a, Rgba>,
+        WriteStorage<'a, Transparent>,
+        WriteStorage<'a, Hidden>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            time,
+            active,
+            cameras,
+            globals,
+            sprite_sheets,
+            mut bars,
+            mut transforms,
+            mut parents,
+            mut meshes,
+            mut materials,
+            mut sprites,
+            mut colors,
+            mut transparents,
+            mut hiddens,
+        ): Self::SystemData,
+    ) {
+        // Bars face the camera by sharing its orientation.
+        let facing = get_camera(active, &cameras, &globals).map(|(_, global)| {
+            let forward = global.0.column(2).xyz();
+            let up = global.0.column(1).xyz();
+            UnitQuaternion::new_observer_frame(&forward, &up)
+        });
+        let delta = time.delta_seconds();
+
+        for (entity, bar) in (&*entities, &mut bars).join() {
+            if !entities.is_alive(bar.target) {
+                let parts = bar
+                    .parts
+                    .map(|(background, foreground)| vec![background, foreground]);
+                for dead in parts.unwrap_or_default().into_iter().chain(Some(entity)) {
+                    if let Err(e) = entities.delete(dead) {
+                        error!("Failed to delete world bar: {}", e);
+                    }
+                }
+                continue;
+            }
+            let target = match globals.get(bar.target) {
+                Some(global) => global.0.column(3).xyz(),
+                None => continue,
+            };
+            bar.update_fade(delta);
+
+            let mut transform = Transform::default();
+            transform.set_position(target + bar.offset);
+            if let Some(facing) = facing {
+                transform.set_rotation(facing);
+            }
+            if let Err(e) = transforms.insert(entity, transform) {
+                error!("Failed to place world bar: {}", e);
+                continue;
+            }
+
+            let (background, foreground) = match bar.parts {
+                Some(parts) => parts,
+                None => {
+                    let mut spawn = |graphic: &BarGraphic| {
+                        let mut builder = entities
+                            .build_entity()
+                            .with(Parent { entity }, &mut parents)
+                            .with(Transform::default(), &mut transforms)
+                            .with(Transparent, &mut transparents);
+                        builder = match *graphic {
+                            BarGraphic::Mesh {
+                                ref mesh,
+                                ref material,
+                            } => builder
+                                .with(mesh.clone(), &mut meshes)
+                                .with(material.clone(), &mut materials),
+                            BarGraphic::Sprite(ref sprite) => {
+                                builder.with(sprite.clone(), &mut sprites)
+                            }
+                        };
+                        builder.build()
+                    };
+                    let parts = (spawn(&bar.background), spawn(&bar.foreground));
+                    bar.parts = Some(parts);
+                    parts
+                }
+            };
+
+            let parts = [
+                (background, &bar.background, bar.background_color, 1., 0.),
+                (
+                    foreground,
+                    &bar.foreground,
+                    bar.foreground_color,
+                    bar.value,
+                    FOREGROUND_DEPTH,
+                ),
+            ];
+            for &(part, graphic, color, fill, depth) in &parts {
+                // Sprites are as large as their pixels, quads are one unit.
+                let (width, height) = match *graphic {
+                    BarGraphic::Mesh { .. } => (1., 1.),
+                    BarGraphic::Sprite(ref sprite) => {
+                        match sprite_sheets
+                            .get(&sprite.sprite_sheet)
+                            .and_then(|sheet| sheet.sprites.get(sprite.sprite_number))
+                        {
+                            Some(sprite) => (sprite.width, sprite.height),
+                            None => continue,
+                        }
+                    }
+                };
+                if let Some(transform) = transforms.get_mut(part) {
+                    // Keep the filled part on the left of the bar.
+                    let filled = bar.width * fill;
+                    transform.set_xyz((filled - bar.width) / 2., 0., depth);
+                    transform.set_scale(filled / width, bar.height / height, 1.);
+                }
+                let mut color = color;
+                color.3 *= bar.fade.alpha;
+                colors.insert(part, color).ok();
+
+                let visible = bar.fade.alpha > 0. && fill > 0.;
+                if visible {
+                    hiddens.remove(part);
+                } else if !hiddens.contains(part) {
+                    hiddens.insert(part, Hidden).ok();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fades_out_once_idle() {
+        let mut fade = Fade {
+            alpha: 1.,
+            idle: 0.,
+        };
+        fade.update(false, 1., 0.5, 1.);
+        assert!((fade.alpha - 1.).abs() < 1e-6);
+
+        // The delay passes before fading starts.
+        fade.update(true, 1., 0.5, 0.75);
+        assert!((fade.alpha - 1.).abs() < 1e-6);
+        fade.update(true, 1., 0.5, 0.25);
+        assert!((fade.alpha - 0.5).abs() < 1e-6);
+        fade.update(true, 1., 0.5, 1.);
+        assert!(fade.alpha.abs() < 1e-6);
+
+        fade.update(false, 1., 0.5, 0.25);
+        assert!((fade.alpha - 0.5).abs() < 1e-6);
+    }
+}
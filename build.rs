@@ -1,8 +1,28 @@
+use std::{env, process::Command};
+
 use vergen::{self, ConstantsFlags};
 
 fn main() {
     vergen::generate_cargo_keys(ConstantsFlags::all())
         .unwrap_or_else(|e| panic!("Vergen crate failed to generate version information! {}", e));
 
+    // `std::any::type_name` is stable since Rust 1.38, older compilers get the fallbacks.
+    if rustc_minor_version().map_or(false, |minor| minor >= 38) {
+        println!("cargo:rustc-cfg=type_name");
+    }
+
     println!("cargo:rerun-if-changed=build.rs");
 }
+
+fn rustc_minor_version() -> Option<u32> {
+    let rustc = env::var_os("RUSTC")?;
+    let output = Command::new(rustc).arg("--version").output().ok()?;
+    let version = String::from_utf8(output.stdout).ok()?;
+    version
+        .split_whitespace()
+        .nth(1)?
+        .split('.')
+        .nth(1)?
+        .parse()
+        .ok()
+}
@@ -140,7 +140,7 @@ fn initialise_camera(world: &mut World) {
 fn initialise_lights(world: &mut World) {
     let light: Light = PointLight {
         intensity: 100.0,
-        radius: 1.0,
+        radius: 40.0,
         color: Rgba::white(),
         ..Default::default()
     }
@@ -51,7 +51,7 @@ pub fn load(texture: Handle<Texture>, definition: &sprite::SpriteSheetDefinition
         }
     }
 
-    SpriteSheet { texture, sprites }
+    SpriteSheet::new(texture, sprites)
 }
 
 /// Returns the pixel offset distances per sprite.
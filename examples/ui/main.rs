@@ -62,6 +62,7 @@ impl SimpleState for Example {
                 );
                 Trans::None
             }
+            _ => Trans::None,
         }
     }
 
@@ -0,0 +1,183 @@
+//! Accessibility options for players.
+//!
+//! The [`Accessibility`](struct.Accessibility.html) resource gathers the options a game's
+//! settings menu offers: the [`UiScale`](../ui/struct.UiScale.html) multiplying the sizes of the
+//! UI, the [`ColorFilter`](../renderer/struct.ColorFilter.html) remapping the colors of the
+//! screen for color vision deficiencies, and the actions that are toggled instead of held down.
+//! The [`AccessibilityBundle`](struct.AccessibilityBundle.html) applies them whenever they change.
+//!
+//! The color filter is drawn by a [`DrawColorFilter`](../renderer/struct.DrawColorFilter.html)
+//! pass, so the pipeline has to draw the frame into a target read by it.
+
+pub use crate::{
+    renderer::{ColorDeficiency, ColorFilter, ColorFilterMode, DrawColorFilter},
+    ui::UiScale,
+};
+
+use std::{hash::Hash, marker::PhantomData};
+
+use crate::{
+    core::{bundle::Result, SystemBundle},
+    ecs::prelude::{DispatcherBuilder, Read, Resources, System, Write},
+    input::InputHandler,
+};
+
+/// Resource with the accessibility options, applied by the `AccessibilitySystem`.
+///
+/// It can be loaded from a config file with the settings of the player, for example:
+///
+/// ```ron
+/// (
+///     ui_scale: 1.5,
+///     color_filter: (deficiency: Some(Deuteranopia), mode: Correct, strength: 1.0),
+///     toggle_actions: ["crouch", "aim"],
+/// )
+/// ```
+#[derive(Clone, Debug, Derivative, PartialEq, Deserialize, Serialize)]
+#[derivative(Default(bound = ""))]
+#[serde(default)]
+pub struct Accessibility<AC> {
+    /// Multiplies the sizes of the UI elements and texts.
+    #[derivative(Default(value = "1.0"))]
+    pub ui_scale: f32,
+    /// Remaps the colors of the screen.
+    pub color_filter: ColorFilter,
+    /// The actions switched on and off by pressing them, instead of being held down.
+    pub toggle_actions: Vec<AC>,
+}
+
+/// Applies the `Accessibility` options to the `UiScale`, the `ColorFilter` and the bindings of
+/// the `InputHandler` when they change.
+pub struct AccessibilitySystem<AX, AC> {
+    initial: Option<Accessibility<AC>>,
+    applied: Option<Accessibility<AC>>,
+    _marker: PhantomData<AX>,
+}
+
+impl<AX, AC> AccessibilitySystem<AX, AC> {
+    /// Creates the system, inserting the options on setup.
+    pub fn new(accessibility: Accessibility<AC>) -> Self {
+        AccessibilitySystem {
+            initial: Some(accessibility),
+            applied: None,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, AX, AC> System<'a> for AccessibilitySystem<AX, AC>
+where
+    AX: Hash + Eq + Clone + Send + Sync + 'static,
+    AC: Hash + Eq + Clone + Send + Sync + 'static,
+{
+    type SystemData = (
+        Read<'a, Accessibility<AC>>,
+        Write<'a, UiScale>,
+        Write<'a, ColorFilter>,
+        Write<'a, InputHandler<AX, AC>>,
+    );
+
+    fn run(
+        &mut self,
+        (accessibility, mut ui_scale, mut color_filter, mut input): Self::SystemData,
+    ) {
+        if self.applied.as_ref() == Some(&*accessibility) {
+            return;
+        }
+        ui_scale.0 = accessibility.ui_scale;
+        *color_filter = accessibility.color_filter;
+        if let Some(applied) = self.applied.as_ref() {
+            for action in &applied.toggle_actions {
+                input.bindings.set_toggle(action.clone(), false);
+            }
+        }
+        for action in &accessibility.toggle_actions {
+            input.bindings.set_toggle(action.clone(), true);
+        }
+        self.applied = Some(accessibility.clone());
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        use crate::ecs::prelude::SystemData;
+        Self::SystemData::setup(res);
+        if let Some(initial) = self.initial.take() {
+            res.insert(initial);
+        }
+    }
+}
+
+/// Adds the `AccessibilitySystem`, applying the options to the UI, the renderer and the input.
+///
+/// ## Type parameters
+///
+/// * `AX`: The axis type of the `InputBundle`.
+/// * `AC`: The action type of the `InputBundle`.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let game_data = GameDataBuilder::default()
+///     .with_bundle(InputBundle::<String, String>::new())?
+///     .with_bundle(UiBundle::<String, String>::new())?
+///     .with_bundle(AccessibilityBundle::<String, String>::new().with_ui_scale(1.5))?;
+/// ```
+#[derive(Debug)]
+pub struct AccessibilityBundle<AX = String, AC = String> {
+    accessibility: Accessibility<AC>,
+    _marker: PhantomData<AX>,
+}
+
+impl<AX, AC> AccessibilityBundle<AX, AC> {
+    /// Creates a new bundle with the default options.
+    pub fn new() -> Self {
+        AccessibilityBundle {
+            accessibility: Accessibility::default(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Starts with the options, e.g. loaded from the settings of the player.
+    pub fn with_accessibility(mut self, accessibility: Accessibility<AC>) -> Self {
+        self.accessibility = accessibility;
+        self
+    }
+
+    /// Sets the scale of the UI.
+    pub fn with_ui_scale(mut self, ui_scale: f32) -> Self {
+        self.accessibility.ui_scale = ui_scale;
+        self
+    }
+
+    /// Sets the filter remapping the colors of the screen.
+    pub fn with_color_filter(mut self, color_filter: ColorFilter) -> Self {
+        self.accessibility.color_filter = color_filter;
+        self
+    }
+
+    /// Makes the action toggle instead of being held down.
+    pub fn with_toggle_action<A: Into<AC>>(mut self, action: A) -> Self {
+        self.accessibility.toggle_actions.push(action.into());
+        self
+    }
+}
+
+impl<AX, AC> Default for AccessibilityBundle<AX, AC> {
+    fn default() -> Self {
+        AccessibilityBundle::new()
+    }
+}
+
+impl<'a, 'b, AX, AC> SystemBundle<'a, 'b> for AccessibilityBundle<AX, AC>
+where
+    AX: Hash + Eq + Clone + Send + Sync + 'static,
+    AC: Hash + Eq + Clone + Send + Sync + 'static,
+{
+    fn build(self, builder: &mut DispatcherBuilder<'a, 'b>) -> Result<()> {
+        builder.add(
+            AccessibilitySystem::<AX, AC>::new(self.accessibility),
+            "accessibility_system",
+            &[],
+        );
+        Ok(())
+    }
+}
@@ -1,6 +1,13 @@
 //! The core engine framework.
 
-use std::{error::Error as StdError, marker::PhantomData, path::Path, sync::Arc, time::Duration};
+use std::{
+    env,
+    error::Error as StdError,
+    marker::PhantomData,
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use crate::shred::Resource;
 use log::Level;
@@ -11,25 +18,33 @@ use thread_profiler::{register_thread_with_profiler, write_profile};
 use winit::Event;
 
 use crate::{
-    assets::{Loader, Source},
+    args::EngineArgs,
+    assets::{Directory, Loader, Source},
     callback_queue::CallbackQueue,
     core::{
         frame_limiter::{FrameLimiter, FrameRateLimitConfig, FrameRateLimitStrategy},
         shrev::{EventChannel, ReaderId},
-        timing::{Stopwatch, Time},
-        EventReader, Named,
+        timing::{duration_to_secs, Stopwatch, Time},
+        EventReader, Named, PauseState, Rng, RngConfig, ShutdownHandlers,
     },
+    crash::CrashHandler,
     ecs::{
         common::Errors,
         prelude::{Component, Read, World, Write},
     },
     error::{Error, Result},
     game_data::DataInit,
-    state::{State, StateData, StateMachine, TransEvent},
+    lifecycle::{BackgroundConfig, LifecycleTracker},
+    logger::override_level,
+    state::{State, StateData, StateMachine, StateStack, StateTransitionEvent, TransEvent},
     state_event::{StateEvent, StateEventReader},
-    ui::UiEvent,
+    telemetry::{Telemetry, TelemetryHooks},
+    watchdog::{Watchdog, WatchdogHandle},
 };
 
+/// Maximum number of fixed updates per frame, before the remaining fixed steps are skipped.
+const MAX_FIXED_UPDATES_PER_FRAME: u32 = 8;
+
 /// `CoreApplication` is the application implementation for the game engine. This is fully generic
 /// over the state type and event type.
 ///
@@ -58,8 +73,17 @@ where
     event_reader_id: ReaderId<Event>,
     #[derivative(Debug = "ignore")]
     trans_reader_id: ReaderId<TransEvent<T, E>>,
+    transition_reader_id: Option<ReaderId<StateTransitionEvent>>,
     states: StateMachine<'a, T, E>,
     ignore_window_close: bool,
+    crash_handler: Option<CrashHandler>,
+    #[derivative(Debug = "ignore")]
+    telemetry: Option<TelemetryHooks>,
+    #[derivative(Debug = "ignore")]
+    lifecycle: LifecycleTracker,
+    #[derivative(Debug = "ignore")]
+    watchdog: Option<WatchdogHandle>,
+    shutdown_timeout: Duration,
     data: T,
 }
 
@@ -149,7 +173,8 @@ where
     ///
     /// # Parameters
     ///
-    /// - `path`: The default path for asset loading.
+    /// - `path`: The default path for asset loading. On Android, assets are loaded from the
+    ///   APK instead.
     ///
     /// - `initial_state`: The initial State handler of your game See
     ///   [State](trait.State.html) for more information on what this is.
@@ -227,16 +252,30 @@ where
         for<'b> R: EventReader<'b, Event = E>,
     {
         self.initialize();
+        if let Some(ref watchdog) = self.watchdog {
+            watchdog.finish_frame(0, self.states.state_names());
+        }
         self.world.write_resource::<Stopwatch>().start();
         while self.states.is_running() {
             self.advance_frame();
 
+            self.enter_phase("frame_limiter");
             self.world.write_resource::<FrameLimiter>().wait();
             {
                 let elapsed = self.world.read_resource::<Stopwatch>().elapsed();
                 let mut time = self.world.write_resource::<Time>();
+                if let Some(ref telemetry) = self.telemetry {
+                    if elapsed > telemetry.frame_spike_threshold {
+                        telemetry
+                            .telemetry
+                            .on_frame_spike(time.frame_number(), elapsed);
+                    }
+                }
                 time.increment_frame_number();
                 time.set_delta_time(elapsed);
+                if let Some(ref watchdog) = self.watchdog {
+                    watchdog.finish_frame(time.frame_number(), self.states.state_names());
+                }
             }
             let mut stopwatch = self.world.write_resource::<Stopwatch>();
             stopwatch.stop();
@@ -246,6 +285,13 @@ where
         self.shutdown();
     }
 
+    /// Tells the watchdog, if any, which part of the frame the main loop is in.
+    fn enter_phase(&self, phase: &'static str) {
+        if let Some(ref watchdog) = self.watchdog {
+            watchdog.enter(phase);
+        }
+    }
+
     /// Sets up the application.
     fn initialize(&mut self) {
         #[cfg(feature = "profiler")]
@@ -260,7 +306,7 @@ where
         if self.ignore_window_close {
             false
         } else {
-            use crate::renderer::WindowEvent;
+            use winit::WindowEvent;
             let world = &mut self.world;
             let reader_id = &mut self.event_reader_id;
             world.exec(|ev: Read<'_, EventChannel<Event>>| {
@@ -297,6 +343,7 @@ where
         for<'b> R: EventReader<'b, Event = E>,
     {
         trace!("Advancing frame (`Application::advance_frame`)");
+        self.enter_phase("transitions");
         if self.should_close() {
             let world = &mut self.world;
             let states = &mut self.states;
@@ -319,6 +366,7 @@ where
             }
         }
 
+        self.enter_phase("callback_queue");
         {
             #[cfg(feature = "profiler")]
             profile_scope!("run_callback_queue");
@@ -329,10 +377,12 @@ where
             }
         }
 
+        self.enter_phase("handle_event");
         {
             #[cfg(feature = "profiler")]
             profile_scope!("handle_event");
 
+            self.lifecycle.update(&self.world);
             {
                 let events = &mut self.events;
                 self.reader.read(self.world.system_data(), events);
@@ -346,29 +396,70 @@ where
                 }
             }
         }
+        self.enter_phase("fixed_update");
         {
-            let do_fixed = {
-                let time = self.world.write_resource::<Time>();
-                time.last_fixed_update().elapsed() >= time.fixed_time()
-            };
             #[cfg(feature = "profiler")]
             profile_scope!("fixed_update");
-            if do_fixed {
+            // Run as many fixed updates as needed to catch up with real time, all before the
+            // variable update, so that systems dispatched in `update` see the latest fixed step.
+            let mut fixed_updates = 0;
+            loop {
+                let do_fixed = {
+                    let time = self.world.read_resource::<Time>();
+                    time.last_fixed_update().elapsed() >= time.fixed_time()
+                };
+                if !do_fixed {
+                    break;
+                }
+                if fixed_updates == MAX_FIXED_UPDATES_PER_FRAME {
+                    // Too far behind, e.g. after a long hitch. Drop the backlog instead of
+                    // making every following frame slower.
+                    self.world.write_resource::<Time>().last_fixed_update = Instant::now();
+                    break;
+                }
                 self.states
                     .fixed_update(StateData::new(&mut self.world, &mut self.data));
                 self.world.write_resource::<Time>().finish_fixed_update();
+                fixed_updates += 1;
+            }
+            {
+                let mut time = self.world.write_resource::<Time>();
+                let alpha =
+                    duration_to_secs(time.last_fixed_update().elapsed()) / time.fixed_seconds();
+                time.set_fixed_alpha(alpha);
             }
 
             #[cfg(feature = "profiler")]
             profile_scope!("update");
+            self.enter_phase("update");
             self.states
                 .update(StateData::new(&mut self.world, &mut self.data));
         }
 
         #[cfg(feature = "profiler")]
         profile_scope!("maintain");
+        self.enter_phase("maintain");
         self.world.maintain();
 
+        if let Some(ref crash_handler) = self.crash_handler {
+            let frame_number = self.world.read_resource::<Time>().frame_number();
+            crash_handler.update_context(frame_number, self.states.state_names());
+        }
+
+        if let (Some(ref telemetry), Some(ref mut reader)) =
+            (&self.telemetry, &mut self.transition_reader_id)
+        {
+            let transitions = self
+                .world
+                .read_resource::<EventChannel<StateTransitionEvent>>();
+            for transition in transitions.read(reader) {
+                telemetry.telemetry.on_state_transition(transition);
+            }
+            for failure in self.world.read_resource::<Loader>().take_load_failures() {
+                telemetry.telemetry.on_asset_load_failure(&failure);
+            }
+        }
+
         // TODO: replace this with a more customizable method.
         // TODO: effectively, the user should have more control over error handling here
         // TODO: because right now the app will just exit in case of an error.
@@ -378,8 +469,15 @@ where
     /// Cleans up after the quit signal is received.
     fn shutdown(&mut self) {
         info!("Engine is shutting down");
+        // Shutdown handlers have their own timeout.
+        self.watchdog = None;
 
-        // Placeholder.
+        let mut handlers = std::mem::replace(
+            &mut *self.world.write_resource::<ShutdownHandlers>(),
+            ShutdownHandlers::default(),
+        );
+        // Handlers that don't finish in time are logged by `run`.
+        handlers.run(&mut self.world, self.shutdown_timeout);
     }
 }
 
@@ -405,6 +503,11 @@ pub struct ApplicationBuilder<S, T, E, R> {
     /// Used by bundles to access the world directly
     pub world: World,
     ignore_window_close: bool,
+    crash_handler: Option<CrashHandler>,
+    telemetry: Option<TelemetryHooks>,
+    watchdog: Option<Watchdog>,
+    background: BackgroundConfig,
+    shutdown_timeout: Duration,
     phantom: PhantomData<(T, E, R)>,
 }
 
@@ -505,16 +608,37 @@ where
             .build()
             .map(Arc::new)
             .map_err(|err| Error::Core(err.description().to_string().into()))?;
-        world.add_resource(Loader::new(path.as_ref().to_owned(), pool.clone()));
+        #[cfg(not(target_os = "android"))]
+        let loader = Loader::new(path.as_ref().to_owned(), pool.clone());
+        // Assets are packaged in the APK on Android, where there is no directory to load from.
+        #[cfg(target_os = "android")]
+        let loader = {
+            let _ = path;
+            Loader::with_default_source(crate::assets::ApkSource::new(), pool.clone())
+        };
+        world.add_resource(loader);
         world.add_resource(pool);
         world.add_resource(EventChannel::<Event>::with_capacity(2000));
-        world.add_resource(EventChannel::<UiEvent>::with_capacity(40));
+        #[cfg(feature = "amethyst_ui")]
+        world.add_resource(EventChannel::<crate::ui::UiEvent>::with_capacity(40));
         world.add_resource(EventChannel::<TransEvent<T, StateEvent>>::with_capacity(2));
+        world.add_resource(EventChannel::<StateTransitionEvent>::with_capacity(8));
+        world.add_resource(StateStack::default());
+        world.add_resource(ShutdownHandlers::default());
         world.add_resource(Errors::default());
         world.add_resource(FrameLimiter::default());
         world.add_resource(Stopwatch::default());
         world.add_resource(Time::default());
+        world.add_resource(PauseState::default());
         world.add_resource(CallbackQueue::default());
+        #[cfg(feature = "file_dialog")]
+        {
+            let callbacks = world.read_resource::<CallbackQueue>().send_handle();
+            world.add_resource(crate::file_dialog::FileDialogs::new(callbacks));
+            world.add_resource(
+                EventChannel::<crate::file_dialog::FileDialogEvent>::with_capacity(4),
+            );
+        }
 
         world.register::<Named>();
 
@@ -522,6 +646,11 @@ where
             initial_state,
             world,
             ignore_window_close: false,
+            crash_handler: None,
+            telemetry: None,
+            watchdog: None,
+            background: BackgroundConfig::default(),
+            shutdown_timeout: Duration::from_secs(5),
             phantom: PhantomData,
         })
     }
@@ -810,6 +939,183 @@ where
         self
     }
 
+    /// Installs a crash handler that writes a crash report to disk when the game panics.
+    ///
+    /// The handler's panic hook is installed when the application is built. The application keeps
+    /// the state stack recorded in the report up to date.
+    ///
+    /// # Parameters
+    ///
+    /// `crash_handler`: The configured crash handler.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the ApplicationBuilder after modifying it.
+    pub fn with_crash_handler(mut self, crash_handler: CrashHandler) -> Self {
+        self.crash_handler = Some(crash_handler);
+        self
+    }
+
+    /// Seeds the [`Rng`](../amethyst_core/struct.Rng.html) resource.
+    ///
+    /// Without a seed, the `Rng` is seeded from the system time and the seed is logged.
+    ///
+    /// # Parameters
+    ///
+    /// `config`: The random number generator configuration.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the ApplicationBuilder after modifying it.
+    pub fn with_rng_config(mut self, config: RngConfig) -> Self {
+        self.world.add_resource(Rng::from_config(&config));
+        self
+    }
+
+    /// Reports engine events to the given telemetry hooks.
+    ///
+    /// The hooks are called on state transitions, asset load failures, crashes and frames that
+    /// take longer than `frame_spike_threshold`.
+    ///
+    /// # Parameters
+    ///
+    /// `telemetry`: The hooks to call.
+    /// `frame_spike_threshold`: Frame time above which `Telemetry::on_frame_spike` is called.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the ApplicationBuilder after modifying it.
+    pub fn with_telemetry<M>(mut self, telemetry: M, frame_spike_threshold: Duration) -> Self
+    where
+        M: Telemetry,
+    {
+        self.telemetry = Some(TelemetryHooks {
+            telemetry: Arc::new(telemetry),
+            frame_spike_threshold,
+        });
+        self
+    }
+
+    /// Starts a watchdog, which logs a report when a frame takes longer than its threshold, e.g.
+    /// because the game froze.
+    ///
+    /// # Parameters
+    ///
+    /// `watchdog`: The configured watchdog.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the ApplicationBuilder after modifying it.
+    pub fn with_watchdog(mut self, watchdog: Watchdog) -> Self {
+        self.watchdog = Some(watchdog);
+        self
+    }
+
+    /// Sets how long the [`ShutdownHandlers`](core/struct.ShutdownHandlers.html) may take to
+    /// finish after the last state stopped, defaults to five seconds.
+    ///
+    /// # Parameters
+    ///
+    /// `timeout`: The time after which unfinished handlers are abandoned.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the ApplicationBuilder after modifying it.
+    pub fn with_shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = timeout;
+        self
+    }
+
+    /// Sets what the application does while it is in the background, i.e. unfocused, minimized
+    /// or suspended by the OS.
+    ///
+    /// Whether the application is in the background can always be checked with the
+    /// [`AppLifecycle`](struct.AppLifecycle.html) resource, and changes are sent to states as
+    /// `StateEvent::Lifecycle`. By default nothing else happens.
+    ///
+    /// # Parameters
+    ///
+    /// `config`: The frame rate limit and audio behavior while in the background.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the ApplicationBuilder after modifying it.
+    pub fn with_background_config(mut self, config: BackgroundConfig) -> Self {
+        self.background = config;
+        self
+    }
+
+    /// Parses the standard engine flags from the process' command line arguments.
+    ///
+    /// `--assets-dir` replaces the default asset source and `--loglevel` replaces the level of the
+    /// [`Logger`](struct.Logger.html), whether it was started already or is started later. The
+    /// parsed flags, including every argument that is not an engine flag, are added as an
+    /// [`EngineArgs`](struct.EngineArgs.html) resource. Flags that configure the window, and
+    /// `--headless`, need to be applied to the `DisplayConfig` with
+    /// [`EngineArgs::apply_to`](struct.EngineArgs.html#method.apply_to).
+    ///
+    /// # Returns
+    ///
+    /// This function returns the ApplicationBuilder after modifying it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a flag is missing its value or the value is malformed.
+    ///
+    /// # Examples
+    ///
+    /// ~~~no_run
+    /// use amethyst::prelude::*;
+    /// use amethyst::EngineArgs;
+    ///
+    /// struct GameState;
+    /// impl SimpleState for GameState {
+    ///     fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+    ///         let args = data.world.read_resource::<EngineArgs>();
+    ///         println!("Game arguments: {:?}", args.remaining);
+    ///     }
+    /// }
+    ///
+    /// let mut game = Application::build("assets/", GameState)
+    ///     .expect("Failed to initialize")
+    ///     .with_args()
+    ///     .expect("Invalid arguments")
+    ///     .build(GameDataBuilder::default())
+    ///     .expect("Failed to build game");
+    /// game.run();
+    /// ~~~
+    pub fn with_args(self) -> Result<Self> {
+        self.with_args_from(env::args().skip(1))
+    }
+
+    /// Parses the standard engine flags from the given arguments.
+    ///
+    /// This behaves the same as [`with_args`](#method.with_args), but takes the arguments
+    /// explicitly. The program name must not be included.
+    ///
+    /// # Parameters
+    ///
+    /// `args`: The arguments to parse.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the ApplicationBuilder after modifying it.
+    pub fn with_args_from<I, A>(mut self, args: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = A>,
+        A: Into<String>,
+    {
+        let args = EngineArgs::parse(args)?;
+        if let Some(ref dir) = args.assets_dir {
+            self = self.with_default_source(Directory::new(dir));
+        }
+        if let Some(level) = args.log_level {
+            override_level(level);
+        }
+        self.world.add_resource(args);
+        Ok(self)
+    }
+
     /// Build an `Application` object using the `ApplicationBuilder` as configured.
     ///
     /// # Returns
@@ -845,6 +1151,10 @@ where
         #[cfg(feature = "profiler")]
         profile_scope!("new");
 
+        if let Some(ref crash_handler) = self.crash_handler {
+            crash_handler.install();
+        }
+
         let mut reader = X::default();
         reader.setup(&mut self.world.res);
         let data = init.build(&mut self.world);
@@ -856,15 +1166,40 @@ where
             .world
             .exec(|mut ev: Write<'_, EventChannel<TransEvent<T, E>>>| ev.register_reader());
 
+        if !self.world.res.has_value::<Rng>() {
+            self.world.add_resource(Rng::default());
+        }
+        let lifecycle = LifecycleTracker::new(&mut self.world, self.background);
+
+        let states = StateMachine::new(self.initial_state);
+        let mut transition_reader_id = None;
+        if let Some(ref telemetry) = self.telemetry {
+            telemetry.install_panic_hook();
+            self.world
+                .write_resource::<Loader>()
+                .set_failure_reporting(true);
+            transition_reader_id = Some(
+                self.world
+                    .write_resource::<EventChannel<StateTransitionEvent>>()
+                    .register_reader(),
+            );
+        }
+
         Ok(CoreApplication {
             world: self.world,
-            states: StateMachine::new(self.initial_state),
+            states,
             reader,
             events: Vec::new(),
             ignore_window_close: self.ignore_window_close,
+            crash_handler: self.crash_handler,
+            telemetry: self.telemetry,
+            lifecycle,
+            watchdog: self.watchdog.map(Watchdog::start),
+            shutdown_timeout: self.shutdown_timeout,
             data,
             event_reader_id,
             trans_reader_id,
+            transition_reader_id,
         })
     }
 }
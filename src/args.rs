@@ -0,0 +1,236 @@
+//! Parsing of the standard engine command line flags.
+
+use std::{
+    error::Error as StdError,
+    fmt::{Display, Formatter, Result as FmtResult},
+    path::PathBuf,
+    str::FromStr,
+};
+
+use log::LevelFilter;
+
+#[cfg(feature = "amethyst_renderer")]
+use crate::renderer::DisplayConfig;
+
+/// Command line flags understood by the engine.
+///
+/// Added as a resource by
+/// [`ApplicationBuilder::with_args`](struct.ApplicationBuilder.html#method.with_args). Arguments
+/// that are not engine flags are kept in `remaining`, in their original order, so the game can
+/// parse them itself.
+///
+/// The supported flags are:
+///
+/// * `--windowed`: Disables fullscreen mode.
+/// * `--resolution <WIDTH>x<HEIGHT>`: Sets the window dimensions.
+/// * `--assets-dir <PATH>`: Loads assets from the given directory.
+/// * `--loglevel <LEVEL>`: Sets the maximum log level, e.g. `warn` or `trace`.
+/// * `--headless`: Renders offscreen without showing a window.
+///
+/// Flags taking a value accept both `--flag value` and `--flag=value`. Everything following a
+/// `--` argument is passed on to the game untouched.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EngineArgs {
+    /// `--windowed` was passed.
+    pub windowed: bool,
+    /// Window dimensions passed with `--resolution`.
+    pub resolution: Option<(u32, u32)>,
+    /// Asset directory passed with `--assets-dir`.
+    pub assets_dir: Option<PathBuf>,
+    /// Log level passed with `--loglevel`.
+    pub log_level: Option<LevelFilter>,
+    /// `--headless` was passed.
+    ///
+    /// `apply_to` turns on `DisplayConfig::headless`. The application decides which other
+    /// bundles to leave out when running headless, e.g. audio.
+    pub headless: bool,
+    /// Arguments that are not engine flags.
+    pub remaining: Vec<String>,
+}
+
+impl EngineArgs {
+    /// Parses the engine flags from a list of arguments, excluding the program name.
+    pub fn parse<I, S>(args: I) -> Result<Self, ArgsError>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut parsed = EngineArgs::default();
+        let mut args = args.into_iter().map(Into::into);
+
+        while let Some(arg) = args.next() {
+            if arg == "--" {
+                parsed.remaining.extend(args.by_ref());
+                break;
+            }
+
+            let (flag, inline_value) = match arg.find('=') {
+                Some(index) if arg.starts_with("--") => {
+                    (arg[..index].to_string(), Some(arg[index + 1..].to_string()))
+                }
+                _ => (arg.clone(), None),
+            };
+            let mut value = || {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| ArgsError::MissingValue(flag.clone()))
+            };
+
+            match flag.as_str() {
+                "--windowed" => parsed.windowed = true,
+                "--headless" => parsed.headless = true,
+                "--resolution" => parsed.resolution = Some(parse_resolution(&value()?)?),
+                "--assets-dir" => parsed.assets_dir = Some(PathBuf::from(value()?)),
+                "--loglevel" => {
+                    let level = value()?;
+                    parsed.log_level = Some(
+                        LevelFilter::from_str(&level)
+                            .map_err(|_| ArgsError::InvalidLogLevel(level))?,
+                    );
+                }
+                _ => parsed.remaining.push(arg),
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    /// Overrides the values of a `DisplayConfig` with the flags that were passed.
+    #[cfg(feature = "amethyst_renderer")]
+    pub fn apply_to(&self, config: &mut DisplayConfig) {
+        if self.windowed {
+            config.fullscreen = false;
+        }
+        if let Some(resolution) = self.resolution {
+            config.dimensions = Some(resolution);
+        }
+        if self.headless {
+            config.headless = true;
+        }
+    }
+}
+
+fn parse_resolution(value: &str) -> Result<(u32, u32), ArgsError> {
+    let invalid = || ArgsError::InvalidResolution(value.to_string());
+    let mut parts = value.splitn(2, |c| c == 'x' || c == 'X');
+    let width = parts.next().ok_or_else(invalid)?;
+    let height = parts.next().ok_or_else(invalid)?;
+    match (width.trim().parse(), height.trim().parse()) {
+        (Ok(width), Ok(height)) if width > 0 && height > 0 => Ok((width, height)),
+        _ => Err(invalid()),
+    }
+}
+
+/// Error produced when parsing the engine flags.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ArgsError {
+    /// A flag that requires a value was the last argument.
+    MissingValue(String),
+    /// The value of `--resolution` was not of the form `<WIDTH>x<HEIGHT>`.
+    InvalidResolution(String),
+    /// The value of `--loglevel` was not a known log level.
+    InvalidLogLevel(String),
+}
+
+impl StdError for ArgsError {
+    fn description(&self) -> &str {
+        match *self {
+            ArgsError::MissingValue(_) => "Missing value for flag",
+            ArgsError::InvalidResolution(_) => "Invalid resolution",
+            ArgsError::InvalidLogLevel(_) => "Invalid log level",
+        }
+    }
+}
+
+impl Display for ArgsError {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> FmtResult {
+        match *self {
+            ArgsError::MissingValue(ref flag) => write!(fmt, "`{}` requires a value", flag),
+            ArgsError::InvalidResolution(ref value) => write!(
+                fmt,
+                "Invalid resolution `{}`, expected `<WIDTH>x<HEIGHT>`",
+                value
+            ),
+            ArgsError::InvalidLogLevel(ref value) => write!(
+                fmt,
+                "Invalid log level `{}`, expected one of `off`, `error`, `warn`, `info`, \
+                 `debug` or `trace`",
+                value
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use log::LevelFilter;
+
+    use super::{ArgsError, EngineArgs};
+
+    #[test]
+    fn parses_engine_flags_and_keeps_remainder() {
+        let args = EngineArgs::parse(vec![
+            "--windowed",
+            "--level",
+            "3",
+            "--resolution",
+            "1280x720",
+            "--assets-dir=resources",
+            "--loglevel",
+            "debug",
+            "--headless",
+        ])
+        .unwrap();
+
+        assert!(args.windowed);
+        assert!(args.headless);
+        assert_eq!(Some((1280, 720)), args.resolution);
+        assert_eq!(Some(PathBuf::from("resources")), args.assets_dir);
+        assert_eq!(Some(LevelFilter::Debug), args.log_level);
+        assert_eq!(vec!["--level", "3"], args.remaining);
+    }
+
+    #[test]
+    fn arguments_after_separator_are_not_parsed() {
+        let args = EngineArgs::parse(vec!["--", "--windowed"]).unwrap();
+
+        assert!(!args.windowed);
+        assert_eq!(vec!["--windowed"], args.remaining);
+    }
+
+    #[cfg(feature = "amethyst_renderer")]
+    #[test]
+    fn flags_apply_to_display_config() {
+        use crate::renderer::DisplayConfig;
+
+        let mut config = DisplayConfig {
+            fullscreen: true,
+            ..Default::default()
+        };
+        EngineArgs::parse(vec!["--windowed", "--headless", "--resolution=640x480"])
+            .unwrap()
+            .apply_to(&mut config);
+        assert!(!config.fullscreen);
+        assert!(config.headless);
+        assert_eq!(Some((640, 480)), config.dimensions);
+    }
+
+    #[test]
+    fn invalid_values_are_errors() {
+        assert_eq!(
+            Err(ArgsError::InvalidResolution("1280".to_string())),
+            EngineArgs::parse(vec!["--resolution", "1280"])
+        );
+        assert_eq!(
+            Err(ArgsError::InvalidLogLevel("loud".to_string())),
+            EngineArgs::parse(vec!["--loglevel=loud"])
+        );
+        assert_eq!(
+            Err(ArgsError::MissingValue("--assets-dir".to_string())),
+            EngineArgs::parse(vec!["--assets-dir"])
+        );
+    }
+}
@@ -0,0 +1,414 @@
+//! Opt-in crash reporting.
+//!
+//! A `CrashHandler` installs a panic hook that writes a crash report to disk. The report contains
+//! the panic message and backtrace, the most recent log lines, the versions of the engine and the
+//! game and how they were built, and the state stack at the time of the crash.
+
+use std::{
+    collections::VecDeque,
+    fmt::Write as FmtWrite,
+    fs::{self, OpenOptions},
+    io::{self, ErrorKind, Write},
+    panic::{self, PanicInfo},
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use backtrace::Backtrace;
+use log::{Log, Metadata, Record};
+
+/// Ring buffer of the most recent log lines, attached to crash reports.
+///
+/// Breadcrumbs are collected by passing them to
+/// [`Logger::with_breadcrumbs`](struct.Logger.html#method.with_breadcrumbs). Games may also push
+/// their own entries, such as "Loaded level 3".
+#[derive(Clone, Debug)]
+pub struct Breadcrumbs {
+    lines: Arc<Mutex<VecDeque<String>>>,
+    capacity: usize,
+}
+
+impl Breadcrumbs {
+    /// Creates a new `Breadcrumbs` buffer that keeps the last `capacity` entries.
+    pub fn new(capacity: usize) -> Self {
+        Breadcrumbs {
+            lines: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    /// Adds an entry, dropping the oldest one if the buffer is full.
+    pub fn push<S: Into<String>>(&self, line: S) {
+        if self.capacity == 0 {
+            return;
+        }
+        if let Ok(mut lines) = self.lines.lock() {
+            if lines.len() == self.capacity {
+                lines.pop_front();
+            }
+            lines.push_back(line.into());
+        }
+    }
+
+    /// Returns a copy of the current entries, oldest first.
+    pub fn snapshot(&self) -> Vec<String> {
+        match self.lines.lock() {
+            Ok(lines) => lines.iter().cloned().collect(),
+            // A logging thread panicked while holding the lock, but the lines are still useful.
+            Err(poisoned) => poisoned.into_inner().iter().cloned().collect(),
+        }
+    }
+}
+
+impl Default for Breadcrumbs {
+    fn default() -> Self {
+        Breadcrumbs::new(100)
+    }
+}
+
+/// `Log` implementation that records every log line as a breadcrumb.
+pub(crate) struct BreadcrumbLog(pub(crate) Breadcrumbs);
+
+impl Log for BreadcrumbLog {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        self.0.push(format!(
+            "[{}][{}] {}",
+            record.level(),
+            record.target(),
+            record.args()
+        ));
+    }
+
+    fn flush(&self) {}
+}
+
+/// Engine information that changes while the game runs, captured in crash reports.
+#[derive(Debug, Default)]
+pub(crate) struct CrashContext {
+    pub(crate) frame_number: u64,
+    pub(crate) state_stack: Vec<&'static str>,
+}
+
+/// Installs a panic hook that writes crash reports to disk.
+///
+/// Add it to the application with
+/// [`ApplicationBuilder::with_crash_handler`](struct.ApplicationBuilder.html#method.with_crash_handler),
+/// which installs the hook and keeps the state stack in the report up to date.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use amethyst::{prelude::*, Breadcrumbs, CrashHandler, Logger};
+///
+/// struct NullState;
+/// impl EmptyState for NullState {}
+///
+/// let breadcrumbs = Breadcrumbs::new(200);
+/// Logger::from_config(Default::default())
+///     .with_breadcrumbs(&breadcrumbs)
+///     .start();
+///
+/// let mut game = Application::build("assets/", NullState)
+///     .expect("Failed to initialize")
+///     .with_crash_handler(
+///         CrashHandler::new("crash_reports")
+///             .with_breadcrumbs(breadcrumbs)
+///             .with_dialog(true),
+///     )
+///     .build(())
+///     .expect("Failed to build game");
+/// game.run();
+/// ```
+#[derive(Clone, Debug)]
+pub struct CrashHandler {
+    directory: PathBuf,
+    game_version: Option<String>,
+    breadcrumbs: Breadcrumbs,
+    show_dialog: bool,
+    context: Arc<Mutex<CrashContext>>,
+}
+
+impl CrashHandler {
+    /// Creates a crash handler that writes reports into the given directory.
+    ///
+    /// The directory is created when the first report is written.
+    pub fn new<P: AsRef<Path>>(directory: P) -> Self {
+        CrashHandler {
+            directory: directory.as_ref().to_path_buf(),
+            game_version: None,
+            breadcrumbs: Breadcrumbs::default(),
+            show_dialog: false,
+            context: Default::default(),
+        }
+    }
+
+    /// Sets the breadcrumbs included in crash reports.
+    ///
+    /// Use the same `Breadcrumbs` with `Logger::with_breadcrumbs` to include recent log lines.
+    pub fn with_breadcrumbs(mut self, breadcrumbs: Breadcrumbs) -> Self {
+        self.breadcrumbs = breadcrumbs;
+        self
+    }
+
+    /// Sets the version of the game included in crash reports, e.g. `env!("CARGO_PKG_VERSION")`.
+    pub fn with_game_version<S: Into<String>>(mut self, version: S) -> Self {
+        self.game_version = Some(version.into());
+        self
+    }
+
+    /// Sets whether a native dialog reporting the crash is shown to the player.
+    ///
+    /// Disabled by default.
+    pub fn with_dialog(mut self, show_dialog: bool) -> Self {
+        self.show_dialog = show_dialog;
+        self
+    }
+
+    /// Returns the breadcrumbs included in crash reports.
+    pub fn breadcrumbs(&self) -> &Breadcrumbs {
+        &self.breadcrumbs
+    }
+
+    /// Installs the panic hook.
+    ///
+    /// The previously installed hook still runs after the report is written, so panics are
+    /// printed to the terminal as usual.
+    pub fn install(&self) {
+        let handler = self.clone();
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            match handler.write_report(info) {
+                Ok(path) => {
+                    eprintln!("Crash report written to `{}`", path.display());
+                    if handler.show_dialog {
+                        show_dialog(&path);
+                    }
+                }
+                Err(e) => eprintln!("Failed to write crash report: {}", e),
+            }
+            previous(info);
+        }));
+    }
+
+    /// Updates the engine information captured in crash reports.
+    pub(crate) fn update_context<I>(&self, frame_number: u64, state_names: I)
+    where
+        I: Iterator<Item = &'static str>,
+    {
+        if let Ok(mut context) = self.context.lock() {
+            context.frame_number = frame_number;
+            context.state_stack.clear();
+            context.state_stack.extend(state_names);
+        }
+    }
+
+    fn write_report(&self, info: &PanicInfo<'_>) -> io::Result<PathBuf> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let message = if let Some(s) = info.payload().downcast_ref::<&str>() {
+            (*s).to_string()
+        } else if let Some(s) = info.payload().downcast_ref::<String>() {
+            s.clone()
+        } else {
+            "Box<Any>".to_string()
+        };
+        let location = info
+            .location()
+            .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()))
+            .unwrap_or_else(|| "unknown".to_string());
+
+        let (path, mut file) = create_report_file(&self.directory, timestamp)?;
+        let mut report = self.report(&message, &location, timestamp);
+        let _ = writeln!(report);
+        let _ = writeln!(report, "Backtrace:");
+        let _ = writeln!(report, "{:?}", Backtrace::new());
+        file.write_all(report.as_bytes())?;
+        Ok(path)
+    }
+
+    /// Returns the report of a crash, without the backtrace.
+    fn report(&self, message: &str, location: &str, timestamp: u64) -> String {
+        let rustc_meta = rustc_version_runtime::version_meta();
+
+        // Writing into a `String` cannot fail.
+        let mut report = String::new();
+        let _ = writeln!(report, "Amethyst crash report");
+        let _ = writeln!(report, "Time: {} (seconds since UNIX epoch)", timestamp);
+        if let Some(ref game_version) = self.game_version {
+            let _ = writeln!(report, "Game version: {}", game_version);
+        }
+        let _ = writeln!(report, "Version: {}", env!("CARGO_PKG_VERSION"));
+        let _ = writeln!(report, "Platform: {}", env!("VERGEN_TARGET_TRIPLE"));
+        let _ = writeln!(report, "Amethyst git commit: {}", env!("VERGEN_SHA"));
+        let _ = writeln!(
+            report,
+            "Build: {}",
+            if cfg!(debug_assertions) {
+                "debug"
+            } else {
+                "release"
+            }
+        );
+        let _ = writeln!(report, "Features: {}", enabled_features().join(", "));
+        let _ = writeln!(
+            report,
+            "Rustc version: {} {:?}",
+            rustc_meta.semver, rustc_meta.channel
+        );
+        let _ = writeln!(report);
+        let _ = writeln!(report, "Panic: {}", message);
+        let _ = writeln!(report, "Location: {}", location);
+
+        match self.context.lock() {
+            Ok(context) => {
+                let _ = writeln!(report, "Frame: {}", context.frame_number);
+                let _ = writeln!(report, "State stack (bottom to top):");
+                for name in &context.state_stack {
+                    let _ = writeln!(report, "    {}", name);
+                }
+            }
+            Err(_) => {
+                let _ = writeln!(report, "State stack unavailable");
+            }
+        }
+
+        let _ = writeln!(report);
+        let _ = writeln!(report, "Recent log lines:");
+        for line in self.breadcrumbs.snapshot() {
+            let _ = writeln!(report, "    {}", line);
+        }
+        report
+    }
+}
+
+/// Creates a new report file in `directory`, named after the time of the crash.
+///
+/// Reports of crashes in the same second get a counter appended, e.g. `crash-1546300800-1.txt`.
+fn create_report_file(directory: &Path, timestamp: u64) -> io::Result<(PathBuf, fs::File)> {
+    fs::create_dir_all(directory)?;
+    let mut attempt = 0;
+    loop {
+        let name = if attempt == 0 {
+            format!("crash-{}.txt", timestamp)
+        } else {
+            format!("crash-{}-{}.txt", timestamp, attempt)
+        };
+        let path = directory.join(name);
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(ref e) if e.kind() == ErrorKind::AlreadyExists => attempt += 1,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Returns the optional features the engine was built with.
+fn enabled_features() -> Vec<&'static str> {
+    let features = [
+        ("client", cfg!(feature = "client")),
+        ("profiler", cfg!(feature = "profiler")),
+        ("nightly", cfg!(feature = "nightly")),
+        ("sdl_controller", cfg!(feature = "sdl_controller")),
+        ("json", cfg!(feature = "json")),
+        ("saveload", cfg!(feature = "saveload")),
+        ("web_source", cfg!(feature = "web_source")),
+        ("dev_tools", cfg!(feature = "dev_tools")),
+        ("discord", cfg!(feature = "discord")),
+        ("file_dialog", cfg!(feature = "file_dialog")),
+        ("steam", cfg!(feature = "steam")),
+        ("replay", cfg!(feature = "replay")),
+    ];
+    features
+        .iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(name, _)| *name)
+        .collect()
+}
+
+/// Shows a native message box pointing the player to the crash report.
+fn show_dialog(report: &Path) {
+    let text = format!(
+        "The game has crashed. A crash report was written to:\n{}",
+        report.display()
+    );
+
+    let result = if cfg!(target_os = "windows") {
+        Command::new("powershell")
+            .arg("-Command")
+            .arg(format!(
+                "Add-Type -AssemblyName PresentationFramework; \
+                 [System.Windows.MessageBox]::Show('{}', 'Crash')",
+                text.replace('\'', "''")
+            ))
+            .status()
+    } else if cfg!(target_os = "macos") {
+        Command::new("osascript")
+            .arg("-e")
+            .arg(format!(
+                "display alert \"Crash\" message \"{}\" as critical",
+                text.replace('"', "\\\"")
+            ))
+            .status()
+    } else {
+        Command::new("zenity")
+            .arg("--error")
+            .arg("--title=Crash")
+            .arg(format!("--text={}", text))
+            .status()
+    };
+
+    if let Err(e) = result {
+        eprintln!("Failed to show crash dialog: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use super::*;
+
+    #[test]
+    fn breadcrumbs_keep_the_latest_lines() {
+        let breadcrumbs = Breadcrumbs::new(2);
+        breadcrumbs.push("a");
+        breadcrumbs.push("b");
+        breadcrumbs.push("c");
+        assert_eq!(vec!["b", "c"], breadcrumbs.snapshot());
+    }
+
+    #[test]
+    fn report_files_of_the_same_second_are_unique() {
+        let directory = env::temp_dir().join(format!("amethyst-crash-test-{}", process::id()));
+        let (first, _) = create_report_file(&directory, 42).unwrap();
+        let (second, _) = create_report_file(&directory, 42).unwrap();
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(directory.join("crash-42.txt"), first);
+        assert_eq!(directory.join("crash-42-1.txt"), second);
+    }
+
+    #[test]
+    fn report_contains_versions_and_state_stack() {
+        let handler = CrashHandler::new(".").with_game_version("1.2.3");
+        handler.breadcrumbs().push("[INFO][game] Loaded level 3");
+        handler.update_context(7, vec!["Menu", "Gameplay"].into_iter());
+
+        let report = handler.report("boom", "src/main.rs:1:1", 42);
+        assert!(report.contains("Game version: 1.2.3"));
+        assert!(report.contains(&format!("Version: {}", env!("CARGO_PKG_VERSION"))));
+        assert!(report.contains("Build: "));
+        assert!(report.contains("Panic: boom"));
+        assert!(report.contains("Frame: 7"));
+        assert!(report.contains("    Menu\n    Gameplay\n"));
+        assert!(report.contains("    [INFO][game] Loaded level 3"));
+    }
+}
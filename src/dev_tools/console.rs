@@ -0,0 +1,140 @@
+use std::hash::Hash;
+
+use winit::VirtualKeyCode;
+
+use crate::{
+    core::shrev::{EventChannel, ReaderId},
+    ecs::prelude::{Read, Resources, System, Write},
+    input::InputEvent,
+};
+
+use super::DevTools;
+
+/// A command entered into the developer console.
+///
+/// Commands are sent on an `EventChannel<ConsoleCommand>`; games register a reader to handle
+/// their own commands.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConsoleCommand {
+    /// The first word of the entered line.
+    pub name: String,
+    /// The remaining words of the entered line.
+    pub args: Vec<String>,
+}
+
+impl ConsoleCommand {
+    /// Splits an entered line into a command, returns `None` if the line is blank.
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut words = line.split_whitespace().map(String::from);
+        words.next().map(|name| ConsoleCommand {
+            name,
+            args: words.collect(),
+        })
+    }
+}
+
+/// State of the developer console.
+///
+/// The console is opened with the grave key (`` ` ``) while the development tools are enabled.
+#[derive(Clone, Debug, Default)]
+pub struct DevConsole {
+    /// Whether the console is open and receiving typed characters.
+    pub open: bool,
+    /// The line currently being typed.
+    pub input: String,
+    /// Previously entered lines, oldest first.
+    pub history: Vec<String>,
+}
+
+/// Collects typed characters into the console and sends entered commands.
+pub(crate) struct DevConsoleSystem<AC>
+where
+    AC: Hash + Eq + Clone + Send + Sync + 'static,
+{
+    reader: Option<ReaderId<InputEvent<AC>>>,
+}
+
+impl<AC> DevConsoleSystem<AC>
+where
+    AC: Hash + Eq + Clone + Send + Sync + 'static,
+{
+    pub(crate) fn new() -> Self {
+        DevConsoleSystem { reader: None }
+    }
+}
+
+impl<'s, AC> System<'s> for DevConsoleSystem<AC>
+where
+    AC: Hash + Eq + Clone + Send + Sync + 'static,
+{
+    type SystemData = (
+        Read<'s, EventChannel<InputEvent<AC>>>,
+        Read<'s, DevTools>,
+        Write<'s, DevConsole>,
+        Write<'s, EventChannel<ConsoleCommand>>,
+    );
+
+    fn run(&mut self, (events, dev_tools, mut console, mut commands): Self::SystemData) {
+        // Always drain the reader, so events don't pile up while the tools are disabled.
+        let events = events.read(self.reader.as_mut().unwrap());
+        if !dev_tools.enabled {
+            console.open = false;
+            return;
+        }
+
+        for event in events {
+            match *event {
+                InputEvent::KeyPressed {
+                    key_code: VirtualKeyCode::Grave,
+                    ..
+                } => console.open = !console.open,
+                InputEvent::KeyPressed {
+                    key_code: VirtualKeyCode::Back,
+                    ..
+                } if console.open => {
+                    console.input.pop();
+                }
+                InputEvent::KeyPressed {
+                    key_code: VirtualKeyCode::Return,
+                    ..
+                } if console.open => {
+                    let line = std::mem::replace(&mut console.input, String::new());
+                    if let Some(command) = ConsoleCommand::parse(&line) {
+                        console.history.push(line);
+                        commands.single_write(command);
+                    }
+                }
+                InputEvent::KeyTyped(c) if console.open && !c.is_control() && c != '`' => {
+                    console.input.push(c);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        use crate::ecs::prelude::SystemData;
+        Self::SystemData::setup(res);
+        self.reader = Some(
+            res.fetch_mut::<EventChannel<InputEvent<AC>>>()
+                .register_reader(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ConsoleCommand;
+
+    #[test]
+    fn parse_splits_name_and_args() {
+        assert_eq!(
+            Some(ConsoleCommand {
+                name: "spawn".to_string(),
+                args: vec!["enemy".to_string(), "3".to_string()],
+            }),
+            ConsoleCommand::parse("  spawn enemy   3 ")
+        );
+        assert_eq!(None, ConsoleCommand::parse("   "));
+    }
+}
@@ -0,0 +1,123 @@
+use crate::{
+    core::shrev::{EventChannel, ReaderId},
+    ecs::prelude::{Entities, Join, Read, ReadStorage, Resources, System, WriteStorage},
+    renderer::{DebugView, MeshHandle, UvView},
+};
+
+use super::ConsoleCommand;
+
+/// Parses the arguments of the `debug_view` command into the view to show and the ids of the
+/// entities to show it on, all entities with a mesh if there are none.
+fn parse_args(args: &[String]) -> Result<(DebugView, Vec<u32>), String> {
+    let mut view = DebugView::default();
+    let mut ids = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "wireframe" => view.wireframe = true,
+            "normals" => view.normals = true,
+            "checker" => view.uv = UvView::Checkerboard,
+            "mips" => view.uv = UvView::MipLevel,
+            "off" => view = DebugView::default(),
+            _ => match arg.parse() {
+                Ok(id) => ids.push(id),
+                Err(_) => return Err(format!("Unknown debug view {:?}", arg)),
+            },
+        }
+    }
+    Ok((view, ids))
+}
+
+/// Handles the `debug_view` console command, which sets the `DebugView` of entities:
+///
+/// ```text
+/// debug_view wireframe normals 12 15
+/// debug_view checker
+/// debug_view off
+/// ```
+///
+/// The views are `wireframe`, `normals`, `checker` and `mips`, or `off` to remove them, followed
+/// by the ids of the entities, or none for all entities with a mesh.
+pub(crate) struct DebugViewCommandSystem {
+    reader: Option<ReaderId<ConsoleCommand>>,
+}
+
+impl DebugViewCommandSystem {
+    pub(crate) fn new() -> Self {
+        DebugViewCommandSystem { reader: None }
+    }
+}
+
+impl<'s> System<'s> for DebugViewCommandSystem {
+    type SystemData = (
+        Read<'s, EventChannel<ConsoleCommand>>,
+        Entities<'s>,
+        ReadStorage<'s, MeshHandle>,
+        WriteStorage<'s, DebugView>,
+    );
+
+    fn run(&mut self, (commands, entities, meshes, mut views): Self::SystemData) {
+        for command in commands.read(self.reader.as_mut().unwrap()) {
+            if command.name != "debug_view" {
+                continue;
+            }
+            let (view, ids) = match parse_args(&command.args) {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    warn!("{}", e);
+                    continue;
+                }
+            };
+            let targets = if ids.is_empty() {
+                (&*entities, &meshes)
+                    .join()
+                    .map(|(entity, _)| entity)
+                    .collect()
+            } else {
+                ids.into_iter()
+                    .map(|id| entities.entity(id))
+                    .filter(|entity| entities.is_alive(*entity))
+                    .collect::<Vec<_>>()
+            };
+            for entity in targets {
+                if view.is_empty() {
+                    views.remove(entity);
+                } else if let Err(e) = views.insert(entity, view) {
+                    warn!("Failed to set the debug view of {:?}: {}", entity, e);
+                }
+            }
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        use crate::ecs::prelude::SystemData;
+        Self::SystemData::setup(res);
+        self.reader = Some(
+            res.fetch_mut::<EventChannel<ConsoleCommand>>()
+                .register_reader(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn parse_views_and_ids() {
+        let (view, ids) = parse_args(&args("wireframe mips 12 15")).unwrap();
+        assert!(view.wireframe);
+        assert!(!view.normals);
+        assert_eq!(UvView::MipLevel, view.uv);
+        assert_eq!(vec![12, 15], ids);
+
+        let (view, ids) = parse_args(&args("normals off")).unwrap();
+        assert!(view.is_empty());
+        assert!(ids.is_empty());
+
+        assert!(parse_args(&args("shiny")).is_err());
+    }
+}
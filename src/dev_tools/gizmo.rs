@@ -0,0 +1,61 @@
+use crate::{
+    core::{
+        nalgebra::{Point3, Vector3},
+        transform::GlobalTransform,
+    },
+    ecs::prelude::{Component, DenseVecStorage, Join, Read, ReadStorage, System, Write},
+    renderer::{DebugLines, Rgba},
+};
+
+use super::DevTools;
+
+/// Draws the local axes of an entity while the development tools are enabled.
+///
+/// The X, Y and Z axes are drawn in red, green and blue.
+#[derive(Clone, Copy, Debug)]
+pub struct Gizmo {
+    /// Length of the drawn axes, in world units.
+    pub size: f32,
+}
+
+impl Default for Gizmo {
+    fn default() -> Self {
+        Gizmo { size: 1. }
+    }
+}
+
+impl Component for Gizmo {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Draws the axes of every entity with a `Gizmo`.
+pub(crate) struct GizmoSystem;
+
+impl<'s> System<'s> for GizmoSystem {
+    type SystemData = (
+        Read<'s, DevTools>,
+        ReadStorage<'s, GlobalTransform>,
+        ReadStorage<'s, Gizmo>,
+        Write<'s, DebugLines>,
+    );
+
+    fn run(&mut self, (dev_tools, globals, gizmos, mut lines): Self::SystemData) {
+        if !dev_tools.enabled {
+            return;
+        }
+
+        for (global, gizmo) in (&globals, &gizmos).join() {
+            let position: [f32; 3] = global.0.column(3).xyz().into();
+            let origin = Point3::from(position);
+            let axes = [
+                (global.0.column(0).xyz(), Rgba::RED),
+                (global.0.column(1).xyz(), Rgba::GREEN),
+                (global.0.column(2).xyz(), Rgba::BLUE),
+            ];
+            for &(axis, color) in &axes {
+                let direction: Vector3<f32> = axis.normalize() * gizmo.size;
+                lines.draw_direction(origin, direction, color);
+            }
+        }
+    }
+}
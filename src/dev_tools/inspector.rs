@@ -0,0 +1,62 @@
+use crate::{
+    core::{transform::Transform, Named},
+    ecs::prelude::{Entities, Join, Read, ReadStorage, System, Write},
+};
+
+use super::DevTools;
+
+/// Summary of an entity, as shown by an entity inspector.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct InspectedEntity {
+    /// Index of the entity.
+    pub id: u32,
+    /// Generation of the entity.
+    pub generation: i32,
+    /// Name of the entity, if it has a `Named` component.
+    pub name: Option<String>,
+    /// Local translation of the entity, if it has a `Transform`.
+    pub translation: Option<[f32; 3]>,
+}
+
+/// Snapshot of the world's entities, refreshed every frame while the development tools are
+/// enabled.
+///
+/// External inspectors read this resource, for example through a network connection or by
+/// serializing it to a file, instead of accessing the `World` directly.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct InspectorSnapshot {
+    /// Number of the frame the snapshot was taken on, counted while the tools are enabled.
+    pub frame: u64,
+    /// All living entities.
+    pub entities: Vec<InspectedEntity>,
+}
+
+/// Refreshes the `InspectorSnapshot`.
+pub(crate) struct InspectorSystem;
+
+impl<'s> System<'s> for InspectorSystem {
+    type SystemData = (
+        Read<'s, DevTools>,
+        Entities<'s>,
+        ReadStorage<'s, Named>,
+        ReadStorage<'s, Transform>,
+        Write<'s, InspectorSnapshot>,
+    );
+
+    fn run(&mut self, (dev_tools, entities, names, transforms, mut snapshot): Self::SystemData) {
+        if !dev_tools.enabled {
+            return;
+        }
+
+        snapshot.frame += 1;
+        snapshot.entities.clear();
+        for (entity, name, transform) in (&entities, names.maybe(), transforms.maybe()).join() {
+            snapshot.entities.push(InspectedEntity {
+                id: entity.id(),
+                generation: entity.gen().id(),
+                name: name.map(|n| n.name.to_string()),
+                translation: transform.map(|t| (*t.translation()).into()),
+            });
+        }
+    }
+}
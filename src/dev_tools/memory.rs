@@ -0,0 +1,56 @@
+use std::fmt::Write as FmtWrite;
+
+use crate::{
+    core::{
+        shrev::{EventChannel, ReaderId},
+        MemoryStats,
+    },
+    ecs::prelude::{Read, Resources, System},
+};
+
+use super::ConsoleCommand;
+
+/// Handles the `memory` console command, which logs the memory used by every type of asset and
+/// component in the `MemoryStats`, with their high-water marks.
+pub(crate) struct MemoryCommandSystem {
+    reader: Option<ReaderId<ConsoleCommand>>,
+}
+
+impl MemoryCommandSystem {
+    pub(crate) fn new() -> Self {
+        MemoryCommandSystem { reader: None }
+    }
+}
+
+impl<'s> System<'s> for MemoryCommandSystem {
+    type SystemData = (
+        Read<'s, EventChannel<ConsoleCommand>>,
+        Read<'s, MemoryStats>,
+    );
+
+    fn run(&mut self, (commands, stats): Self::SystemData) {
+        for command in commands.read(self.reader.as_mut().unwrap()) {
+            if command.name != "memory" {
+                continue;
+            }
+            let mut report = format!("Memory: {} (peak {})", stats.total(), stats.peak());
+            for (category, name, entry) in stats.entries() {
+                let _ = write!(
+                    report,
+                    "\n  {:?} {}: {} x{} (peak {})",
+                    category, name, entry.usage, entry.count, entry.peak
+                );
+            }
+            info!("{}", report);
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        use crate::ecs::prelude::SystemData;
+        Self::SystemData::setup(res);
+        self.reader = Some(
+            res.fetch_mut::<EventChannel<ConsoleCommand>>()
+                .register_reader(),
+        );
+    }
+}
@@ -0,0 +1,180 @@
+//! Development tools for debug builds.
+//!
+//! The [`DevToolsBundle`](struct.DevToolsBundle.html) packages a developer console, a debug
+//! overlay, transform gizmos, a snapshot of the world for external entity inspectors, input
+//! recording, a `debug_view` console command showing the wireframe, normals or texture coordinates
+//! of meshes, a `dump_pipeline` command writing the structure of the pipeline to a Graphviz or JSON
+//! file and a `memory` command logging the `MemoryStats`. The module is only available with the
+//! `dev_tools` feature, and the bundle only adds its systems to debug builds, so release builds
+//! don't contain any of the tools even when the feature stays enabled.
+//!
+//! At runtime the tools are switched on and off through the [`DevTools`](struct.DevTools.html)
+//! resource, by default with the `F12` key.
+
+pub use self::{
+    console::{ConsoleCommand, DevConsole},
+    gizmo::Gizmo,
+    inspector::{InspectedEntity, InspectorSnapshot},
+    recording::{InputRecording, RecordedInput},
+};
+
+use std::{hash::Hash, marker::PhantomData};
+
+use serde::Serialize;
+use winit::VirtualKeyCode;
+
+use crate::{
+    core::{bundle::Result, SystemBundle},
+    ecs::prelude::DispatcherBuilder,
+};
+
+mod console;
+mod debug_view;
+mod gizmo;
+mod inspector;
+mod memory;
+mod overlay;
+mod pipeline_dump;
+mod recording;
+mod toggle;
+
+/// Runtime switch for the development tools.
+#[derive(Clone, Debug)]
+pub struct DevTools {
+    /// Whether the tools are currently active.
+    pub enabled: bool,
+    /// Key that toggles `enabled`, if any.
+    pub toggle_key: Option<VirtualKeyCode>,
+}
+
+impl Default for DevTools {
+    fn default() -> Self {
+        DevTools {
+            enabled: false,
+            toggle_key: Some(VirtualKeyCode::F12),
+        }
+    }
+}
+
+/// Adds the development tools to the dispatcher.
+///
+/// The bundle requires the `InputBundle` with the same action type, and the `UiBundle` for the
+/// overlay and console. Gizmos are drawn through the `DebugLines` resource, so the pipeline needs
+/// a `DrawDebugLines` pass to show them, and the debug views of meshes need a `DrawDebugView` or
+/// `DrawDebugViewSeparate` pass after the passes drawing the meshes.
+///
+/// In release builds this bundle adds nothing.
+///
+/// ## Type parameters
+///
+/// * `AC`: The action type of the `InputBundle`.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let game_data = GameDataBuilder::default()
+///     .with_bundle(InputBundle::<String, String>::new())?
+///     .with_bundle(UiBundle::<String, String>::new())?
+///     .with_bundle(DevToolsBundle::<String>::new().with_enabled(true))?;
+/// ```
+#[derive(Debug)]
+pub struct DevToolsBundle<AC = String> {
+    dev_tools: DevTools,
+    dep: &'static [&'static str],
+    _marker: PhantomData<AC>,
+}
+
+impl<AC> DevToolsBundle<AC> {
+    /// Creates a new bundle with the tools initially disabled.
+    pub fn new() -> Self {
+        DevToolsBundle {
+            dev_tools: DevTools::default(),
+            dep: &[],
+            _marker: PhantomData,
+        }
+    }
+
+    /// Sets whether the tools are enabled when the game starts.
+    pub fn with_enabled(mut self, enabled: bool) -> Self {
+        self.dev_tools.enabled = enabled;
+        self
+    }
+
+    /// Sets the key that toggles the tools, or `None` to only toggle them from code.
+    pub fn with_toggle_key(mut self, toggle_key: Option<VirtualKeyCode>) -> Self {
+        self.dev_tools.toggle_key = toggle_key;
+        self
+    }
+
+    /// Set dependencies for the tools' systems, e.g. the system moving the entities with gizmos.
+    pub fn with_dep(mut self, dep: &'static [&'static str]) -> Self {
+        self.dep = dep;
+        self
+    }
+}
+
+impl<AC> Default for DevToolsBundle<AC> {
+    fn default() -> Self {
+        DevToolsBundle::new()
+    }
+}
+
+impl<'a, 'b, AC> SystemBundle<'a, 'b> for DevToolsBundle<AC>
+where
+    AC: Hash + Eq + Clone + Send + Sync + Serialize + 'static,
+{
+    #[cfg(debug_assertions)]
+    fn build(self, builder: &mut DispatcherBuilder<'a, 'b>) -> Result<()> {
+        builder.add(
+            toggle::DevToolsToggleSystem::<AC>::new(self.dev_tools),
+            "dev_tools_toggle",
+            &["input_system"],
+        );
+        builder.add(
+            console::DevConsoleSystem::<AC>::new(),
+            "dev_console",
+            &["dev_tools_toggle"],
+        );
+        builder.add(
+            debug_view::DebugViewCommandSystem::new(),
+            "dev_debug_view",
+            &["dev_console"],
+        );
+        builder.add(
+            memory::MemoryCommandSystem::new(),
+            "dev_memory",
+            &["dev_console"],
+        );
+        builder.add(
+            pipeline_dump::PipelineDumpCommandSystem::new(),
+            "dev_pipeline_dump",
+            &["dev_console"],
+        );
+        builder.add(
+            recording::InputRecordingSystem::<AC>::new(),
+            "dev_input_recording",
+            &["input_system"],
+        );
+        builder.add(
+            gizmo::GizmoSystem,
+            "dev_gizmos",
+            &[&["dev_tools_toggle"][..], self.dep].concat(),
+        );
+        builder.add(
+            inspector::InspectorSystem,
+            "dev_inspector",
+            &[&["dev_tools_toggle"][..], self.dep].concat(),
+        );
+        builder.add(
+            overlay::DebugOverlaySystem::default(),
+            "dev_overlay",
+            &["dev_console"],
+        );
+        Ok(())
+    }
+
+    #[cfg(not(debug_assertions))]
+    fn build(self, _: &mut DispatcherBuilder<'a, 'b>) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+use std::fmt::Write as FmtWrite;
+
+use crate::{
+    assets::{AssetStorage, Loader},
+    core::{Bytes, MemoryStats},
+    ecs::prelude::{Entities, Entity, Join, Read, ReadExpect, System, WriteStorage},
+    ui::{get_default_font, Anchor, FontAsset, LineMode, UiText, UiTransform},
+    utils::fps_counter::FPSCounter,
+};
+
+use super::{DevConsole, DevTools};
+
+const OVERLAY_WIDTH: f32 = 600.;
+const OVERLAY_HEIGHT: f32 = 80.;
+
+/// Shows frame rate, entity count, memory usage and the console input line in the top left
+/// corner.
+///
+/// The frame rate is only measured if the `FPSCounterBundle` is added, and the heap usage if the
+/// `TrackingAllocator` is the global allocator.
+#[derive(Default)]
+pub(crate) struct DebugOverlaySystem {
+    entity: Option<Entity>,
+}
+
+impl<'s> System<'s> for DebugOverlaySystem {
+    type SystemData = (
+        Read<'s, DevTools>,
+        Read<'s, DevConsole>,
+        Read<'s, FPSCounter>,
+        Read<'s, MemoryStats>,
+        Entities<'s>,
+        ReadExpect<'s, Loader>,
+        Read<'s, AssetStorage<FontAsset>>,
+        WriteStorage<'s, UiTransform>,
+        WriteStorage<'s, UiText>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            dev_tools,
+            console,
+            fps,
+            memory,
+            entities,
+            loader,
+            fonts,
+            mut transforms,
+            mut texts,
+        ): Self::SystemData,
+    ) {
+        if !dev_tools.enabled {
+            if let Some(entity) = self.entity.take() {
+                if let Err(e) = entities.delete(entity) {
+                    error!("Failed to remove the debug overlay: {}", e);
+                }
+            }
+            return;
+        }
+
+        let entity = match self.entity {
+            Some(entity) => entity,
+            None => {
+                let entity = entities.create();
+                let mut transform = UiTransform::new(
+                    "dev_tools_overlay".to_string(),
+                    Anchor::TopLeft,
+                    OVERLAY_WIDTH / 2. + 5.,
+                    -OVERLAY_HEIGHT / 2. - 5.,
+                    1000.,
+                    OVERLAY_WIDTH,
+                    OVERLAY_HEIGHT,
+                );
+                transform.opaque = false;
+                let mut text = UiText::new(
+                    get_default_font(&loader, &fonts),
+                    String::new(),
+                    [1., 1., 1., 1.],
+                    16.,
+                );
+                text.line_mode = LineMode::Wrap;
+                text.align = Anchor::TopLeft;
+                // The entity was just created, so inserting can't fail.
+                transforms.insert(entity, transform).ok();
+                texts.insert(entity, text).ok();
+                self.entity = Some(entity);
+                entity
+            }
+        };
+
+        if let Some(text) = texts.get_mut(entity) {
+            text.text.clear();
+            let _ = write!(
+                text.text,
+                "FPS: {:.1}  Entities: {}",
+                fps.sampled_fps(),
+                (&*entities).join().count()
+            );
+            let _ = write!(
+                text.text,
+                "\nMemory: {} (peak {})",
+                memory.total(),
+                memory.peak()
+            );
+            if let Some((heap, peak)) = memory.heap() {
+                let _ = write!(text.text, "  Heap: {} (peak {})", Bytes(heap), Bytes(peak));
+            }
+            if console.open {
+                let _ = write!(text.text, "\n> {}_", console.input);
+            }
+        }
+    }
+}
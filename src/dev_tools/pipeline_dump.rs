@@ -0,0 +1,59 @@
+use crate::{
+    core::shrev::{EventChannel, ReaderId},
+    ecs::prelude::{Read, Resources, System, Write},
+    renderer::PipelineDump,
+};
+
+use super::ConsoleCommand;
+
+/// File the pipeline is written to when the `dump_pipeline` command has no argument.
+const DEFAULT_PATH: &str = "pipeline.dot";
+
+/// Handles the `dump_pipeline` console command, which writes the structure of the pipeline, its
+/// targets and which passes read them to a file through the `PipelineDump`:
+///
+/// ```text
+/// dump_pipeline
+/// dump_pipeline frame_graph.json
+/// ```
+///
+/// Paths ending in `.json` are written as JSON, the others as a Graphviz graph, `pipeline.dot`
+/// by default.
+pub(crate) struct PipelineDumpCommandSystem {
+    reader: Option<ReaderId<ConsoleCommand>>,
+}
+
+impl PipelineDumpCommandSystem {
+    pub(crate) fn new() -> Self {
+        PipelineDumpCommandSystem { reader: None }
+    }
+}
+
+impl<'s> System<'s> for PipelineDumpCommandSystem {
+    type SystemData = (
+        Read<'s, EventChannel<ConsoleCommand>>,
+        Write<'s, PipelineDump>,
+    );
+
+    fn run(&mut self, (commands, mut dump): Self::SystemData) {
+        for command in commands.read(self.reader.as_mut().unwrap()) {
+            if command.name != "dump_pipeline" {
+                continue;
+            }
+            match command.args.as_slice() {
+                [] => dump.request(DEFAULT_PATH),
+                [path] => dump.request(path.as_str()),
+                _ => warn!("Usage: dump_pipeline [path]"),
+            }
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        use crate::ecs::prelude::SystemData;
+        Self::SystemData::setup(res);
+        self.reader = Some(
+            res.fetch_mut::<EventChannel<ConsoleCommand>>()
+                .register_reader(),
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use std::{fs, hash::Hash, path::Path};
+
+use ron::ser::{to_string_pretty, PrettyConfig};
+use serde::Serialize;
+
+use crate::{
+    core::{
+        shrev::{EventChannel, ReaderId},
+        timing::Time,
+    },
+    ecs::prelude::{Read, Resources, System, Write},
+    error::{Error, Result},
+    input::InputEvent,
+};
+
+/// An input event, together with the frame it was received on.
+#[derive(Clone, Debug, Serialize)]
+pub struct RecordedInput<AC> {
+    /// Frame number, counted from the start of the recording.
+    pub frame: u64,
+    /// Seconds since the start of the recording.
+    pub time: f64,
+    /// The received event.
+    pub event: InputEvent<AC>,
+}
+
+/// Records input events while recording is started.
+///
+/// Recording works regardless of whether the development tools are enabled, so that toggling the
+/// tools doesn't interrupt a recording.
+#[derive(Clone, Debug)]
+pub struct InputRecording<AC> {
+    recording: bool,
+    frame: u64,
+    time: f64,
+    events: Vec<RecordedInput<AC>>,
+}
+
+impl<AC> Default for InputRecording<AC> {
+    fn default() -> Self {
+        InputRecording {
+            recording: false,
+            frame: 0,
+            time: 0.,
+            events: Vec::new(),
+        }
+    }
+}
+
+impl<AC> InputRecording<AC> {
+    /// Starts a new recording, discarding previously recorded events.
+    pub fn start(&mut self) {
+        self.recording = true;
+        self.frame = 0;
+        self.time = 0.;
+        self.events.clear();
+    }
+
+    /// Stops recording, keeping the recorded events.
+    pub fn stop(&mut self) {
+        self.recording = false;
+    }
+
+    /// Returns true while recording.
+    pub fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    /// Returns the recorded events, oldest first.
+    pub fn events(&self) -> &[RecordedInput<AC>] {
+        &self.events
+    }
+}
+
+impl<AC> InputRecording<AC>
+where
+    AC: Serialize,
+{
+    /// Writes the recorded events to a RON file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let ron = to_string_pretty(&self.events, PrettyConfig::default()).map_err(|e| {
+            Error::Core(format!("Failed to serialize input recording: {}", e).into())
+        })?;
+        fs::write(path, ron)?;
+        Ok(())
+    }
+}
+
+/// Appends received input events to the `InputRecording`.
+pub(crate) struct InputRecordingSystem<AC>
+where
+    AC: Hash + Eq + Clone + Send + Sync + 'static,
+{
+    reader: Option<ReaderId<InputEvent<AC>>>,
+}
+
+impl<AC> InputRecordingSystem<AC>
+where
+    AC: Hash + Eq + Clone + Send + Sync + 'static,
+{
+    pub(crate) fn new() -> Self {
+        InputRecordingSystem { reader: None }
+    }
+}
+
+impl<'s, AC> System<'s> for InputRecordingSystem<AC>
+where
+    AC: Hash + Eq + Clone + Send + Sync + 'static,
+{
+    type SystemData = (
+        Read<'s, EventChannel<InputEvent<AC>>>,
+        Read<'s, Time>,
+        Write<'s, InputRecording<AC>>,
+    );
+
+    fn run(&mut self, (events, time, mut recording): Self::SystemData) {
+        let events = events.read(self.reader.as_mut().unwrap());
+        if !recording.recording {
+            return;
+        }
+
+        for event in events {
+            let (frame, elapsed) = (recording.frame, recording.time);
+            recording.events.push(RecordedInput {
+                frame,
+                time: elapsed,
+                event: event.clone(),
+            });
+        }
+        recording.frame += 1;
+        recording.time += f64::from(time.delta_real_seconds());
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        use crate::ecs::prelude::SystemData;
+        Self::SystemData::setup(res);
+        self.reader = Some(
+            res.fetch_mut::<EventChannel<InputEvent<AC>>>()
+                .register_reader(),
+        );
+    }
+}
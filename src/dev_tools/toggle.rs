@@ -0,0 +1,67 @@
+use std::hash::Hash;
+
+use crate::{
+    core::shrev::{EventChannel, ReaderId},
+    ecs::prelude::{Read, Resources, System, Write},
+    input::InputEvent,
+};
+
+use super::DevTools;
+
+/// Toggles the development tools when the toggle key is pressed.
+pub(crate) struct DevToolsToggleSystem<AC>
+where
+    AC: Hash + Eq + Clone + Send + Sync + 'static,
+{
+    initial: Option<DevTools>,
+    reader: Option<ReaderId<InputEvent<AC>>>,
+}
+
+impl<AC> DevToolsToggleSystem<AC>
+where
+    AC: Hash + Eq + Clone + Send + Sync + 'static,
+{
+    pub(crate) fn new(initial: DevTools) -> Self {
+        DevToolsToggleSystem {
+            initial: Some(initial),
+            reader: None,
+        }
+    }
+}
+
+impl<'s, AC> System<'s> for DevToolsToggleSystem<AC>
+where
+    AC: Hash + Eq + Clone + Send + Sync + 'static,
+{
+    type SystemData = (Read<'s, EventChannel<InputEvent<AC>>>, Write<'s, DevTools>);
+
+    fn run(&mut self, (events, mut dev_tools): Self::SystemData) {
+        for event in events.read(self.reader.as_mut().unwrap()) {
+            if let InputEvent::KeyPressed { key_code, .. } = *event {
+                if Some(key_code) == dev_tools.toggle_key {
+                    dev_tools.enabled = !dev_tools.enabled;
+                    info!(
+                        "Development tools {}",
+                        if dev_tools.enabled {
+                            "enabled"
+                        } else {
+                            "disabled"
+                        }
+                    );
+                }
+            }
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        use crate::ecs::prelude::SystemData;
+        Self::SystemData::setup(res);
+        if let Some(initial) = self.initial.take() {
+            res.insert(initial);
+        }
+        self.reader = Some(
+            res.fetch_mut::<EventChannel<InputEvent<AC>>>()
+                .register_reader(),
+        );
+    }
+}
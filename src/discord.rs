@@ -0,0 +1,202 @@
+//! Discord rich presence, the activity shown to the player's friends on Discord.
+//!
+//! The [`DiscordBundle`](struct.DiscordBundle.html) connects to the Discord client running on the
+//! player's machine, and keeps the activity in sync with the
+//! [`Presence`](struct.Presence.html) resource. It reconnects when Discord is started after the
+//! game, or restarted, and sends the presence again. The module is only available with the
+//! `discord` feature.
+
+use std::time::{Duration, Instant};
+
+use discord_rpc_client::Client;
+
+use crate::{
+    core::{bundle::Result, SystemBundle},
+    ecs::prelude::{DispatcherBuilder, Read, System},
+};
+
+/// Resource with the activity shown on Discord, the same for every field it has left to `None`.
+///
+/// What the fields look like is described in the
+/// [Discord documentation](https://discordapp.com/developers/docs/rich-presence/how-to). The
+/// images are the names of the assets uploaded for the application on the Discord website.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Presence {
+    /// What the player is doing, e.g. `"In a group"`.
+    pub state: Option<String>,
+    /// More about what the player is doing, e.g. `"Competitive - Captain's Mode"`.
+    pub details: Option<String>,
+    /// Number of players in the party of the player, and its largest size.
+    pub party_size: Option<(u32, u32)>,
+    /// When the activity started, as seconds since `UNIX_EPOCH`, to show the elapsed time.
+    pub start_timestamp: Option<u64>,
+    /// When the activity ends, as seconds since `UNIX_EPOCH`, to show the remaining time.
+    pub end_timestamp: Option<u64>,
+    /// Large image shown next to the activity.
+    pub large_image: Option<String>,
+    /// Tooltip of the large image.
+    pub large_text: Option<String>,
+    /// Small image shown over the large image.
+    pub small_image: Option<String>,
+    /// Tooltip of the small image.
+    pub small_text: Option<String>,
+}
+
+impl Presence {
+    /// Checks whether nothing is set, in which case the activity is cleared.
+    pub fn is_empty(&self) -> bool {
+        *self == Presence::default()
+    }
+}
+
+/// Connects to Discord and keeps the activity in sync with the `Presence` resource.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let game_data = GameDataBuilder::default().with_bundle(DiscordBundle::new(CLIENT_ID))?;
+///
+/// // Later, in a system or state:
+/// let mut presence = world.write_resource::<Presence>();
+/// presence.state = Some("In a match".to_owned());
+/// presence.party_size = Some((2, 4));
+/// ```
+#[derive(Debug)]
+pub struct DiscordBundle {
+    client_id: u64,
+    refresh_interval: Duration,
+}
+
+impl DiscordBundle {
+    /// Creates a bundle for the Discord application with the given client id.
+    pub fn new(client_id: u64) -> Self {
+        DiscordBundle {
+            client_id,
+            refresh_interval: Duration::from_secs(15),
+        }
+    }
+
+    /// Sets how often the presence is sent again even if it didn't change, in case Discord was
+    /// restarted or didn't answer, 15 seconds by default.
+    pub fn with_refresh_interval(mut self, refresh_interval: Duration) -> Self {
+        self.refresh_interval = refresh_interval;
+        self
+    }
+}
+
+impl<'a, 'b> SystemBundle<'a, 'b> for DiscordBundle {
+    fn build(self, builder: &mut DispatcherBuilder<'a, 'b>) -> Result<()> {
+        let mut client = Client::new(self.client_id);
+        // Connects on a thread of its own, reconnecting whenever the connection is lost.
+        client.start();
+        builder.add(
+            DiscordPresenceSystem {
+                client,
+                refresh_interval: self.refresh_interval,
+                sent: None,
+                sent_at: None,
+                failed: false,
+            },
+            "discord_presence",
+            &[],
+        );
+        Ok(())
+    }
+}
+
+/// Sends the `Presence` to Discord when it changes.
+struct DiscordPresenceSystem {
+    client: Client,
+    refresh_interval: Duration,
+    /// The presence last sent.
+    sent: Option<Presence>,
+    /// When the presence was last sent.
+    sent_at: Option<Instant>,
+    /// Whether Discord failed to accept the presence last time.
+    failed: bool,
+}
+
+impl DiscordPresenceSystem {
+    fn send(&mut self, presence: &Presence) -> ::std::result::Result<(), String> {
+        let result = if presence.is_empty() {
+            self.client.clear_activity().map(|_| ())
+        } else {
+            self.client
+                .set_activity(|mut activity| {
+                    if let Some(ref state) = presence.state {
+                        activity = activity.state(state.as_str());
+                    }
+                    if let Some(ref details) = presence.details {
+                        activity = activity.details(details.as_str());
+                    }
+                    if let Some(size) = presence.party_size {
+                        activity = activity.party(|party| party.size(size));
+                    }
+                    if presence.start_timestamp.is_some() || presence.end_timestamp.is_some() {
+                        activity = activity.timestamps(|mut timestamps| {
+                            if let Some(start) = presence.start_timestamp {
+                                timestamps = timestamps.start(start);
+                            }
+                            if let Some(end) = presence.end_timestamp {
+                                timestamps = timestamps.end(end);
+                            }
+                            timestamps
+                        });
+                    }
+                    activity.assets(|mut assets| {
+                        if let Some(ref image) = presence.large_image {
+                            assets = assets.large_image(image.as_str());
+                        }
+                        if let Some(ref text) = presence.large_text {
+                            assets = assets.large_text(text.as_str());
+                        }
+                        if let Some(ref image) = presence.small_image {
+                            assets = assets.small_image(image.as_str());
+                        }
+                        if let Some(ref text) = presence.small_text {
+                            assets = assets.small_text(text.as_str());
+                        }
+                        assets
+                    })
+                })
+                .map(|_| ())
+        };
+        result.map_err(|e| format!("{:?}", e))
+    }
+}
+
+impl<'a> System<'a> for DiscordPresenceSystem {
+    type SystemData = Read<'a, Presence>;
+
+    fn run(&mut self, presence: Self::SystemData) {
+        let due = self
+            .sent_at
+            .map_or(true, |sent_at| sent_at.elapsed() >= self.refresh_interval);
+        // Changes are sent right away, unless Discord isn't answering.
+        let changed = !self.failed && self.sent.as_ref() != Some(&*presence);
+        if !due && !changed {
+            return;
+        }
+        let result = self.send(&presence);
+        self.sent = Some(presence.clone());
+        self.sent_at = Some(Instant::now());
+        match result {
+            Ok(()) => {
+                if self.failed {
+                    info!("Reconnected to Discord");
+                }
+                self.failed = false;
+            }
+            Err(e) => {
+                // Only warn once until it works again, Discord not running is common.
+                if !self.failed {
+                    warn!(
+                        "Failed to update the Discord presence, retrying later: {}",
+                        e
+                    );
+                }
+                self.failed = true;
+            }
+        }
+    }
+}
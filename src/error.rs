@@ -8,7 +8,9 @@ use std::{
     result::Result as StdResult,
 };
 
-use crate::{config::ConfigError, core, renderer, state::StateError};
+#[cfg(feature = "amethyst_renderer")]
+use crate::renderer;
+use crate::{args::ArgsError, config::ConfigError, core, state::StateError};
 
 /// Engine result type.
 pub type Result<T> = StdResult<T, Error>;
@@ -18,6 +20,8 @@ pub type Result<T> = StdResult<T, Error>;
 pub enum Error {
     /// Application error.
     Application,
+    /// Command line argument error.
+    Args(ArgsError),
     /// StateMachine error
     StateMachine(StateError),
     /// Asset management error.
@@ -34,6 +38,7 @@ impl StdError for Error {
     fn description(&self) -> &str {
         match *self {
             Error::Application => "Application error!",
+            Error::Args(_) => "Command line argument error!",
             Error::Config(_) => "Configuration error!",
             Error::Core(_) => "Core error!",
             Error::StateMachine(_) => "StateMachine error!",
@@ -43,6 +48,7 @@ impl StdError for Error {
 
     fn cause(&self) -> Option<&dyn StdError> {
         match *self {
+            Error::Args(ref e) => Some(e),
             Error::Config(ref e) => Some(e),
             _ => None,
         }
@@ -53,6 +59,7 @@ impl Display for Error {
     fn fmt(&self, fmt: &mut Formatter<'_>) -> FmtResult {
         match *self {
             Error::Application => write!(fmt, "Application initialization failed!"),
+            Error::Args(ref e) => write!(fmt, "Invalid command line arguments: {}", e),
             Error::Config(ref e) => write!(fmt, "Configuration loading failed: {}", e),
             Error::Core(ref e) => write!(fmt, "System creation failed: {}", e),
             Error::StateMachine(ref e) => write!(fmt, "Error in state machine: {}", e),
@@ -67,12 +74,19 @@ impl From<core::Error> for Error {
     }
 }
 
+#[cfg(feature = "amethyst_renderer")]
 impl From<renderer::error::Error> for Error {
     fn from(err: renderer::error::Error) -> Self {
         Error::Core(core::Error::with_chain(err, "Renderer error"))
     }
 }
 
+impl From<ArgsError> for Error {
+    fn from(err: ArgsError) -> Self {
+        Error::Args(err)
+    }
+}
+
 impl From<ConfigError> for Error {
     fn from(err: ConfigError) -> Self {
         Error::Config(err)
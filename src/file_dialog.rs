@@ -0,0 +1,222 @@
+//! Native dialogs to pick files and folders, shown without blocking the game loop.
+
+use std::{
+    path::{Path, PathBuf},
+    thread,
+};
+
+use crossbeam_channel::Sender;
+
+use crate::{callback_queue::Callback, core::shrev::EventChannel};
+
+/// What a `FileDialog` asks the user for.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum FileDialogKind {
+    /// An existing file.
+    Open,
+    /// One or more existing files.
+    OpenMultiple,
+    /// A file to save to, which may not exist yet.
+    Save,
+    /// An existing folder.
+    PickFolder,
+}
+
+/// A native dialog to pick files or a folder, shown with `FileDialogs::show`.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let id = world.write_resource::<FileDialogs>().show(
+///     FileDialog::open()
+///         .with_title("Import avatar")
+///         .with_filter("Images", &["*.png", "*.jpg"]),
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileDialog {
+    /// What the dialog asks for.
+    pub kind: FileDialogKind,
+    /// Title of the dialog window.
+    pub title: String,
+    /// Folder, or file for save dialogs, the dialog starts at.
+    pub path: PathBuf,
+    /// Description of the files matching the filter, e.g. `"Images"`.
+    pub filter_description: String,
+    /// Patterns of the file names shown, e.g. `"*.png"`, or empty to show all files.
+    pub filter: Vec<String>,
+}
+
+impl FileDialog {
+    /// Creates a dialog of the given kind, starting in the current directory and showing all
+    /// files.
+    pub fn new(kind: FileDialogKind) -> Self {
+        let title = match kind {
+            FileDialogKind::Open | FileDialogKind::OpenMultiple => "Open",
+            FileDialogKind::Save => "Save",
+            FileDialogKind::PickFolder => "Select folder",
+        };
+        FileDialog {
+            kind,
+            title: title.to_owned(),
+            path: PathBuf::new(),
+            filter_description: String::new(),
+            filter: Vec::new(),
+        }
+    }
+
+    /// Creates a dialog to open a file.
+    pub fn open() -> Self {
+        Self::new(FileDialogKind::Open)
+    }
+
+    /// Creates a dialog to open one or more files.
+    pub fn open_multiple() -> Self {
+        Self::new(FileDialogKind::OpenMultiple)
+    }
+
+    /// Creates a dialog to pick the file to save to.
+    pub fn save() -> Self {
+        Self::new(FileDialogKind::Save)
+    }
+
+    /// Creates a dialog to pick a folder.
+    pub fn pick_folder() -> Self {
+        Self::new(FileDialogKind::PickFolder)
+    }
+
+    /// Sets the title of the dialog window.
+    pub fn with_title<S>(mut self, title: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.title = title.into();
+        self
+    }
+
+    /// Sets the folder, or file for save dialogs, the dialog starts at.
+    pub fn with_path<P>(mut self, path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.path = path.into();
+        self
+    }
+
+    /// Only shows the files matching one of the patterns, e.g. `&["*.png", "*.jpg"]`.
+    pub fn with_filter<S>(mut self, description: S, patterns: &[&str]) -> Self
+    where
+        S: Into<String>,
+    {
+        self.filter_description = description.into();
+        self.filter = patterns.iter().map(|pattern| pattern.to_string()).collect();
+        self
+    }
+
+    /// Shows the dialog, blocking until the user closes it, and returns the picked paths.
+    fn run(&self) -> Vec<PathBuf> {
+        let path = self.path.to_string_lossy();
+        let patterns = self.filter.iter().map(String::as_str).collect::<Vec<_>>();
+        let filter = if patterns.is_empty() {
+            None
+        } else {
+            Some((&patterns[..], &self.filter_description[..]))
+        };
+        let paths = match self.kind {
+            FileDialogKind::Open => {
+                tinyfiledialogs::open_file_dialog(&self.title, &path, filter).map(|p| vec![p])
+            }
+            FileDialogKind::OpenMultiple => {
+                tinyfiledialogs::open_file_dialog_multi(&self.title, &path, filter)
+            }
+            FileDialogKind::Save => tinyfiledialogs::save_file_dialog_with_filter(
+                &self.title,
+                &path,
+                &patterns,
+                &self.filter_description,
+            )
+            .map(|p| vec![p]),
+            FileDialogKind::PickFolder => {
+                tinyfiledialogs::select_folder_dialog(&self.title, &path).map(|p| vec![p])
+            }
+        };
+        paths
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|p| !p.is_empty())
+            .map(PathBuf::from)
+            .collect()
+    }
+}
+
+/// Identifies a dialog shown with `FileDialogs::show`, to match it with its `FileDialogEvent`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct FileDialogId(u64);
+
+/// Event sent on the `EventChannel<FileDialogEvent>` once the user closes a dialog.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileDialogEvent {
+    /// The dialog that was closed.
+    pub id: FileDialogId,
+    /// What the dialog asked for.
+    pub kind: FileDialogKind,
+    /// The picked paths, empty if the dialog was cancelled.
+    pub paths: Vec<PathBuf>,
+}
+
+impl FileDialogEvent {
+    /// Checks whether the user closed the dialog without picking anything.
+    pub fn is_cancelled(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// Returns the first picked path, the only one for all but `OpenMultiple` dialogs.
+    pub fn path(&self) -> Option<&Path> {
+        self.paths.first().map(PathBuf::as_path)
+    }
+}
+
+/// Resource showing `FileDialog`s, added by the `ApplicationBuilder`.
+///
+/// Each dialog is shown from a thread of its own, so the game keeps running and drawing while it
+/// is open, and its result is sent as a `FileDialogEvent` at the start of the next frame after
+/// it closes, through the `CallbackQueue`.
+pub struct FileDialogs {
+    callbacks: Sender<Callback>,
+    next_id: u64,
+}
+
+impl FileDialogs {
+    pub(crate) fn new(callbacks: Sender<Callback>) -> Self {
+        FileDialogs {
+            callbacks,
+            next_id: 0,
+        }
+    }
+
+    /// Shows the dialog, returning the id of its `FileDialogEvent`.
+    pub fn show(&mut self, dialog: FileDialog) -> FileDialogId {
+        let id = FileDialogId(self.next_id);
+        self.next_id += 1;
+        let callbacks = self.callbacks.clone();
+        let spawned = thread::Builder::new()
+            .name("file dialog".to_owned())
+            .spawn(move || {
+                let event = FileDialogEvent {
+                    id,
+                    kind: dialog.kind,
+                    paths: dialog.run(),
+                };
+                let callback: Callback = Box::new(move |world| {
+                    world
+                        .write_resource::<EventChannel<FileDialogEvent>>()
+                        .single_write(event.clone());
+                });
+                callbacks.send(callback).ok();
+            });
+        if let Err(e) = spawned {
+            error!("Failed to show file dialog: {}", e);
+        }
+        id
+    }
+}
@@ -1,12 +1,15 @@
+#[cfg(all(feature = "amethyst_renderer", feature = "amethyst_ui"))]
 use std::path::Path;
 
+#[cfg(all(feature = "amethyst_renderer", feature = "amethyst_ui"))]
+use crate::renderer::pipe::pass::Pass;
 use crate::{
     core::{
-        specs::prelude::{Dispatcher, DispatcherBuilder, System, World},
-        ArcThreadPool, SystemBundle,
+        specs::prelude::{Dispatcher, DispatcherBuilder, System, SystemData, World},
+        ArcThreadPool, PauseState, RunCriteria, SystemBundle, SystemExt,
     },
     error::{Error, Result},
-    renderer::pipe::pass::Pass,
+    watchdog::SystemTimings,
 };
 
 /// Initialise trait for game data
@@ -38,6 +41,7 @@ impl<'a, 'b> GameData<'a, 'b> {
 /// Builder for default game data
 pub struct GameDataBuilder<'a, 'b> {
     disp_builder: DispatcherBuilder<'a, 'b>,
+    timings: Option<SystemTimings>,
 }
 
 impl<'a, 'b> Default for GameDataBuilder<'a, 'b> {
@@ -51,6 +55,7 @@ impl<'a, 'b> GameDataBuilder<'a, 'b> {
     pub fn new() -> Self {
         GameDataBuilder {
             disp_builder: DispatcherBuilder::new(),
+            timings: None,
         }
     }
 
@@ -146,10 +151,128 @@ impl<'a, 'b> GameDataBuilder<'a, 'b> {
     where
         for<'c> S: System<'c> + Send + 'a,
     {
-        self.disp_builder.add(system, name, dependencies);
+        match self.timings {
+            Some(ref timings) => {
+                self.disp_builder
+                    .add(timings.timed(system, name), name, dependencies)
+            }
+            None => self.disp_builder.add(system, name, dependencies),
+        }
         self
     }
 
+    /// Times the systems added with `with`, `with_pausable` and `with_run_if` after this call,
+    /// so a [`Watchdog`](struct.Watchdog.html) given the same timings reports them.
+    ///
+    /// Systems added by bundles aren't timed.
+    ///
+    /// # Parameters
+    ///
+    /// - `timings`: The timings recording the systems.
+    ///
+    /// # Returns
+    ///
+    /// This function returns GameDataBuilder after it has modified it.
+    pub fn with_system_timings(mut self, timings: SystemTimings) -> Self {
+        self.timings = Some(timings);
+        self
+    }
+
+    /// Adds a system that only runs while the game isn't paused.
+    ///
+    /// The system is skipped while the `PauseState` resource is `PauseState::Paused`, e.g. while
+    /// a [`PausedState`](struct.PausedState.html) is on the state stack.
+    ///
+    /// # Parameters
+    ///
+    /// - `system`: The system that is to be added to the game loop.
+    /// - `name`: A unique string to identify the system by.
+    /// - `dependencies`: A list of named system that _must_ have completed running
+    ///                 before this system is permitted to run.
+    ///
+    /// # Returns
+    ///
+    /// This function returns GameDataBuilder after it has modified it.
+    ///
+    /// # Panics
+    ///
+    /// Panics for the same reasons as [`with`](#method.with).
+    pub fn with_pausable<S>(self, system: S, name: &str, dependencies: &[&str]) -> Self
+    where
+        for<'c> S: System<'c> + Send + 'a,
+        for<'c> <S as System<'c>>::SystemData: SystemData<'c>,
+    {
+        self.with(system.pausable(PauseState::Running), name, dependencies)
+    }
+
+    /// Adds a system that only runs while the given criteria are met.
+    ///
+    /// Use [`InState`](struct.InState.html) to run a system while a specific state is active,
+    /// or [`ResourceCriteria`](core/struct.ResourceCriteria.html) to run it depending on a
+    /// resource, instead of checking flags at the top of the system's `run`.
+    ///
+    /// # Parameters
+    ///
+    /// - `system`: The system that is to be added to the game loop.
+    /// - `name`: A unique string to identify the system by.
+    /// - `dependencies`: A list of named system that _must_ have completed running
+    ///                 before this system is permitted to run.
+    /// - `criteria`: The criteria deciding every frame whether the system runs.
+    ///
+    /// # Returns
+    ///
+    /// This function returns GameDataBuilder after it has modified it.
+    ///
+    /// # Panics
+    ///
+    /// Panics for the same reasons as [`with`](#method.with).
+    ///
+    /// # Examples
+    ///
+    /// ~~~no_run
+    /// use amethyst::core::ResourceCriteria;
+    /// use amethyst::ecs::prelude::System;
+    /// use amethyst::prelude::*;
+    /// use amethyst::InState;
+    ///
+    /// struct NopSystem;
+    /// impl<'a> System<'a> for NopSystem {
+    ///     type SystemData = ();
+    ///     fn run(&mut self, _: Self::SystemData) {}
+    /// }
+    ///
+    /// #[derive(Default)]
+    /// struct Settings {
+    ///     particles: bool,
+    /// }
+    ///
+    /// struct Gameplay;
+    /// impl SimpleState for Gameplay {}
+    ///
+    /// GameDataBuilder::default()
+    ///     .with_run_if(NopSystem, "ai", &[], InState::<Gameplay>::new())
+    ///     .with_run_if(
+    ///         NopSystem,
+    ///         "particles",
+    ///         &[],
+    ///         ResourceCriteria::new(|settings: &Settings| settings.particles),
+    ///     );
+    /// ~~~
+    pub fn with_run_if<S, C>(
+        self,
+        system: S,
+        name: &str,
+        dependencies: &[&str],
+        criteria: C,
+    ) -> Self
+    where
+        for<'c> S: System<'c> + Send + 'a,
+        for<'c> <S as System<'c>>::SystemData: SystemData<'c>,
+        for<'c> C: RunCriteria<'c> + Send + 'a,
+    {
+        self.with(system.run_if(criteria), name, dependencies)
+    }
+
     /// Add a given thread-local system.
     ///
     /// A thread-local system is one that _must_ run on the main thread of the
@@ -232,6 +355,7 @@ impl<'a, 'b> GameDataBuilder<'a, 'b> {
     /// - `path`: Path to the `DisplayConfig` configuration file
     /// - `pass`: The single pass in the render graph
     /// - `with_ui`: If set to true, will add the UI render pass
+    #[cfg(all(feature = "amethyst_renderer", feature = "amethyst_ui"))]
     pub fn with_basic_renderer<A, P>(self, path: A, pass: P, with_ui: bool) -> Result<Self>
     where
         A: AsRef<Path>,
@@ -52,6 +52,22 @@
 //!     game.run();
 //! }
 //! ```
+//!
+//! # Dedicated servers
+//!
+//! The `client` feature, enabled by default, brings in the renderer, audio, UI, animation and
+//! camera controls, along with `amethyst_utils` which depends on them. Servers can leave it out
+//! to keep graphics and audio drivers out of their binary, and keep the ECS, assets, config,
+//! input, locale and networking:
+//!
+//! ```toml
+//! [dependencies]
+//! amethyst = { version = "0.10", default-features = false }
+//! ```
+//!
+//! Window events still reach the states, but there is no window to send them, and
+//! `StateEvent::Ui` doesn't exist. Other features of the engine, such as `profiler` or `json`,
+//! enable the crates they configure, so they bring some of the client back.
 
 #![doc(html_logo_url = "https://www.amethyst.rs/assets/amethyst.svg")]
 #![warn(missing_docs, rust_2018_idioms, rust_2018_compatibility)]
@@ -60,10 +76,13 @@
 #[cfg(feature = "profiler")]
 pub extern crate thread_profiler;
 
+#[cfg(feature = "amethyst_animation")]
 pub use amethyst_animation as animation;
 pub use amethyst_assets as assets;
+#[cfg(feature = "amethyst_audio")]
 pub use amethyst_audio as audio;
 pub use amethyst_config as config;
+#[cfg(feature = "amethyst_controls")]
 pub use amethyst_controls as controls;
 pub use amethyst_core as core;
 #[macro_use]
@@ -71,8 +90,11 @@ pub extern crate amethyst_derive as derive;
 pub use amethyst_input as input;
 pub use amethyst_locale as locale;
 pub use amethyst_network as network;
+#[cfg(feature = "amethyst_renderer")]
 pub use amethyst_renderer as renderer;
+#[cfg(feature = "amethyst_ui")]
 pub use amethyst_ui as ui;
+#[cfg(feature = "amethyst_utils")]
 pub use amethyst_utils as utils;
 pub use winit;
 
@@ -90,26 +112,71 @@ pub use crate::core::{shred, shrev, specs as ecs};
 
 pub use self::{
     app::{Application, ApplicationBuilder, CoreApplication},
+    args::{ArgsError, EngineArgs},
     callback_queue::{Callback, CallbackQueue},
+    crash::{Breadcrumbs, CrashHandler},
     error::{Error, Result},
     game_data::{DataInit, GameData, GameDataBuilder},
+    lifecycle::{AppLifecycle, BackgroundConfig, LifecycleEvent},
+    loading_state::{FailurePolicy, LoadingProgress, LoadingState},
     logger::{start_logger, LevelFilter as LogLevelFilter, Logger, LoggerConfig, StdoutLog},
+    paused_state::PausedState,
     state::{
-        EmptyState, EmptyTrans, SimpleState, SimpleTrans, State, StateData, StateMachine, Trans,
-        TransEvent,
+        AsyncTrans, EmptyState, EmptyTrans, Payload, SimpleState, SimpleTrans, State, StateData,
+        StateMachine, StateStack, StateTransitionEvent, Trans, TransEvent,
     },
     state_event::{StateEvent, StateEventReader},
+    telemetry::Telemetry,
+    watchdog::{SystemTimings, TimedSystem, Watchdog},
+};
+
+#[cfg(type_name)]
+pub use crate::state::InState;
+
+#[cfg(feature = "file_dialog")]
+pub use crate::file_dialog::{
+    FileDialog, FileDialogEvent, FileDialogId, FileDialogKind, FileDialogs,
 };
 
 #[doc(hidden)]
 pub use crate::derive::*;
 
+#[cfg(all(feature = "amethyst_renderer", feature = "amethyst_ui"))]
+pub mod accessibility;
+#[cfg(feature = "dev_tools")]
+pub mod dev_tools;
+#[cfg(feature = "discord")]
+pub mod discord;
+#[cfg(all(feature = "amethyst_renderer", feature = "amethyst_ui"))]
+pub mod photo_mode;
+
 pub mod prelude;
+#[cfg(feature = "replay")]
+pub mod replay;
+#[cfg(feature = "steam")]
+pub mod steam;
+#[cfg(all(
+    feature = "amethyst_animation",
+    feature = "amethyst_audio",
+    feature = "amethyst_renderer"
+))]
+pub mod timeline;
 
 mod app;
+mod args;
 mod callback_queue;
+mod crash;
 mod error;
+#[cfg(feature = "file_dialog")]
+mod file_dialog;
 mod game_data;
+mod lifecycle;
+mod loading_state;
 mod logger;
+mod paused_state;
+#[cfg(feature = "amethyst_renderer")]
+mod screen_transition;
 mod state;
 mod state_event;
+mod telemetry;
+mod watchdog;
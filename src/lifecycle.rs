@@ -0,0 +1,248 @@
+//! Tracking of window focus, minimizing and OS suspend/resume.
+
+use winit::{Event, WindowEvent};
+
+#[cfg(feature = "amethyst_audio")]
+use crate::audio::AudioSink;
+use crate::{
+    core::{
+        frame_limiter::{FrameLimiter, FrameRateLimitStrategy},
+        shrev::{EventChannel, ReaderId},
+    },
+    ecs::prelude::World,
+};
+
+/// Change of the application's lifecycle, sent on an `EventChannel<LifecycleEvent>` and to
+/// states as `StateEvent::Lifecycle`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LifecycleEvent {
+    /// The window lost the input focus.
+    FocusLost,
+    /// The window gained the input focus.
+    FocusGained,
+    /// The window was minimized.
+    Minimized,
+    /// The window was restored after being minimized.
+    Restored,
+    /// The OS suspended the application, e.g. because it was moved to the background on mobile.
+    Suspended,
+    /// The OS resumed the application.
+    Resumed,
+}
+
+/// Resource with the current lifecycle state of the application.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AppLifecycle {
+    focused: bool,
+    minimized: bool,
+    suspended: bool,
+}
+
+impl Default for AppLifecycle {
+    fn default() -> Self {
+        AppLifecycle {
+            focused: true,
+            minimized: false,
+            suspended: false,
+        }
+    }
+}
+
+impl AppLifecycle {
+    /// Checks whether the window has the input focus.
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    /// Checks whether the window is minimized.
+    pub fn is_minimized(&self) -> bool {
+        self.minimized
+    }
+
+    /// Checks whether the OS suspended the application.
+    pub fn is_suspended(&self) -> bool {
+        self.suspended
+    }
+
+    /// Checks whether the application is in the foreground, i.e. focused, not minimized and not
+    /// suspended.
+    pub fn is_foreground(&self) -> bool {
+        self.focused && !self.minimized && !self.suspended
+    }
+
+    /// Updates the state from a window event, returning the resulting lifecycle event if the state
+    /// changed.
+    pub(crate) fn handle(&mut self, event: &Event) -> Option<LifecycleEvent> {
+        match *event {
+            Event::WindowEvent {
+                event: WindowEvent::Focused(focused),
+                ..
+            } if focused != self.focused => {
+                self.focused = focused;
+                Some(if focused {
+                    LifecycleEvent::FocusGained
+                } else {
+                    LifecycleEvent::FocusLost
+                })
+            }
+            // Minimized windows are resized to zero on most platforms.
+            Event::WindowEvent {
+                event: WindowEvent::Resized(size),
+                ..
+            } => {
+                let minimized = size.width <= 0. && size.height <= 0.;
+                if minimized == self.minimized {
+                    return None;
+                }
+                self.minimized = minimized;
+                Some(if minimized {
+                    LifecycleEvent::Minimized
+                } else {
+                    LifecycleEvent::Restored
+                })
+            }
+            Event::Suspended(suspended) if suspended != self.suspended => {
+                self.suspended = suspended;
+                Some(if suspended {
+                    LifecycleEvent::Suspended
+                } else {
+                    LifecycleEvent::Resumed
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// What the application does while it is in the background, see
+/// [`ApplicationBuilder::with_background_config`](struct.ApplicationBuilder.html#method.with_background_config).
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct BackgroundConfig {
+    /// Frame rate to limit the game loop to while in the background, or `None` to keep the
+    /// configured frame rate.
+    #[serde(default)]
+    pub frame_rate: Option<u32>,
+    /// Pauses the `AudioSink` while in the background. Does nothing in builds without audio.
+    #[serde(default)]
+    pub pause_audio: bool,
+}
+
+/// Updates `AppLifecycle` from window events and applies the `BackgroundConfig`.
+pub(crate) struct LifecycleTracker {
+    reader: ReaderId<Event>,
+    config: BackgroundConfig,
+    saved_rate: Option<(FrameRateLimitStrategy, u32)>,
+    paused_audio: bool,
+}
+
+impl LifecycleTracker {
+    pub(crate) fn new(world: &mut World, config: BackgroundConfig) -> Self {
+        world.add_resource(AppLifecycle::default());
+        world.add_resource(EventChannel::<LifecycleEvent>::with_capacity(8));
+        LifecycleTracker {
+            reader: world
+                .write_resource::<EventChannel<Event>>()
+                .register_reader(),
+            config,
+            saved_rate: None,
+            paused_audio: false,
+        }
+    }
+
+    /// Reads the window events of this frame. Must run before the state events are read, so
+    /// states receive the lifecycle events in the same frame.
+    pub(crate) fn update(&mut self, world: &World) {
+        let (was_foreground, is_foreground) = {
+            let events = world.read_resource::<EventChannel<Event>>();
+            let mut lifecycle = world.write_resource::<AppLifecycle>();
+            let mut lifecycle_events = world.write_resource::<EventChannel<LifecycleEvent>>();
+            let was_foreground = lifecycle.is_foreground();
+            for event in events.read(&mut self.reader) {
+                if let Some(event) = lifecycle.handle(event) {
+                    lifecycle_events.single_write(event);
+                }
+            }
+            (was_foreground, lifecycle.is_foreground())
+        };
+
+        if was_foreground && !is_foreground {
+            self.enter_background(world);
+        } else if !was_foreground && is_foreground {
+            self.enter_foreground(world);
+        }
+    }
+
+    fn enter_background(&mut self, world: &World) {
+        if let Some(frame_rate) = self.config.frame_rate {
+            let mut limiter = world.write_resource::<FrameLimiter>();
+            self.saved_rate = Some((limiter.strategy(), limiter.fps().unwrap_or(0)));
+            limiter.set_rate(FrameRateLimitStrategy::Sleep, frame_rate);
+        }
+        if self.config.pause_audio {
+            self.paused_audio = pause_audio(world);
+        }
+    }
+
+    fn enter_foreground(&mut self, world: &World) {
+        if let Some((strategy, fps)) = self.saved_rate.take() {
+            world
+                .write_resource::<FrameLimiter>()
+                .set_rate(strategy, fps);
+        }
+        if self.paused_audio {
+            self.paused_audio = false;
+            resume_audio(world);
+        }
+    }
+}
+
+/// Pauses the `AudioSink`, returning whether it was playing.
+#[cfg(feature = "amethyst_audio")]
+fn pause_audio(world: &World) -> bool {
+    match world.res.try_fetch::<AudioSink>() {
+        Some(ref sink) if !sink.is_paused() => {
+            sink.pause();
+            true
+        }
+        _ => false,
+    }
+}
+
+#[cfg(not(feature = "amethyst_audio"))]
+fn pause_audio(_: &World) -> bool {
+    false
+}
+
+#[cfg(feature = "amethyst_audio")]
+fn resume_audio(world: &World) {
+    if let Some(sink) = world.res.try_fetch::<AudioSink>() {
+        sink.play();
+    }
+}
+
+#[cfg(not(feature = "amethyst_audio"))]
+fn resume_audio(_: &World) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suspend_and_resume() {
+        let mut lifecycle = AppLifecycle::default();
+        assert!(lifecycle.is_foreground());
+
+        assert_eq!(
+            Some(LifecycleEvent::Suspended),
+            lifecycle.handle(&Event::Suspended(true))
+        );
+        assert_eq!(None, lifecycle.handle(&Event::Suspended(true)));
+        assert!(!lifecycle.is_foreground());
+
+        assert_eq!(
+            Some(LifecycleEvent::Resumed),
+            lifecycle.handle(&Event::Suspended(false))
+        );
+        assert!(lifecycle.is_foreground());
+    }
+}
@@ -0,0 +1,288 @@
+//! Reusable state that loads assets before switching to the next state.
+
+use crate::{
+    assets::{Format, Prefab, PrefabData, PrefabLoader, ProgressCounter},
+    ecs::prelude::{Builder, Entity, World},
+    state::{SimpleState, SimpleTrans, StateData, Trans},
+    GameData,
+};
+
+type LoadFn = Box<dyn FnMut(&mut World, &mut ProgressCounter)>;
+type ProgressFn = Box<dyn FnMut(&mut World, &LoadingProgress)>;
+
+/// What a `LoadingState` does when assets fail to load.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FailurePolicy {
+    /// Quit the application.
+    Quit,
+    /// Load everything again, up to the given number of additional attempts, then quit.
+    Retry(u32),
+    /// Switch to the next state anyway.
+    Continue,
+}
+
+/// Progress of a `LoadingState`, passed to its progress callback every frame.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct LoadingProgress {
+    /// Number of assets that finished loading.
+    pub finished: usize,
+    /// Number of assets that failed to load.
+    pub failed: usize,
+    /// Number of assets being tracked.
+    pub total: usize,
+    /// Number of the current attempt, starting at 1.
+    pub attempt: u32,
+}
+
+impl LoadingProgress {
+    /// Returns the fraction of finished assets, between 0 and 1.
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            1.
+        } else {
+            self.finished as f32 / self.total as f32
+        }
+    }
+}
+
+/// State that loads a list of assets and prefabs, then switches to the next state.
+///
+/// Loading functions are called when the state starts, and again on every retry. The progress
+/// callback is called every frame, e.g. to update a progress bar; the state itself doesn't
+/// display anything.
+///
+/// ## Type parameters
+///
+/// * `N`: The state switched to once loading is complete.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let loading = LoadingState::new(GameplayState::default())
+///     .with_prefab::<ScenePrefab, _>("prefab/scene.ron", RonFormat)
+///     .with_load(|world, progress| {
+///         let handle = world.exec(|loader: AssetLoaderSystemData<'_, Texture>| {
+///             loader.load("texture/logo.png", PngFormat, Default::default(), progress)
+///         });
+///         world.add_resource(Logo(handle));
+///     })
+///     .with_progress_callback(|world, progress| {
+///         world.write_resource::<LoadingBar>().fraction = progress.fraction();
+///     })
+///     .with_failure_policy(FailurePolicy::Retry(2));
+/// ```
+pub struct LoadingState<N> {
+    next: Option<N>,
+    loads: Vec<LoadFn>,
+    on_progress: Option<ProgressFn>,
+    failure_policy: FailurePolicy,
+    progress: ProgressCounter,
+    attempt: u32,
+}
+
+impl<N> LoadingState<N>
+where
+    N: SimpleState + 'static,
+{
+    /// Creates a loading state that switches to `next` once loading is complete.
+    ///
+    /// Failures quit the application, unless a different `FailurePolicy` is set.
+    pub fn new(next: N) -> Self {
+        LoadingState {
+            next: Some(next),
+            loads: Vec::new(),
+            on_progress: None,
+            failure_policy: FailurePolicy::Quit,
+            progress: ProgressCounter::new(),
+            attempt: 0,
+        }
+    }
+
+    /// Adds a function that starts loading assets, tracking them with the given progress counter.
+    ///
+    /// The function runs again on retries, so it must be safe to call several times, e.g. by
+    /// replacing the resources it inserts.
+    pub fn with_load<F>(mut self, load_fn: F) -> Self
+    where
+        F: FnMut(&mut World, &mut ProgressCounter) + 'static,
+    {
+        self.loads.push(Box::new(load_fn));
+        self
+    }
+
+    /// Loads a prefab and creates an entity with its handle.
+    ///
+    /// When retrying, the entity created by the previous attempt is deleted.
+    pub fn with_prefab<T, F>(self, name: &str, format: F) -> Self
+    where
+        T: Send + Sync + 'static,
+        for<'p> T: PrefabData<'p>,
+        F: Format<Prefab<T>, Options = ()> + Clone,
+    {
+        let name = name.to_string();
+        let mut entity: Option<Entity> = None;
+        self.with_load(move |world, progress| {
+            if let Some(entity) = entity.take() {
+                if let Err(e) = world.delete_entity(entity) {
+                    error!("Failed to delete prefab entity of last attempt: {}", e);
+                }
+            }
+            let handle = world.exec(|loader: PrefabLoader<'_, T>| {
+                loader.load(name.as_str(), format.clone(), (), &mut *progress)
+            });
+            entity = Some(world.create_entity().with(handle).build());
+        })
+    }
+
+    /// Sets a function that is called with the loading progress every frame.
+    pub fn with_progress_callback<F>(mut self, on_progress: F) -> Self
+    where
+        F: FnMut(&mut World, &LoadingProgress) + 'static,
+    {
+        self.on_progress = Some(Box::new(on_progress));
+        self
+    }
+
+    /// Sets what happens when assets fail to load.
+    pub fn with_failure_policy(mut self, failure_policy: FailurePolicy) -> Self {
+        self.failure_policy = failure_policy;
+        self
+    }
+
+    fn start_loading(&mut self, world: &mut World) {
+        self.attempt += 1;
+        self.progress = ProgressCounter::new();
+        for load in &mut self.loads {
+            load(world, &mut self.progress);
+        }
+    }
+
+    fn next_state(&mut self) -> SimpleTrans {
+        match self.next.take() {
+            Some(next) => Trans::Switch(Box::new(next)),
+            None => Trans::None,
+        }
+    }
+}
+
+impl<N> SimpleState for LoadingState<N>
+where
+    N: SimpleState + 'static,
+{
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.start_loading(data.world);
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        let progress = LoadingProgress {
+            finished: self.progress.num_finished(),
+            failed: self.progress.num_failed(),
+            total: self.progress.num_assets(),
+            attempt: self.attempt,
+        };
+        if let Some(ref mut on_progress) = self.on_progress {
+            on_progress(data.world, &progress);
+        }
+
+        // Failed assets are never removed from the loading count.
+        if self.progress.num_loading() > progress.failed {
+            return Trans::None;
+        }
+        if progress.failed == 0 {
+            return self.next_state();
+        }
+
+        match self.failure_policy {
+            FailurePolicy::Continue => {
+                warn!(
+                    "{} assets failed to load, continuing anyway",
+                    progress.failed
+                );
+                self.next_state()
+            }
+            FailurePolicy::Retry(retries) if self.attempt <= retries => {
+                warn!(
+                    "{} assets failed to load, retrying (attempt {} of {})",
+                    progress.failed,
+                    self.attempt + 1,
+                    retries + 1
+                );
+                self.start_loading(data.world);
+                Trans::None
+            }
+            _ => {
+                error!("{} assets failed to load, quitting", progress.failed);
+                Trans::Quit
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc, sync::Arc};
+
+    use rayon::ThreadPoolBuilder;
+
+    use super::*;
+
+    use crate::{
+        assets::{Progress, Tracker},
+        DataInit, GameDataBuilder,
+    };
+
+    struct Next;
+
+    impl SimpleState for Next {}
+
+    fn game_data(world: &mut World) -> GameData<'static, 'static> {
+        let pool = ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+        world.add_resource(Arc::new(pool));
+        GameDataBuilder::default().build(world)
+    }
+
+    #[test]
+    fn failures_are_retried_then_quit() {
+        let mut world = World::new();
+        let mut data = game_data(&mut world);
+        let attempts = Rc::new(Cell::new(0));
+        let counted = attempts.clone();
+        let mut state = LoadingState::new(Next)
+            .with_load(move |_, mut progress| {
+                counted.set(counted.get() + 1);
+                progress.add_assets(1);
+                let tracker = Box::new(progress.create_tracker());
+                tracker.fail(0, "Test", "missing".to_string(), "Not found".into());
+            })
+            .with_failure_policy(FailurePolicy::Retry(1));
+
+        state.on_start(StateData::new(&mut world, &mut data));
+        let trans = state.update(&mut StateData::new(&mut world, &mut data));
+        assert!(match trans {
+            Trans::None => true,
+            _ => false,
+        });
+        assert_eq!(2, attempts.get());
+
+        let trans = state.update(&mut StateData::new(&mut world, &mut data));
+        assert!(match trans {
+            Trans::Quit => true,
+            _ => false,
+        });
+        assert_eq!(2, attempts.get());
+    }
+
+    #[test]
+    fn nothing_to_load_switches_to_next() {
+        let mut world = World::new();
+        let mut data = game_data(&mut world);
+        let mut state = LoadingState::new(Next);
+
+        state.on_start(StateData::new(&mut world, &mut data));
+        let trans = state.update(&mut StateData::new(&mut world, &mut data));
+        assert!(match trans {
+            Trans::Switch(_) => true,
+            _ => false,
+        });
+    }
+}
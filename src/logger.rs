@@ -1,9 +1,55 @@
 pub use log::LevelFilter;
 
-use std::{env, io, path::PathBuf, str::FromStr};
+use std::{
+    borrow::Cow,
+    cmp, env, io,
+    path::PathBuf,
+    str::FromStr,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
 
 use fern;
 
+use crate::crash::{BreadcrumbLog, Breadcrumbs};
+
+/// Level of the logger started by `Logger::start`, for the modules without a level of their own.
+static BASE_LEVEL: AtomicUsize = AtomicUsize::new(0);
+/// Highest level of the modules with a level of their own.
+static MODULE_LEVEL: AtomicUsize = AtomicUsize::new(0);
+/// Level set by `override_level`, plus one, or 0 if it wasn't called.
+static OVERRIDE_LEVEL: AtomicUsize = AtomicUsize::new(0);
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+fn level_from_usize(level: usize) -> LevelFilter {
+    match level {
+        0 => LevelFilter::Off,
+        1 => LevelFilter::Error,
+        2 => LevelFilter::Warn,
+        3 => LevelFilter::Info,
+        4 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    }
+}
+
+/// Replaces the level of the logger, whether it was started already or is started later, e.g.
+/// for `--loglevel`. Modules given a level with `Logger::level_for` keep it.
+pub(crate) fn override_level(level: LevelFilter) {
+    OVERRIDE_LEVEL.store(level as usize + 1, Ordering::SeqCst);
+    BASE_LEVEL.store(level as usize, Ordering::SeqCst);
+    let modules = if STARTED.load(Ordering::SeqCst) {
+        level_from_usize(MODULE_LEVEL.load(Ordering::SeqCst))
+    } else {
+        LevelFilter::Off
+    };
+    log::set_max_level(cmp::max(level, modules));
+}
+
+/// Returns whether `target` is `module` or one of its submodules, like `fern` matches them.
+fn in_module(target: &str, module: &str) -> bool {
+    target.starts_with(module)
+        && (target.len() == module.len() || target[module.len()..].starts_with("::"))
+}
+
 /// An enum that contains options for logging to the terminal.
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum StdoutLog {
@@ -51,6 +97,8 @@ impl Default for LoggerConfig {
 /// ```
 pub struct Logger {
     dispatch: fern::Dispatch,
+    level: LevelFilter,
+    modules: Vec<(Cow<'static, str>, LevelFilter)>,
 }
 
 impl Logger {
@@ -63,7 +111,11 @@ impl Logger {
                 message = message,
             ))
         });
-        Logger { dispatch }
+        Logger {
+            dispatch,
+            level: LevelFilter::Trace,
+            modules: Vec::new(),
+        }
     }
 
     /// Create a new Logger from [`LoggerConfig`]
@@ -73,7 +125,7 @@ impl Logger {
         }
 
         let mut logger = Logger::new();
-        logger.dispatch = logger.dispatch.level(config.level_filter);
+        logger.level = config.level_filter;
 
         match config.stdout {
             StdoutLog::Plain => logger.dispatch = logger.dispatch.chain(io::stdout()),
@@ -96,20 +148,60 @@ impl Logger {
     }
 
     /// Set individual log levels for modules.
-    pub fn level_for<T: Into<std::borrow::Cow<'static, str>>>(
-        mut self,
-        module: T,
-        level: LevelFilter,
-    ) -> Self {
-        self.dispatch = self.dispatch.level_for(module, level);
+    pub fn level_for<T: Into<Cow<'static, str>>>(mut self, module: T, level: LevelFilter) -> Self {
+        let module = module.into();
+        self.dispatch = self.dispatch.level_for(module.clone(), level);
+        self.modules.push((module, level));
+        self
+    }
+
+    /// Records every log line in the given [`Breadcrumbs`], so they can be included in crash
+    /// reports.
+    pub fn with_breadcrumbs(mut self, breadcrumbs: &Breadcrumbs) -> Self {
+        let log: Box<dyn log::Log> = Box::new(BreadcrumbLog(breadcrumbs.clone()));
+        self.dispatch = self.dispatch.chain(log);
         self
     }
 
     /// Starts [`Logger`] by consuming it.
+    ///
+    /// The level of the logger can still be changed afterwards by the `--loglevel` flag, see
+    /// [`ApplicationBuilder::with_args`](struct.ApplicationBuilder.html#method.with_args).
     pub fn start(self) {
-        self.dispatch.apply().unwrap_or_else(|_| {
-            debug!("Global logger already set, default Amethyst logger will not be used")
-        });
+        let base = match OVERRIDE_LEVEL.load(Ordering::SeqCst) {
+            0 => self.level,
+            level => level_from_usize(level - 1),
+        };
+        let modules = self
+            .modules
+            .iter()
+            .map(|&(_, level)| level)
+            .fold(LevelFilter::Off, cmp::max);
+        let names = self
+            .modules
+            .into_iter()
+            .map(|(module, _)| module)
+            .collect::<Vec<_>>();
+        // Let everything through `fern`, apart from the modules with a level of their own, and
+        // filter by the base level here, so it can be changed later.
+        let dispatch = self
+            .dispatch
+            .level(LevelFilter::Trace)
+            .filter(move |metadata| {
+                metadata.level() as usize <= BASE_LEVEL.load(Ordering::Relaxed)
+                    || names
+                        .iter()
+                        .any(|module| in_module(metadata.target(), module))
+            });
+        BASE_LEVEL.store(base as usize, Ordering::SeqCst);
+        match dispatch.apply() {
+            Ok(()) => {
+                MODULE_LEVEL.store(modules as usize, Ordering::SeqCst);
+                STARTED.store(true, Ordering::SeqCst);
+                log::set_max_level(cmp::max(base, modules));
+            }
+            Err(_) => debug!("Global logger already set, default Amethyst logger will not be used"),
+        }
     }
 }
 
@@ -158,3 +250,19 @@ fn colored_stdout(color_config: fern::colors::ColoredLevelConfig) -> fern::Dispa
             ))
         })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modules_match_like_fern() {
+        assert!(in_module("gfx_device_gl", "gfx_device_gl"));
+        assert!(in_module("gfx_device_gl::factory", "gfx_device_gl"));
+        assert!(!in_module("gfx_device_gl_extra", "gfx_device_gl"));
+        assert!(!in_module("gfx", "gfx_device_gl"));
+        for &level in &[LevelFilter::Off, LevelFilter::Warn, LevelFilter::Trace] {
+            assert_eq!(level, level_from_usize(level as usize));
+        }
+    }
+}
@@ -0,0 +1,101 @@
+//! State that pauses the game while it is on top of the stack.
+
+use winit::VirtualKeyCode;
+
+use crate::{
+    core::{timing::Time, PauseState},
+    input::{is_close_requested, is_key_down},
+    state::{SimpleState, SimpleTrans, StateData, Trans},
+    GameData, StateEvent,
+};
+
+/// State that pauses the game while it is active.
+///
+/// Pushing the state sets the `PauseState` resource to `PauseState::Paused` and the time scale to
+/// zero, so the game time stands still. Systems added with
+/// [`GameDataBuilder::with_pausable`](struct.GameDataBuilder.html#method.with_pausable) stop
+/// running, while all other systems, such as UI and audio, keep running. When the state is
+/// popped, the previous pause state and time scale are restored.
+///
+/// By default the state pops itself when `Escape` is pressed. Shadow updates of the states below
+/// keep running, as they do for every pushed state.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// impl SimpleState for Gameplay {
+///     fn handle_event(&mut self, _: StateData<'_, GameData<'_, '_>>, event: StateEvent) -> SimpleTrans {
+///         match event {
+///             StateEvent::Window(ref event) if is_key_down(event, VirtualKeyCode::P) => {
+///                 Trans::Push(Box::new(PausedState::new()))
+///             }
+///             _ => Trans::None,
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct PausedState {
+    resume_key: Option<VirtualKeyCode>,
+    previous: Option<(PauseState, f32)>,
+}
+
+impl PausedState {
+    /// Creates a paused state that resumes the game when `Escape` is pressed.
+    pub fn new() -> Self {
+        PausedState {
+            resume_key: Some(VirtualKeyCode::Escape),
+            previous: None,
+        }
+    }
+
+    /// Sets the key that resumes the game, or `None` to only resume by popping the state, e.g.
+    /// from a menu button.
+    pub fn with_resume_key(mut self, resume_key: Option<VirtualKeyCode>) -> Self {
+        self.resume_key = resume_key;
+        self
+    }
+}
+
+impl Default for PausedState {
+    fn default() -> Self {
+        PausedState::new()
+    }
+}
+
+impl SimpleState for PausedState {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let mut pause = data.world.write_resource::<PauseState>();
+        let mut time = data.world.write_resource::<Time>();
+        self.previous = Some((*pause, time.time_scale()));
+        *pause = PauseState::Paused;
+        time.set_time_scale(0.);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        if let Some((pause, time_scale)) = self.previous.take() {
+            *data.world.write_resource::<PauseState>() = pause;
+            data.world
+                .write_resource::<Time>()
+                .set_time_scale(time_scale);
+        }
+    }
+
+    fn handle_event(
+        &mut self,
+        _: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(ref event) = event {
+            if is_close_requested(event) {
+                return Trans::Quit;
+            }
+            if let Some(key) = self.resume_key {
+                if is_key_down(event, key) {
+                    return Trans::Pop;
+                }
+            }
+        }
+        Trans::None
+    }
+}
@@ -0,0 +1,673 @@
+//! Photo mode, to frame and take high resolution pictures of the paused game.
+//!
+//! The [`PhotoModeState`](struct.PhotoModeState.html) pauses the game like the `PausedState`,
+//! hides the UI and detaches a free camera from the active one, flying through the scene without
+//! colliding with it. Its field of view, roll and focus are changed with the keys listed in the
+//! built-in UI, and stored in the [`PhotoSettings`](struct.PhotoSettings.html) resource, so a
+//! game's own photo mode menu can change them too.
+//!
+//! Photos are rendered in tiles, each one a part of the view filling the screen, read back with
+//! the `FrameCapture` and put together, so they can have a higher resolution than the screen.
+//! Rendering more tiles than the resolution of the photo supersamples it. The photos are sent as
+//! [`Photo`](struct.Photo.html)s on an `EventChannel<Photo>`, for the game to save them.
+//!
+//! The renderer has no depth of field, so the focus distance and aperture are only given to the
+//! game's own passes through the `PhotoSettings`. The filters are applied to the photos.
+
+use std::{
+    collections::HashSet,
+    f32::consts::{FRAC_PI_2, FRAC_PI_3},
+    fmt::Write as FmtWrite,
+};
+
+use winit::{DeviceEvent, ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+use crate::{
+    assets::{AssetStorage, Loader},
+    core::{
+        nalgebra::{Matrix4, UnitQuaternion, Vector3},
+        timing::Time,
+        GlobalTransform, Parent, PauseState, Transform,
+    },
+    ecs::prelude::{Builder, Entity, Join, World},
+    input::is_close_requested,
+    renderer::{
+        ActiveCamera, Camera, CapturedFrame, FrameCapture, HiddenPropagate, Projection,
+        RenderLayers, ScreenDimensions,
+    },
+    shrev::EventChannel,
+    state::{SimpleState, SimpleTrans, StateData, Trans},
+    ui::{get_default_font, Anchor, FontAsset, LineMode, UiText, UiTransform},
+    GameData, StateEvent,
+};
+
+/// A color filter of the photos.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum PhotoFilter {
+    /// The colors as they are.
+    None,
+    /// Black and white.
+    Grayscale,
+    /// Brown tones, like an old photo.
+    Sepia,
+    /// Warmer colors.
+    Warm,
+    /// Colder colors.
+    Cool,
+}
+
+impl PhotoFilter {
+    /// Returns the next filter, cycling back to `None` after the last one.
+    pub fn next(self) -> Self {
+        match self {
+            PhotoFilter::None => PhotoFilter::Grayscale,
+            PhotoFilter::Grayscale => PhotoFilter::Sepia,
+            PhotoFilter::Sepia => PhotoFilter::Warm,
+            PhotoFilter::Warm => PhotoFilter::Cool,
+            PhotoFilter::Cool => PhotoFilter::None,
+        }
+    }
+
+    /// Returns the matrix multiplying the colors, as rows of weights of the red, green and blue
+    /// channels.
+    pub fn matrix(self) -> [[f32; 3]; 3] {
+        match self {
+            PhotoFilter::None => [[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]],
+            PhotoFilter::Grayscale => [[0.2126, 0.7152, 0.0722]; 3],
+            PhotoFilter::Sepia => [
+                [0.393, 0.769, 0.189],
+                [0.349, 0.686, 0.168],
+                [0.272, 0.534, 0.131],
+            ],
+            PhotoFilter::Warm => [[1.1, 0., 0.], [0., 1., 0.], [0., 0., 0.9]],
+            PhotoFilter::Cool => [[0.9, 0., 0.], [0., 1., 0.], [0., 0., 1.1]],
+        }
+    }
+
+    /// Applies the filter to the pixels of the frame.
+    pub fn apply(self, frame: &mut CapturedFrame) {
+        if self == PhotoFilter::None {
+            return;
+        }
+        let matrix = self.matrix();
+        for pixel in frame.pixels.chunks_mut(4) {
+            let color = [
+                f32::from(pixel[0]),
+                f32::from(pixel[1]),
+                f32::from(pixel[2]),
+            ];
+            for (channel, row) in pixel.iter_mut().zip(&matrix) {
+                let value: f32 = row.iter().zip(&color).map(|(m, c)| m * c).sum();
+                *channel = value.max(0.).min(255.) as u8;
+            }
+        }
+    }
+}
+
+impl Default for PhotoFilter {
+    fn default() -> Self {
+        PhotoFilter::None
+    }
+}
+
+/// Resource with the settings of the photo mode, while the `PhotoModeState` is active.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct PhotoSettings {
+    /// The vertical field of view of the camera, in radians.
+    pub fov: f32,
+    /// The roll of the camera, in radians.
+    pub roll: f32,
+    /// The distance in focus, for the depth of field passes of the game.
+    pub focus_distance: f32,
+    /// The aperture of the camera, `0` for everything to be in focus.
+    pub aperture: f32,
+    /// The color filter of the photos.
+    pub filter: PhotoFilter,
+    /// The number of tiles along each side of the photos, multiplying their resolution.
+    pub tiles: u32,
+    /// The number of rendered pixels averaged into a pixel of the photos along each side, e.g.
+    /// `2` to render 4 pixels for each one.
+    pub supersampling: u32,
+    /// The speed of the camera, in units per second.
+    pub speed: f32,
+    /// The rotation of the camera per pixel moved by the mouse, in radians.
+    pub sensitivity: f32,
+}
+
+impl Default for PhotoSettings {
+    fn default() -> Self {
+        PhotoSettings {
+            fov: FRAC_PI_3,
+            roll: 0.,
+            focus_distance: 10.,
+            aperture: 0.,
+            filter: PhotoFilter::None,
+            tiles: 2,
+            supersampling: 1,
+            speed: 5.,
+            sensitivity: 0.003,
+        }
+    }
+}
+
+/// A photo taken in photo mode, sent on an `EventChannel<Photo>`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Photo {
+    /// The pixels of the photo, with the filter applied.
+    pub frame: CapturedFrame,
+    /// The settings the photo was taken with.
+    pub settings: PhotoSettings,
+}
+
+/// State pausing the game to take photos with a free camera, see the
+/// [module documentation](index.html).
+///
+/// The camera flies with `W`, `A`, `S` and `D`, up and down with `R` and `F`, and looks around
+/// with the mouse. `Z` and `X` change the field of view, `Q` and `E` the roll, `C` and `V` the
+/// focus distance and `B` and `N` the aperture. `Tab` cycles through the filters, `H` hides the
+/// built-in UI, `Return` takes a photo and `Escape` pops the state, giving the game its camera
+/// back.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// StateEvent::Window(ref event) if is_key_down(event, VirtualKeyCode::F10) => {
+///     Trans::Push(Box::new(PhotoModeState::new().with_hidden_layers(hud_layers)))
+/// }
+/// ```
+#[derive(Debug)]
+pub struct PhotoModeState {
+    settings: PhotoSettings,
+    hidden_layers: RenderLayers,
+    exit_key: VirtualKeyCode,
+    session: Option<Session>,
+}
+
+#[derive(Debug)]
+struct Session {
+    previous: (PauseState, f32),
+    original_camera: Option<Entity>,
+    camera: Entity,
+    ui: Entity,
+    hidden_ui: Vec<Entity>,
+    show_ui: bool,
+    position: Vector3<f32>,
+    yaw: f32,
+    pitch: f32,
+    held: HashSet<VirtualKeyCode>,
+    capture: Option<TiledCapture>,
+}
+
+#[derive(Debug)]
+struct TiledCapture {
+    projection: Matrix4<f32>,
+    next: u32,
+    requested: bool,
+    frames: Vec<CapturedFrame>,
+}
+
+impl PhotoModeState {
+    /// Creates the state with the default settings.
+    pub fn new() -> Self {
+        PhotoModeState {
+            settings: PhotoSettings::default(),
+            hidden_layers: RenderLayers::none(),
+            exit_key: VirtualKeyCode::Escape,
+            session: None,
+        }
+    }
+
+    /// Sets the settings the photo mode starts with.
+    pub fn with_settings(mut self, settings: PhotoSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Sets the render layers hidden from the photo camera, e.g. the ones of the health bars and
+    /// markers of the HUD drawn in the world.
+    pub fn with_hidden_layers(mut self, layers: RenderLayers) -> Self {
+        self.hidden_layers = layers;
+        self
+    }
+
+    /// Sets the key leaving the photo mode.
+    pub fn with_exit_key(mut self, key: VirtualKeyCode) -> Self {
+        self.exit_key = key;
+        self
+    }
+}
+
+impl Default for PhotoModeState {
+    fn default() -> Self {
+        PhotoModeState::new()
+    }
+}
+
+impl SimpleState for PhotoModeState {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+        let previous = {
+            let mut pause = world.write_resource::<PauseState>();
+            let mut time = world.write_resource::<Time>();
+            let previous = (*pause, time.time_scale());
+            *pause = PauseState::Paused;
+            time.set_time_scale(0.);
+            previous
+        };
+        world.add_resource(self.settings.clone());
+        if !world.res.has_value::<EventChannel<Photo>>() {
+            world.add_resource(EventChannel::<Photo>::new());
+        }
+
+        let original_camera = world.read_resource::<ActiveCamera>().entity;
+        let (matrix, layers) = {
+            let entities = world.entities();
+            let cameras = world.read_storage::<Camera>();
+            let globals = world.read_storage::<GlobalTransform>();
+            original_camera
+                .filter(|&entity| cameras.contains(entity))
+                .or_else(|| (&*entities, &cameras).join().map(|(e, _)| e).next())
+                .and_then(|entity| Some((globals.get(entity)?.0, cameras.get(entity)?.layers)))
+                .unwrap_or_else(|| (Matrix4::identity(), RenderLayers::all()))
+        };
+        let forward = -matrix.column(2).xyz();
+        let forward = forward
+            .try_normalize(std::f32::EPSILON)
+            .unwrap_or_else(|| -Vector3::z());
+        let position = matrix.column(3).xyz();
+        let (yaw, pitch) = ((-forward.x).atan2(-forward.z), forward.y.asin());
+
+        let camera = world
+            .create_entity()
+            .with(Transform::default())
+            .with(GlobalTransform::default())
+            .with(
+                Camera::from(Projection::perspective(1., self.settings.fov))
+                    .with_layers(layers.without(self.hidden_layers)),
+            )
+            .build();
+        world.write_resource::<ActiveCamera>().entity = Some(camera);
+
+        let hidden_ui = {
+            let entities = world.entities();
+            let transforms = world.read_storage::<UiTransform>();
+            let parents = world.read_storage::<Parent>();
+            let mut hidden = world.write_storage::<HiddenPropagate>();
+            let roots = (&*entities, &transforms, !&parents, !&hidden)
+                .join()
+                .map(|(entity, _, _, _)| entity)
+                .collect::<Vec<_>>();
+            for &entity in &roots {
+                if let Err(err) = hidden.insert(entity, HiddenPropagate) {
+                    error!("Failed to hide the UI for the photo mode: {}", err);
+                }
+            }
+            roots
+        };
+        let ui = create_ui(world);
+
+        self.session = Some(Session {
+            previous,
+            original_camera,
+            camera,
+            ui,
+            hidden_ui,
+            show_ui: true,
+            position,
+            yaw,
+            pitch,
+            held: HashSet::new(),
+            capture: None,
+        });
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+        let session = match self.session.take() {
+            Some(session) => session,
+            None => return,
+        };
+        self.settings = world.read_resource::<PhotoSettings>().clone();
+        if let Err(err) = world.delete_entities(&[session.camera, session.ui]) {
+            error!("Failed to remove the photo camera: {}", err);
+        }
+        world.write_resource::<ActiveCamera>().entity = session.original_camera;
+        let mut hidden = world.write_storage::<HiddenPropagate>();
+        for entity in session.hidden_ui {
+            hidden.remove(entity);
+        }
+        let (pause, time_scale) = session.previous;
+        *world.write_resource::<PauseState>() = pause;
+        world.write_resource::<Time>().set_time_scale(time_scale);
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        let event = match event {
+            StateEvent::Window(event) => event,
+            _ => return Trans::None,
+        };
+        if is_close_requested(&event) {
+            return Trans::Quit;
+        }
+        let session = match self.session {
+            Some(ref mut session) => session,
+            None => return Trans::None,
+        };
+        match event {
+            Event::WindowEvent {
+                event:
+                    WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                virtual_keycode: Some(key),
+                                state,
+                                ..
+                            },
+                        ..
+                    },
+                ..
+            } => {
+                if state == ElementState::Released {
+                    session.held.remove(&key);
+                    return Trans::None;
+                }
+                // Key repeats don't trigger the actions again.
+                if !session.held.insert(key) {
+                    return Trans::None;
+                }
+                match key {
+                    key if key == self.exit_key => return Trans::Pop,
+                    VirtualKeyCode::Tab => {
+                        let mut settings = data.world.write_resource::<PhotoSettings>();
+                        settings.filter = settings.filter.next();
+                    }
+                    VirtualKeyCode::H => {
+                        session.show_ui = !session.show_ui;
+                        session.set_ui_visible(data.world, session.show_ui);
+                    }
+                    VirtualKeyCode::Return if session.capture.is_none() => {
+                        let projection = data
+                            .world
+                            .read_storage::<Camera>()
+                            .get(session.camera)
+                            .map(|camera| camera.proj);
+                        if let Some(projection) = projection {
+                            session.set_ui_visible(data.world, false);
+                            session.capture = Some(TiledCapture {
+                                projection,
+                                next: 0,
+                                requested: false,
+                                frames: Vec::new(),
+                            });
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Event::DeviceEvent {
+                event: DeviceEvent::MouseMotion { delta: (x, y) },
+                ..
+            } => {
+                if session.capture.is_none() {
+                    let sensitivity = data.world.read_resource::<PhotoSettings>().sensitivity;
+                    session.yaw -= x as f32 * sensitivity;
+                    session.pitch = (session.pitch - y as f32 * sensitivity)
+                        .max(-FRAC_PI_2 + 0.01)
+                        .min(FRAC_PI_2 - 0.01);
+                }
+            }
+            _ => {}
+        }
+        Trans::None
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        let world = &mut *data.world;
+        let session = match self.session {
+            Some(ref mut session) => session,
+            None => return Trans::None,
+        };
+        if session.capture.is_some() {
+            session.capture(world);
+        } else {
+            let seconds = world.read_resource::<Time>().delta_real_seconds();
+            session.fly(world, seconds);
+        }
+        Trans::None
+    }
+}
+
+impl Session {
+    /// Moves the camera with the held keys and applies the settings to it.
+    fn fly(&mut self, world: &mut World, seconds: f32) {
+        let held = |key| self.held.contains(&key);
+        let axis = |negative, positive| match (held(negative), held(positive)) {
+            (true, false) => -1.,
+            (false, true) => 1.,
+            _ => 0.,
+        };
+        let settings = {
+            let mut settings = world.write_resource::<PhotoSettings>();
+            settings.fov = (settings.fov + axis(VirtualKeyCode::Z, VirtualKeyCode::X) * seconds)
+                .max(0.1)
+                .min(2.5);
+            settings.roll += axis(VirtualKeyCode::Q, VirtualKeyCode::E) * seconds;
+            let focus = axis(VirtualKeyCode::C, VirtualKeyCode::V);
+            settings.focus_distance = (settings.focus_distance * (1. + focus * seconds)).max(0.1);
+            settings.aperture =
+                (settings.aperture + axis(VirtualKeyCode::B, VirtualKeyCode::N) * seconds).max(0.);
+            settings.clone()
+        };
+
+        let heading = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), self.yaw)
+            * UnitQuaternion::from_axis_angle(&Vector3::x_axis(), self.pitch);
+        let direction = Vector3::new(
+            axis(VirtualKeyCode::A, VirtualKeyCode::D),
+            axis(VirtualKeyCode::F, VirtualKeyCode::R),
+            axis(VirtualKeyCode::W, VirtualKeyCode::S),
+        );
+        self.position += heading * direction * settings.speed * seconds;
+        let rotation = heading * UnitQuaternion::from_axis_angle(&Vector3::z_axis(), settings.roll);
+
+        if let Some(transform) = world.write_storage::<Transform>().get_mut(self.camera) {
+            transform.set_position(self.position);
+            transform.set_rotation(rotation);
+        }
+        let aspect = world.read_resource::<ScreenDimensions>().aspect_ratio();
+        if let Some(camera) = world.write_storage::<Camera>().get_mut(self.camera) {
+            camera.proj = Camera::from(Projection::perspective(aspect, settings.fov)).proj;
+        }
+        if self.show_ui {
+            let mut texts = world.write_storage::<UiText>();
+            if let Some(text) = texts.get_mut(self.ui) {
+                text.text = describe(&settings);
+            }
+        }
+    }
+
+    /// Renders the next tile of the photo, or sends the photo once all of them are read back.
+    fn capture(&mut self, world: &mut World) {
+        let mut capture = match self.capture.take() {
+            Some(capture) => capture,
+            None => return,
+        };
+        let settings = world.read_resource::<PhotoSettings>().clone();
+        let tiles = settings.tiles.max(1);
+        if capture.requested {
+            match world.write_resource::<FrameCapture>().take() {
+                Some(frame) => {
+                    capture.frames.push(frame);
+                    capture.next += 1;
+                    capture.requested = false;
+                }
+                None => {
+                    self.capture = Some(capture);
+                    return;
+                }
+            }
+        }
+
+        let projection = if capture.next < tiles * tiles {
+            let (column, row) = (capture.next % tiles, capture.next / tiles);
+            tile_projection(&capture.projection, tiles, column, row)
+        } else {
+            capture.projection
+        };
+        if let Some(camera) = world.write_storage::<Camera>().get_mut(self.camera) {
+            camera.proj = projection;
+        }
+        if capture.next < tiles * tiles {
+            world.write_resource::<FrameCapture>().request();
+            capture.requested = true;
+            self.capture = Some(capture);
+            return;
+        }
+
+        let mut frame = assemble(&capture.frames, tiles, settings.supersampling);
+        settings.filter.apply(&mut frame);
+        world
+            .write_resource::<EventChannel<Photo>>()
+            .single_write(Photo { frame, settings });
+        let show_ui = self.show_ui;
+        self.set_ui_visible(world, show_ui);
+    }
+
+    fn set_ui_visible(&self, world: &mut World, visible: bool) {
+        let mut hidden = world.write_storage::<HiddenPropagate>();
+        if visible {
+            hidden.remove(self.ui);
+        } else if let Err(err) = hidden.insert(self.ui, HiddenPropagate) {
+            error!("Failed to hide the photo mode UI: {}", err);
+        }
+    }
+}
+
+/// Creates the text listing the settings and their keys.
+fn create_ui(world: &mut World) -> Entity {
+    let font = get_default_font(
+        &world.read_resource::<Loader>(),
+        &world.read_resource::<AssetStorage<FontAsset>>(),
+    );
+    let mut transform = UiTransform::new(
+        "photo_mode".to_string(),
+        Anchor::BottomLeft,
+        205.,
+        85.,
+        1000.,
+        400.,
+        160.,
+    );
+    transform.opaque = false;
+    let mut text = UiText::new(font, String::new(), [1., 1., 1., 1.], 16.);
+    text.line_mode = LineMode::Wrap;
+    text.align = Anchor::BottomLeft;
+    world.create_entity().with(transform).with(text).build()
+}
+
+fn describe(settings: &PhotoSettings) -> String {
+    let mut text = String::new();
+    let _ = writeln!(
+        text,
+        "Field of view: {:.0} [Z/X]",
+        settings.fov.to_degrees()
+    );
+    let _ = writeln!(text, "Roll: {:.0} [Q/E]", settings.roll.to_degrees());
+    let _ = writeln!(text, "Focus: {:.1} [C/V]", settings.focus_distance);
+    let _ = writeln!(text, "Aperture: {:.2} [B/N]", settings.aperture);
+    let _ = writeln!(text, "Filter: {:?} [Tab]", settings.filter);
+    let _ = write!(text, "Take photo [Return]  Hide [H]");
+    text
+}
+
+/// Returns the projection rendering the tile of the view to the whole screen, the tiles being
+/// numbered from the top left.
+fn tile_projection(projection: &Matrix4<f32>, tiles: u32, column: u32, row: u32) -> Matrix4<f32> {
+    let scale = tiles as f32;
+    let center_x = -1. + (2. * column as f32 + 1.) / scale;
+    let center_y = 1. - (2. * row as f32 + 1.) / scale;
+    let mut tile = Matrix4::identity();
+    tile[(0, 0)] = scale;
+    tile[(1, 1)] = scale;
+    tile[(0, 3)] = -scale * center_x;
+    tile[(1, 3)] = -scale * center_y;
+    tile * projection
+}
+
+/// Puts the frames of the tiles together, averaging the pixels by the supersampling.
+fn assemble(frames: &[CapturedFrame], tiles: u32, supersampling: u32) -> CapturedFrame {
+    let (tile_width, tile_height) = frames
+        .first()
+        .map_or((0, 0), |frame| (frame.width, frame.height));
+    let samples = supersampling.max(1);
+    let width = tile_width * tiles / samples;
+    let height = tile_height * tiles / samples;
+    let mut pixels = vec![0; (width * height * 4) as usize];
+    for y in 0..height {
+        for x in 0..width {
+            let mut sum = [0u32; 4];
+            for sample_y in y * samples..(y + 1) * samples {
+                for sample_x in x * samples..(x + 1) * samples {
+                    let tile = (sample_y / tile_height) * tiles + sample_x / tile_width;
+                    let pixel = frames.get(tile as usize).and_then(|frame| {
+                        frame.pixel(sample_x % tile_width, sample_y % tile_height)
+                    });
+                    for (sum, value) in sum.iter_mut().zip(&pixel.unwrap_or([0; 4])) {
+                        *sum += u32::from(*value);
+                    }
+                }
+            }
+            let i = ((y * width + x) * 4) as usize;
+            for (channel, sum) in pixels[i..i + 4].iter_mut().zip(&sum) {
+                *channel = (sum / (samples * samples)) as u8;
+            }
+        }
+    }
+    CapturedFrame {
+        width,
+        height,
+        pixels,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::core::nalgebra::Vector4;
+
+    #[test]
+    fn tiles_cover_the_view_and_are_put_together() {
+        let projection = Matrix4::new_perspective(1., 1., 0.1, 100.);
+        // The center of the top right tile of 2 x 2 is at the center of the screen.
+        let point = Vector4::new(0.5, 0.5, -1., 1.);
+        let clip = projection * point;
+        let (x, y) = (clip.x / clip.w, clip.y / clip.w);
+        let tile = tile_projection(&projection, 2, 1, 0) * point;
+        assert!((tile.x / tile.w - (2. * x - 1.)).abs() < 1e-4);
+        assert!((tile.y / tile.w - (2. * y - 1.)).abs() < 1e-4);
+
+        let frames = (0..4u8)
+            .map(|i| CapturedFrame {
+                width: 2,
+                height: 2,
+                pixels: [i * 10, 0, 0, 255]
+                    .iter()
+                    .cycle()
+                    .take(16)
+                    .cloned()
+                    .collect(),
+            })
+            .collect::<Vec<_>>();
+        let photo = assemble(&frames, 2, 2);
+        assert_eq!((2, 2), (photo.width, photo.height));
+        assert_eq!(Some([10, 0, 0, 255]), photo.pixel(1, 0));
+        assert_eq!(Some([20, 0, 0, 255]), photo.pixel(0, 1));
+        let photo = assemble(&frames, 2, 1);
+        assert_eq!(Some([30, 0, 0, 255]), photo.pixel(3, 3));
+    }
+}
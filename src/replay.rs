@@ -0,0 +1,436 @@
+//! Replays of recorded matches.
+//!
+//! A replay relies on the game being deterministic: the same world, with the same `Rng`, given
+//! the same input on every fixed update, plays the same way. So a `Replay` only holds a snapshot
+//! of the world at the start, the input of every frame, and keyframes, snapshots taken
+//! periodically to seek without playing everything from the start again.
+//!
+//! The world is snapshotted by the game, implementing `WorldSnapshot` for the resources and
+//! components its simulation depends on. The `ReplayRecorder` is called from
+//! `State::fixed_update` before the simulation runs, and the `ReplayState` plays a `Replay` back,
+//! controlled with the `ReplayControls` resource.
+//!
+//! Frames are fixed updates: systems the replay drives have to be dispatched from
+//! `State::fixed_update` and read their input as `InputEvent`s, which the `ReplayState` sends
+//! instead of the player. Live input should be ignored while a replay plays.
+
+use std::{fs, hash::Hash, path::Path};
+
+use serde::{de::DeserializeOwned, Serialize};
+use winit::VirtualKeyCode;
+
+use crate::{
+    core::{
+        shrev::{EventChannel, ReaderId},
+        Rng,
+    },
+    ecs::prelude::{Dispatcher, World},
+    error::{Error, Result},
+    input::{is_close_requested, is_key_down, InputEvent},
+    state::{SimpleState, SimpleTrans, StateData, Trans},
+    GameData, StateEvent,
+};
+
+/// Saves and restores the parts of the world the simulation of the game depends on.
+pub trait WorldSnapshot: Send + Sync {
+    /// Returns a snapshot of the world.
+    fn save(&mut self, world: &World) -> Result<String>;
+
+    /// Puts the world back at the snapshot.
+    fn restore(&mut self, world: &mut World, snapshot: &str) -> Result<()>;
+}
+
+/// The world at the start of a frame of a `Replay`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ReplayKeyframe {
+    /// The frame, counted from the start of the replay.
+    pub frame: u64,
+    /// The random number generator.
+    pub rng: Rng,
+    /// The snapshot of the world.
+    pub world: String,
+}
+
+/// An input event received before a frame of a `Replay`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ReplayInput<AC> {
+    /// The frame, counted from the start of the replay.
+    pub frame: u64,
+    /// The received event.
+    pub event: InputEvent<AC>,
+}
+
+/// A recorded match, see the [module documentation](index.html).
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Replay<AC> {
+    /// The keyframes, the first one at frame 0, in the order of their frames.
+    pub keyframes: Vec<ReplayKeyframe>,
+    /// The input events, in the order they were received.
+    pub inputs: Vec<ReplayInput<AC>>,
+    /// The number of frames recorded.
+    pub frames: u64,
+}
+
+impl<AC> Replay<AC> {
+    /// Returns the last keyframe at or before the frame.
+    pub fn keyframe_before(&self, frame: u64) -> Option<&ReplayKeyframe> {
+        self.keyframes.iter().rev().find(|key| key.frame <= frame)
+    }
+}
+
+impl<AC> Replay<AC>
+where
+    AC: Serialize,
+{
+    /// Writes the replay to a compact RON file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let ron = ron::ser::to_string(self)
+            .map_err(|e| Error::Core(format!("Failed to serialize replay: {}", e).into()))?;
+        fs::write(path, ron)?;
+        Ok(())
+    }
+}
+
+impl<AC> Replay<AC>
+where
+    AC: DeserializeOwned,
+{
+    /// Reads a replay written with `save`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let ron = fs::read_to_string(path)?;
+        ron::de::from_str(&ron)
+            .map_err(|e| Error::Core(format!("Failed to deserialize replay: {}", e).into()))
+    }
+}
+
+fn keyframe(world: &World, snapshot: &mut dyn WorldSnapshot, frame: u64) -> Result<ReplayKeyframe> {
+    Ok(ReplayKeyframe {
+        frame,
+        rng: world.read_resource::<Rng>().clone(),
+        world: snapshot.save(world)?,
+    })
+}
+
+/// Records a `Replay` of the input events, for the state running the fixed updates of the game.
+pub struct ReplayRecorder<AC> {
+    keyframe_interval: u64,
+    reader: Option<ReaderId<InputEvent<AC>>>,
+    replay: Option<Replay<AC>>,
+}
+
+impl<AC> ReplayRecorder<AC>
+where
+    AC: Hash + Eq + Clone + Send + Sync + 'static,
+{
+    /// Creates a recorder taking a keyframe every that many frames.
+    pub fn new(keyframe_interval: u64) -> Self {
+        ReplayRecorder {
+            keyframe_interval: keyframe_interval.max(1),
+            reader: None,
+            replay: None,
+        }
+    }
+
+    /// Starts recording, from the world as it is now.
+    pub fn start(&mut self, world: &mut World, snapshot: &mut dyn WorldSnapshot) -> Result<()> {
+        self.reader = Some(
+            world
+                .write_resource::<EventChannel<InputEvent<AC>>>()
+                .register_reader(),
+        );
+        self.replay = Some(Replay {
+            keyframes: vec![keyframe(world, snapshot, 0)?],
+            inputs: Vec::new(),
+            frames: 0,
+        });
+        Ok(())
+    }
+
+    /// Returns whether the recorder is recording.
+    pub fn is_recording(&self) -> bool {
+        self.replay.is_some()
+    }
+
+    /// Records the input events received since the last frame, taking a keyframe when it's
+    /// time to. Called on every fixed update, before the simulation runs.
+    pub fn record_frame(&mut self, world: &World, snapshot: &mut dyn WorldSnapshot) -> Result<()> {
+        let (replay, reader) = match (self.replay.as_mut(), self.reader.as_mut()) {
+            (Some(replay), Some(reader)) => (replay, reader),
+            _ => return Ok(()),
+        };
+        let frame = replay.frames;
+        if frame > 0 && frame % self.keyframe_interval == 0 {
+            replay.keyframes.push(keyframe(world, snapshot, frame)?);
+        }
+        let channel = world.read_resource::<EventChannel<InputEvent<AC>>>();
+        replay
+            .inputs
+            .extend(channel.read(reader).map(|event| ReplayInput {
+                frame,
+                event: event.clone(),
+            }));
+        replay.frames += 1;
+        Ok(())
+    }
+
+    /// Stops recording, returning the replay.
+    pub fn finish(&mut self) -> Option<Replay<AC>> {
+        self.reader = None;
+        self.replay.take()
+    }
+}
+
+/// Plays a `Replay` back, sending its input events frame by frame.
+pub struct ReplayPlayer<AC> {
+    replay: Replay<AC>,
+    frame: u64,
+    next_input: usize,
+}
+
+impl<AC> ReplayPlayer<AC>
+where
+    AC: Hash + Eq + Clone + Send + Sync + 'static,
+{
+    /// Creates a player of the replay.
+    pub fn new(replay: Replay<AC>) -> Self {
+        ReplayPlayer {
+            replay,
+            frame: 0,
+            next_input: 0,
+        }
+    }
+
+    /// Returns the replay.
+    pub fn replay(&self) -> &Replay<AC> {
+        &self.replay
+    }
+
+    /// Returns the next frame.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Returns whether all frames were played.
+    pub fn is_finished(&self) -> bool {
+        self.frame >= self.replay.frames
+    }
+
+    /// Puts the world back at the last keyframe at or before the frame. Playing the frames up to
+    /// it then reaches the frame.
+    pub fn seek(
+        &mut self,
+        world: &mut World,
+        snapshot: &mut dyn WorldSnapshot,
+        frame: u64,
+    ) -> Result<()> {
+        let key = self
+            .replay
+            .keyframe_before(frame)
+            .ok_or_else(|| Error::Core("Replay has no keyframe".into()))?;
+        snapshot.restore(world, &key.world)?;
+        *world.write_resource::<Rng>() = key.rng.clone();
+        self.frame = key.frame;
+        self.next_input = self
+            .replay
+            .inputs
+            .iter()
+            .position(|input| input.frame >= key.frame)
+            .unwrap_or_else(|| self.replay.inputs.len());
+        Ok(())
+    }
+
+    /// Sends the input events of the next frame. Returns `false` once all frames were played.
+    pub fn step(&mut self, world: &World) -> bool {
+        if self.is_finished() {
+            return false;
+        }
+        let inputs = &self.replay.inputs[self.next_input..];
+        let count = inputs
+            .iter()
+            .take_while(|input| input.frame == self.frame)
+            .count();
+        world
+            .write_resource::<EventChannel<InputEvent<AC>>>()
+            .iter_write(inputs[..count].iter().map(|input| input.event.clone()));
+        self.next_input += count;
+        self.frame += 1;
+        true
+    }
+}
+
+/// Resource controlling the `ReplayState`, e.g. from the buttons of a replay UI.
+#[derive(Clone, Debug)]
+pub struct ReplayControls {
+    /// Whether the playback is paused.
+    pub paused: bool,
+    /// How many frames are played per fixed update, e.g. 2 for double speed.
+    pub speed: f32,
+    /// The frame to seek to on the next fixed update.
+    pub seek: Option<u64>,
+    /// The next frame, set by the `ReplayState`.
+    pub frame: u64,
+    /// The number of frames of the replay, set by the `ReplayState`.
+    pub frames: u64,
+}
+
+impl Default for ReplayControls {
+    fn default() -> Self {
+        ReplayControls {
+            paused: false,
+            speed: 1.,
+            seek: None,
+            frame: 0,
+            frames: 0,
+        }
+    }
+}
+
+/// State playing a `Replay` back, running the simulation of the game on its fixed updates.
+///
+/// The simulation is the dispatcher with the systems the game runs on its fixed updates. The
+/// playback is controlled with the `ReplayControls` resource, and the state pops itself when
+/// `Escape` is pressed.
+pub struct ReplayState<AC, S> {
+    player: ReplayPlayer<AC>,
+    snapshot: S,
+    simulation: Dispatcher<'static, 'static>,
+    exit_key: Option<VirtualKeyCode>,
+    budget: f32,
+}
+
+impl<AC, S> ReplayState<AC, S>
+where
+    AC: Hash + Eq + Clone + Send + Sync + 'static,
+    S: WorldSnapshot,
+{
+    /// Creates a state playing the replay.
+    pub fn new(replay: Replay<AC>, snapshot: S, simulation: Dispatcher<'static, 'static>) -> Self {
+        ReplayState {
+            player: ReplayPlayer::new(replay),
+            snapshot,
+            simulation,
+            exit_key: Some(VirtualKeyCode::Escape),
+            budget: 0.,
+        }
+    }
+
+    /// Sets the key leaving the replay, or `None` to only leave by popping the state.
+    pub fn with_exit_key(mut self, exit_key: Option<VirtualKeyCode>) -> Self {
+        self.exit_key = exit_key;
+        self
+    }
+
+    fn seek(&mut self, world: &mut World, frame: u64) {
+        if let Err(err) = self.player.seek(world, &mut self.snapshot, frame) {
+            error!("Failed to seek the replay: {}", err);
+            return;
+        }
+        while self.player.frame() < frame && self.player.step(world) {
+            self.simulation.dispatch(&world.res);
+            world.maintain();
+        }
+    }
+}
+
+impl<AC, S> SimpleState for ReplayState<AC, S>
+where
+    AC: Hash + Eq + Clone + Send + Sync + 'static,
+    S: WorldSnapshot,
+{
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.simulation.setup(&mut data.world.res);
+        self.seek(data.world, 0);
+        data.world.add_resource(ReplayControls {
+            frames: self.player.replay().frames,
+            ..Default::default()
+        });
+    }
+
+    fn handle_event(
+        &mut self,
+        _: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(ref event) = event {
+            if is_close_requested(event) {
+                return Trans::Quit;
+            }
+            if self.exit_key.map_or(false, |key| is_key_down(event, key)) {
+                return Trans::Pop;
+            }
+        }
+        Trans::None
+    }
+
+    fn fixed_update(&mut self, data: StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        let (seek, paused, speed) = {
+            let mut controls = data.world.write_resource::<ReplayControls>();
+            (controls.seek.take(), controls.paused, controls.speed)
+        };
+        if let Some(frame) = seek {
+            self.seek(data.world, frame);
+        } else if !paused {
+            self.budget += speed.max(0.);
+            while self.budget >= 1. {
+                self.budget -= 1.;
+                if !self.player.step(data.world) {
+                    break;
+                }
+                self.simulation.dispatch(&data.world.res);
+                data.world.maintain();
+            }
+        }
+        data.world.write_resource::<ReplayControls>().frame = self.player.frame();
+        Trans::None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replay(frames: &[u64]) -> Replay<String> {
+        Replay {
+            keyframes: [0, 10, 20]
+                .iter()
+                .map(|&frame| ReplayKeyframe {
+                    frame,
+                    rng: Rng::new(frame),
+                    world: String::new(),
+                })
+                .collect(),
+            inputs: frames
+                .iter()
+                .map(|&frame| ReplayInput {
+                    frame,
+                    event: InputEvent::ActionPressed(frame.to_string()),
+                })
+                .collect(),
+            frames: 30,
+        }
+    }
+
+    #[test]
+    fn steps_send_the_inputs_of_their_frame() {
+        let replay = replay(&[0, 0, 2, 15]);
+        assert_eq!(10, replay.keyframe_before(15).unwrap().frame);
+
+        let mut world = World::new();
+        world.add_resource(EventChannel::<InputEvent<String>>::new());
+        let mut reader = world
+            .write_resource::<EventChannel<InputEvent<String>>>()
+            .register_reader();
+        let mut player = ReplayPlayer::new(replay);
+        let mut counts = Vec::new();
+        for _ in 0..3 {
+            assert!(player.step(&world));
+            counts.push(
+                world
+                    .read_resource::<EventChannel<InputEvent<String>>>()
+                    .read(&mut reader)
+                    .count(),
+            );
+        }
+        assert_eq!(vec![2, 0, 1], counts);
+    }
+}
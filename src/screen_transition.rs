@@ -0,0 +1,68 @@
+//! Transitions between states behind a full-screen effect.
+
+use std::{cell::Cell, rc::Rc};
+
+use crate::{
+    renderer::{ScreenTransition, TransitionEffect},
+    state::{AsyncTrans, SimpleState, SimpleTrans, StateData, Trans},
+    GameData,
+};
+
+/// State pushed for one frame, so the last frame of the state below is drawn and copied for
+/// the `ScreenTransition`.
+struct TransitionCapture {
+    transition: Option<(TransitionEffect, f32)>,
+    started: Rc<Cell<bool>>,
+}
+
+impl SimpleState for TransitionCapture {
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        if let Some((effect, duration)) = self.transition.take() {
+            match data.world.res.try_fetch_mut::<ScreenTransition>() {
+                Some(mut transition) => transition.start(effect, duration),
+                None => warn!("Screen transitions need the `RenderSystem`"),
+            }
+            self.started.set(true);
+        }
+        Trans::None
+    }
+}
+
+impl SimpleTrans {
+    /// Performs the transition behind a `ScreenTransition`, drawn by the `DrawTransition` pass.
+    ///
+    /// The transition is put off for a frame, to draw and copy the last frame of the active
+    /// state. It is paused and resumed for that frame, as for a `Trans::Async`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// fn handle_event(&mut self, _: StateData<'_, GameData<'_, '_>>, event: StateEvent) -> SimpleTrans {
+    ///     match event {
+    ///         StateEvent::Window(ref event) if is_key_down(event, VirtualKeyCode::Return) => {
+    ///             Trans::Switch(Box::new(Gameplay))
+    ///                 .with_screen_transition(TransitionEffect::Crossfade, 0.5)
+    ///         }
+    ///         _ => Trans::None,
+    ///     }
+    /// }
+    /// ```
+    pub fn with_screen_transition(self, effect: TransitionEffect, duration: f32) -> Self {
+        if let Trans::None = self {
+            return self;
+        }
+        let started = Rc::new(Cell::new(false));
+        let state = TransitionCapture {
+            transition: Some((effect, duration)),
+            started: started.clone(),
+        };
+        let mut trans = Some(self);
+        Trans::Async(AsyncTrans::from_poll(state, move || {
+            if started.get() {
+                trans.take()
+            } else {
+                None
+            }
+        }))
+    }
+}
@@ -1,11 +1,20 @@
 //! Utilities for game state management.
 
 use amethyst_input::is_close_requested;
+use crossbeam_channel::TryRecvError;
+use rayon::ThreadPool;
 
-use crate::{ecs::prelude::World, GameData, StateEvent};
+use crate::{
+    core::{shrev::EventChannel, RunCriteria},
+    ecs::prelude::{Read, World},
+    GameData, StateEvent,
+};
 
+use std::any::Any;
 use std::fmt::Result as FmtResult;
-use std::fmt::{Display, Formatter};
+use std::fmt::{Debug, Display, Formatter};
+use std::marker::PhantomData;
+use std::panic::{self, AssertUnwindSafe};
 
 /// Error type for errors occurring in StateMachine
 #[derive(Debug)]
@@ -55,8 +64,172 @@ pub enum Trans<T, E> {
     Push(Box<dyn State<T, E>>),
     /// Remove the current state on the stack and insert a different one.
     Switch(Box<dyn State<T, E>>),
+    /// Like `Push`, but passes a payload to the new state's `receive` method before it starts.
+    PushWith(Box<dyn State<T, E>>, Payload),
+    /// Like `Switch`, but passes a payload to the new state's `receive` method before it starts.
+    SwitchWith(Box<dyn State<T, E>>, Payload),
+    /// Remove states until the state with the given name is active and resume it. Nothing
+    /// happens if there is no such state on the stack. See `Trans::pop_to`.
+    PopTo(&'static str),
+    /// Remove all states but the bottom one and resume it.
+    PopAll,
     /// Stop and remove all states and shut down the engine.
     Quit,
+    /// Push a transitional state and run a task on the thread pool. Once the task finished, the
+    /// transitional state is popped and the transition produced from the task's result is
+    /// performed. See `AsyncTrans`.
+    Async(AsyncTrans<T, E>),
+}
+
+impl<T, E> Trans<T, E> {
+    /// Returns a `Trans::PopTo` that returns to the topmost state of type `S`.
+    ///
+    /// States are found by their [`State::name`](trait.State.html#method.name), so this only
+    /// works for states that don't override it. Requires Rust 1.38 or later.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// // Settings -> Keybinds -> Confirm: go back to the main menu.
+    /// Trans::pop_to::<MainMenu>()
+    /// ```
+    #[cfg(type_name)]
+    pub fn pop_to<S: ?Sized>() -> Self {
+        Trans::PopTo(std::any::type_name::<S>())
+    }
+}
+
+/// A long running task together with the state that is active while it runs.
+///
+/// The task runs on the thread pool, so loading a world, connecting to a server or similar work
+/// doesn't block the frame. In the meantime the transitional state is pushed on the stack and
+/// keeps updating and rendering, e.g. to show a spinner. When the task finished, the transitional
+/// state (and any state pushed above it) is popped and the transition returned by `then` is
+/// performed, as if the state that returned `Trans::Async` had returned it.
+///
+/// If the task panics, the transitional state is popped and no further transition is performed.
+/// If the transitional state is removed before the task finished, e.g. because the user
+/// cancelled, the result of the task is discarded.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// fn handle_event(&mut self, data: StateData<'_, GameData<'_, '_>>, event: StateEvent) -> SimpleTrans {
+///     if let StateEvent::Ui(UiEvent { event_type: UiEventType::Click, .. }) = event {
+///         let pool = data.world.read_resource::<ArcThreadPool>().clone();
+///         let address = self.address.clone();
+///         return Trans::Async(AsyncTrans::new(
+///             &pool,
+///             ConnectingState::default(),
+///             move || Connection::open(&address),
+///             |connection| match connection {
+///                 Ok(connection) => Trans::Switch(Box::new(OnlineState::new(connection))),
+///                 Err(_) => Trans::None,
+///             },
+///         ));
+///     }
+///     Trans::None
+/// }
+/// ```
+pub struct AsyncTrans<T, E> {
+    state: Box<dyn State<T, E>>,
+    poll: Box<dyn FnMut() -> Option<Trans<T, E>>>,
+}
+
+impl<T, E: Send + Sync + 'static> AsyncTrans<T, E> {
+    /// Spawns `task` on the thread pool, showing `state` until it finished.
+    ///
+    /// `then` is called on the main thread with the result of the task, so it can create states
+    /// that aren't `Send`.
+    pub fn new<S, R, F, C>(pool: &ThreadPool, state: S, task: F, then: C) -> Self
+    where
+        S: State<T, E> + 'static,
+        R: Send + 'static,
+        F: FnOnce() -> R + Send + 'static,
+        C: FnOnce(R) -> Trans<T, E> + 'static,
+    {
+        let (sender, receiver) = crossbeam_channel::bounded(1);
+        pool.spawn(move || {
+            // The pool would abort on a panic, so it drops the sender instead, which the poll
+            // sees as a disconnection.
+            if let Ok(result) = panic::catch_unwind(AssertUnwindSafe(task)) {
+                // The receiver is gone if the transition was cancelled.
+                let _ = sender.send(result);
+            }
+        });
+
+        let mut then = Some(then);
+        let poll = move || match receiver.try_recv() {
+            Ok(result) => then.take().map(|then| then(result)),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                error!("Task of an asynchronous transition panicked");
+                Some(Trans::None)
+            }
+        };
+
+        AsyncTrans::from_poll(state, poll)
+    }
+
+    /// Shows `state` until `poll` returns the transition to perform.
+    pub(crate) fn from_poll<S, P>(state: S, poll: P) -> Self
+    where
+        S: State<T, E> + 'static,
+        P: FnMut() -> Option<Trans<T, E>> + 'static,
+    {
+        AsyncTrans {
+            state: Box::new(state),
+            poll: Box::new(poll),
+        }
+    }
+}
+
+/// An `AsyncTrans` whose transitional state was pushed at `depth`.
+struct PendingTrans<T, E> {
+    depth: usize,
+    poll: Box<dyn FnMut() -> Option<Trans<T, E>>>,
+}
+
+/// A value passed from one state to the next with `Trans::PushWith` or `Trans::SwitchWith`.
+///
+/// The receiving state gets the payload in [`State::receive`](trait.State.html#method.receive),
+/// right before it starts, and takes the value out with `downcast`.
+///
+/// # Examples
+///
+/// ```rust
+/// # extern crate amethyst;
+/// use amethyst::Payload;
+///
+/// struct SelectedLevel(u32);
+///
+/// let payload = Payload::new(SelectedLevel(3));
+/// assert!(payload.is::<SelectedLevel>());
+/// assert_eq!(3, payload.downcast::<SelectedLevel>().ok().unwrap().0);
+/// ```
+pub struct Payload(Box<dyn Any>);
+
+impl Payload {
+    /// Creates a payload containing `value`.
+    pub fn new<P: 'static>(value: P) -> Self {
+        Payload(Box::new(value))
+    }
+
+    /// Checks whether the payload contains a value of type `P`.
+    pub fn is<P: 'static>(&self) -> bool {
+        self.0.is::<P>()
+    }
+
+    /// Takes the value out of the payload, or returns the payload if it doesn't contain a `P`.
+    pub fn downcast<P: 'static>(self) -> Result<P, Payload> {
+        self.0.downcast().map(|value| *value).map_err(Payload)
+    }
+}
+
+impl Debug for Payload {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> FmtResult {
+        fmt.write_str("Payload")
+    }
 }
 
 /// Event queue to trigger state `Trans` from other places than a `State`'s methods.
@@ -77,6 +250,10 @@ pub type SimpleTrans = Trans<GameData<'static, 'static>, StateEvent>;
 
 /// A trait which defines game states that can be used by the state machine.
 pub trait State<T, E: Send + Sync + 'static> {
+    /// Executed with the payload of `Trans::PushWith` or `Trans::SwitchWith`, right before
+    /// `on_start`. The payload is dropped by default.
+    fn receive(&mut self, _data: StateData<'_, T>, _payload: Payload) {}
+
     /// Executed when the game state begins.
     fn on_start(&mut self, _data: StateData<'_, T>) {}
 
@@ -97,6 +274,10 @@ pub trait State<T, E: Send + Sync + 'static> {
     /// Executed repeatedly at stable, predictable intervals (1/60th of a second
     /// by default),
     /// if this is the active state.
+    ///
+    /// Each frame runs as many fixed updates as needed to catch up with real time, possibly none,
+    /// and always before `update`. Afterwards `Time::fixed_alpha` tells how far the frame is
+    /// between the last and the next fixed update, which rendering uses to interpolate.
     fn fixed_update(&mut self, _data: StateData<'_, T>) -> Trans<T, E> {
         Trans::None
     }
@@ -117,10 +298,25 @@ pub trait State<T, E: Send + Sync + 'static> {
     /// even when this is not the active state,
     /// as long as this state is on the [StateMachine](struct.StateMachine.html)'s state-stack.
     fn shadow_update(&mut self, _data: StateData<'_, T>) {}
+
+    /// Returns a name identifying this state in diagnostics, such as crash reports.
+    ///
+    /// Defaults to the type name of the state when building with Rust 1.38 or later, and to
+    /// `"unnamed state"` before, so override it to tell states apart on older compilers.
+    fn name(&self) -> &'static str {
+        #[cfg(type_name)]
+        return std::any::type_name::<Self>();
+        #[cfg(not(type_name))]
+        return "unnamed state";
+    }
 }
 
 /// An empty `State` trait. It contains no `StateData` or custom `StateEvent`.
 pub trait EmptyState {
+    /// Executed with the payload of `Trans::PushWith` or `Trans::SwitchWith`, right before
+    /// `on_start`. The payload is dropped by default.
+    fn receive(&mut self, _data: StateData<'_, ()>, _payload: Payload) {}
+
     /// Executed when the game state begins.
     fn on_start(&mut self, _data: StateData<'_, ()>) {}
 
@@ -170,6 +366,12 @@ pub trait EmptyState {
 }
 
 impl<T: EmptyState> State<(), StateEvent> for T {
+    /// Executed with the payload of `Trans::PushWith` or `Trans::SwitchWith`, right before
+    /// `on_start`.
+    fn receive(&mut self, data: StateData<'_, ()>, payload: Payload) {
+        self.receive(data, payload)
+    }
+
     /// Executed when the game state begins.
     fn on_start(&mut self, data: StateData<'_, ()>) {
         self.on_start(data)
@@ -224,6 +426,10 @@ impl<T: EmptyState> State<(), StateEvent> for T {
 
 /// A simple `State` trait. It contains `GameData` as its `StateData` and no custom `StateEvent`.
 pub trait SimpleState {
+    /// Executed with the payload of `Trans::PushWith` or `Trans::SwitchWith`, right before
+    /// `on_start`. The payload is dropped by default.
+    fn receive(&mut self, _data: StateData<'_, GameData<'_, '_>>, _payload: Payload) {}
+
     /// Executed when the game state begins.
     fn on_start(&mut self, _data: StateData<'_, GameData<'_, '_>>) {}
 
@@ -279,6 +485,12 @@ pub trait SimpleState {
 impl<T: SimpleState> State<GameData<'static, 'static>, StateEvent> for T {
     //pub trait SimpleState<'a,'b>: State<GameData<'a,'b>,()> {
 
+    /// Executed with the payload of `Trans::PushWith` or `Trans::SwitchWith`, right before
+    /// `on_start`.
+    fn receive(&mut self, data: StateData<'_, GameData<'_, '_>>, payload: Payload) {
+        self.receive(data, payload)
+    }
+
     /// Executed when the game state begins.
     fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
         self.on_start(data)
@@ -337,6 +549,120 @@ impl<T: SimpleState> State<GameData<'static, 'static>, StateEvent> for T {
     }
 }
 
+/// Notification about a change of the state stack, sent on an
+/// `EventChannel<StateTransitionEvent>`.
+///
+/// Systems can read this channel to react to state changes, e.g. to pause physics while a menu
+/// is pushed. States are identified by [`State::name`](trait.State.html#method.name).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum StateTransitionEvent {
+    /// A state was pushed onto the stack and started. Also sent for the initial state.
+    Pushed(&'static str),
+    /// A state was stopped and removed from the stack.
+    Popped(&'static str),
+    /// The active state was replaced.
+    Switched {
+        /// Name of the stopped state.
+        from: &'static str,
+        /// Name of the started state.
+        to: &'static str,
+    },
+    /// A state was paused because another state was pushed on top of it.
+    Paused(&'static str),
+    /// A state was resumed because the state on top of it was popped.
+    Resumed(&'static str),
+}
+
+/// Resource mirroring the state stack of the `StateMachine`, so systems and states can inspect
+/// it.
+///
+/// States are identified by [`State::name`](trait.State.html#method.name). The resource is
+/// updated after every transition.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct StateStack {
+    names: Vec<&'static str>,
+}
+
+impl StateStack {
+    /// Returns the names of the states on the stack, from the bottom to the active state.
+    pub fn names(&self) -> &[&'static str] {
+        &self.names
+    }
+
+    /// Returns the number of states on the stack.
+    pub fn depth(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Returns the name of the active state.
+    pub fn active(&self) -> Option<&'static str> {
+        self.names.last().cloned()
+    }
+
+    /// Checks whether a state with the given name is on the stack.
+    pub fn contains(&self, name: &str) -> bool {
+        self.names.iter().any(|n| *n == name)
+    }
+
+    /// Checks whether a state of type `S` is on the stack, see `Trans::pop_to` for the caveats.
+    #[cfg(type_name)]
+    pub fn contains_type<S: ?Sized>(&self) -> bool {
+        self.contains(std::any::type_name::<S>())
+    }
+}
+
+/// Run criteria for systems that run only while a state of type `S` is the active state.
+///
+/// The state is found by its [`State::name`](trait.State.html#method.name), see
+/// `Trans::pop_to` for the caveats. Requires Rust 1.38 or later.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// GameDataBuilder::default()
+///     .with_run_if(EnemyAiSystem, "enemy_ai", &[], InState::<Gameplay>::new())
+/// ```
+#[cfg(type_name)]
+pub struct InState<S> {
+    marker: PhantomData<fn() -> S>,
+}
+
+#[cfg(type_name)]
+impl<S> InState<S> {
+    /// Creates the criteria.
+    pub fn new() -> Self {
+        InState {
+            marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(type_name)]
+impl<S> Default for InState<S> {
+    fn default() -> Self {
+        InState::new()
+    }
+}
+
+#[cfg(type_name)]
+impl<'s, S> RunCriteria<'s> for InState<S> {
+    type SystemData = Read<'s, StateStack>;
+
+    fn should_run(&mut self, stack: Self::SystemData) -> bool {
+        stack.active() == Some(std::any::type_name::<S>())
+    }
+}
+
+/// Sends a transition event, if the world contains a channel for them.
+fn send_event(world: &World, event: StateTransitionEvent) {
+    if let Some(mut channel) = world
+        .res
+        .try_fetch_mut::<EventChannel<StateTransitionEvent>>()
+    {
+        channel.single_write(event);
+    }
+}
+
 /// A simple stack-based state machine (pushdown automaton).
 #[derive(Derivative)]
 #[derivative(Debug)]
@@ -344,6 +670,8 @@ pub struct StateMachine<'a, T, E> {
     running: bool,
     #[derivative(Debug = "ignore")]
     state_stack: Vec<Box<dyn State<T, E> + 'a>>,
+    #[derivative(Debug = "ignore")]
+    pending: Option<PendingTrans<T, E>>,
 }
 
 impl<'a, T, E: Send + Sync + 'static> StateMachine<'a, T, E> {
@@ -352,6 +680,7 @@ impl<'a, T, E: Send + Sync + 'static> StateMachine<'a, T, E> {
         StateMachine {
             running: false,
             state_stack: vec![Box::new(initial_state)],
+            pending: None,
         }
     }
 
@@ -360,6 +689,11 @@ impl<'a, T, E: Send + Sync + 'static> StateMachine<'a, T, E> {
         self.running
     }
 
+    /// Returns the names of the states on the stack, from the bottom to the active state.
+    pub fn state_names<'s>(&'s self) -> impl Iterator<Item = &'static str> + 's {
+        self.state_stack.iter().map(|state| state.name())
+    }
+
     /// Initializes the state machine.
     pub fn start(&mut self, data: StateData<'_, T>) -> Result<(), StateError> {
         if !self.running {
@@ -367,12 +701,24 @@ impl<'a, T, E: Send + Sync + 'static> StateMachine<'a, T, E> {
                 .state_stack
                 .last_mut()
                 .ok_or(StateError::NoStatesPresent)?;
-            state.on_start(data);
+            let name = state.name();
+            let StateData { world, data } = data;
+            state.on_start(StateData { world, data });
+            send_event(world, StateTransitionEvent::Pushed(name));
             self.running = true;
+            self.sync_stack(world);
         }
         Ok(())
     }
 
+    /// Updates the `StateStack` resource, if the world contains one.
+    fn sync_stack(&self, world: &World) {
+        if let Some(mut stack) = world.res.try_fetch_mut::<StateStack>() {
+            stack.names.clear();
+            stack.names.extend(self.state_names());
+        }
+    }
+
     /// Passes a single event to the active state to handle.
     pub fn handle_event(&mut self, data: StateData<'_, T>, event: E) {
         let StateData { world, data } = data;
@@ -415,7 +761,33 @@ impl<'a, T, E: Send + Sync + 'static> StateMachine<'a, T, E> {
             }
 
             self.transition(trans, StateData { world, data });
+            self.poll_pending(StateData { world, data });
+        }
+    }
+
+    /// Checks whether the task of a pending `Trans::Async` finished, and performs the resulting
+    /// transition if it did.
+    fn poll_pending(&mut self, data: StateData<'_, T>) {
+        let StateData { world, data } = data;
+        let depth = match self.pending {
+            Some(ref pending) => pending.depth,
+            None => return,
+        };
+        if self.state_stack.len() <= depth {
+            // The transitional state is gone, so nobody is waiting for the result anymore.
+            self.pending = None;
+            return;
+        }
+
+        let trans = match self.pending.as_mut().and_then(|pending| (pending.poll)()) {
+            Some(trans) => trans,
+            None => return,
+        };
+        self.pending = None;
+        while self.running && self.state_stack.len() > depth {
+            self.transition(Trans::Pop, StateData { world, data });
         }
+        self.transition(trans, StateData { world, data });
     }
 
     /// Performs a state transition.
@@ -425,45 +797,103 @@ impl<'a, T, E: Send + Sync + 'static> StateMachine<'a, T, E> {
     /// sequentially in the order of insertion.
     pub fn transition(&mut self, request: Trans<T, E>, data: StateData<'_, T>) {
         if self.running {
+            let StateData { world, data } = data;
             match request {
-                Trans::None => (),
-                Trans::Pop => self.pop(data),
-                Trans::Push(state) => self.push(state, data),
-                Trans::Switch(state) => self.switch(state, data),
-                Trans::Quit => self.stop(data),
+                Trans::None => return,
+                Trans::Pop => self.pop(StateData { world, data }),
+                Trans::PopTo(name) => {
+                    match self
+                        .state_stack
+                        .iter()
+                        .rposition(|state| state.name() == name)
+                    {
+                        Some(index) => self.pop_to(index, StateData { world, data }),
+                        None => {
+                            warn!("Can't pop to state {}, it isn't on the stack", name);
+                            return;
+                        }
+                    }
+                }
+                Trans::PopAll => self.pop_to(0, StateData { world, data }),
+                Trans::Push(state) => self.push(state, None, StateData { world, data }),
+                Trans::Switch(state) => self.switch(state, None, StateData { world, data }),
+                Trans::PushWith(state, payload) => {
+                    self.push(state, Some(payload), StateData { world, data })
+                }
+                Trans::SwitchWith(state, payload) => {
+                    self.switch(state, Some(payload), StateData { world, data })
+                }
+                Trans::Quit => self.stop(StateData { world, data }),
+                Trans::Async(task) => {
+                    if self.pending.is_some() {
+                        warn!("Starting an asynchronous transition discards the unfinished one");
+                    }
+                    let depth = self.state_stack.len();
+                    self.push(task.state, None, StateData { world, data });
+                    self.pending = Some(PendingTrans {
+                        depth,
+                        poll: task.poll,
+                    });
+                }
             }
+            self.sync_stack(world);
         }
     }
 
     /// Removes the current state on the stack and inserts a different one.
-    fn switch(&mut self, state: Box<dyn State<T, E>>, data: StateData<'_, T>) {
+    fn switch(
+        &mut self,
+        state: Box<dyn State<T, E>>,
+        payload: Option<Payload>,
+        data: StateData<'_, T>,
+    ) {
         if self.running {
             let StateData { world, data } = data;
+            let mut from = None;
             if let Some(mut state) = self.state_stack.pop() {
                 state.on_stop(StateData { world, data });
+                from = Some(state.name());
             }
 
             self.state_stack.push(state);
 
             //State was just pushed, thus pop will always succeed
             let state = self.state_stack.last_mut().unwrap();
+            if let Some(payload) = payload {
+                state.receive(StateData { world, data }, payload);
+            }
             state.on_start(StateData { world, data });
+            let to = state.name();
+            match from {
+                Some(from) => send_event(world, StateTransitionEvent::Switched { from, to }),
+                None => send_event(world, StateTransitionEvent::Pushed(to)),
+            }
         }
     }
 
     /// Pauses the active state and pushes a new state onto the state stack.
-    fn push(&mut self, state: Box<dyn State<T, E>>, data: StateData<'_, T>) {
+    fn push(
+        &mut self,
+        state: Box<dyn State<T, E>>,
+        payload: Option<Payload>,
+        data: StateData<'_, T>,
+    ) {
         if self.running {
             let StateData { world, data } = data;
             if let Some(state) = self.state_stack.last_mut() {
                 state.on_pause(StateData { world, data });
+                send_event(world, StateTransitionEvent::Paused(state.name()));
             }
 
             self.state_stack.push(state);
 
             //State was just pushed, thus pop will always succeed
             let state = self.state_stack.last_mut().unwrap();
+            if let Some(payload) = payload {
+                state.receive(StateData { world, data }, payload);
+            }
             state.on_start(StateData { world, data });
+            send_event(world, StateTransitionEvent::Pushed(state.name()));
         }
     }
 
@@ -474,22 +904,42 @@ impl<'a, T, E: Send + Sync + 'static> StateMachine<'a, T, E> {
             let StateData { world, data } = data;
             if let Some(mut state) = self.state_stack.pop() {
                 state.on_stop(StateData { world, data });
+                send_event(world, StateTransitionEvent::Popped(state.name()));
             }
 
             if let Some(state) = self.state_stack.last_mut() {
                 state.on_resume(StateData { world, data });
+                send_event(world, StateTransitionEvent::Resumed(state.name()));
             } else {
                 self.running = false;
             }
         }
     }
 
+    /// Stops and removes all states above `index` and resumes the state at `index`.
+    fn pop_to(&mut self, index: usize, data: StateData<'_, T>) {
+        if self.running && index + 1 < self.state_stack.len() {
+            let StateData { world, data } = data;
+            while self.state_stack.len() > index + 1 {
+                // The loop condition guarantees that there is a state to pop.
+                let mut state = self.state_stack.pop().unwrap();
+                state.on_stop(StateData { world, data });
+                send_event(world, StateTransitionEvent::Popped(state.name()));
+            }
+
+            let state = self.state_stack.last_mut().unwrap();
+            state.on_resume(StateData { world, data });
+            send_event(world, StateTransitionEvent::Resumed(state.name()));
+        }
+    }
+
     /// Shuts the state machine down.
     pub(crate) fn stop(&mut self, data: StateData<'_, T>) {
         if self.running {
             let StateData { world, data } = data;
             while let Some(mut state) = self.state_stack.pop() {
                 state.on_stop(StateData { world, data });
+                send_event(world, StateTransitionEvent::Popped(state.name()));
             }
 
             self.running = false;
@@ -539,4 +989,228 @@ mod tests {
         sm.update(StateData::new(&mut world, &mut ()));
         assert!(!sm.is_running());
     }
+
+    #[cfg(type_name)]
+    #[test]
+    fn async_transition_switches_when_task_finished() {
+        use crate::ecs::prelude::World;
+        use rayon::ThreadPoolBuilder;
+        use std::{thread, time::Duration};
+
+        struct Waiting;
+        impl State<(), ()> for Waiting {}
+
+        struct Start(Option<ThreadPool>);
+        impl State<(), ()> for Start {
+            fn update(&mut self, _: StateData<'_, ()>) -> Trans<(), ()> {
+                match self.0.take() {
+                    Some(pool) => Trans::Async(AsyncTrans::new(
+                        &pool,
+                        Waiting,
+                        || 7,
+                        |n| {
+                            assert_eq!(7, n);
+                            Trans::Switch(Box::new(State2))
+                        },
+                    )),
+                    None => Trans::None,
+                }
+            }
+        }
+
+        let mut world = World::new();
+        let pool = ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+        let mut sm = StateMachine::new(Start(Some(pool)));
+        sm.start(StateData::new(&mut world, &mut ())).unwrap();
+
+        let state2 = std::any::type_name::<State2>();
+        for _ in 0..1000 {
+            sm.update(StateData::new(&mut world, &mut ()));
+            if sm.state_names().any(|name| name == state2) {
+                break;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(vec![state2], sm.state_names().collect::<Vec<_>>());
+    }
+
+    #[cfg(type_name)]
+    #[test]
+    fn async_transition_pops_when_task_panicked() {
+        use crate::ecs::prelude::World;
+        use rayon::ThreadPoolBuilder;
+        use std::{thread, time::Duration};
+
+        struct Waiting;
+        impl State<(), ()> for Waiting {}
+
+        struct Start(Option<ThreadPool>);
+        impl State<(), ()> for Start {
+            fn update(&mut self, _: StateData<'_, ()>) -> Trans<(), ()> {
+                match self.0.take() {
+                    Some(pool) => Trans::Async(AsyncTrans::new(
+                        &pool,
+                        Waiting,
+                        || -> u32 { panic!("task failed") },
+                        |_| Trans::Switch(Box::new(State2)),
+                    )),
+                    None => Trans::None,
+                }
+            }
+        }
+
+        let mut world = World::new();
+        let pool = ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+        let mut sm = StateMachine::new(Start(Some(pool)));
+        sm.start(StateData::new(&mut world, &mut ())).unwrap();
+
+        let waiting = std::any::type_name::<Waiting>();
+        sm.update(StateData::new(&mut world, &mut ()));
+        for _ in 0..1000 {
+            sm.update(StateData::new(&mut world, &mut ()));
+            if !sm.state_names().any(|name| name == waiting) {
+                break;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(
+            vec![std::any::type_name::<Start>()],
+            sm.state_names().collect::<Vec<_>>()
+        );
+    }
+
+    #[cfg(type_name)]
+    #[test]
+    fn payload_is_received_before_start() {
+        use crate::ecs::prelude::World;
+
+        struct Sender;
+        impl State<(), ()> for Sender {
+            fn update(&mut self, _: StateData<'_, ()>) -> Trans<(), ()> {
+                Trans::SwitchWith(Box::new(Receiver(None)), Payload::new(5u32))
+            }
+        }
+
+        struct Receiver(Option<u32>);
+        impl State<(), ()> for Receiver {
+            fn receive(&mut self, _: StateData<'_, ()>, payload: Payload) {
+                self.0 = payload.downcast().ok();
+            }
+
+            fn on_start(&mut self, _: StateData<'_, ()>) {
+                assert_eq!(Some(5), self.0);
+            }
+
+            fn update(&mut self, _: StateData<'_, ()>) -> Trans<(), ()> {
+                Trans::Quit
+            }
+        }
+
+        let mut world = World::new();
+        let mut sm = StateMachine::new(Sender);
+        sm.start(StateData::new(&mut world, &mut ())).unwrap();
+        sm.update(StateData::new(&mut world, &mut ()));
+        assert_eq!(
+            vec![std::any::type_name::<Receiver>()],
+            sm.state_names().collect::<Vec<_>>()
+        );
+    }
+
+    #[cfg(type_name)]
+    #[test]
+    fn pop_to_and_pop_all() {
+        use crate::ecs::prelude::World;
+
+        struct Menu;
+        impl State<(), ()> for Menu {}
+        struct Settings;
+        impl State<(), ()> for Settings {}
+        struct Confirm;
+        impl State<(), ()> for Confirm {}
+
+        let mut world = World::new();
+        world.add_resource(StateStack::default());
+        let mut sm = StateMachine::new(State2);
+        sm.start(StateData::new(&mut world, &mut ())).unwrap();
+        for trans in vec![
+            Trans::Push(Box::new(Menu)),
+            Trans::Push(Box::new(Settings)),
+            Trans::Push(Box::new(Confirm)),
+        ] {
+            sm.transition(trans, StateData::new(&mut world, &mut ()));
+        }
+        assert_eq!(4, world.read_resource::<StateStack>().depth());
+
+        sm.transition(Trans::pop_to::<Menu>(), StateData::new(&mut world, &mut ()));
+        {
+            let stack = world.read_resource::<StateStack>();
+            assert_eq!(Some(std::any::type_name::<Menu>()), stack.active());
+            assert!(!stack.contains_type::<Settings>());
+        }
+
+        sm.transition(
+            Trans::pop_to::<Confirm>(),
+            StateData::new(&mut world, &mut ()),
+        );
+        assert_eq!(2, world.read_resource::<StateStack>().depth());
+
+        sm.transition(Trans::PopAll, StateData::new(&mut world, &mut ()));
+        assert!(sm.is_running());
+        assert_eq!(
+            &[std::any::type_name::<State2>()],
+            world.read_resource::<StateStack>().names()
+        );
+    }
+
+    #[cfg(type_name)]
+    #[test]
+    fn in_state_criteria() {
+        use crate::ecs::prelude::World;
+
+        let mut world = World::new();
+        world.add_resource(StateStack::default());
+        let mut sm = StateMachine::new(State1(1));
+        sm.start(StateData::new(&mut world, &mut ())).unwrap();
+
+        let mut in_state1 = InState::<State1>::new();
+        let mut in_state2 = InState::<State2>::new();
+        assert!(world.exec(|stack: Read<'_, StateStack>| in_state1.should_run(stack)));
+        assert!(!world.exec(|stack: Read<'_, StateStack>| in_state2.should_run(stack)));
+    }
+
+    #[cfg(type_name)]
+    #[test]
+    fn transition_events_are_sent() {
+        use crate::ecs::prelude::World;
+
+        let mut world = World::new();
+        world.add_resource(EventChannel::<StateTransitionEvent>::new());
+        let mut reader = world
+            .write_resource::<EventChannel<StateTransitionEvent>>()
+            .register_reader();
+
+        let mut sm = StateMachine::new(State1(0));
+        sm.start(StateData::new(&mut world, &mut ())).unwrap();
+        sm.update(StateData::new(&mut world, &mut ()));
+        sm.update(StateData::new(&mut world, &mut ()));
+
+        let state1 = std::any::type_name::<State1>();
+        let state2 = std::any::type_name::<State2>();
+        let events = world
+            .read_resource::<EventChannel<StateTransitionEvent>>()
+            .read(&mut reader)
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                StateTransitionEvent::Pushed(state1),
+                StateTransitionEvent::Switched {
+                    from: state1,
+                    to: state2,
+                },
+                StateTransitionEvent::Popped(state2),
+            ],
+            events
+        );
+    }
 }
@@ -1,12 +1,14 @@
+#[cfg(feature = "amethyst_ui")]
+use crate::ui::UiEvent;
 use crate::{
     core::{
         shrev::{EventChannel, ReaderId},
         specs::{Read, Resources, SystemData},
         EventReader,
     },
-    renderer::Event,
-    ui::UiEvent,
+    lifecycle::LifecycleEvent,
 };
+use winit::Event;
 
 /// The enum holding the different types of event that can be received in a `State` in the handle_event method.
 #[derive(Clone, EventReader)]
@@ -15,5 +17,8 @@ pub enum StateEvent {
     /// Events sent by the winit window.
     Window(Event),
     /// Events sent by the ui system.
+    #[cfg(feature = "amethyst_ui")]
     Ui(UiEvent),
+    /// Focus, minimize and suspend changes of the application.
+    Lifecycle(LifecycleEvent),
 }
@@ -0,0 +1,478 @@
+//! Integration with Steam, through the Steamworks SDK.
+//!
+//! The [`SteamBundle`](struct.SteamBundle.html) connects to the running Steam client when the
+//! game starts and disconnects once the world is dropped. Achievements and stats are changed by
+//! writing [`SteamEvent`](enum.SteamEvent.html)s, and the rich presence shown to friends is kept
+//! in sync with the [`RichPresence`](struct.RichPresence.html) resource. The actions of a Steam
+//! Input action set can be mapped onto the actions of the engine with
+//! [`SteamInputBindings`](struct.SteamInputBindings.html). Anything else the SDK offers is
+//! available through the `Client` of the [`Steam`](struct.Steam.html) resource.
+//!
+//! The module is only available with the `steam` feature. The game has to be started by Steam,
+//! or have a `steam_appid.txt` file with its app id next to the executable during development.
+
+use std::{collections::HashMap, hash::Hash};
+
+use steamworks::{Client, SingleClient};
+
+use crate::{
+    core::{
+        bundle::{Error, Result},
+        shrev::{EventChannel, ReaderId},
+        SystemBundle,
+    },
+    ecs::prelude::{
+        DispatcherBuilder, Read, Resources, RunNow, System, SystemData, Write, WriteExpect,
+    },
+    input::InputEvent,
+};
+
+/// Resource with the connection to the Steam client, added by the `SteamBundle`.
+pub struct Steam {
+    client: Client,
+}
+
+impl Steam {
+    /// Returns the client, to use the parts of the SDK the engine doesn't wrap.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+}
+
+/// Change to the achievements and stats of the player, sent on an `EventChannel<SteamEvent>`.
+///
+/// The changes are stored on the Steam servers once per frame.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SteamEvent {
+    /// Unlocks the achievement with the given API name.
+    UnlockAchievement(String),
+    /// Locks the achievement with the given API name again, e.g. for testing.
+    ClearAchievement(String),
+    /// Sets the integer stat with the given API name.
+    SetStat(String, i32),
+    /// Sets the float stat with the given API name.
+    SetStatFloat(String, f32),
+}
+
+/// Resource with the rich presence of the player, the key and value pairs shown to their friends
+/// in the Steam client.
+///
+/// Which keys are shown, and how, is set up on the Steamworks website, e.g. `steam_display` for
+/// the localized text.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RichPresence {
+    values: HashMap<String, String>,
+}
+
+impl RichPresence {
+    /// Sets the value of a key.
+    pub fn set<K, V>(&mut self, key: K, value: V)
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.values.insert(key.into(), value.into());
+    }
+
+    /// Removes a key.
+    pub fn remove(&mut self, key: &str) {
+        self.values.remove(key);
+    }
+
+    /// Removes all keys.
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+
+    /// Gets the value of a key.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+}
+
+/// Mapping of the digital actions of a Steam Input action set onto the actions of the engine.
+///
+/// The action sets and actions are set up on the Steamworks website, or in the in-game actions
+/// file during development, and are referred to by their names.
+#[derive(Clone, Debug)]
+pub struct SteamInputBindings<AC> {
+    action_set: String,
+    actions: Vec<(String, AC)>,
+}
+
+impl<AC> SteamInputBindings<AC> {
+    /// Creates bindings activating the given action set first.
+    pub fn new<S>(action_set: S) -> Self
+    where
+        S: Into<String>,
+    {
+        SteamInputBindings {
+            action_set: action_set.into(),
+            actions: Vec::new(),
+        }
+    }
+
+    /// Maps the Steam Input action with the given name onto the action of the engine.
+    pub fn with_action<S>(mut self, steam_action: S, action: AC) -> Self
+    where
+        S: Into<String>,
+    {
+        self.actions.push((steam_action.into(), action));
+        self
+    }
+}
+
+/// Resource with the state of the Steam Input actions, added by the `SteamBundle` when it has
+/// `SteamInputBindings`.
+///
+/// The mapped actions are sent as `InputEvent::ActionPressed` and `InputEvent::ActionReleased` on
+/// the `EventChannel<InputEvent<AC>>`, like the actions of the `InputHandler`, but aren't
+/// reported by `InputHandler::action_is_down`.
+#[derive(Clone, Debug)]
+pub struct SteamInput<AC> {
+    action_set: String,
+    down: Vec<AC>,
+}
+
+impl<AC> SteamInput<AC>
+where
+    AC: PartialEq + Clone,
+{
+    /// Returns the name of the action set active on the controllers.
+    pub fn action_set(&self) -> &str {
+        &self.action_set
+    }
+
+    /// Activates another action set on the controllers, e.g. the menu controls when pausing.
+    pub fn set_action_set<S>(&mut self, action_set: S)
+    where
+        S: Into<String>,
+    {
+        self.action_set = action_set.into();
+    }
+
+    /// Returns whether the action is held on any controller.
+    pub fn action_is_down(&self, action: &AC) -> bool {
+        self.down.contains(action)
+    }
+
+    /// Replaces the actions held down, sending the events of those that changed.
+    fn update(&mut self, down: Vec<AC>, events: &mut EventChannel<InputEvent<AC>>) {
+        for action in self.down.iter().filter(|action| !down.contains(action)) {
+            events.single_write(InputEvent::ActionReleased(action.clone()));
+        }
+        for action in down.iter().filter(|action| !self.down.contains(action)) {
+            events.single_write(InputEvent::ActionPressed(action.clone()));
+        }
+        self.down = down;
+    }
+}
+
+/// Connects to Steam and adds the systems keeping it up to date.
+///
+/// The connection is made when the bundle is created, so a game can still run without Steam
+/// when it fails.
+///
+/// # Type parameters
+///
+/// * `AC`: The type of the actions of the `InputHandler` the Steam Input actions are mapped onto.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let steam = SteamBundle::new()?.with_input(
+///     SteamInputBindings::new("InGameControls").with_action("jump", "jump".to_owned()),
+/// );
+/// let game_data = GameDataBuilder::default().with_bundle(steam)?;
+///
+/// // Later, in a system or state:
+/// world
+///     .write_resource::<EventChannel<SteamEvent>>()
+///     .single_write(SteamEvent::UnlockAchievement("ACH_WIN_ONE_GAME".to_owned()));
+/// world.write_resource::<RichPresence>().set("status", "In the main menu");
+/// ```
+pub struct SteamBundle<AC = String> {
+    client: Client,
+    single: SingleClient,
+    input: Option<SteamInputBindings<AC>>,
+}
+
+impl SteamBundle {
+    /// Connects to the running Steam client.
+    pub fn new() -> Result<Self> {
+        let (client, single) = Client::init().map_err(|e| {
+            Error::from(format!(
+                "Failed to connect to Steam, is the client running? {:?}",
+                e
+            ))
+        })?;
+        Ok(SteamBundle {
+            client,
+            single,
+            input: None,
+        })
+    }
+}
+
+impl<AC> SteamBundle<AC> {
+    /// Maps the actions of a Steam Input action set onto the actions of the engine.
+    pub fn with_input<N>(self, bindings: SteamInputBindings<N>) -> SteamBundle<N> {
+        SteamBundle {
+            client: self.client,
+            single: self.single,
+            input: Some(bindings),
+        }
+    }
+}
+
+impl<'a, 'b, AC> SystemBundle<'a, 'b> for SteamBundle<AC>
+where
+    AC: Hash + Eq + Clone + Send + Sync + 'static,
+{
+    fn build(self, builder: &mut DispatcherBuilder<'a, 'b>) -> Result<()> {
+        self.client.user_stats().request_current_stats();
+        if let Some(bindings) = self.input {
+            builder.add(
+                SteamInputSystem {
+                    client: self.client.clone(),
+                    action_set: bindings.action_set,
+                    action_sets: HashMap::new(),
+                    actions: bindings
+                        .actions
+                        .into_iter()
+                        .map(|(name, action)| (name, 0, action))
+                        .collect(),
+                },
+                "steam_input",
+                &[],
+            );
+        }
+        builder.add(
+            SteamSystem {
+                client: self.client,
+                reader: None,
+                presence: HashMap::new(),
+            },
+            "steam_system",
+            &[],
+        );
+        // Steam callbacks have to be run from the thread that connected.
+        builder.add_thread_local(SteamCallbackSystem {
+            single: self.single,
+        });
+        Ok(())
+    }
+}
+
+/// Applies the `SteamEvent`s and the `RichPresence`.
+struct SteamSystem {
+    client: Client,
+    reader: Option<ReaderId<SteamEvent>>,
+    presence: HashMap<String, String>,
+}
+
+impl<'a> System<'a> for SteamSystem {
+    type SystemData = (Read<'a, EventChannel<SteamEvent>>, Read<'a, RichPresence>);
+
+    fn run(&mut self, (events, presence): Self::SystemData) {
+        let client = &self.client;
+        let stats = client.user_stats();
+        let mut changed = false;
+        for event in events.read(self.reader.as_mut().unwrap()) {
+            let (name, result) = match *event {
+                SteamEvent::UnlockAchievement(ref name) => (name, stats.achievement(name).set()),
+                SteamEvent::ClearAchievement(ref name) => (name, stats.achievement(name).clear()),
+                SteamEvent::SetStat(ref name, value) => (name, stats.set_stat_i32(name, value)),
+                SteamEvent::SetStatFloat(ref name, value) => {
+                    (name, stats.set_stat_f32(name, value))
+                }
+            };
+            match result {
+                Ok(()) => changed = true,
+                Err(()) => warn!(
+                    "Failed to apply {:?}, is {:?} set up on Steamworks?",
+                    event, name
+                ),
+            }
+        }
+        if changed && stats.store_stats().is_err() {
+            warn!("Failed to store the Steam stats");
+        }
+
+        if presence.values != self.presence {
+            let friends = client.friends();
+            for (key, value) in presence_changes(&self.presence, &presence.values) {
+                if !friends.set_rich_presence(key, value) && value.is_some() {
+                    warn!(
+                        "Failed to set the Steam rich presence {:?} to {:?}",
+                        key, value
+                    );
+                }
+            }
+            self.presence = presence.values.clone();
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+        res.insert(Steam {
+            client: self.client.clone(),
+        });
+        self.reader = Some(
+            res.fetch_mut::<EventChannel<SteamEvent>>()
+                .register_reader(),
+        );
+    }
+}
+
+/// Returns the keys of the rich presence to set, with their new values, or `None` for the keys
+/// to remove.
+fn presence_changes<'a>(
+    sent: &'a HashMap<String, String>,
+    values: &'a HashMap<String, String>,
+) -> Vec<(&'a str, Option<&'a str>)> {
+    let removed = sent
+        .keys()
+        .filter(|key| !values.contains_key(*key))
+        .map(|key| (key.as_str(), None));
+    let changed = values
+        .iter()
+        .filter(|&(key, value)| sent.get(key) != Some(value))
+        .map(|(key, value)| (key.as_str(), Some(value.as_str())));
+    removed.chain(changed).collect()
+}
+
+/// Activates the action set of the `SteamInput` on the connected controllers, and maps their
+/// actions onto the actions of the engine.
+struct SteamInputSystem<AC> {
+    client: Client,
+    /// The action set the resource starts with.
+    action_set: String,
+    action_sets: HashMap<String, u64>,
+    /// The Steam Input actions with their handles, `0` until they are known.
+    actions: Vec<(String, u64, AC)>,
+}
+
+impl<'a, AC> System<'a> for SteamInputSystem<AC>
+where
+    AC: Hash + Eq + Clone + Send + Sync + 'static,
+{
+    type SystemData = (
+        WriteExpect<'a, SteamInput<AC>>,
+        Write<'a, EventChannel<InputEvent<AC>>>,
+    );
+
+    fn run(&mut self, (mut steam_input, mut events): Self::SystemData) {
+        let input = self.client.input();
+        // Handles stay `0` until Steam has loaded the configuration of the game.
+        let action_set = match self.action_sets.get(steam_input.action_set()) {
+            Some(&handle) => handle,
+            None => {
+                let handle = input.get_action_set_handle(steam_input.action_set());
+                if handle != 0 {
+                    self.action_sets
+                        .insert(steam_input.action_set().to_owned(), handle);
+                }
+                handle
+            }
+        };
+        for &mut (ref name, ref mut handle, _) in &mut self.actions {
+            if *handle == 0 {
+                *handle = input.get_digital_action_handle(name);
+            }
+        }
+
+        let mut down = Vec::new();
+        if action_set != 0 {
+            for controller in input.get_connected_controllers() {
+                input.activate_action_set_handle(controller, action_set);
+                for &(_, handle, ref action) in &self.actions {
+                    if handle != 0
+                        && input.get_digital_action_data(controller, handle).bState
+                        && !down.contains(action)
+                    {
+                        down.push(action.clone());
+                    }
+                }
+            }
+        }
+        steam_input.update(down, &mut events);
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        // Steam runs the input frame with the callbacks.
+        if !self.client.input().init(false) {
+            warn!("Failed to initialize Steam Input");
+        }
+        res.insert(SteamInput::<AC> {
+            action_set: self.action_set.clone(),
+            down: Vec::new(),
+        });
+        Self::SystemData::setup(res);
+    }
+}
+
+impl<AC> Drop for SteamInputSystem<AC> {
+    fn drop(&mut self) {
+        self.client.input().shutdown();
+    }
+}
+
+/// Runs the callbacks of the Steam client, on the main thread.
+struct SteamCallbackSystem {
+    single: SingleClient,
+}
+
+impl<'a> RunNow<'a> for SteamCallbackSystem {
+    fn run_now(&mut self, _: &'a Resources) {
+        self.single.run_callbacks();
+    }
+
+    fn setup(&mut self, _: &mut Resources) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steam_input_sends_changed_actions() {
+        let mut events = EventChannel::<InputEvent<&str>>::new();
+        let mut reader = events.register_reader();
+        let mut input = SteamInput {
+            action_set: "InGameControls".to_owned(),
+            down: Vec::new(),
+        };
+
+        input.update(vec!["jump", "fire"], &mut events);
+        input.update(vec!["fire"], &mut events);
+        assert!(input.action_is_down(&"fire"));
+        assert!(!input.action_is_down(&"jump"));
+        let events = events
+            .read(&mut reader)
+            .map(|event| match *event {
+                InputEvent::ActionPressed(action) => (true, action),
+                InputEvent::ActionReleased(action) => (false, action),
+                ref event => panic!("Unexpected event {:?}", event),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![(true, "jump"), (true, "fire"), (false, "jump")],
+            events
+        );
+    }
+
+    #[test]
+    fn presence_changes_set_and_remove_keys() {
+        let mut sent = HashMap::new();
+        sent.insert("status".to_owned(), "In the menu".to_owned());
+        sent.insert("score".to_owned(), "3".to_owned());
+        let mut presence = RichPresence::default();
+        presence.set("status", "In the menu");
+        presence.set("level", "2");
+
+        let mut changes = presence_changes(&sent, &presence.values);
+        changes.sort();
+        assert_eq!(vec![("level", Some("2")), ("score", None)], changes);
+    }
+}
@@ -0,0 +1,78 @@
+//! Opt-in engine event hooks for analytics and telemetry.
+
+use std::{panic::PanicInfo, sync::Arc, time::Duration};
+
+use crate::{assets::AssetLoadFailure, state::StateTransitionEvent};
+
+/// Receives engine events, for example to forward them to an analytics backend.
+///
+/// Add an implementation to the application with
+/// [`ApplicationBuilder::with_telemetry`](struct.ApplicationBuilder.html#method.with_telemetry).
+/// Every hook has an empty default implementation, so only the events of interest need to be
+/// handled.
+///
+/// Hooks are called on the main thread between frames, except for `on_crash`, which is called
+/// from the panic hook on the panicking thread.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+///
+/// use amethyst::{prelude::*, StateTransitionEvent, Telemetry};
+///
+/// struct LogTelemetry;
+///
+/// impl Telemetry for LogTelemetry {
+///     fn on_state_transition(&self, transition: &StateTransitionEvent) {
+///         println!("{:?}", transition);
+///     }
+///
+///     fn on_frame_spike(&self, frame_number: u64, frame_time: Duration) {
+///         println!("Frame {} took {:?}", frame_number, frame_time);
+///     }
+/// }
+///
+/// struct NullState;
+/// impl EmptyState for NullState {}
+///
+/// let mut game = Application::build("assets/", NullState)
+///     .expect("Failed to initialize")
+///     .with_telemetry(LogTelemetry, Duration::from_millis(50))
+///     .build(())
+///     .expect("Failed to build game");
+/// game.run();
+/// ```
+pub trait Telemetry: Send + Sync + 'static {
+    /// Called once per frame for every change of the state stack sent on the
+    /// `EventChannel<StateTransitionEvent>` during the frame.
+    fn on_state_transition(&self, _transition: &StateTransitionEvent) {}
+
+    /// Called for every asset that failed to load.
+    fn on_asset_load_failure(&self, _failure: &AssetLoadFailure) {}
+
+    /// Called when a frame took longer than the configured threshold.
+    fn on_frame_spike(&self, _frame_number: u64, _frame_time: Duration) {}
+
+    /// Called when the game panics, before the previously installed panic hook runs.
+    fn on_crash(&self, _info: &PanicInfo<'_>) {}
+}
+
+/// A `Telemetry` implementation together with its configuration.
+#[derive(Clone)]
+pub(crate) struct TelemetryHooks {
+    pub(crate) telemetry: Arc<dyn Telemetry>,
+    pub(crate) frame_spike_threshold: Duration,
+}
+
+impl TelemetryHooks {
+    /// Installs a panic hook that reports crashes to the telemetry.
+    pub(crate) fn install_panic_hook(&self) {
+        let telemetry = self.telemetry.clone();
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            telemetry.on_crash(info);
+            previous(info);
+        }));
+    }
+}
@@ -0,0 +1,646 @@
+//! Timelines sequencing cutscenes, tutorials and scripted sequences.
+//!
+//! A [`Timeline`](struct.Timeline.html) is an asset, usually loaded from a RON file with
+//! `RonFormat`, with tracks of keys over time: animation clips started on entities, cuts between
+//! cameras, sounds with their captions, events sent to the game and entities shown or hidden.
+//! The entities are found by their `Named` name. A
+//! [`TimelinePlayer`](struct.TimelinePlayer.html) plays it, and can be paused and moved to
+//! any time of the timeline, which puts the entities, cameras and clips in their state at that
+//! time. [`TimelineState`](struct.TimelineState.html) plays a timeline as a state of its own.
+//!
+//! ```ron
+//! (
+//!     clips: [(time: 0.0, target: "hero", animation: "walk")],
+//!     cuts: [(time: 0.0, camera: "wide"), (time: 4.0, camera: "close_up")],
+//!     sounds: [
+//!         (
+//!             time: 4.5,
+//!             sound: "greeting",
+//!             caption: Some((text: "hello-there", speaker: Some("Hero"), duration: 2.0)),
+//!         ),
+//!     ],
+//!     events: [(time: 6.0, name: "open_gate")],
+//!     activations: [(time: 6.0, target: "gate", active: false)],
+//! )
+//! ```
+
+use std::collections::HashMap;
+
+use winit::VirtualKeyCode;
+
+use crate::{
+    animation::{
+        get_animation_set, AnimationCommand, AnimationControlSet, AnimationSet, EndControl,
+    },
+    assets::{Asset, AssetStorage, Handle, ProcessingState, Processor, Result as AssetResult},
+    audio::{output::Output, Caption, Captions, Source, SourceHandle},
+    core::{
+        bundle::{Result, SystemBundle},
+        shrev::EventChannel,
+        timing::Time,
+        Named, Transform,
+    },
+    ecs::prelude::{
+        Builder, Component, DenseVecStorage, DispatcherBuilder, Entities, Entity, Join, Read,
+        ReadStorage, System, VecStorage, Write, WriteStorage,
+    },
+    input::{is_close_requested, is_key_down},
+    renderer::{ActiveCamera, HiddenPropagate},
+    state::{SimpleState, SimpleTrans, StateData, Trans},
+    GameData, StateEvent,
+};
+
+fn one() -> f32 {
+    1.
+}
+
+/// Starts an animation of the `AnimationSet<String, Transform>` of an entity.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ClipKey {
+    /// Time of the key in seconds from the start of the timeline.
+    pub time: f32,
+    /// Name of the animated entity.
+    pub target: String,
+    /// Id of the animation in the `AnimationSet` of the entity.
+    pub animation: String,
+    /// Playback speed of the animation, 1 being normal speed.
+    #[serde(default = "one")]
+    pub speed: f32,
+}
+
+/// Switches the `ActiveCamera` to a camera entity.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct CutKey {
+    /// Time of the key in seconds from the start of the timeline.
+    pub time: f32,
+    /// Name of the camera entity.
+    pub camera: String,
+}
+
+/// Plays a sound of the `TimelinePlayer`, showing its caption.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct SoundKey {
+    /// Time of the key in seconds from the start of the timeline.
+    pub time: f32,
+    /// Name of the sound in the `TimelinePlayer`.
+    pub sound: String,
+    /// Volume of the sound, from 0 to 1.
+    #[serde(default = "one")]
+    pub volume: f32,
+    /// Caption shown in the `Captions` while the sound plays.
+    #[serde(default)]
+    pub caption: Option<Caption>,
+}
+
+/// Sends a `TimelineEvent::Fired`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct EventKey {
+    /// Time of the key in seconds from the start of the timeline.
+    pub time: f32,
+    /// Name of the event.
+    pub name: String,
+}
+
+/// Shows or hides an entity and its children.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ActivationKey {
+    /// Time of the key in seconds from the start of the timeline.
+    pub time: f32,
+    /// Name of the entity.
+    pub target: String,
+    /// Whether the entity is shown from then on. Before its first key, it's in the opposite
+    /// state.
+    pub active: bool,
+}
+
+/// A timeline, see the [module documentation](index.html).
+///
+/// The keys of each track are ordered by time.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct Timeline {
+    /// Animation clips.
+    pub clips: Vec<ClipKey>,
+    /// Camera cuts.
+    pub cuts: Vec<CutKey>,
+    /// Sounds.
+    pub sounds: Vec<SoundKey>,
+    /// Events fired.
+    pub events: Vec<EventKey>,
+    /// Entities shown or hidden.
+    pub activations: Vec<ActivationKey>,
+    /// Length of the timeline in seconds, by default the time of its last key.
+    pub length: Option<f32>,
+}
+
+impl Timeline {
+    /// Returns the length of the timeline in seconds.
+    pub fn duration(&self) -> f32 {
+        self.length.unwrap_or_else(|| {
+            let clips = self.clips.iter().map(|k| k.time);
+            let cuts = self.cuts.iter().map(|k| k.time);
+            let sounds = self.sounds.iter().map(|k| k.time);
+            let events = self.events.iter().map(|k| k.time);
+            let activations = self.activations.iter().map(|k| k.time);
+            clips
+                .chain(cuts)
+                .chain(sounds)
+                .chain(events)
+                .chain(activations)
+                .fold(0., f32::max)
+        })
+    }
+
+    /// Returns whether each target of the activations is active at the time.
+    pub fn activations_at(&self, time: f32) -> HashMap<&str, bool> {
+        let mut active = HashMap::new();
+        for key in &self.activations {
+            let state = active
+                .entry(key.target.as_str())
+                .or_insert_with(|| !key.active);
+            if key.time <= time {
+                *state = key.active;
+            }
+        }
+        active
+    }
+
+    /// Returns the camera the timeline cut to last at the time.
+    pub fn camera_at(&self, time: f32) -> Option<&str> {
+        self.cuts
+            .iter()
+            .take_while(|cut| cut.time <= time)
+            .last()
+            .map(|cut| cut.camera.as_str())
+    }
+}
+
+impl Asset for Timeline {
+    const NAME: &'static str = "amethyst::Timeline";
+    type Data = Self;
+    type HandleStorage = VecStorage<Handle<Self>>;
+}
+
+impl Into<AssetResult<ProcessingState<Timeline>>> for Timeline {
+    fn into(self) -> AssetResult<ProcessingState<Timeline>> {
+        Ok(ProcessingState::Loaded(self))
+    }
+}
+
+/// Plays a `Timeline`, put on any entity.
+#[derive(Clone, Debug)]
+pub struct TimelinePlayer {
+    /// The timeline to play.
+    pub timeline: Handle<Timeline>,
+    /// The sounds of the sound keys, by name.
+    pub sounds: HashMap<String, SourceHandle>,
+    /// Playback speed, 1 being normal speed.
+    pub speed: f32,
+    time: f32,
+    playing: bool,
+    seek: Option<f32>,
+    started: bool,
+    finished: bool,
+    clips_paused: bool,
+    clips: Vec<(Entity, String)>,
+}
+
+impl TimelinePlayer {
+    /// Creates a player that starts playing the timeline from the beginning.
+    pub fn new(timeline: Handle<Timeline>) -> Self {
+        TimelinePlayer {
+            timeline,
+            sounds: HashMap::new(),
+            speed: 1.,
+            time: 0.,
+            playing: true,
+            seek: None,
+            started: false,
+            finished: false,
+            clips_paused: false,
+            clips: Vec::new(),
+        }
+    }
+
+    /// Adds a sound played by the sound keys with the name.
+    pub fn with_sound<S: Into<String>>(mut self, name: S, sound: SourceHandle) -> Self {
+        self.sounds.insert(name.into(), sound);
+        self
+    }
+
+    /// Returns the current time in the timeline in seconds.
+    pub fn time(&self) -> f32 {
+        self.seek.unwrap_or(self.time)
+    }
+
+    /// Returns whether the timeline is playing.
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Returns whether the timeline reached its end.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Pauses the timeline and its animation clips.
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    /// Resumes the timeline after `pause`.
+    pub fn resume(&mut self) {
+        self.playing = !self.finished;
+    }
+
+    /// Moves to the time in seconds, putting the entities, cameras and clips in their state at
+    /// that time. The events and sounds in between are skipped.
+    pub fn seek(&mut self, time: f32) {
+        self.seek = Some(time.max(0.));
+    }
+}
+
+impl Component for TimelinePlayer {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Event sent by the `TimelineSystem` on an `EventChannel<TimelineEvent>`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TimelineEvent {
+    /// An event key of the timeline played by the entity was reached.
+    Fired {
+        /// The entity with the `TimelinePlayer`.
+        entity: Entity,
+        /// Name of the event.
+        name: String,
+    },
+    /// The timeline played by the entity reached its end.
+    Finished {
+        /// The entity with the `TimelinePlayer`.
+        entity: Entity,
+    },
+}
+
+/// System playing the `TimelinePlayer`s.
+#[derive(Default)]
+pub struct TimelineSystem;
+
+impl<'a> System<'a> for TimelineSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, Time>,
+        Read<'a, AssetStorage<Timeline>>,
+        Read<'a, AssetStorage<Source>>,
+        Option<Read<'a, Output>>,
+        WriteStorage<'a, TimelinePlayer>,
+        ReadStorage<'a, Named>,
+        ReadStorage<'a, AnimationSet<String, Transform>>,
+        WriteStorage<'a, AnimationControlSet<String, Transform>>,
+        WriteStorage<'a, HiddenPropagate>,
+        Write<'a, ActiveCamera>,
+        Write<'a, Captions>,
+        Write<'a, EventChannel<TimelineEvent>>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            time,
+            timelines,
+            sources,
+            output,
+            mut players,
+            names,
+            animation_sets,
+            mut controls,
+            mut hidden,
+            mut active_camera,
+            mut captions,
+            mut events,
+        ) = data;
+
+        let find = |name: &str| {
+            let found = (&*entities, &names)
+                .join()
+                .find(|(_, named)| named.name == name)
+                .map(|(entity, _)| entity);
+            if found.is_none() {
+                error!("Timeline refers to unknown entity {}", name);
+            }
+            found
+        };
+        let mut activate = |entity: Entity, active: bool| {
+            let result = if active {
+                hidden.remove(entity);
+                Ok(())
+            } else {
+                hidden.insert(entity, HiddenPropagate).map(|_| ())
+            };
+            if let Err(err) = result {
+                error!("Failed to activate timeline entity: {}", err);
+            }
+        };
+
+        for (entity, player) in (&*entities, &mut players).join() {
+            let timeline = match timelines.get(&player.timeline) {
+                Some(timeline) => timeline,
+                None => continue,
+            };
+            let duration = timeline.duration();
+
+            if let Some(to) = player.seek.take() {
+                let to = to.min(duration);
+                for (target, active) in timeline.activations_at(to) {
+                    if let Some(target) = find(target) {
+                        activate(target, active);
+                    }
+                }
+                if let Some(camera) = timeline.camera_at(to) {
+                    active_camera.entity = find(camera);
+                }
+                for (target, id) in player.clips.drain(..) {
+                    if let Some(set) = controls.get_mut(target) {
+                        set.abort(id);
+                    }
+                }
+                for clip in timeline.clips.iter().filter(|clip| clip.time <= to) {
+                    if let Some(target) = find(&clip.target) {
+                        let started = start_clip(target, clip, &animation_sets, &mut controls);
+                        if started {
+                            if let Some(set) = controls.get_mut(target) {
+                                set.set_input(
+                                    clip.animation.clone(),
+                                    (to - clip.time) * clip.speed,
+                                );
+                            }
+                            player.clips.push((target, clip.animation.clone()));
+                        }
+                    }
+                }
+                player.clips_paused = false;
+                player.started = true;
+                player.time = to;
+                player.finished = to >= duration;
+                if player.finished {
+                    player.playing = false;
+                    events.single_write(TimelineEvent::Finished { entity });
+                }
+            } else if player.playing {
+                let from = player.time;
+                let to = (from + time.delta_seconds() * player.speed).min(duration);
+                // The first frame also reaches the keys at the start, and puts the entities
+                // activated later in their state before it.
+                let first = !player.started;
+                player.started = true;
+                if first {
+                    for (target, active) in timeline.activations_at(from) {
+                        if let Some(target) = find(target) {
+                            activate(target, active);
+                        }
+                    }
+                }
+                let reached = |t: f32| (first || from < t) && t <= to;
+
+                for key in timeline.activations.iter().filter(|k| reached(k.time)) {
+                    if let Some(target) = find(&key.target) {
+                        activate(target, key.active);
+                    }
+                }
+                for cut in timeline.cuts.iter().filter(|k| reached(k.time)) {
+                    active_camera.entity = find(&cut.camera);
+                }
+                for clip in timeline.clips.iter().filter(|k| reached(k.time)) {
+                    if let Some(target) = find(&clip.target) {
+                        if start_clip(target, clip, &animation_sets, &mut controls) {
+                            player.clips.push((target, clip.animation.clone()));
+                        }
+                    }
+                }
+                for key in timeline.sounds.iter().filter(|k| reached(k.time)) {
+                    let source = player
+                        .sounds
+                        .get(&key.sound)
+                        .and_then(|handle| sources.get(handle));
+                    match (source, output.as_ref()) {
+                        (Some(source), Some(output)) => output.play_once(source, key.volume),
+                        (None, _) => error!("Timeline plays unknown sound {}", key.sound),
+                        (_, None) => {}
+                    }
+                    if let Some(ref caption) = key.caption {
+                        captions.show(caption.clone());
+                    }
+                }
+                for key in timeline.events.iter().filter(|k| reached(k.time)) {
+                    events.single_write(TimelineEvent::Fired {
+                        entity,
+                        name: key.name.clone(),
+                    });
+                }
+
+                player.time = to;
+                if to >= duration {
+                    player.playing = false;
+                    player.finished = true;
+                    events.single_write(TimelineEvent::Finished { entity });
+                }
+            }
+
+            // The clips are paused and resumed with the timeline, until it's finished.
+            let pause_clips = !player.playing && !player.finished;
+            if pause_clips != player.clips_paused {
+                player.clips_paused = pause_clips;
+                for (target, id) in &player.clips {
+                    if let Some(set) = controls.get_mut(*target) {
+                        if pause_clips {
+                            set.pause(id.clone());
+                        } else {
+                            set.start(id.clone());
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Adds the animation of the clip to the controls of the target, returns whether it has it.
+fn start_clip(
+    target: Entity,
+    clip: &ClipKey,
+    animation_sets: &ReadStorage<'_, AnimationSet<String, Transform>>,
+    controls: &mut WriteStorage<'_, AnimationControlSet<String, Transform>>,
+) -> bool {
+    let handle = match animation_sets
+        .get(target)
+        .and_then(|set| set.get(&clip.animation))
+    {
+        Some(handle) => handle,
+        None => {
+            error!(
+                "Timeline plays unknown animation {} of {}",
+                clip.animation, clip.target
+            );
+            return false;
+        }
+    };
+    match get_animation_set(controls, target) {
+        Some(set) => {
+            set.add_animation(
+                clip.animation.clone(),
+                handle,
+                EndControl::Stay,
+                clip.speed,
+                AnimationCommand::Start,
+            );
+            true
+        }
+        None => false,
+    }
+}
+
+/// Adds the `Processor` for `Timeline` assets and the `TimelineSystem`.
+///
+/// The animation clips need the `AnimationBundle` for `Transform` with `String` ids.
+#[derive(Default)]
+pub struct TimelineBundle;
+
+impl<'a, 'b> SystemBundle<'a, 'b> for TimelineBundle {
+    fn build(self, builder: &mut DispatcherBuilder<'a, 'b>) -> Result<()> {
+        builder.add(Processor::<Timeline>::new(), "timeline_processor", &[]);
+        builder.add(TimelineSystem, "timeline_system", &["timeline_processor"]);
+        Ok(())
+    }
+}
+
+/// State playing a `Timeline`, popping itself when it's finished.
+///
+/// The timeline is paused while another state is pushed on top of it, e.g. a `PausedState`,
+/// and skipped to its end when the skip key is pressed, by default `Escape`.
+#[derive(Debug)]
+pub struct TimelineState {
+    player: Option<TimelinePlayer>,
+    skip_key: Option<VirtualKeyCode>,
+    entity: Option<Entity>,
+}
+
+impl TimelineState {
+    /// Creates a state playing the timeline.
+    pub fn new(player: TimelinePlayer) -> Self {
+        TimelineState {
+            player: Some(player),
+            skip_key: Some(VirtualKeyCode::Escape),
+            entity: None,
+        }
+    }
+
+    /// Sets the key skipping the rest of the timeline, or `None` to play it to the end.
+    pub fn with_skip_key(mut self, skip_key: Option<VirtualKeyCode>) -> Self {
+        self.skip_key = skip_key;
+        self
+    }
+
+    fn with_player<F: FnOnce(&mut TimelinePlayer)>(
+        &self,
+        data: &StateData<'_, GameData<'_, '_>>,
+        f: F,
+    ) {
+        if let Some(entity) = self.entity {
+            if let Some(player) = data.world.write_storage::<TimelinePlayer>().get_mut(entity) {
+                f(player);
+            }
+        }
+    }
+}
+
+impl SimpleState for TimelineState {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        if let Some(player) = self.player.take() {
+            self.entity = Some(data.world.create_entity().with(player).build());
+        }
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        if let Some(entity) = self.entity.take() {
+            if let Err(err) = data.world.delete_entity(entity) {
+                error!("Failed to delete the timeline player: {}", err);
+            }
+        }
+    }
+
+    fn on_pause(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.with_player(&data, TimelinePlayer::pause);
+    }
+
+    fn on_resume(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.with_player(&data, TimelinePlayer::resume);
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(ref event) = event {
+            if is_close_requested(event) {
+                return Trans::Quit;
+            }
+            if let Some(key) = self.skip_key {
+                if is_key_down(event, key) {
+                    self.with_player(&data, |player| player.seek(std::f32::MAX));
+                }
+            }
+        }
+        Trans::None
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        let finished = self.entity.map_or(true, |entity| {
+            data.world
+                .read_storage::<TimelinePlayer>()
+                .get(entity)
+                .map_or(true, TimelinePlayer::is_finished)
+        });
+        if finished {
+            Trans::Pop
+        } else {
+            Trans::None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn activation(time: f32, target: &str, active: bool) -> ActivationKey {
+        ActivationKey {
+            time,
+            target: target.to_string(),
+            active,
+        }
+    }
+
+    #[test]
+    fn seeking_finds_the_state_at_the_time() {
+        let timeline = Timeline {
+            activations: vec![
+                activation(1., "door", false),
+                activation(2., "light", true),
+                activation(3., "door", true),
+            ],
+            cuts: vec![CutKey {
+                time: 2.,
+                camera: "close_up".to_string(),
+            }],
+            ..Default::default()
+        };
+        assert_eq!(3., timeline.duration());
+
+        let at = timeline.activations_at(0.);
+        assert_eq!((true, false), (at["door"], at["light"]));
+        let at = timeline.activations_at(2.5);
+        assert_eq!((false, true), (at["door"], at["light"]));
+        assert_eq!(None, timeline.camera_at(1.));
+        assert_eq!(Some("close_up"), timeline.camera_at(2.5));
+    }
+}
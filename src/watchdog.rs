@@ -0,0 +1,505 @@
+//! Opt-in detection of hangs of the main loop.
+//!
+//! A `Watchdog` runs a thread of its own, which logs a report when the main loop doesn't finish a
+//! frame in time. The report contains the part of the frame the main loop is stuck in, the state
+//! stack, the durations of the last frames, the systems timed with `SystemTimings`, the most
+//! recent breadcrumbs and, if enabled, the stacks of the stuck threads.
+
+use std::{
+    cmp,
+    collections::VecDeque,
+    fmt::Write,
+    process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    core::specs::prelude::{Resources, System},
+    crash::Breadcrumbs,
+    shred::RunningTime,
+};
+
+const FRAME_HISTORY: usize = 10;
+
+/// How long to wait for a thread to record its stack.
+const STACK_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Configuration of the watchdog, installed with
+/// [`ApplicationBuilder::with_watchdog`](struct.ApplicationBuilder.html#method.with_watchdog).
+///
+/// The threshold has to be larger than the longest frame the game expects, including the
+/// `on_start` of states loading assets synchronously.
+///
+/// To see where the main loop is stuck, enable `with_stacks` on Unix, which adds the stacks of
+/// the main thread and of the threads running timed systems to the report, or `with_abort`:
+/// aborting the process writes a core dump on most systems, holding the stacks of every thread,
+/// and is caught by debuggers.
+#[derive(Clone, Debug)]
+pub struct Watchdog {
+    threshold: Duration,
+    abort_after: Option<Duration>,
+    breadcrumbs: Option<Breadcrumbs>,
+    timings: Option<SystemTimings>,
+    stacks: bool,
+}
+
+impl Watchdog {
+    /// Creates a watchdog reporting frames which take longer than `threshold`.
+    pub fn new(threshold: Duration) -> Self {
+        Watchdog {
+            threshold,
+            abort_after: None,
+            breadcrumbs: None,
+            timings: None,
+            stacks: false,
+        }
+    }
+
+    /// Aborts the process when a frame takes longer than `abort_after`, which should be larger
+    /// than the threshold, to not abort on hitches the game recovers from.
+    pub fn with_abort(mut self, abort_after: Duration) -> Self {
+        self.abort_after = Some(abort_after);
+        self
+    }
+
+    /// Adds the given breadcrumbs to the report, e.g. the ones of the `CrashHandler`.
+    pub fn with_breadcrumbs(mut self, breadcrumbs: Breadcrumbs) -> Self {
+        self.breadcrumbs = Some(breadcrumbs);
+        self
+    }
+
+    /// Adds the durations of the systems timed with `timings` to the report, naming the systems
+    /// the frame is waiting on.
+    pub fn with_system_timings(mut self, timings: SystemTimings) -> Self {
+        self.timings = Some(timings);
+        self
+    }
+
+    /// Adds the stacks of the main thread and of the threads running timed systems to the
+    /// report.
+    ///
+    /// The stacks are recorded by a `SIGUSR2` handler, so this does nothing on other systems than
+    /// Unix, and replaces any handler of `SIGUSR2` the game installed.
+    pub fn with_stacks(mut self) -> Self {
+        self.stacks = true;
+        self
+    }
+
+    /// Starts the watchdog thread, which stops once the returned handle is dropped. It has to be
+    /// called from the main thread.
+    pub(crate) fn start(self) -> WatchdogHandle {
+        let main_thread = if self.stacks && stacks::install() {
+            Some(stacks::current_thread())
+        } else {
+            if self.stacks {
+                warn!("The watchdog can't record the stacks of the threads on this system");
+            }
+            None
+        };
+        let progress = Arc::new(Mutex::new(Progress {
+            frame_number: 0,
+            phase: "initialize",
+            frame_start: Instant::now(),
+            frame_times: VecDeque::with_capacity(FRAME_HISTORY),
+            state_stack: Vec::new(),
+        }));
+        let running = Arc::new(AtomicBool::new(true));
+        let handle = WatchdogHandle {
+            progress: progress.clone(),
+            running: running.clone(),
+        };
+        let spawned = thread::Builder::new()
+            .name("watchdog".to_owned())
+            .spawn(move || self.watch(&progress, &running, main_thread));
+        if let Err(e) = spawned {
+            error!("Failed to start the watchdog: {}", e);
+        }
+        handle
+    }
+
+    fn watch(
+        &self,
+        progress: &Mutex<Progress>,
+        running: &AtomicBool,
+        main_thread: Option<stacks::Thread>,
+    ) {
+        let interval = cmp::max(self.threshold / 4, Duration::from_millis(1));
+        let mut reported = None;
+        while running.load(Ordering::Relaxed) {
+            thread::sleep(interval);
+            let progress = match progress.lock() {
+                Ok(progress) => progress,
+                Err(_) => return,
+            };
+            let elapsed = progress.frame_start.elapsed();
+            if elapsed < self.threshold {
+                continue;
+            }
+            if reported != Some(progress.frame_number) {
+                reported = Some(progress.frame_number);
+                error!("{}", self.report(&progress, elapsed, main_thread));
+            }
+            if let Some(abort_after) = self.abort_after {
+                if elapsed >= abort_after {
+                    error!(
+                        "Frame {} is still stuck in `{}` after {:?}, aborting",
+                        progress.frame_number, progress.phase, elapsed
+                    );
+                    process::abort();
+                }
+            }
+        }
+    }
+
+    fn report(
+        &self,
+        progress: &Progress,
+        elapsed: Duration,
+        main_thread: Option<stacks::Thread>,
+    ) -> String {
+        let mut report = String::new();
+        writeln!(
+            report,
+            "Frame {} hasn't finished after {:?}, stuck in `{}`",
+            progress.frame_number, elapsed, progress.phase
+        )
+        .ok();
+        writeln!(report, "State stack: {:?}", progress.state_stack).ok();
+        writeln!(report, "Last frame times: {:?}", progress.frame_times).ok();
+        let systems = self
+            .timings
+            .as_ref()
+            .map(SystemTimings::snapshot)
+            .unwrap_or_default();
+        if !systems.is_empty() {
+            writeln!(report, "Systems:").ok();
+            for system in &systems {
+                match system.running {
+                    Some((start, _)) => {
+                        writeln!(
+                            report,
+                            "  {}: running for {:?}",
+                            system.name,
+                            start.elapsed()
+                        )
+                    }
+                    None => writeln!(report, "  {}: last run took {:?}", system.name, system.last),
+                }
+                .ok();
+            }
+        }
+        if let Some(ref breadcrumbs) = self.breadcrumbs {
+            writeln!(report, "Breadcrumbs:").ok();
+            for line in breadcrumbs.snapshot() {
+                writeln!(report, "  {}", line).ok();
+            }
+        }
+        if let Some(main_thread) = main_thread {
+            write_stack(&mut report, "the main thread", main_thread);
+            for system in &systems {
+                if let Some((_, thread)) = system.running {
+                    if thread != main_thread {
+                        write_stack(&mut report, &format!("`{}`", system.name), thread);
+                    }
+                }
+            }
+        }
+        report
+    }
+}
+
+fn write_stack(report: &mut String, name: &str, thread: stacks::Thread) {
+    match stacks::capture(thread, STACK_TIMEOUT) {
+        Some(frames) => {
+            writeln!(report, "Stack of {}:", name).ok();
+            for frame in frames {
+                writeln!(report, "  {}", frame).ok();
+            }
+        }
+        None => {
+            writeln!(report, "The stack of {} couldn't be recorded", name).ok();
+        }
+    }
+}
+
+/// Durations of the systems wrapped with `timed`, reported by a `Watchdog`.
+///
+/// Systems added to a `GameDataBuilder` after
+/// [`with_system_timings`](struct.GameDataBuilder.html#method.with_system_timings) are timed
+/// with it. Systems added by bundles aren't, since a bundle adds them to the dispatcher directly.
+#[derive(Clone, Debug, Default)]
+pub struct SystemTimings {
+    systems: Arc<Mutex<Vec<SystemTiming>>>,
+}
+
+#[derive(Clone, Debug)]
+struct SystemTiming {
+    name: String,
+    /// Start of the current run and the thread it runs on.
+    running: Option<(Instant, stacks::Thread)>,
+    last: Duration,
+}
+
+impl SystemTimings {
+    /// Creates empty timings.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Wraps a system to record when it starts and how long it takes.
+    pub fn timed<S>(&self, system: S, name: &str) -> TimedSystem<S> {
+        let mut systems = self.systems.lock().unwrap_or_else(|e| e.into_inner());
+        systems.push(SystemTiming {
+            name: name.to_string(),
+            running: None,
+            last: Duration::from_secs(0),
+        });
+        TimedSystem {
+            system,
+            timings: self.clone(),
+            index: systems.len() - 1,
+        }
+    }
+
+    fn snapshot(&self) -> Vec<SystemTiming> {
+        self.systems
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    fn update<F>(&self, index: usize, f: F)
+    where
+        F: FnOnce(&mut SystemTiming),
+    {
+        if let Some(timing) = self
+            .systems
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get_mut(index)
+        {
+            f(timing);
+        }
+    }
+}
+
+/// System timed by `SystemTimings`.
+#[derive(Debug)]
+pub struct TimedSystem<S> {
+    system: S,
+    timings: SystemTimings,
+    index: usize,
+}
+
+impl<'s, S> System<'s> for TimedSystem<S>
+where
+    S: System<'s>,
+{
+    type SystemData = S::SystemData;
+
+    fn run(&mut self, data: Self::SystemData) {
+        let start = Instant::now();
+        self.timings.update(self.index, |timing| {
+            timing.running = Some((start, stacks::current_thread()));
+        });
+        self.system.run(data);
+        self.timings.update(self.index, |timing| {
+            timing.running = None;
+            timing.last = start.elapsed();
+        });
+    }
+
+    fn running_time(&self) -> RunningTime {
+        self.system.running_time()
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        self.system.setup(res);
+    }
+}
+
+/// Records the stacks of other threads by sending them a signal, whose handler walks the stack
+/// of the thread it interrupted.
+#[cfg(unix)]
+mod stacks {
+    use std::{
+        mem, ptr,
+        sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+        thread,
+        time::{Duration, Instant},
+    };
+
+    pub type Thread = libc::pthread_t;
+
+    const SIGNAL: libc::c_int = libc::SIGUSR2;
+    const MAX_FRAMES: usize = 64;
+
+    // Written by the signal handler only, which mustn't allocate, until `RECORDED` is set.
+    static mut FRAMES: [usize; MAX_FRAMES] = [0; MAX_FRAMES];
+    static FRAME_COUNT: AtomicUsize = AtomicUsize::new(0);
+    static RECORDED: AtomicBool = AtomicBool::new(false);
+
+    extern "C" fn record(_: libc::c_int) {
+        let mut count = 0;
+        unsafe {
+            backtrace::trace_unsynchronized(|frame| {
+                FRAMES[count] = frame.ip() as usize;
+                count += 1;
+                count < MAX_FRAMES
+            });
+        }
+        FRAME_COUNT.store(count, Ordering::Relaxed);
+        RECORDED.store(true, Ordering::Release);
+    }
+
+    /// Installs the signal handler, returning `false` if it failed.
+    pub fn install() -> bool {
+        unsafe {
+            let mut action: libc::sigaction = mem::zeroed();
+            action.sa_sigaction = record as usize;
+            action.sa_flags = libc::SA_RESTART;
+            libc::sigemptyset(&mut action.sa_mask);
+            libc::sigaction(SIGNAL, &action, ptr::null_mut()) == 0
+        }
+    }
+
+    pub fn current_thread() -> Thread {
+        unsafe { libc::pthread_self() }
+    }
+
+    /// Returns the frames of the stack of a thread, or `None` if it didn't record them in time.
+    pub fn capture(thread: Thread, timeout: Duration) -> Option<Vec<String>> {
+        RECORDED.store(false, Ordering::SeqCst);
+        if unsafe { libc::pthread_kill(thread, SIGNAL) } != 0 {
+            return None;
+        }
+        let start = Instant::now();
+        while !RECORDED.load(Ordering::Acquire) {
+            if start.elapsed() > timeout {
+                return None;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        let count = FRAME_COUNT.load(Ordering::Relaxed);
+        let frames = unsafe { FRAMES[..count].to_vec() };
+        Some(frames.into_iter().map(describe).collect())
+    }
+
+    fn describe(ip: usize) -> String {
+        let mut description = format!("{:#x}", ip);
+        backtrace::resolve(ip as *mut libc::c_void, |symbol| {
+            if let Some(name) = symbol.name() {
+                description = format!("{:#x} {}", ip, name);
+            }
+            if let (Some(file), Some(line)) = (symbol.filename(), symbol.lineno()) {
+                description = format!("{} at {}:{}", description, file.display(), line);
+            }
+        });
+        description
+    }
+}
+
+#[cfg(not(unix))]
+mod stacks {
+    use std::time::Duration;
+
+    pub type Thread = ();
+
+    pub fn install() -> bool {
+        false
+    }
+
+    pub fn current_thread() -> Thread {}
+
+    pub fn capture(_: Thread, _: Duration) -> Option<Vec<String>> {
+        None
+    }
+}
+
+struct Progress {
+    frame_number: u64,
+    phase: &'static str,
+    frame_start: Instant,
+    frame_times: VecDeque<Duration>,
+    state_stack: Vec<&'static str>,
+}
+
+/// Handle the main loop reports its progress to.
+pub(crate) struct WatchdogHandle {
+    progress: Arc<Mutex<Progress>>,
+    running: Arc<AtomicBool>,
+}
+
+impl WatchdogHandle {
+    /// Records which part of the frame the main loop is in.
+    pub(crate) fn enter(&self, phase: &'static str) {
+        if let Ok(mut progress) = self.progress.lock() {
+            progress.phase = phase;
+        }
+    }
+
+    /// Records the end of a frame, restarting the countdown.
+    pub(crate) fn finish_frame<I>(&self, frame_number: u64, state_names: I)
+    where
+        I: Iterator<Item = &'static str>,
+    {
+        if let Ok(mut progress) = self.progress.lock() {
+            if progress.frame_times.len() == FRAME_HISTORY {
+                progress.frame_times.pop_front();
+            }
+            let frame_time = progress.frame_start.elapsed();
+            progress.frame_times.push_back(frame_time);
+            progress.frame_number = frame_number;
+            progress.phase = "frame start";
+            progress.frame_start = Instant::now();
+            progress.state_stack.clear();
+            progress.state_stack.extend(state_names);
+        }
+    }
+}
+
+impl Drop for WatchdogHandle {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::core::specs::prelude::{RunNow, World};
+
+    struct Nop;
+
+    impl<'a> System<'a> for Nop {
+        type SystemData = ();
+
+        fn run(&mut self, _: ()) {}
+    }
+
+    #[test]
+    fn report_lists_timed_systems() {
+        let timings = SystemTimings::new();
+        let mut system = timings.timed(Nop, "nop");
+        let world = World::new();
+        system.run_now(&world.res);
+
+        let watchdog = Watchdog::new(Duration::from_secs(1)).with_system_timings(timings);
+        let progress = Progress {
+            frame_number: 3,
+            phase: "update",
+            frame_start: Instant::now(),
+            frame_times: VecDeque::new(),
+            state_stack: vec!["Game"],
+        };
+        let report = watchdog.report(&progress, Duration::from_secs(2), None);
+        assert!(report.contains("stuck in `update`"), "{}", report);
+        assert!(report.contains("nop: last run took"), "{}", report);
+    }
+}
@@ -24,7 +24,8 @@ use boxfnonce::SendBoxFnOnce;
 use hetseq::Queue;
 
 use crate::{
-    CustomDispatcherStateBuilder, FunctionState, GameUpdate, SequencerState, SystemInjectionBundle,
+    CaptureState, CustomDispatcherStateBuilder, FunctionState, GameUpdate, GoldenImage,
+    SequencerState, SystemInjectionBundle,
 };
 
 type BundleAddFn = SendBoxFnOnce<
@@ -585,6 +586,21 @@ where
         self.with_fn(assertion_fn)
     }
 
+    /// Runs a number of frames, then compares the rendered frame against a golden image.
+    ///
+    /// This requires the `RenderBundle`, such as through `render_base` or
+    /// `.with_render_bundle()`. The comparison fails the test if too many pixels differ. Set the
+    /// `AMETHYST_UPDATE_GOLDEN` environment variable to write the captured frame as the new golden
+    /// image instead.
+    ///
+    /// # Parameters
+    ///
+    /// * `golden`: Image to compare against, along with its tolerance.
+    /// * `frames`: Number of frames to run before capturing, to let the scene settle.
+    pub fn with_golden_image(self, golden: GoldenImage, frames: u32) -> Self {
+        self.with_state(move || CaptureState::new(golden, frames))
+    }
+
     /// Marks that this application uses the `RenderBundle`.
     ///
     /// **Note:** There is a `.with_render_bundle()` convenience function if you just need the
@@ -609,6 +625,8 @@ where
     /// * `vsync`: `true`
     /// * `multisampling`: `0` (disabled)
     /// * `visibility`: As provided.
+    /// * `headless`: `true` when the window is hidden, so frames are read back from an offscreen
+    ///   target rather than from a hidden window.
     ///
     /// This is exposed to allow external crates a convenient way of obtaining display
     /// configuration.
@@ -627,6 +645,8 @@ where
             vsync: true,
             multisampling: 0, // Must be multiple of 2, use 0 to disable
             visibility,
+            headless: !visibility,
+            ..Default::default()
         }
     }
 
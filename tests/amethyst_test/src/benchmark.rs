@@ -0,0 +1,499 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use amethyst::{
+    core::SystemBundle,
+    ecs::prelude::*,
+    shred::{RunningTime, SystemData},
+    Error, Result,
+};
+
+type FnSetup = Box<dyn FnMut(&mut World)>;
+type Samples = Arc<Mutex<Vec<Duration>>>;
+
+/// Builder for a benchmark of systems and bundles.
+///
+/// The benchmark constructs a `World` with generated entities, then dispatches the registered
+/// systems a number of times and reports how long each dispatch took. Systems registered through
+/// `.with_system(..)` are also timed individually. The systems of a bundle are added to the
+/// dispatcher by the bundle itself, so they can't be wrapped one by one; instead each bundle is
+/// timed as a whole, from the start of its first system to the end of its last one.
+///
+/// This does not open a window or run an `Application`, so it is suitable for benchmarking logic
+/// systems. It runs on the calling thread.
+///
+/// # Examples
+///
+/// ```rust
+/// # extern crate amethyst;
+/// # extern crate amethyst_test;
+/// #
+/// # use amethyst_test::Benchmark;
+/// # use amethyst::ecs::prelude::*;
+/// #
+/// # struct Position(f32);
+/// # impl Component for Position {
+/// #     type Storage = VecStorage<Self>;
+/// # }
+/// #
+/// # struct MoveSystem;
+/// # impl<'s> System<'s> for MoveSystem {
+/// #     type SystemData = WriteStorage<'s, Position>;
+/// #     fn run(&mut self, mut positions: Self::SystemData) {
+/// #         for position in (&mut positions).join() {
+/// #             position.0 += 1.;
+/// #         }
+/// #     }
+/// # }
+/// #
+/// # fn main() -> amethyst::Result<()> {
+/// let report = Benchmark::new()
+///     .with_system(MoveSystem, "move", &[])
+///     .with_entities(10_000, |builder, index| builder.with(Position(index as f32)))
+///     .with_iterations(100)
+///     .run()?;
+///
+/// println!("{}", report);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct Benchmark<'a, 'b> {
+    /// Dispatcher builder that systems and bundles are added to.
+    #[derivative(Debug = "ignore")]
+    dispatcher_builder: DispatcherBuilder<'a, 'b>,
+    /// Functions to run on the `World` before the benchmark starts, in order.
+    #[derivative(Debug = "ignore")]
+    setup_fns: Vec<FnSetup>,
+    /// Timing samples of individually timed systems and bundles.
+    system_samples: Vec<(String, Samples)>,
+    /// Number of dispatches that are measured.
+    iterations: u32,
+    /// Number of dispatches run before measuring starts.
+    warmup_iterations: u32,
+}
+
+impl<'a, 'b> Default for Benchmark<'a, 'b> {
+    fn default() -> Self {
+        Benchmark::new()
+    }
+}
+
+impl<'a, 'b> Benchmark<'a, 'b> {
+    /// Returns a new benchmark that runs 100 iterations after 10 warm up iterations.
+    pub fn new() -> Self {
+        Benchmark {
+            dispatcher_builder: DispatcherBuilder::new(),
+            setup_fns: Vec::new(),
+            system_samples: Vec::new(),
+            iterations: 100,
+            warmup_iterations: 10,
+        }
+    }
+
+    /// Adds a bundle's systems to the benchmarked dispatcher, timing each run of them as a whole.
+    ///
+    /// The bundle's systems are separated from the other systems by barriers, so they don't run in
+    /// parallel with systems registered before or after the bundle.
+    ///
+    /// # Parameters
+    ///
+    /// * `bundle`: Bundle to add.
+    /// * `name`: Name to report the timings of the bundle with.
+    pub fn with_bundle<B>(mut self, bundle: B, name: &str) -> Result<Self>
+    where
+        B: SystemBundle<'a, 'b>,
+    {
+        let start = Arc::new(Mutex::new(None));
+        let samples = Samples::default();
+        self.system_samples
+            .push((name.to_string(), samples.clone()));
+
+        self.dispatcher_builder.add_barrier();
+        self.dispatcher_builder
+            .add(BundleStart(start.clone()), "", &[]);
+        self.dispatcher_builder.add_barrier();
+        bundle
+            .build(&mut self.dispatcher_builder)
+            .map_err(Error::Core)?;
+        self.dispatcher_builder.add_barrier();
+        self.dispatcher_builder
+            .add(BundleEnd { start, samples }, "", &[]);
+        self.dispatcher_builder.add_barrier();
+        Ok(self)
+    }
+
+    /// Adds a system to the benchmarked dispatcher, timing each of its runs.
+    ///
+    /// # Parameters
+    ///
+    /// * `system`: The `System` to register.
+    /// * `name`: Name to register the system with, used for dependency ordering and reporting.
+    /// * `deps`: Names of systems that must run before this system.
+    pub fn with_system<S>(mut self, system: S, name: &str, deps: &[&str]) -> Self
+    where
+        for<'c> S: System<'c> + Send + 'a,
+    {
+        let samples = Samples::default();
+        self.system_samples
+            .push((name.to_string(), samples.clone()));
+        self.dispatcher_builder
+            .add(Timed { system, samples }, name, deps);
+        self
+    }
+
+    /// Creates entities in the `World` before the benchmark starts.
+    ///
+    /// # Parameters
+    ///
+    /// * `count`: Number of entities to create.
+    /// * `entity_fn`: Function that adds components to the entity with the given index.
+    pub fn with_entities<F>(mut self, count: usize, entity_fn: F) -> Self
+    where
+        F: for<'w> Fn(EntityBuilder<'w>, usize) -> EntityBuilder<'w> + 'static,
+    {
+        self.setup_fns.push(Box::new(move |world: &mut World| {
+            for index in 0..count {
+                entity_fn(world.create_entity(), index).build();
+            }
+        }));
+        self
+    }
+
+    /// Registers a function that sets up the `World` before the benchmark starts.
+    ///
+    /// Setup functions and entity generators run in the order they are registered, after the
+    /// dispatcher's systems have been set up.
+    ///
+    /// # Parameters
+    ///
+    /// * `setup_fn`: Function to execute.
+    pub fn with_setup<F>(mut self, setup_fn: F) -> Self
+    where
+        F: FnMut(&mut World) + 'static,
+    {
+        self.setup_fns.push(Box::new(setup_fn));
+        self
+    }
+
+    /// Sets the number of measured dispatches.
+    pub fn with_iterations(mut self, iterations: u32) -> Self {
+        self.iterations = iterations;
+        self
+    }
+
+    /// Sets the number of dispatches run before measuring starts.
+    pub fn with_warmup_iterations(mut self, warmup_iterations: u32) -> Self {
+        self.warmup_iterations = warmup_iterations;
+        self
+    }
+
+    /// Runs the benchmark and returns the measured timings.
+    pub fn run(self) -> Result<BenchmarkReport> {
+        let Benchmark {
+            dispatcher_builder,
+            setup_fns,
+            system_samples,
+            iterations,
+            warmup_iterations,
+        } = self;
+
+        if iterations == 0 {
+            return Err(Error::Core(
+                "Benchmark requires at least one iteration".into(),
+            ));
+        }
+
+        let mut world = World::new();
+        let mut dispatcher = dispatcher_builder.build();
+        dispatcher.setup(&mut world.res);
+        for mut setup_fn in setup_fns {
+            setup_fn(&mut world);
+        }
+        world.maintain();
+
+        for _ in 0..warmup_iterations {
+            dispatcher.dispatch(&world.res);
+            world.maintain();
+        }
+        for (_, samples) in &system_samples {
+            samples.lock().expect("Timing samples poisoned").clear();
+        }
+
+        let mut dispatch_samples = Vec::with_capacity(iterations as usize);
+        for _ in 0..iterations {
+            let start = Instant::now();
+            dispatcher.dispatch(&world.res);
+            world.maintain();
+            dispatch_samples.push(start.elapsed());
+        }
+
+        let systems = system_samples
+            .into_iter()
+            .map(|(name, samples)| {
+                let samples = samples.lock().expect("Timing samples poisoned");
+                (name, TimingStats::from_samples(&samples))
+            })
+            .collect();
+
+        Ok(BenchmarkReport {
+            iterations,
+            dispatch: TimingStats::from_samples(&dispatch_samples),
+            systems,
+        })
+    }
+}
+
+/// Summary of a set of timing samples.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TimingStats {
+    /// Number of samples.
+    pub count: usize,
+    /// Shortest sample.
+    pub min: Duration,
+    /// Longest sample.
+    pub max: Duration,
+    /// Average of the samples.
+    pub mean: Duration,
+    /// Middle sample when sorted.
+    pub median: Duration,
+}
+
+impl TimingStats {
+    /// Computes the statistics of the given samples.
+    pub fn from_samples(samples: &[Duration]) -> Self {
+        if samples.is_empty() {
+            return TimingStats::default();
+        }
+
+        let mut sorted = samples.to_vec();
+        sorted.sort();
+        let total = sorted.iter().fold(Duration::new(0, 0), |sum, d| sum + *d);
+
+        TimingStats {
+            count: sorted.len(),
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+            mean: total / sorted.len() as u32,
+            median: sorted[sorted.len() / 2],
+        }
+    }
+}
+
+/// Timings measured by a `Benchmark`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BenchmarkReport {
+    /// Number of measured dispatches.
+    pub iterations: u32,
+    /// Timing of each dispatch, including `World::maintain`.
+    pub dispatch: TimingStats,
+    /// Timing of each individually timed system and bundle, in registration order.
+    pub systems: Vec<(String, TimingStats)>,
+}
+
+impl BenchmarkReport {
+    /// Returns the timing of the system or bundle with the given name.
+    pub fn system(&self, name: &str) -> Option<&TimingStats> {
+        self.systems
+            .iter()
+            .find(|(system_name, _)| system_name == name)
+            .map(|(_, stats)| stats)
+    }
+}
+
+impl Display for BenchmarkReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fn micros(d: Duration) -> f64 {
+            d.as_secs() as f64 * 1e6 + f64::from(d.subsec_nanos()) / 1e3
+        }
+        fn row(f: &mut Formatter<'_>, name: &str, stats: &TimingStats) -> fmt::Result {
+            writeln!(
+                f,
+                "{:<32} {:>12.1} {:>12.1} {:>12.1} {:>12.1}",
+                name,
+                micros(stats.min),
+                micros(stats.median),
+                micros(stats.mean),
+                micros(stats.max)
+            )
+        }
+
+        writeln!(f, "{} iterations, timings in µs", self.iterations)?;
+        writeln!(
+            f,
+            "{:<32} {:>12} {:>12} {:>12} {:>12}",
+            "", "min", "median", "mean", "max"
+        )?;
+        row(f, "dispatch", &self.dispatch)?;
+        for (name, stats) in &self.systems {
+            row(f, name, stats)?;
+        }
+        Ok(())
+    }
+}
+
+/// Wraps a system to record how long each of its runs takes.
+struct Timed<S> {
+    system: S,
+    samples: Samples,
+}
+
+impl<'s, S> System<'s> for Timed<S>
+where
+    S: System<'s>,
+    S::SystemData: SystemData<'s>,
+{
+    type SystemData = S::SystemData;
+
+    fn run(&mut self, data: Self::SystemData) {
+        let start = Instant::now();
+        self.system.run(data);
+        let elapsed = start.elapsed();
+        self.samples
+            .lock()
+            .expect("Timing samples poisoned")
+            .push(elapsed);
+    }
+
+    fn running_time(&self) -> RunningTime {
+        self.system.running_time()
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        self.system.setup(res);
+    }
+}
+
+/// Records when the systems of a bundle start running.
+struct BundleStart(Arc<Mutex<Option<Instant>>>);
+
+impl<'s> System<'s> for BundleStart {
+    type SystemData = ();
+
+    fn run(&mut self, _: ()) {
+        *self.0.lock().expect("Timing samples poisoned") = Some(Instant::now());
+    }
+}
+
+/// Records how long the systems of a bundle ran, once they all finished.
+struct BundleEnd {
+    start: Arc<Mutex<Option<Instant>>>,
+    samples: Samples,
+}
+
+impl<'s> System<'s> for BundleEnd {
+    type SystemData = ();
+
+    fn run(&mut self, _: ()) {
+        let start = self.start.lock().expect("Timing samples poisoned").take();
+        if let Some(start) = start {
+            self.samples
+                .lock()
+                .expect("Timing samples poisoned")
+                .push(start.elapsed());
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use amethyst::{
+        core::bundle::{Result, SystemBundle},
+        ecs::prelude::*,
+    };
+
+    use super::{Benchmark, TimingStats};
+
+    #[test]
+    fn timed_system_is_sampled_once_per_iteration() {
+        let report = Benchmark::new()
+            .with_system(SystemIncrement, "increment", &[])
+            .with_entities(50, |builder, index| builder.with(ComponentZero(index)))
+            .with_warmup_iterations(3)
+            .with_iterations(7)
+            .run()
+            .expect("Benchmark failed to run");
+
+        assert_eq!(7, report.dispatch.count);
+        assert_eq!(
+            7,
+            report
+                .system("increment")
+                .expect("Expected `increment` timings")
+                .count
+        );
+    }
+
+    #[test]
+    fn bundle_is_sampled_once_per_iteration() {
+        let report = Benchmark::new()
+            .with_bundle(BundleIncrement, "increment_bundle")
+            .expect("Failed to add bundle")
+            .with_system(SystemIncrement, "increment", &[])
+            .with_entities(50, |builder, index| builder.with(ComponentZero(index)))
+            .with_warmup_iterations(3)
+            .with_iterations(7)
+            .run()
+            .expect("Benchmark failed to run");
+
+        let names = report
+            .systems
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["increment_bundle", "increment"], names);
+        assert_eq!(
+            7,
+            report
+                .system("increment_bundle")
+                .expect("Expected `increment_bundle` timings")
+                .count
+        );
+    }
+
+    #[test]
+    fn zero_iterations_is_error() {
+        assert!(Benchmark::new().with_iterations(0).run().is_err());
+    }
+
+    #[test]
+    fn timing_stats_from_samples() {
+        let samples = [3, 1, 2, 6].iter().map(|ms| Duration::from_millis(*ms));
+        let stats = TimingStats::from_samples(&samples.collect::<Vec<_>>());
+
+        assert_eq!(4, stats.count);
+        assert_eq!(Duration::from_millis(1), stats.min);
+        assert_eq!(Duration::from_millis(6), stats.max);
+        assert_eq!(Duration::from_millis(3), stats.mean);
+        assert_eq!(Duration::from_millis(3), stats.median);
+    }
+
+    struct ComponentZero(usize);
+    impl Component for ComponentZero {
+        type Storage = VecStorage<Self>;
+    }
+
+    struct SystemIncrement;
+    impl<'s> System<'s> for SystemIncrement {
+        type SystemData = WriteStorage<'s, ComponentZero>;
+        fn run(&mut self, mut components: Self::SystemData) {
+            for component in (&mut components).join() {
+                component.0 += 1;
+            }
+        }
+    }
+
+    struct BundleIncrement;
+    impl<'a, 'b> SystemBundle<'a, 'b> for BundleIncrement {
+        fn build(self, builder: &mut DispatcherBuilder<'a, 'b>) -> Result<()> {
+            builder.add(SystemIncrement, "bundle_increment", &[]);
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,283 @@
+use std::{
+    env,
+    fmt::{self, Display, Formatter},
+    path::{Path, PathBuf},
+};
+
+use amethyst::renderer::CapturedFrame;
+use image::{self, RgbaImage};
+
+/// Environment variable that, when set, writes captured frames over the golden images instead of
+/// comparing against them.
+pub const UPDATE_GOLDEN_ENV: &str = "AMETHYST_UPDATE_GOLDEN";
+
+/// Largest possible value of the YIQ colour delta between two pixels.
+const MAX_YIQ_DELTA: f32 = 35_215.;
+
+/// How much a captured frame may differ from the golden image and still pass.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tolerance {
+    /// Perceptual difference in `[0, 1]` above which a pixel is counted as different.
+    pub pixel_threshold: f32,
+    /// Fraction of pixels in `[0, 1]` that may differ before the comparison fails.
+    pub max_differing_ratio: f32,
+}
+
+impl Tolerance {
+    /// Tolerance that requires every pixel to be perceptually identical.
+    pub fn exact() -> Self {
+        Tolerance {
+            pixel_threshold: 0.,
+            max_differing_ratio: 0.,
+        }
+    }
+}
+
+impl Default for Tolerance {
+    /// Allows small differences caused by driver specific rasterization and dithering.
+    fn default() -> Self {
+        Tolerance {
+            pixel_threshold: 0.1,
+            max_differing_ratio: 0.001,
+        }
+    }
+}
+
+/// Reason why a captured frame does not match its golden image.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ImageMismatch {
+    /// The golden image does not exist, and updating golden images is not enabled.
+    MissingGolden(PathBuf),
+    /// The golden image could not be read or written.
+    Io(String),
+    /// The captured frame and golden image have different dimensions.
+    Dimensions {
+        /// Width and height of the golden image.
+        expected: (u32, u32),
+        /// Width and height of the captured frame.
+        actual: (u32, u32),
+    },
+    /// Too many pixels differ between the captured frame and golden image.
+    Pixels {
+        /// Number of pixels that were perceptually different.
+        differing: usize,
+        /// Total number of pixels compared.
+        total: usize,
+    },
+}
+
+impl Display for ImageMismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match *self {
+            ImageMismatch::MissingGolden(ref path) => write!(
+                f,
+                "Golden image `{}` does not exist. Run with `{}=1` to create it.",
+                path.display(),
+                UPDATE_GOLDEN_ENV
+            ),
+            ImageMismatch::Io(ref e) => write!(f, "Failed to access golden image: {}", e),
+            ImageMismatch::Dimensions { expected, actual } => write!(
+                f,
+                "Expected a {}x{} frame, but captured {}x{}.",
+                expected.0, expected.1, actual.0, actual.1
+            ),
+            ImageMismatch::Pixels { differing, total } => write!(
+                f,
+                "{} of {} pixels differ from the golden image.",
+                differing, total
+            ),
+        }
+    }
+}
+
+/// A reference image that captured frames are compared against.
+///
+/// When a comparison fails, the captured frame is written next to the golden image with an
+/// `.actual.png` suffix so that it can be inspected or collected by CI.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GoldenImage {
+    /// Path to the golden PNG image.
+    pub path: PathBuf,
+    /// Allowed difference between the captured frame and the golden image.
+    pub tolerance: Tolerance,
+}
+
+impl GoldenImage {
+    /// Returns a new `GoldenImage` with the default tolerance.
+    ///
+    /// # Parameters
+    ///
+    /// * `path`: Path to the golden PNG image.
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        GoldenImage {
+            path: path.as_ref().to_path_buf(),
+            tolerance: Tolerance::default(),
+        }
+    }
+
+    /// Sets the tolerance for the comparison.
+    pub fn with_tolerance(mut self, tolerance: Tolerance) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Compares the captured frame against the golden image.
+    ///
+    /// If the `AMETHYST_UPDATE_GOLDEN` environment variable is set, the frame is written as the
+    /// new golden image instead.
+    pub fn compare(&self, frame: &CapturedFrame) -> Result<(), ImageMismatch> {
+        if env::var_os(UPDATE_GOLDEN_ENV).is_some() {
+            return save(frame, &self.path);
+        }
+        if !self.path.exists() {
+            return Err(ImageMismatch::MissingGolden(self.path.clone()));
+        }
+
+        let golden = image::open(&self.path)
+            .map_err(|e| ImageMismatch::Io(e.to_string()))?
+            .to_rgba();
+        let result = compare_pixels(&golden, frame, self.tolerance);
+        if result.is_err() {
+            let actual = self.path.with_extension("actual.png");
+            if let Err(e) = save(frame, &actual) {
+                eprintln!("Failed to write `{}`: {}", actual.display(), e);
+            }
+        }
+        result
+    }
+}
+
+/// Compares the golden image with a captured frame, pixel by pixel.
+fn compare_pixels(
+    golden: &RgbaImage,
+    frame: &CapturedFrame,
+    tolerance: Tolerance,
+) -> Result<(), ImageMismatch> {
+    if golden.dimensions() != (frame.width, frame.height) {
+        return Err(ImageMismatch::Dimensions {
+            expected: golden.dimensions(),
+            actual: (frame.width, frame.height),
+        });
+    }
+
+    let threshold = tolerance.pixel_threshold * tolerance.pixel_threshold * MAX_YIQ_DELTA;
+    let differing = golden
+        .pixels()
+        .zip(frame.pixels.chunks(4))
+        .filter(|(expected, actual)| yiq_delta(expected.data, actual) > threshold)
+        .count();
+    let total = frame.width as usize * frame.height as usize;
+
+    if differing as f32 > tolerance.max_differing_ratio * total as f32 {
+        Err(ImageMismatch::Pixels { differing, total })
+    } else {
+        Ok(())
+    }
+}
+
+/// Perceptual colour difference between two pixels in the YIQ colour space, with each pixel
+/// blended against white according to its alpha.
+fn yiq_delta(a: [u8; 4], b: &[u8]) -> f32 {
+    fn blend(c: u8, alpha: u8) -> f32 {
+        255. + (f32::from(c) - 255.) * f32::from(alpha) / 255.
+    }
+    fn yiq(p: &[u8]) -> (f32, f32, f32) {
+        let (r, g, b) = (blend(p[0], p[3]), blend(p[1], p[3]), blend(p[2], p[3]));
+        (
+            r * 0.298_895_31 + g * 0.586_622_47 + b * 0.114_482_23,
+            r * 0.595_977_99 - g * 0.274_176_1 - b * 0.321_801_89,
+            r * 0.211_470_17 - g * 0.522_617_55 + b * 0.311_147_38,
+        )
+    }
+
+    let (ya, ia, qa) = yiq(&a);
+    let (yb, ib, qb) = yiq(b);
+    let (y, i, q) = (ya - yb, ia - ib, qa - qb);
+    0.5053 * y * y + 0.299 * i * i + 0.1957 * q * q
+}
+
+fn save(frame: &CapturedFrame, path: &Path) -> Result<(), ImageMismatch> {
+    image::save_buffer(
+        path,
+        &frame.pixels,
+        frame.width,
+        frame.height,
+        image::ColorType::RGBA(8),
+    )
+    .map_err(|e| ImageMismatch::Io(e.to_string()))
+}
+
+#[cfg(test)]
+mod test {
+    use amethyst::renderer::CapturedFrame;
+    use image::{Rgba, RgbaImage};
+
+    use super::{compare_pixels, ImageMismatch, Tolerance};
+
+    fn frame(width: u32, height: u32, pixel: [u8; 4]) -> CapturedFrame {
+        CapturedFrame {
+            width,
+            height,
+            pixels: pixel
+                .iter()
+                .cloned()
+                .cycle()
+                .take((width * height * 4) as usize)
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn identical_images_match_exactly() {
+        let golden = RgbaImage::from_pixel(4, 4, Rgba([10, 20, 30, 255]));
+
+        assert_eq!(
+            Ok(()),
+            compare_pixels(&golden, &frame(4, 4, [10, 20, 30, 255]), Tolerance::exact())
+        );
+    }
+
+    #[test]
+    fn small_colour_shift_is_within_default_tolerance() {
+        let golden = RgbaImage::from_pixel(4, 4, Rgba([10, 20, 30, 255]));
+
+        assert_eq!(
+            Ok(()),
+            compare_pixels(
+                &golden,
+                &frame(4, 4, [11, 21, 31, 255]),
+                Tolerance::default()
+            )
+        );
+    }
+
+    #[test]
+    fn different_colour_is_reported() {
+        let golden = RgbaImage::from_pixel(4, 4, Rgba([0, 0, 0, 255]));
+
+        assert_eq!(
+            Err(ImageMismatch::Pixels {
+                differing: 16,
+                total: 16
+            }),
+            compare_pixels(
+                &golden,
+                &frame(4, 4, [255, 255, 255, 255]),
+                Tolerance::default()
+            )
+        );
+    }
+
+    #[test]
+    fn different_dimensions_are_reported() {
+        let golden = RgbaImage::from_pixel(4, 4, Rgba([0, 0, 0, 255]));
+
+        assert_eq!(
+            Err(ImageMismatch::Dimensions {
+                expected: (4, 4),
+                actual: (2, 2)
+            }),
+            compare_pixels(&golden, &frame(2, 2, [0, 0, 0, 255]), Tolerance::exact())
+        );
+    }
+}
@@ -7,6 +7,7 @@
 //! * `State`
 //! * `System`
 //! * Resource loading.
+//! * Rendered output, compared against golden images.
 //! * Arbitrary types that `System`s use during processing.
 //!
 //! The test harness minimizes boilerplate code to set up an Amethyst `Application` with common
@@ -138,6 +139,23 @@
 //! }
 //! ```
 //!
+//! # Benchmarks
+//!
+//! The [`Benchmark`](struct.Benchmark.html) builder runs systems and bundles against a `World`
+//! filled with generated entities, and reports how long the dispatches, each bundle and each
+//! individually registered system took:
+//!
+//! ```rust,ignore
+//! let report = Benchmark::new()
+//!     .with_bundle(MyBundle::new(), "my_bundle")?             // Systems timed as a whole.
+//!     .with_system(MySystem::new(), "my_sys", &[])            // Timed individually.
+//!     .with_entities(1_000, |builder, _index| builder.with(MyComponent::default()))
+//!     .with_iterations(500)
+//!     .run()?;
+//!
+//! println!("{}", report);
+//! ```
+//!
 //! # Examples
 //!
 //! Testing a bundle:
@@ -320,19 +338,23 @@ extern crate lazy_static;
 pub(crate) use crate::system_injection_bundle::SystemInjectionBundle;
 pub use crate::{
     amethyst_application::{AmethystApplication, HIDPI, SCREEN_HEIGHT, SCREEN_WIDTH},
+    benchmark::{Benchmark, BenchmarkReport, TimingStats},
     effect_return::EffectReturn,
     fixture::{MaterialAnimationFixture, SpriteRenderAnimationFixture},
     game_update::GameUpdate,
+    golden_image::{GoldenImage, ImageMismatch, Tolerance, UPDATE_GOLDEN_ENV},
     state::{
-        CustomDispatcherState, CustomDispatcherStateBuilder, FunctionState, PopState,
+        CaptureState, CustomDispatcherState, CustomDispatcherStateBuilder, FunctionState, PopState,
         SequencerState,
     },
 };
 
 mod amethyst_application;
+mod benchmark;
 mod effect_return;
 mod fixture;
 mod game_update;
+mod golden_image;
 pub mod prelude;
 mod state;
 mod system_injection_bundle;
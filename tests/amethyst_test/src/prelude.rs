@@ -1,3 +1,6 @@
 //! Commonly used imports.
 
-pub use crate::{AmethystApplication, EffectReturn, FunctionState, PopState, SequencerState};
+pub use crate::{
+    AmethystApplication, Benchmark, EffectReturn, FunctionState, GoldenImage, PopState,
+    SequencerState, Tolerance,
+};
@@ -0,0 +1,65 @@
+use amethyst::{prelude::*, renderer::FrameCapture};
+
+use crate::{GameUpdate, GoldenImage};
+
+/// Runs a number of frames, captures the next rendered frame, and compares it against a golden
+/// image.
+///
+/// Panics if the captured frame does not match the golden image.
+#[derive(Debug)]
+pub struct CaptureState {
+    /// Image to compare the captured frame against.
+    golden: GoldenImage,
+    /// Number of frames to run before the capture is requested.
+    frames_remaining: u32,
+    /// Whether the capture has been requested.
+    requested: bool,
+}
+
+impl CaptureState {
+    /// Returns a new `CaptureState`.
+    ///
+    /// # Parameters
+    ///
+    /// * `golden`: Image to compare the captured frame against.
+    /// * `frames`: Number of frames to run before capturing.
+    pub fn new(golden: GoldenImage, frames: u32) -> Self {
+        CaptureState {
+            golden,
+            frames_remaining: frames,
+            requested: false,
+        }
+    }
+}
+
+impl<T, E> State<T, E> for CaptureState
+where
+    T: GameUpdate,
+    E: Send + Sync + 'static,
+{
+    fn update(&mut self, data: StateData<'_, T>) -> Trans<T, E> {
+        data.data.update(&data.world);
+
+        if self.frames_remaining > 0 {
+            self.frames_remaining -= 1;
+            return Trans::None;
+        }
+
+        let mut capture = data.world.write_resource::<FrameCapture>();
+        if !self.requested {
+            capture.request();
+            self.requested = true;
+            return Trans::None;
+        }
+
+        match capture.take() {
+            Some(frame) => {
+                if let Err(mismatch) = self.golden.compare(&frame) {
+                    panic!("{}", mismatch);
+                }
+                Trans::Pop
+            }
+            None => Trans::None,
+        }
+    }
+}
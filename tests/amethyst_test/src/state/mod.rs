@@ -1,10 +1,12 @@
 pub use self::{
+    capture_state::CaptureState,
     custom_dispatcher_state::{CustomDispatcherState, CustomDispatcherStateBuilder},
     function_state::FunctionState,
     pop_state::PopState,
     sequencer_state::SequencerState,
 };
 
+mod capture_state;
 mod custom_dispatcher_state;
 mod function_state;
 mod pop_state;